@@ -0,0 +1,60 @@
+"""Add interaction analytics
+
+Revision ID: 3b7d2c9e4f1a
+Revises: 0161ef85d15a
+Create Date: 2025-08-20 10:24:31.512087
+
+"""
+from typing import Sequence, Union
+
+from alembic import op
+import sqlalchemy as sa
+
+
+# revision identifiers, used by Alembic.
+revision: str = '3b7d2c9e4f1a'
+down_revision: Union[str, Sequence[str], None] = '0161ef85d15a'
+branch_labels: Union[str, Sequence[str], None] = None
+depends_on: Union[str, Sequence[str], None] = None
+
+
+def upgrade() -> None:
+    """Upgrade schema."""
+    # ### commands auto generated by Alembic - please adjust! ###
+    op.create_table('interaction_sessions',
+    sa.Column('started_at', sa.DateTime(), nullable=False),
+    sa.Column('ended_at', sa.DateTime(), nullable=True),
+    sa.Column('faces_seen', sa.Integer(), nullable=False),
+    sa.Column('greetings_performed', sa.Integer(), nullable=False),
+    sa.Column('commands_executed', sa.Integer(), nullable=False),
+    sa.Column('run_time_seconds', sa.Float(), nullable=False),
+    sa.Column('id', sa.String(length=36), nullable=False),
+    sa.Column('created_at', sa.DateTime(), nullable=False),
+    sa.Column('updated_at', sa.DateTime(), nullable=False),
+    sa.PrimaryKeyConstraint('id')
+    )
+    op.create_index('idx_interaction_session_started', 'interaction_sessions', ['started_at'], unique=False)
+    op.create_table('interaction_events',
+    sa.Column('session_id', sa.String(length=36), nullable=False),
+    sa.Column('event_type', sa.String(length=50), nullable=False),
+    sa.Column('details', sa.JSON(), nullable=True),
+    sa.Column('id', sa.String(length=36), nullable=False),
+    sa.Column('created_at', sa.DateTime(), nullable=False),
+    sa.Column('updated_at', sa.DateTime(), nullable=False),
+    sa.ForeignKeyConstraint(['session_id'], ['interaction_sessions.id'], ),
+    sa.PrimaryKeyConstraint('id')
+    )
+    op.create_index('idx_interaction_event_session_created', 'interaction_events', ['session_id', 'created_at'], unique=False)
+    op.create_index('idx_interaction_event_type', 'interaction_events', ['event_type'], unique=False)
+    # ### end Alembic commands ###
+
+
+def downgrade() -> None:
+    """Downgrade schema."""
+    # ### commands auto generated by Alembic - please adjust! ###
+    op.drop_index('idx_interaction_event_type', table_name='interaction_events')
+    op.drop_index('idx_interaction_event_session_created', table_name='interaction_events')
+    op.drop_table('interaction_events')
+    op.drop_index('idx_interaction_session_started', table_name='interaction_sessions')
+    op.drop_table('interaction_sessions')
+    # ### end Alembic commands ###
@@ -0,0 +1,47 @@
+"""Add conversation memory
+
+Revision ID: 8e2f4a6c1d3b
+Revises: 3b7d2c9e4f1a
+Create Date: 2025-09-02 14:08:17.263410
+
+"""
+from typing import Sequence, Union
+
+from alembic import op
+import sqlalchemy as sa
+
+
+# revision identifiers, used by Alembic.
+revision: str = '8e2f4a6c1d3b'
+down_revision: Union[str, Sequence[str], None] = '3b7d2c9e4f1a'
+branch_labels: Union[str, Sequence[str], None] = None
+depends_on: Union[str, Sequence[str], None] = None
+
+
+def upgrade() -> None:
+    """Upgrade schema."""
+    # ### commands auto generated by Alembic - please adjust! ###
+    op.create_table('conversation_memories',
+    sa.Column('identity', sa.String(length=100), nullable=False),
+    sa.Column('display_name', sa.String(length=100), nullable=True),
+    sa.Column('preferred_language', sa.String(length=20), nullable=True),
+    sa.Column('preferences', sa.JSON(), nullable=True),
+    sa.Column('summaries', sa.JSON(), nullable=True),
+    sa.Column('interaction_count', sa.Integer(), nullable=False),
+    sa.Column('last_seen_at', sa.DateTime(), nullable=False),
+    sa.Column('id', sa.String(length=36), nullable=False),
+    sa.Column('created_at', sa.DateTime(), nullable=False),
+    sa.Column('updated_at', sa.DateTime(), nullable=False),
+    sa.PrimaryKeyConstraint('id'),
+    sa.UniqueConstraint('identity')
+    )
+    op.create_index('idx_conversation_memory_last_seen', 'conversation_memories', ['last_seen_at'], unique=False)
+    # ### end Alembic commands ###
+
+
+def downgrade() -> None:
+    """Downgrade schema."""
+    # ### commands auto generated by Alembic - please adjust! ###
+    op.drop_index('idx_conversation_memory_last_seen', table_name='conversation_memories')
+    op.drop_table('conversation_memories')
+    # ### end Alembic commands ###
@@ -0,0 +1,47 @@
+"""Add event journal
+
+Revision ID: 9d4b6f2a7c1e
+Revises: 5c9a1e7b2d4f
+Create Date: 2026-10-16 17:12:41.503927
+
+"""
+from typing import Sequence, Union
+
+from alembic import op
+import sqlalchemy as sa
+
+
+# revision identifiers, used by Alembic.
+revision: str = '9d4b6f2a7c1e'
+down_revision: Union[str, Sequence[str], None] = '5c9a1e7b2d4f'
+branch_labels: Union[str, Sequence[str], None] = None
+depends_on: Union[str, Sequence[str], None] = None
+
+
+def upgrade() -> None:
+    """Upgrade schema."""
+    # ### commands auto generated by Alembic - please adjust! ###
+    op.create_table('event_journal',
+    sa.Column('occurred_at', sa.DateTime(), nullable=False),
+    sa.Column('event_type', sa.String(length=50), nullable=False),
+    sa.Column('category', sa.String(length=20), nullable=False),
+    sa.Column('significance', sa.Float(), nullable=True),
+    sa.Column('message', sa.String(length=500), nullable=True),
+    sa.Column('details', sa.JSON(), nullable=True),
+    sa.Column('id', sa.String(length=36), nullable=False),
+    sa.Column('created_at', sa.DateTime(), nullable=False),
+    sa.Column('updated_at', sa.DateTime(), nullable=False),
+    sa.PrimaryKeyConstraint('id')
+    )
+    op.create_index('idx_event_journal_occurred', 'event_journal', ['occurred_at'], unique=False)
+    op.create_index('idx_event_journal_type_occurred', 'event_journal', ['event_type', 'occurred_at'], unique=False)
+    # ### end Alembic commands ###
+
+
+def downgrade() -> None:
+    """Downgrade schema."""
+    # ### commands auto generated by Alembic - please adjust! ###
+    op.drop_index('idx_event_journal_type_occurred', table_name='event_journal')
+    op.drop_index('idx_event_journal_occurred', table_name='event_journal')
+    op.drop_table('event_journal')
+    # ### end Alembic commands ###
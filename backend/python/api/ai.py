@@ -4,17 +4,21 @@ AI服务API路由
 提供AI推理功能的REST API接口
 """
 
-from fastapi import APIRouter, HTTPException, UploadFile, File, Form
+from fastapi import APIRouter, HTTPException, UploadFile, File, Form, Query
 from fastapi.responses import JSONResponse
 from pydantic import BaseModel, Field
 from typing import Dict, List, Optional, Any, Union
 from datetime import datetime
+from pathlib import Path
+import asyncio
 import base64
 import io
 from PIL import Image
 import numpy as np
 import cv2
 
+from core.config import get_config
+from rust_bindings import get_rust_bindings_manager
 from services.ai_service import ai_service
 from utils.logger import setup_logger
 
@@ -104,6 +108,21 @@ class SceneAnalysisResponse(BaseModel):
     scene_summary: Dict[str, Any]
 
 
+class ModelTensorInfo(BaseModel):
+    """模型的一个输入或输出"""
+    name: str
+    shape: List[int]  # 动态维度为-1
+    dtype: str
+
+
+class ModelSignatureResponse(BaseModel):
+    """模型实际的输入输出"""
+    file: str
+    inputs: List[ModelTensorInfo]
+    outputs: List[ModelTensorInfo]
+    mismatches: List[str]
+
+
 class AIStatsResponse(BaseModel):
     """AI统计响应"""
     initialized: bool
@@ -452,6 +471,36 @@ async def get_available_models():
         raise HTTPException(status_code=500, detail=str(e))
 
 
+@router.get("/models/inspect", response_model=ModelSignatureResponse)
+async def inspect_model(
+    file: str = Query(..., description="AI模型目录下的ONNX模型文件"),
+    input_shape: Optional[str] = Query(None, description="期望的输入形状，逗号分隔，如1,3,640,640"),
+    output_names: Optional[str] = Query(None, description="期望的输出名称，逗号分隔"),
+):
+    """加载模型读取实际的输入输出名称、形状和类型，给出期望时列出差异"""
+    model_dir = Path(get_config().ai.AI_MODEL_PATH).resolve()
+    path = (model_dir / file).resolve()
+    if model_dir not in path.parents:
+        raise HTTPException(status_code=400, detail="只能读取AI模型目录下的文件")
+    
+    try:
+        shape = [int(v) for v in input_shape.split(",")] if input_shape else None
+    except ValueError:
+        raise HTTPException(status_code=400, detail=f"输入形状格式错误: {input_shape}")
+    names = [v.strip() for v in output_names.split(",") if v.strip()] if output_names else None
+    
+    try:
+        signature = await asyncio.to_thread(get_rust_bindings_manager().inspect_model, str(path), shape, names)
+    except FileNotFoundError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+    except RuntimeError as e:
+        raise HTTPException(status_code=503, detail=str(e))
+    
+    for mismatch in signature["mismatches"]:
+        logger.warning(f"模型 {file} 与期望不符: {mismatch}")
+    return ModelSignatureResponse(file=file, **signature)
+
+
 @router.get("/health")
 async def ai_health_check():
     """AI服务健康检查"""
@@ -0,0 +1,48 @@
+#!/usr/bin/env python3
+"""
+告警API路由
+查看告警规则、正在触发的告警和最近的通知，修改规则文件后重新加载
+"""
+
+from fastapi import APIRouter, HTTPException, Query
+from typing import Any, Dict, List
+
+import yaml
+
+from services.alert_service import alert_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/alerts", tags=["alerts"])
+
+
+@router.get("")
+async def get_alerts() -> Dict[str, Any]:
+    """告警规则、通知渠道和正在触发的告警"""
+    return alert_service.get_status()
+
+
+@router.get("/history")
+async def get_alert_history(
+    limit: int = Query(50, ge=1, le=1000, description="返回的最大通知数")
+) -> List[Dict[str, Any]]:
+    """最近的告警和恢复通知，最新的在前"""
+    return list(reversed(alert_service.history))[:limit]
+
+
+@router.post("/reload")
+async def reload_alerts() -> Dict[str, Any]:
+    """重新读取告警规则文件，格式错误时保留原规则"""
+    if not alert_service.enabled:
+        raise HTTPException(status_code=503, detail="告警未启用")
+    
+    try:
+        await alert_service.reload()
+    except (ValueError, yaml.YAMLError) as e:
+        raise HTTPException(status_code=422, detail=f"告警规则文件有误: {e}")
+    except OSError as e:
+        raise HTTPException(status_code=500, detail=f"读取告警规则文件失败: {e}")
+    
+    logger.info("告警规则已重新加载")
+    return alert_service.get_status()
@@ -0,0 +1,149 @@
+#!/usr/bin/env python3
+"""
+交互分析API路由
+提供交互统计、会话事件日志和隐私设置的REST API接口
+"""
+
+from fastapi import APIRouter, HTTPException, Query
+from pydantic import BaseModel, Field
+from typing import Dict, List, Optional, Any
+from datetime import datetime
+
+from services.analytics_service import analytics_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/analytics", tags=["analytics"])
+
+
+# 请求模型
+class AnalyticsEventRequest(BaseModel):
+    """交互事件上报请求"""
+    event_type: str = Field(..., min_length=1, max_length=50, description="事件类型")
+    details: Dict[str, Any] = Field(default_factory=dict, description="事件详情")
+
+
+class PrivacySettingsRequest(BaseModel):
+    """隐私设置请求"""
+    store_session_log: Optional[bool] = Field(None, description="是否保存交互事件日志")
+    store_identifiable_data: Optional[bool] = Field(None, description="是否保存可识别个人的数据")
+
+
+# 响应模型
+class AnalyticsSummaryResponse(BaseModel):
+    """交互统计响应"""
+    enabled: bool
+    session_id: Optional[str]
+    started_at: str
+    current_session: Dict[str, float]
+    totals: Dict[str, float]
+    privacy: Dict[str, bool]
+
+
+class CommandResponse(BaseModel):
+    """命令执行响应"""
+    success: bool
+    message: str
+    timestamp: str
+    data: Optional[Dict[str, Any]] = None
+
+
+# 上报事件类型对应的计数
+EVENT_COUNTERS = {
+    "greeting": "greetings_performed",
+    "command": "commands_executed",
+    "face_seen": "faces_seen",
+}
+
+
+@router.get("", response_model=AnalyticsSummaryResponse)
+async def get_analytics_summary():
+    """获取交互统计"""
+    try:
+        summary = await analytics_service.get_summary()
+        return AnalyticsSummaryResponse(**summary)
+        
+    except Exception as e:
+        logger.error(f"获取交互统计失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.get("/events")
+async def get_analytics_events(
+    limit: int = Query(100, ge=1, le=1000, description="返回的最大事件数"),
+    event_type: Optional[str] = Query(None, description="按事件类型过滤")
+) -> List[Dict[str, Any]]:
+    """获取最近的交互事件"""
+    try:
+        return await analytics_service.get_events(limit=limit, event_type=event_type)
+        
+    except Exception as e:
+        logger.error(f"获取交互事件失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.post("/events", response_model=CommandResponse)
+async def report_analytics_event(request: AnalyticsEventRequest):
+    """上报交互事件（例如由行为规则触发的问候）"""
+    try:
+        await analytics_service.record_event(
+            request.event_type,
+            request.details,
+            counter=EVENT_COUNTERS.get(request.event_type)
+        )
+        
+        return CommandResponse(
+            success=True,
+            message="交互事件已记录",
+            timestamp=datetime.now().isoformat()
+        )
+        
+    except Exception as e:
+        logger.error(f"记录交互事件失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.delete("/events", response_model=CommandResponse)
+async def clear_analytics_events():
+    """删除所有已保存的交互事件"""
+    try:
+        deleted = await analytics_service.clear_events()
+        
+        return CommandResponse(
+            success=True,
+            message=f"已删除 {deleted} 条交互事件",
+            timestamp=datetime.now().isoformat(),
+            data={"deleted": deleted}
+        )
+        
+    except Exception as e:
+        logger.error(f"删除交互事件失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.get("/privacy")
+async def get_privacy_settings() -> Dict[str, bool]:
+    """获取隐私设置"""
+    return analytics_service.get_privacy()
+
+
+@router.put("/privacy", response_model=CommandResponse)
+async def update_privacy_settings(request: PrivacySettingsRequest):
+    """更新隐私设置"""
+    try:
+        analytics_service.set_privacy(
+            store_session_log=request.store_session_log,
+            store_identifiable_data=request.store_identifiable_data
+        )
+        
+        return CommandResponse(
+            success=True,
+            message="隐私设置已更新",
+            timestamp=datetime.now().isoformat(),
+            data=analytics_service.get_privacy()
+        )
+        
+    except Exception as e:
+        logger.error(f"更新隐私设置失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
@@ -0,0 +1,159 @@
+#!/usr/bin/env python3
+"""
+模型和动画资源API路由
+提供分块上传（断点续传、校验和验证）以及已登记资源的查询和删除接口
+
+上传流程：
+1. POST /api/assets/uploads 提交文件名、类型、大小和SHA-256，返回上传ID和已接收的字节数offset
+2. 从offset开始依次 PUT /api/assets/uploads/{id}?offset=N 上传分块，可附带 X-Chunk-SHA256 头
+3. POST /api/assets/uploads/{id}/complete 校验整个文件并登记
+中断后重新提交第1步（或 GET 上传）即可得到offset继续上传
+"""
+
+from fastapi import APIRouter, Header, HTTPException, Query, Request
+from pydantic import BaseModel, Field
+from typing import Dict, List, Optional, Any
+
+from services.upload_service import upload_service, UploadOffsetError
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/assets", tags=["assets"])
+
+
+# 请求模型
+class UploadRequest(BaseModel):
+    """创建上传请求"""
+    filename: str = Field(..., description="文件名（不含路径）")
+    kind: str = Field(..., pattern="^(model|animation)$", description="资源类型：model 或 animation")
+    size: int = Field(..., gt=0, description="文件大小（字节）")
+    sha256: str = Field(..., description="整个文件的SHA-256（十六进制）")
+
+
+# 响应模型
+class UploadSession(BaseModel):
+    """上传进度"""
+    upload_id: str
+    kind: str
+    filename: str
+    size: int
+    sha256: str
+    offset: int = Field(..., description="已接收的字节数，下一个分块从这里开始")
+    chunk_size: int = Field(..., description="建议的分块大小（字节）")
+    created_at: float
+    updated_at: float
+
+
+class Asset(BaseModel):
+    """已登记的模型或动画"""
+    id: str
+    kind: str
+    filename: str
+    original_filename: str
+    path: str
+    size: int
+    mime_type: Optional[str] = None
+    sha256: Optional[str] = None
+    created_at: Optional[str] = None
+    deduplicated: bool = False
+
+
+def _raise_for(e: Exception):
+    if isinstance(e, UploadOffsetError):
+        # 409时在响应中给出正确的偏移，客户端据此续传
+        raise HTTPException(status_code=409, detail={"message": str(e), "offset": e.expected_offset})
+    if isinstance(e, KeyError):
+        raise HTTPException(status_code=404, detail=e.args[0] if e.args else str(e))
+    if isinstance(e, ValueError):
+        raise HTTPException(status_code=422, detail=str(e))
+    logger.error(f"资源上传失败: {e}")
+    raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.get("/status")
+async def get_upload_status() -> Dict[str, Any]:
+    """上传限制和未完成的上传数量"""
+    return upload_service.get_status()
+
+
+@router.post("/uploads", response_model=UploadSession)
+async def create_upload(request: UploadRequest):
+    """创建上传；同一文件已有未完成的上传时返回该上传以便续传"""
+    try:
+        return await upload_service.create_upload(request.filename, request.kind, request.size, request.sha256)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.get("/uploads", response_model=List[UploadSession])
+async def list_uploads():
+    """未完成的上传"""
+    return upload_service.list_uploads()
+
+
+@router.get("/uploads/{upload_id}", response_model=UploadSession)
+async def get_upload(upload_id: str):
+    """查询上传进度"""
+    try:
+        return upload_service.get_upload(upload_id)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.put("/uploads/{upload_id}", response_model=UploadSession)
+async def upload_chunk(
+    upload_id: str,
+    request: Request,
+    offset: int = Query(..., ge=0, description="分块在文件中的起始字节"),
+    x_chunk_sha256: Optional[str] = Header(None, description="分块的SHA-256（十六进制），用于校验传输")
+):
+    """上传一个分块，请求体为原始字节"""
+    content_length = request.headers.get("content-length")
+    if content_length and int(content_length) > upload_service.max_chunk_size:
+        raise HTTPException(status_code=413, detail="分块过大")
+    
+    data = await request.body()
+    try:
+        return await upload_service.write_chunk(upload_id, offset, data, x_chunk_sha256)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.post("/uploads/{upload_id}/complete", response_model=Asset)
+async def complete_upload(upload_id: str):
+    """校验整个文件并登记为模型或动画"""
+    try:
+        return await upload_service.complete_upload(upload_id)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.delete("/uploads/{upload_id}")
+async def abort_upload(upload_id: str) -> Dict[str, Any]:
+    """取消上传并删除已接收的数据"""
+    try:
+        await upload_service.abort_upload(upload_id)
+    except Exception as e:
+        _raise_for(e)
+    return {"success": True, "upload_id": upload_id}
+
+
+@router.get("", response_model=List[Asset])
+async def list_assets(
+    kind: Optional[str] = Query(None, pattern="^(model|animation)$", description="只列出该类型")
+):
+    """已登记的模型和动画"""
+    try:
+        return upload_service.list_assets(kind)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.delete("/{asset_id}", response_model=Asset)
+async def delete_asset(asset_id: str):
+    """删除已登记的模型或动画"""
+    try:
+        return upload_service.delete_asset(asset_id)
+    except Exception as e:
+        _raise_for(e)
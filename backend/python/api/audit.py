@@ -0,0 +1,65 @@
+#!/usr/bin/env python3
+"""
+命令审计API路由
+提供查询外部命令审计记录的REST API接口
+"""
+
+from fastapi import APIRouter, HTTPException, Query
+from pydantic import BaseModel
+from typing import Dict, List, Optional, Any
+
+from core.models import UserRole
+from services.audit_service import audit_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/audit", tags=["audit"])
+
+
+# 响应模型
+class AuditRecordsResponse(BaseModel):
+    """审计记录查询响应"""
+    count: int
+    records: List[Dict[str, Any]]
+
+
+class AuditStatusResponse(BaseModel):
+    """审计文件概况响应"""
+    directory: str
+    files: List[str]
+    total_bytes: int
+
+
+@router.get("/commands", response_model=AuditRecordsResponse)
+async def query_commands(
+    source: Optional[str] = Query(None, description="命令来源，如 WebSocket、Python、Teleop"),
+    client_id: Optional[str] = Query(None, description="客户端标识"),
+    role: Optional[UserRole] = Query(None, description="发送方角色"),
+    since: Optional[int] = Query(None, ge=0, description="起始时间（毫秒时间戳）"),
+    until: Optional[int] = Query(None, ge=0, description="结束时间（毫秒时间戳）"),
+    accepted: Optional[bool] = Query(None, description="只看被接受（true）或被拒绝（false）的命令"),
+    limit: int = Query(100, ge=1, le=1000, description="最多返回的记录数（取最新的）")
+):
+    """查询外部命令审计记录"""
+    try:
+        records = await audit_service.query(
+            source=source,
+            client_id=client_id,
+            role=role.value if role else None,
+            since=since,
+            until=until,
+            accepted=accepted,
+            limit=limit
+        )
+        return AuditRecordsResponse(count=len(records), records=records)
+        
+    except Exception as e:
+        logger.error(f"查询命令审计记录失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.get("", response_model=AuditStatusResponse)
+async def get_audit_status():
+    """获取审计文件概况"""
+    return AuditStatusResponse(**audit_service.get_status())
@@ -0,0 +1,113 @@
+#!/usr/bin/env python3
+"""
+行为包API路由
+安装、启用、禁用和卸载社区分享的行为包（zip格式，含动画、声音、灯效和行为树清单），
+包内容由Rust端在沙箱中检查，新安装的包默认禁用
+"""
+
+import asyncio
+import os
+import tempfile
+
+from fastapi import APIRouter, HTTPException, Request
+from pydantic import BaseModel, Field
+from typing import Dict, List, Any
+
+from rust_bindings import get_rust_bindings_manager
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/behavior-packs", tags=["behavior-packs"])
+
+# 与Rust端默认的解压后大小上限一致，压缩包本身不会更大
+MAX_PACK_SIZE = 64 * 1024 * 1024
+
+
+# 响应模型
+class BehaviorPackInfo(BaseModel):
+    """已安装的行为包，资源名称带有"包ID/"前缀"""
+    id: str
+    name: str
+    version: str
+    author: str
+    description: str
+    enabled: bool
+    installed_at: int
+    animations: List[str]
+    sounds: List[str]
+    led_patterns: List[str]
+    behaviors: List[str]
+    rules: List[str]
+    permissions: List[str] = Field(..., description="包内容需要的权限：motion、audio、leds、state、triggers、events")
+
+
+def _raise_for(e: Exception):
+    if isinstance(e, ValueError):
+        raise HTTPException(status_code=422, detail=str(e))
+    if isinstance(e, RuntimeError):
+        raise HTTPException(status_code=503, detail=str(e))
+    logger.error(f"行为包操作失败: {e}")
+    raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.post("", response_model=BehaviorPackInfo)
+async def install_pack(request: Request):
+    """安装行为包，请求体为zip文件（application/zip）；同ID的包被替换并保留启用状态"""
+    content_length = request.headers.get("content-length")
+    if content_length and int(content_length) > MAX_PACK_SIZE:
+        raise HTTPException(status_code=413, detail="行为包过大")
+    
+    data = await request.body()
+    if len(data) > MAX_PACK_SIZE:
+        raise HTTPException(status_code=413, detail="行为包过大")
+    
+    fd, path = tempfile.mkstemp(suffix=".zip")
+    try:
+        with os.fdopen(fd, "wb") as f:
+            f.write(data)
+        result = await asyncio.to_thread(get_rust_bindings_manager().install_behavior_pack, path)
+    except Exception as e:
+        _raise_for(e)
+    finally:
+        os.unlink(path)
+    
+    logger.info(f"已安装行为包 {result['id']} {result['version']}")
+    return result
+
+
+@router.get("", response_model=List[BehaviorPackInfo])
+async def list_packs():
+    """已安装的行为包"""
+    try:
+        return get_rust_bindings_manager().list_behavior_packs()
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.post("/{pack_id}/enable", response_model=BehaviorPackInfo)
+async def enable_pack(pack_id: str):
+    """启用行为包，其规则和行为树在下次加载规则时生效"""
+    try:
+        return get_rust_bindings_manager().set_behavior_pack_enabled(pack_id, True)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.post("/{pack_id}/disable", response_model=BehaviorPackInfo)
+async def disable_pack(pack_id: str):
+    """禁用行为包"""
+    try:
+        return get_rust_bindings_manager().set_behavior_pack_enabled(pack_id, False)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.delete("/{pack_id}")
+async def uninstall_pack(pack_id: str) -> Dict[str, Any]:
+    """卸载行为包并删除其文件"""
+    try:
+        get_rust_bindings_manager().uninstall_behavior_pack(pack_id)
+    except Exception as e:
+        _raise_for(e)
+    return {"success": True, "pack_id": pack_id}
@@ -0,0 +1,99 @@
+#!/usr/bin/env python3
+"""
+编舞API路由
+保存编舞文件，发出演出提示（同时通知其他机器人），接收其他机器人发来的提示
+"""
+
+from fastapi import APIRouter, Body, HTTPException
+from pydantic import BaseModel, Field
+from typing import Any, Dict, List, Optional
+
+from services.choreography_service import choreography_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/choreography", tags=["choreography"])
+
+
+# 请求模型
+class StartRequest(BaseModel):
+    """其他机器人发来的提示：按参考时钟开始的时间，可以附带编舞文件"""
+    start_at: int = Field(..., description="开始时间（参考时钟，Unix毫秒）")
+    choreography: Optional[Dict[str, Any]] = Field(None, description="编舞文件，为空时使用本机保存的同名编舞")
+
+
+class CueRequest(BaseModel):
+    """演出提示"""
+    lead_time: Optional[float] = Field(None, gt=0, le=60, description="提示到开始的时间（秒），默认CHOREOGRAPHY_LEAD_TIME")
+    peers: Optional[List[str]] = Field(None, description="一起演出的机器人API地址，默认CHOREOGRAPHY_PEERS")
+
+
+@router.get("")
+async def list_choreographies() -> List[Dict[str, Any]]:
+    """已保存的编舞"""
+    return choreography_service.list_choreographies()
+
+
+@router.get("/status")
+async def get_choreography_status() -> Dict[str, Any]:
+    """演出状态和参考时钟"""
+    return choreography_service.get_status()
+
+
+@router.post("/stop")
+async def stop_choreography() -> Dict[str, Any]:
+    """停止演出"""
+    await choreography_service.stop()
+    return choreography_service.get_status()
+
+
+@router.get("/{name}")
+async def get_choreography(name: str) -> Dict[str, Any]:
+    """读取编舞文件"""
+    try:
+        document = choreography_service.load(name)
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+    if document is None:
+        raise HTTPException(status_code=404, detail="编舞不存在")
+    return document
+
+
+@router.put("/{name}")
+async def save_choreography(name: str, document: Dict[str, Any] = Body(...)) -> Dict[str, Any]:
+    """保存编舞文件（格式见Rust端choreography模块）"""
+    try:
+        choreography_service.save(name, document)
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+    return {"name": name, "tracks": [track["robot"] for track in document["tracks"]]}
+
+
+@router.delete("/{name}")
+async def delete_choreography(name: str) -> Dict[str, Any]:
+    try:
+        deleted = choreography_service.delete(name)
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+    if not deleted:
+        raise HTTPException(status_code=404, detail="编舞不存在")
+    return {"name": name, "deleted": True}
+
+
+@router.post("/{name}/cue")
+async def cue_choreography(name: str, request: CueRequest) -> Dict[str, Any]:
+    """发出演出提示：通知其他机器人并在本机开始"""
+    try:
+        return await choreography_service.cue(name, request.lead_time, request.peers)
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+
+
+@router.post("/{name}/start")
+async def start_choreography(name: str, request: StartRequest) -> Dict[str, Any]:
+    """按提示中的开始时间演出本机的轨道"""
+    try:
+        return await choreography_service.start(name, request.start_at, request.choreography)
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
@@ -0,0 +1,58 @@
+#!/usr/bin/env python3
+"""
+配置管理API路由
+设置界面提交新配置前先试运行，查看校验结果、与当前配置的差异以及需要重启的子系统
+"""
+
+from fastapi import APIRouter, HTTPException
+from pydantic import BaseModel
+from typing import Dict, List, Optional, Any
+
+from rust_bindings import get_rust_bindings_manager
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/config", tags=["config"])
+
+
+# 请求模型
+class DryRunRequest(BaseModel):
+    """配置试运行请求"""
+    config: Dict[str, Any]
+    # 不指定时与Rust端当前生效的配置比较
+    active: Optional[Dict[str, Any]] = None
+
+
+# 响应模型
+class ConfigChangeModel(BaseModel):
+    """一项配置差异"""
+    path: str
+    old: Optional[Any] = None
+    new: Optional[Any] = None
+
+
+class DryRunResponse(BaseModel):
+    """配置试运行响应"""
+    valid: bool
+    errors: List[str]
+    changes: List[ConfigChangeModel]
+    summary: List[str]
+    restart_required: List[str]
+    warnings: List[str]
+
+
+@router.post("/dry-run", response_model=DryRunResponse)
+async def dry_run_config(request: DryRunRequest):
+    """校验新配置并返回与当前配置的差异，不修改任何配置"""
+    try:
+        result = get_rust_bindings_manager().dry_run_config(request.config, request.active)
+        return DryRunResponse(**result)
+        
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+    except RuntimeError as e:
+        raise HTTPException(status_code=503, detail=str(e))
+    except Exception as e:
+        logger.error(f"配置试运行失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
@@ -0,0 +1,85 @@
+#!/usr/bin/env python3
+"""
+数据管理API路由
+提供数据保留策略查询、手动清理和清除全部用户数据的REST API接口
+"""
+
+from fastapi import APIRouter, HTTPException
+from pydantic import BaseModel, Field
+from typing import Dict, Optional, Any
+from datetime import datetime
+
+from services.retention_service import retention_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/data", tags=["data"])
+
+# 清除全部用户数据时需要提交的确认短语
+WIPE_CONFIRMATION = "WIPE ALL USER DATA"
+
+
+# 请求模型
+class WipeRequest(BaseModel):
+    """清除全部用户数据请求"""
+    confirm: str = Field(..., description=f"确认短语，必须为 '{WIPE_CONFIRMATION}'")
+
+
+# 响应模型
+class CommandResponse(BaseModel):
+    """命令执行响应"""
+    success: bool
+    message: str
+    timestamp: str
+    data: Optional[Dict[str, Any]] = None
+
+
+@router.get("/retention")
+async def get_retention_status() -> Dict[str, Any]:
+    """获取数据占用情况和保留策略"""
+    try:
+        return retention_service.get_usage()
+        
+    except Exception as e:
+        logger.error(f"获取数据保留状态失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.post("/cleanup", response_model=CommandResponse)
+async def run_cleanup():
+    """立即按保留策略清理数据"""
+    try:
+        report = await retention_service.run_cleanup()
+        
+        return CommandResponse(
+            success=True,
+            message="数据清理完成",
+            timestamp=datetime.now().isoformat(),
+            data=report
+        )
+        
+    except Exception as e:
+        logger.error(f"数据清理失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.post("/wipe", response_model=CommandResponse)
+async def wipe_user_data(request: WipeRequest):
+    """清除全部用户数据（录像、快照、日志、黑匣子、用户和交互记录）"""
+    if request.confirm != WIPE_CONFIRMATION:
+        raise HTTPException(status_code=400, detail=f"确认短语错误，请提交 '{WIPE_CONFIRMATION}'")
+    
+    try:
+        report = await retention_service.wipe_user_data()
+        
+        return CommandResponse(
+            success=True,
+            message="全部用户数据已清除",
+            timestamp=datetime.now().isoformat(),
+            data=report
+        )
+        
+    except Exception as e:
+        logger.error(f"清除用户数据失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
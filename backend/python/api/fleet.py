@@ -0,0 +1,74 @@
+#!/usr/bin/env python3
+"""
+机群心跳API路由
+提供机群心跳状态查询、立即上报和启停的REST API接口
+"""
+
+from fastapi import APIRouter, HTTPException
+from pydantic import BaseModel
+from typing import Dict, Optional, Any
+from datetime import datetime
+
+from services.fleet_service import fleet_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/fleet", tags=["fleet"])
+
+
+# 响应模型
+class CommandResponse(BaseModel):
+    """命令执行响应"""
+    success: bool
+    message: str
+    timestamp: str
+    data: Optional[Dict[str, Any]] = None
+
+
+@router.get("/status")
+async def get_fleet_status() -> Dict[str, Any]:
+    """获取机群心跳上报状态"""
+    return fleet_service.get_status()
+
+
+@router.get("/report")
+async def preview_report() -> Dict[str, Any]:
+    """预览将要上报的心跳内容"""
+    return fleet_service.build_report()
+
+
+@router.post("/start", response_model=CommandResponse)
+async def start_fleet():
+    """启动机群心跳上报"""
+    try:
+        success = await fleet_service.start()
+        
+        return CommandResponse(
+            success=success,
+            message="机群心跳上报已启动" if success else f"机群心跳上报启动失败: {fleet_service.last_error}",
+            timestamp=datetime.now().isoformat(),
+            data=fleet_service.get_status()
+        )
+        
+    except Exception as e:
+        logger.error(f"启动机群心跳上报失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.post("/stop", response_model=CommandResponse)
+async def stop_fleet():
+    """停止机群心跳上报"""
+    try:
+        await fleet_service.stop()
+        
+        return CommandResponse(
+            success=True,
+            message="机群心跳上报已停止",
+            timestamp=datetime.now().isoformat(),
+            data=fleet_service.get_status()
+        )
+        
+    except Exception as e:
+        logger.error(f"停止机群心跳上报失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
@@ -0,0 +1,74 @@
+#!/usr/bin/env python3
+"""
+事件日志API路由
+提供活动时间线所需的重要事件分页查询接口
+"""
+
+from fastapi import APIRouter, HTTPException, Query
+from pydantic import BaseModel
+from typing import Dict, List, Optional, Any
+from datetime import datetime
+
+from services.journal_service import journal_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/journal", tags=["journal"])
+
+
+# 响应模型
+class JournalEntryResponse(BaseModel):
+    """一条事件日志"""
+    id: str
+    occurred_at: str
+    event_type: str
+    category: str
+    significance: Optional[float] = None
+    message: Optional[str] = None
+    details: Optional[Dict[str, Any]] = None
+
+
+class JournalPageResponse(BaseModel):
+    """事件日志分页查询响应"""
+    items: List[JournalEntryResponse]
+    total: int
+    page: int
+    page_size: int
+    has_more: bool
+
+
+@router.get("", response_model=JournalPageResponse)
+async def query_journal(
+    page: int = Query(1, ge=1, description="页码，从1开始"),
+    page_size: int = Query(50, ge=1, le=500, description="每页条数"),
+    event_type: Optional[List[str]] = Query(None, description="事件类型，可以指定多个"),
+    category: Optional[str] = Query(None, description="分类：mode、error、detection、event"),
+    since: Optional[datetime] = Query(None, description="起始时间（包含）"),
+    until: Optional[datetime] = Query(None, description="结束时间（不包含）"),
+):
+    """按类型和时间范围分页查询事件日志，按发生时间从新到旧"""
+    if since and until and since >= until:
+        raise HTTPException(status_code=400, detail="起始时间必须早于结束时间")
+    
+    try:
+        result = await journal_service.query(
+            page=page, page_size=page_size, event_types=event_type,
+            category=category, since=since, until=until
+        )
+        return JournalPageResponse(**result)
+        
+    except Exception as e:
+        logger.error(f"查询事件日志失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.get("/types")
+async def get_journal_event_types() -> List[str]:
+    """已记录过的事件类型"""
+    try:
+        return await journal_service.get_event_types()
+        
+    except Exception as e:
+        logger.error(f"查询事件类型失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
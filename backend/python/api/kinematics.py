@@ -0,0 +1,138 @@
+#!/usr/bin/env python3
+"""
+运动学API路由
+求解注视目标的关节位置，目标不可达时返回最接近的可达方向和挡住目标的关节限位；
+并提供可达注视范围的包络，供前端可视化；以及命名注视目标的管理和按名字看向目标
+"""
+
+from fastapi import APIRouter, HTTPException, Query
+from pydantic import BaseModel, Field
+from typing import Any, Dict, List, Optional
+
+from rust_bindings import get_rust_bindings_manager
+from services.gaze_target_service import GazeTargetUnreachable, gaze_target_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/kinematics", tags=["kinematics"])
+
+
+# 请求模型
+class GazeTargetRequest(BaseModel):
+    """注视目标点（底座坐标系，米）"""
+    x: float
+    y: float
+    z: float
+
+
+# 响应模型
+class GazeSolutionResponse(BaseModel):
+    """注视求解结果"""
+    targets: Dict[str, float]
+
+
+class Point3Model(BaseModel):
+    x: float
+    y: float
+    z: float
+
+
+class NamedGazeTargetRequest(BaseModel):
+    """命名注视目标（底座坐标系，米）"""
+    x: float
+    y: float
+    z: float
+    description: str = Field("", max_length=200, description="备注")
+
+
+class LookAtTargetRequest(BaseModel):
+    """看向命名目标"""
+    speed: Optional[float] = Field(None, gt=0, le=30, description="头部速度（度/秒），默认GAZE_TARGET_LOOK_SPEED")
+
+
+class GazeWorkspaceResponse(BaseModel):
+    """可达注视范围（rad），boundary为包络边界上的采样点（米）"""
+    full_turn: bool
+    azimuth_min: float
+    azimuth_max: float
+    elevation_min: float
+    elevation_max: float
+    boundary: List[Point3Model]
+
+
+@router.post("/gaze", response_model=GazeSolutionResponse,
+             responses={422: {"description": "目标不可达，detail中给出最接近的可达方向和挡住目标的关节限位"}})
+async def solve_gaze(request: GazeTargetRequest):
+    """求解看向目标点的关节位置"""
+    try:
+        result = get_rust_bindings_manager().solve_gaze(request.x, request.y, request.z)
+    except RuntimeError as e:
+        raise HTTPException(status_code=503, detail=str(e))
+    
+    if not result["reachable"]:
+        logger.info(f"注视目标不可达: {result['message']}")
+        raise HTTPException(status_code=422, detail={
+            "message": result["message"],
+            "unreachable": result["unreachable"],
+        })
+    
+    return GazeSolutionResponse(targets=result["targets"])
+
+
+@router.get("/workspace", response_model=GazeWorkspaceResponse)
+async def get_gaze_workspace(
+    radius: float = Query(1.0, gt=0, description="包络球面半径（米）"),
+    samples: int = Query(16, ge=2, le=256, description="每条边的采样点数")
+):
+    """获取可达注视范围及包络边界"""
+    try:
+        return GazeWorkspaceResponse(**get_rust_bindings_manager().gaze_workspace(radius, samples))
+    except RuntimeError as e:
+        raise HTTPException(status_code=503, detail=str(e))
+
+
+@router.get("/targets")
+async def list_gaze_targets() -> List[Dict[str, Any]]:
+    """已保存的命名注视目标"""
+    return gaze_target_service.list_targets()
+
+
+@router.get("/targets/{name}")
+async def get_gaze_target(name: str) -> Dict[str, Any]:
+    target = gaze_target_service.get(name)
+    if target is None:
+        raise HTTPException(status_code=404, detail="注视目标不存在")
+    return target.to_dict()
+
+
+@router.put("/targets/{name}")
+async def put_gaze_target(name: str, request: NamedGazeTargetRequest) -> Dict[str, Any]:
+    """新建或更新命名注视目标"""
+    try:
+        target, created = gaze_target_service.set_target(name, request.x, request.y, request.z, request.description)
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+    return {**target, "created": created}
+
+
+@router.delete("/targets/{name}")
+async def delete_gaze_target(name: str) -> Dict[str, Any]:
+    if not gaze_target_service.delete(name):
+        raise HTTPException(status_code=404, detail="注视目标不存在")
+    return {"name": name, "deleted": True}
+
+
+@router.post("/targets/{name}/look",
+             responses={422: {"description": "目标不可达，detail中给出最接近的可达方向和挡住目标的关节限位"}})
+async def look_at_gaze_target(name: str, request: Optional[LookAtTargetRequest] = None) -> Dict[str, Any]:
+    """按名字看向注视目标"""
+    try:
+        return await gaze_target_service.look_at(name, request.speed if request else None)
+    except LookupError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+    except GazeTargetUnreachable as e:
+        logger.info(f"注视目标不可达: {e}")
+        raise HTTPException(status_code=422, detail={"message": str(e), "unreachable": e.details})
+    except RuntimeError as e:
+        raise HTTPException(status_code=503, detail=str(e))
@@ -0,0 +1,89 @@
+#!/usr/bin/env python3
+"""
+多语言语音API路由
+查看和切换当前语言，以及为识别结果选择回复的语言、提示词和音色
+"""
+
+from fastapi import APIRouter, HTTPException
+from pydantic import BaseModel, Field
+from typing import Dict, List, Optional, Any
+
+from services.language_service import language_service
+from services.memory_service import memory_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/language", tags=["language"])
+
+
+# 请求模型
+class LanguageUpdate(BaseModel):
+    """切换语言"""
+    language: str = Field(..., description="语言代码，如 zh-CN、en-US")
+    auto_detect: Optional[bool] = Field(None, description="是否按识别结果自动检测语言，留空时不改变")
+
+
+class TranscriptRequest(BaseModel):
+    """一条语音识别结果"""
+    text: str = Field(..., min_length=1, description="识别出的文字")
+    language: Optional[str] = Field(None, description="STT模型给出的语言（如果有）")
+    identity: Optional[str] = Field(None, description="人脸识别得到的说话人身份，用于读取偏好语言和对话记忆")
+
+
+# 响应模型
+class LanguageStatus(BaseModel):
+    """当前语言和各语言配置"""
+    language: str
+    auto_detect: bool
+    stt_model: str = Field(..., description="语音识别当前应使用的模型")
+    profiles: List[Dict[str, Any]]
+    switched_at: Optional[float] = None
+
+
+class TranscriptRoute(BaseModel):
+    """回复识别结果所用的语言"""
+    text: str
+    language: str
+    detected: Optional[str] = Field(None, description="检测到的语言，未启用或无法判断时为空")
+    system_prompt: str
+    tts_voice: str
+    memory: Optional[Dict[str, Any]] = Field(None, description="说话人的对话记忆（称呼、偏好和最近的对话摘要）")
+
+
+def _raise_for(e: Exception):
+    if isinstance(e, KeyError):
+        raise HTTPException(status_code=404, detail=e.args[0] if e.args else str(e))
+    if isinstance(e, ValueError):
+        raise HTTPException(status_code=422, detail=str(e))
+    logger.error(f"语言操作失败: {e}")
+    raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.get("", response_model=LanguageStatus)
+async def get_language():
+    """当前语言和各语言的音色、模型配置"""
+    return language_service.get_status()
+
+
+@router.put("", response_model=LanguageStatus)
+async def set_language(request: LanguageUpdate):
+    """切换当前语言"""
+    try:
+        return language_service.set_language(request.language, request.auto_detect)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.post("/route", response_model=TranscriptRoute)
+async def route_transcript(request: TranscriptRequest):
+    """为识别结果选择回复的语言、对话提示词和TTS音色，给出说话人时附带其对话记忆"""
+    try:
+        memory = await memory_service.get_context(request.identity) if request.identity else None
+        route = language_service.route_transcript(
+            request.text, request.language, memory["preferred_language"] if memory else None
+        )
+        route["memory"] = memory
+        return route
+    except Exception as e:
+        _raise_for(e)
@@ -0,0 +1,71 @@
+#!/usr/bin/env python3
+"""
+日志级别API路由
+运行中调整Rust端的全局和各模块日志级别，无需重启，便于在机器人上现场排查问题
+"""
+
+from fastapi import APIRouter, HTTPException
+from pydantic import BaseModel
+from typing import Optional
+
+from rust_bindings import get_rust_bindings_manager
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/logs", tags=["logs"])
+
+
+# 请求模型
+class LogLevelRequest(BaseModel):
+    """修改日志级别请求，不指定模块时修改全局级别"""
+    level: str
+    module: Optional[str] = None
+
+
+class LogFilterRequest(BaseModel):
+    """整体替换过滤规则请求，写法与RUST_LOG相同"""
+    filter: str
+
+
+# 响应模型
+class LogFilterResponse(BaseModel):
+    """当前生效的日志过滤规则"""
+    filter: str
+
+
+def _call(operation, *args):
+    try:
+        return operation(*args)
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+    except RuntimeError as e:
+        raise HTTPException(status_code=503, detail=str(e))
+
+
+@router.get("/level", response_model=LogFilterResponse)
+async def get_log_filter():
+    """获取当前生效的日志过滤规则"""
+    return LogFilterResponse(filter=_call(get_rust_bindings_manager().get_log_filter))
+
+
+@router.put("/level", response_model=LogFilterResponse)
+async def set_log_level(request: LogLevelRequest):
+    """修改全局或某个模块（含子模块）的日志级别，立即生效"""
+    manager = get_rust_bindings_manager()
+    return LogFilterResponse(filter=_call(manager.set_log_level, request.level, request.module))
+
+
+@router.put("/filter", response_model=LogFilterResponse)
+async def set_log_filter(request: LogFilterRequest):
+    """整体替换日志过滤规则，如 "info,reachy_mini_rust::hardware=debug" """
+    return LogFilterResponse(filter=_call(get_rust_bindings_manager().set_log_filter, request.filter))
+
+
+@router.delete("/level/{module}", response_model=LogFilterResponse)
+async def clear_log_level(module: str):
+    """移除模块的单独日志级别，恢复使用上级模块或全局级别"""
+    manager = get_rust_bindings_manager()
+    if not _call(manager.clear_log_level, module):
+        raise HTTPException(status_code=404, detail=f"模块 {module} 没有单独设置日志级别")
+    return LogFilterResponse(filter=_call(manager.get_log_filter))
@@ -0,0 +1,90 @@
+#!/usr/bin/env python3
+"""
+行为包市场API路由
+浏览索引中的行为包（元数据、签名状态、所需权限）并安装；
+安装请求必须带上用户确认过的权限，未确认时返回409和需要确认的权限列表
+"""
+
+from fastapi import APIRouter, HTTPException, Query
+from pydantic import BaseModel, Field
+from typing import Dict, List, Optional, Any
+
+from services.marketplace_service import marketplace_service, PermissionsRequired, SignatureError
+from api.behaviors import BehaviorPackInfo
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/marketplace", tags=["marketplace"])
+
+
+# 请求模型
+class InstallRequest(BaseModel):
+    """安装请求"""
+    accepted_permissions: List[str] = Field(default=[], description="用户确认授予的权限")
+
+
+# 响应模型
+class MarketplacePack(BaseModel):
+    """索引中的行为包"""
+    id: str
+    name: str
+    version: str
+    author: str
+    description: str
+    size: int
+    sha256: str
+    permissions: List[str] = Field(..., description="包声明需要的权限，安装前需要用户确认")
+    signature: str = Field(..., description="签名状态：valid、invalid、untrusted_key 或 unsigned")
+    key_id: Optional[str] = None
+    installable: bool
+    installed_version: Optional[str] = None
+
+
+def _raise_for(e: Exception):
+    if isinstance(e, PermissionsRequired):
+        # 409时给出需要确认的权限，前端据此提示用户后重新提交
+        raise HTTPException(status_code=409, detail={"message": str(e), "permissions": e.permissions})
+    if isinstance(e, SignatureError):
+        raise HTTPException(status_code=403, detail=str(e))
+    if isinstance(e, KeyError):
+        raise HTTPException(status_code=404, detail=e.args[0] if e.args else str(e))
+    if isinstance(e, ValueError):
+        raise HTTPException(status_code=422, detail=str(e))
+    if isinstance(e, RuntimeError):
+        raise HTTPException(status_code=503, detail=str(e))
+    logger.error(f"行为包市场操作失败: {e}")
+    raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.get("/status")
+async def get_marketplace_status() -> Dict[str, Any]:
+    """索引地址、受信任的公钥和缓存状态"""
+    return marketplace_service.get_status()
+
+
+@router.get("/packs", response_model=List[MarketplacePack])
+async def list_packs(refresh: bool = Query(False, description="忽略缓存重新获取索引")):
+    """索引中的行为包"""
+    try:
+        return await marketplace_service.list_packs(refresh)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.get("/packs/{pack_id}", response_model=MarketplacePack)
+async def get_pack(pack_id: str):
+    """单个行为包的元数据、签名状态和所需权限"""
+    try:
+        return await marketplace_service.get_pack(pack_id)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.post("/packs/{pack_id}/install", response_model=BehaviorPackInfo)
+async def install_pack(pack_id: str, request: InstallRequest):
+    """下载并安装行为包，安装后默认禁用"""
+    try:
+        return await marketplace_service.install_pack(pack_id, request.accepted_permissions)
+    except Exception as e:
+        _raise_for(e)
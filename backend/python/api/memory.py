@@ -0,0 +1,121 @@
+#!/usr/bin/env python3
+"""
+对话记忆API路由
+查看、更新和删除按人脸识别身份保存的对话摘要和用户偏好
+"""
+
+from fastapi import APIRouter, HTTPException
+from pydantic import BaseModel, Field
+from typing import Dict, List, Optional, Any
+
+from services.memory_service import memory_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/memory", tags=["memory"])
+
+
+# 请求模型
+class SummaryRequest(BaseModel):
+    """一次对话的摘要"""
+    summary: str = Field(..., min_length=1, description="对话摘要")
+    language: Optional[str] = Field(None, description="对话语言")
+
+
+class PreferencesUpdate(BaseModel):
+    """用户偏好，未给出的字段保持不变，空字符串表示清除"""
+    display_name: Optional[str] = Field(None, description="称呼")
+    preferred_language: Optional[str] = Field(None, description="偏好语言，如 zh-CN、en-US")
+    preferences: Optional[Dict[str, Any]] = Field(None, description="其他偏好，值为null的键会被删除")
+
+
+# 响应模型
+class MemoryResponse(BaseModel):
+    """一个人的对话记忆"""
+    identity: str
+    display_name: Optional[str] = None
+    preferred_language: Optional[str] = None
+    preferences: Dict[str, Any]
+    summaries: List[Dict[str, Any]]
+    interaction_count: int
+    last_seen_at: Optional[str] = None
+    created_at: Optional[str] = None
+
+
+class RecordResponse(BaseModel):
+    """写入结果；未启用或隐私模式下recorded为False"""
+    recorded: bool
+    memory: Optional[MemoryResponse] = None
+
+
+class EraseResponse(BaseModel):
+    """删除结果"""
+    deleted: int
+
+
+def _raise_for(e: Exception):
+    if isinstance(e, KeyError):
+        raise HTTPException(status_code=404, detail=e.args[0] if e.args else str(e))
+    if isinstance(e, ValueError):
+        raise HTTPException(status_code=422, detail=str(e))
+    logger.error(f"对话记忆操作失败: {e}")
+    raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.get("")
+async def list_memories() -> List[Dict[str, Any]]:
+    """所有人的记忆概要"""
+    try:
+        return await memory_service.list_memories()
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.delete("", response_model=EraseResponse)
+async def erase_all_memories():
+    """删除所有人的对话记忆"""
+    try:
+        return {"deleted": await memory_service.erase_all()}
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.get("/{identity}", response_model=MemoryResponse)
+async def get_memory(identity: str):
+    """查看一个人的对话记忆"""
+    try:
+        return await memory_service.get_memory(identity)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.post("/{identity}/summaries", response_model=RecordResponse)
+async def add_summary(identity: str, request: SummaryRequest):
+    """保存一次对话的摘要"""
+    try:
+        memory = await memory_service.add_summary(identity, request.summary, request.language)
+        return {"recorded": memory is not None, "memory": memory}
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.put("/{identity}/preferences", response_model=RecordResponse)
+async def set_preferences(identity: str, request: PreferencesUpdate):
+    """更新称呼、偏好语言等"""
+    try:
+        memory = await memory_service.set_preferences(
+            identity, request.display_name, request.preferred_language, request.preferences
+        )
+        return {"recorded": memory is not None, "memory": memory}
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.delete("/{identity}", response_model=EraseResponse)
+async def erase_memory(identity: str):
+    """删除一个人的对话记忆"""
+    try:
+        return {"deleted": await memory_service.erase(identity)}
+    except Exception as e:
+        _raise_for(e)
@@ -0,0 +1,112 @@
+#!/usr/bin/env python3
+"""
+状态历史API路由
+按时间范围和分辨率查询降采样保存的关键指标，供前端绘制趋势图；查看和重置性能基线；
+另外提供CPU性能剖析，在设备上采集一段时间的调用栈用于生成火焰图
+"""
+
+import asyncio
+
+from fastapi import APIRouter, HTTPException, Query, Response
+from fastapi.responses import JSONResponse
+from pydantic import BaseModel
+from typing import Any, Dict, List, Optional
+
+from rust_bindings import get_rust_bindings_manager
+from services.baseline_service import baseline_service
+from services.metrics_history_service import metrics_history_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/metrics", tags=["metrics"])
+
+
+# 响应模型
+class MetricPoint(BaseModel):
+    """一个时间段内指标的均值、最小值和最大值"""
+    t: float
+    mean: float
+    min: float
+    max: float
+
+
+class MetricsHistoryResponse(BaseModel):
+    """状态历史查询响应，t为时间段起点（Unix时间，秒）"""
+    start: float
+    end: float
+    resolution: float
+    units: Dict[str, str]
+    series: Dict[str, List[MetricPoint]]
+
+
+@router.get("", response_model=Dict[str, str])
+async def list_metrics():
+    """可查询的指标及单位"""
+    return metrics_history_service.list_metrics()
+
+
+@router.get("/history", response_model=MetricsHistoryResponse)
+async def get_metrics_history(
+    metrics: Optional[str] = Query(None, description="逗号分隔的指标名，为空时返回全部指标"),
+    start: Optional[float] = Query(None, description="开始时间（Unix时间，秒），默认结束时间前一小时"),
+    end: Optional[float] = Query(None, description="结束时间（Unix时间，秒），默认当前时间"),
+    resolution: Optional[float] = Query(None, gt=0, description="分辨率（秒），默认按最大点数自动选择")
+):
+    """查询状态历史"""
+    if not metrics_history_service.enabled:
+        raise HTTPException(status_code=503, detail="状态历史未启用")
+    
+    names = [name.strip() for name in metrics.split(",") if name.strip()] if metrics else None
+    try:
+        return MetricsHistoryResponse(**metrics_history_service.query(names, start, end, resolution))
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+
+
+@router.get("/baselines")
+async def get_baselines() -> Dict[str, Any]:
+    """各指标的性能基线、最近一个窗口的值以及是否处于退化状态"""
+    return baseline_service.get_status()
+
+
+@router.delete("/baselines")
+async def reset_baselines(
+    metric: Optional[str] = Query(None, description="只重置该指标，为空时重置全部")
+) -> Dict[str, Any]:
+    """丢弃性能基线并重新积累，新模型或新配置确实更慢时用于接受新的性能水平"""
+    try:
+        return {"reset": baseline_service.reset(metric)}
+    except KeyError:
+        raise HTTPException(status_code=404, detail=f"指标没有基线: {metric}")
+
+
+@router.get("/profile", response_class=Response,
+            responses={200: {"content": {"text/plain": {}, "image/svg+xml": {}, "application/json": {}}}})
+async def get_cpu_profile(
+    seconds: int = Query(10, ge=1, description="采样时长（秒），上限为Rust端配置的profiling_max_seconds"),
+    format: str = Query("folded", pattern="^(folded|svg|json)$",
+                        description="folded为折叠调用栈文本，svg为火焰图，json附带采样信息")
+):
+    """采集本进程的CPU调用栈，需要在Rust端配置中打开performance.profiling_enabled
+    
+    svg格式可以直接在浏览器中查看；folded格式也可以交给speedscope等工具
+    """
+    manager = get_rust_bindings_manager()
+    try:
+        profile = await asyncio.to_thread(manager.capture_cpu_profile, seconds)
+    except PermissionError as e:
+        raise HTTPException(status_code=403, detail=str(e))
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+    except RuntimeError as e:
+        raise HTTPException(status_code=503, detail=str(e))
+    except OSError as e:
+        logger.error(f"CPU性能剖析失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+    
+    if format == "json":
+        return JSONResponse(profile)
+    if format == "svg":
+        return Response(content=profile["flamegraph"], media_type="image/svg+xml")
+    return Response(content=profile["folded"], media_type="text/plain; charset=utf-8")
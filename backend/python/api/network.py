@@ -0,0 +1,118 @@
+#!/usr/bin/env python3
+"""
+Wi-Fi网络管理API路由
+提供连接状态查询、扫描、加入和删除网络以及设置热点开关的REST API接口，用于首次联网设置
+"""
+
+from fastapi import APIRouter, HTTPException
+from pydantic import BaseModel, Field
+from typing import Dict, List, Optional, Any
+
+from services.network_service import network_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/network", tags=["network"])
+
+
+# 请求模型
+class JoinRequest(BaseModel):
+    """加入网络请求"""
+    ssid: str = Field(..., min_length=1, description="网络名称")
+    password: str = Field(default="", description="WPA密码，开放网络留空")
+
+
+# 响应模型
+class WifiNetwork(BaseModel):
+    """扫描到的网络"""
+    ssid: str
+    bssid: str
+    signal: int = Field(..., description="信号强度（0-100）")
+    security: str = Field(..., description="加密方式，开放网络为空")
+    frequency: Optional[int] = Field(None, description="频率（MHz）")
+    in_use: bool
+
+
+class JoinResponse(BaseModel):
+    """加入结果；pending为True时在后台执行，结果见状态接口的last_join"""
+    pending: bool
+    ssid: str
+    message: str
+
+
+def _raise_for(e: Exception):
+    if isinstance(e, KeyError):
+        raise HTTPException(status_code=404, detail=e.args[0] if e.args else str(e))
+    if isinstance(e, ValueError):
+        raise HTTPException(status_code=422, detail=str(e))
+    if isinstance(e, RuntimeError):
+        raise HTTPException(status_code=503, detail=str(e))
+    logger.error(f"网络管理操作失败: {e}")
+    raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.get("/status")
+async def get_network_status() -> Dict[str, Any]:
+    """当前连接、IP地址、外网连通性和设置热点状态"""
+    try:
+        return await network_service.get_status()
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.get("/wifi/scan", response_model=List[WifiNetwork])
+async def scan_wifi():
+    """扫描附近的Wi-Fi网络"""
+    try:
+        return await network_service.scan()
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.get("/wifi/saved", response_model=List[str])
+async def list_saved_networks():
+    """已保存的网络"""
+    try:
+        return await network_service.saved_networks()
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.post("/wifi/join", response_model=JoinResponse)
+async def join_wifi(request: JoinRequest):
+    """加入网络并保存，以后自动重连"""
+    try:
+        return await network_service.join(request.ssid, request.password)
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.delete("/wifi/saved/{ssid}")
+async def forget_network(ssid: str) -> Dict[str, Any]:
+    """删除保存的网络"""
+    try:
+        await network_service.forget(ssid)
+    except Exception as e:
+        _raise_for(e)
+    return {"success": True, "ssid": ssid}
+
+
+@router.post("/ap/start")
+async def start_ap() -> Dict[str, Any]:
+    """开启设置热点（会断开当前的Wi-Fi连接）"""
+    try:
+        await network_service.start_ap()
+        return await network_service.get_status()
+    except Exception as e:
+        _raise_for(e)
+
+
+@router.post("/ap/stop")
+async def stop_ap() -> Dict[str, Any]:
+    """关闭设置热点并恢复已保存的网络"""
+    try:
+        await network_service.stop_ap()
+        return await network_service.get_status()
+    except Exception as e:
+        _raise_for(e)
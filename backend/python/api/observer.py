@@ -0,0 +1,95 @@
+#!/usr/bin/env python3
+"""
+观察者会话API路由
+远程协助者申请只读观察者会话，主人用管理员密码同意或拒绝，随时查看和撤销会话
+"""
+
+from fastapi import APIRouter, HTTPException, Query
+from pydantic import BaseModel, Field
+from typing import Any, Dict, List, Optional
+
+from services.observer_service import observer_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/observer", tags=["observer"])
+
+
+# 请求模型
+class ObserverRequestBody(BaseModel):
+    """协助者的观察者会话申请"""
+    name: str = Field(..., min_length=1, max_length=64, description="协助者名称，主人同意时可以看到")
+    reason: str = Field("", max_length=500, description="申请原因")
+    duration_minutes: Optional[float] = Field(None, gt=0, description="希望的会话时长（分钟），默认OBSERVER_DEFAULT_DURATION_MINUTES")
+
+
+class ApproveRequest(BaseModel):
+    """主人同意申请"""
+    admin_password: str = Field(..., description="管理员密码，作为主人同意的凭证")
+    duration_minutes: Optional[float] = Field(None, gt=0, description="会话时长（分钟），为空时使用申请的时长")
+
+
+@router.get("/status")
+async def get_observer_status() -> Dict[str, Any]:
+    """观察者会话状态"""
+    return observer_service.get_status()
+
+
+@router.post("/requests")
+async def create_observer_request(body: ObserverRequestBody) -> Dict[str, Any]:
+    """申请观察者会话，返回的secret用于查询申请结果"""
+    try:
+        return await observer_service.create_request(body.name, body.reason, body.duration_minutes)
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+
+
+@router.get("/requests")
+async def list_observer_requests() -> List[Dict[str, Any]]:
+    """等待同意的申请"""
+    return observer_service.list_requests()
+
+
+@router.get("/requests/{request_id}")
+async def poll_observer_request(request_id: str, secret: str = Query(..., description="申请时返回的secret")) -> Dict[str, Any]:
+    """查询申请结果，同意后第一次查询返回观察者令牌"""
+    result = observer_service.poll(request_id, secret)
+    if result is None:
+        raise HTTPException(status_code=404, detail="申请不存在")
+    return result
+
+
+@router.post("/requests/{request_id}/approve")
+async def approve_observer_request(request_id: str, body: ApproveRequest) -> Dict[str, Any]:
+    """同意申请并创建会话"""
+    try:
+        return await observer_service.approve(request_id, body.admin_password, body.duration_minutes)
+    except LookupError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+    except PermissionError as e:
+        raise HTTPException(status_code=403, detail=str(e))
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+
+
+@router.post("/requests/{request_id}/deny")
+async def deny_observer_request(request_id: str) -> Dict[str, Any]:
+    """拒绝申请"""
+    if not observer_service.deny(request_id):
+        raise HTTPException(status_code=404, detail="申请不存在或已处理")
+    return {"id": request_id, "status": "denied"}
+
+
+@router.get("/sessions")
+async def list_observer_sessions() -> List[Dict[str, Any]]:
+    """有效的观察者会话"""
+    return observer_service.list_sessions()
+
+
+@router.delete("/sessions/{session_id}")
+async def revoke_observer_session(session_id: str) -> Dict[str, Any]:
+    """撤销会话并断开观察者的连接"""
+    if not await observer_service.revoke(session_id):
+        raise HTTPException(status_code=404, detail="会话不存在")
+    return {"id": session_id, "revoked": True}
@@ -0,0 +1,60 @@
+#!/usr/bin/env python3
+"""
+人员热力图API路由
+查询一周中各时间各位置有人的概率，例如"一般什么时候有人在书桌前"；数据只保存在本机
+"""
+
+from fastapi import APIRouter, HTTPException, Query
+from typing import Any, Dict, Optional
+
+from services.gaze_target_service import gaze_target_service
+from services.presence_service import presence_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/presence", tags=["presence"])
+
+
+@router.get("/status")
+async def get_presence_status() -> Dict[str, Any]:
+    """热力图状态"""
+    return presence_service.get_status()
+
+
+@router.get("/heatmap")
+async def get_presence_heatmap(
+    weekday: Optional[int] = Query(None, ge=0, le=6, description="一周中的某天，0为周一"),
+    hour: Optional[int] = Query(None, ge=0, le=23, description="一天中的小时")
+) -> Dict[str, Any]:
+    """各格子有人的概率（底座坐标系地面网格）"""
+    return presence_service.heatmap(weekday, hour)
+
+
+@router.get("/usual")
+async def get_usual_presence(
+    target: Optional[str] = Query(None, description="命名注视目标，例如desk；与x、y二选一"),
+    x: Optional[float] = Query(None, description="位置x（底座坐标系，米）"),
+    y: Optional[float] = Query(None, description="位置y（底座坐标系，米）"),
+    radius: Optional[float] = Query(None, gt=0, le=5, description="统计半径（米），默认一个格子"),
+    min_probability: float = Query(0.3, ge=0, le=1, description="usual中只列出不低于该概率的时间")
+) -> Dict[str, Any]:
+    """某个位置附近一般什么时候有人"""
+    if target is not None:
+        gaze_target = gaze_target_service.get(target)
+        if gaze_target is None:
+            raise HTTPException(status_code=404, detail=f"注视目标不存在: {target}")
+        x, y = gaze_target.x, gaze_target.y
+    elif x is None or y is None:
+        raise HTTPException(status_code=422, detail="需要指定target或x、y")
+    
+    result = presence_service.usual_times(x, y, radius, min_probability)
+    if target is not None:
+        result["target"] = gaze_target.name
+    return result
+
+
+@router.delete("/data")
+async def clear_presence_data() -> Dict[str, Any]:
+    """删除全部热力图数据"""
+    return {"cleared_cells": presence_service.clear()}
@@ -0,0 +1,86 @@
+#!/usr/bin/env python3
+"""
+隐私模式API路由
+提供开启、关闭和查询隐私模式的REST API接口
+"""
+
+from fastapi import APIRouter, HTTPException
+from pydantic import BaseModel, Field
+from typing import Dict, List, Optional, Any
+from datetime import datetime
+
+from services.privacy_service import privacy_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/privacy", tags=["privacy"])
+
+
+# 请求模型
+class PrivacyEnableRequest(BaseModel):
+    """开启隐私模式请求"""
+    park_head: Optional[bool] = Field(None, description="是否低头停放（默认使用配置）")
+    reason: Optional[str] = Field(None, max_length=200, description="开启原因")
+
+
+# 响应模型
+class PrivacyStatusResponse(BaseModel):
+    """隐私模式状态响应"""
+    enabled: bool
+    since: Optional[str]
+    reason: Optional[str]
+    head_parked: bool
+    led_on: bool
+    pipelines: List[str]
+    persisted: bool
+
+
+class CommandResponse(BaseModel):
+    """命令执行响应"""
+    success: bool
+    message: str
+    timestamp: str
+    data: Optional[Dict[str, Any]] = None
+
+
+@router.get("", response_model=PrivacyStatusResponse)
+async def get_privacy_status():
+    """获取隐私模式状态"""
+    return PrivacyStatusResponse(**privacy_service.get_status())
+
+
+@router.post("/enable", response_model=CommandResponse)
+async def enable_privacy_mode(request: PrivacyEnableRequest):
+    """开启隐私模式，立即停止摄像头和麦克风采集"""
+    try:
+        status = await privacy_service.enable(park_head=request.park_head, reason=request.reason)
+        
+        return CommandResponse(
+            success=True,
+            message="隐私模式已开启",
+            timestamp=datetime.now().isoformat(),
+            data=status
+        )
+        
+    except Exception as e:
+        logger.error(f"开启隐私模式失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.post("/disable", response_model=CommandResponse)
+async def disable_privacy_mode():
+    """关闭隐私模式，之后需要重新初始化视频流"""
+    try:
+        status = await privacy_service.disable()
+        
+        return CommandResponse(
+            success=True,
+            message="隐私模式已关闭",
+            timestamp=datetime.now().isoformat(),
+            data=status
+        )
+        
+    except Exception as e:
+        logger.error(f"关闭隐私模式失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
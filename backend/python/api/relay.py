@@ -0,0 +1,68 @@
+#!/usr/bin/env python3
+"""
+远程中继API路由
+提供远程中继状态查询和启停的REST API接口
+"""
+
+from fastapi import APIRouter, HTTPException
+from pydantic import BaseModel
+from typing import Dict, Optional, Any
+from datetime import datetime
+
+from services.relay_service import relay_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/relay", tags=["relay"])
+
+
+# 响应模型
+class CommandResponse(BaseModel):
+    """命令执行响应"""
+    success: bool
+    message: str
+    timestamp: str
+    data: Optional[Dict[str, Any]] = None
+
+
+@router.get("/status")
+async def get_relay_status() -> Dict[str, Any]:
+    """获取远程中继状态"""
+    return relay_service.get_status()
+
+
+@router.post("/start", response_model=CommandResponse)
+async def start_relay():
+    """启动远程中继"""
+    try:
+        success = await relay_service.start()
+        
+        return CommandResponse(
+            success=success,
+            message="远程中继已启动" if success else f"远程中继启动失败: {relay_service.last_error}",
+            timestamp=datetime.now().isoformat(),
+            data=relay_service.get_status()
+        )
+        
+    except Exception as e:
+        logger.error(f"启动远程中继失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.post("/stop", response_model=CommandResponse)
+async def stop_relay():
+    """停止远程中继"""
+    try:
+        await relay_service.stop()
+        
+        return CommandResponse(
+            success=True,
+            message="远程中继已停止",
+            timestamp=datetime.now().isoformat(),
+            data=relay_service.get_status()
+        )
+        
+    except Exception as e:
+        logger.error(f"停止远程中继失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
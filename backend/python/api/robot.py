@@ -10,7 +10,9 @@ from pydantic import BaseModel, Field
 from typing import Dict, List, Optional, Any
 from datetime import datetime
 
+from core.correlation import get_correlation_id
 from services.robot_service import robot_service
+from services.privacy_service import privacy_service
 from utils.logger import setup_logger
 
 logger = setup_logger(__name__)
@@ -36,7 +38,7 @@ class BodyMoveRequest(BaseModel):
 
 class LEDColorRequest(BaseModel):
     """LED颜色设置请求"""
-    color: str = Field(..., regex=r'^#[0-9A-Fa-f]{6}$|^(red|green|blue|yellow|purple|cyan|white|off)$', 
+    color: str = Field(..., pattern=r'^#[0-9A-Fa-f]{6}$|^(red|green|blue|yellow|purple|cyan|white|off)$', 
                       description="颜色值（十六进制或预定义颜色名）")
 
 
@@ -56,6 +58,7 @@ class RobotStatusResponse(BaseModel):
     temperature: float
     error_messages: List[str]
     last_update: str
+    privacy_mode: bool
 
 
 class CommandResponse(BaseModel):
@@ -64,6 +67,8 @@ class CommandResponse(BaseModel):
     message: str
     timestamp: str
     data: Optional[Dict[str, Any]] = None
+    # 本次请求的关联ID，与日志和X-Request-ID响应头中的相同
+    request_id: Optional[str] = Field(default_factory=get_correlation_id)
 
 
 @router.get("/status", response_model=RobotStatusResponse)
@@ -79,7 +84,8 @@ async def get_robot_status():
             battery_level=robot_state.battery_level,
             temperature=robot_state.temperature,
             error_messages=robot_state.error_messages,
-            last_update=robot_state.last_update.isoformat()
+            last_update=robot_state.last_update.isoformat(),
+            privacy_mode=privacy_service.enabled
         )
         
     except Exception as e:
@@ -0,0 +1,139 @@
+#!/usr/bin/env python3
+"""
+首次设置API路由
+设置模式下提供设置页面（/setup）和保存接口：设置Wi-Fi、机器人名称和管理员密码后切换到正常模式
+"""
+
+from fastapi import APIRouter, HTTPException
+from fastapi.responses import HTMLResponse
+from pydantic import BaseModel, Field
+from typing import Dict, List, Optional, Any
+
+from core.models import OperatingMode
+from services.mode_service import mode_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(tags=["setup"])
+
+PORTAL_PAGE = """<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Reachy Mini 设置</title>
+<style>
+body { font-family: sans-serif; max-width: 420px; margin: 2em auto; padding: 0 1em; }
+label { display: block; margin-top: 1em; }
+input, select, button { width: 100%; padding: 0.5em; margin-top: 0.3em; box-sizing: border-box; }
+button { margin-top: 1.5em; }
+#message { margin-top: 1em; }
+</style>
+</head>
+<body>
+<h1>Reachy Mini 设置</h1>
+<form id="setup">
+<label>机器人名称<input name="robot_name" value="Reachy Mini" maxlength="32" required></label>
+<label>管理员密码<input name="admin_password" type="password" required></label>
+<div id="wifi" hidden>
+<label>Wi-Fi网络<select name="ssid"><option value="">不设置（使用有线网络）</option></select></label>
+<label>Wi-Fi密码<input name="wifi_password" type="password"></label>
+</div>
+<button type="submit">保存</button>
+</form>
+<p id="message"></p>
+<script>
+const form = document.getElementById("setup");
+const message = document.getElementById("message");
+fetch("/api/setup/status").then(r => r.json()).then(status => {
+  if (!status.wifi_enabled) return;
+  document.getElementById("wifi").hidden = false;
+  fetch("/api/network/wifi/scan").then(r => r.json()).then(networks => {
+    for (const network of networks) form.ssid.add(new Option(network.ssid + " (" + network.signal + "%)", network.ssid));
+  });
+});
+form.addEventListener("submit", async event => {
+  event.preventDefault();
+  const body = Object.fromEntries(new FormData(form));
+  if (!body.ssid) delete body.ssid;
+  const response = await fetch("/api/setup", {
+    method: "POST", headers: {"Content-Type": "application/json"}, body: JSON.stringify(body)
+  });
+  const result = await response.json();
+  message.textContent = response.ok
+    ? (result.wifi ? result.wifi.message : "设置完成")
+    : (typeof result.detail === "string" ? result.detail : JSON.stringify(result.detail));
+});
+</script>
+</body>
+</html>
+"""
+
+COMPLETED_PAGE = """<!DOCTYPE html>
+<html lang="zh-CN">
+<head><meta charset="utf-8"><title>Reachy Mini 设置</title></head>
+<body><p>已完成首次设置。</p></body>
+</html>
+"""
+
+
+# 请求模型
+class SetupRequest(BaseModel):
+    """首次设置请求"""
+    robot_name: str = Field(..., min_length=1, max_length=32, description="机器人名称")
+    admin_password: str = Field(..., description="管理员密码")
+    ssid: Optional[str] = Field(None, description="要加入的Wi-Fi网络，留空时不设置Wi-Fi")
+    wifi_password: str = Field(default="", description="Wi-Fi密码，开放网络留空")
+
+
+# 响应模型
+class SetupStatus(BaseModel):
+    """运行模式和设置状态"""
+    mode: str = Field(..., description="运行模式：boot、setup 或 normal")
+    configured: bool
+    robot_name: str
+    wifi_enabled: bool
+    history: List[Dict[str, Any]]
+    last_error: Optional[str] = None
+
+
+class SetupResponse(SetupStatus):
+    """设置结果；wifi为加入网络的结果，pending为True时在后台执行"""
+    wifi: Optional[Dict[str, Any]] = None
+
+
+def _raise_for(e: Exception):
+    if isinstance(e, KeyError):
+        raise HTTPException(status_code=404, detail=e.args[0] if e.args else str(e))
+    if isinstance(e, ValueError):
+        raise HTTPException(status_code=422, detail=str(e))
+    if isinstance(e, RuntimeError):
+        raise HTTPException(status_code=409, detail=str(e))
+    logger.error(f"首次设置失败: {e}")
+    raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.get("/setup", response_class=HTMLResponse, include_in_schema=False)
+async def setup_page():
+    """设置页面，完成设置后不再提供"""
+    if mode_service.mode == OperatingMode.SETUP:
+        return PORTAL_PAGE
+    return COMPLETED_PAGE
+
+
+@router.get("/api/setup/status", response_model=SetupStatus)
+async def get_setup_status():
+    """当前运行模式和设置状态"""
+    return mode_service.get_status()
+
+
+@router.post("/api/setup", response_model=SetupResponse)
+async def complete_setup(request: SetupRequest):
+    """保存首次设置并切换到正常模式，只能在设置模式下调用"""
+    try:
+        return await mode_service.complete_setup(
+            request.robot_name, request.admin_password, request.ssid, request.wifi_password
+        )
+    except Exception as e:
+        _raise_for(e)
@@ -0,0 +1,31 @@
+#!/usr/bin/env python3
+"""
+仿真API路由
+导出由关节配置生成的URDF/SDF模型，供Gazebo/MuJoCo加载；
+仿真桥接本身在实时控制配置realtime.sim_bridge中启用
+"""
+
+from fastapi import APIRouter, HTTPException, Query, Response
+
+from rust_bindings import get_rust_bindings_manager
+
+router = APIRouter(prefix="/api/sim", tags=["sim"])
+
+@router.get("/model", response_class=Response,
+            responses={200: {"content": {"application/xml": {}}, "description": "URDF或SDF模型"}})
+async def export_model(
+    format: str = Query("urdf", pattern="^(urdf|sdf)$", description="模型格式：urdf或sdf")
+):
+    """导出机器人仿真模型"""
+    try:
+        model = get_rust_bindings_manager().export_robot_model(format)
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+    except RuntimeError as e:
+        raise HTTPException(status_code=503, detail=str(e))
+    
+    return Response(
+        content=model,
+        media_type="application/xml",
+        headers={"Content-Disposition": f'attachment; filename="reachy_mini.{format}"'},
+    )
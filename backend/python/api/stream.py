@@ -16,6 +16,9 @@ import numpy as np
 from io import BytesIO
 
 from services.stream_service import stream_service
+from services.adaptive_stream import AdaptiveStreamSender
+from services.privacy_service import privacy_service
+from services.observer_service import check_observer_websocket, observer_service
 from core.websocket_manager import WebSocketManager
 from utils.logger import setup_logger
 
@@ -46,6 +49,7 @@ class StreamStatusResponse(BaseModel):
     """流状态响应"""
     is_streaming: bool
     camera_opened: bool
+    privacy_mode: bool
     stream_settings: Dict[str, Any]
     stats: Dict[str, Any]
     processors_count: int
@@ -69,6 +73,7 @@ async def get_stream_status():
         return StreamStatusResponse(
             is_streaming=stream_info["is_streaming"],
             camera_opened=stream_info["camera_opened"],
+            privacy_mode=stream_info["privacy_mode"],
             stream_settings=stream_info["stream_settings"],
             stats=stream_info["stats"],
             processors_count=stream_info["processors_count"],
@@ -83,6 +88,8 @@ async def get_stream_status():
 @router.post("/start", response_model=CommandResponse)
 async def start_stream():
     """启动视频流"""
+    privacy_service.ensure_capture_allowed("camera")
+    
     try:
         success = await stream_service.start_streaming()
         
@@ -117,6 +124,8 @@ async def stop_stream():
 @router.post("/initialize", response_model=CommandResponse)
 async def initialize_stream(request: CameraSourceRequest):
     """初始化视频流"""
+    privacy_service.ensure_capture_allowed("camera")
+    
     try:
         success = await stream_service.initialize(request.source)
         
@@ -161,6 +170,8 @@ async def configure_stream(request: StreamConfigRequest):
 @router.get("/snapshot")
 async def capture_snapshot():
     """捕获快照"""
+    privacy_service.ensure_capture_allowed("camera")
+    
     try:
         snapshot_data = await stream_service.capture_snapshot()
         
@@ -201,10 +212,18 @@ async def get_stream_clients():
 async def websocket_stream_endpoint(websocket: WebSocket):
     """WebSocket视频流端点"""
     client_id = f"stream_client_{id(websocket)}"
+    # 自适应模式的发送器，客户端发送stream_mode切换
+    adaptive: Optional[AdaptiveStreamSender] = None
+    # 观察者可以观看视频流，但不能启停摄像头
+    allowed, observer = await check_observer_websocket(websocket)
+    if not allowed:
+        return
     
     try:
         # 建立WebSocket连接
         await ws_manager.connect(websocket, "stream")
+        if observer:
+            observer_service.attach(observer, websocket)
         
         # 添加到流客户端
         stream_service.add_client(client_id)
@@ -227,6 +246,10 @@ async def websocket_stream_endpoint(websocket: WebSocket):
                 
                 message_type = message.get("type")
                 
+                # 自适应模式下的帧确认和pong
+                if adaptive and adaptive.handle_message(message):
+                    continue
+                
                 if message_type == "ping":
                     await websocket.send_json({
                         "type": "pong",
@@ -243,6 +266,13 @@ async def websocket_stream_endpoint(websocket: WebSocket):
                             "timestamp": datetime.now().isoformat()
                         })
                 
+                elif message_type == "stream_control" and observer:
+                    await websocket.send_json({
+                        "type": "error",
+                        "message": "观察者会话只读，不能控制视频流",
+                        "timestamp": datetime.now().isoformat()
+                    })
+                
                 elif message_type == "stream_control":
                     # 流控制命令
                     command = message.get("command")
@@ -263,6 +293,25 @@ async def websocket_stream_endpoint(websocket: WebSocket):
                             "timestamp": datetime.now().isoformat()
                         })
                 
+                elif message_type == "stream_mode":
+                    # 切换固定画质和自适应模式
+                    mode = message.get("mode")
+                    if mode == "adaptive" and adaptive is None:
+                        adaptive = AdaptiveStreamSender(websocket)
+                        stream_service.add_frame_callback(adaptive.on_frame)
+                        adaptive.start()
+                    elif mode == "fixed" and adaptive is not None:
+                        stream_service.remove_frame_callback(adaptive.on_frame)
+                        await adaptive.stop()
+                        adaptive = None
+                    
+                    await websocket.send_json({
+                        "type": "stream_mode_response",
+                        "mode": "adaptive" if adaptive else "fixed",
+                        "adaptive": adaptive.get_status() if adaptive else None,
+                        "timestamp": datetime.now().isoformat()
+                    })
+                
                 else:
                     logger.warning(f"未知的WebSocket消息类型: {message_type}")
                 
@@ -284,8 +333,13 @@ async def websocket_stream_endpoint(websocket: WebSocket):
         logger.error(f"WebSocket连接错误: {e}")
     finally:
         # 清理资源
+        if adaptive is not None:
+            stream_service.remove_frame_callback(adaptive.on_frame)
+            await adaptive.stop()
         stream_service.remove_client(client_id)
         ws_manager.disconnect(websocket, "stream")
+        if observer:
+            observer_service.detach(observer, websocket)
 
 
 @router.get("/formats")
@@ -347,9 +401,15 @@ async def stream_health_check():
     try:
         stream_info = stream_service.get_stream_info()
         
+        if stream_info["privacy_mode"]:
+            status = "privacy_mode"
+        else:
+            status = "healthy" if stream_info["camera_opened"] else "camera_not_available"
+        
         health_status = {
-            "status": "healthy" if stream_info["camera_opened"] else "camera_not_available",
+            "status": status,
             "camera_opened": stream_info["camera_opened"],
+            "privacy_mode": stream_info["privacy_mode"],
             "is_streaming": stream_info["is_streaming"],
             "connected_clients": stream_info["stats"]["connected_clients"],
             "average_fps": stream_info["stats"]["average_fps"],
@@ -409,6 +469,8 @@ async def add_frame_processor():
 @router.get("/debug/frame")
 async def get_debug_frame():
     """获取调试帧信息"""
+    privacy_service.ensure_capture_allowed("camera")
+    
     try:
         if not stream_service.is_streaming:
             raise HTTPException(status_code=503, detail="视频流未启动")
@@ -0,0 +1,88 @@
+#!/usr/bin/env python3
+"""
+主从同步API路由
+本机跟随另一台机器人的关节运动：开始跟随、停止跟随、查询同步状态
+"""
+
+from fastapi import APIRouter, HTTPException
+from pydantic import BaseModel, Field
+from typing import Dict, Optional, Any
+from datetime import datetime
+
+from services.sync_service import sync_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/sync", tags=["sync"])
+
+
+# 请求模型
+class FollowRequest(BaseModel):
+    """跟随请求，未给出的参数使用SYNC_*配置"""
+    leader_url: str = Field(..., description="主机器人控制WebSocket地址，如 ws://192.168.1.20:8000/ws/control")
+    rate_hz: Optional[float] = Field(None, gt=0, le=100, description="关节数据推送频率（Hz）")
+    latency_compensation: Optional[float] = Field(None, ge=0, description="延迟补偿（秒）")
+    head_scale: Optional[float] = Field(None, description="头部角度缩放系数，负数为镜像")
+    body_scale: Optional[float] = Field(None, description="身体位置缩放系数")
+
+
+# 响应模型
+class CommandResponse(BaseModel):
+    """命令执行响应"""
+    success: bool
+    message: str
+    timestamp: str
+    data: Optional[Dict[str, Any]] = None
+
+
+@router.get("/status")
+async def get_sync_status() -> Dict[str, Any]:
+    """获取主从同步状态"""
+    return sync_service.get_status()
+
+
+@router.post("/follow", response_model=CommandResponse)
+async def follow_leader(request: FollowRequest):
+    """开始跟随主机器人"""
+    try:
+        success = await sync_service.follow(
+            request.leader_url,
+            rate_hz=request.rate_hz,
+            latency_compensation=request.latency_compensation,
+            head_scale=request.head_scale,
+            body_scale=request.body_scale
+        )
+        if not success:
+            raise HTTPException(status_code=422, detail=sync_service.last_error)
+        
+        return CommandResponse(
+            success=True,
+            message=f"开始跟随 {request.leader_url}",
+            timestamp=datetime.now().isoformat(),
+            data=sync_service.get_status()
+        )
+        
+    except HTTPException:
+        raise
+    except Exception as e:
+        logger.error(f"开始跟随失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.post("/stop", response_model=CommandResponse)
+async def stop_following():
+    """停止跟随"""
+    try:
+        await sync_service.stop()
+        
+        return CommandResponse(
+            success=True,
+            message="主从同步已停止",
+            timestamp=datetime.now().isoformat(),
+            data=sync_service.get_status()
+        )
+        
+    except Exception as e:
+        logger.error(f"停止跟随失败: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
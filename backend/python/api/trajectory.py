@@ -0,0 +1,90 @@
+#!/usr/bin/env python3
+"""
+轨迹可视化API路由
+执行动作之前把规划轨迹展开为关节曲线和笛卡尔路径供前端绘图检查，
+也可以直接返回PNG图片，便于无界面调试
+"""
+
+import asyncio
+
+from fastapi import APIRouter, HTTPException, Query, Response
+from pydantic import BaseModel, Field
+from typing import Dict, List, Any
+
+from core.config import get_config
+from rust_bindings import get_rust_bindings_manager
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/trajectory", tags=["trajectory"])
+
+
+# 请求模型
+class TrajectoryPlotRequest(BaseModel):
+    """轨迹可视化请求，trajectory为轨迹文件的JSON格式"""
+    trajectory: Dict[str, Any]
+    sample_rate: float = Field(default=50.0, gt=0, le=1000, description="采样率（Hz）")
+
+
+# 响应模型
+class Point3Model(BaseModel):
+    x: float
+    y: float
+    z: float
+
+
+class JointSeriesModel(BaseModel):
+    """单个关节的采样曲线"""
+    joint: str
+    positions: List[float]
+    velocities: List[float]
+
+
+class TrajectoryPlotResponse(BaseModel):
+    """展开后的轨迹（秒、rad、米）"""
+    name: str
+    duration: float
+    times: List[float]
+    joints: List[JointSeriesModel]
+    camera_path: List[Point3Model]
+    gaze_path: List[Point3Model]
+
+
+def _raise_for(e: Exception):
+    if isinstance(e, ValueError):
+        raise HTTPException(status_code=422, detail=str(e))
+    if isinstance(e, RuntimeError):
+        raise HTTPException(status_code=503, detail=str(e))
+    logger.error(f"轨迹可视化失败: {e}")
+    raise HTTPException(status_code=500, detail=str(e))
+
+
+@router.post("/plot", response_model=TrajectoryPlotResponse)
+async def plot_trajectory(request: TrajectoryPlotRequest):
+    """展开轨迹为关节位置/速度曲线，以及相机和视线落点的路径"""
+    manager = get_rust_bindings_manager()
+    try:
+        result = await asyncio.to_thread(manager.sample_trajectory, request.trajectory, request.sample_rate)
+    except Exception as e:
+        _raise_for(e)
+    return TrajectoryPlotResponse(**result)
+
+
+@router.post("/plot.png", response_class=Response,
+             responses={200: {"content": {"image/png": {}}}})
+async def plot_trajectory_png(
+    request: TrajectoryPlotRequest,
+    width: int = Query(1200, ge=64, le=4096, description="图片宽度（像素）"),
+    height: int = Query(800, ge=64, le=4096, description="图片高度（像素）")
+):
+    """把轨迹的关节位置和速度曲线渲染为PNG"""
+    manager = get_rust_bindings_manager()
+    font_path = get_config().realtime.PLOT_FONT_PATH
+    try:
+        png = await asyncio.to_thread(
+            manager.plot_trajectory_png, request.trajectory, request.sample_rate, width, height, font_path
+        )
+    except Exception as e:
+        _raise_for(e)
+    return Response(content=png, media_type="image/png")
@@ -0,0 +1,90 @@
+#!/usr/bin/env python3
+"""
+Webhook API路由
+登记、修改、删除和测试Webhook，查看最近的投递结果
+"""
+
+from fastapi import APIRouter, HTTPException, Query
+from pydantic import BaseModel, Field
+from typing import Any, Dict, List, Optional
+
+from services.webhook_service import webhook_service
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+router = APIRouter(prefix="/api/webhooks", tags=["webhooks"])
+
+
+# 请求模型
+class WebhookCreateRequest(BaseModel):
+    """登记Webhook请求"""
+    url: str = Field(..., max_length=500, description="接收事件的地址")
+    event_types: List[str] = Field(..., min_length=1, description="订阅的事件类型，\"*\"表示全部")
+    description: Optional[str] = Field(None, max_length=200, description="说明")
+
+
+class WebhookUpdateRequest(BaseModel):
+    """修改Webhook请求，未给出的字段保持不变"""
+    event_types: Optional[List[str]] = Field(None, min_length=1, description="订阅的事件类型")
+    enabled: Optional[bool] = Field(None, description="是否启用，重新启用时清零失败计数")
+    description: Optional[str] = Field(None, max_length=200, description="说明，空字符串表示清除")
+
+
+@router.get("")
+async def list_webhooks() -> Dict[str, Any]:
+    """登记的Webhook（不含密钥）和投递统计"""
+    try:
+        return {"webhooks": await webhook_service.list_webhooks(), **webhook_service.get_status()}
+    except RuntimeError as e:
+        raise HTTPException(status_code=503, detail=str(e))
+
+
+@router.post("", status_code=201)
+async def create_webhook(request: WebhookCreateRequest) -> Dict[str, Any]:
+    """登记Webhook，返回的secret用于校验签名，只返回这一次"""
+    try:
+        return await webhook_service.create_webhook(request.url, request.event_types, request.description)
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+    except RuntimeError as e:
+        raise HTTPException(status_code=503, detail=str(e))
+
+
+@router.patch("/{webhook_id}")
+async def update_webhook(webhook_id: str, request: WebhookUpdateRequest) -> Dict[str, Any]:
+    """修改订阅的事件、启用状态或说明"""
+    try:
+        webhook = await webhook_service.update_webhook(
+            webhook_id, request.event_types, request.enabled, request.description
+        )
+    except ValueError as e:
+        raise HTTPException(status_code=422, detail=str(e))
+    if webhook is None:
+        raise HTTPException(status_code=404, detail=f"Webhook {webhook_id} 不存在")
+    return webhook
+
+
+@router.delete("/{webhook_id}")
+async def delete_webhook(webhook_id: str) -> Dict[str, Any]:
+    """删除Webhook"""
+    if not await webhook_service.delete_webhook(webhook_id):
+        raise HTTPException(status_code=404, detail=f"Webhook {webhook_id} 不存在")
+    logger.info(f"删除Webhook {webhook_id}")
+    return {"success": True}
+
+
+@router.post("/{webhook_id}/test")
+async def test_webhook(webhook_id: str) -> Dict[str, Any]:
+    """发送一个test事件并返回投递结果（包含重试，可能需要较长时间）"""
+    if not await webhook_service.send_test(webhook_id):
+        raise HTTPException(status_code=404, detail=f"Webhook {webhook_id} 不存在或未启用")
+    return webhook_service.deliveries[-1]
+
+
+@router.get("/deliveries")
+async def get_deliveries(
+    limit: int = Query(50, ge=1, le=200, description="返回的最大记录数")
+) -> List[Dict[str, Any]]:
+    """最近的投递结果，最新的在前"""
+    return list(reversed(webhook_service.deliveries))[:limit]
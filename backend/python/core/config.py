@@ -14,6 +14,7 @@ Reachy Mini 配置管理模块
 """
 
 import os
+import uuid
 from functools import lru_cache
 from pathlib import Path
 from typing import List, Optional, Dict, Any
@@ -21,6 +22,8 @@ from typing import List, Optional, Dict, Any
 from pydantic import Field, validator
 from pydantic_settings import BaseSettings, SettingsConfigDict
 
+from core.config_crypto import decrypt_value
+
 
 class DatabaseSettings(BaseSettings):
     """数据库配置"""
@@ -87,7 +90,7 @@ class AISettings(BaseSettings):
     
     # 模型配置
     AI_MODEL_PATH: str = Field(default="./models", description="AI模型路径")
-    AI_DEVICE: str = Field(default="cpu", description="AI推理设备 (cpu, cuda, mps)")
+    AI_DEVICE: str = Field(default="cpu", description="AI推理设备 (cpu, cuda, mps, mock)，mock不加载模型，返回脚本化结果")
     AI_BATCH_SIZE: int = Field(default=1, description="AI推理批次大小")
     AI_MAX_WORKERS: int = Field(default=4, description="AI工作线程数")
     
@@ -137,7 +140,7 @@ class AISettings(BaseSettings):
     
     @validator('AI_DEVICE')
     def validate_device(cls, v):
-        allowed_devices = ['cpu', 'cuda', 'mps', 'auto']
+        allowed_devices = ['cpu', 'cuda', 'mps', 'auto', 'mock']
         if v not in allowed_devices:
             raise ValueError(f'AI_DEVICE must be one of {allowed_devices}')
         return v
@@ -165,6 +168,16 @@ class StreamSettings(BaseSettings):
     STREAM_BUFFER_SIZE: int = Field(default=10, description="流缓冲区大小")
     STREAM_MAX_CLIENTS: int = Field(default=10, description="最大客户端数")
     STREAM_TIMEOUT: int = Field(default=30, description="流超时时间")
+    JPEG_QUALITY: int = Field(default=80, description="WebSocket视频帧的JPEG质量")
+    
+    # 自适应视频流配置（按客户端带宽和RTT调整分辨率和质量）
+    ADAPTIVE_FPS: float = Field(default=15.0, description="自适应模式的最大帧率")
+    ADAPTIVE_MAX_IN_FLIGHT: int = Field(default=2, description="最多未确认的帧数，超过时丢帧")
+    ADAPTIVE_PING_INTERVAL: float = Field(default=2.0, description="测量RTT的ping间隔（秒）")
+    ADAPTIVE_ACK_TIMEOUT: float = Field(default=5.0, description="帧确认超时（秒），超时视为丢失")
+    ADAPTIVE_UTILIZATION: float = Field(default=0.8, description="可使用的带宽比例")
+    ADAPTIVE_MAX_RTT: float = Field(default=0.5, description="RTT超过时降低画质（秒）")
+    ADAPTIVE_UPGRADE_DELAY: float = Field(default=5.0, description="带宽余量持续多久后提高画质（秒）")
     
     # WebRTC配置
     WEBRTC_STUN_SERVER: str = Field(
@@ -231,6 +244,29 @@ class RobotSettings(BaseSettings):
     model_config = SettingsConfigDict(env_prefix="ROBOT_")
 
 
+class IdentitySettings(BaseSettings):
+    """机器人身份配置（同一网络中有多台机器人时区分来源）"""
+    
+    UUID: str = Field(default="", description="机器人UUID，为空时首次启动生成并保存在数据目录")
+    NAME: str = Field(default="", description="机器人名称，为空时使用首次设置中填写的名称")
+    GROUP: str = Field(default="default", description="机器人分组")
+    
+    @validator('UUID')
+    def validate_uuid(cls, v):
+        if v:
+            uuid.UUID(v)
+        return v.lower()
+    
+    @validator('GROUP')
+    def validate_group(cls, v):
+        # 分组用作MQTT主题的一级，不能包含主题分隔符和通配符
+        if not v or any(c in v for c in "/+#") or len(v) > 50:
+            raise ValueError('GROUP must be 1-50 characters without "/", "+" or "#"')
+        return v
+    
+    model_config = SettingsConfigDict(env_prefix="IDENTITY_")
+
+
 class SecuritySettings(BaseSettings):
     """安全配置"""
     
@@ -246,7 +282,10 @@ class SecuritySettings(BaseSettings):
     # API密钥
     API_KEYS: List[str] = Field(default=[], description="API密钥列表")
     
-    # CORS配置
+    # CORS配置（同时用于WebSocket握手的Origin校验）
+    CORS_ENABLED: bool = Field(default=True, description="允许配置的源跨域访问，关闭时只允许同源")
+    ALLOW_CREDENTIALS: bool = Field(default=True, description="跨域请求允许携带凭据（允许任意源时无效）")
+    CORS_MAX_AGE: int = Field(default=600, description="预检请求缓存时间（秒）")
     ALLOWED_ORIGINS: List[str] = Field(
         default=["http://localhost:3000", "http://127.0.0.1:3000"],
         description="允许的源"
@@ -269,6 +308,11 @@ class SecuritySettings(BaseSettings):
     SSL_CERT_FILE: Optional[str] = Field(default=None, description="SSL证书文件")
     SSL_KEY_FILE: Optional[str] = Field(default=None, description="SSL私钥文件")
     
+    # 敏感配置可以用 python -m core.config_crypto 加密后写入环境变量
+    @validator('SECRET_KEY')
+    def decrypt_secret_key(cls, v):
+        return decrypt_value("SECURITY_SECRET_KEY", v)
+    
     model_config = SettingsConfigDict(env_prefix="SECURITY_")
 
 
@@ -285,6 +329,7 @@ class VisionSettings(BaseSettings):
     ENABLE_FEATURE_DETECTION: bool = Field(default=False, description="启用特征检测")
     FACE_CASCADE_PATH: str = Field(default="haarcascade_frontalface_default.xml", description="人脸级联分类器路径")
     PROCESSING_THREADS: int = Field(default=2, description="处理线程数")
+    PLAYBACK_PATH: Optional[str] = Field(default=None, description="回放的图片目录或MJPEG文件，设置后代替摄像头")
     
     model_config = SettingsConfigDict(env_prefix="VISION_")
 
@@ -301,6 +346,7 @@ class RealtimeSettings(BaseSettings):
     EMERGENCY_STOP_ENABLED: bool = Field(default=True, description="启用紧急停止")
     SENSOR_UPDATE_RATE: float = Field(default=200.0, description="传感器更新率")
     COMMAND_TIMEOUT_MS: int = Field(default=1000, description="命令超时时间(毫秒)")
+    PLOT_FONT_PATH: Optional[str] = Field(default=None, description="轨迹PNG绘图使用的字体文件，不设置时图中没有文字")
     PID_GAINS: Dict[str, Dict[str, float]] = Field(
         default={
             "joint_0": {"kp": 10.0, "ki": 0.1, "kd": 0.5},
@@ -337,6 +383,404 @@ class HardwareSettings(BaseSettings):
     model_config = SettingsConfigDict(env_prefix="HARDWARE_")
 
 
+class AnalyticsSettings(BaseSettings):
+    """交互分析配置"""
+    
+    ENABLE_ANALYTICS: bool = Field(default=True, description="启用交互分析统计")
+    STORE_SESSION_LOG: bool = Field(default=True, description="保存交互事件日志")
+    STORE_IDENTIFIABLE_DATA: bool = Field(
+        default=False,
+        description="在事件日志中保存可识别个人的数据（身份、人脸位置、年龄、性别、情绪）"
+    )
+    FLUSH_INTERVAL: float = Field(default=30.0, description="统计写入数据库的间隔（秒）")
+    
+    model_config = SettingsConfigDict(env_prefix="ANALYTICS_")
+
+
+class PrivacySettings(BaseSettings):
+    """隐私模式配置"""
+    
+    PARK_HEAD: bool = Field(default=True, description="开启隐私模式时让头部低头停放")
+    PARK_PAN: float = Field(default=0.0, description="停放时的水平角度（度）")
+    PARK_TILT: float = Field(default=40.0, description="停放时的垂直角度（度，正值为低头）")
+    LED_COLOR: str = Field(default="red", description="隐私模式状态灯颜色")
+    
+    model_config = SettingsConfigDict(env_prefix="PRIVACY_")
+
+
+class AuditSettings(BaseSettings):
+    """命令审计日志配置（日志由Rust实时控制器写入，这里只负责查询）"""
+    
+    DIRECTORY: str = Field(default="./data/audit", description="审计文件目录，与Rust端audit.directory一致")
+    MAX_QUERY_LIMIT: int = Field(default=1000, description="单次查询最多返回的记录数")
+    
+    model_config = SettingsConfigDict(env_prefix="AUDIT_")
+
+
+class UploadSettings(BaseSettings):
+    """模型和动画上传配置（分块上传，支持断点续传）"""
+    
+    MAX_FILE_SIZE_MB: int = Field(default=2048, description="单个文件最大大小（MB）")
+    CHUNK_SIZE_MB: int = Field(default=8, description="建议的分块大小（MB）")
+    MAX_CHUNK_SIZE_MB: int = Field(default=64, description="单个分块最大大小（MB）")
+    SESSION_TTL_HOURS: float = Field(default=24.0, description="未完成的上传保留时长（小时）")
+    MODEL_EXTENSIONS: List[str] = Field(default=[".onnx"], description="允许上传的模型文件扩展名")
+    ANIMATION_EXTENSIONS: List[str] = Field(default=[".json", ".zip"], description="允许上传的动画文件扩展名")
+    
+    model_config = SettingsConfigDict(env_prefix="UPLOAD_")
+
+
+class MarketplaceSettings(BaseSettings):
+    """行为包市场配置（从索引地址浏览和安装社区分享的行为包）"""
+    
+    INDEX_URL: str = Field(default="", description="行为包索引地址（https://），返回JSON格式的包列表")
+    TRUSTED_KEYS: Dict[str, str] = Field(
+        default={},
+        description="受信任的发布者公钥，键为key_id，值为Base64编码的Ed25519公钥"
+    )
+    REQUIRE_SIGNATURE: bool = Field(default=True, description="只允许安装受信任公钥签名的包")
+    ALLOW_INSECURE: bool = Field(default=False, description="允许非TLS的索引和下载地址（仅用于测试）")
+    CACHE_TTL: float = Field(default=300.0, description="索引缓存时间（秒）")
+    MAX_DOWNLOAD_SIZE_MB: int = Field(default=64, description="单个包最大下载大小（MB）")
+    REQUEST_TIMEOUT: float = Field(default=30.0, description="请求超时（秒）")
+    
+    model_config = SettingsConfigDict(env_prefix="MARKETPLACE_")
+
+
+class RetentionSettings(BaseSettings):
+    """数据保留配置（期限为0表示不限制）"""
+    
+    ENABLE_CLEANUP: bool = Field(default=True, description="启用定期清理")
+    CLEANUP_INTERVAL: float = Field(default=3600.0, description="清理间隔（秒）")
+    
+    # 录像
+    RECORDINGS_MAX_AGE_DAYS: float = Field(default=30.0, description="录像最长保留天数")
+    RECORDINGS_MAX_SIZE_MB: int = Field(default=2048, description="录像最大占用空间（MB）")
+    
+    # 快照
+    SNAPSHOTS_MAX_AGE_DAYS: float = Field(default=7.0, description="快照最长保留天数")
+    SNAPSHOTS_MAX_SIZE_MB: int = Field(default=512, description="快照最大占用空间（MB）")
+    
+    # 日志（日志文件和数据库日志表）
+    LOGS_MAX_AGE_DAYS: float = Field(default=14.0, description="日志最长保留天数")
+    LOGS_MAX_SIZE_MB: int = Field(default=256, description="日志文件最大占用空间（MB）")
+    
+    # 黑匣子
+    BLACKBOX_MAX_AGE_DAYS: float = Field(default=7.0, description="黑匣子记录最长保留天数")
+    BLACKBOX_MAX_SIZE_MB: int = Field(default=256, description="黑匣子最大占用空间（MB）")
+    
+    # 交互分析记录
+    INTERACTIONS_MAX_AGE_DAYS: float = Field(default=90.0, description="交互记录最长保留天数")
+    
+    # 事件日志
+    JOURNAL_MAX_AGE_DAYS: float = Field(default=90.0, description="事件日志最长保留天数")
+    
+    # 对话记忆（按最后一次见到的时间计算）
+    MEMORIES_MAX_AGE_DAYS: float = Field(default=180.0, description="对话记忆最长保留天数")
+    
+    model_config = SettingsConfigDict(env_prefix="RETENTION_")
+
+
+class RelaySettings(BaseSettings):
+    """远程中继配置（通过外连隧道在局域网外访问本地API）"""
+    
+    ENABLED: bool = Field(default=False, description="启用远程中继")
+    BROKER_URL: str = Field(default="", description="中继服务器地址（wss://）")
+    ROBOT_ID: str = Field(default="", description="机器人标识")
+    TOKEN: str = Field(default="", description="中继认证令牌（可以是加密值）")
+    ALLOW_INSECURE: bool = Field(default=False, description="允许非TLS连接（仅用于测试）")
+    LOCAL_API_URL: Optional[str] = Field(default=None, description="本地API地址，默认使用服务器主机和端口")
+    FORWARD_PREFIXES: List[str] = Field(
+        default=["/api/", "/system/", "/health"],
+        description="允许转发的路径前缀"
+    )
+    RECONNECT_INTERVAL: float = Field(default=5.0, description="初始重连间隔（秒）")
+    MAX_RECONNECT_INTERVAL: float = Field(default=60.0, description="最大重连间隔（秒）")
+    REQUEST_TIMEOUT: float = Field(default=30.0, description="转发请求超时（秒）")
+    MAX_CONCURRENT_REQUESTS: int = Field(default=8, description="最大并发转发请求数")
+    
+    @validator('TOKEN')
+    def decrypt_token(cls, v):
+        return decrypt_value("RELAY_TOKEN", v)
+    
+    model_config = SettingsConfigDict(env_prefix="RELAY_")
+
+
+class SyncSettings(BaseSettings):
+    """主从同步配置（本机跟随另一台机器人的关节运动）"""
+    
+    ENABLED: bool = Field(default=False, description="启动时跟随LEADER_URL指定的机器人")
+    LEADER_URL: str = Field(default="", description="主机器人控制WebSocket地址，如 ws://192.168.1.20:8000/ws/control")
+    RATE_HZ: float = Field(default=30.0, gt=0, le=100, description="关节数据推送频率（Hz）")
+    LATENCY_COMPENSATION: float = Field(default=0.1, ge=0, description="延迟补偿（秒），按关节速度向前预测的时间，0为不补偿")
+    MAX_EXTRAPOLATION: float = Field(default=0.3, ge=0, description="最大预测时间（秒）")
+    HEAD_SCALE: float = Field(default=1.0, description="头部角度缩放系数，负数为镜像")
+    BODY_SCALE: float = Field(default=1.0, description="身体位置缩放系数")
+    STALE_TIMEOUT: float = Field(default=0.5, gt=0, description="超过该时间没有收到关节数据视为中断（秒），期间保持当前姿态")
+    RECONNECT_INTERVAL: float = Field(default=2.0, description="初始重连间隔（秒）")
+    MAX_RECONNECT_INTERVAL: float = Field(default=30.0, description="最大重连间隔（秒）")
+    
+    model_config = SettingsConfigDict(env_prefix="SYNC_")
+
+
+class ChoreographySettings(BaseSettings):
+    """编舞配置（多台机器人按共享时间轴同步演出）"""
+    
+    DIR: str = Field(default="./data/choreographies", description="编舞文件目录")
+    LEAD_TIME: float = Field(default=3.0, gt=0, description="发出提示到演出开始的时间（秒），需大于网络延迟")
+    PEERS: List[str] = Field(default=[], description="一起演出的其他机器人API地址，如 http://192.168.1.21:8000")
+    PLAYBACK_RATE: float = Field(default=50.0, gt=0, le=200, description="动画回放频率（Hz）")
+    REQUEST_TIMEOUT: float = Field(default=5.0, description="向其他机器人发送提示的超时（秒）")
+    
+    model_config = SettingsConfigDict(env_prefix="CHOREOGRAPHY_")
+
+
+class FleetSettings(BaseSettings):
+    """机群心跳上报配置（定期上报匿名健康信息，便于集中监控多台机器人）"""
+    
+    ENABLED: bool = Field(default=False, description="启用机群心跳上报")
+    ENDPOINT: str = Field(default="", description="心跳上报地址（https://）")
+    TOKEN: str = Field(default="", description="上报认证令牌（可以是加密值）")
+    ROBOT_ID: str = Field(default="", description="机器人标识，为空时由机器ID匿名生成")
+    ALLOW_INSECURE: bool = Field(default=False, description="允许非TLS上报（仅用于测试）")
+    REPORT_INTERVAL: float = Field(default=300.0, description="上报间隔（秒）")
+    INITIAL_BACKOFF: float = Field(default=10.0, description="上报失败后的初始重试间隔（秒）")
+    MAX_BACKOFF: float = Field(default=3600.0, description="最大重试间隔（秒）")
+    MAX_QUEUE_SIZE: int = Field(default=288, description="离线时最多缓存的心跳数量")
+    REQUEST_TIMEOUT: float = Field(default=10.0, description="上报请求超时（秒）")
+    
+    @validator('TOKEN')
+    def decrypt_token(cls, v):
+        return decrypt_value("FLEET_TOKEN", v)
+    
+    model_config = SettingsConfigDict(env_prefix="FLEET_")
+
+
+class MetricsHistorySettings(BaseSettings):
+    """状态历史配置（降采样保存关键指标，前端直接绘制趋势图，无需外部时序数据库）"""
+    
+    ENABLED: bool = Field(default=True, description="启用状态历史")
+    SAMPLE_INTERVAL: float = Field(default=1.0, description="采样间隔（秒）")
+    BUCKET_SECONDS: float = Field(default=10.0, description="降采样桶长度（秒），每桶保存均值、最小值和最大值")
+    RETENTION_HOURS: float = Field(default=24.0, description="保留时长（小时）")
+    PERSIST: bool = Field(default=False, description="同时写入磁盘，重启后保留历史")
+    FILE: str = Field(default="./data/metrics_history.jsonl", description="历史文件路径")
+    MAX_POINTS: int = Field(default=500, description="一次查询每个指标最多返回的点数")
+    
+    model_config = SettingsConfigDict(env_prefix="METRICS_HISTORY_")
+
+
+class AlertSettings(BaseSettings):
+    """告警配置（规则和通知渠道写在YAML文件中，指标规则依赖状态历史采样）"""
+    
+    ENABLED: bool = Field(default=True, description="启用告警")
+    RULES_FILE: str = Field(default="./alerts.yaml", description="告警规则文件路径")
+    REQUEST_TIMEOUT: float = Field(default=5.0, description="Webhook和MQTT通知超时（秒）")
+    HISTORY_SIZE: int = Field(default=200, description="保留的最近告警通知数")
+    
+    model_config = SettingsConfigDict(env_prefix="ALERT_")
+
+
+class WebhookSettings(BaseSettings):
+    """Webhook配置（事件发生时POST到用户登记的地址，带HMAC签名）"""
+    
+    ENABLED: bool = Field(default=True, description="启用Webhook投递")
+    MAX_WEBHOOKS: int = Field(default=20, description="最多登记的Webhook数量")
+    ALLOW_INSECURE: bool = Field(default=False, description="允许非TLS地址（局域网内的自动化服务）")
+    MAX_ATTEMPTS: int = Field(default=5, description="每次投递的最大尝试次数")
+    INITIAL_BACKOFF: float = Field(default=2.0, description="首次重试间隔（秒），之后每次加倍")
+    MAX_BACKOFF: float = Field(default=300.0, description="最大重试间隔（秒）")
+    REQUEST_TIMEOUT: float = Field(default=10.0, description="请求超时（秒）")
+    MAX_PENDING: int = Field(default=1000, description="最多同时等待投递的事件数，超出时丢弃")
+    
+    model_config = SettingsConfigDict(env_prefix="WEBHOOK_")
+
+
+class NetworkSettings(BaseSettings):
+    """Wi-Fi网络管理配置（扫描和加入网络，未配置网络时开启热点供首次设置）"""
+    
+    ENABLED: bool = Field(default=False, description="启用Wi-Fi网络管理")
+    BACKEND: str = Field(default="auto", description="网络后端：auto、networkmanager 或 wpa_supplicant")
+    INTERFACE: str = Field(default="wlan0", description="无线网卡")
+    CHECK_INTERVAL: float = Field(default=10.0, description="连接状态检查间隔（秒）")
+    COMMAND_TIMEOUT: float = Field(default=30.0, description="网络命令超时（秒）")
+    AP_FALLBACK: bool = Field(default=True, description="没有可用网络时开启设置热点")
+    AP_FALLBACK_DELAY: float = Field(default=60.0, description="断开多久后开启设置热点（秒）")
+    AP_SSID: str = Field(default="ReachyMini-Setup-{id}", description="设置热点名称，{id}替换为机器人UUID的前4位")
+    AP_PASSWORD: str = Field(default="", description="设置热点密码（8-63位，可以是加密值），为空时热点不加密")
+    
+    @validator('AP_PASSWORD')
+    def decrypt_ap_password(cls, v):
+        return decrypt_value("NETWORK_AP_PASSWORD", v)
+    
+    model_config = SettingsConfigDict(env_prefix="NETWORK_")
+
+
+class ObserverSettings(BaseSettings):
+    """观察者会话配置（远程协助者经主人同意后只读查看遥测、日志和摄像头）"""
+    
+    ENABLED: bool = Field(default=True, description="允许申请观察者会话")
+    DEFAULT_DURATION_MINUTES: float = Field(default=30.0, gt=0, description="会话默认时长（分钟）")
+    MAX_DURATION_MINUTES: float = Field(default=240.0, gt=0, description="会话最长时长（分钟）")
+    REQUEST_TTL_MINUTES: float = Field(default=10.0, gt=0, description="申请等待主人同意的时长（分钟）")
+    MAX_PENDING_REQUESTS: int = Field(default=5, description="同时等待同意的申请数")
+    ALLOWED_PATHS: List[str] = Field(
+        default=["/api/metrics", "/api/logs", "/api/stream", "/api/robot/status", "/api/alerts",
+                 "/api/health", "/health", "/system/info"],
+        description="观察者可以访问的路径前缀（只允许GET）"
+    )
+    
+    model_config = SettingsConfigDict(env_prefix="OBSERVER_")
+
+
+class SetupSettings(BaseSettings):
+    """首次设置配置（未完成设置时进入设置模式，通过热点和设置页面配置Wi-Fi、名称和管理员密码）"""
+    
+    ENABLED: bool = Field(default=True, description="未完成设置时进入设置模式")
+    STATE_FILE: str = Field(default="setup.json", description="设置状态文件（相对于数据目录）")
+    ADMIN_USERNAME: str = Field(default="admin", description="设置页面创建的管理员用户名")
+    ADMIN_EMAIL: str = Field(default="admin@reachy-mini.local", description="管理员邮箱")
+    MIN_PASSWORD_LENGTH: int = Field(default=8, description="管理员密码最小长度")
+    DEFAULT_ROBOT_NAME: str = Field(default="Reachy Mini", description="设置完成前使用的机器人名称")
+    
+    model_config = SettingsConfigDict(env_prefix="SETUP_")
+
+
+class SystemdSettings(BaseSettings):
+    """systemd集成配置（只在由systemd以Type=notify启动时生效）"""
+    
+    ENABLED: bool = Field(default=True, description="启用就绪通知、看门狗和状态报告")
+    WATCHDOG_FRACTION: float = Field(default=0.5, description="喂看门狗的间隔占WatchdogSec的比例")
+    HEALTH_CHECK_INTERVAL: float = Field(default=30.0, description="健康检查间隔（秒），启用看门狗时取两者中较小的")
+    REQUIRED_COMPONENTS: List[str] = Field(
+        default=["database", "api_server"],
+        description="启动自检要求已就绪的组件，Rust模块可用时还要求rust_bindings"
+    )
+    
+    @validator('WATCHDOG_FRACTION')
+    def validate_watchdog_fraction(cls, v):
+        if not 0 < v < 1:
+            raise ValueError('WATCHDOG_FRACTION must be between 0 and 1')
+        return v
+    
+    model_config = SettingsConfigDict(env_prefix="SYSTEMD_")
+
+
+class LanguageSettings(BaseSettings):
+    """多语言语音配置（每种语言的TTS音色、STT模型和对话提示词）"""
+    
+    DEFAULT: str = Field(default="zh-CN", description="默认语言")
+    STATE_FILE: str = Field(default="language.json", description="当前语言状态文件（相对于数据目录）")
+    AUTO_DETECT: bool = Field(default=True, description="按识别结果检测语言，用对应语言的提示词和音色回复")
+    AUTO_DETECT_STT_MODEL: str = Field(default="whisper-small", description="自动检测时使用的多语言STT模型")
+    DETECTION_MIN_CHARS: int = Field(default=2, description="少于这么多字符的识别结果不做语言检测")
+    PROFILES: Dict[str, Dict[str, str]] = Field(
+        default={
+            "zh-CN": {
+                "name": "简体中文",
+                "tts_voice": "zh-CN-XiaoxiaoNeural",
+                "stt_model": "whisper-small-zh",
+                "system_prompt": "你是桌面机器人Reachy Mini，请用简洁、友好的中文回答。",
+            },
+            "en-US": {
+                "name": "English",
+                "tts_voice": "en-US-JennyNeural",
+                "stt_model": "whisper-small-en",
+                "system_prompt": "You are Reachy Mini, a desktop robot. Answer briefly and warmly in English.",
+            },
+        },
+        description="各语言的配置：name、tts_voice、stt_model、system_prompt"
+    )
+    
+    @validator('PROFILES')
+    def validate_profiles(cls, v, values):
+        required = {"tts_voice", "stt_model", "system_prompt"}
+        for code, profile in v.items():
+            missing = required - set(profile)
+            if missing:
+                raise ValueError(f'PROFILES[{code}] is missing {sorted(missing)}')
+        if values.get('DEFAULT') not in v:
+            raise ValueError('DEFAULT must be one of PROFILES')
+        return v
+    
+    model_config = SettingsConfigDict(env_prefix="LANGUAGE_")
+
+
+class MemorySettings(BaseSettings):
+    """对话记忆配置（按人脸识别身份保存对话摘要和偏好）"""
+    
+    ENABLED: bool = Field(default=True, description="保存对话摘要和用户偏好")
+    MAX_SUMMARIES: int = Field(default=20, description="每人最多保留的对话摘要数，超出时删除最早的")
+    MAX_SUMMARY_LENGTH: int = Field(default=2000, description="单条摘要最大长度（字符）")
+    CONTEXT_SUMMARIES: int = Field(default=3, description="提供给对话模型的最近摘要数")
+    
+    model_config = SettingsConfigDict(env_prefix="MEMORY_")
+
+
+class GazeTargetSettings(BaseSettings):
+    """命名注视目标配置（"门口"、"书桌"等底座坐标系中的点）"""
+    
+    STATE_FILE: str = Field(default="gaze_targets.json", description="注视目标文件（相对于数据目录）")
+    MAX_TARGETS: int = Field(default=100, description="最多保存的注视目标数")
+    LOOK_SPEED: float = Field(default=20.0, description="看向注视目标时的头部速度（度/秒）")
+    
+    model_config = SettingsConfigDict(env_prefix="GAZE_TARGET_")
+
+
+class PresenceSettings(BaseSettings):
+    """有人出现的空间/时间热力图配置（数据只保存在本机）"""
+    
+    ENABLED: bool = Field(default=True, description="统计人员检测的位置和时间")
+    STATE_FILE: str = Field(default="presence_heatmap.json", description="热力图文件（相对于数据目录）")
+    SAMPLE_INTERVAL_SECONDS: float = Field(default=60.0, description="采样时段长度（秒），同一时段同一格子只计一次")
+    CELL_SIZE_M: float = Field(default=0.5, description="地面网格边长（米）")
+    MAX_RANGE_M: float = Field(default=5.0, description="超出该距离的人员不统计（米）")
+    HORIZONTAL_FOV_DEG: float = Field(default=70.0, description="相机水平视场角（度），用于估计人员方位")
+    PERSON_HEIGHT_M: float = Field(default=1.7, description="估计距离时假设的人体高度（米）")
+    MIN_CONFIDENCE: float = Field(default=0.5, description="低于该置信度的检测不统计")
+    
+    model_config = SettingsConfigDict(env_prefix="PRESENCE_")
+
+
+class PerformanceBaselineSettings(BaseSettings):
+    """性能基线配置（滚动保存关键性能指标的正常水平，升级或改配置后明显变差时告警）"""
+    
+    ENABLED: bool = Field(default=True, description="记录性能基线并检查性能退化")
+    STATE_FILE: str = Field(default="performance_baselines.json", description="基线文件（相对于数据目录）")
+    METRICS: Dict[str, str] = Field(
+        default={"loop_jitter": "lower", "inference_p95": "lower", "camera_fps": "higher"},
+        description="参与比较的状态历史指标及其较好的方向（lower/higher）"
+    )
+    WINDOW_SECONDS: float = Field(default=300.0, description="统计窗口（秒），每个窗口的均值与基线比较一次")
+    BASELINE_WINDOWS: int = Field(default=24, description="基线由最近多少个正常窗口的均值组成")
+    MIN_WINDOWS: int = Field(default=3, description="基线至少积累多少个窗口后才开始比较")
+    REGRESSION_PERCENT: float = Field(default=25.0, description="比基线差多少百分比算作性能退化")
+    
+    model_config = SettingsConfigDict(env_prefix="PERF_BASELINE_")
+
+
+class JournalSettings(BaseSettings):
+    """事件日志配置（把模式切换、错误和高置信度检测保存到数据库，供前端活动时间线查询）"""
+    
+    ENABLED: bool = Field(default=True, description="记录重要事件")
+    EVENT_TYPES: List[str] = Field(
+        default=["mode_changed", "emergency_stop", "performance_regression", "performance_recovered",
+                 "observer_approved", "behavior_finished"],
+        description="总是记录的事件类型"
+    )
+    DETECTION_EVENTS: List[str] = Field(
+        default=["face_seen", "face_recognized"],
+        description="按置信度筛选的检测事件类型"
+    )
+    MIN_CONFIDENCE: float = Field(default=0.8, description="检测事件的置信度不低于该值才记录")
+    RECORD_ERRORS: bool = Field(default=True, description="记录ERROR级别的日志")
+    ERROR_DEDUP_SECONDS: float = Field(default=60.0, description="同一条错误日志在该时间内只记录一次（秒）")
+    FLUSH_INTERVAL: float = Field(default=5.0, description="写入数据库的间隔（秒）")
+    MAX_ENTRIES: int = Field(default=100000, description="最多保存的条数，超出时删除最早的记录")
+    
+    model_config = SettingsConfigDict(env_prefix="JOURNAL_")
+
+
 class LoggingSettings(BaseSettings):
     """日志配置"""
     
@@ -398,11 +842,35 @@ class Settings(BaseSettings):
     ai: AISettings = AISettings()
     stream: StreamSettings = StreamSettings()
     robot: RobotSettings = RobotSettings()
+    identity: IdentitySettings = IdentitySettings()
     security: SecuritySettings = SecuritySettings()
     logging: LoggingSettings = LoggingSettings()
     vision: VisionSettings = VisionSettings()
     realtime: RealtimeSettings = RealtimeSettings()
     hardware: HardwareSettings = HardwareSettings()
+    analytics: AnalyticsSettings = AnalyticsSettings()
+    privacy: PrivacySettings = PrivacySettings()
+    audit: AuditSettings = AuditSettings()
+    retention: RetentionSettings = RetentionSettings()
+    upload: UploadSettings = UploadSettings()
+    marketplace: MarketplaceSettings = MarketplaceSettings()
+    relay: RelaySettings = RelaySettings()
+    sync: SyncSettings = SyncSettings()
+    choreography: ChoreographySettings = ChoreographySettings()
+    observer: ObserverSettings = ObserverSettings()
+    fleet: FleetSettings = FleetSettings()
+    metrics_history: MetricsHistorySettings = MetricsHistorySettings()
+    alert: AlertSettings = AlertSettings()
+    webhook: WebhookSettings = WebhookSettings()
+    network: NetworkSettings = NetworkSettings()
+    setup: SetupSettings = SetupSettings()
+    systemd: SystemdSettings = SystemdSettings()
+    language: LanguageSettings = LanguageSettings()
+    memory: MemorySettings = MemorySettings()
+    gaze_targets: GazeTargetSettings = GazeTargetSettings()
+    presence: PresenceSettings = PresenceSettings()
+    performance_baseline: PerformanceBaselineSettings = PerformanceBaselineSettings()
+    journal: JournalSettings = JournalSettings()
     
     # 环境配置
     ENVIRONMENT: str = Field(default="development", description="运行环境")
@@ -0,0 +1,101 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+敏感配置加密
+
+与Rust端 config_crypto 模块使用相同的格式和密钥：
+- 加密值形如 ENC[AES-256-GCM,<base64(12字节随机数 + 密文 + 认证标签)>]
+- 明文为值的JSON，配置名（如 RELAY_TOKEN）作为附加认证数据，密文不能挪给其他配置项
+- 32字节密钥以base64形式从环境变量 REACHY_CONFIG_KEY 或 REACHY_CONFIG_KEY_FILE 指向的文件读取
+
+用法：
+    python -m core.config_crypto generate-key
+    python -m core.config_crypto encrypt RELAY_TOKEN <令牌>
+"""
+
+import base64
+import json
+import os
+import sys
+from pathlib import Path
+from typing import Any, Optional
+
+from cryptography.hazmat.primitives.ciphers.aead import AESGCM
+
+ENCRYPTED_PREFIX = "ENC[AES-256-GCM,"
+ENCRYPTED_SUFFIX = "]"
+
+KEY_ENV = "REACHY_CONFIG_KEY"
+KEY_FILE_ENV = "REACHY_CONFIG_KEY_FILE"
+
+USAGE = "用法: python -m core.config_crypto generate-key | encrypt <配置名> <值>"
+
+KEY_LEN = 32
+NONCE_LEN = 12
+
+
+def is_encrypted(value: Any) -> bool:
+    return isinstance(value, str) and value.startswith(ENCRYPTED_PREFIX) and value.endswith(ENCRYPTED_SUFFIX)
+
+
+def load_key() -> Optional[bytes]:
+    """读取配置加密密钥：环境变量优先，其次是密钥文件；都没有时返回None"""
+    encoded = os.environ.get(KEY_ENV, "").strip()
+    if not encoded:
+        key_file = os.environ.get(KEY_FILE_ENV)
+        if not key_file:
+            return None
+        encoded = Path(key_file).read_text(encoding="utf-8").strip()
+    
+    key = base64.b64decode(encoded, validate=True)
+    if len(key) != KEY_LEN:
+        raise ValueError(f"配置加密密钥必须为 {KEY_LEN} 字节，实际为 {len(key)} 字节")
+    return key
+
+
+def encrypt_value(name: str, value: Any, key: Optional[bytes] = None) -> str:
+    """加密一个配置值"""
+    key = key or load_key()
+    if key is None:
+        raise ValueError(f"没有配置加密密钥（环境变量 {KEY_ENV} 或 {KEY_FILE_ENV}）")
+    
+    nonce = os.urandom(NONCE_LEN)
+    ciphertext = AESGCM(key).encrypt(nonce, json.dumps(value).encode("utf-8"), name.encode("utf-8"))
+    return f"{ENCRYPTED_PREFIX}{base64.b64encode(nonce + ciphertext).decode('ascii')}{ENCRYPTED_SUFFIX}"
+
+
+def decrypt_value(name: str, value: Any, key: Optional[bytes] = None) -> Any:
+    """解密一个配置值，未加密的值原样返回"""
+    if not is_encrypted(value):
+        return value
+    
+    key = key or load_key()
+    if key is None:
+        raise ValueError(f"配置 {name} 已加密，但没有密钥（环境变量 {KEY_ENV} 或 {KEY_FILE_ENV}）")
+    
+    sealed = base64.b64decode(value[len(ENCRYPTED_PREFIX):-len(ENCRYPTED_SUFFIX)])
+    if len(sealed) <= NONCE_LEN:
+        raise ValueError(f"配置 {name} 的密文过短")
+    
+    try:
+        plaintext = AESGCM(key).decrypt(sealed[:NONCE_LEN], sealed[NONCE_LEN:], name.encode("utf-8"))
+    except Exception:
+        raise ValueError(f"解密配置 {name} 失败：密钥错误或内容被篡改")
+    return json.loads(plaintext)
+
+
+def main(argv: list) -> int:
+    if len(argv) == 1 and argv[0] == "generate-key":
+        print(base64.b64encode(os.urandom(KEY_LEN)).decode("ascii"))
+        return 0
+    
+    if len(argv) == 3 and argv[0] == "encrypt":
+        print(encrypt_value(argv[1], argv[2]))
+        return 0
+    
+    print(USAGE, file=sys.stderr)
+    return 1
+
+
+if __name__ == "__main__":
+    sys.exit(main(sys.argv[1:]))
@@ -0,0 +1,73 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+Reachy Mini 请求关联ID
+
+一条API请求会经过多个服务、Rust扩展，最后到达硬件，排查问题时需要把这些日志串起来：
+- 每个HTTP请求带一个关联ID，客户端可以通过X-Request-ID请求头指定，否则生成一个ULID
+- 关联ID保存在上下文变量中，同一请求内的所有日志行自动带上该ID
+- 命令事件和发给Rust硬件管理器的命令也带上同一个ID，Rust侧的日志使用相同格式
+- 响应通过X-Request-ID响应头返回该ID
+
+ULID由48位毫秒时间戳和80位随机数组成，按生成时间排序，与Rust侧common::Ulid格式相同
+"""
+
+import contextvars
+import logging
+import os
+import re
+import threading
+import time
+from typing import Optional
+
+# 请求头名称
+REQUEST_ID_HEADER = "X-Request-ID"
+
+# 客户端指定的ID只接受这些字符，避免把任意内容写入日志
+_VALID_ID = re.compile(r"^[A-Za-z0-9._:-]{1,64}$")
+
+# Crockford Base32字母表
+_ALPHABET = "0123456789ABCDEFGHJKMNPQRSTVWXYZ"
+
+correlation_id: contextvars.ContextVar[Optional[str]] = contextvars.ContextVar("correlation_id", default=None)
+
+_lock = threading.Lock()
+_last_ulid = 0
+
+
+def new_id() -> str:
+    """生成一个ULID；同一毫秒内生成的ID在上一个的基础上加1，保证单调递增"""
+    global _last_ulid
+    with _lock:
+        timestamp = int(time.time() * 1000) & ((1 << 48) - 1)
+        value = (timestamp << 80) | int.from_bytes(os.urandom(10), "big")
+        if value >> 80 <= _last_ulid >> 80:
+            value = _last_ulid + 1
+        _last_ulid = value
+    
+    return "".join(_ALPHABET[(value >> shift) & 0x1F] for shift in range(125, -1, -5))
+
+
+def is_valid_id(value: str) -> bool:
+    """客户端指定的关联ID是否可以使用"""
+    return bool(_VALID_ID.match(value))
+
+
+def get_correlation_id() -> Optional[str]:
+    """当前请求的关联ID，不在请求上下文中时为None"""
+    return correlation_id.get()
+
+
+def install_log_record_factory():
+    """让每条日志记录带上correlation_id属性，没有关联ID时为"-"，重复调用无副作用"""
+    factory = logging.getLogRecordFactory()
+    if getattr(factory, "_with_correlation_id", False):
+        return
+    
+    def record_factory(*args, **kwargs):
+        record = factory(*args, **kwargs)
+        record.correlation_id = correlation_id.get() or "-"
+        return record
+    
+    record_factory._with_correlation_id = True
+    logging.setLogRecordFactory(record_factory)
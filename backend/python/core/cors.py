@@ -0,0 +1,103 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+跨域访问控制
+
+按 security 配置处理HTTP跨域请求，并在WebSocket握手时校验Origin请求头：
+- 允许的源可以是完整的源（https://app.example.com）、通配子域名
+  （https://*.example.com）或 "*"
+- 浏览器总会在WebSocket握手中携带Origin，与服务端同源的页面始终允许
+- 没有Origin头的连接来自非浏览器客户端（Python SDK、命令行工具），不受跨域限制
+"""
+
+import re
+from typing import Optional
+from urllib.parse import urlsplit
+
+from fastapi import FastAPI, WebSocket
+from fastapi.middleware.cors import CORSMiddleware
+
+from core.config import get_config
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+# 拒绝WebSocket连接时使用的关闭码（违反策略）
+WS_POLICY_VIOLATION = 1008
+
+
+def wildcard_regex(pattern: str) -> str:
+    """把通配子域名的配置项转换为正则，通配符只匹配至少一级子域名，不匹配裸域名"""
+    return re.escape(pattern.strip().rstrip("/").lower()).replace(r"\*", r"[a-z0-9-]+(?:\.[a-z0-9-]+)*")
+
+
+def origin_matches(origin: str, pattern: str) -> bool:
+    """源是否匹配一条配置项"""
+    pattern = pattern.strip().rstrip("/")
+    if pattern == "*":
+        return True
+    
+    origin = origin.strip().rstrip("/").lower()
+    if "*" not in pattern:
+        return origin == pattern.lower()
+    
+    return re.fullmatch(wildcard_regex(pattern), origin) is not None
+
+
+def is_same_origin(origin: str, host: Optional[str]) -> bool:
+    """源的主机和端口与请求的Host头一致"""
+    if not host:
+        return False
+    return urlsplit(origin).netloc.lower() == host.strip().lower()
+
+
+def is_origin_allowed(origin: Optional[str], host: Optional[str] = None) -> bool:
+    """请求的源是否允许访问"""
+    if not origin:
+        return True
+    
+    if is_same_origin(origin, host):
+        return True
+    
+    security = get_config().security
+    if not security.CORS_ENABLED:
+        return False
+    
+    return any(origin_matches(origin, pattern) for pattern in security.ALLOWED_ORIGINS)
+
+
+def setup_cors(app: FastAPI):
+    """按配置添加CORS中间件，关闭时只允许同源访问"""
+    security = get_config().security
+    if not security.CORS_ENABLED:
+        logger.info("CORS已关闭，只允许同源访问")
+        return
+    
+    exact = [origin.rstrip("/") for origin in security.ALLOWED_ORIGINS if "*" not in origin or origin == "*"]
+    wildcard = [origin.rstrip("/") for origin in security.ALLOWED_ORIGINS if "*" in origin and origin != "*"]
+    allow_all = "*" in exact
+    
+    if allow_all:
+        logger.warning("CORS允许任意源访问，启用认证后请改为具体的源")
+    
+    app.add_middleware(
+        CORSMiddleware,
+        allow_origins=exact,
+        allow_origin_regex="|".join(wildcard_regex(origin) for origin in wildcard) or None,
+        # 携带凭据时浏览器不接受通配的源
+        allow_credentials=security.ALLOW_CREDENTIALS and not allow_all,
+        allow_methods=security.ALLOWED_METHODS,
+        allow_headers=security.ALLOWED_HEADERS,
+        max_age=security.CORS_MAX_AGE,
+    )
+
+
+async def check_websocket_origin(websocket: WebSocket) -> bool:
+    """在accept之前调用，源不允许时关闭连接（客户端收到403）并返回False"""
+    origin = websocket.headers.get("origin")
+    if is_origin_allowed(origin, websocket.headers.get("host")):
+        return True
+    
+    logger.warning(f"拒绝来自 {origin} 的WebSocket连接: {websocket.url.path}")
+    await websocket.close(code=WS_POLICY_VIOLATION)
+    return False
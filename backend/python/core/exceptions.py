@@ -70,6 +70,12 @@ class ErrorCode(str, Enum):
     TASK_TIMEOUT_ERROR = "TASK_TIMEOUT_ERROR"
     TASK_CANCELLED_ERROR = "TASK_CANCELLED_ERROR"
     
+    # 隐私模式
+    PRIVACY_MODE_ACTIVE = "PRIVACY_MODE_ACTIVE"
+    
+    # 协议版本
+    PROTOCOL_VERSION_ERROR = "PROTOCOL_VERSION_ERROR"
+    
     # 配置错误
     CONFIG_ERROR = "CONFIG_ERROR"
     CONFIG_VALIDATION_ERROR = "CONFIG_VALIDATION_ERROR"
@@ -214,6 +220,30 @@ class RateLimitException(APIException):
         )
 
 
+class PrivacyModeException(APIException):
+    """隐私模式下拒绝访问摄像头/麦克风"""
+    
+    def __init__(self, message: str = "Privacy mode is active", device: str = ""):
+        super().__init__(
+            message=message,
+            status_code=status.HTTP_423_LOCKED,
+            error_code=ErrorCode.PRIVACY_MODE_ACTIVE,
+            details={"device": device} if device else {}
+        )
+
+
+class ProtocolVersionException(APIException):
+    """客户端协议版本不兼容"""
+    
+    def __init__(self, message: str, client_version: str = "", server_version: str = ""):
+        super().__init__(
+            message=message,
+            status_code=status.HTTP_400_BAD_REQUEST,
+            error_code=ErrorCode.PROTOCOL_VERSION_ERROR,
+            details={"client_version": client_version, "server_version": server_version}
+        )
+
+
 # 数据库相关异常
 class DatabaseException(BaseReachyException):
     """数据库异常基类"""
@@ -0,0 +1,84 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+Reachy Mini 机器人身份
+
+同一网络中有多台机器人时，工具靠身份区分消息来源：
+- UUID 在配置中指定，未指定时首次启动生成并保存在数据目录，之后保持不变
+- 名称和分组供人阅读和按组筛选，名称未配置时使用首次设置中填写的名称
+- 遥测消息、设置热点名称和MQTT主题前缀都带上身份
+"""
+
+import json
+import uuid
+from dataclasses import asdict, dataclass
+from functools import lru_cache
+from pathlib import Path
+from typing import Any, Dict
+
+from core.config import get_config
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+# 数据目录中保存自动生成的UUID的文件
+UUID_FILE = "robot_uuid"
+
+
+@dataclass(frozen=True)
+class RobotIdentity:
+    """机器人身份"""
+    uuid: str
+    name: str
+    group: str
+    
+    @property
+    def short_id(self) -> str:
+        """UUID的前4位，用于热点名称等长度有限的地方"""
+        return self.uuid[:4]
+    
+    @property
+    def mqtt_prefix(self) -> str:
+        """MQTT主题前缀，按分组订阅时可用 reachy/<分组>/#"""
+        return f"reachy/{self.group}/{self.uuid}"
+    
+    def to_dict(self) -> Dict[str, Any]:
+        return asdict(self)
+
+
+def _load_or_create_uuid(data_dir: str) -> str:
+    """读取数据目录中保存的UUID，不存在或无效时生成新的并保存"""
+    path = Path(data_dir) / UUID_FILE
+    try:
+        return str(uuid.UUID(path.read_text(encoding="utf-8").strip()))
+    except (OSError, ValueError):
+        pass
+    
+    robot_uuid = str(uuid.uuid4())
+    try:
+        path.parent.mkdir(parents=True, exist_ok=True)
+        path.write_text(robot_uuid + "\n", encoding="utf-8")
+        logger.info(f"生成机器人UUID {robot_uuid}，保存在 {path}")
+    except OSError as e:
+        # 保存失败时本次运行仍使用生成的UUID，下次启动会变化
+        logger.warning(f"保存机器人UUID失败: {e}")
+    return robot_uuid
+
+
+def _setup_robot_name(config) -> str:
+    """首次设置中填写的机器人名称，未完成设置时使用默认名称"""
+    try:
+        with open(Path(config.DATA_DIR) / config.setup.STATE_FILE, encoding="utf-8") as f:
+            return json.load(f).get("robot_name") or config.setup.DEFAULT_ROBOT_NAME
+    except (OSError, ValueError, AttributeError):
+        return config.setup.DEFAULT_ROBOT_NAME
+
+
+@lru_cache()
+def get_identity() -> RobotIdentity:
+    """获取机器人身份（单例，首次设置修改名称后清除缓存）"""
+    config = get_config()
+    settings = config.identity
+    robot_uuid = settings.UUID or _load_or_create_uuid(config.DATA_DIR)
+    name = settings.NAME or _setup_robot_name(config)
+    return RobotIdentity(uuid=robot_uuid, name=name, group=settings.GROUP)
@@ -9,6 +9,10 @@ Reachy Mini 数据库模型
 - 任务管理
 - 传感器数据
 - 系统日志
+- 交互分析
+- 对话记忆
+- Webhook
+- 事件日志
 """
 
 import uuid
@@ -48,6 +52,13 @@ class UserRole(str, Enum):
     VIEWER = "viewer"
 
 
+class OperatingMode(str, Enum):
+    """运行模式"""
+    BOOT = "boot"      # 启动中，尚未确定模式
+    SETUP = "setup"    # 未完成首次设置，开启热点和设置页面
+    NORMAL = "normal"  # 正常运行
+
+
 class TaskStatus(str, Enum):
     """任务状态"""
     PENDING = "pending"
@@ -386,6 +397,134 @@ class PerformanceMetric(Base, UUIDMixin, TimestampMixin):
         return f"<PerformanceMetric(name='{self.metric_name}', value={self.metric_value})>"
 
 
+# 交互分析模型
+class InteractionSession(Base, UUIDMixin, TimestampMixin):
+    """交互会话模型（一次服务运行期间的统计）"""
+    __tablename__ = "interaction_sessions"
+    
+    started_at = Column(DateTime, default=func.now(), nullable=False)
+    ended_at = Column(DateTime, nullable=True)
+    
+    # 统计计数
+    faces_seen = Column(Integer, default=0, nullable=False)
+    greetings_performed = Column(Integer, default=0, nullable=False)
+    commands_executed = Column(Integer, default=0, nullable=False)
+    run_time_seconds = Column(Float, default=0.0, nullable=False)
+    
+    # 关系
+    events = relationship("InteractionEvent", back_populates="session", cascade="all, delete-orphan")
+    
+    # 索引
+    __table_args__ = (
+        Index('idx_interaction_session_started', 'started_at'),
+    )
+    
+    def __repr__(self):
+        return f"<InteractionSession(started_at='{self.started_at}', faces={self.faces_seen})>"
+
+
+class InteractionEvent(Base, UUIDMixin, TimestampMixin):
+    """交互事件模型"""
+    __tablename__ = "interaction_events"
+    
+    session_id = Column(String(36), ForeignKey("interaction_sessions.id"), nullable=False)
+    event_type = Column(String(50), nullable=False)
+    
+    # 事件详情（隐私模式下不包含可识别数据）
+    details = Column(JSON, nullable=True)
+    
+    # 关系
+    session = relationship("InteractionSession", back_populates="events")
+    
+    # 索引
+    __table_args__ = (
+        Index('idx_interaction_event_session_created', 'session_id', 'created_at'),
+        Index('idx_interaction_event_type', 'event_type'),
+    )
+    
+    def __repr__(self):
+        return f"<InteractionEvent(type='{self.event_type}', session_id='{self.session_id}')>"
+
+
+# 对话记忆模型
+class ConversationMemory(Base, UUIDMixin, TimestampMixin):
+    """对话记忆模型（按人脸识别身份区分的对话摘要和偏好）"""
+    __tablename__ = "conversation_memories"
+    
+    identity = Column(String(100), unique=True, nullable=False)
+    
+    # 用户偏好
+    display_name = Column(String(100), nullable=True)
+    preferred_language = Column(String(20), nullable=True)
+    preferences = Column(JSON, nullable=True)
+    
+    # 对话摘要列表，每项包含text、language、timestamp，按时间先后排列
+    summaries = Column(JSON, nullable=True)
+    
+    interaction_count = Column(Integer, default=0, nullable=False)
+    last_seen_at = Column(DateTime, default=func.now(), nullable=False)
+    
+    # 索引
+    __table_args__ = (
+        Index('idx_conversation_memory_last_seen', 'last_seen_at'),
+    )
+    
+    def __repr__(self):
+        return f"<ConversationMemory(identity='{self.identity}', summaries={len(self.summaries or [])})>"
+
+
+# Webhook模型
+class Webhook(Base, UUIDMixin, TimestampMixin):
+    """Webhook模型（事件发生时POST到外部地址）"""
+    __tablename__ = "webhooks"
+    
+    url = Column(String(500), nullable=False)
+    description = Column(String(200), nullable=True)
+    
+    # 订阅的事件类型列表，"*"表示全部事件
+    event_types = Column(JSON, nullable=False)
+    
+    # HMAC-SHA256签名密钥，只在创建时返回一次
+    secret = Column(String(128), nullable=False)
+    enabled = Column(Boolean, default=True, nullable=False)
+    
+    # 最近一次投递结果
+    last_delivery_at = Column(DateTime, nullable=True)
+    last_status = Column(String(200), nullable=True)
+    failure_count = Column(Integer, default=0, nullable=False)
+    
+    def __repr__(self):
+        return f"<Webhook(url='{self.url}', events={self.event_types})>"
+
+
+# 事件日志模型
+class JournalEntry(Base, UUIDMixin, TimestampMixin):
+    """事件日志模型（模式切换、错误、高置信度检测等重要事件，供活动时间线查询）"""
+    __tablename__ = "event_journal"
+    
+    occurred_at = Column(DateTime, default=func.now(), nullable=False)
+    event_type = Column(String(50), nullable=False)
+    
+    # 时间线分类：mode、error、detection、event
+    category = Column(String(20), nullable=False)
+    
+    # 检测事件为置信度，其他事件为空
+    significance = Column(Float, nullable=True)
+    message = Column(String(500), nullable=True)
+    
+    # 事件详情（不包含可识别个人的数据）
+    details = Column(JSON, nullable=True)
+    
+    # 索引
+    __table_args__ = (
+        Index('idx_event_journal_occurred', 'occurred_at'),
+        Index('idx_event_journal_type_occurred', 'event_type', 'occurred_at'),
+    )
+    
+    def __repr__(self):
+        return f"<JournalEntry(type='{self.event_type}', occurred_at='{self.occurred_at}')>"
+
+
 # 数据库工具函数
 def create_all_tables(engine):
     """创建所有表"""
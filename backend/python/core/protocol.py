@@ -0,0 +1,187 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+Reachy Mini 通信协议版本
+
+与Rust端 protocol 模块保持一致：
+- 协议版本使用语义化版本号（主.次.修订）
+- 主版本号不同即不兼容，次版本号取双方较小者
+- WebSocket连接先交换 hello 消息，HTTP请求通过请求头声明版本
+- 消息统一用 type 字段标记类型，未知类型忽略而不是报错
+- 协商出 topics 能力后按主题订阅推送数据，每个主题可以单独设置推送频率
+- hello 应答带机器人身份，主题消息带机器人UUID，多台机器人时客户端据此区分来源
+- 协商出 teleop 能力后客户端可以发送遥操作消息直接控制速度（见 services/teleop_service.py）
+"""
+
+import time
+from dataclasses import dataclass, field
+from typing import Any, Dict, List, Optional, Tuple
+
+from core.exceptions import ProtocolVersionException
+
+# 当前协议版本和仍然支持的最低版本（与Rust端 PROTOCOL_VERSION 同步修改）
+PROTOCOL_VERSION = "1.0.0"
+MIN_PROTOCOL_VERSION = "1.0.0"
+
+# HTTP请求和响应中携带协议版本的请求头
+PROTOCOL_HEADER = "X-Reachy-Protocol"
+
+# 本端支持的可选能力
+CAPABILITIES = ["events", "time_sync", "trajectory", "topics", "teleop"]
+
+# 推送主题及未指定频率时的默认推送频率（Hz），None表示每条消息都推送
+TOPICS: Dict[str, Optional[float]] = {
+    "joints": 30.0,
+    "imu": 30.0,
+    "detections": 10.0,
+    "logs": None,
+    "events": None,
+}
+
+# 主题推送频率上限（Hz）
+MAX_TOPIC_RATE_HZ = 100.0
+
+
+def parse_version(version: str) -> Tuple[int, int, int]:
+    """解析"主.次.修订"格式的版本号"""
+    parts = str(version).strip().split(".")
+    if len(parts) != 3 or not all(part.isdigit() for part in parts):
+        raise ProtocolVersionException(f"协议版本格式错误: '{version}'，应为 主.次.修订", str(version), PROTOCOL_VERSION)
+    return int(parts[0]), int(parts[1]), int(parts[2])
+
+
+def format_version(version: Tuple[int, int, int]) -> str:
+    return ".".join(str(part) for part in version)
+
+
+def is_compatible(client_version: str) -> bool:
+    """客户端版本能否与本端通信"""
+    client = parse_version(client_version)
+    server = parse_version(PROTOCOL_VERSION)
+    return client[0] == server[0] and client >= parse_version(MIN_PROTOCOL_VERSION)
+
+
+def negotiate(client_version: str, capabilities: Optional[List[str]] = None) -> Dict[str, Any]:
+    """按客户端声明的版本协商，返回握手应答（不含type字段）"""
+    if not is_compatible(client_version):
+        major = parse_version(PROTOCOL_VERSION)[0]
+        raise ProtocolVersionException(
+            f"协议版本 {client_version} 不兼容，支持 {MIN_PROTOCOL_VERSION} 到 {major}.x",
+            client_version,
+            PROTOCOL_VERSION
+        )
+    
+    negotiated = min(parse_version(client_version), parse_version(PROTOCOL_VERSION))
+    return {
+        "protocol_version": format_version(negotiated),
+        "server_protocol_version": PROTOCOL_VERSION,
+        "capabilities": [c for c in (capabilities or []) if c in CAPABILITIES],
+    }
+
+
+class TopicSubscriptions:
+    """一条连接订阅的主题，按各主题的频率丢弃过密的消息"""
+    
+    def __init__(self):
+        # 主题 -> [最小推送间隔（秒，None为不限频）, 上次推送时刻]
+        self._topics: Dict[str, List[Optional[float]]] = {}
+    
+    @property
+    def topics(self) -> Dict[str, Optional[float]]:
+        """已订阅的主题及推送频率"""
+        return {topic: (1.0 / interval if interval else None) for topic, (interval, _) in self._topics.items()}
+    
+    def subscribe(self, topic: str, rate_hz: Optional[float] = None) -> Optional[float]:
+        """订阅主题或修改频率，返回实际使用的频率"""
+        if topic not in TOPICS:
+            raise ValueError(f"未知的主题: {topic}")
+        
+        rate = TOPICS[topic] if rate_hz is None else rate_hz
+        if rate is not None:
+            if not isinstance(rate, (int, float)) or not 0 < rate <= MAX_TOPIC_RATE_HZ:
+                raise ValueError(f"推送频率必须在0到{MAX_TOPIC_RATE_HZ:g}Hz之间")
+            rate = float(rate)
+        
+        last_sent = self._topics.get(topic, [None, None])[1]
+        self._topics[topic] = [1.0 / rate if rate else None, last_sent]
+        return rate
+    
+    def unsubscribe(self, topic: str) -> bool:
+        """取消订阅，没有订阅时返回False"""
+        return self._topics.pop(topic, None) is not None
+    
+    def is_subscribed(self, topic: str) -> bool:
+        return topic in self._topics
+    
+    def should_send(self, topic: str, now: Optional[float] = None) -> bool:
+        """判断now时刻（time.monotonic）是否推送该主题的一条消息，推送时记录时刻"""
+        subscription = self._topics.get(topic)
+        if subscription is None:
+            return False
+        
+        now = time.monotonic() if now is None else now
+        interval, last_sent = subscription
+        if interval is not None and last_sent is not None and now - last_sent < interval:
+            return False
+        subscription[1] = now
+        return True
+
+
+@dataclass
+class ProtocolSession:
+    """一条WebSocket连接的协议状态：握手之前只接受 hello 消息"""
+    
+    version: Optional[str] = None
+    capabilities: List[str] = field(default_factory=list)
+    subscriptions: TopicSubscriptions = field(default_factory=TopicSubscriptions)
+    # 机器人身份（uuid、name、group），为空时消息中不带身份
+    robot: Optional[Dict[str, Any]] = None
+    
+    @property
+    def negotiated(self) -> bool:
+        return self.version is not None
+    
+    def supports(self, capability: str) -> bool:
+        return capability in self.capabilities
+    
+    def handshake(self, message: Dict[str, Any]) -> Dict[str, Any]:
+        """处理 hello 消息，返回要发送给客户端的应答"""
+        client_version = message.get("protocol_version")
+        if not client_version:
+            raise ProtocolVersionException("hello 消息缺少 protocol_version 字段")
+        
+        reply = negotiate(client_version, message.get("capabilities"))
+        self.version = reply["protocol_version"]
+        self.capabilities = reply["capabilities"]
+        if self.robot:
+            reply["robot"] = self.robot
+        return {"type": "hello", **reply}
+    
+    def handle_subscription(self, message: Dict[str, Any]) -> Optional[Dict[str, Any]]:
+        """处理 subscribe / unsubscribe 消息，返回应答；其他消息返回None"""
+        message_type = message.get("type")
+        if message_type not in ("subscribe", "unsubscribe"):
+            return None
+        
+        if not self.supports("topics"):
+            return {"type": "error", "message": "没有协商topics能力，不能订阅主题"}
+        
+        topic = message.get("topic")
+        if message_type == "unsubscribe":
+            self.subscriptions.unsubscribe(topic)
+            return {"type": "unsubscribed", "topic": topic}
+        
+        try:
+            rate_hz = self.subscriptions.subscribe(topic, message.get("rate_hz"))
+        except ValueError as e:
+            return {"type": "error", "message": str(e)}
+        return {"type": "subscribed", "topic": topic, "rate_hz": rate_hz}
+    
+    def publish(self, topic: str, data: Any, now: Optional[float] = None) -> Optional[Dict[str, Any]]:
+        """按订阅和频率决定是否推送一条主题数据，需要推送时返回消息"""
+        if not self.subscriptions.should_send(topic, now):
+            return None
+        message = {"type": "topic", "topic": topic, "timestamp": int(time.time() * 1000), "data": data}
+        if self.robot:
+            message["robot"] = self.robot["uuid"]
+        return message
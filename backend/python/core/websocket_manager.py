@@ -11,6 +11,7 @@ from typing import Dict, List, Set, Any
 from fastapi import WebSocket, WebSocketDisconnect
 from datetime import datetime
 
+from core.cors import WS_POLICY_VIOLATION, check_websocket_origin
 from utils.logger import setup_logger
 
 logger = setup_logger(__name__)
@@ -38,7 +39,10 @@ class WebSocketManager:
         self._start_heartbeat()
     
     async def connect(self, websocket: WebSocket, connection_type: str = "control"):
-        """建立WebSocket连接"""
+        """建立WebSocket连接，源不允许时关闭连接并抛出WebSocketDisconnect"""
+        if not await check_websocket_origin(websocket):
+            raise WebSocketDisconnect(code=WS_POLICY_VIOLATION)
+        
         try:
             await websocket.accept()
             
@@ -22,7 +22,6 @@ from pathlib import Path
 from typing import AsyncGenerator, Dict, Any
 
 from fastapi import FastAPI, HTTPException, WebSocket, WebSocketDisconnect
-from fastapi.middleware.cors import CORSMiddleware
 from fastapi.middleware.gzip import GZipMiddleware
 from fastapi.staticfiles import StaticFiles
 from fastapi.responses import FileResponse
@@ -32,14 +31,25 @@ import uvicorn
 # 导入核心模块
 from core.config import get_config, validate_config
 from core.database import get_database_manager
-from core.exceptions import register_exception_handlers, BaseReachyException
+from core.cors import setup_cors, check_websocket_origin
+from core.correlation import REQUEST_ID_HEADER, correlation_id, is_valid_id, new_id
+from core.exceptions import register_exception_handlers, BaseReachyException, ProtocolVersionException
+from core.identity import get_identity
+from core.models import UserRole
+from core.protocol import (
+    PROTOCOL_VERSION, MIN_PROTOCOL_VERSION, PROTOCOL_HEADER, CAPABILITIES,
+    ProtocolSession, is_compatible,
+)
 from service_manager import get_service_manager, setup_signal_handlers
+from services.observer_service import check_observer_websocket, observer_service, setup_observer_guard
+from services.teleop_service import teleop_service
+from services.topic_service import topic_service
 from rust_bindings import is_rust_available, get_rust_system_info
 
 # 设置日志
 logging.basicConfig(
     level=logging.INFO,
-    format='%(asctime)s - %(name)s - %(levelname)s - %(message)s',
+    format='%(asctime)s - %(correlation_id)s - %(name)s - %(levelname)s - %(message)s',
     handlers=[
         logging.StreamHandler(sys.stdout),
         logging.FileHandler('logs/reachy_mini.log', encoding='utf-8')
@@ -117,24 +127,60 @@ def setup_middleware(app: FastAPI):
     配置应用程序的中间件栈，包括：
     - CORS: 处理跨域请求，允许前端访问API
     - GZip: 压缩响应数据，减少网络传输量
+    - 观察者: 带观察者令牌的请求只能读取遥测、日志和摄像头
+    - 关联ID: 每个请求带一个关联ID，写入该请求的所有日志并通过响应头返回
     
     Args:
         app: FastAPI应用实例
     """
     # CORS中间件 - 处理跨域资源共享
-    # 允许前端应用从不同端口访问后端API
-    app.add_middleware(
-        CORSMiddleware,
-        allow_origins=["*"],  # 在生产环境中应该限制具体域名
-        allow_credentials=True,  # 允许携带认证信息
-        allow_methods=["*"],     # 允许所有HTTP方法
-        allow_headers=["*"],     # 允许所有请求头
-    )
+    # 只允许security.ALLOWED_ORIGINS中配置的源从其他端口或域名访问后端API
+    setup_cors(app)
     
     # Gzip压缩中间件 - 自动压缩响应数据
     # 当响应大小超过1000字节时启用压缩
     app.add_middleware(GZipMiddleware, minimum_size=1000)
     
+    # 观察者中间件 - 远程协助者的只读会话，不能发送命令或修改设置
+    setup_observer_guard(app)
+    
+    # 协议版本中间件 - 客户端通过请求头声明协议版本
+    # 主版本不兼容时直接拒绝，所有响应都带上服务端协议版本
+    @app.middleware("http")
+    async def protocol_version_middleware(request, call_next):
+        client_version = request.headers.get(PROTOCOL_HEADER)
+        if client_version:
+            try:
+                compatible = is_compatible(client_version)
+            except ProtocolVersionException as e:
+                return JSONResponse(status_code=e.status_code, content=e.to_dict(),
+                                    headers={PROTOCOL_HEADER: PROTOCOL_VERSION})
+            if not compatible:
+                e = ProtocolVersionException(f"协议版本 {client_version} 不兼容",
+                                             client_version, PROTOCOL_VERSION)
+                return JSONResponse(status_code=e.status_code, content=e.to_dict(),
+                                    headers={PROTOCOL_HEADER: PROTOCOL_VERSION})
+        
+        response = await call_next(request)
+        response.headers[PROTOCOL_HEADER] = PROTOCOL_VERSION
+        return response
+    
+    # 关联ID中间件 - 最后添加，位于最外层，其他中间件的日志也带上关联ID
+    # 客户端通过请求头指定的ID格式合法时沿用，否则生成新的ULID
+    @app.middleware("http")
+    async def correlation_id_middleware(request, call_next):
+        request_id = request.headers.get(REQUEST_ID_HEADER)
+        if not request_id or not is_valid_id(request_id):
+            request_id = new_id()
+        
+        token = correlation_id.set(request_id)
+        try:
+            response = await call_next(request)
+        finally:
+            correlation_id.reset(token)
+        response.headers[REQUEST_ID_HEADER] = request_id
+        return response
+    
     logger.info("✅ 中间件设置完成")
 
 
@@ -161,10 +207,25 @@ def setup_routes(app: FastAPI):
             "message": "Reachy Mini Control System",
             "version": "1.0.0",
             "status": "running",
+            "robot": get_identity().to_dict(),
             "docs": "/docs" if config.debug else "disabled",
             "health": "/health"
         }
     
+    @app.get("/api/protocol")
+    async def protocol_info():
+        """协议版本API - 客户端连接前查询支持的协议版本范围和能力
+        
+        Returns:
+            dict: 当前协议版本、最低支持版本和可选能力
+        """
+        return {
+            "protocol_version": PROTOCOL_VERSION,
+            "min_protocol_version": MIN_PROTOCOL_VERSION,
+            "capabilities": CAPABILITIES,
+            "header": PROTOCOL_HEADER,
+        }
+    
     @app.get("/api/health")
     async def health_check():
         """健康检查API - 返回系统健康状态
@@ -194,6 +255,7 @@ def setup_routes(app: FastAPI):
         
         收集并返回系统的详细信息，包括：
         - 服务管理器状态
+        - 机器人身份
         - 系统配置参数
         - Python运行环境信息
         - Rust模块可用性和信息
@@ -204,6 +266,7 @@ def setup_routes(app: FastAPI):
         service_manager = get_service_manager()
         info = {
             "service_manager": service_manager.get_status(),
+            "robot": get_identity().to_dict(),
             "config": {
                 "api_host": config.api_host,
                 "api_port": config.api_port,
@@ -247,6 +310,8 @@ def setup_routes(app: FastAPI):
     @app.websocket("/ws")
     async def websocket_endpoint(websocket: WebSocket):
         """通用WebSocket端点"""
+        if not await check_websocket_origin(websocket):
+            return
         await websocket.accept()
         logger.info("WebSocket连接建立")
         
@@ -268,32 +333,86 @@ def setup_routes(app: FastAPI):
     @app.websocket("/ws/control")
     async def websocket_control_endpoint(websocket: WebSocket):
         """机器人控制WebSocket端点"""
+        if not await check_websocket_origin(websocket):
+            return
+        allowed, observer = await check_observer_websocket(websocket)
+        if not allowed:
+            return
         await websocket.accept()
-        logger.info("控制WebSocket连接建立")
+        # 连接时确定角色：观察者只读，其余控制连接为操作员
+        role = UserRole.VIEWER if observer else UserRole.OPERATOR
+        logger.info(f"控制WebSocket连接建立，角色: {role.value}" + (f"（观察者: {observer.name}）" if observer else ""))
+        session = ProtocolSession(robot=get_identity().to_dict())
+        if observer:
+            observer_service.attach(observer, websocket)
         
         try:
             while True:
-                data = await websocket.receive_text()
-                logger.info(f"收到控制命令: {data}")
+                data = await websocket.receive_json()
+                message_type = data.get("type") if isinstance(data, dict) else None
                 
-                # 这里可以添加实际的机器人控制逻辑
-                response = {
-                    "type": "control_response",
-                    "data": f"已处理控制命令: {data}",
-                    "timestamp": asyncio.get_event_loop().time()
-                }
+                # 连接后第一条消息必须是hello，协商协议版本
+                if message_type == "hello":
+                    try:
+                        await websocket.send_json(session.handshake(data))
+                        topic_service.attach(websocket, session)
+                        logger.info(f"控制连接协商协议版本 {session.version}，客户端: {data.get('client', '')}")
+                    except ProtocolVersionException as e:
+                        await websocket.send_json({"type": "error", **e.to_dict()})
+                        await websocket.close(code=1002)
+                        return
+                    continue
                 
-                await websocket.send_json(response)
+                if not session.negotiated:
+                    await websocket.send_json({"type": "error", "message": "请先发送hello消息协商协议版本"})
+                    continue
                 
+                # 主题订阅：只推送客户端正在显示的数据
+                reply = session.handle_subscription(data)
+                if reply is not None:
+                    await websocket.send_json(reply)
+                elif message_type == "ping":
+                    await websocket.send_json({"type": "pong", "timestamp": data.get("timestamp")})
+                elif message_type == "teleop":
+                    if role == UserRole.VIEWER:
+                        reply = {"type": "error", "message": "观察者会话只读，不能遥操作"}
+                    elif not session.supports("teleop"):
+                        reply = {"type": "error", "message": "没有协商teleop能力，不能遥操作"}
+                    else:
+                        reply = await teleop_service.handle(websocket, data, role)
+                    if reply is not None:
+                        await websocket.send_json(reply)
+                elif message_type == "command" and observer:
+                    await websocket.send_json({"type": "error", "message": "观察者会话只读，不能发送控制命令"})
+                elif message_type == "command":
+                    logger.info(f"收到控制命令: {data.get('command')}")
+                    
+                    # 这里可以添加实际的机器人控制逻辑
+                    await websocket.send_json({
+                        "type": "control_response",
+                        "data": data.get("command"),
+                        "timestamp": asyncio.get_event_loop().time()
+                    })
+                else:
+                    # 更高版本客户端的新消息类型，忽略而不是断开
+                    logger.debug(f"忽略未知的控制消息类型: {message_type}")
+                    
         except WebSocketDisconnect:
             logger.info("控制WebSocket连接断开")
         except Exception as e:
             logger.error(f"控制WebSocket错误: {e}")
             await websocket.close()
+        finally:
+            topic_service.detach(websocket)
+            await teleop_service.release(websocket)
+            if observer:
+                observer_service.detach(observer, websocket)
     
     @app.websocket("/ws/stream")
     async def websocket_stream_endpoint(websocket: WebSocket):
         """视频流WebSocket端点"""
+        if not await check_websocket_origin(websocket):
+            return
         await websocket.accept()
         logger.info("流媒体WebSocket连接建立")
         
@@ -560,11 +679,11 @@ def main():
             print("  REACHY_MINI_CONFIG      # 配置文件路径")
             print("  REACHY_MINI_LOG_LEVEL   # 日志级别 (DEBUG/INFO/WARNING/ERROR)")
             sys.exit(0)
-        
+            
         elif command in ["--version", "-v"]:
             print("Reachy Mini Python后端 v1.0.0")
             sys.exit(0)
-        
+            
         else:
             print(f"❌ 未知命令: {command}")
             print("使用 --help 查看帮助信息")
@@ -6,6 +6,7 @@ Rust绑定模块
 提供Python与Rust后端模块的接口，包括视觉处理、实时控制、硬件管理和AI推理功能。
 """
 
+import asyncio
 import json
 import logging
 import threading
@@ -14,6 +15,8 @@ from typing import Optional, Dict, Any, List, Union
 from dataclasses import dataclass, asdict
 from pathlib import Path
 
+from core.correlation import get_correlation_id
+
 try:
     import reachy_mini_rust
     RUST_AVAILABLE = True
@@ -38,6 +41,7 @@ class VisionConfig:
     enable_feature_detection: bool = False
     face_cascade_path: str = "data/haarcascade_frontalface_alt.xml"
     processing_threads: int = 2
+    playback: Optional[Dict[str, Any]] = None
 
 
 @dataclass
@@ -54,7 +58,7 @@ class RealtimeConfig:
     command_timeout_ms: int = 1000
     pid_gains: Dict[str, Dict[str, float]] = None
     joint_limits: Dict[str, Dict[str, float]] = None
-
+    
     def __post_init__(self):
         if self.pid_gains is None:
             self.pid_gains = {
@@ -93,7 +97,7 @@ class HardwareConfig:
     sensor_update_rate: float = 100.0
     enable_watchdog: bool = True
     watchdog_timeout: float = 5.0
-
+    
     def __post_init__(self):
         if self.gpio_pins is None:
             self.gpio_pins = {
@@ -217,9 +221,9 @@ class RustRealtimeController:
             raise
     
     def send_joint_command(self, joint_name: str, command: Dict[str, Any]) -> None:
-        """发送关节命令"""
+        """发送关节命令，如 {"command_type": "Position", "target_position": 0.3}，发送前检查字段组合"""
         try:
-            command_json = json.dumps(command)
+            command_json = reachy_mini_rust.build_motion_command(json.dumps({"joint_name": joint_name, **command}))
             self._controller.send_joint_command(joint_name, command_json)
         except Exception as e:
             logger.error(f"发送关节命令失败: {e}")
@@ -255,6 +259,16 @@ class RustRealtimeController:
             raise
 
 
+def hardware_config_json(config: HardwareConfig) -> str:
+    """只传递与Rust端HardwareConfig对应的字段，其余使用Rust端默认值"""
+    return json.dumps({
+        "serial_port": config.serial_port,
+        "baud_rate": config.serial_baudrate,
+        "i2c_bus": config.i2c_bus,
+        "gpio_pins": config.gpio_pins,
+    })
+
+
 class RustHardwareManager:
     """Rust硬件管理器包装类"""
     
@@ -263,8 +277,7 @@ class RustHardwareManager:
             raise RuntimeError("Rust模块不可用")
         
         self.config = config
-        config_json = json.dumps(asdict(config))
-        self._manager = reachy_mini_rust.PyHardwareManager(config_json)
+        self._manager = reachy_mini_rust.PyHardwareInterface(hardware_config_json(config))
         self._running = False
         
         logger.info("Rust硬件管理器初始化完成")
@@ -292,14 +305,34 @@ class RustHardwareManager:
     def get_sensor_data(self, sensor_name: str) -> Optional[Dict[str, Any]]:
         """获取传感器数据"""
         try:
-            data_json = self._manager.get_sensor_data(sensor_name)
-            if data_json:
-                return json.loads(data_json)
-            return None
+            sensor_status = self.get_status().get("sensor_status", {})
+            return sensor_status.get(sensor_name)
         except Exception as e:
             logger.error(f"获取传感器数据失败: {e}")
             return None
     
+    # 底层访问：绕过控制层直接操作硬件，用于装配调试，舵机限位仍然生效
+    
+    def send_command(self, command: Dict[str, Any], correlation_id: Optional[str] = None) -> str:
+        """发送硬件命令，如 {"type": "servo_move", "id": 1, "position": 100, "speed": None}
+        
+        未指定关联ID时使用当前请求的关联ID，都没有时由Rust侧生成，返回实际使用的ID
+        """
+        return self._manager.send_command(json.dumps(command), correlation_id or get_correlation_id())
+    
+    def get_servo_status(self, servo_id: int) -> Optional[Dict[str, Any]]:
+        """获取舵机状态（舵机原始单位）"""
+        status_json = self._manager.get_servo_status(servo_id)
+        return json.loads(status_json) if status_json else None
+    
+    def set_led(self, pin: int, state: bool) -> None:
+        """设置已配置的GPIO引脚"""
+        self._manager.set_led(pin, state)
+    
+    def scan_bus(self, first: int = 0, last: int = 252) -> List[Dict[str, Any]]:
+        """向每个ID发送PING，扫描总线上应答的舵机"""
+        return json.loads(self._manager.scan_bus(first, last))
+    
     def get_status(self) -> Dict[str, Any]:
         """获取状态"""
         try:
@@ -310,6 +343,23 @@ class RustHardwareManager:
             return {}
 
 
+class RustCommandGate:
+    """命令准入包装类：Python端直接执行的运动命令先经过Rust端的角色检查、仲裁和审计"""
+    
+    def __init__(self, audit_directory: str):
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        
+        self._gate = reachy_mini_rust.PyCommandGate(json.dumps({"audit": {"directory": audit_directory}}))
+    
+    def submit(self, origin: Dict[str, Any], payload: Dict[str, Any], emergency: bool = False) -> None:
+        """提交一条命令，origin如 {"source": "Teleop", "client_id": "...", "role": "operator"}
+        
+        角色没有控制权限或被仲裁拒绝时抛出PermissionError，拒绝同样写入审计日志
+        """
+        self._gate.submit(json.dumps(origin), json.dumps(payload), emergency)
+
+
 class RustAIEngine:
     """Rust AI引擎包装类"""
     
@@ -345,9 +395,9 @@ class RustAIEngine:
             raise
     
     def inference(self, request: Dict[str, Any]) -> Dict[str, Any]:
-        """执行推理"""
+        """执行推理，发送前补全请求ID、时间戳和选项并检查输入形状"""
         try:
-            request_json = json.dumps(request)
+            request_json = reachy_mini_rust.build_inference_request(json.dumps(request))
             response_json = self._engine.inference(request_json)
             return json.loads(response_json)
         except Exception as e:
@@ -388,7 +438,10 @@ class RustReachyMiniSystem:
         }
         
         config_json = json.dumps(config)
-        self._system = reachy_mini_rust.PyReachyMiniSystem("reachy_mini", "1.0.0")
+        # 挂接硬件接口：系统启动时启动，停止时先停靠
+        self._system = reachy_mini_rust.PyReachyMiniSystem(
+            "reachy_mini", "1.0.0", hardware_config_json(hardware_config)
+        )
         self._running = False
         
         logger.info("Rust完整系统初始化完成")
@@ -431,6 +484,69 @@ class RustReachyMiniSystem:
             return False
 
 
+class ReachyMini(RustReachyMiniSystem):
+    """带上下文管理的系统入口
+    
+    进入时启动系统和硬件接口，退出时无论是否有异常传出都会停止系统：
+    舵机先回到停靠姿态并卸掉扭矩，再停止硬件接口。停靠失败时抛出RuntimeError，
+    已有异常传出时只记录日志::
+        
+        async with ReachyMini() as bot:
+            print(bot.get_status())
+    
+    同步代码可以使用 ``with ReachyMini() as bot:``。
+    """
+    
+    def __init__(self,
+                 vision_config: Optional[VisionConfig] = None,
+                 realtime_config: Optional[RealtimeConfig] = None,
+                 hardware_config: Optional[HardwareConfig] = None,
+                 ai_config: Optional[AIConfig] = None):
+        super().__init__(
+            vision_config or VisionConfig(),
+            realtime_config or RealtimeConfig(),
+            hardware_config or HardwareConfig(),
+            ai_config or AIConfig(),
+        )
+    
+    def _stop_on_exit(self, exc_value: Optional[BaseException]) -> None:
+        """退出时停止系统；已有异常传出时停止失败只记录日志，不覆盖原异常"""
+        try:
+            self.stop()
+        except Exception as e:
+            if exc_value is None:
+                raise
+            logger.error(f"退出时停止系统失败: {e}")
+    
+    def __enter__(self) -> "ReachyMini":
+        try:
+            self.start()
+        except BaseException as e:
+            # 启动到一半失败时释放已经启动的部分
+            self._stop_on_exit(e)
+            raise
+        return self
+    
+    def __exit__(self, exc_type, exc_value, traceback) -> bool:
+        self._stop_on_exit(exc_value)
+        return False
+    
+    async def __aenter__(self) -> "ReachyMini":
+        loop = asyncio.get_running_loop()
+        try:
+            await loop.run_in_executor(None, self.start)
+        except BaseException as e:
+            await asyncio.shield(loop.run_in_executor(None, self._stop_on_exit, e))
+            raise
+        return self
+    
+    async def __aexit__(self, exc_type, exc_value, traceback) -> bool:
+        # 启停在线程池中执行，不阻塞事件循环；任务被取消时停止仍会执行完
+        loop = asyncio.get_running_loop()
+        await asyncio.shield(loop.run_in_executor(None, self._stop_on_exit, exc_value))
+        return False
+
+
 class RustBindingsManager:
     """Rust绑定管理器"""
     
@@ -440,6 +556,7 @@ class RustBindingsManager:
         self._hardware_manager: Optional[RustHardwareManager] = None
         self._ai_engine: Optional[RustAIEngine] = None
         self._system: Optional[RustReachyMiniSystem] = None
+        self._command_gate: Optional[RustCommandGate] = None
         self._lock = threading.Lock()
         
         # 初始化Rust日志系统
@@ -490,6 +607,13 @@ class RustBindingsManager:
             self._ai_engine = RustAIEngine(config)
             return self._ai_engine
     
+    def get_command_gate(self, audit_directory: str) -> RustCommandGate:
+        """命令准入，第一次调用时创建"""
+        with self._lock:
+            if self._command_gate is None:
+                self._command_gate = RustCommandGate(audit_directory)
+            return self._command_gate
+    
     def create_system(self, 
                      vision_config: VisionConfig,
                      realtime_config: RealtimeConfig,
@@ -544,8 +668,19 @@ class RustBindingsManager:
                     logger.error(f"停止AI引擎失败: {e}")
                 self._ai_engine = None
             
+            self._command_gate = None
             logger.info("Rust绑定资源清理完成")
     
+    def get_realtime_status(self) -> Optional[Dict[str, Any]]:
+        """已创建的实时控制器的状态，没有控制器时返回None"""
+        controller = self._realtime_controller
+        return controller.get_status() if controller is not None else None
+    
+    def get_vision_status(self) -> Optional[Dict[str, Any]]:
+        """已创建的视觉处理器的状态，没有处理器时返回None"""
+        processor = self._vision_processor
+        return processor.get_status() if processor is not None else None
+    
     def get_system_info(self) -> Dict[str, Any]:
         """获取系统信息"""
         if not RUST_AVAILABLE:
@@ -578,6 +713,167 @@ class RustBindingsManager:
         except Exception as e:
             logger.error(f"配置验证失败: {e}")
             return False
+    
+    def dry_run_config(self, proposed: Dict[str, Any], active: Optional[Dict[str, Any]] = None) -> Dict[str, Any]:
+        """试运行新配置：校验并返回与当前配置的差异和需要重启的子系统，不修改配置
+        
+        Raises:
+            RuntimeError: Rust模块不可用
+            ValueError: 配置不是完整的Config
+        """
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        
+        active_json = json.dumps(active) if active is not None else None
+        return json.loads(reachy_mini_rust.dry_run_config(json.dumps(proposed), active_json))
+    
+    def build_motion_command(self, command: Dict[str, Any]) -> Dict[str, Any]:
+        """构建并验证运动命令，补全时间戳；字段组合无效时抛出ValueError"""
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return json.loads(reachy_mini_rust.build_motion_command(json.dumps(command)))
+    
+    def build_inference_request(self, request: Dict[str, Any]) -> Dict[str, Any]:
+        """构建并验证推理请求，补全请求ID、时间戳和选项；输入形状无效时抛出ValueError"""
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return json.loads(reachy_mini_rust.build_inference_request(json.dumps(request)))
+    
+    def inspect_model(self, path: str, input_shape: Optional[List[int]] = None,
+                      output_names: Optional[List[str]] = None) -> Dict[str, Any]:
+        """读取ONNX模型实际的输入输出，给出input_shape时同时列出与之及output_names的差异"""
+        if not RUST_AVAILABLE or not hasattr(reachy_mini_rust, "inspect_model"):
+            raise RuntimeError("Rust模块未启用ONNX模型读取（vision特性）")
+        return json.loads(reachy_mini_rust.inspect_model(path, input_shape, output_names))
+    
+    def solve_gaze(self, x: float, y: float, z: float) -> Dict[str, Any]:
+        """求解看向一点的关节目标；不可达时结果中给出最接近的可达方向和挡住目标的关节限位"""
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return json.loads(reachy_mini_rust.solve_gaze(x, y, z))
+    
+    def gaze_workspace(self, radius: float = 1.0, samples: int = 16) -> Dict[str, Any]:
+        """可达注视范围及其包络边界采样点，用于可视化"""
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return json.loads(reachy_mini_rust.gaze_workspace(radius, samples))
+    
+    def export_robot_model(self, format: str = "urdf",
+                           dynamics: Optional[Dict[str, Dict[str, float]]] = None) -> str:
+        """导出Gazebo/MuJoCo可加载的URDF或SDF模型，dynamics为各关节辨识得到的惯量和摩擦参数
+        
+        Raises:
+            RuntimeError: Rust模块不可用
+            ValueError: 格式不支持
+        """
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        dynamics_json = json.dumps(dynamics) if dynamics is not None else None
+        return reachy_mini_rust.export_robot_model(format, dynamics_json)
+    
+    def sample_trajectory(self, trajectory: Dict[str, Any], sample_rate: float = 50.0) -> Dict[str, Any]:
+        """按采样率展开轨迹，返回关节曲线和相机、视线落点的笛卡尔路径
+        
+        Raises:
+            RuntimeError: Rust模块不可用
+            ValueError: 轨迹格式错误或采样点过多
+        """
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return json.loads(reachy_mini_rust.sample_trajectory(json.dumps(trajectory), sample_rate))
+    
+    def install_behavior_pack(self, path: str) -> Dict[str, Any]:
+        """安装zip格式的行为包（动画、声音、灯效和行为树），新安装的包默认禁用
+        
+        Raises:
+            RuntimeError: Rust模块不可用
+            ValueError: 包内容未通过检查
+        """
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return json.loads(reachy_mini_rust.install_behavior_pack(path))
+    
+    def inspect_behavior_pack(self, path: str) -> Dict[str, Any]:
+        """检查行为包但不安装，返回包信息和包内容实际需要的权限
+        
+        Raises:
+            RuntimeError: Rust模块不可用
+            ValueError: 包内容未通过检查
+        """
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return json.loads(reachy_mini_rust.inspect_behavior_pack(path))
+    
+    def list_behavior_packs(self) -> List[Dict[str, Any]]:
+        """已安装的行为包"""
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return json.loads(reachy_mini_rust.list_behavior_packs())
+    
+    def set_behavior_pack_enabled(self, pack_id: str, enabled: bool) -> Dict[str, Any]:
+        """启用或禁用行为包"""
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return json.loads(reachy_mini_rust.set_behavior_pack_enabled(pack_id, enabled))
+    
+    def uninstall_behavior_pack(self, pack_id: str):
+        """卸载行为包"""
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        reachy_mini_rust.uninstall_behavior_pack(pack_id)
+    
+    def plot_trajectory_png(self, trajectory: Dict[str, Any], sample_rate: float = 50.0, width: int = 1200,
+                            height: int = 800, font_path: Optional[str] = None) -> bytes:
+        """把轨迹渲染为PNG，Rust模块需启用plot特性构建"""
+        if not RUST_AVAILABLE or not hasattr(reachy_mini_rust, "plot_trajectory_png"):
+            raise RuntimeError("Rust模块未启用轨迹绘图（plot特性）")
+        return reachy_mini_rust.plot_trajectory_png(json.dumps(trajectory), sample_rate, width, height, font_path)
+    
+    def capture_cpu_profile(self, seconds: int = 10) -> Dict[str, Any]:
+        """用pprof采集本进程的CPU调用栈（Python和Rust两边），阻塞seconds秒
+        
+        Raises:
+            RuntimeError: Rust模块不可用、未启用profiling特性或已有一次采样正在进行
+            PermissionError: Rust端配置未启用performance.profiling_enabled
+            ValueError: 采样时长超出范围
+            OSError: 采样失败
+        """
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return json.loads(reachy_mini_rust.capture_cpu_profile(seconds))
+    
+    def get_log_filter(self) -> str:
+        """当前生效的Rust日志过滤规则，写法与RUST_LOG相同"""
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return reachy_mini_rust.get_log_filter()
+    
+    def set_log_filter(self, spec: str) -> str:
+        """整体替换Rust日志过滤规则，返回替换后的规则"""
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        reachy_mini_rust.set_log_filter(spec)
+        logger.info(f"Rust日志过滤规则已改为: {spec}")
+        return reachy_mini_rust.get_log_filter()
+    
+    def set_log_level(self, level: str, module: Optional[str] = None) -> str:
+        """修改Rust全局或某个模块的日志级别，返回修改后的过滤规则
+        
+        Raises:
+            RuntimeError: Rust模块不可用
+            ValueError: 日志级别无效
+        """
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        reachy_mini_rust.set_log_level(level, module)
+        logger.info(f"Rust日志级别已修改: {module or '全局'} = {level}")
+        return reachy_mini_rust.get_log_filter()
+    
+    def clear_log_level(self, module: str) -> bool:
+        """移除Rust模块的单独日志级别，恢复使用上级模块或全局级别"""
+        if not RUST_AVAILABLE:
+            raise RuntimeError("Rust模块不可用")
+        return reachy_mini_rust.clear_log_level(module)
 
 
 # 全局实例
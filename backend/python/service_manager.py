@@ -17,14 +17,37 @@ from typing import Dict, Any, Optional, List
 from pathlib import Path
 
 from fastapi import FastAPI
-from fastapi.middleware.cors import CORSMiddleware
 from fastapi.middleware.gzip import GZipMiddleware
 from fastapi.staticfiles import StaticFiles
 import uvicorn
 
 from core.config import get_config
+from core.cors import setup_cors, check_websocket_origin
 from core.database import get_database_manager, get_migration_manager
 from core.exceptions import register_exception_handlers
+from core.identity import get_identity
+from services.analytics_service import analytics_service
+from services.journal_service import journal_service
+from services.privacy_service import privacy_service
+from services.robot_service import robot_service
+from services.retention_service import retention_service
+from services.relay_service import relay_service
+from services.sync_service import sync_service
+from services.choreography_service import choreography_service
+from services.observer_service import observer_service, setup_observer_guard
+from services.fleet_service import fleet_service
+from services.network_service import network_service
+from services.mode_service import mode_service
+from services.language_service import language_service
+from services.gaze_target_service import gaze_target_service
+from services.presence_service import presence_service
+from services.systemd_service import systemd_notifier
+from services.topic_service import topic_service
+from services.metrics_history_service import metrics_history_service
+from services.alert_service import alert_service
+from services.baseline_service import baseline_service
+from services.ai_service import ai_service
+from services.webhook_service import webhook_service
 from rust_bindings import (
     get_rust_bindings_manager, cleanup_rust_bindings,
     VisionConfig, RealtimeConfig, HardwareConfig, AIConfig,
@@ -62,9 +85,28 @@ class ServiceManager:
         self._shutdown_event = asyncio.Event()  # 关闭事件信号
         self._components_status = {
             "database": False,      # 数据库连接状态
+            "analytics": False,     # 交互分析服务状态
+            "journal": False,       # 事件日志状态
+            "privacy": False,       # 隐私模式服务状态
+            "retention": False,     # 数据保留服务状态
+            "robot": False,         # 机器人控制服务状态
+            "relay": False,         # 远程中继状态
+            "sync": False,          # 主从同步状态
+            "fleet": False,         # 机群心跳上报状态
+            "network": False,       # Wi-Fi网络管理状态
+            "mode": False,          # 运行模式状态机状态
+            "language": False,      # 多语言语音服务状态
+            "gaze_targets": False,  # 命名注视目标状态
+            "presence": False,      # 人员热力图状态
             "rust_bindings": False, # Rust模块绑定状态
             "api_server": False,    # API服务器状态
             "websocket": False,     # WebSocket服务状态
+            "topics": False,        # 主题推送状态
+            "metrics_history": False, # 状态历史采样状态
+            "alert": False,         # 告警状态
+            "baseline": False,      # 性能基线状态
+            "webhook": False,       # Webhook投递状态
+            "observer": False,      # 观察者会话状态
             "scheduler": False,     # 任务调度器状态
         }
         
@@ -89,11 +131,29 @@ class ServiceManager:
             Exception: 当任何组件初始化失败时抛出异常
         """
         logger.info("开始初始化服务组件...")
+        self._shutdown_event.clear()
+        systemd_notifier.initialize()
+        systemd_notifier.status("正在初始化服务组件")
         
         try:
             # 初始化数据库 - 必须首先建立数据连接
             await self._initialize_database()
             
+            # 初始化交互分析 - 依赖数据库保存统计
+            await self._initialize_analytics()
+            
+            # 启动事件日志 - 依赖数据库，需要在运行模式确定之前开始记录
+            await self._initialize_journal()
+            
+            # 恢复隐私模式 - 必须在任何采集管线启动之前
+            await self._initialize_privacy()
+            
+            # 初始化数据保留 - 定期清理过期数据
+            await self._initialize_retention()
+            
+            # 初始化机器人控制服务 - 连接机器人并开始更新状态
+            await self._initialize_robot()
+            
             # 初始化Rust绑定 - 加载高性能计算模块
             await self._initialize_rust_bindings()
             
@@ -103,11 +163,61 @@ class ServiceManager:
             # 初始化WebSocket - 建立实时通信能力
             await self._initialize_websocket()
             
+            # 启动主题推送 - 控制连接按主题订阅实时数据
+            await self._initialize_topics()
+            
+            # 启动状态历史采样 - 依赖机器人服务和Rust绑定
+            await self._initialize_metrics_history()
+            
+            # 加载告警规则 - 指标规则随状态历史采样检查
+            await self._initialize_alert()
+            
+            # 读取性能基线 - 随状态历史采样检查性能退化
+            await self._initialize_baseline()
+            
+            # 加载登记的Webhook - 依赖数据库
+            await self._initialize_webhook()
+            
+            # 启动观察者会话到期检查
+            await self._initialize_observer()
+            
             # 初始化任务调度器 - 启动后台任务管理
             await self._initialize_scheduler()
             
+            # 启动远程中继 - 默认关闭，需要API服务就绪
+            await self._initialize_relay()
+            
+            # 跟随主机器人 - 默认关闭，依赖机器人服务
+            await self._initialize_sync()
+            
+            # 启动机群心跳上报 - 默认关闭
+            await self._initialize_fleet()
+            
+            # 启动Wi-Fi网络管理 - 默认关闭，需要nmcli或wpa_cli
+            await self._initialize_network()
+            
+            # 确定运行模式 - 未完成首次设置时开启设置热点
+            await self._initialize_mode()
+            
+            # 读取上次选择的语言
+            await self._initialize_language()
+            
+            # 读取保存的命名注视目标
+            await self._initialize_gaze_targets()
+            
+            # 读取人员热力图
+            await self._initialize_presence()
+            
             logger.info("所有服务组件初始化完成")
             
+            # 启动自检通过后才向systemd报告就绪，未就绪时由TimeoutStartSec处理
+            failures = self._self_test()
+            if failures:
+                logger.error(f"启动自检失败: {', '.join(failures)}")
+                systemd_notifier.status(f"启动自检失败: {', '.join(failures)}")
+            else:
+                systemd_notifier.ready(f"运行中（{mode_service.mode.value}模式）")
+            
         except Exception as e:
             logger.error(f"服务组件初始化失败: {e}")
             # 发生错误时自动清理已初始化的组件
@@ -150,6 +260,84 @@ class ServiceManager:
             logger.error(f"数据库初始化失败: {e}")
             raise
     
+    async def _initialize_analytics(self) -> None:
+        """初始化交互分析服务"""
+        try:
+            logger.info("初始化交互分析服务...")
+            
+            if not await analytics_service.initialize():
+                logger.warning("交互分析服务初始化失败，统计数据将不会保存")
+                return
+            
+            self._components_status["analytics"] = True
+            logger.info("交互分析服务初始化完成")
+            
+        except Exception as e:
+            logger.error(f"交互分析服务初始化失败: {e}")
+            raise
+    
+    async def _initialize_journal(self) -> None:
+        """初始化事件日志"""
+        try:
+            if not await journal_service.start():
+                return
+            
+            mode_service.add_listener(journal_service.on_mode_changed)
+            self._components_status["journal"] = True
+            logger.info("事件日志初始化完成")
+            
+        except Exception as e:
+            # 事件日志不影响其他功能
+            logger.warning(f"事件日志初始化失败: {e}")
+    
+    async def _initialize_privacy(self) -> None:
+        """初始化隐私模式服务"""
+        try:
+            logger.info("初始化隐私模式服务...")
+            
+            if not await privacy_service.initialize():
+                logger.warning("隐私模式状态恢复失败，状态不会在重启后保留")
+                return
+            
+            self._components_status["privacy"] = True
+            logger.info("隐私模式服务初始化完成")
+            
+        except Exception as e:
+            logger.error(f"隐私模式服务初始化失败: {e}")
+            raise
+    
+    async def _initialize_retention(self) -> None:
+        """初始化数据保留服务"""
+        try:
+            logger.info("初始化数据保留服务...")
+            
+            if not await retention_service.initialize():
+                logger.warning("数据保留服务初始化失败，过期数据不会自动清理")
+                return
+            
+            self._components_status["retention"] = True
+            logger.info("数据保留服务初始化完成")
+            
+        except Exception as e:
+            logger.error(f"数据保留服务初始化失败: {e}")
+            raise
+    
+    async def _initialize_robot(self) -> None:
+        """初始化机器人控制服务"""
+        try:
+            logger.info("初始化机器人控制服务...")
+            
+            if not await robot_service.initialize():
+                logger.warning("机器人控制服务初始化失败，运动控制不可用")
+                return
+            
+            self._components_status["robot"] = True
+            logger.info("机器人控制服务初始化完成")
+            
+        except Exception as e:
+            logger.error(f"机器人控制服务初始化失败: {e}")
+            raise
+    
     async def _initialize_rust_bindings(self) -> None:
         """初始化Rust绑定"""
         try:
@@ -186,6 +374,7 @@ class ServiceManager:
                 'enable_feature_detection': self.config.vision.ENABLE_FEATURE_DETECTION,
                 'face_cascade_path': self.config.vision.FACE_CASCADE_PATH,
                 'processing_threads': self.config.vision.PROCESSING_THREADS,
+                'playback': {'path': self.config.vision.PLAYBACK_PATH} if self.config.vision.PLAYBACK_PATH else None,
             }
             
             realtime_dict = {
@@ -257,7 +446,7 @@ class ServiceManager:
                 yield
                 # 关闭时的操作
                 logger.info("FastAPI应用关闭")
-                await self._shutdown_event.set()
+                self._shutdown_event.set()
             
             self.app = FastAPI(
                 title="Reachy Mini API",
@@ -267,16 +456,13 @@ class ServiceManager:
             )
             
             # 添加中间件
-            self.app.add_middleware(
-                CORSMiddleware,
-                allow_origins=["*"],
-                allow_credentials=True,
-                allow_methods=["*"],
-                allow_headers=["*"],
-            )
+            setup_cors(self.app)
             
             self.app.add_middleware(GZipMiddleware, minimum_size=1000)
             
+            # 观察者会话只读
+            setup_observer_guard(self.app)
+            
             # 注册异常处理器
             register_exception_handlers(self.app)
             
@@ -318,6 +504,7 @@ class ServiceManager:
                         "running": self._running,
                         "components": self._components_status
                     },
+                    "robot": get_identity().to_dict(),
                     "config": {
                         "api_host": self.config.api_host,
                         "api_port": self.config.api_port,
@@ -332,13 +519,123 @@ class ServiceManager:
                 
                 return info
             
+            # 机器人控制路由
+            from api.robot import router as robot_router
+            self.app.include_router(robot_router)
+            
+            # 配置试运行路由
+            from api.config import router as config_router
+            self.app.include_router(config_router)
+            
+            # 运动学路由
+            from api.kinematics import router as kinematics_router
+            self.app.include_router(kinematics_router)
+            
+            # 轨迹可视化路由
+            from api.trajectory import router as trajectory_router
+            self.app.include_router(trajectory_router)
+            
+            # 仿真模型路由
+            from api.sim import router as sim_router
+            self.app.include_router(sim_router)
+            
+            # 日志级别路由
+            from api.logs import router as logs_router
+            self.app.include_router(logs_router)
+            
+            # 交互分析路由
+            from api.analytics import router as analytics_router
+            self.app.include_router(analytics_router)
+            
+            # 隐私模式路由
+            from api.privacy import router as privacy_router
+            self.app.include_router(privacy_router)
+            
+            # 命令审计路由
+            from api.audit import router as audit_router
+            self.app.include_router(audit_router)
+            
+            # 数据管理路由
+            from api.data import router as data_router
+            self.app.include_router(data_router)
+            
+            # 模型和动画上传路由
+            from api.assets import router as assets_router
+            self.app.include_router(assets_router)
+            
+            # 行为包路由
+            from api.behaviors import router as behaviors_router
+            self.app.include_router(behaviors_router)
+            
+            # 行为包市场路由
+            from api.marketplace import router as marketplace_router
+            self.app.include_router(marketplace_router)
+            
+            # 远程中继路由
+            from api.relay import router as relay_router
+            self.app.include_router(relay_router)
+            
+            # 主从同步路由
+            from api.sync import router as sync_router
+            self.app.include_router(sync_router)
+            
+            # 编舞路由
+            from api.choreography import router as choreography_router
+            self.app.include_router(choreography_router)
+            
+            # 观察者会话路由
+            from api.observer import router as observer_router
+            self.app.include_router(observer_router)
+            
+            # 机群心跳路由
+            from api.fleet import router as fleet_router
+            self.app.include_router(fleet_router)
+            
+            # Wi-Fi网络管理路由
+            from api.network import router as network_router
+            self.app.include_router(network_router)
+            
+            # 首次设置路由
+            from api.setup import router as setup_router
+            self.app.include_router(setup_router)
+            
+            # 多语言语音路由
+            from api.language import router as language_router
+            self.app.include_router(language_router)
+            
+            # 人员热力图路由
+            from api.presence import router as presence_router
+            self.app.include_router(presence_router)
+            
+            # 对话记忆路由
+            from api.memory import router as memory_router
+            self.app.include_router(memory_router)
+            
+            # 状态历史路由
+            from api.metrics import router as metrics_router
+            self.app.include_router(metrics_router)
+            
+            # 告警路由
+            from api.alerts import router as alerts_router
+            self.app.include_router(alerts_router)
+            
+            # Webhook路由
+            from api.webhooks import router as webhooks_router
+            self.app.include_router(webhooks_router)
+            
+            # 事件日志路由
+            from api.journal import router as journal_router
+            self.app.include_router(journal_router)
+            
             # 系统状态路由
             @self.app.get("/system/status")
             async def system_status():
                 return {
                     "running": self._running,
+                    "privacy_mode": privacy_service.get_status(),
                     "components": self._components_status,
-                    "uptime": time.time() - getattr(self, '_start_time', time.time())
+                    "uptime": time.time() - getattr(self, '_start_time', time.time()),
+                    "log_filter": get_rust_bindings_manager().get_log_filter() if is_rust_available() else None
                 }
             
             logger.info("API路由注册完成")
@@ -394,6 +691,8 @@ class ServiceManager:
             # WebSocket路由
             @self.app.websocket("/ws")
             async def websocket_endpoint(websocket: WebSocket):
+                if not await check_websocket_origin(websocket):
+                    return
                 await self.websocket_manager.connect(websocket)
                 try:
                     while True:
@@ -412,14 +711,209 @@ class ServiceManager:
             logger.error(f"WebSocket初始化失败: {e}")
             raise
     
+    async def _initialize_topics(self) -> None:
+        """初始化主题推送"""
+        logger.info("初始化主题推送...")
+        
+        async def joint_state():
+            state = await robot_service.get_robot_state()
+            return {"head": state.head_position, "body": state.body_position}
+        
+        topic_service.register_source("joints", joint_state)
+        await topic_service.start()
+        self._components_status["topics"] = True
+        logger.info("主题推送初始化完成")
+    
+    async def _initialize_metrics_history(self) -> None:
+        """初始化状态历史采样"""
+        logger.info("初始化状态历史...")
+        
+        def realtime(key):
+            status = get_rust_bindings_manager().get_realtime_status() if is_rust_available() else None
+            return status.get(key) if status else None
+        
+        def servo_temperature():
+            joints = realtime("joint_states") or {}
+            temperatures = [joint["temperature"] for joint in joints.values() if joint.get("temperature") is not None]
+            return max(temperatures) if temperatures else None
+        
+        def vision(key):
+            status = get_rust_bindings_manager().get_vision_status() if is_rust_available() else None
+            return status.get(key) if status else None
+        
+        def loop_jitter():
+            """控制循环实际频率偏离目标频率的百分比"""
+            frequency, target = realtime("control_loop_frequency"), realtime("target_control_frequency")
+            return abs(frequency - target) / target * 100 if frequency is not None and target else None
+        
+        def inference_p95():
+            stats = ai_service.inference_stats
+            return stats["p95_time"] * 1000 if stats["total_inferences"] else None
+        
+        def robot_state(key):
+            state = robot_service.robot_state
+            return getattr(state, key) if state.connected else None
+        
+        metrics_history_service.register_metric("loop_frequency", "Hz", lambda: realtime("control_loop_frequency"))
+        metrics_history_service.register_metric("servo_temperature_max", "°C", servo_temperature)
+        metrics_history_service.register_metric("robot_temperature", "°C", lambda: robot_state("temperature"))
+        metrics_history_service.register_metric("battery_level", "%", lambda: robot_state("battery_level"))
+        metrics_history_service.register_metric("camera_fps", "fps", lambda: vision("current_fps"))
+        metrics_history_service.register_metric("camera_connected", "", lambda: vision("camera_connected"))
+        metrics_history_service.register_metric("loop_jitter", "%", loop_jitter)
+        metrics_history_service.register_metric("inference_p95", "ms", inference_p95)
+        
+        if await metrics_history_service.start():
+            self._components_status["metrics_history"] = True
+            logger.info("状态历史初始化完成")
+    
+    async def _initialize_alert(self) -> None:
+        """初始化告警"""
+        logger.info("初始化告警...")
+        
+        # 规则文件有误时不影响其他服务启动
+        if await alert_service.start():
+            self._components_status["alert"] = True
+            logger.info("告警初始化完成")
+        else:
+            logger.warning(f"告警未启动: {alert_service.last_error or '未启用'}")
+    
+    async def _initialize_baseline(self) -> None:
+        """初始化性能基线"""
+        if baseline_service.start():
+            self._components_status["baseline"] = True
+            logger.info(f"性能基线初始化完成，已有 {len(baseline_service.baselines)} 个指标的基线")
+    
+    async def _initialize_webhook(self) -> None:
+        """初始化Webhook投递"""
+        logger.info("初始化Webhook...")
+        
+        if await webhook_service.start():
+            self._components_status["webhook"] = True
+            logger.info("Webhook初始化完成")
+        else:
+            logger.warning("Webhook未启动，事件不会投递到外部地址")
+    
+    async def _initialize_observer(self) -> None:
+        """初始化观察者会话"""
+        if not self.config.observer.ENABLED:
+            logger.info("观察者会话未启用")
+            return
+        
+        await observer_service.start()
+        self._components_status["observer"] = True
+        logger.info("观察者会话初始化完成")
+    
+    async def _initialize_relay(self) -> None:
+        """初始化远程中继"""
+        if not self.config.relay.ENABLED:
+            logger.info("远程中继未启用")
+            return
+        
+        logger.info("初始化远程中继...")
+        
+        # 中继连接失败不影响本地服务
+        if await relay_service.start():
+            self._components_status["relay"] = True
+            logger.info("远程中继初始化完成")
+        else:
+            logger.warning(f"远程中继启动失败: {relay_service.last_error}")
+    
+    async def _initialize_sync(self) -> None:
+        """初始化主从同步"""
+        if not self.config.sync.ENABLED:
+            logger.info("主从同步未启用")
+            return
+        
+        logger.info("初始化主从同步...")
+        
+        # 主机器人暂时不可达时在后台重连
+        if await sync_service.follow(self.config.sync.LEADER_URL):
+            self._components_status["sync"] = True
+            logger.info("主从同步初始化完成")
+        else:
+            logger.warning(f"主从同步启动失败: {sync_service.last_error}")
+    
+    async def _initialize_fleet(self) -> None:
+        """初始化机群心跳上报"""
+        if not self.config.fleet.ENABLED:
+            logger.info("机群心跳上报未启用")
+            return
+        
+        logger.info("初始化机群心跳上报...")
+        
+        # 上报失败只会排队重试，不影响本地服务
+        if await fleet_service.start(status_provider=self.get_status):
+            self._components_status["fleet"] = True
+            logger.info("机群心跳上报初始化完成")
+        else:
+            logger.warning(f"机群心跳上报启动失败: {fleet_service.last_error}")
+    
+    async def _initialize_network(self) -> None:
+        """初始化Wi-Fi网络管理"""
+        if not self.config.network.ENABLED:
+            logger.info("Wi-Fi网络管理未启用")
+            return
+        
+        logger.info("初始化Wi-Fi网络管理...")
+        
+        # 网络后端不可用时仍可通过有线网络访问本地服务
+        if await network_service.start():
+            self._components_status["network"] = True
+            logger.info("Wi-Fi网络管理初始化完成")
+        else:
+            logger.warning(f"Wi-Fi网络管理启动失败: {network_service.last_error}")
+    
+    async def _initialize_mode(self) -> None:
+        """初始化运行模式"""
+        logger.info("确定运行模式...")
+        
+        # 设置热点开启失败时仍可通过有线网络访问设置页面
+        if await mode_service.start():
+            self._components_status["mode"] = True
+            logger.info(f"运行模式: {mode_service.mode.value}")
+        else:
+            logger.warning(f"确定运行模式失败: {mode_service.last_error}")
+    
+    async def _initialize_language(self) -> None:
+        """初始化多语言语音服务"""
+        language_service.load()
+        self._components_status["language"] = True
+        logger.info(f"当前语言: {language_service.current}（自动检测: {language_service.auto_detect}）")
+    
+    async def _initialize_gaze_targets(self) -> None:
+        """初始化命名注视目标"""
+        gaze_target_service.load()
+        self._components_status["gaze_targets"] = True
+        logger.info(f"已加载 {len(gaze_target_service.targets)} 个命名注视目标")
+    
+    async def _initialize_presence(self) -> None:
+        """初始化人员热力图"""
+        presence_service.load()
+        self._components_status["presence"] = True
+        logger.info(f"人员热力图: {len(presence_service.cells)} 个格子，已观察 {sum(presence_service.observed)} 个时段")
+    
+    def _self_test(self) -> List[str]:
+        """启动自检，返回未就绪的组件"""
+        required = list(self.config.systemd.REQUIRED_COMPONENTS)
+        if is_rust_available():
+            required.append("rust_bindings")
+        return [name for name in required if not self._components_status.get(name)]
+    
     async def _initialize_scheduler(self) -> None:
         """初始化任务调度器"""
         try:
             logger.info("初始化任务调度器...")
             
+            # 启用systemd看门狗时按看门狗的要求缩短检查间隔
+            interval = self.config.systemd.HEALTH_CHECK_INTERVAL
+            watchdog_interval = systemd_notifier.watchdog_interval()
+            if watchdog_interval is not None:
+                interval = min(interval, watchdog_interval)
+            
             # 这里可以添加定期任务，如系统监控、数据清理等
             async def periodic_health_check():
-                while self._running:
+                while not self._shutdown_event.is_set():
                     try:
                         # 检查各组件健康状态
                         db_manager = get_database_manager()
@@ -429,13 +923,17 @@ class ServiceManager:
                         
                         if not db_healthy:
                             logger.warning(f"数据库健康检查失败: {health_status.get('error', '未知错误')}")
-                        
-                        # 等待下次检查
-                        await asyncio.sleep(30)  # 每30秒检查一次
+                            systemd_notifier.status(f"数据库异常: {health_status.get('error', '未知错误')}")
+                        elif systemd_notifier.ready_sent:
+                            # 只在健康时喂看门狗，持续异常时由systemd重启服务
+                            systemd_notifier.watchdog()
+                            systemd_notifier.status(f"运行中（{mode_service.mode.value}模式）")
                         
                     except Exception as e:
                         logger.error(f"健康检查失败: {e}")
-                        await asyncio.sleep(30)
+                    
+                    # 等待下次检查
+                    await asyncio.sleep(interval)
             
             # 启动健康检查任务
             asyncio.create_task(periodic_health_check())
@@ -519,6 +1017,7 @@ class ServiceManager:
     async def cleanup(self) -> None:
         """清理所有资源"""
         logger.info("清理服务资源...")
+        systemd_notifier.stopping()
         
         try:
             # 清理Rust绑定
@@ -526,6 +1025,84 @@ class ServiceManager:
                 cleanup_rust_bindings()
                 self._components_status["rust_bindings"] = False
             
+            # 写入当前时段的人员热力图
+            if self._components_status.get("presence"):
+                presence_service.flush()
+                self._components_status["presence"] = False
+            
+            # 停止Wi-Fi网络管理
+            if self._components_status.get("network"):
+                await network_service.stop()
+                self._components_status["network"] = False
+            
+            # 停止Webhook投递
+            if self._components_status.get("webhook"):
+                await webhook_service.stop()
+                self._components_status["webhook"] = False
+            
+            # 停止性能基线检查
+            if self._components_status.get("baseline"):
+                baseline_service.stop()
+                self._components_status["baseline"] = False
+            
+            # 停止告警检查，等待已发出的通知
+            if self._components_status.get("alert"):
+                await alert_service.stop()
+                self._components_status["alert"] = False
+            
+            # 停止状态历史采样（结束当前桶并写入磁盘）
+            if self._components_status.get("metrics_history"):
+                await metrics_history_service.stop()
+                self._components_status["metrics_history"] = False
+            
+            # 停止主题推送
+            if self._components_status.get("topics"):
+                await topic_service.stop()
+                self._components_status["topics"] = False
+            
+            # 停止机群心跳上报
+            if self._components_status.get("fleet"):
+                await fleet_service.stop()
+                self._components_status["fleet"] = False
+            
+            # 结束观察者会话
+            if self._components_status.get("observer"):
+                await observer_service.stop()
+                self._components_status["observer"] = False
+            
+            # 停止编舞演出
+            await choreography_service.stop()
+            
+            # 停止跟随主机器人
+            if self._components_status.get("sync") or sync_service.is_running:
+                await sync_service.stop()
+                self._components_status["sync"] = False
+            
+            # 断开远程中继
+            if self._components_status.get("relay"):
+                await relay_service.stop()
+                self._components_status["relay"] = False
+            
+            # 停止状态更新并断开机器人
+            if self._components_status.get("robot"):
+                await robot_service.cleanup()
+                self._components_status["robot"] = False
+            
+            # 停止定期数据清理
+            if self._components_status.get("retention"):
+                await retention_service.cleanup()
+                self._components_status["retention"] = False
+            
+            # 写入剩余的事件日志（需要在数据库关闭前完成）
+            if self._components_status.get("journal"):
+                await journal_service.stop()
+                self._components_status["journal"] = False
+            
+            # 结束交互分析会话（需要在数据库关闭前完成）
+            if self._components_status.get("analytics"):
+                await analytics_service.cleanup()
+                self._components_status["analytics"] = False
+            
             # 清理数据库连接
             if self._components_status.get("database"):
                 db_manager = get_database_manager()
@@ -621,7 +1198,7 @@ async def main() -> None:
         manager = get_service_manager()
         while manager.is_running():
             await asyncio.sleep(1)
-        
+            
     except KeyboardInterrupt:
         logger.info("接收到键盘中断，关闭服务...")
     except Exception as e:
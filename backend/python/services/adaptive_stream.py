@@ -0,0 +1,273 @@
+#!/usr/bin/env python3
+"""
+自适应视频流
+按每个客户端实测的带宽和RTT调整分辨率和JPEG质量，弱Wi-Fi下降低画质而不是让画面卡住
+
+客户端在自适应模式下需要：
+    - 收到 {"type": "video_frame", "seq": n, ...} 后回复 {"type": "frame_ack", "seq": n}
+    - 收到 {"type": "ping", "id": n} 后回复 {"type": "pong", "id": n}
+未确认的帧达到上限时不再发送新帧，只保留最新一帧，所以链路变慢时丢帧而不是积压
+"""
+
+import asyncio
+import base64
+import time
+from typing import Dict, List, Optional, Any, Tuple
+
+import cv2
+import numpy as np
+from fastapi import WebSocket
+
+from core.config import get_config
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 画质档位：(缩放比例, JPEG质量)，从高到低
+QUALITY_LEVELS: List[Tuple[float, int]] = [
+    (1.0, 80),
+    (0.75, 70),
+    (0.5, 60),
+    (0.35, 50),
+    (0.25, 40),
+]
+
+# 估计值的平滑系数
+SMOOTHING = 0.3
+
+# 两次降档之间至少间隔（秒），等新档位的帧大小和确认反映出来
+DOWNGRADE_HOLD = 1.0
+
+
+def encode_frame(frame: np.ndarray, scale: float, quality: int) -> bytes:
+    """缩放并编码为JPEG"""
+    if scale < 1.0:
+        height, width = frame.shape[:2]
+        size = (max(1, int(width * scale)), max(1, int(height * scale)))
+        frame = cv2.resize(frame, size, interpolation=cv2.INTER_AREA)
+    ok, buffer = cv2.imencode('.jpg', frame, [cv2.IMWRITE_JPEG_QUALITY, quality])
+    if not ok:
+        raise ValueError("JPEG编码失败")
+    return buffer.tobytes()
+
+
+def _smooth(current: Optional[float], sample: float) -> float:
+    return sample if current is None else current + SMOOTHING * (sample - current)
+
+
+class LinkEstimator:
+    """根据ping和帧确认估计RTT和带宽
+    
+    一帧从发送到确认的时间约为 RTT + 大小/带宽，未确认帧数受限时排队很少，
+    所以每次确认得到一个带宽样本：大小 / (确认耗时 - RTT)
+    """
+    
+    def __init__(self):
+        self.rtt: Optional[float] = None
+        self.bandwidth: Optional[float] = None  # 字节/秒
+        self.in_flight: Dict[int, Tuple[float, int]] = {}
+        self.pings: Dict[int, float] = {}
+        self.lost = 0
+    
+    def on_ping(self, ping_id: int, now: float):
+        self.pings[ping_id] = now
+    
+    def on_pong(self, ping_id: int, now: float):
+        sent_at = self.pings.pop(ping_id, None)
+        if sent_at is not None:
+            self.rtt = _smooth(self.rtt, now - sent_at)
+    
+    def on_sent(self, seq: int, size: int, now: float):
+        self.in_flight[seq] = (now, size)
+    
+    def on_ack(self, seq: int, now: float):
+        sent = self.in_flight.pop(seq, None)
+        if sent is None:
+            return
+        sent_at, size = sent
+        # 至少按1ms计算，避免RTT估计偏大时得到负值或无穷大
+        transfer = max(now - sent_at - (self.rtt or 0.0), 0.001)
+        self.bandwidth = _smooth(self.bandwidth, size / transfer)
+    
+    def expire(self, now: float, timeout: float) -> int:
+        """超时未确认的帧视为丢失，返回本次丢失的数量"""
+        expired = [seq for seq, (sent_at, _) in self.in_flight.items() if now - sent_at > timeout]
+        for seq in expired:
+            del self.in_flight[seq]
+        self.pings = {ping_id: sent_at for ping_id, sent_at in self.pings.items() if now - sent_at <= timeout}
+        self.lost += len(expired)
+        return len(expired)
+
+
+class QualityController:
+    """选择画质档位：超出带宽立即降档，余量充足并稳定一段时间后才升一档"""
+    
+    def __init__(self, fps: float, utilization: float, max_rtt: float, upgrade_delay: float):
+        self.fps = fps
+        self.utilization = utilization
+        self.max_rtt = max_rtt
+        self.upgrade_delay = upgrade_delay
+        self.level = len(QUALITY_LEVELS) // 2  # 从中间档开始，等待测量
+        self.frame_sizes: Dict[int, float] = {}
+        self.stable_since: Optional[float] = None
+        self.downgraded_at: Optional[float] = None
+    
+    def on_encoded(self, level: int, size: int):
+        self.frame_sizes[level] = _smooth(self.frame_sizes.get(level), size)
+    
+    def _estimated_size(self, level: int) -> Optional[float]:
+        if level in self.frame_sizes:
+            return self.frame_sizes[level]
+        # 没有测量过的档位按像素数从当前档位推算
+        current = self.frame_sizes.get(self.level)
+        if current is None:
+            return None
+        return current * (QUALITY_LEVELS[level][0] / QUALITY_LEVELS[self.level][0]) ** 2
+    
+    def update(self, estimator: LinkEstimator, lost: int, now: float) -> int:
+        """根据最新的估计调整档位，返回当前档位"""
+        budget = None
+        if estimator.bandwidth is not None:
+            budget = estimator.bandwidth * self.utilization / self.fps
+        
+        size = self._estimated_size(self.level)
+        rtt_too_high = estimator.rtt is not None and estimator.rtt > self.max_rtt
+        over_budget = budget is not None and size is not None and size > budget
+        
+        if lost or rtt_too_high or over_budget:
+            self.stable_since = None
+            holding = self.downgraded_at is not None and now - self.downgraded_at < DOWNGRADE_HOLD
+            if self.level < len(QUALITY_LEVELS) - 1 and not holding:
+                self.level += 1
+                self.downgraded_at = now
+            return self.level
+        
+        if self.level == 0 or budget is None:
+            return self.level
+        
+        # 升一档后仍留有余量才升档，避免在两档之间来回切换
+        higher = self._estimated_size(self.level - 1)
+        if higher is None or higher > budget * 0.8:
+            self.stable_since = None
+            return self.level
+        
+        if self.stable_since is None:
+            self.stable_since = now
+        elif now - self.stable_since >= self.upgrade_delay:
+            self.level -= 1
+            self.stable_since = None
+        return self.level
+
+
+class AdaptiveStreamSender:
+    """一个客户端的自适应发送器，由视频流的帧回调提供最新帧"""
+    
+    def __init__(self, websocket: WebSocket):
+        stream = config.stream
+        self.websocket = websocket
+        self.fps = stream.ADAPTIVE_FPS
+        self.estimator = LinkEstimator()
+        self.controller = QualityController(
+            stream.ADAPTIVE_FPS, stream.ADAPTIVE_UTILIZATION, stream.ADAPTIVE_MAX_RTT, stream.ADAPTIVE_UPGRADE_DELAY
+        )
+        self.latest: Optional[Tuple[np.ndarray, Any]] = None
+        self.seq = 0
+        self.ping_id = 0
+        self.task: Optional[asyncio.Task] = None
+    
+    async def on_frame(self, frame: np.ndarray, timestamp):
+        """视频流帧回调，只保留最新一帧"""
+        self.latest = (frame, timestamp)
+    
+    def handle_message(self, message: Dict[str, Any]) -> bool:
+        """处理客户端的确认消息，返回是否已处理"""
+        now = time.monotonic()
+        if message.get("type") == "frame_ack" and isinstance(message.get("seq"), int):
+            self.estimator.on_ack(message["seq"], now)
+            return True
+        if message.get("type") == "pong" and isinstance(message.get("id"), int):
+            self.estimator.on_pong(message["id"], now)
+            return True
+        return False
+    
+    def start(self):
+        if self.task is None or self.task.done():
+            self.task = asyncio.create_task(self._send_loop())
+    
+    async def stop(self):
+        if self.task and not self.task.done():
+            self.task.cancel()
+            try:
+                await self.task
+            except asyncio.CancelledError:
+                pass
+        self.task = None
+    
+    def get_status(self) -> Dict[str, Any]:
+        scale, quality = QUALITY_LEVELS[self.controller.level]
+        estimator = self.estimator
+        return {
+            "level": self.controller.level,
+            "scale": scale,
+            "quality": quality,
+            "bandwidth_kbps": round(estimator.bandwidth * 8 / 1000, 1) if estimator.bandwidth else None,
+            "rtt_ms": round(estimator.rtt * 1000, 1) if estimator.rtt is not None else None,
+            "in_flight": len(estimator.in_flight),
+            "lost_frames": estimator.lost,
+        }
+    
+    async def _send_ping(self, now: float):
+        self.ping_id += 1
+        self.estimator.on_ping(self.ping_id, now)
+        await self.websocket.send_json({"type": "ping", "id": self.ping_id})
+    
+    async def _send_frame(self, now: float):
+        frame, timestamp = self.latest
+        self.latest = None
+        level = self.controller.level
+        scale, quality = QUALITY_LEVELS[level]
+        
+        data = await asyncio.to_thread(encode_frame, frame, scale, quality)
+        self.controller.on_encoded(level, len(data))
+        
+        self.seq += 1
+        self.estimator.on_sent(self.seq, len(data), now)
+        await self.websocket.send_json({
+            "type": "video_frame",
+            "seq": self.seq,
+            "data": f"data:image/jpeg;base64,{base64.b64encode(data).decode('utf-8')}",
+            "timestamp": timestamp.isoformat() if hasattr(timestamp, "isoformat") else timestamp,
+            "adaptive": self.get_status(),
+        })
+    
+    async def _send_loop(self):
+        stream = config.stream
+        frame_interval = 1.0 / self.fps
+        last_ping = 0.0
+        
+        while True:
+            try:
+                now = time.monotonic()
+                lost = self.estimator.expire(now, stream.ADAPTIVE_ACK_TIMEOUT)
+                level = self.controller.level
+                if self.controller.update(self.estimator, lost, now) != level:
+                    logger.debug(f"自适应视频流档位 {level} -> {self.controller.level}: {self.get_status()}")
+                
+                if now - last_ping >= stream.ADAPTIVE_PING_INTERVAL:
+                    await self._send_ping(now)
+                    last_ping = now
+                
+                # 未确认的帧太多时跳过，等待链路排空
+                if self.latest is not None and len(self.estimator.in_flight) < stream.ADAPTIVE_MAX_IN_FLIGHT:
+                    await self._send_frame(now)
+                
+                await asyncio.sleep(frame_interval)
+                
+            except asyncio.CancelledError:
+                raise
+            except Exception as e:
+                logger.warning(f"自适应视频流发送失败: {e}")
+                break
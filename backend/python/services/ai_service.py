@@ -6,6 +6,7 @@ AI推理服务
 
 import asyncio
 import json
+from collections import deque
 import logging
 import numpy as np
 import cv2
@@ -18,6 +19,9 @@ import io
 from PIL import Image
 
 from core.config import get_config
+from services.analytics_service import analytics_service
+from services.presence_service import presence_service
+from services.topic_service import topic_service
 from utils.logger import setup_logger
 
 # 获取配置
@@ -103,8 +107,11 @@ class AIService:
         self.inference_stats = {
             "total_inferences": 0,
             "average_time": 0.0,
-            "last_inference_time": 0.0
+            "last_inference_time": 0.0,
+            "p95_time": 0.0
         }
+        # 最近的推理耗时，用于计算p95
+        self.recent_times = deque(maxlen=200)
     
     async def initialize(self) -> bool:
         """初始化AI服务"""
@@ -182,6 +189,11 @@ class AIService:
             # 更新统计信息
             inference_time = asyncio.get_event_loop().time() - start_time
             await self._update_stats(inference_time)
+            await presence_service.record(detections, processed_image.shape[1::-1])
+            await topic_service.publish("detections", {
+                "kind": "objects",
+                "items": [{"class_name": d.class_name, "confidence": d.confidence, "bbox": list(d.bbox)} for d in detections],
+            })
             
             logger.info(f"目标检测完成，检测到 {len(detections)} 个对象，耗时 {inference_time:.3f}s")
             return detections
@@ -207,6 +219,12 @@ class AIService:
             # 更新统计信息
             inference_time = asyncio.get_event_loop().time() - start_time
             await self._update_stats(inference_time)
+            await analytics_service.record_faces(faces)
+            # 只推送位置和置信度，身份等可识别信息不离开本机
+            await topic_service.publish("detections", {
+                "kind": "faces",
+                "items": [{"confidence": f.confidence, "bbox": list(f.bbox)} for f in faces],
+            })
             
             logger.info(f"人脸检测完成，检测到 {len(faces)} 个人脸，耗时 {inference_time:.3f}s")
             return faces
@@ -451,6 +469,10 @@ class AIService:
         total = self.inference_stats["total_inferences"]
         current_avg = self.inference_stats["average_time"]
         self.inference_stats["average_time"] = (current_avg * (total - 1) + inference_time) / total
+        
+        self.recent_times.append(inference_time)
+        ordered = sorted(self.recent_times)
+        self.inference_stats["p95_time"] = ordered[round((len(ordered) - 1) * 0.95)]
     
     def get_stats(self) -> Dict[str, Any]:
         """获取推理统计信息"""
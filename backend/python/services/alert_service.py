@@ -0,0 +1,366 @@
+#!/usr/bin/env python3
+"""
+告警服务
+按YAML文件中的规则检查指标和事件：指标规则在阈值条件持续for秒后触发、条件消失后恢复，
+事件规则在收到匹配的事件时触发。告警和恢复通知发送到规则指定的渠道（日志、WebSocket
+events主题、Webhook POST、MQTT）。指标取自状态历史的每次采样
+"""
+
+import asyncio
+import json
+import operator
+import time
+from collections import deque
+from dataclasses import dataclass, field
+from pathlib import Path
+from typing import Any, Deque, Dict, List, Optional
+
+import httpx
+import yaml
+
+from core.config import get_config
+from core.identity import get_identity
+from services.metrics_history_service import metrics_history_service
+from services.topic_service import topic_service
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+OPERATORS = {
+    ">": operator.gt,
+    ">=": operator.ge,
+    "<": operator.lt,
+    "<=": operator.le,
+    "==": operator.eq,
+    "!=": operator.ne,
+}
+
+SEVERITIES = ("info", "warning", "critical")
+
+
+@dataclass
+class AlertRule:
+    """一条告警规则，metric和event二选一"""
+    name: str
+    metric: Optional[str] = None
+    op: str = ">"
+    threshold: float = 0.0
+    for_seconds: float = 0.0
+    event: Optional[str] = None
+    match: Dict[str, Any] = field(default_factory=dict)
+    severity: str = "warning"
+    sinks: List[str] = field(default_factory=list)
+    repeat_interval: float = 300.0
+    message: str = ""
+    
+    # 运行状态
+    pending_since: Optional[float] = None
+    firing: bool = False
+    last_fired: Optional[float] = None
+    
+    def describe(self) -> str:
+        if self.message:
+            return self.message
+        if self.metric:
+            return f"{self.metric} {self.op} {self.threshold:g}" + (f" 持续 {self.for_seconds:g} 秒" if self.for_seconds else "")
+        return f"事件 {self.event}"
+    
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "name": self.name,
+            "metric": self.metric,
+            "op": self.op,
+            "threshold": self.threshold,
+            "for": self.for_seconds,
+            "event": self.event,
+            "match": self.match,
+            "severity": self.severity,
+            "sinks": self.sinks,
+            "description": self.describe(),
+            "firing": self.firing,
+            "last_fired": self.last_fired,
+        }
+
+
+class LogSink:
+    """写入日志，级别按严重程度"""
+    
+    async def send(self, alert: Dict[str, Any]):
+        text = f"告警[{alert['status']}] {alert['rule']}: {alert['message']}"
+        if alert["status"] == "resolved" or alert["severity"] == "info":
+            logger.info(text)
+        elif alert["severity"] == "warning":
+            logger.warning(text)
+        else:
+            logger.error(text)
+
+
+class WebSocketSink:
+    """推送到WebSocket的events主题"""
+    
+    async def send(self, alert: Dict[str, Any]):
+        await topic_service.publish("events", {"event_type": "alert", "details": alert})
+
+
+class WebhookSink:
+    """POST JSON到指定地址"""
+    
+    def __init__(self, url: str, headers: Optional[Dict[str, str]] = None):
+        if not url.startswith(("http://", "https://")):
+            raise ValueError(f"Webhook地址必须以http://或https://开头: {url}")
+        self.url = url
+        self.headers = headers or {}
+    
+    async def send(self, alert: Dict[str, Any]):
+        async with httpx.AsyncClient(timeout=config.alert.REQUEST_TIMEOUT) as client:
+            response = await client.post(self.url, json=alert, headers=self.headers)
+            response.raise_for_status()
+
+
+class MqttSink:
+    """发布到MQTT主题（每次通知单独连接，告警频率很低）
+    
+    主题前面加上机器人的主题前缀 reachy/<分组>/<UUID>/，多台机器人可以共用一个broker
+    """
+    
+    def __init__(self, host: str, topic: str = "alerts", port: int = 1883, username: Optional[str] = None,
+                 password: Optional[str] = None, tls: bool = False, qos: int = 1):
+        try:
+            import paho.mqtt.publish  # noqa: F401
+        except ImportError:
+            raise ValueError("MQTT通知需要安装paho-mqtt")
+        
+        self.host = host
+        self.topic = f"{get_identity().mqtt_prefix}/{topic.strip('/')}"
+        self.port = int(port)
+        self.auth = {"username": username, "password": password} if username else None
+        self.tls = {} if tls else None
+        self.qos = int(qos)
+    
+    def _publish(self, payload: str):
+        import paho.mqtt.publish as publish
+        publish.single(self.topic, payload, qos=self.qos, hostname=self.host, port=self.port,
+                       auth=self.auth, tls=self.tls, keepalive=int(config.alert.REQUEST_TIMEOUT) + 5)
+    
+    async def send(self, alert: Dict[str, Any]):
+        loop = asyncio.get_running_loop()
+        await asyncio.wait_for(loop.run_in_executor(None, self._publish, json.dumps(alert, ensure_ascii=False)),
+                               timeout=config.alert.REQUEST_TIMEOUT)
+
+
+SINK_TYPES = {
+    "log": LogSink,
+    "websocket": WebSocketSink,
+    "webhook": WebhookSink,
+    "mqtt": MqttSink,
+}
+
+
+def parse_rules(document: Dict[str, Any]):
+    """解析规则文件，返回(通知渠道, 规则列表)，格式错误时抛出ValueError"""
+    if not isinstance(document, dict):
+        raise ValueError("告警规则文件的顶层必须是映射")
+    
+    sinks = {}
+    for name, spec in (document.get("sinks") or {}).items():
+        spec = dict(spec or {})
+        sink_type = spec.pop("type", name)
+        if sink_type not in SINK_TYPES:
+            raise ValueError(f"通知渠道 {name} 的类型 {sink_type} 未知，可选: {', '.join(SINK_TYPES)}")
+        try:
+            sinks[name] = SINK_TYPES[sink_type](**spec)
+        except TypeError as e:
+            raise ValueError(f"通知渠道 {name} 的参数错误: {e}")
+    
+    rules = []
+    names = set()
+    for index, spec in enumerate(document.get("rules") or []):
+        if not isinstance(spec, dict) or not spec.get("name"):
+            raise ValueError(f"第 {index + 1} 条规则缺少name")
+        name = str(spec["name"])
+        if name in names:
+            raise ValueError(f"规则名重复: {name}")
+        names.add(name)
+        
+        if bool(spec.get("metric")) == bool(spec.get("event")):
+            raise ValueError(f"规则 {name} 必须且只能指定metric或event之一")
+        if spec.get("op", ">") not in OPERATORS:
+            raise ValueError(f"规则 {name} 的比较运算符未知，可选: {' '.join(OPERATORS)}")
+        if spec.get("severity", "warning") not in SEVERITIES:
+            raise ValueError(f"规则 {name} 的严重程度必须是 {', '.join(SEVERITIES)} 之一")
+        
+        rule_sinks = list(spec.get("sinks") or sinks)
+        unknown = [sink for sink in rule_sinks if sink not in sinks]
+        if unknown:
+            raise ValueError(f"规则 {name} 引用了未定义的通知渠道: {', '.join(unknown)}")
+        
+        rules.append(AlertRule(
+            name=name,
+            metric=spec.get("metric"),
+            op=spec.get("op", ">"),
+            threshold=float(spec.get("threshold", 0.0)),
+            for_seconds=float(spec.get("for", 0.0)),
+            event=spec.get("event"),
+            match=dict(spec.get("match") or {}),
+            severity=spec.get("severity", "warning"),
+            sinks=rule_sinks,
+            repeat_interval=float(spec.get("repeat_interval", 300.0)),
+            message=str(spec.get("message", "")),
+        ))
+    
+    return sinks, rules
+
+
+class AlertService:
+    """告警服务"""
+    
+    def __init__(self):
+        self.enabled = config.alert.ENABLED
+        self.rules_file = Path(config.alert.RULES_FILE)
+        self.sinks: Dict[str, Any] = {}
+        self.rules: List[AlertRule] = []
+        self.history: Deque[Dict[str, Any]] = deque(maxlen=config.alert.HISTORY_SIZE)
+        self.last_error: Optional[str] = None
+        self.stats = {
+            "fired": 0,
+            "resolved": 0,
+            "notifications_failed": 0,
+        }
+        self._tasks = set()
+    
+    def load(self):
+        """读取规则文件，文件不存在时没有规则"""
+        if not self.rules_file.exists():
+            self.sinks, self.rules = {}, []
+            logger.info(f"告警规则文件 {self.rules_file} 不存在，未配置告警")
+            return
+        
+        with open(self.rules_file, encoding="utf-8") as f:
+            document = yaml.safe_load(f) or {}
+        self.sinks, self.rules = parse_rules(document)
+        logger.info(f"加载了 {len(self.rules)} 条告警规则，通知渠道: {', '.join(self.sinks) or '无'}")
+    
+    def evaluate_metrics(self, now: float, values: Dict[str, float]):
+        """状态历史每次采样后调用，检查指标规则"""
+        for rule in self.rules:
+            if not rule.metric:
+                continue
+            
+            value = values.get(rule.metric)
+            if value is not None and OPERATORS[rule.op](value, rule.threshold):
+                if rule.pending_since is None:
+                    rule.pending_since = now
+                if not rule.firing and now - rule.pending_since >= rule.for_seconds:
+                    rule.firing = True
+                    rule.last_fired = now
+                    self._notify(rule, "firing", value)
+            else:
+                # 没有数据时不判断恢复，避免采样失败时告警反复触发
+                if value is None:
+                    continue
+                rule.pending_since = None
+                if rule.firing:
+                    rule.firing = False
+                    self._notify(rule, "resolved", value)
+    
+    async def observe_event(self, event_type: str, details: Optional[Dict[str, Any]] = None):
+        """检查事件规则，同一规则在repeat_interval内只通知一次"""
+        if not self.enabled:
+            return
+        
+        details = details or {}
+        now = time.time()
+        for rule in self.rules:
+            if rule.event != event_type:
+                continue
+            if any(details.get(key) != value for key, value in rule.match.items()):
+                continue
+            if rule.last_fired is not None and now - rule.last_fired < rule.repeat_interval:
+                continue
+            
+            rule.last_fired = now
+            self._notify(rule, "firing", None, details)
+    
+    def _notify(self, rule: AlertRule, status: str, value: Optional[float], details: Optional[Dict[str, Any]] = None):
+        alert = {
+            "rule": rule.name,
+            "robot": get_identity().to_dict(),
+            "status": status,
+            "severity": rule.severity,
+            "message": rule.describe(),
+            "metric": rule.metric,
+            "value": value,
+            "event": rule.event,
+            "details": details,
+            "timestamp": time.time(),
+        }
+        self.history.append(alert)
+        self.stats["fired" if status == "firing" else "resolved"] += 1
+        
+        task = asyncio.ensure_future(self._deliver(rule, alert))
+        self._tasks.add(task)
+        task.add_done_callback(self._tasks.discard)
+    
+    async def _deliver(self, rule: AlertRule, alert: Dict[str, Any]):
+        for name in rule.sinks:
+            sink = self.sinks.get(name)
+            if sink is None:
+                continue
+            try:
+                await sink.send(alert)
+            except Exception as e:
+                self.stats["notifications_failed"] += 1
+                self.last_error = f"{name}: {e}"
+                logger.warning(f"告警 {rule.name} 发送到 {name} 失败: {e}")
+    
+    async def start(self) -> bool:
+        """加载规则并开始检查"""
+        if not self.enabled:
+            logger.info("告警未启用")
+            return False
+        
+        try:
+            self.load()
+        except (OSError, ValueError, yaml.YAMLError) as e:
+            self.last_error = str(e)
+            logger.error(f"加载告警规则失败: {e}")
+            return False
+        
+        if self.evaluate_metrics not in metrics_history_service.listeners:
+            metrics_history_service.listeners.append(self.evaluate_metrics)
+        return True
+    
+    async def reload(self):
+        """重新读取规则文件，规则状态重置；格式错误时保留原规则"""
+        sinks, rules = self.sinks, self.rules
+        try:
+            self.load()
+        except Exception:
+            self.sinks, self.rules = sinks, rules
+            raise
+    
+    async def stop(self):
+        if self.evaluate_metrics in metrics_history_service.listeners:
+            metrics_history_service.listeners.remove(self.evaluate_metrics)
+        
+        # 等待已发出的通知发送完
+        if self._tasks:
+            await asyncio.wait(self._tasks, timeout=config.alert.REQUEST_TIMEOUT)
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "enabled": self.enabled,
+            "rules_file": str(self.rules_file),
+            "rules": [rule.to_dict() for rule in self.rules],
+            "sinks": list(self.sinks),
+            "firing": [rule.name for rule in self.rules if rule.firing],
+            "last_error": self.last_error,
+            **self.stats,
+        }
+
+
+# 全局告警服务实例
+alert_service = AlertService()
@@ -0,0 +1,317 @@
+#!/usr/bin/env python3
+"""
+交互分析服务
+统计人脸出现、问候、命令执行和运行时间，并把交互会话记录保存到数据库
+"""
+
+import asyncio
+from typing import Dict, List, Optional, Any
+from datetime import datetime
+
+from sqlalchemy import func
+
+from core.config import get_config
+from core.correlation import get_correlation_id
+from core.database import get_database_manager
+from core.models import InteractionSession, InteractionEvent
+from services.alert_service import alert_service
+from services.journal_service import journal_service
+from services.topic_service import topic_service
+from services.webhook_service import webhook_service
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+
+class AnalyticsService:
+    """交互分析服务"""
+    
+    # 可识别个人的字段，隐私模式下不写入事件日志
+    IDENTIFIABLE_FIELDS = {"identity", "bbox", "landmarks", "age", "gender", "emotion", "embedding", "image"}
+    
+    COUNTERS = ("faces_seen", "greetings_performed", "commands_executed")
+    
+    def __init__(self):
+        self.enabled = config.analytics.ENABLE_ANALYTICS
+        self.store_session_log = config.analytics.STORE_SESSION_LOG
+        self.store_identifiable_data = config.analytics.STORE_IDENTIFIABLE_DATA
+        self.flush_interval = config.analytics.FLUSH_INTERVAL
+        
+        self.session_id: Optional[str] = None
+        self.started_at = datetime.now()
+        self.counters: Dict[str, int] = {name: 0 for name in self.COUNTERS}
+        self.pending_events: List[Dict[str, Any]] = []
+        self.last_face_count = 0
+        
+        self.lock = asyncio.Lock()
+        self.flush_task = None
+        self.is_initialized = False
+    
+    async def initialize(self) -> bool:
+        """初始化交互分析服务，创建新的会话记录"""
+        try:
+            if not self.enabled:
+                logger.info("交互分析已禁用")
+                return True
+            
+            logger.info("正在初始化交互分析服务...")
+            
+            self.started_at = datetime.now()
+            self.session_id = await asyncio.to_thread(self._create_session)
+            self.flush_task = asyncio.create_task(self._flush_loop())
+            self.is_initialized = True
+            
+            logger.info(f"交互分析服务初始化完成，会话ID: {self.session_id}")
+            return True
+            
+        except Exception as e:
+            logger.error(f"交互分析服务初始化失败: {e}")
+            return False
+    
+    def _create_session(self) -> str:
+        """在数据库中创建会话记录"""
+        with get_database_manager().get_session() as session:
+            record = InteractionSession(started_at=self.started_at)
+            session.add(record)
+            session.flush()
+            return record.id
+    
+    def _sanitize(self, details: Optional[Dict[str, Any]]) -> Optional[Dict[str, Any]]:
+        """按隐私设置过滤事件详情"""
+        if not details:
+            return None
+        if self.store_identifiable_data:
+            return dict(details)
+        return {k: v for k, v in details.items() if k not in self.IDENTIFIABLE_FIELDS}
+    
+    async def record_event(self, event_type: str, details: Optional[Dict[str, Any]] = None, counter: Optional[str] = None):
+        """记录交互事件，可同时增加一个计数"""
+        # 实时推送给订阅了events主题的客户端，与是否保存无关，同样去掉可识别个人的字段
+        if topic_service.has_subscribers("events"):
+            await topic_service.publish("events", {"event_type": event_type, "details": self._sanitize(details)})
+        await alert_service.observe_event(event_type, self._sanitize(details))
+        webhook_service.dispatch(event_type, self._sanitize(details))
+        journal_service.observe(event_type, self._sanitize(details))
+        
+        if not self.enabled:
+            return
+        
+        async with self.lock:
+            if counter:
+                self.counters[counter] += 1
+            
+            if self.store_session_log:
+                self.pending_events.append({
+                    "event_type": event_type,
+                    "details": self._sanitize(details),
+                    "created_at": datetime.now(),
+                })
+    
+    async def record_faces(self, faces: List[Any]):
+        """记录人脸检测结果，只统计新出现的人脸（人数增加的部分）"""
+        new_faces = max(0, len(faces) - self.last_face_count)
+        self.last_face_count = len(faces)
+        
+        for face in faces[len(faces) - new_faces:]:
+            details = {
+                "confidence": getattr(face, "confidence", None),
+                "identity": getattr(face, "identity", None),
+                "bbox": list(getattr(face, "bbox", ()) or ()),
+                "age": getattr(face, "age", None),
+                "gender": getattr(face, "gender", None),
+                "emotion": getattr(face, "emotion", None),
+            }
+            await self.record_event("face_seen", details, counter="faces_seen")
+            if details["identity"]:
+                await self.record_event("face_recognized", {"identity": details["identity"], "confidence": details["confidence"]})
+    
+    async def record_greeting(self, details: Optional[Dict[str, Any]] = None):
+        """记录一次问候"""
+        await self.record_event("greeting", details, counter="greetings_performed")
+    
+    async def record_command(self, command: str, parameters: Optional[Dict[str, Any]] = None):
+        """记录一次执行的命令，带上当前请求的关联ID"""
+        details = {"command": command, **(parameters or {})}
+        details.setdefault("correlation_id", get_correlation_id())
+        await self.record_event("command", details, counter="commands_executed")
+    
+    def set_privacy(self, store_session_log: Optional[bool] = None, store_identifiable_data: Optional[bool] = None):
+        """运行时修改隐私设置"""
+        if store_session_log is not None:
+            self.store_session_log = store_session_log
+            if not store_session_log:
+                self.pending_events.clear()
+        if store_identifiable_data is not None:
+            self.store_identifiable_data = store_identifiable_data
+        
+        logger.info(f"隐私设置已更新: 事件日志={self.store_session_log}, 可识别数据={self.store_identifiable_data}")
+    
+    def get_privacy(self) -> Dict[str, bool]:
+        """获取隐私设置"""
+        return {
+            "store_session_log": self.store_session_log,
+            "store_identifiable_data": self.store_identifiable_data,
+        }
+    
+    @property
+    def run_time_seconds(self) -> float:
+        """当前会话运行时间（秒）"""
+        return (datetime.now() - self.started_at).total_seconds()
+    
+    async def flush(self):
+        """把计数和待写入事件保存到数据库"""
+        if not self.is_initialized:
+            return
+        
+        async with self.lock:
+            counters = dict(self.counters)
+            events = self.pending_events
+            self.pending_events = []
+        
+        try:
+            await asyncio.to_thread(self._write_session, counters, events, None)
+        except Exception as e:
+            logger.error(f"保存交互分析数据失败: {e}")
+            # 写入失败时保留事件，下次重试
+            async with self.lock:
+                self.pending_events = events + self.pending_events
+    
+    def _write_session(self, counters: Dict[str, int], events: List[Dict[str, Any]], ended_at: Optional[datetime]):
+        """写入会话计数和事件"""
+        with get_database_manager().get_session() as session:
+            record = session.get(InteractionSession, self.session_id)
+            if record is None:
+                return
+            
+            for name, value in counters.items():
+                setattr(record, name, value)
+            record.run_time_seconds = self.run_time_seconds
+            if ended_at:
+                record.ended_at = ended_at
+            
+            for event in events:
+                session.add(InteractionEvent(session_id=self.session_id, **event))
+    
+    async def _flush_loop(self):
+        """定期保存统计数据"""
+        while True:
+            try:
+                await asyncio.sleep(self.flush_interval)
+                await self.flush()
+            except asyncio.CancelledError:
+                break
+            except Exception as e:
+                logger.error(f"交互分析定期保存出错: {e}")
+    
+    async def get_summary(self) -> Dict[str, Any]:
+        """获取当前会话和历史累计统计"""
+        current = {**self.counters, "run_time_seconds": self.run_time_seconds}
+        totals = dict(current)
+        
+        if self.is_initialized:
+            try:
+                history = await asyncio.to_thread(self._query_history_totals)
+                for name, value in history.items():
+                    totals[name] = totals.get(name, 0) + value
+            except Exception as e:
+                logger.error(f"查询历史统计失败: {e}")
+        
+        return {
+            "enabled": self.enabled,
+            "session_id": self.session_id,
+            "started_at": self.started_at.isoformat(),
+            "current_session": current,
+            "totals": totals,
+            "privacy": self.get_privacy(),
+        }
+    
+    def _query_history_totals(self) -> Dict[str, float]:
+        """累计历史会话（不含当前会话）的统计"""
+        with get_database_manager().get_session() as session:
+            row = session.query(
+                func.coalesce(func.sum(InteractionSession.faces_seen), 0),
+                func.coalesce(func.sum(InteractionSession.greetings_performed), 0),
+                func.coalesce(func.sum(InteractionSession.commands_executed), 0),
+                func.coalesce(func.sum(InteractionSession.run_time_seconds), 0.0),
+            ).filter(InteractionSession.id != self.session_id).one()
+        
+        return dict(zip(self.COUNTERS + ("run_time_seconds",), row))
+    
+    async def get_events(self, limit: int = 100, event_type: Optional[str] = None) -> List[Dict[str, Any]]:
+        """获取最近的交互事件"""
+        await self.flush()
+        return await asyncio.to_thread(self._query_events, limit, event_type)
+    
+    def _query_events(self, limit: int, event_type: Optional[str]) -> List[Dict[str, Any]]:
+        with get_database_manager().get_session() as session:
+            query = session.query(InteractionEvent)
+            if event_type:
+                query = query.filter(InteractionEvent.event_type == event_type)
+            events = query.order_by(InteractionEvent.created_at.desc()).limit(limit).all()
+            
+            return [
+                {
+                    "id": event.id,
+                    "session_id": event.session_id,
+                    "event_type": event.event_type,
+                    "details": event.details,
+                    "created_at": event.created_at.isoformat(),
+                }
+                for event in events
+            ]
+    
+    async def clear_events(self) -> int:
+        """删除所有已保存的交互事件，返回删除数量"""
+        async with self.lock:
+            self.pending_events.clear()
+        
+        def _delete() -> int:
+            with get_database_manager().get_session() as session:
+                return session.query(InteractionEvent).delete()
+        
+        deleted = await asyncio.to_thread(_delete)
+        logger.info(f"已删除 {deleted} 条交互事件")
+        return deleted
+    
+    async def reset(self):
+        """清空统计并开始新的会话（清除用户数据后调用）"""
+        async with self.lock:
+            self.counters = {name: 0 for name in self.COUNTERS}
+            self.pending_events = []
+            self.last_face_count = 0
+        
+        self.started_at = datetime.now()
+        if self.is_initialized:
+            self.session_id = await asyncio.to_thread(self._create_session)
+        
+        logger.info("交互统计已重置")
+    
+    async def cleanup(self):
+        """清理资源，结束当前会话"""
+        try:
+            if self.flush_task and not self.flush_task.done():
+                self.flush_task.cancel()
+                try:
+                    await self.flush_task
+                except asyncio.CancelledError:
+                    pass
+            
+            if self.is_initialized:
+                async with self.lock:
+                    counters = dict(self.counters)
+                    events = self.pending_events
+                    self.pending_events = []
+                await asyncio.to_thread(self._write_session, counters, events, datetime.now())
+            
+            self.is_initialized = False
+            logger.info("交互分析服务清理完成")
+            
+        except Exception as e:
+            logger.error(f"交互分析服务清理时出错: {e}")
+
+
+# 全局交互分析服务实例
+analytics_service = AnalyticsService()
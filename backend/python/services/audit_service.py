@@ -0,0 +1,116 @@
+#!/usr/bin/env python3
+"""
+命令审计日志服务
+读取Rust实时控制器写入的命令审计文件（JSON Lines，带轮转），按来源、客户端、
+角色、时间范围和处理结果查询，用于追查每条运动命令是谁在什么时候发出的
+"""
+
+import asyncio
+import json
+import re
+from pathlib import Path
+from typing import Dict, List, Optional, Any
+
+from core.config import get_config
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 当前审计文件名，轮转后的文件为 commands.jsonl.1、commands.jsonl.2 ...（数字越大越旧）
+AUDIT_FILE_NAME = "commands.jsonl"
+ROTATED_PATTERN = re.compile(rf"^{re.escape(AUDIT_FILE_NAME)}\.(\d+)$")
+
+
+class AuditService:
+    """命令审计日志查询服务"""
+    
+    def __init__(self, directory: Optional[str] = None):
+        self.directory = Path(directory or config.audit.DIRECTORY)
+    
+    def _files(self) -> List[Path]:
+        """从最旧到最新排列的审计文件"""
+        if not self.directory.is_dir():
+            return []
+        
+        rotated = []
+        for path in self.directory.iterdir():
+            match = ROTATED_PATTERN.match(path.name)
+            if match:
+                rotated.append((int(match.group(1)), path))
+        
+        files = [path for _, path in sorted(rotated, reverse=True)]
+        current = self.directory / AUDIT_FILE_NAME
+        if current.exists():
+            files.append(current)
+        return files
+    
+    @staticmethod
+    def _matches(record: Dict[str, Any], source: Optional[str], client_id: Optional[str], role: Optional[str],
+                 since: Optional[int], until: Optional[int], accepted: Optional[bool]) -> bool:
+        if source is not None and record.get("source") != source:
+            return False
+        if client_id is not None and record.get("client_id") != client_id:
+            return False
+        # 旧记录没有角色字段，与Rust端一致按操作员处理
+        if role is not None and record.get("role", "operator") != role:
+            return False
+        
+        timestamp = record.get("timestamp", 0)
+        if since is not None and timestamp < since:
+            return False
+        if until is not None and timestamp > until:
+            return False
+        
+        if accepted is not None:
+            is_accepted = record.get("result", {}).get("status") == "accepted"
+            if is_accepted != accepted:
+                return False
+        
+        return True
+    
+    def _query(self, source: Optional[str], client_id: Optional[str], role: Optional[str], since: Optional[int],
+               until: Optional[int], accepted: Optional[bool], limit: int) -> List[Dict[str, Any]]:
+        matched: List[Dict[str, Any]] = []
+        
+        for path in self._files():
+            try:
+                lines = path.read_text(encoding="utf-8").splitlines()
+            except OSError as e:
+                logger.warning(f"读取审计文件 {path} 失败: {e}")
+                continue
+            
+            for line in lines:
+                if not line.strip():
+                    continue
+                try:
+                    record = json.loads(line)
+                except json.JSONDecodeError:
+                    # 断电时写了一半的最后一行
+                    continue
+                if self._matches(record, source, client_id, role, since, until, accepted):
+                    matched.append(record)
+        
+        return matched[-limit:] if limit > 0 else []
+    
+    async def query(self, source: Optional[str] = None, client_id: Optional[str] = None,
+                    role: Optional[str] = None, since: Optional[int] = None, until: Optional[int] = None,
+                    accepted: Optional[bool] = None, limit: int = 100) -> List[Dict[str, Any]]:
+        """查询审计记录，返回最新的limit条，按序号升序排列（时间为毫秒时间戳）"""
+        limit = min(limit, config.audit.MAX_QUERY_LIMIT)
+        return await asyncio.to_thread(self._query, source, client_id, role, since, until, accepted, limit)
+    
+    def get_status(self) -> Dict[str, Any]:
+        """审计文件概况"""
+        files = self._files()
+        return {
+            "directory": str(self.directory),
+            "files": [path.name for path in files],
+            "total_bytes": sum(path.stat().st_size for path in files),
+        }
+
+
+# 全局命令审计服务实例
+audit_service = AuditService()
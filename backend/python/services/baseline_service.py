@@ -0,0 +1,236 @@
+#!/usr/bin/env python3
+"""
+性能基线服务
+按固定窗口统计状态历史中的关键性能指标（控制循环抖动、推理p95耗时、帧率），每个窗口的
+均值与该指标的基线比较：基线是最近若干个正常窗口均值的中位数，保存在数据目录中，重启和
+升级后保留。某个指标比基线差超过设定的百分比时记录日志并发出performance_regression事件
+（推送到events主题，可以配置告警规则和Webhook），恢复后发出performance_recovered事件
+
+退化的窗口不计入基线，新模型或新配置确实更慢时需要调用reset接受新的水平。基线中同时
+记录版本和配置指纹，退化发生在升级或改配置之后时会在事件中注明
+"""
+
+import asyncio
+import hashlib
+import json
+import os
+import statistics
+from pathlib import Path
+from typing import Any, Dict, List, Optional
+
+from core.config import get_config
+from services.analytics_service import analytics_service
+from services.metrics_history_service import metrics_history_service
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+
+def current_fingerprint() -> str:
+    """应用版本和影响性能的配置（AI、视觉、实时控制）的指纹"""
+    settings = {
+        "version": config.APP_VERSION,
+        "ai": config.ai.model_dump(),
+        "vision": config.vision.model_dump(),
+        "realtime": config.realtime.model_dump(),
+    }
+    document = json.dumps(settings, sort_keys=True, default=str)
+    return hashlib.sha256(document.encode("utf-8")).hexdigest()[:16]
+
+
+class MetricBaseline:
+    """一个指标的基线：最近的正常窗口均值"""
+    
+    def __init__(self, windows: Optional[List[float]] = None, fingerprint: Optional[str] = None,
+                 version: Optional[str] = None):
+        self.windows: List[float] = windows or []
+        # 最近一次计入基线时的配置指纹和版本
+        self.fingerprint = fingerprint
+        self.version = version
+        self.regressed = False
+        self.last_value: Optional[float] = None
+    
+    @property
+    def value(self) -> Optional[float]:
+        return statistics.median(self.windows) if self.windows else None
+    
+    def to_dict(self) -> Dict[str, Any]:
+        return {"windows": self.windows, "fingerprint": self.fingerprint, "version": self.version}
+
+
+class BaselineService:
+    """性能基线服务"""
+    
+    def __init__(self):
+        self.baselines: Dict[str, MetricBaseline] = {}
+        # 当前窗口：起始时间和各指标的[样本数, 总和]
+        self.window_start: Optional[float] = None
+        self.window: Dict[str, List[float]] = {}
+        self.fingerprint = current_fingerprint()
+        self._tasks = set()
+    
+    @property
+    def settings(self):
+        return config.performance_baseline
+    
+    @property
+    def state_path(self) -> Path:
+        return Path(config.DATA_DIR) / self.settings.STATE_FILE
+    
+    def load(self):
+        """读取保存的基线"""
+        try:
+            with open(self.state_path, encoding="utf-8") as f:
+                state = json.load(f)
+        except FileNotFoundError:
+            return
+        except (OSError, ValueError) as e:
+            logger.warning(f"读取性能基线失败: {e}")
+            return
+        
+        try:
+            self.baselines = {
+                name: MetricBaseline([float(v) for v in entry["windows"]], entry.get("fingerprint"), entry.get("version"))
+                for name, entry in state["metrics"].items()
+            }
+        except (KeyError, TypeError, ValueError, AttributeError) as e:
+            logger.warning(f"性能基线格式错误，丢弃: {e}")
+    
+    def _save(self):
+        path = self.state_path
+        path.parent.mkdir(parents=True, exist_ok=True)
+        tmp = path.with_suffix(".tmp")
+        with open(tmp, "w", encoding="utf-8") as f:
+            json.dump({"metrics": {name: baseline.to_dict() for name, baseline in self.baselines.items()}}, f)
+        os.replace(tmp, path)
+    
+    def observe(self, now: float, values: Dict[str, float]):
+        """状态历史每次采样后调用，窗口结束时与基线比较"""
+        if self.window_start is None:
+            self.window_start = now
+        for name in self.settings.METRICS:
+            if name in values:
+                entry = self.window.setdefault(name, [0, 0.0])
+                entry[0] += 1
+                entry[1] += values[name]
+        
+        if now - self.window_start >= self.settings.WINDOW_SECONDS:
+            means = {name: total / count for name, (count, total) in self.window.items() if count}
+            self.window_start, self.window = now, {}
+            self.finish_window(means)
+    
+    def regression_percent(self, name: str, value: float, baseline: float) -> float:
+        """当前值比基线差的百分比，变好时为负数"""
+        if baseline == 0:
+            return 0.0
+        worse = value - baseline if self.settings.METRICS[name] == "lower" else baseline - value
+        return worse / abs(baseline) * 100
+    
+    def finish_window(self, means: Dict[str, float]):
+        """比较一个窗口的均值，正常的窗口计入基线"""
+        settings = self.settings
+        changed = False
+        for name, value in means.items():
+            baseline = self.baselines.setdefault(name, MetricBaseline())
+            baseline.last_value = value
+            reference = baseline.value
+            
+            if reference is not None and len(baseline.windows) >= settings.MIN_WINDOWS:
+                percent = self.regression_percent(name, value, reference)
+                if percent > settings.REGRESSION_PERCENT:
+                    if not baseline.regressed:
+                        baseline.regressed = True
+                        self._report(name, "performance_regression", value, reference, percent, baseline)
+                    # 退化的窗口不计入基线
+                    continue
+                if baseline.regressed:
+                    baseline.regressed = False
+                    self._report(name, "performance_recovered", value, reference, percent, baseline)
+            
+            baseline.windows = (baseline.windows + [value])[-settings.BASELINE_WINDOWS:]
+            baseline.fingerprint, baseline.version = self.fingerprint, config.APP_VERSION
+            changed = True
+        
+        if changed:
+            try:
+                self._save()
+            except OSError as e:
+                logger.warning(f"保存性能基线失败: {e}")
+    
+    def _report(self, name: str, event_type: str, value: float, reference: float, percent: float,
+                baseline: MetricBaseline):
+        after_update = baseline.fingerprint is not None and baseline.fingerprint != self.fingerprint
+        details = {
+            "metric": name,
+            "value": value,
+            "baseline": reference,
+            "regression_percent": round(percent, 1),
+            "after_update": after_update,
+            "baseline_version": baseline.version,
+            "version": config.APP_VERSION,
+        }
+        if event_type == "performance_regression":
+            cause = f"（版本或配置自 {baseline.version} 以来有变更）" if after_update else ""
+            logger.warning(f"性能退化: {name} 为 {value:.3g}，基线 {reference:.3g}，差 {percent:.0f}%{cause}")
+        else:
+            logger.info(f"性能恢复: {name} 为 {value:.3g}，基线 {reference:.3g}")
+        
+        task = asyncio.ensure_future(analytics_service.record_event(event_type, details))
+        self._tasks.add(task)
+        task.add_done_callback(self._tasks.discard)
+    
+    def reset(self, name: Optional[str] = None) -> List[str]:
+        """丢弃基线（全部或一个指标），从之后的窗口重新积累，用于接受新的性能水平"""
+        if name is not None and name not in self.baselines:
+            raise KeyError(name)
+        names = [name] if name is not None else list(self.baselines)
+        for item in names:
+            del self.baselines[item]
+        try:
+            self._save()
+        except OSError as e:
+            logger.warning(f"保存性能基线失败: {e}")
+        logger.info(f"已重置性能基线: {', '.join(names) or '无'}")
+        return names
+    
+    def start(self) -> bool:
+        if not self.settings.ENABLED:
+            logger.info("性能基线未启用")
+            return False
+        
+        self.load()
+        if self.observe not in metrics_history_service.listeners:
+            metrics_history_service.listeners.append(self.observe)
+        return True
+    
+    def stop(self):
+        if self.observe in metrics_history_service.listeners:
+            metrics_history_service.listeners.remove(self.observe)
+    
+    def get_status(self) -> Dict[str, Any]:
+        settings = self.settings
+        return {
+            "enabled": settings.ENABLED,
+            "fingerprint": self.fingerprint,
+            "window_seconds": settings.WINDOW_SECONDS,
+            "regression_percent": settings.REGRESSION_PERCENT,
+            "metrics": {
+                name: {
+                    "better": settings.METRICS.get(name),
+                    "baseline": baseline.value,
+                    "windows": len(baseline.windows),
+                    "last_value": baseline.last_value,
+                    "regressed": baseline.regressed,
+                    "version": baseline.version,
+                    "after_update": baseline.fingerprint is not None and baseline.fingerprint != self.fingerprint,
+                }
+                for name, baseline in self.baselines.items()
+            },
+        }
+
+
+# 全局性能基线服务实例
+baseline_service = BaselineService()
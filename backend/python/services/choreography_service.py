@@ -0,0 +1,294 @@
+#!/usr/bin/env python3
+"""
+编舞服务
+与Rust端 choreography 模块使用相同的编舞文件格式：共享时间轴上每台机器人一条轨道，
+轨道上的提示点在指定时刻播放一段动画（轨迹文件）。一台机器人发出提示后，把编舞文件
+和按参考时钟计算的开始时间发给其他机器人，各机器人换算为本地时间后同时开始
+
+参考时钟取Rust实时控制器的时间同步结果（NTP或客户端时间交换），没有同步时使用本地时钟
+"""
+
+import asyncio
+import json
+import math
+import re
+import time
+from pathlib import Path
+from typing import Any, Dict, List, Optional, Tuple
+
+import httpx
+
+from core.config import get_config
+from core.identity import RobotIdentity, get_identity
+from services.analytics_service import analytics_service
+from services.robot_service import robot_service
+from rust_bindings import get_rust_bindings_manager
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 与Rust端 CHOREOGRAPHY_FORMAT / CHOREOGRAPHY_VERSION 保持一致
+CHOREOGRAPHY_FORMAT = "reachy-mini-choreography"
+CHOREOGRAPHY_VERSION = 1
+TRAJECTORY_FORMAT = "reachy-mini-trajectory"
+ANY_ROBOT = "*"
+
+NAME_PATTERN = re.compile(r"^[A-Za-z0-9_-]{1,64}$")
+
+# 动画关节到机器人服务头部关节的映射（轨迹为弧度，机器人服务为度）
+HEAD_JOINTS = {"head_pan": "pan", "head_tilt": "tilt"}
+
+
+def _duration(animation: Dict[str, Any]) -> float:
+    frames = animation.get("frames") or []
+    return float(frames[-1]["time"]) if frames else 0.0
+
+
+def _validate_animation(name: str, animation: Dict[str, Any]):
+    if not isinstance(animation, dict) or animation.get("format") != TRAJECTORY_FORMAT:
+        raise ValueError(f"动画 '{name}' 不是轨迹文件")
+    joints = animation.get("joints") or []
+    if not joints:
+        raise ValueError(f"动画 '{name}' 没有关节")
+    
+    previous = None
+    for index, frame in enumerate(animation.get("frames") or []):
+        t = frame.get("time")
+        if not isinstance(t, (int, float)) or not math.isfinite(t) or t < 0 or (previous is not None and t <= previous):
+            raise ValueError(f"动画 '{name}' 第 {index} 帧的时间无效")
+        previous = t
+        positions = frame.get("positions") or []
+        if len(positions) != len(joints) or not all(isinstance(p, (int, float)) and math.isfinite(p) for p in positions):
+            raise ValueError(f"动画 '{name}' 第 {index} 帧的位置无效")
+
+
+def validate_choreography(document: Dict[str, Any]):
+    """检查格式、动画、轨道和提示点时间，格式错误时抛出ValueError"""
+    if not isinstance(document, dict) or document.get("format") != CHOREOGRAPHY_FORMAT:
+        raise ValueError("不是编舞文件")
+    if int(document.get("version", 0)) > CHOREOGRAPHY_VERSION:
+        raise ValueError(f"编舞格式版本 {document.get('version')} 高于支持的版本 {CHOREOGRAPHY_VERSION}")
+    
+    animations = document.get("animations") or {}
+    for name, animation in animations.items():
+        _validate_animation(name, animation)
+    
+    tracks = document.get("tracks") or []
+    if not tracks:
+        raise ValueError("编舞没有轨道")
+    
+    robots = set()
+    for track in tracks:
+        robot = track.get("robot")
+        if not robot or robot in robots:
+            raise ValueError(f"轨道的机器人为空或重复: {robot}")
+        robots.add(robot)
+        
+        free_at = 0.0
+        for cue in track.get("cues") or []:
+            animation = animations.get(cue.get("animation"))
+            if animation is None:
+                raise ValueError(f"轨道 '{robot}' 引用了不存在的动画 '{cue.get('animation')}'")
+            at = cue.get("at")
+            if not isinstance(at, (int, float)) or not math.isfinite(at) or at < 0:
+                raise ValueError(f"轨道 '{robot}' 的提示点时间无效: {at}")
+            if at < free_at:
+                raise ValueError(f"轨道 '{robot}' 在 {at:.2f} 秒的动画 '{cue['animation']}' 与前一段动画重叠")
+            free_at = at + _duration(animation)
+
+
+def track_for(document: Dict[str, Any], robot: RobotIdentity) -> Optional[Dict[str, Any]]:
+    """机器人的轨道：先按UUID匹配，再按名称，最后使用 * 轨道"""
+    tracks = {track["robot"]: track for track in document.get("tracks") or []}
+    for key in (robot.uuid, robot.name, ANY_ROBOT):
+        if key in tracks:
+            return tracks[key]
+    return None
+
+
+def sample_animation(animation: Dict[str, Any], t: float) -> Optional[Dict[str, float]]:
+    """在t秒处线性插值各关节位置，超过动画末尾时返回None"""
+    frames = animation.get("frames") or []
+    index = next((i for i, frame in enumerate(frames) if frame["time"] >= t), None)
+    if index is None:
+        return None
+    if index == 0:
+        positions = frames[0]["positions"]
+    else:
+        a, b = frames[index - 1], frames[index]
+        progress = (t - a["time"]) / (b["time"] - a["time"])
+        positions = [p0 + (p1 - p0) * progress for p0, p1 in zip(a["positions"], b["positions"])]
+    return dict(zip(animation["joints"], positions))
+
+
+class ChoreographyService:
+    """编舞服务"""
+    
+    def __init__(self):
+        self.directory = Path(config.choreography.DIR)
+        self.current: Optional[Dict[str, Any]] = None
+        self.last_error: Optional[str] = None
+        self.show_task: Optional[asyncio.Task] = None
+    
+    @property
+    def is_playing(self) -> bool:
+        return self.show_task is not None and not self.show_task.done()
+    
+    def _path(self, name: str) -> Path:
+        if not NAME_PATTERN.match(name):
+            raise ValueError("编舞名称只能包含字母、数字、下划线和连字符")
+        return self.directory / f"{name}.json"
+    
+    def list_choreographies(self) -> List[Dict[str, Any]]:
+        result = []
+        for path in sorted(self.directory.glob("*.json")):
+            try:
+                document = json.loads(path.read_text(encoding="utf-8"))
+                result.append({"name": path.stem, "tracks": [t["robot"] for t in document.get("tracks") or []]})
+            except (OSError, ValueError, KeyError):
+                continue
+        return result
+    
+    def load(self, name: str) -> Optional[Dict[str, Any]]:
+        path = self._path(name)
+        if not path.exists():
+            return None
+        return json.loads(path.read_text(encoding="utf-8"))
+    
+    def save(self, name: str, document: Dict[str, Any]):
+        validate_choreography(document)
+        path = self._path(name)
+        path.parent.mkdir(parents=True, exist_ok=True)
+        tmp = path.with_suffix(".tmp")
+        tmp.write_text(json.dumps(document, ensure_ascii=False), encoding="utf-8")
+        tmp.replace(path)
+        logger.info(f"保存编舞 {name}")
+    
+    def delete(self, name: str) -> bool:
+        path = self._path(name)
+        if not path.exists():
+            return False
+        path.unlink()
+        return True
+    
+    def _clock_offset_ms(self) -> float:
+        """参考时钟减本地时钟（毫秒），没有时间同步结果时为0"""
+        try:
+            status = get_rust_bindings_manager().get_realtime_status() or {}
+            time_sync = status.get("time_sync") or {}
+            if time_sync.get("synchronized"):
+                return float(time_sync.get("offset_ms", 0.0))
+        except Exception as e:
+            logger.debug(f"读取时间同步状态失败: {e}")
+        return 0.0
+    
+    def reference_now(self) -> int:
+        """参考时钟的当前时间（毫秒）"""
+        return int(time.time() * 1000 + self._clock_offset_ms())
+    
+    async def start(self, name: str, start_at: int, document: Optional[Dict[str, Any]] = None) -> Dict[str, Any]:
+        """在参考时钟start_at（毫秒）开始演出本机的轨道；document为空时使用已保存的编舞"""
+        document = document if document is not None else self.load(name)
+        if document is None:
+            raise ValueError(f"编舞 {name} 不存在")
+        validate_choreography(document)
+        
+        identity = get_identity()
+        track = track_for(document, identity)
+        if track is None:
+            raise ValueError(f"编舞 {name} 中没有机器人 '{identity.name}' 的轨道")
+        
+        delay = (start_at - self.reference_now()) / 1000.0
+        if delay < 0:
+            raise ValueError(f"演出开始时间已过 {-delay * 1000:.0f}ms")
+        
+        await self.stop()
+        self.current = {"name": name, "start_at": start_at, "track": track["robot"], "cues": len(track.get("cues") or [])}
+        self.last_error = None
+        self.show_task = asyncio.create_task(self._perform(name, document, track, delay))
+        logger.info(f"编舞 {name} 将在 {delay:.2f} 秒后开始，轨道: {track['robot']}")
+        return self.get_status()
+    
+    async def _perform(self, name: str, document: Dict[str, Any], track: Dict[str, Any], delay: float):
+        loop = asyncio.get_running_loop()
+        start = loop.time() + delay
+        period = 1.0 / config.choreography.PLAYBACK_RATE
+        
+        try:
+            for cue in track.get("cues") or []:
+                animation = document["animations"][cue["animation"]]
+                cue_start = start + cue["at"]
+                await asyncio.sleep(max(0.0, cue_start - loop.time()))
+                
+                while True:
+                    positions = sample_animation(animation, loop.time() - cue_start)
+                    if positions is None:
+                        break
+                    head = {HEAD_JOINTS[j]: math.degrees(v) for j, v in positions.items() if j in HEAD_JOINTS}
+                    if not await robot_service.set_pose(head, {}):
+                        raise RuntimeError("机器人未连接")
+                    await asyncio.sleep(period)
+            
+            logger.info(f"编舞 {name} 演出结束")
+            await analytics_service.record_event("behavior_finished", {"behavior": f"choreography:{name}"})
+        except asyncio.CancelledError:
+            raise
+        except Exception as e:
+            self.last_error = str(e)
+            logger.error(f"编舞 {name} 演出中断: {e}")
+    
+    async def cue(self, name: str, lead_time: Optional[float] = None, peers: Optional[List[str]] = None) -> Dict[str, Any]:
+        """发出提示：把编舞和开始时间发给其他机器人，然后本机也开始"""
+        document = self.load(name)
+        if document is None:
+            raise ValueError(f"编舞 {name} 不存在")
+        validate_choreography(document)
+        
+        lead_time = config.choreography.LEAD_TIME if lead_time is None else lead_time
+        start_at = self.reference_now() + int(lead_time * 1000)
+        peers = config.choreography.PEERS if peers is None else peers
+        
+        async with httpx.AsyncClient(timeout=config.choreography.REQUEST_TIMEOUT) as client:
+            results = await asyncio.gather(*(self._send_cue(client, peer, name, start_at, document) for peer in peers))
+        
+        local = await self.start(name, start_at, document)
+        return {"start_at": start_at, "local": local, "peers": dict(results)}
+    
+    async def _send_cue(self, client: httpx.AsyncClient, peer: str, name: str, start_at: int,
+                        document: Dict[str, Any]) -> Tuple[str, Dict[str, Any]]:
+        try:
+            response = await client.post(f"{peer.rstrip('/')}/api/choreography/{name}/start",
+                                         json={"start_at": start_at, "choreography": document})
+            if response.status_code >= 300:
+                return peer, {"success": False, "error": response.text[:200]}
+            return peer, {"success": True}
+        except httpx.HTTPError as e:
+            logger.warning(f"向 {peer} 发送编舞提示失败: {e}")
+            return peer, {"success": False, "error": str(e)}
+    
+    async def stop(self):
+        """停止演出，机器人保持当前姿态"""
+        if self.show_task and not self.show_task.done():
+            self.show_task.cancel()
+            try:
+                await self.show_task
+            except asyncio.CancelledError:
+                pass
+            logger.info("编舞演出已停止")
+        self.show_task = None
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "playing": self.is_playing,
+            "current": self.current,
+            "reference_time": self.reference_now(),
+            "clock_offset_ms": self._clock_offset_ms(),
+            "last_error": self.last_error,
+        }
+
+
+# 全局编舞服务实例
+choreography_service = ChoreographyService()
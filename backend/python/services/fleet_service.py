@@ -0,0 +1,249 @@
+#!/usr/bin/env python3
+"""
+机群心跳服务
+定期把匿名的健康信息（运行时间、版本、错误计数）上报到配置的地址，
+离线时缓存心跳并按指数退避重试，便于实验室集中监控多台机器人
+"""
+
+import asyncio
+import hashlib
+import logging
+import platform
+import time
+from collections import deque
+from typing import Callable, Deque, Dict, Optional, Any
+from datetime import datetime
+
+import httpx
+
+from core.config import get_config
+from core.identity import get_identity
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 用于生成匿名机器人标识的机器ID文件
+MACHINE_ID_PATHS = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+
+
+class ErrorCounter(logging.Handler):
+    """统计ERROR及以上级别的日志数量（只计数，不记录内容）"""
+    
+    def __init__(self):
+        super().__init__(level=logging.ERROR)
+        self.errors = 0
+        self.critical = 0
+    
+    def emit(self, record: logging.LogRecord):
+        if record.levelno >= logging.CRITICAL:
+            self.critical += 1
+        else:
+            self.errors += 1
+
+
+class FleetService:
+    """机群心跳服务"""
+    
+    def __init__(self):
+        self.started_at = time.time()
+        self.last_report: Optional[datetime] = None
+        self.last_error: Optional[str] = None
+        self.backoff: Optional[float] = None
+        self.queue: Deque[Dict[str, Any]] = deque(maxlen=config.fleet.MAX_QUEUE_SIZE)
+        self.stats = {
+            "reports_sent": 0,
+            "reports_failed": 0,
+            "reports_dropped": 0,
+        }
+        
+        self.error_counter = ErrorCounter()
+        self.status_provider: Optional[Callable[[], Dict[str, Any]]] = None
+        self.report_task = None
+        self.http_client: Optional[httpx.AsyncClient] = None
+    
+    @property
+    def is_running(self) -> bool:
+        return self.report_task is not None and not self.report_task.done()
+    
+    @property
+    def robot_id(self) -> str:
+        """机器人标识；未配置时使用机器ID的哈希，不泄露主机信息"""
+        if config.fleet.ROBOT_ID:
+            return config.fleet.ROBOT_ID
+        
+        machine_id = platform.node()
+        for path in MACHINE_ID_PATHS:
+            try:
+                with open(path) as f:
+                    machine_id = f.read().strip()
+                break
+            except OSError:
+                continue
+        return hashlib.sha256(f"reachy-mini:{machine_id}".encode()).hexdigest()[:16]
+    
+    def _validate_settings(self):
+        fleet = config.fleet
+        if not fleet.ENDPOINT:
+            raise ValueError("未配置心跳上报地址")
+        if not fleet.ENDPOINT.startswith("https://") and not fleet.ALLOW_INSECURE:
+            raise ValueError("心跳上报地址必须使用https://")
+    
+    async def start(self, status_provider: Optional[Callable[[], Dict[str, Any]]] = None) -> bool:
+        """启动心跳上报"""
+        try:
+            if self.is_running:
+                logger.info("机群心跳已经在运行")
+                return True
+            
+            self._validate_settings()
+            
+            if status_provider:
+                self.status_provider = status_provider
+            
+            headers = {"Authorization": f"Bearer {config.fleet.TOKEN}"} if config.fleet.TOKEN else {}
+            self.http_client = httpx.AsyncClient(timeout=config.fleet.REQUEST_TIMEOUT, headers=headers)
+            
+            self._attach_error_counter()
+            self.report_task = asyncio.create_task(self._report_loop())
+            
+            logger.info(f"机群心跳已启动，上报地址: {config.fleet.ENDPOINT}")
+            return True
+            
+        except Exception as e:
+            self.last_error = str(e)
+            logger.error(f"启动机群心跳失败: {e}")
+            return False
+    
+    async def stop(self):
+        """停止心跳上报（未发送的心跳保留在队列中）"""
+        try:
+            if self.report_task and not self.report_task.done():
+                self.report_task.cancel()
+                try:
+                    await self.report_task
+                except asyncio.CancelledError:
+                    pass
+            self.report_task = None
+            
+            if self.http_client:
+                await self.http_client.aclose()
+                self.http_client = None
+            
+            self._detach_error_counter()
+            logger.info("机群心跳已停止")
+            
+        except Exception as e:
+            logger.error(f"停止机群心跳时出错: {e}")
+    
+    def _error_loggers(self):
+        """根日志器以及不向上传播的日志器（setup_logger创建的日志器都不传播）"""
+        loggers = [logging.getLogger()]
+        for item in logging.root.manager.loggerDict.values():
+            if isinstance(item, logging.Logger) and not item.propagate:
+                loggers.append(item)
+        return loggers
+    
+    def _attach_error_counter(self):
+        for item in self._error_loggers():
+            if self.error_counter not in item.handlers:
+                item.addHandler(self.error_counter)
+    
+    def _detach_error_counter(self):
+        for item in self._error_loggers():
+            item.removeHandler(self.error_counter)
+    
+    def build_report(self) -> Dict[str, Any]:
+        """生成一条匿名健康报告"""
+        report = {
+            "robot_id": self.robot_id,
+            # 只上报分组，不上报用户设置的名称
+            "group": get_identity().group,
+            "timestamp": datetime.now().isoformat(),
+            "version": config.APP_VERSION,
+            "uptime": round(time.time() - self.started_at, 1),
+            "errors": {
+                "error": self.error_counter.errors,
+                "critical": self.error_counter.critical,
+            },
+        }
+        
+        # 只上报组件是否正常，不包含任何配置或用户数据
+        if self.status_provider:
+            try:
+                status = self.status_provider()
+                report["running"] = status.get("running", False)
+                report["components"] = status.get("components", {})
+            except Exception as e:
+                logger.debug(f"获取组件状态失败: {e}")
+        
+        return report
+    
+    def enqueue(self, report: Dict[str, Any]):
+        """加入待发送队列，队列已满时丢弃最旧的心跳"""
+        if len(self.queue) == self.queue.maxlen:
+            self.stats["reports_dropped"] += 1
+        self.queue.append(report)
+    
+    async def flush(self) -> bool:
+        """按时间顺序发送队列中的心跳，遇到失败即停止"""
+        while self.queue:
+            try:
+                response = await self.http_client.post(config.fleet.ENDPOINT, json=self.queue[0])
+                response.raise_for_status()
+            except Exception as e:
+                self.stats["reports_failed"] += 1
+                self.last_error = str(e)
+                return False
+            
+            self.queue.popleft()
+            self.stats["reports_sent"] += 1
+            self.last_report = datetime.now()
+        
+        self.last_error = None
+        return True
+    
+    async def _report_loop(self):
+        """定期上报，失败后按指数退避重试"""
+        fleet = config.fleet
+        
+        while True:
+            try:
+                self.enqueue(self.build_report())
+                
+                if await self.flush():
+                    self.backoff = None
+                    delay = fleet.REPORT_INTERVAL
+                else:
+                    self.backoff = min(self.backoff * 2, fleet.MAX_BACKOFF) if self.backoff else fleet.INITIAL_BACKOFF
+                    # 离线期间每次重试都会生成一条新心跳并排队
+                    delay = self.backoff
+                    logger.warning(f"机群心跳上报失败: {self.last_error}，{delay:.0f}秒后重试（队列 {len(self.queue)}）")
+                
+                await asyncio.sleep(delay)
+                
+            except asyncio.CancelledError:
+                break
+            except Exception as e:
+                logger.error(f"机群心跳循环出错: {e}")
+                await asyncio.sleep(fleet.REPORT_INTERVAL)
+    
+    def get_status(self) -> Dict[str, Any]:
+        """获取心跳上报状态（不包含令牌）"""
+        return {
+            "enabled": config.fleet.ENABLED,
+            "running": self.is_running,
+            "endpoint": config.fleet.ENDPOINT,
+            "robot_id": self.robot_id,
+            "last_report": self.last_report.isoformat() if self.last_report else None,
+            "last_error": self.last_error,
+            "backoff": self.backoff,
+            "queued": len(self.queue),
+            "stats": self.stats.copy(),
+        }
+
+
+# 全局机群心跳服务实例
+fleet_service = FleetService()
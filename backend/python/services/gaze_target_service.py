@@ -0,0 +1,166 @@
+#!/usr/bin/env python3
+"""
+命名注视目标服务
+用户教给机器人一些有名字的注视目标（"门口"、"书桌"），保存为底座坐标系中的3D点，
+之后可以按名字看向目标。目标保存在数据目录的JSON文件中，重启后仍然有效
+
+名字不区分大小写、忽略首尾空白；看向目标时由Rust端求解注视的关节位置，
+底座转盘的角度叠加到头部水平转动上（Python端机器人服务没有底座转盘）
+"""
+
+import json
+import math
+import os
+import time
+from dataclasses import dataclass, asdict
+from pathlib import Path
+from typing import Any, Dict, List, Optional, Tuple
+
+from core.config import get_config
+from rust_bindings import get_rust_bindings_manager
+from services.robot_service import robot_service
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+MAX_NAME_LENGTH = 64
+
+
+def _key(name: str) -> str:
+    return name.strip().casefold()
+
+
+@dataclass
+class GazeTarget:
+    """一个命名注视目标，坐标为底座坐标系（米）"""
+    name: str
+    x: float
+    y: float
+    z: float
+    description: str = ""
+    created_at: float = 0.0
+    updated_at: float = 0.0
+    
+    def to_dict(self) -> Dict[str, Any]:
+        return asdict(self)
+
+
+class GazeTargetUnreachable(Exception):
+    """注视目标超出可达范围"""
+    
+    def __init__(self, message: str, details: Dict[str, Any]):
+        super().__init__(message)
+        self.details = details
+
+
+class GazeTargetService:
+    """命名注视目标的保存、查询和看向"""
+    
+    def __init__(self):
+        self.targets: Dict[str, GazeTarget] = {}
+    
+    @property
+    def state_path(self) -> Path:
+        return Path(config.DATA_DIR) / config.gaze_targets.STATE_FILE
+    
+    def load(self):
+        """读取保存的注视目标，格式错误的条目被跳过"""
+        try:
+            with open(self.state_path, encoding="utf-8") as f:
+                state = json.load(f)
+        except FileNotFoundError:
+            return
+        except (OSError, ValueError) as e:
+            logger.warning(f"读取注视目标失败: {e}")
+            return
+        
+        targets = {}
+        for item in state.get("targets", []) if isinstance(state, dict) else []:
+            try:
+                target = GazeTarget(**item)
+                self._validate(target.name, target.x, target.y, target.z)
+            except (TypeError, ValueError) as e:
+                logger.warning(f"跳过格式错误的注视目标 {item!r}: {e}")
+                continue
+            targets[_key(target.name)] = target
+        self.targets = targets
+    
+    def _save(self):
+        path = self.state_path
+        path.parent.mkdir(parents=True, exist_ok=True)
+        tmp = path.with_suffix(".tmp")
+        with open(tmp, "w", encoding="utf-8") as f:
+            json.dump({"targets": [t.to_dict() for t in self.targets.values()]}, f, ensure_ascii=False, indent=2)
+        os.replace(tmp, path)
+    
+    @staticmethod
+    def _validate(name: str, x: float, y: float, z: float):
+        if not name.strip() or len(name.strip()) > MAX_NAME_LENGTH:
+            raise ValueError(f"目标名称不能为空且不超过{MAX_NAME_LENGTH}个字符")
+        if not all(math.isfinite(v) for v in (x, y, z)):
+            raise ValueError("目标坐标必须是有限值")
+        if math.hypot(x, y, z) < 1e-6:
+            raise ValueError("目标不能与底座原点重合")
+    
+    def list_targets(self) -> List[Dict[str, Any]]:
+        return [t.to_dict() for t in sorted(self.targets.values(), key=lambda t: t.name)]
+    
+    def get(self, name: str) -> Optional[GazeTarget]:
+        return self.targets.get(_key(name))
+    
+    def set_target(self, name: str, x: float, y: float, z: float, description: str = "") -> Tuple[Dict[str, Any], bool]:
+        """新建或更新目标，返回(目标, 是否新建)"""
+        self._validate(name, x, y, z)
+        now = time.time()
+        existing = self.get(name)
+        if existing is None and len(self.targets) >= config.gaze_targets.MAX_TARGETS:
+            raise ValueError(f"注视目标已达上限 {config.gaze_targets.MAX_TARGETS} 个")
+        
+        target = GazeTarget(
+            name=name.strip(),
+            x=float(x),
+            y=float(y),
+            z=float(z),
+            description=description.strip(),
+            created_at=existing.created_at if existing else now,
+            updated_at=now,
+        )
+        self.targets[_key(name)] = target
+        self._save()
+        
+        logger.info(f"{'新建' if existing is None else '更新'}注视目标: {target.name} ({x:.3f}, {y:.3f}, {z:.3f})")
+        return target.to_dict(), existing is None
+    
+    def delete(self, name: str) -> bool:
+        target = self.targets.pop(_key(name), None)
+        if target is None:
+            return False
+        self._save()
+        logger.info(f"删除注视目标: {target.name}")
+        return True
+    
+    async def look_at(self, name: str, speed: Optional[float] = None) -> Dict[str, Any]:
+        """看向命名目标；目标不存在时抛出LookupError，不可达时抛出GazeTargetUnreachable"""
+        target = self.get(name)
+        if target is None:
+            raise LookupError(f"注视目标不存在: {name}")
+        
+        result = get_rust_bindings_manager().solve_gaze(target.x, target.y, target.z)
+        if not result["reachable"]:
+            raise GazeTargetUnreachable(result["message"], result["unreachable"])
+        
+        joints = result["targets"]
+        pan = math.degrees(joints.get("head_pan", 0.0) + joints.get("base_yaw", 0.0))
+        tilt = math.degrees(joints.get("head_tilt", 0.0))
+        if not await robot_service.move_head(pan, tilt, speed or config.gaze_targets.LOOK_SPEED):
+            raise RuntimeError("头部运动失败")
+        
+        logger.info(f"看向注视目标: {target.name}")
+        return {"target": target.to_dict(), "joints": joints, "pan": pan, "tilt": tilt}
+
+
+# 全局注视目标服务实例
+gaze_target_service = GazeTargetService()
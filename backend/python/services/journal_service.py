@@ -0,0 +1,259 @@
+#!/usr/bin/env python3
+"""
+事件日志服务
+把重要事件（模式切换、ERROR级别的日志、置信度足够高的检测以及配置中列出的事件类型）
+带时间戳保存到数据库，供前端的活动时间线按类型和时间分页查询。
+
+事件先放入内存缓冲，按固定间隔批量写入；超过最大条数时删除最早的记录
+"""
+
+import asyncio
+import logging
+import threading
+import time
+from datetime import datetime
+from typing import Any, Dict, List, Optional
+
+from core.config import get_config
+from core.database import get_database_manager
+from core.models import JournalEntry
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 时间线分类
+CATEGORY_MODE = "mode"
+CATEGORY_ERROR = "error"
+CATEGORY_DETECTION = "detection"
+CATEGORY_EVENT = "event"
+
+# 数据库不可用时最多保留在内存中的条数
+MAX_PENDING = 1000
+
+
+class JournalLogHandler(logging.Handler):
+    """把ERROR级别的日志交给事件日志"""
+    
+    def __init__(self, journal: "JournalService"):
+        super().__init__(level=logging.ERROR)
+        self.journal = journal
+    
+    def emit(self, record: logging.LogRecord):
+        # 写入失败时记录的错误日志不再进入事件日志，避免循环
+        if record.name == __name__:
+            return
+        try:
+            self.journal.record_error(record.name, record.getMessage())
+        except Exception:
+            self.handleError(record)
+
+
+class JournalService:
+    """事件日志服务"""
+    
+    def __init__(self):
+        self.pending: List[Dict[str, Any]] = []
+        # 日志处理器可能在任意线程中调用
+        self._lock = threading.Lock()
+        # 错误去重：(日志器, 消息) -> 上次记录的时间
+        self._recent_errors: Dict[tuple, float] = {}
+        self.handler = JournalLogHandler(self)
+        self.flush_task = None
+        self.is_running = False
+    
+    @property
+    def settings(self):
+        return config.journal
+    
+    def categorize(self, event_type: str, details: Optional[Dict[str, Any]]) -> Optional[tuple]:
+        """判断事件是否需要记录，返回(分类, 重要程度)，不需要记录时为None"""
+        settings = self.settings
+        if event_type in settings.DETECTION_EVENTS:
+            confidence = (details or {}).get("confidence")
+            if isinstance(confidence, (int, float)) and confidence >= settings.MIN_CONFIDENCE:
+                return CATEGORY_DETECTION, float(confidence)
+            return None
+        if event_type in settings.EVENT_TYPES:
+            return (CATEGORY_MODE if event_type == "mode_changed" else CATEGORY_EVENT), None
+        return None
+    
+    def observe(self, event_type: str, details: Optional[Dict[str, Any]] = None, message: Optional[str] = None):
+        """交互事件发生时调用，重要的事件放入写入缓冲"""
+        if not self.is_running:
+            return
+        result = self.categorize(event_type, details)
+        if result is None:
+            return
+        category, significance = result
+        self._append(event_type, category, significance, message, details)
+    
+    def on_mode_changed(self, previous, target):
+        """运行模式变化回调"""
+        details = {"from": previous.value, "to": target.value}
+        self.observe("mode_changed", details, message=f"{previous.value} -> {target.value}")
+    
+    def record_error(self, source: str, message: str):
+        """记录一条错误，同一来源的相同消息在去重时间内只记录一次"""
+        if not self.is_running or not self.settings.RECORD_ERRORS:
+            return
+        
+        now = time.monotonic()
+        key = (source, message)
+        with self._lock:
+            last = self._recent_errors.get(key)
+            if last is not None and now - last < self.settings.ERROR_DEDUP_SECONDS:
+                return
+            self._recent_errors[key] = now
+            # 清理过期的去重记录
+            if len(self._recent_errors) > 1000:
+                cutoff = now - self.settings.ERROR_DEDUP_SECONDS
+                self._recent_errors = {k: t for k, t in self._recent_errors.items() if t >= cutoff}
+        
+        self._append("error", CATEGORY_ERROR, None, message, {"source": source})
+    
+    def _append(self, event_type: str, category: str, significance: Optional[float],
+                message: Optional[str], details: Optional[Dict[str, Any]]):
+        with self._lock:
+            self.pending.append({
+                "occurred_at": datetime.now(),
+                "event_type": event_type[:50],
+                "category": category,
+                "significance": significance,
+                "message": message[:500] if message else None,
+                "details": details or None,
+            })
+    
+    def _attach_log_handler(self):
+        """setup_logger创建的日志器不向根日志器传播，需要逐个加上处理器"""
+        loggers = [logging.getLogger()] + [
+            item for item in logging.root.manager.loggerDict.values()
+            if isinstance(item, logging.Logger) and not item.propagate
+        ]
+        for item in loggers:
+            if self.handler not in item.handlers:
+                item.addHandler(self.handler)
+    
+    def _detach_log_handler(self):
+        for item in [logging.getLogger()] + list(logging.root.manager.loggerDict.values()):
+            if isinstance(item, logging.Logger) and self.handler in item.handlers:
+                item.removeHandler(self.handler)
+    
+    async def start(self) -> bool:
+        if not self.settings.ENABLED:
+            logger.info("事件日志未启用")
+            return False
+        
+        self.is_running = True
+        if self.settings.RECORD_ERRORS:
+            self._attach_log_handler()
+        self.flush_task = asyncio.create_task(self._flush_loop())
+        return True
+    
+    async def stop(self):
+        """停止记录并写入缓冲中剩余的事件"""
+        self.is_running = False
+        self._detach_log_handler()
+        if self.flush_task and not self.flush_task.done():
+            self.flush_task.cancel()
+            try:
+                await self.flush_task
+            except asyncio.CancelledError:
+                pass
+        await self.flush()
+    
+    async def flush(self):
+        """把缓冲中的事件写入数据库"""
+        with self._lock:
+            entries, self.pending = self.pending, []
+        if not entries:
+            return
+        
+        try:
+            await asyncio.to_thread(self._write, entries)
+        except Exception as e:
+            logger.warning(f"写入事件日志失败: {e}")
+            # 写入失败时保留最近的事件，下次重试
+            with self._lock:
+                self.pending = (entries + self.pending)[-MAX_PENDING:]
+    
+    def _write(self, entries: List[Dict[str, Any]]):
+        with get_database_manager().get_session() as session:
+            session.add_all(JournalEntry(**entry) for entry in entries)
+            session.flush()
+            
+            excess = session.query(JournalEntry).count() - self.settings.MAX_ENTRIES
+            if excess > 0:
+                oldest = [row.id for row in session.query(JournalEntry.id).order_by(JournalEntry.occurred_at).limit(excess)]
+                session.query(JournalEntry).filter(JournalEntry.id.in_(oldest)).delete(synchronize_session=False)
+    
+    async def _flush_loop(self):
+        """定期写入数据库"""
+        while True:
+            try:
+                await asyncio.sleep(self.settings.FLUSH_INTERVAL)
+                await self.flush()
+            except asyncio.CancelledError:
+                break
+            except Exception as e:
+                logger.warning(f"事件日志定期写入出错: {e}")
+    
+    async def query(self, page: int = 1, page_size: int = 50, event_types: Optional[List[str]] = None,
+                    category: Optional[str] = None, since: Optional[datetime] = None,
+                    until: Optional[datetime] = None) -> Dict[str, Any]:
+        """按类型、分类和时间范围分页查询，按发生时间从新到旧"""
+        await self.flush()
+        return await asyncio.to_thread(self._query, page, page_size, event_types, category, since, until)
+    
+    def _query(self, page: int, page_size: int, event_types: Optional[List[str]], category: Optional[str],
+               since: Optional[datetime], until: Optional[datetime]) -> Dict[str, Any]:
+        with get_database_manager().get_session() as session:
+            query = session.query(JournalEntry)
+            if event_types:
+                query = query.filter(JournalEntry.event_type.in_(event_types))
+            if category:
+                query = query.filter(JournalEntry.category == category)
+            if since:
+                query = query.filter(JournalEntry.occurred_at >= since)
+            if until:
+                query = query.filter(JournalEntry.occurred_at < until)
+            
+            total = query.count()
+            entries = query.order_by(JournalEntry.occurred_at.desc(), JournalEntry.id.desc()) \
+                .offset((page - 1) * page_size).limit(page_size).all()
+            
+            return {
+                "items": [
+                    {
+                        "id": entry.id,
+                        "occurred_at": entry.occurred_at.isoformat(),
+                        "event_type": entry.event_type,
+                        "category": entry.category,
+                        "significance": entry.significance,
+                        "message": entry.message,
+                        "details": entry.details,
+                    }
+                    for entry in entries
+                ],
+                "total": total,
+                "page": page,
+                "page_size": page_size,
+                "has_more": page * page_size < total,
+            }
+    
+    async def get_event_types(self) -> List[str]:
+        """已记录过的事件类型，供前端生成过滤选项"""
+        await self.flush()
+        
+        def _types() -> List[str]:
+            with get_database_manager().get_session() as session:
+                rows = session.query(JournalEntry.event_type).distinct().order_by(JournalEntry.event_type)
+                return [row.event_type for row in rows]
+        
+        return await asyncio.to_thread(_types)
+
+
+# 全局事件日志服务实例
+journal_service = JournalService()
@@ -0,0 +1,195 @@
+#!/usr/bin/env python3
+"""
+多语言语音服务
+管理当前语言以及每种语言的TTS音色、STT模型和对话提示词，支持运行时切换；
+启用自动检测时按STT识别结果的文字判断语言，用对应语言的提示词和音色回复，
+当前语言本身不因一句话改变
+
+语言检测只区分文字体系（汉字、拉丁字母等），同一文字体系有多个语言时优先当前语言
+"""
+
+import json
+import os
+import time
+from dataclasses import dataclass, asdict
+from pathlib import Path
+from typing import Callable, Dict, List, Optional, Any
+
+from core.config import get_config
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 语言主标签对应的文字体系
+LANGUAGE_SCRIPTS = {
+    "zh": "han",
+    "ja": "han",
+    "en": "latin",
+    "fr": "latin",
+    "de": "latin",
+    "es": "latin",
+}
+
+
+def _script_of(char: str) -> Optional[str]:
+    code = ord(char)
+    if 0x4E00 <= code <= 0x9FFF or 0x3400 <= code <= 0x4DBF or 0xF900 <= code <= 0xFAFF:
+        return "han"
+    if (char.isascii() and char.isalpha()) or 0x00C0 <= code <= 0x024F:
+        return "latin"
+    return None
+
+
+@dataclass
+class LanguageProfile:
+    """一种语言的语音和对话配置"""
+    code: str
+    name: str
+    tts_voice: str
+    stt_model: str
+    system_prompt: str
+
+
+class LanguageService:
+    """当前语言、语言切换和识别结果的语言路由"""
+    
+    def __init__(self):
+        self.current = config.language.DEFAULT
+        self.auto_detect = config.language.AUTO_DETECT
+        self.listeners: List[Callable[[str, str], None]] = []
+        self.switched_at: Optional[float] = None
+    
+    @property
+    def state_path(self) -> Path:
+        return Path(config.DATA_DIR) / config.language.STATE_FILE
+    
+    @property
+    def profiles(self) -> Dict[str, LanguageProfile]:
+        return {
+            code: LanguageProfile(
+                code=code,
+                name=profile.get("name", code),
+                tts_voice=profile["tts_voice"],
+                stt_model=profile["stt_model"],
+                system_prompt=profile["system_prompt"],
+            )
+            for code, profile in config.language.PROFILES.items()
+        }
+    
+    def get_profile(self, code: Optional[str] = None) -> LanguageProfile:
+        """指定语言的配置，未指定时为当前语言，不支持的语言抛出KeyError"""
+        code = code or self.current
+        profiles = self.profiles
+        if code not in profiles:
+            raise KeyError(f"不支持的语言: {code}")
+        return profiles[code]
+    
+    def load(self):
+        """读取上次选择的语言，配置中已删除的语言回退到默认语言"""
+        try:
+            with open(self.state_path, encoding="utf-8") as f:
+                state = json.load(f)
+        except FileNotFoundError:
+            return
+        except (OSError, ValueError) as e:
+            logger.warning(f"读取语言状态失败: {e}")
+            return
+        
+        if state.get("language") in self.profiles:
+            self.current = state["language"]
+        if isinstance(state.get("auto_detect"), bool):
+            self.auto_detect = state["auto_detect"]
+    
+    def _save_state(self):
+        path = self.state_path
+        tmp = path.with_suffix(".tmp")
+        with open(tmp, "w", encoding="utf-8") as f:
+            json.dump({"language": self.current, "auto_detect": self.auto_detect}, f)
+        os.replace(tmp, path)
+    
+    def add_listener(self, listener: Callable[[str, str], None]):
+        """注册语言切换回调，参数为(原语言, 新语言)"""
+        self.listeners.append(listener)
+    
+    def set_language(self, code: str, auto_detect: Optional[bool] = None) -> Dict[str, Any]:
+        """切换当前语言，同时可开关自动检测"""
+        self.get_profile(code)
+        previous, self.current = self.current, code
+        if auto_detect is not None:
+            self.auto_detect = auto_detect
+        self._save_state()
+        
+        if previous != code:
+            self.switched_at = time.time()
+            logger.info(f"语言切换: {previous} -> {code}")
+            for listener in self.listeners:
+                try:
+                    listener(previous, code)
+                except Exception as e:
+                    logger.warning(f"语言切换回调出错: {e}")
+        return self.get_status()
+    
+    def stt_model(self) -> str:
+        """语音识别应使用的模型：自动检测时用多语言模型，否则用当前语言的模型"""
+        if self.auto_detect:
+            return config.language.AUTO_DETECT_STT_MODEL
+        return self.get_profile().stt_model
+    
+    def detect(self, text: str) -> Optional[str]:
+        """按文字体系检测语言，无法判断时返回None"""
+        counts: Dict[str, int] = {}
+        for char in text:
+            script = _script_of(char)
+            if script:
+                counts[script] = counts.get(script, 0) + 1
+        if sum(counts.values()) < config.language.DETECTION_MIN_CHARS:
+            return None
+        
+        # 一个汉字大致相当于3个字母，中文句子里又常夹着英文名词，所以汉字按4个字母计
+        weights = {script: count * (4 if script == "han" else 1) for script, count in counts.items()}
+        script = max(weights, key=weights.get)
+        
+        candidates = [code for code in self.profiles if LANGUAGE_SCRIPTS.get(code.split("-")[0].lower()) == script]
+        if not candidates:
+            return None
+        return self.current if self.current in candidates else candidates[0]
+    
+    def route_transcript(self, text: str, language: Optional[str] = None,
+                         preferred: Optional[str] = None) -> Dict[str, Any]:
+        """为一条识别结果选择回复所用的语言、提示词和音色
+        
+        language为STT模型自带的语言判断，优先于文字检测；preferred为说话人的偏好语言，
+        无法检测时代替当前语言
+        """
+        detected = None
+        if self.auto_detect:
+            if language in self.profiles:
+                detected = language
+            else:
+                detected = self.detect(text)
+        fallback = preferred if preferred in self.profiles else self.current
+        profile = self.get_profile(detected or fallback)
+        
+        return {
+            "text": text,
+            "language": profile.code,
+            "detected": detected,
+            "system_prompt": profile.system_prompt,
+            "tts_voice": profile.tts_voice,
+        }
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "language": self.current,
+            "auto_detect": self.auto_detect,
+            "stt_model": self.stt_model(),
+            "profiles": [asdict(profile) for profile in self.profiles.values()],
+            "switched_at": self.switched_at,
+        }
+
+
+# 全局多语言语音服务实例
+language_service = LanguageService()
@@ -0,0 +1,246 @@
+#!/usr/bin/env python3
+"""
+行为包市场客户端
+从配置的索引地址获取社区分享的行为包列表，展示元数据、签名状态和所需权限；
+安装前校验下载内容的SHA-256和发布者的Ed25519签名，并要求用户确认包需要的权限
+
+索引格式：
+    {"packs": [{"id", "name", "version", "author", "description", "download_url",
+                "size", "sha256", "permissions": [...], "signature": {"key_id", "value"}}]}
+签名覆盖 id、version、size、sha256 和 permissions，见 signed_message()
+"""
+
+import asyncio
+import base64
+import hashlib
+import json
+import os
+import tempfile
+import time
+from typing import Dict, List, Optional, Any
+from urllib.parse import urljoin
+
+import httpx
+from cryptography.exceptions import InvalidSignature
+from cryptography.hazmat.primitives.asymmetric.ed25519 import Ed25519PublicKey
+
+from core.config import get_config
+from rust_bindings import get_rust_bindings_manager
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+PERMISSIONS = {"motion", "audio", "leds", "state", "triggers", "events"}
+
+
+class SignatureError(Exception):
+    """包没有受信任的有效签名"""
+
+
+class PermissionsRequired(Exception):
+    """安装前需要用户确认的权限"""
+    
+    def __init__(self, permissions: List[str]):
+        super().__init__(f"安装前需要确认权限: {', '.join(permissions)}")
+        self.permissions = permissions
+
+
+def signed_message(entry: Dict[str, Any]) -> bytes:
+    """签名覆盖的内容：规范化的JSON，键排序且无空白"""
+    message = {
+        "id": entry["id"],
+        "version": entry["version"],
+        "size": entry["size"],
+        "sha256": entry["sha256"].lower(),
+        "permissions": sorted(entry.get("permissions", [])),
+    }
+    return json.dumps(message, sort_keys=True, separators=(",", ":")).encode()
+
+
+class MarketplaceService:
+    """行为包市场客户端"""
+    
+    def __init__(self):
+        self.index: List[Dict[str, Any]] = []
+        self.index_fetched_at: Optional[float] = None
+        self.last_error: Optional[str] = None
+        self.install_lock = asyncio.Lock()
+    
+    def _check_url(self, url: str):
+        if not url.startswith("https://") and not config.marketplace.ALLOW_INSECURE:
+            raise ValueError(f"行为包市场地址必须使用https://: {url}")
+    
+    def _validate_entry(self, entry: Dict[str, Any]):
+        for key in ("id", "name", "version", "download_url", "size", "sha256"):
+            if key not in entry:
+                raise ValueError(f"索引条目缺少 {key}")
+        unknown = set(entry.get("permissions", [])) - PERMISSIONS
+        if unknown:
+            raise ValueError(f"索引条目 {entry['id']} 声明了未知权限: {', '.join(sorted(unknown))}")
+    
+    def verify_signature(self, entry: Dict[str, Any]) -> str:
+        """签名状态：valid、invalid、untrusted_key 或 unsigned"""
+        signature = entry.get("signature")
+        if not signature:
+            return "unsigned"
+        
+        public_key = config.marketplace.TRUSTED_KEYS.get(signature.get("key_id", ""))
+        if not public_key:
+            return "untrusted_key"
+        
+        try:
+            key = Ed25519PublicKey.from_public_bytes(base64.b64decode(public_key))
+            key.verify(base64.b64decode(signature.get("value", "")), signed_message(entry))
+            return "valid"
+        except (InvalidSignature, ValueError):
+            return "invalid"
+    
+    async def fetch_index(self, refresh: bool = False) -> List[Dict[str, Any]]:
+        """获取索引，在缓存时间内直接返回缓存"""
+        marketplace = config.marketplace
+        if not marketplace.INDEX_URL:
+            raise RuntimeError("未配置行为包索引地址")
+        
+        if (not refresh and self.index_fetched_at is not None
+                and time.time() - self.index_fetched_at < marketplace.CACHE_TTL):
+            return self.index
+        
+        self._check_url(marketplace.INDEX_URL)
+        try:
+            async with httpx.AsyncClient(timeout=marketplace.REQUEST_TIMEOUT) as client:
+                response = await client.get(marketplace.INDEX_URL)
+                response.raise_for_status()
+                packs = response.json().get("packs", [])
+        except (httpx.HTTPError, ValueError) as e:
+            self.last_error = str(e)
+            raise RuntimeError(f"获取行为包索引失败: {e}")
+        
+        index = []
+        for entry in packs:
+            try:
+                self._validate_entry(entry)
+            except (ValueError, TypeError, AttributeError) as e:
+                logger.warning(f"跳过无效的索引条目: {e}")
+                continue
+            entry["download_url"] = urljoin(marketplace.INDEX_URL, entry["download_url"])
+            index.append(entry)
+        
+        self.index = index
+        self.index_fetched_at = time.time()
+        self.last_error = None
+        return index
+    
+    def _installed_versions(self) -> Dict[str, str]:
+        try:
+            return {pack["id"]: pack["version"] for pack in get_rust_bindings_manager().list_behavior_packs()}
+        except RuntimeError:
+            return {}
+    
+    def _describe(self, entry: Dict[str, Any], installed: Dict[str, str]) -> Dict[str, Any]:
+        signature = self.verify_signature(entry)
+        return {
+            "id": entry["id"],
+            "name": entry["name"],
+            "version": entry["version"],
+            "author": entry.get("author", ""),
+            "description": entry.get("description", ""),
+            "size": entry["size"],
+            "sha256": entry["sha256"],
+            "permissions": sorted(entry.get("permissions", [])),
+            "signature": signature,
+            "key_id": (entry.get("signature") or {}).get("key_id"),
+            "installable": signature == "valid" or not config.marketplace.REQUIRE_SIGNATURE,
+            "installed_version": installed.get(entry["id"]),
+        }
+    
+    async def list_packs(self, refresh: bool = False) -> List[Dict[str, Any]]:
+        """索引中的行为包，附带签名状态和已安装的版本"""
+        index = await self.fetch_index(refresh)
+        installed = self._installed_versions()
+        return [self._describe(entry, installed) for entry in index]
+    
+    async def _entry(self, pack_id: str) -> Dict[str, Any]:
+        for entry in await self.fetch_index():
+            if entry["id"] == pack_id:
+                return entry
+        raise KeyError(f"索引中没有行为包: {pack_id}")
+    
+    async def get_pack(self, pack_id: str) -> Dict[str, Any]:
+        return self._describe(await self._entry(pack_id), self._installed_versions())
+    
+    async def _download(self, entry: Dict[str, Any], path: str):
+        """下载到指定文件，限制大小并校验SHA-256"""
+        max_size = config.marketplace.MAX_DOWNLOAD_SIZE_MB * 1024 * 1024
+        if entry["size"] > max_size:
+            raise ValueError(f"行为包过大（{entry['size']} 字节）")
+        
+        self._check_url(entry["download_url"])
+        digest = hashlib.sha256()
+        received = 0
+        async with httpx.AsyncClient(timeout=config.marketplace.REQUEST_TIMEOUT, follow_redirects=True) as client:
+            async with client.stream("GET", entry["download_url"]) as response:
+                response.raise_for_status()
+                with open(path, "wb") as f:
+                    async for chunk in response.aiter_bytes():
+                        received += len(chunk)
+                        if received > entry["size"]:
+                            raise ValueError("下载内容超过索引中声明的大小")
+                        digest.update(chunk)
+                        f.write(chunk)
+        
+        if received != entry["size"] or digest.hexdigest() != entry["sha256"].lower():
+            raise ValueError("下载内容与索引中的大小或SHA-256不一致")
+    
+    async def install_pack(self, pack_id: str, accepted_permissions: List[str]) -> Dict[str, Any]:
+        """下载、校验并安装行为包；包需要的权限必须全部包含在accepted_permissions中"""
+        entry = await self._entry(pack_id)
+        
+        signature = self.verify_signature(entry)
+        if signature != "valid" and config.marketplace.REQUIRE_SIGNATURE:
+            raise SignatureError(f"行为包 {pack_id} 的签名状态为 {signature}，不允许安装")
+        
+        declared = set(entry.get("permissions", []))
+        if not declared <= set(accepted_permissions):
+            raise PermissionsRequired(sorted(declared))
+        
+        manager = get_rust_bindings_manager()
+        async with self.install_lock:
+            fd, path = tempfile.mkstemp(suffix=".zip")
+            os.close(fd)
+            try:
+                try:
+                    await self._download(entry, path)
+                except httpx.HTTPError as e:
+                    raise RuntimeError(f"下载行为包失败: {e}")
+                
+                # 签名只担保索引中的声明，包内容实际用到的权限不能超出声明
+                info = await asyncio.to_thread(manager.inspect_behavior_pack, path)
+                if info["id"] != entry["id"] or info["version"] != entry["version"]:
+                    raise ValueError("包内清单的ID或版本与索引不一致")
+                undeclared = set(info["permissions"]) - declared
+                if undeclared:
+                    raise ValueError(f"包需要未声明的权限: {', '.join(sorted(undeclared))}")
+                
+                info = await asyncio.to_thread(manager.install_behavior_pack, path)
+            finally:
+                os.unlink(path)
+        
+        logger.info(f"已从市场安装行为包 {pack_id} {entry['version']}（签名: {signature}）")
+        return info
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "index_url": config.marketplace.INDEX_URL,
+            "require_signature": config.marketplace.REQUIRE_SIGNATURE,
+            "trusted_keys": sorted(config.marketplace.TRUSTED_KEYS),
+            "cached_packs": len(self.index),
+            "index_fetched_at": self.index_fetched_at,
+            "last_error": self.last_error,
+        }
+
+
+# 全局行为包市场实例
+marketplace_service = MarketplaceService()
@@ -0,0 +1,194 @@
+#!/usr/bin/env python3
+"""
+对话记忆服务
+按人脸识别得到的身份保存对话摘要和用户偏好（称呼、语言等），
+下次认出同一个人时把这些内容提供给对话模型；隐私模式下不记录新的内容
+
+记忆可以通过API查看和删除，长期没有见到的人的记忆由数据保留服务清理
+"""
+
+import asyncio
+import time
+from datetime import datetime
+from typing import Dict, List, Optional, Any
+
+from core.config import get_config
+from core.database import get_database_manager
+from core.models import ConversationMemory
+from services.privacy_service import privacy_service
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+MAX_IDENTITY_LENGTH = 100
+MAX_NAME_LENGTH = 100
+
+
+def _validate_identity(identity: str):
+    if not identity or len(identity) > MAX_IDENTITY_LENGTH:
+        raise ValueError(f"身份标识长度必须在1-{MAX_IDENTITY_LENGTH}个字符之间")
+
+
+def _to_dict(record: ConversationMemory) -> Dict[str, Any]:
+    return {
+        "identity": record.identity,
+        "display_name": record.display_name,
+        "preferred_language": record.preferred_language,
+        "preferences": dict(record.preferences or {}),
+        "summaries": list(record.summaries or []),
+        "interaction_count": record.interaction_count,
+        "last_seen_at": record.last_seen_at.isoformat() if record.last_seen_at else None,
+        "created_at": record.created_at.isoformat() if record.created_at else None,
+    }
+
+
+class MemoryService:
+    """对话记忆服务"""
+    
+    def _check_recording(self) -> bool:
+        if not config.memory.ENABLED:
+            return False
+        if not privacy_service.is_capture_allowed():
+            logger.debug("隐私模式已开启，不记录对话记忆")
+            return False
+        return True
+    
+    def _get_or_create(self, session, identity: str) -> ConversationMemory:
+        record = session.query(ConversationMemory).filter(ConversationMemory.identity == identity).first()
+        if record is None:
+            record = ConversationMemory(identity=identity, preferences={}, summaries=[], interaction_count=0)
+            session.add(record)
+        return record
+    
+    def _add_summary(self, identity: str, summary: str, language: Optional[str]) -> Dict[str, Any]:
+        memory = config.memory
+        with get_database_manager().get_session() as session:
+            record = self._get_or_create(session, identity)
+            summaries = list(record.summaries or [])
+            summaries.append({
+                "text": summary[:memory.MAX_SUMMARY_LENGTH],
+                "language": language,
+                "timestamp": time.time(),
+            })
+            # JSON列需要赋新对象才会被标记为已修改
+            record.summaries = summaries[-memory.MAX_SUMMARIES:]
+            record.interaction_count = (record.interaction_count or 0) + 1
+            record.last_seen_at = datetime.now()
+            session.flush()
+            return _to_dict(record)
+    
+    async def add_summary(self, identity: str, summary: str, language: Optional[str] = None) -> Optional[Dict[str, Any]]:
+        """保存一次对话的摘要，未启用或隐私模式下返回None"""
+        _validate_identity(identity)
+        if not summary.strip():
+            raise ValueError("摘要不能为空")
+        if not self._check_recording():
+            return None
+        return await asyncio.to_thread(self._add_summary, identity, summary.strip(), language)
+    
+    def _set_preferences(self, identity: str, display_name: Optional[str], preferred_language: Optional[str],
+                         preferences: Optional[Dict[str, Any]]) -> Dict[str, Any]:
+        with get_database_manager().get_session() as session:
+            record = self._get_or_create(session, identity)
+            if display_name is not None:
+                record.display_name = display_name or None
+            if preferred_language is not None:
+                record.preferred_language = preferred_language or None
+            if preferences is not None:
+                # 值为None的键表示删除
+                merged = dict(record.preferences or {})
+                merged.update(preferences)
+                record.preferences = {key: value for key, value in merged.items() if value is not None}
+            record.last_seen_at = datetime.now()
+            session.flush()
+            return _to_dict(record)
+    
+    async def set_preferences(self, identity: str, display_name: Optional[str] = None,
+                              preferred_language: Optional[str] = None,
+                              preferences: Optional[Dict[str, Any]] = None) -> Optional[Dict[str, Any]]:
+        """更新用户偏好，未给出的字段保持不变，空字符串表示清除"""
+        _validate_identity(identity)
+        if display_name is not None and len(display_name) > MAX_NAME_LENGTH:
+            raise ValueError(f"称呼不能超过{MAX_NAME_LENGTH}个字符")
+        if preferred_language and preferred_language not in config.language.PROFILES:
+            raise ValueError(f"不支持的语言: {preferred_language}")
+        if not self._check_recording():
+            return None
+        return await asyncio.to_thread(
+            self._set_preferences, identity, display_name, preferred_language, preferences
+        )
+    
+    def _get(self, identity: str) -> Optional[Dict[str, Any]]:
+        with get_database_manager().get_session() as session:
+            record = session.query(ConversationMemory).filter(ConversationMemory.identity == identity).first()
+            return _to_dict(record) if record else None
+    
+    async def get_memory(self, identity: str) -> Dict[str, Any]:
+        """查看一个人的记忆，不存在时抛出KeyError"""
+        memory = await asyncio.to_thread(self._get, identity)
+        if memory is None:
+            raise KeyError(f"没有 {identity} 的对话记忆")
+        return memory
+    
+    def _list(self) -> List[Dict[str, Any]]:
+        with get_database_manager().get_session() as session:
+            records = session.query(ConversationMemory).order_by(ConversationMemory.last_seen_at.desc()).all()
+            return [
+                {
+                    "identity": record.identity,
+                    "display_name": record.display_name,
+                    "preferred_language": record.preferred_language,
+                    "summary_count": len(record.summaries or []),
+                    "interaction_count": record.interaction_count,
+                    "last_seen_at": record.last_seen_at.isoformat() if record.last_seen_at else None,
+                }
+                for record in records
+            ]
+    
+    async def list_memories(self) -> List[Dict[str, Any]]:
+        """所有人的记忆概要，最近见到的在前"""
+        return await asyncio.to_thread(self._list)
+    
+    def _erase(self, identity: Optional[str]) -> int:
+        with get_database_manager().get_session() as session:
+            query = session.query(ConversationMemory)
+            if identity is not None:
+                query = query.filter(ConversationMemory.identity == identity)
+            return query.delete(synchronize_session=False)
+    
+    async def erase(self, identity: str) -> int:
+        """删除一个人的全部记忆，不存在时抛出KeyError"""
+        deleted = await asyncio.to_thread(self._erase, identity)
+        if not deleted:
+            raise KeyError(f"没有 {identity} 的对话记忆")
+        logger.info(f"已删除 {identity} 的对话记忆")
+        return deleted
+    
+    async def erase_all(self) -> int:
+        """删除所有人的记忆"""
+        deleted = await asyncio.to_thread(self._erase, None)
+        logger.warning(f"已删除全部对话记忆（{deleted} 人）")
+        return deleted
+    
+    async def get_context(self, identity: str) -> Optional[Dict[str, Any]]:
+        """提供给对话模型的上下文：称呼、偏好语言和最近几次对话的摘要"""
+        if not config.memory.ENABLED:
+            return None
+        memory = await asyncio.to_thread(self._get, identity)
+        if memory is None:
+            return None
+        
+        count = config.memory.CONTEXT_SUMMARIES
+        return {
+            "display_name": memory["display_name"],
+            "preferred_language": memory["preferred_language"],
+            "preferences": memory["preferences"],
+            "recent_summaries": [item["text"] for item in memory["summaries"][-count:]] if count > 0 else [],
+        }
+
+
+# 全局对话记忆服务实例
+memory_service = MemoryService()
@@ -0,0 +1,281 @@
+#!/usr/bin/env python3
+"""
+状态历史服务
+定期采样关键指标（控制循环频率、温度、帧率、错误数等），按固定长度的桶降采样，
+每桶保存均值、最小值和最大值，保留最近N小时（可选写入磁盘）。查询时按时间范围和
+分辨率合并桶，前端可以直接绘制趋势图
+"""
+
+import asyncio
+import json
+import logging
+import math
+import time
+from collections import deque
+from pathlib import Path
+from typing import Any, Callable, Deque, Dict, List, Optional
+
+from core.config import get_config
+from services.fleet_service import ErrorCounter
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+
+class MetricBucket:
+    """一个降采样桶：各指标的样本数、总和、最小值和最大值"""
+    
+    def __init__(self, start: float):
+        self.start = start
+        self.values: Dict[str, List[float]] = {}
+    
+    def add(self, name: str, value: float):
+        entry = self.values.get(name)
+        if entry is None:
+            self.values[name] = [1, value, value, value]
+        else:
+            entry[0] += 1
+            entry[1] += value
+            entry[2] = min(entry[2], value)
+            entry[3] = max(entry[3], value)
+    
+    def merge(self, other: "MetricBucket"):
+        for name, (count, total, low, high) in other.values.items():
+            entry = self.values.get(name)
+            if entry is None:
+                self.values[name] = [count, total, low, high]
+            else:
+                entry[0] += count
+                entry[1] += total
+                entry[2] = min(entry[2], low)
+                entry[3] = max(entry[3], high)
+    
+    def to_dict(self) -> Dict[str, Any]:
+        return {"t": self.start, "m": self.values}
+    
+    @classmethod
+    def from_dict(cls, data: Dict[str, Any]) -> "MetricBucket":
+        bucket = cls(float(data["t"]))
+        bucket.values = {name: list(entry) for name, entry in data["m"].items()}
+        return bucket
+
+
+class MetricsHistoryService:
+    """状态历史服务"""
+    
+    def __init__(self):
+        settings = config.metrics_history
+        self.enabled = settings.ENABLED
+        self.sample_interval = settings.SAMPLE_INTERVAL
+        self.bucket_seconds = settings.BUCKET_SECONDS
+        self.retention = settings.RETENTION_HOURS * 3600
+        self.persist = settings.PERSIST
+        self.file = Path(settings.FILE)
+        self.max_points = settings.MAX_POINTS
+        
+        self.buckets: Deque[MetricBucket] = deque(maxlen=max(1, math.ceil(self.retention / self.bucket_seconds)))
+        self.current: Optional[MetricBucket] = None
+        
+        # 指标名 -> (单位, 读取函数)，读取函数返回None表示当前没有数据
+        self.metrics: Dict[str, tuple] = {}
+        # 最近一次采样的值，以及每次采样后调用的监听函数（如告警规则）
+        self.latest: Dict[str, float] = {}
+        self.listeners: List[Callable[[float, Dict[str, float]], None]] = []
+        self.error_counter = ErrorCounter()
+        self._last_errors = 0
+        self.register_metric("errors", "次", self._read_errors)
+        
+        self.sample_task: Optional[asyncio.Task] = None
+        self._persisted_lines = 0
+    
+    def register_metric(self, name: str, unit: str, reader: Callable[[], Optional[float]]):
+        """注册一个指标，读取失败或返回None时该次不采样"""
+        self.metrics[name] = (unit, reader)
+    
+    def _read_errors(self) -> float:
+        """上次采样以来ERROR及以上级别的日志数"""
+        total = self.error_counter.errors + self.error_counter.critical
+        delta = total - self._last_errors
+        self._last_errors = total
+        return float(delta)
+    
+    def sample(self, now: Optional[float] = None):
+        """读取所有指标写入当前桶，到了新桶时结束上一个桶"""
+        now = time.time() if now is None else now
+        start = now - now % self.bucket_seconds
+        if self.current is not None and self.current.start != start:
+            self._finish_bucket()
+        if self.current is None:
+            self.current = MetricBucket(start)
+        
+        latest = {}
+        for name, (_, reader) in self.metrics.items():
+            try:
+                value = reader()
+            except Exception as e:
+                logger.debug(f"读取指标 {name} 失败: {e}")
+                continue
+            if value is not None and math.isfinite(value):
+                latest[name] = float(value)
+                self.current.add(name, float(value))
+        self.latest = latest
+        
+        for listener in self.listeners:
+            try:
+                listener(now, latest)
+            except Exception as e:
+                logger.error(f"状态历史监听函数出错: {e}")
+    
+    def _finish_bucket(self):
+        bucket, self.current = self.current, None
+        if not bucket.values:
+            return
+        
+        self.buckets.append(bucket)
+        if self.persist:
+            self._append_to_disk(bucket)
+    
+    def _append_to_disk(self, bucket: MetricBucket):
+        try:
+            self.file.parent.mkdir(parents=True, exist_ok=True)
+            with open(self.file, "a", encoding="utf-8") as f:
+                f.write(json.dumps(bucket.to_dict()) + "\n")
+            self._persisted_lines += 1
+            
+            # 过期的桶积累到保留量的一倍时重写文件
+            if self._persisted_lines > 2 * self.buckets.maxlen:
+                self._rewrite_file()
+        except OSError as e:
+            logger.warning(f"写入状态历史文件失败: {e}")
+    
+    def _rewrite_file(self):
+        tmp = self.file.with_suffix(".tmp")
+        with open(tmp, "w", encoding="utf-8") as f:
+            for bucket in self.buckets:
+                f.write(json.dumps(bucket.to_dict()) + "\n")
+        tmp.replace(self.file)
+        self._persisted_lines = len(self.buckets)
+    
+    def _load_from_disk(self):
+        """加载保留期内的历史并压缩文件"""
+        if not self.file.exists():
+            return
+        
+        cutoff = time.time() - self.retention
+        try:
+            with open(self.file, encoding="utf-8") as f:
+                for line in f:
+                    try:
+                        bucket = MetricBucket.from_dict(json.loads(line))
+                    except (ValueError, KeyError, TypeError):
+                        continue
+                    if bucket.start >= cutoff:
+                        self.buckets.append(bucket)
+            self._rewrite_file()
+            logger.info(f"从 {self.file} 加载了 {len(self.buckets)} 个历史桶")
+        except OSError as e:
+            logger.warning(f"读取状态历史文件失败: {e}")
+    
+    def query(self, names: Optional[List[str]] = None, start: Optional[float] = None,
+              end: Optional[float] = None, resolution: Optional[float] = None) -> Dict[str, Any]:
+        """查询时间范围内的历史（Unix时间，秒），按分辨率合并桶
+        
+        未指定范围时返回最近一小时；分辨率（秒）向上取整到桶长度的整数倍，
+        每个指标的点数超过MAX_POINTS时自动降低分辨率。
+        """
+        end = time.time() if end is None else end
+        start = end - 3600 if start is None else start
+        if start >= end:
+            raise ValueError("开始时间必须早于结束时间")
+        
+        names = list(self.metrics) if not names else names
+        unknown = [name for name in names if name not in self.metrics]
+        if unknown:
+            raise ValueError(f"未知的指标: {', '.join(unknown)}")
+        
+        if resolution is not None and resolution <= 0:
+            raise ValueError("分辨率必须为正数")
+        resolution = max(resolution or 0.0, (end - start) / self.max_points)
+        resolution = max(1, math.ceil(resolution / self.bucket_seconds)) * self.bucket_seconds
+        
+        # 当前未结束的桶也参与查询，图表最右端是最新数据
+        buckets = list(self.buckets) + ([self.current] if self.current else [])
+        merged: Dict[float, MetricBucket] = {}
+        for bucket in buckets:
+            if not start <= bucket.start < end:
+                continue
+            key = bucket.start - bucket.start % resolution
+            merged.setdefault(key, MetricBucket(key)).merge(bucket)
+        
+        series: Dict[str, List[Dict[str, float]]] = {name: [] for name in names}
+        for key in sorted(merged):
+            for name in names:
+                entry = merged[key].values.get(name)
+                if entry is None:
+                    continue
+                count, total, low, high = entry
+                series[name].append({"t": key, "mean": total / count, "min": low, "max": high})
+        
+        return {
+            "start": start,
+            "end": end,
+            "resolution": resolution,
+            "units": {name: self.metrics[name][0] for name in names},
+            "series": series,
+        }
+    
+    def list_metrics(self) -> Dict[str, str]:
+        """可查询的指标及单位"""
+        return {name: unit for name, (unit, _) in self.metrics.items()}
+    
+    async def _sample_loop(self):
+        while True:
+            self.sample()
+            await asyncio.sleep(self.sample_interval)
+    
+    async def start(self) -> bool:
+        """开始采样"""
+        if not self.enabled:
+            logger.info("状态历史未启用")
+            return False
+        
+        if self.persist:
+            self._load_from_disk()
+        
+        # 与fleet_service相同：根日志器和不向上传播的日志器都要挂上计数器
+        loggers = [logging.getLogger()] + [
+            item for item in logging.root.manager.loggerDict.values()
+            if isinstance(item, logging.Logger) and not item.propagate
+        ]
+        for item in loggers:
+            if self.error_counter not in item.handlers:
+                item.addHandler(self.error_counter)
+        
+        self.sample_task = asyncio.create_task(self._sample_loop())
+        logger.info(f"状态历史已启动，指标: {', '.join(self.metrics)}")
+        return True
+    
+    async def stop(self):
+        """停止采样，结束当前桶"""
+        if self.sample_task and not self.sample_task.done():
+            self.sample_task.cancel()
+            try:
+                await self.sample_task
+            except asyncio.CancelledError:
+                pass
+        self.sample_task = None
+        
+        for item in [logging.getLogger()] + list(logging.root.manager.loggerDict.values()):
+            if isinstance(item, logging.Logger):
+                item.removeHandler(self.error_counter)
+        
+        if self.current is not None:
+            self._finish_bucket()
+        logger.info("状态历史已停止")
+
+
+# 全局状态历史服务实例
+metrics_history_service = MetricsHistoryService()
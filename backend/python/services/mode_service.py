@@ -0,0 +1,185 @@
+#!/usr/bin/env python3
+"""
+运行模式状态机
+启动时根据设置状态进入设置模式或正常模式：未完成首次设置时开启设置热点，
+用户连接热点后在设置页面（/setup，由同一个API服务提供）填写Wi-Fi、机器人名称和管理员密码，
+保存后加入Wi-Fi并切换到正常模式
+
+模式转换：
+    boot -> setup -> normal
+    boot -> normal
+"""
+
+import asyncio
+import json
+import os
+import time
+from pathlib import Path
+from typing import Callable, Dict, List, Optional, Any
+
+from passlib.context import CryptContext
+
+from core.config import get_config
+from core.database import get_database_manager
+from core.identity import get_identity
+from core.models import OperatingMode, User, UserRole
+from services.network_service import network_service, validate_credentials
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+TRANSITIONS = {
+    OperatingMode.BOOT: {OperatingMode.SETUP, OperatingMode.NORMAL},
+    OperatingMode.SETUP: {OperatingMode.NORMAL},
+    OperatingMode.NORMAL: set(),
+}
+
+MAX_ROBOT_NAME_LENGTH = 32
+
+pwd_context = CryptContext(schemes=["bcrypt"], deprecated="auto")
+
+
+class ModeService:
+    """运行模式状态机和首次设置流程"""
+    
+    def __init__(self):
+        self.mode = OperatingMode.BOOT
+        self.state: Dict[str, Any] = {}
+        self.history: List[Dict[str, Any]] = []
+        self.listeners: List[Callable[[OperatingMode, OperatingMode], None]] = []
+        self.setup_lock = asyncio.Lock()
+        self.last_error: Optional[str] = None
+    
+    @property
+    def state_path(self) -> Path:
+        return Path(config.DATA_DIR) / config.setup.STATE_FILE
+    
+    @property
+    def robot_name(self) -> str:
+        return self.state.get("robot_name") or config.setup.DEFAULT_ROBOT_NAME
+    
+    @property
+    def is_configured(self) -> bool:
+        return bool(self.state.get("completed"))
+    
+    def _load_state(self):
+        try:
+            with open(self.state_path, encoding="utf-8") as f:
+                self.state = json.load(f)
+        except FileNotFoundError:
+            self.state = {}
+    
+    def _save_state(self):
+        path = self.state_path
+        tmp = path.with_suffix(".tmp")
+        with open(tmp, "w", encoding="utf-8") as f:
+            json.dump(self.state, f, ensure_ascii=False)
+        os.replace(tmp, path)
+    
+    def add_listener(self, listener: Callable[[OperatingMode, OperatingMode], None]):
+        """注册模式变化回调，参数为(原模式, 新模式)"""
+        self.listeners.append(listener)
+    
+    def transition(self, target: OperatingMode, reason: str = ""):
+        """切换运行模式，不允许的转换抛出RuntimeError"""
+        if target not in TRANSITIONS[self.mode]:
+            raise RuntimeError(f"不允许从 {self.mode.value} 模式切换到 {target.value} 模式")
+        
+        previous, self.mode = self.mode, target
+        self.history.append({"from": previous.value, "to": target.value, "reason": reason, "timestamp": time.time()})
+        logger.info(f"运行模式: {previous.value} -> {target.value}（{reason}）")
+        
+        for listener in self.listeners:
+            try:
+                listener(previous, target)
+            except Exception as e:
+                logger.warning(f"模式变化回调出错: {e}")
+    
+    async def start(self) -> bool:
+        """读取设置状态并进入设置模式或正常模式"""
+        try:
+            self._load_state()
+            if self.is_configured or not config.setup.ENABLED:
+                self.transition(OperatingMode.NORMAL, "已完成设置" if self.is_configured else "未启用首次设置")
+                return True
+            
+            self.transition(OperatingMode.SETUP, "未完成首次设置")
+            # 没有Wi-Fi管理时仍可以通过有线网络访问设置页面
+            if config.network.ENABLED:
+                await network_service.start_ap()
+            return True
+            
+        except Exception as e:
+            self.last_error = str(e)
+            logger.error(f"确定运行模式失败: {e}")
+            return False
+    
+    def _validate(self, robot_name: str, admin_password: str, ssid: Optional[str], wifi_password: str):
+        if not robot_name.strip() or len(robot_name) > MAX_ROBOT_NAME_LENGTH:
+            raise ValueError(f"机器人名称长度必须在1-{MAX_ROBOT_NAME_LENGTH}个字符之间")
+        if len(admin_password) < config.setup.MIN_PASSWORD_LENGTH:
+            raise ValueError(f"管理员密码至少需要{config.setup.MIN_PASSWORD_LENGTH}个字符")
+        if ssid:
+            if not config.network.ENABLED:
+                raise ValueError("未启用Wi-Fi网络管理，不能设置Wi-Fi")
+            validate_credentials(ssid, wifi_password)
+    
+    def _set_admin_password(self, password: str):
+        """创建管理员账户，已存在时更新密码"""
+        setup = config.setup
+        hashed_password = pwd_context.hash(password)
+        with get_database_manager().get_session() as session:
+            user = session.query(User).filter(User.username == setup.ADMIN_USERNAME).first()
+            if user is None:
+                user = User(username=setup.ADMIN_USERNAME, email=setup.ADMIN_EMAIL, is_verified=True)
+                session.add(user)
+            user.hashed_password = hashed_password
+            user.role = UserRole.ADMIN.value
+            user.is_active = True
+    
+    async def complete_setup(self, robot_name: str, admin_password: str,
+                             ssid: Optional[str] = None, wifi_password: str = "") -> Dict[str, Any]:
+        """保存首次设置并切换到正常模式
+        
+        指定Wi-Fi时加入请求在后台执行（先关闭热点），连接失败由网络管理重新开启热点；
+        不指定时直接关闭热点
+        """
+        self._validate(robot_name, admin_password, ssid, wifi_password)
+        
+        async with self.setup_lock:
+            if self.mode != OperatingMode.SETUP:
+                raise RuntimeError("当前不在设置模式")
+            
+            await asyncio.to_thread(self._set_admin_password, admin_password)
+            
+            # 不在热点模式时直接连接，失败则保持设置模式，用户可以重新提交
+            join = None
+            if ssid:
+                join = await network_service.join(ssid, wifi_password)
+            elif config.network.ENABLED:
+                await network_service.stop_ap()
+            
+            self.state = {"completed": True, "robot_name": robot_name.strip(), "completed_at": time.time()}
+            self._save_state()
+            self.transition(OperatingMode.NORMAL, "首次设置完成")
+        
+        get_identity.cache_clear()
+        logger.info(f"首次设置完成，机器人名称: {self.robot_name}")
+        return {**self.get_status(), "wifi": join}
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "mode": self.mode.value,
+            "configured": self.is_configured,
+            "robot_name": self.robot_name,
+            "wifi_enabled": config.network.ENABLED,
+            "history": self.history[-10:],
+            "last_error": self.last_error,
+        }
+
+
+# 全局运行模式实例
+mode_service = ModeService()
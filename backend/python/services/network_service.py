@@ -0,0 +1,555 @@
+#!/usr/bin/env python3
+"""
+Wi-Fi网络管理服务
+通过NetworkManager（nmcli）或wpa_supplicant（wpa_cli）扫描和加入无线网络、查询连接状态；
+没有配置任何网络或断开超过设定时间时开启设置热点，前端连接热点后完成首次联网设置
+
+只有一块无线网卡时热点和客户端连接不能同时存在：从热点发起的加入请求在后台执行，
+先关闭热点再连接，失败时重新开启热点，结果通过状态接口的last_join查询
+"""
+
+import asyncio
+import shutil
+import time
+from typing import Dict, List, Optional, Any
+
+from core.config import get_config
+from core.identity import get_identity
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# NetworkManager中设置热点使用的连接名
+AP_CONNECTION = "reachy-setup"
+
+# 从热点发起加入时，等待响应发出后再关闭热点（秒）
+AP_HANDOVER_DELAY = 2.0
+
+
+class NetworkCommandError(RuntimeError):
+    """网络命令执行失败"""
+
+
+def validate_credentials(ssid: str, password: str):
+    if not ssid or len(ssid.encode("utf-8")) > 32:
+        raise ValueError("SSID长度必须在1-32字节之间")
+    if password and not 8 <= len(password) <= 63:
+        raise ValueError("WPA密码长度必须在8-63个字符之间")
+
+
+def split_terse(line: str) -> List[str]:
+    """拆分nmcli -t的输出行，字段中的':'和'\\'以反斜杠转义"""
+    fields, current, escaped = [], [], False
+    for c in line:
+        if escaped:
+            current.append(c)
+            escaped = False
+        elif c == "\\":
+            escaped = True
+        elif c == ":":
+            fields.append("".join(current))
+            current = []
+        else:
+            current.append(c)
+    fields.append("".join(current))
+    return fields
+
+
+async def run_command(*args: str, input: Optional[str] = None) -> str:
+    """执行命令并返回标准输出，参数不经过shell"""
+    try:
+        process = await asyncio.create_subprocess_exec(
+            *args,
+            stdin=asyncio.subprocess.PIPE if input is not None else asyncio.subprocess.DEVNULL,
+            stdout=asyncio.subprocess.PIPE,
+            stderr=asyncio.subprocess.PIPE,
+        )
+    except FileNotFoundError:
+        raise NetworkCommandError(f"找不到命令 {args[0]}")
+    
+    try:
+        stdout, stderr = await asyncio.wait_for(
+            process.communicate(input.encode() if input is not None else None),
+            timeout=config.network.COMMAND_TIMEOUT,
+        )
+    except asyncio.TimeoutError:
+        process.kill()
+        await process.wait()
+        raise NetworkCommandError(f"{args[0]} {args[1] if len(args) > 1 else ''} 超时")
+    
+    if process.returncode != 0:
+        message = stderr.decode(errors="replace").strip() or stdout.decode(errors="replace").strip()
+        raise NetworkCommandError(f"{args[0]} 执行失败: {message}")
+    return stdout.decode(errors="replace")
+
+
+def _strongest(networks: List[Dict[str, Any]]) -> List[Dict[str, Any]]:
+    """同名网络只保留正在使用或信号最强的一个，按信号强度排序"""
+    best: Dict[str, Dict[str, Any]] = {}
+    for network in networks:
+        if not network["ssid"]:
+            continue
+        current = best.get(network["ssid"])
+        if current is None or (network["in_use"], network["signal"]) > (current["in_use"], current["signal"]):
+            best[network["ssid"]] = network
+    return sorted(best.values(), key=lambda n: n["signal"], reverse=True)
+
+
+class NetworkManagerBackend:
+    """通过nmcli控制NetworkManager（NetworkManager经DBus执行实际操作）"""
+    
+    name = "networkmanager"
+    
+    def __init__(self, interface: str):
+        self.interface = interface
+    
+    async def _nmcli(self, *args: str, input: Optional[str] = None) -> str:
+        return await run_command("nmcli", *args, input=input)
+    
+    async def scan(self) -> List[Dict[str, Any]]:
+        output = await self._nmcli(
+            "-t", "-f", "IN-USE,SSID,BSSID,SIGNAL,SECURITY,FREQ",
+            "device", "wifi", "list", "ifname", self.interface, "--rescan", "yes",
+        )
+        networks = []
+        for line in output.splitlines():
+            fields = split_terse(line)
+            if len(fields) < 6:
+                continue
+            in_use, ssid, bssid, signal, security, freq = fields[:6]
+            networks.append({
+                "ssid": ssid,
+                "bssid": bssid,
+                "signal": int(signal or 0),
+                "security": "" if security in ("", "--") else security,
+                "frequency": int(freq.split()[0]) if freq else None,
+                "in_use": in_use == "*",
+            })
+        return _strongest(networks)
+    
+    async def connect(self, ssid: str, password: str):
+        args = ["--wait", str(int(config.network.COMMAND_TIMEOUT))]
+        if password:
+            # 密码经标准输入传给nmcli，不出现在进程参数中
+            await self._nmcli(*args, "--ask", "device", "wifi", "connect", ssid, "ifname", self.interface,
+                              input=password + "\n")
+        else:
+            await self._nmcli(*args, "device", "wifi", "connect", ssid, "ifname", self.interface)
+    
+    async def status(self) -> Dict[str, Any]:
+        output = await self._nmcli(
+            "-t", "-f", "GENERAL.STATE,GENERAL.CONNECTION,IP4.ADDRESS", "device", "show", self.interface,
+        )
+        values: Dict[str, str] = {}
+        for line in output.splitlines():
+            key, _, value = line.partition(":")
+            values.setdefault(key.split("[")[0], value.replace("\\:", ":"))
+        
+        connection = values.get("GENERAL.CONNECTION") or None
+        if connection == "--":
+            connection = None
+        state = values.get("GENERAL.STATE", "")
+        connected = state.startswith("100") and connection is not None
+        
+        try:
+            connectivity = (await self._nmcli("-t", "networking", "connectivity")).strip() or "unknown"
+        except NetworkCommandError:
+            connectivity = "unknown"
+        
+        return {
+            "connected": connected and connection != AP_CONNECTION,
+            "ap_active": connected and connection == AP_CONNECTION,
+            "ssid": connection if connected and connection != AP_CONNECTION else None,
+            "ip_address": (values.get("IP4.ADDRESS") or "").split("/")[0] or None,
+            "connectivity": connectivity,
+            "state": state,
+        }
+    
+    async def saved_networks(self) -> List[str]:
+        output = await self._nmcli("-t", "-f", "NAME,TYPE", "connection", "show")
+        saved = []
+        for line in output.splitlines():
+            fields = split_terse(line)
+            if len(fields) >= 2 and fields[1] == "802-11-wireless" and fields[0] != AP_CONNECTION:
+                saved.append(fields[0])
+        return saved
+    
+    async def forget(self, ssid: str):
+        await self._nmcli("connection", "delete", "id", ssid)
+    
+    async def start_ap(self, ssid: str, password: str):
+        try:
+            await self._nmcli("connection", "delete", "id", AP_CONNECTION)
+        except NetworkCommandError:
+            pass
+        
+        args = [
+            "connection", "add", "type", "wifi", "ifname", self.interface, "con-name", AP_CONNECTION,
+            "autoconnect", "no", "ssid", ssid,
+            "802-11-wireless.mode", "ap", "802-11-wireless.band", "bg", "ipv4.method", "shared",
+        ]
+        if password:
+            args += ["wifi-sec.key-mgmt", "wpa-psk", "wifi-sec.psk", password]
+        await self._nmcli(*args)
+        await self._nmcli("connection", "up", "id", AP_CONNECTION)
+    
+    async def stop_ap(self):
+        try:
+            await self._nmcli("connection", "down", "id", AP_CONNECTION)
+        finally:
+            await self._nmcli("connection", "delete", "id", AP_CONNECTION)
+
+
+class WpaSupplicantBackend:
+    """通过wpa_cli控制wpa_supplicant
+    
+    热点模式下wpa_supplicant只负责无线部分，需要另外在该网卡上运行DHCP服务（如dnsmasq）
+    """
+    
+    name = "wpa_supplicant"
+    
+    def __init__(self, interface: str):
+        self.interface = interface
+        self.ap_network_id: Optional[str] = None
+    
+    async def _cli(self, *args: str) -> str:
+        output = (await run_command("wpa_cli", "-i", self.interface, *args)).strip()
+        if output.startswith("FAIL"):
+            raise NetworkCommandError(f"wpa_cli {args[0]} 失败")
+        return output
+    
+    async def _add_network(self, ssid: str, password: str) -> str:
+        network_id = await self._cli("add_network")
+        try:
+            # SSID以十六进制写入，避免引号和特殊字符的转义问题
+            await self._cli("set_network", network_id, "ssid", ssid.encode("utf-8").hex())
+            if password:
+                await self._cli("set_network", network_id, "psk", f'"{password}"')
+            else:
+                await self._cli("set_network", network_id, "key_mgmt", "NONE")
+        except NetworkCommandError:
+            await self._cli("remove_network", network_id)
+            raise
+        return network_id
+    
+    async def scan(self) -> List[Dict[str, Any]]:
+        await self._cli("scan")
+        await asyncio.sleep(3.0)
+        output = await self._cli("scan_results")
+        current = (await self.status()).get("bssid")
+        
+        networks = []
+        for line in output.splitlines()[1:]:
+            fields = line.split("\t")
+            if len(fields) < 5:
+                continue
+            bssid, freq, level, flags, ssid = fields[:5]
+            networks.append({
+                "ssid": ssid,
+                "bssid": bssid,
+                # dBm换算为0-100，与NetworkManager的信号强度一致
+                "signal": max(0, min(100, 2 * (int(level) + 100))),
+                "security": "WPA" if "WPA" in flags else ("WEP" if "WEP" in flags else ""),
+                "frequency": int(freq),
+                "in_use": bssid == current,
+            })
+        return _strongest(networks)
+    
+    async def _raw_status(self) -> Dict[str, str]:
+        output = await self._cli("status")
+        return dict(line.split("=", 1) for line in output.splitlines() if "=" in line)
+    
+    async def connect(self, ssid: str, password: str):
+        network_id = await self._add_network(ssid, password)
+        await self._cli("select_network", network_id)
+        
+        deadline = time.monotonic() + config.network.COMMAND_TIMEOUT
+        while time.monotonic() < deadline:
+            status = await self._raw_status()
+            if status.get("wpa_state") == "COMPLETED" and status.get("id") == network_id:
+                # select_network会禁用其他网络，连接成功后重新启用以便自动重连
+                await self._cli("enable_network", "all")
+                await self._cli("save_config")
+                return
+            await asyncio.sleep(1.0)
+        
+        await self._cli("remove_network", network_id)
+        await self._cli("enable_network", "all")
+        await self._cli("reconnect")
+        raise NetworkCommandError(f"连接 {ssid} 超时，请检查密码和信号")
+    
+    async def status(self) -> Dict[str, Any]:
+        status = await self._raw_status()
+        completed = status.get("wpa_state") == "COMPLETED"
+        ap_active = completed and status.get("mode") == "AP"
+        connected = completed and not ap_active
+        return {
+            "connected": connected,
+            "ap_active": ap_active,
+            "ssid": status.get("ssid") if connected else None,
+            "bssid": status.get("bssid") if connected else None,
+            "ip_address": status.get("ip_address"),
+            # wpa_supplicant不检查外网连通性
+            "connectivity": "unknown" if connected else "none",
+            "state": status.get("wpa_state", ""),
+        }
+    
+    async def _networks(self) -> List[List[str]]:
+        output = await self._cli("list_networks")
+        return [line.split("\t") for line in output.splitlines()[1:] if line.count("\t") >= 2]
+    
+    async def saved_networks(self) -> List[str]:
+        return [fields[1] for fields in await self._networks() if fields[0] != self.ap_network_id]
+    
+    async def forget(self, ssid: str):
+        ids = [fields[0] for fields in await self._networks()
+               if fields[1] == ssid and fields[0] != self.ap_network_id]
+        if not ids:
+            raise KeyError(f"没有保存的网络: {ssid}")
+        for network_id in ids:
+            await self._cli("remove_network", network_id)
+        await self._cli("save_config")
+    
+    async def start_ap(self, ssid: str, password: str):
+        network_id = await self._add_network(ssid, password)
+        await self._cli("set_network", network_id, "mode", "2")
+        await self._cli("set_network", network_id, "frequency", "2437")
+        if password:
+            await self._cli("set_network", network_id, "key_mgmt", "WPA-PSK")
+            await self._cli("set_network", network_id, "proto", "RSN")
+        await self._cli("select_network", network_id)
+        self.ap_network_id = network_id
+    
+    async def stop_ap(self):
+        if self.ap_network_id is None:
+            return
+        # 热点网络不写入配置文件，移除后恢复已保存的网络
+        await self._cli("remove_network", self.ap_network_id)
+        self.ap_network_id = None
+        await self._cli("enable_network", "all")
+        await self._cli("reconnect")
+
+
+class NetworkService:
+    """Wi-Fi网络管理服务"""
+    
+    def __init__(self):
+        self.backend = None
+        self.monitor_task: Optional[asyncio.Task] = None
+        self.join_task: Optional[asyncio.Task] = None
+        self.lock = asyncio.Lock()
+        self.ap_started_by_fallback = False
+        self.disconnected_since: Optional[float] = None
+        self.last_status: Optional[Dict[str, Any]] = None
+        self.last_join: Optional[Dict[str, Any]] = None
+        self.last_error: Optional[str] = None
+    
+    @property
+    def is_running(self) -> bool:
+        return self.monitor_task is not None and not self.monitor_task.done()
+    
+    def _get_backend(self):
+        if self.backend is not None:
+            return self.backend
+        
+        network = config.network
+        backend = network.BACKEND
+        if backend == "auto":
+            if shutil.which("nmcli"):
+                backend = "networkmanager"
+            elif shutil.which("wpa_cli"):
+                backend = "wpa_supplicant"
+            else:
+                raise RuntimeError("找不到nmcli或wpa_cli，无法管理Wi-Fi")
+        
+        if backend == "networkmanager":
+            self.backend = NetworkManagerBackend(network.INTERFACE)
+        elif backend == "wpa_supplicant":
+            self.backend = WpaSupplicantBackend(network.INTERFACE)
+        else:
+            raise ValueError(f"不支持的网络后端: {backend}")
+        
+        logger.info(f"Wi-Fi网络后端: {self.backend.name}（{network.INTERFACE}）")
+        return self.backend
+    
+    async def start(self) -> bool:
+        """启动连接监控（没有可用网络时开启设置热点）"""
+        try:
+            if self.is_running:
+                logger.info("网络管理已经在运行")
+                return True
+            
+            self._get_backend()
+            password = config.network.AP_PASSWORD
+            if password and not 8 <= len(password) <= 63:
+                raise ValueError("设置热点密码长度必须在8-63个字符之间")
+            if not password:
+                logger.warning("设置热点密码为空，热点不加密")
+            
+            self.monitor_task = asyncio.create_task(self._monitor_loop())
+            logger.info("Wi-Fi网络管理已启动")
+            return True
+            
+        except Exception as e:
+            self.last_error = str(e)
+            logger.error(f"启动网络管理失败: {e}")
+            return False
+    
+    async def stop(self):
+        """停止连接监控，关闭由监控开启的设置热点"""
+        try:
+            for task in (self.monitor_task, self.join_task):
+                if task and not task.done():
+                    task.cancel()
+                    try:
+                        await task
+                    except asyncio.CancelledError:
+                        pass
+            self.monitor_task = None
+            self.join_task = None
+            
+            if self.ap_started_by_fallback:
+                await self.stop_ap()
+            logger.info("Wi-Fi网络管理已停止")
+            
+        except Exception as e:
+            logger.error(f"停止网络管理时出错: {e}")
+    
+    async def get_status(self) -> Dict[str, Any]:
+        backend = self._get_backend()
+        async with self.lock:
+            status = await backend.status()
+        self.last_status = status
+        return {
+            **status,
+            "backend": backend.name,
+            "interface": config.network.INTERFACE,
+            "ap_ssid": self.ap_ssid if status["ap_active"] else None,
+            "monitoring": self.is_running,
+            "joining": self.join_task is not None and not self.join_task.done(),
+            "last_join": self.last_join,
+            "last_error": self.last_error,
+        }
+    
+    async def scan(self) -> List[Dict[str, Any]]:
+        """扫描附近的网络；热点模式下部分网卡只能返回有限的结果"""
+        backend = self._get_backend()
+        async with self.lock:
+            return await backend.scan()
+    
+    async def saved_networks(self) -> List[str]:
+        backend = self._get_backend()
+        async with self.lock:
+            return await backend.saved_networks()
+    
+    async def forget(self, ssid: str):
+        backend = self._get_backend()
+        async with self.lock:
+            if ssid not in await backend.saved_networks():
+                raise KeyError(f"没有保存的网络: {ssid}")
+            await backend.forget(ssid)
+        logger.info(f"已删除保存的网络 {ssid}")
+    
+    async def join(self, ssid: str, password: str = "") -> Dict[str, Any]:
+        """加入网络；热点开启时改为后台执行并立即返回"""
+        validate_credentials(ssid, password)
+        backend = self._get_backend()
+        
+        if self.join_task is not None and not self.join_task.done():
+            raise RuntimeError("正在加入其他网络")
+        
+        async with self.lock:
+            ap_active = (await backend.status())["ap_active"]
+        
+        if ap_active:
+            self.join_task = asyncio.create_task(self._join_from_ap(ssid, password))
+            return {"pending": True, "ssid": ssid, "message": "设置热点即将关闭，请稍后通过新网络查询连接结果"}
+        
+        await self._join(ssid, password)
+        return {"pending": False, "ssid": ssid, "message": f"已连接到 {ssid}"}
+    
+    async def _join(self, ssid: str, password: str):
+        backend = self._get_backend()
+        try:
+            async with self.lock:
+                await backend.connect(ssid, password)
+        except Exception as e:
+            self.last_join = {"ssid": ssid, "success": False, "error": str(e), "timestamp": time.time()}
+            logger.warning(f"加入网络 {ssid} 失败: {e}")
+            raise
+        
+        self.last_join = {"ssid": ssid, "success": True, "error": None, "timestamp": time.time()}
+        self.disconnected_since = None
+        logger.info(f"已加入网络 {ssid}")
+    
+    async def _join_from_ap(self, ssid: str, password: str):
+        await asyncio.sleep(AP_HANDOVER_DELAY)
+        await self.stop_ap()
+        try:
+            await self._join(ssid, password)
+        except Exception:
+            # 连接失败时重新开启热点，用户可以看到错误并重试
+            await self.start_ap(fallback=True)
+    
+    @property
+    def ap_ssid(self) -> str:
+        """设置热点名称，带UUID前缀以区分同一网络中的多台机器人（SSID最长32字节）"""
+        return config.network.AP_SSID.replace("{id}", get_identity().short_id)[:32]
+    
+    async def start_ap(self, fallback: bool = False):
+        network = config.network
+        backend = self._get_backend()
+        async with self.lock:
+            await backend.start_ap(self.ap_ssid, network.AP_PASSWORD)
+        self.ap_started_by_fallback = fallback
+        logger.info(f"设置热点 {self.ap_ssid} 已开启")
+    
+    async def stop_ap(self):
+        backend = self._get_backend()
+        async with self.lock:
+            await backend.stop_ap()
+        self.ap_started_by_fallback = False
+        logger.info("设置热点已关闭")
+    
+    async def _check_fallback(self):
+        network = config.network
+        status = await self.get_status()
+        
+        if status["connected"] or status["ap_active"]:
+            self.disconnected_since = None
+            return
+        if self.join_task is not None and not self.join_task.done():
+            return
+        
+        now = time.monotonic()
+        if self.disconnected_since is None:
+            self.disconnected_since = now
+        
+        if not network.AP_FALLBACK:
+            return
+        # 没有保存任何网络时不会自动连上，直接开启热点
+        if await self.saved_networks() and now - self.disconnected_since < network.AP_FALLBACK_DELAY:
+            return
+        
+        logger.info("没有可用的Wi-Fi网络，开启设置热点")
+        await self.start_ap(fallback=True)
+    
+    async def _monitor_loop(self):
+        while True:
+            try:
+                await self._check_fallback()
+                self.last_error = None
+            except asyncio.CancelledError:
+                raise
+            except Exception as e:
+                self.last_error = str(e)
+                logger.warning(f"网络状态检查失败: {e}")
+            
+            await asyncio.sleep(config.network.CHECK_INTERVAL)
+
+
+# 全局网络管理实例
+network_service = NetworkService()
@@ -0,0 +1,320 @@
+#!/usr/bin/env python3
+"""
+观察者会话服务
+远程协助者（如社区志愿者）申请观察者会话，主人用管理员密码同意后协助者获得一个
+有期限的令牌，可以只读查看实时遥测、日志和摄像头，不能发送运动命令或修改设置。
+主人可以随时撤销会话，会话到期或撤销时已建立的WebSocket连接被断开
+
+令牌通过 X-Observer-Token 请求头或 observer_token 查询参数（WebSocket、图片地址）携带；
+带令牌的HTTP请求只允许GET观察者路径，控制WebSocket拒绝命令消息
+"""
+
+import asyncio
+import hashlib
+import secrets
+import time
+import uuid
+from dataclasses import dataclass, field
+from typing import Any, Dict, List, Optional, Set, Tuple
+
+from fastapi import FastAPI, Request, WebSocket
+from fastapi.responses import JSONResponse
+from core.config import get_config
+from core.cors import WS_POLICY_VIOLATION
+from core.database import get_database_manager
+from core.models import User, UserRole
+from services.analytics_service import analytics_service
+from services.mode_service import pwd_context
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+OBSERVER_HEADER = "X-Observer-Token"
+OBSERVER_QUERY = "observer_token"
+
+# 检查会话到期的间隔（秒）
+EXPIRY_CHECK_INTERVAL = 5.0
+
+
+def _hash(value: str) -> str:
+    return hashlib.sha256(value.encode()).hexdigest()
+
+
+@dataclass
+class ObserverRequest:
+    """一条等待主人同意的观察者申请"""
+    id: str
+    name: str
+    reason: str
+    duration_minutes: float
+    secret_hash: str
+    created_at: float
+    status: str = "pending"
+    session_id: Optional[str] = None
+    # 同意后生成的令牌，协助者取走后清除
+    token: Optional[str] = None
+    
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "id": self.id,
+            "name": self.name,
+            "reason": self.reason,
+            "duration_minutes": self.duration_minutes,
+            "status": self.status,
+            "created_at": self.created_at,
+        }
+
+
+@dataclass
+class ObserverSession:
+    """一个已同意的观察者会话"""
+    id: str
+    name: str
+    token_hash: str
+    created_at: float
+    expires_at: float
+    websockets: Set[int] = field(default_factory=set)
+    
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "id": self.id,
+            "name": self.name,
+            "created_at": self.created_at,
+            "expires_at": self.expires_at,
+            "remaining_seconds": max(0.0, self.expires_at - time.time()),
+            "connections": len(self.websockets),
+        }
+
+
+class ObserverService:
+    """观察者会话服务"""
+    
+    def __init__(self):
+        self.requests: Dict[str, ObserverRequest] = {}
+        self.sessions: Dict[str, ObserverSession] = {}
+        self.connections: Dict[int, WebSocket] = {}
+        self.expiry_task: Optional[asyncio.Task] = None
+    
+    def _clamp_duration(self, minutes: Optional[float]) -> float:
+        settings = config.observer
+        minutes = settings.DEFAULT_DURATION_MINUTES if minutes is None else minutes
+        if minutes <= 0:
+            raise ValueError("会话时长必须为正数")
+        return min(minutes, settings.MAX_DURATION_MINUTES)
+    
+    def _expire_requests(self, now: float):
+        ttl = config.observer.REQUEST_TTL_MINUTES * 60
+        for request in self.requests.values():
+            if request.status == "pending" and now - request.created_at > ttl:
+                request.status = "expired"
+        # 已处理的申请保留一个有效期，供协助者查询结果
+        self.requests = {
+            request_id: request for request_id, request in self.requests.items()
+            if now - request.created_at <= 2 * ttl
+        }
+    
+    async def create_request(self, name: str, reason: str = "", duration_minutes: Optional[float] = None) -> Dict[str, Any]:
+        """协助者申请观察者会话，返回申请ID和查询结果用的密钥"""
+        if not config.observer.ENABLED:
+            raise ValueError("观察者会话未启用")
+        
+        now = time.time()
+        self._expire_requests(now)
+        pending = [r for r in self.requests.values() if r.status == "pending"]
+        if len(pending) >= config.observer.MAX_PENDING_REQUESTS:
+            raise ValueError("等待同意的申请过多，请稍后再试")
+        
+        secret = secrets.token_urlsafe(24)
+        request = ObserverRequest(
+            id=str(uuid.uuid4()),
+            name=name.strip()[:64],
+            reason=reason.strip()[:500],
+            duration_minutes=self._clamp_duration(duration_minutes),
+            secret_hash=_hash(secret),
+            created_at=now,
+        )
+        self.requests[request.id] = request
+        
+        logger.info(f"收到观察者会话申请: {request.name}（{request.duration_minutes:g}分钟）")
+        await analytics_service.record_event("observer_requested", {"request_id": request.id, "name": request.name})
+        return {**request.to_dict(), "secret": secret}
+    
+    def list_requests(self) -> List[Dict[str, Any]]:
+        """等待主人同意的申请"""
+        self._expire_requests(time.time())
+        return [r.to_dict() for r in self.requests.values() if r.status == "pending"]
+    
+    def _verify_admin_password(self, password: str) -> bool:
+        with get_database_manager().get_session() as session:
+            admin = session.query(User).filter(
+                User.username == config.setup.ADMIN_USERNAME,
+                User.role == UserRole.ADMIN.value,
+                User.is_active.is_(True),
+            ).first()
+            return admin is not None and pwd_context.verify(password, admin.hashed_password)
+    
+    async def approve(self, request_id: str, admin_password: str, duration_minutes: Optional[float] = None) -> Dict[str, Any]:
+        """主人同意申请（需要管理员密码），生成令牌供协助者取走"""
+        self._expire_requests(time.time())
+        request = self.requests.get(request_id)
+        if request is None or request.status != "pending":
+            raise LookupError("申请不存在或已处理")
+        if not await asyncio.to_thread(self._verify_admin_password, admin_password):
+            raise PermissionError("管理员密码错误")
+        
+        token = secrets.token_urlsafe(32)
+        now = time.time()
+        duration = self._clamp_duration(duration_minutes if duration_minutes is not None else request.duration_minutes)
+        session = ObserverSession(
+            id=str(uuid.uuid4()),
+            name=request.name,
+            token_hash=_hash(token),
+            created_at=now,
+            expires_at=now + duration * 60,
+        )
+        self.sessions[session.id] = session
+        request.status, request.session_id, request.token = "approved", session.id, token
+        
+        logger.info(f"同意观察者会话: {session.name}，{duration:g}分钟后到期")
+        await analytics_service.record_event("observer_approved", {"session_id": session.id, "name": session.name})
+        return session.to_dict()
+    
+    def deny(self, request_id: str) -> bool:
+        request = self.requests.get(request_id)
+        if request is None or request.status != "pending":
+            return False
+        request.status = "denied"
+        logger.info(f"拒绝观察者会话申请: {request.name}")
+        return True
+    
+    def poll(self, request_id: str, secret: str) -> Optional[Dict[str, Any]]:
+        """协助者查询申请结果，同意后第一次查询返回令牌；密钥不符时返回None"""
+        self._expire_requests(time.time())
+        request = self.requests.get(request_id)
+        if request is None or not secrets.compare_digest(request.secret_hash, _hash(secret)):
+            return None
+        
+        result = request.to_dict()
+        session = self.sessions.get(request.session_id) if request.session_id else None
+        if session is not None:
+            result["expires_at"] = session.expires_at
+            if request.token:
+                result["token"], request.token = request.token, None
+        return result
+    
+    def validate(self, token: str) -> Optional[ObserverSession]:
+        """令牌对应的有效会话，无效或已到期时返回None"""
+        token_hash = _hash(token)
+        now = time.time()
+        for session in self.sessions.values():
+            if secrets.compare_digest(session.token_hash, token_hash):
+                return session if session.expires_at > now else None
+        return None
+    
+    def is_allowed_path(self, path: str) -> bool:
+        return any(path == prefix or path.startswith(prefix.rstrip("/") + "/") for prefix in config.observer.ALLOWED_PATHS)
+    
+    def list_sessions(self) -> List[Dict[str, Any]]:
+        now = time.time()
+        return [s.to_dict() for s in self.sessions.values() if s.expires_at > now]
+    
+    async def revoke(self, session_id: str) -> bool:
+        """撤销会话并断开它的WebSocket连接"""
+        session = self.sessions.pop(session_id, None)
+        if session is None:
+            return False
+        await self._disconnect(session)
+        logger.info(f"撤销观察者会话: {session.name}")
+        return True
+    
+    def attach(self, session: ObserverSession, websocket: WebSocket):
+        """登记观察者的WebSocket连接，会话结束时断开"""
+        self.connections[id(websocket)] = websocket
+        session.websockets.add(id(websocket))
+    
+    def detach(self, session: ObserverSession, websocket: WebSocket):
+        self.connections.pop(id(websocket), None)
+        session.websockets.discard(id(websocket))
+    
+    async def _disconnect(self, session: ObserverSession):
+        for key in list(session.websockets):
+            websocket = self.connections.pop(key, None)
+            if websocket is not None:
+                try:
+                    await websocket.close(code=WS_POLICY_VIOLATION)
+                except Exception as e:
+                    logger.debug(f"断开观察者连接失败: {e}")
+        session.websockets.clear()
+    
+    async def _expiry_loop(self):
+        while True:
+            await asyncio.sleep(EXPIRY_CHECK_INTERVAL)
+            now = time.time()
+            for session in [s for s in self.sessions.values() if s.expires_at <= now]:
+                del self.sessions[session.id]
+                await self._disconnect(session)
+                logger.info(f"观察者会话到期: {session.name}")
+            self._expire_requests(now)
+    
+    async def start(self):
+        self.expiry_task = asyncio.create_task(self._expiry_loop())
+    
+    async def stop(self):
+        if self.expiry_task and not self.expiry_task.done():
+            self.expiry_task.cancel()
+            try:
+                await self.expiry_task
+            except asyncio.CancelledError:
+                pass
+        self.expiry_task = None
+        for session in list(self.sessions.values()):
+            await self._disconnect(session)
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "enabled": config.observer.ENABLED,
+            "pending_requests": len(self.list_requests()),
+            "sessions": self.list_sessions(),
+            "allowed_paths": config.observer.ALLOWED_PATHS,
+        }
+
+
+# 全局观察者会话服务实例
+observer_service = ObserverService()
+
+
+def observer_token(request) -> Optional[str]:
+    """请求或WebSocket握手中携带的观察者令牌"""
+    return request.headers.get(OBSERVER_HEADER) or request.query_params.get(OBSERVER_QUERY)
+
+
+async def check_observer_websocket(websocket: WebSocket) -> Tuple[bool, Optional[ObserverSession]]:
+    """在accept之前调用，令牌无效时关闭连接；返回(是否允许连接, 观察者会话)，不带令牌时会话为None"""
+    token = observer_token(websocket)
+    if not token:
+        return True, None
+    
+    session = observer_service.validate(token)
+    if session is None:
+        logger.warning(f"拒绝无效观察者令牌的WebSocket连接: {websocket.url.path}")
+        await websocket.close(code=WS_POLICY_VIOLATION)
+        return False, None
+    return True, session
+
+
+def setup_observer_guard(app: FastAPI):
+    """带观察者令牌的HTTP请求只允许GET观察者路径"""
+    
+    @app.middleware("http")
+    async def observer_guard_middleware(request: Request, call_next):
+        token = observer_token(request)
+        if token:
+            session = observer_service.validate(token)
+            if session is None:
+                return JSONResponse(status_code=401, content={"detail": "观察者会话无效或已到期"})
+            if request.method not in ("GET", "HEAD") or not observer_service.is_allowed_path(request.url.path):
+                return JSONResponse(status_code=403, content={"detail": "观察者会话只读，不能访问该接口"})
+        return await call_next(request)
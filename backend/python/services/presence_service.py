@@ -0,0 +1,251 @@
+#!/usr/bin/env python3
+"""
+有人出现的热力图服务
+把人员检测结果按位置和时间聚合成粗粒度的热力图：位置是底座坐标系地面上的网格，
+时间是一周中的小时（周一0点到周日23点共168个）。同一采样时段内同一格子只计一次，
+同时记录每个小时有多少个时段在观察，两者相除就是该小时该位置有人的概率，
+可以回答"一般什么时候有人在书桌前"这类问题
+
+位置由检测框估计：水平方位由框中心在画面中的位置加上头部水平角得到，距离由框高度按
+假设的人体高度估计，只是粗略位置。数据只保存在本机的数据目录中，不推送、不上传
+"""
+
+import json
+import math
+import os
+import time
+from pathlib import Path
+from typing import Any, Dict, Iterable, List, Optional, Set, Tuple
+
+from core.config import get_config
+from services.robot_service import robot_service
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+HOURS_PER_WEEK = 7 * 24
+WEEKDAYS = ["周一", "周二", "周三", "周四", "周五", "周六", "周日"]
+
+Cell = Tuple[int, int]
+
+
+def hour_of_week(timestamp: float) -> int:
+    """本地时间在一周中的小时，周一0点为0"""
+    local = time.localtime(timestamp)
+    return local.tm_wday * 24 + local.tm_hour
+
+
+class PresenceService:
+    """有人出现的空间/时间热力图"""
+    
+    def __init__(self):
+        # 每个小时被观察的时段数
+        self.observed: List[int] = [0] * HOURS_PER_WEEK
+        # 格子 -> 每个小时有人的时段数
+        self.cells: Dict[Cell, List[int]] = {}
+        # 当前采样时段及其中有人的格子
+        self.slot: Optional[int] = None
+        self.slot_cells: Set[Cell] = set()
+    
+    @property
+    def state_path(self) -> Path:
+        return Path(config.DATA_DIR) / config.presence.STATE_FILE
+    
+    @property
+    def cell_size(self) -> float:
+        return config.presence.CELL_SIZE_M
+    
+    def load(self):
+        """读取保存的热力图，网格大小改变后旧数据不再使用"""
+        try:
+            with open(self.state_path, encoding="utf-8") as f:
+                state = json.load(f)
+        except FileNotFoundError:
+            return
+        except (OSError, ValueError) as e:
+            logger.warning(f"读取人员热力图失败: {e}")
+            return
+        
+        if state.get("cell_size") != self.cell_size:
+            logger.warning(f"热力图网格大小由 {state.get('cell_size')} 改为 {self.cell_size}，丢弃旧数据")
+            return
+        
+        try:
+            observed = [int(v) for v in state["observed"]]
+            cells = {
+                tuple(int(i) for i in key.split(",")): [int(v) for v in counts]
+                for key, counts in state["cells"].items()
+            }
+        except (KeyError, TypeError, ValueError) as e:
+            logger.warning(f"人员热力图格式错误，丢弃: {e}")
+            return
+        if len(observed) != HOURS_PER_WEEK or any(len(c) != HOURS_PER_WEEK or len(k) != 2 for k, c in cells.items()):
+            logger.warning("人员热力图格式错误，丢弃")
+            return
+        self.observed, self.cells = observed, cells
+    
+    def _save(self):
+        path = self.state_path
+        path.parent.mkdir(parents=True, exist_ok=True)
+        tmp = path.with_suffix(".tmp")
+        with open(tmp, "w", encoding="utf-8") as f:
+            json.dump({
+                "cell_size": self.cell_size,
+                "observed": self.observed,
+                "cells": {f"{i},{j}": counts for (i, j), counts in self.cells.items()},
+            }, f)
+        os.replace(tmp, path)
+    
+    def locate(self, bbox: Iterable[float], image_size: Tuple[int, int], head_pan_deg: float) -> Optional[Tuple[float, float]]:
+        """由人员检测框估计其在底座坐标系地面上的位置（米），无法估计或超出范围时返回None
+        
+        image_size为检测框坐标所在的图像尺寸（推理输入可能被缩放），垂直视场角按摄像头帧的宽高比计算
+        """
+        x1, y1, x2, y2 = bbox
+        width, height = image_size
+        if width <= 0 or height <= 0 or y2 <= y1:
+            return None
+        
+        settings = config.presence
+        hfov = math.radians(settings.HORIZONTAL_FOV_DEG)
+        vfov = 2 * math.atan(math.tan(hfov / 2) * config.vision.FRAME_HEIGHT / config.vision.FRAME_WIDTH)
+        
+        # 画面左侧为正方位（与头部水平角同向）
+        bearing = math.radians(head_pan_deg) + ((x1 + x2) / 2 / width - 0.5) * -hfov
+        angular_height = (y2 - y1) / height * vfov
+        distance = settings.PERSON_HEIGHT_M / (2 * math.tan(angular_height / 2))
+        if not 0 < distance <= settings.MAX_RANGE_M:
+            return None
+        return distance * math.cos(bearing), distance * math.sin(bearing)
+    
+    def _cell_of(self, x: float, y: float) -> Cell:
+        return math.floor(x / self.cell_size), math.floor(y / self.cell_size)
+    
+    def _cell_center(self, cell: Cell) -> Tuple[float, float]:
+        return (cell[0] + 0.5) * self.cell_size, (cell[1] + 0.5) * self.cell_size
+    
+    def _finish_slot(self):
+        if self.slot is None:
+            return
+        
+        how = hour_of_week(self.slot * config.presence.SAMPLE_INTERVAL_SECONDS)
+        self.observed[how] += 1
+        for cell in self.slot_cells:
+            self.cells.setdefault(cell, [0] * HOURS_PER_WEEK)[how] += 1
+        self.slot, self.slot_cells = None, set()
+        
+        try:
+            self._save()
+        except OSError as e:
+            logger.warning(f"保存人员热力图失败: {e}")
+    
+    async def record(self, detections: List[Any], image_size: Tuple[int, int], now: Optional[float] = None):
+        """记录一帧的检测结果（没有检测到人也算作一次观察）"""
+        if not config.presence.ENABLED:
+            return
+        
+        now = time.time() if now is None else now
+        slot = int(now // config.presence.SAMPLE_INTERVAL_SECONDS)
+        if slot != self.slot:
+            self._finish_slot()
+            self.slot = slot
+        
+        people = [
+            d for d in detections
+            if d.class_name == "person" and d.confidence >= config.presence.MIN_CONFIDENCE
+        ]
+        if not people:
+            return
+        
+        state = await robot_service.get_robot_state()
+        for person in people:
+            position = self.locate(person.bbox, image_size, state.head_position["pan"])
+            if position is not None:
+                self.slot_cells.add(self._cell_of(*position))
+    
+    def flush(self):
+        """结束当前采样时段并写入磁盘"""
+        self._finish_slot()
+    
+    def _hours(self, weekday: Optional[int], hour: Optional[int]) -> List[int]:
+        return [
+            how for how in range(HOURS_PER_WEEK)
+            if (weekday is None or how // 24 == weekday) and (hour is None or how % 24 == hour)
+        ]
+    
+    def heatmap(self, weekday: Optional[int] = None, hour: Optional[int] = None) -> Dict[str, Any]:
+        """各格子有人的概率，可以只看一周中的某天和/或某个小时"""
+        hours = self._hours(weekday, hour)
+        observed = sum(self.observed[how] for how in hours)
+        cells = []
+        for cell, counts in sorted(self.cells.items()):
+            count = sum(counts[how] for how in hours)
+            if count:
+                x, y = self._cell_center(cell)
+                cells.append({"x": x, "y": y, "count": count, "probability": count / observed if observed else 0.0})
+        return {"cell_size": self.cell_size, "observed_slots": observed, "cells": cells}
+    
+    def usual_times(self, x: float, y: float, radius: Optional[float] = None, min_probability: float = 0.3) -> Dict[str, Any]:
+        """某个位置附近一般什么时候有人：每个小时有人的概率，以及概率不低于min_probability的时间"""
+        radius = self.cell_size if radius is None else radius
+        center = self._cell_of(x, y)
+        region = [
+            counts for cell, counts in self.cells.items()
+            if cell == center or math.dist(self._cell_center(cell), (x, y)) <= radius
+        ]
+        
+        # 同一时段相邻格子可能都有人，概率截断到1
+        by_week = []
+        for how in range(HOURS_PER_WEEK):
+            occupied = sum(counts[how] for counts in region)
+            by_week.append(min(1.0, occupied / self.observed[how]) if self.observed[how] else None)
+        
+        by_hour = []
+        for hour in range(24):
+            occupied = sum(counts[how] for counts in region for how in self._hours(None, hour))
+            observed = sum(self.observed[how] for how in self._hours(None, hour))
+            by_hour.append(min(1.0, occupied / observed) if observed else None)
+        
+        usual = sorted(
+            ({"weekday": WEEKDAYS[how // 24], "hour": how % 24, "probability": p}
+             for how, p in enumerate(by_week) if p is not None and p >= min_probability),
+            key=lambda item: -item["probability"]
+        )
+        return {
+            "x": x,
+            "y": y,
+            "radius": radius,
+            "observed_slots": sum(self.observed),
+            "by_hour": by_hour,
+            "by_weekday_hour": [by_week[day * 24:(day + 1) * 24] for day in range(7)],
+            "usual": usual,
+        }
+    
+    def clear(self) -> int:
+        """删除全部热力图数据，返回删除的格子数"""
+        count = len(self.cells)
+        self.observed = [0] * HOURS_PER_WEEK
+        self.cells = {}
+        self.slot, self.slot_cells = None, set()
+        try:
+            os.remove(self.state_path)
+        except FileNotFoundError:
+            pass
+        logger.info(f"已清除人员热力图（{count} 个格子）")
+        return count
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "enabled": config.presence.ENABLED,
+            "cell_size": self.cell_size,
+            "cells": len(self.cells),
+            "observed_slots": sum(self.observed),
+            "sample_interval_seconds": config.presence.SAMPLE_INTERVAL_SECONDS,
+        }
+
+
+# 全局人员热力图服务实例
+presence_service = PresenceService()
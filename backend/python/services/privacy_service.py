@@ -0,0 +1,180 @@
+#!/usr/bin/env python3
+"""
+隐私模式服务
+开启后立即停止摄像头/麦克风采集管线，可选让头部低头停放并点亮状态灯，
+在关闭之前拒绝任何画面和音频访问。状态保存到数据库，重启后保持不变。
+"""
+
+import asyncio
+from typing import Dict, Optional, Any, Callable, Awaitable
+from datetime import datetime
+
+from core.config import get_config
+from core.database import get_database_manager
+from core.exceptions import PrivacyModeException
+from core.models import Configuration
+from services.robot_service import robot_service
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 数据库中保存隐私模式状态的配置键
+PRIVACY_CONFIG_KEY = "privacy_mode"
+
+
+class PrivacyService:
+    """隐私模式服务"""
+    
+    def __init__(self):
+        self.enabled = False
+        self.since: Optional[datetime] = None
+        self.reason: Optional[str] = None
+        self.head_parked = False
+        self.led_on = False
+        
+        # 采集管线停止回调，键为设备名（camera、microphone）
+        self.pipelines: Dict[str, Callable[[], Awaitable[None]]] = {}
+        
+        self.lock = asyncio.Lock()
+        self.persisted = False
+    
+    async def initialize(self) -> bool:
+        """从数据库恢复隐私模式状态"""
+        try:
+            logger.info("正在初始化隐私模式服务...")
+            
+            state = await asyncio.to_thread(self._load_state)
+            self.persisted = True
+            
+            if state and state.get("enabled"):
+                self.enabled = True
+                self.reason = state.get("reason")
+                since = state.get("since")
+                self.since = datetime.fromisoformat(since) if since else datetime.now()
+                logger.warning(f"隐私模式已开启（自 {self.since.isoformat()}），摄像头和麦克风保持关闭")
+            
+            logger.info("隐私模式服务初始化完成")
+            return True
+            
+        except Exception as e:
+            logger.error(f"隐私模式服务初始化失败: {e}")
+            return False
+    
+    def _load_state(self) -> Optional[Dict[str, Any]]:
+        with get_database_manager().get_session() as session:
+            record = session.query(Configuration).filter(Configuration.key == PRIVACY_CONFIG_KEY).first()
+            return dict(record.value) if record else None
+    
+    def _save_state(self, state: Dict[str, Any]):
+        with get_database_manager().get_session() as session:
+            record = session.query(Configuration).filter(Configuration.key == PRIVACY_CONFIG_KEY).first()
+            if record is None:
+                session.add(Configuration(
+                    key=PRIVACY_CONFIG_KEY,
+                    value=state,
+                    description="隐私模式状态",
+                    category="privacy",
+                    is_system=True
+                ))
+            else:
+                record.value = state
+                record.version += 1
+    
+    async def _persist(self):
+        """保存当前状态，数据库不可用时只保留在内存中"""
+        if not self.persisted:
+            logger.warning("数据库未就绪，隐私模式状态不会在重启后保留")
+            return
+        
+        state = {
+            "enabled": self.enabled,
+            "since": self.since.isoformat() if self.since else None,
+            "reason": self.reason,
+        }
+        await asyncio.to_thread(self._save_state, state)
+    
+    def register_pipeline(self, device: str, stop: Callable[[], Awaitable[None]]):
+        """注册采集管线，开启隐私模式时调用stop立即停止采集"""
+        self.pipelines[device] = stop
+        logger.info(f"注册采集管线: {device}")
+    
+    def unregister_pipeline(self, device: str):
+        """注销采集管线"""
+        self.pipelines.pop(device, None)
+    
+    def is_capture_allowed(self) -> bool:
+        """当前是否允许采集画面和音频"""
+        return not self.enabled
+    
+    def ensure_capture_allowed(self, device: str):
+        """隐私模式下访问采集设备时抛出PrivacyModeException"""
+        if self.enabled:
+            raise PrivacyModeException(f"隐私模式已开启，禁止访问{device}", device=device)
+    
+    async def enable(self, park_head: Optional[bool] = None, reason: Optional[str] = None) -> Dict[str, Any]:
+        """开启隐私模式"""
+        async with self.lock:
+            if not self.enabled:
+                self.enabled = True
+                self.since = datetime.now()
+                self.reason = reason
+                logger.warning(f"开启隐私模式{f'：{reason}' if reason else ''}")
+            
+            # 先停止采集，再处理机械和灯光，保证画面尽快切断
+            for device, stop in self.pipelines.items():
+                try:
+                    await stop()
+                    logger.info(f"已停止采集管线: {device}")
+                except Exception as e:
+                    logger.error(f"停止采集管线 {device} 失败: {e}")
+            
+            if park_head is None:
+                park_head = config.privacy.PARK_HEAD
+            if park_head:
+                self.head_parked = await robot_service.move_head(
+                    config.privacy.PARK_PAN, config.privacy.PARK_TILT
+                )
+            
+            self.led_on = await robot_service.set_led_color(config.privacy.LED_COLOR)
+            if not self.led_on:
+                logger.warning("隐私模式状态灯未能点亮")
+            
+            await self._persist()
+            return self.get_status()
+    
+    async def disable(self) -> Dict[str, Any]:
+        """关闭隐私模式，采集管线需要重新启动"""
+        async with self.lock:
+            if self.enabled:
+                logger.warning("关闭隐私模式")
+            
+            self.enabled = False
+            self.since = None
+            self.reason = None
+            self.head_parked = False
+            
+            if self.led_on:
+                await robot_service.set_led_color("off")
+                self.led_on = False
+            
+            await self._persist()
+            return self.get_status()
+    
+    def get_status(self) -> Dict[str, Any]:
+        """获取隐私模式状态"""
+        return {
+            "enabled": self.enabled,
+            "since": self.since.isoformat() if self.since else None,
+            "reason": self.reason,
+            "head_parked": self.head_parked,
+            "led_on": self.led_on,
+            "pipelines": sorted(self.pipelines.keys()),
+            "persisted": self.persisted,
+        }
+
+
+# 全局隐私模式服务实例
+privacy_service = PrivacyService()
@@ -0,0 +1,240 @@
+#!/usr/bin/env python3
+"""
+远程中继服务
+主动连接配置的中继服务器（WebSocket over TLS），把服务器转来的HTTP请求
+转发给本地API并回传响应，使机器人主人无需端口映射即可在局域网外访问机器人
+"""
+
+import asyncio
+import base64
+import json
+import ssl
+from typing import Dict, Optional, Any
+from datetime import datetime
+
+import httpx
+import websockets
+
+from core.config import get_config
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 不转发的逐跳请求头
+HOP_BY_HOP_HEADERS = {"connection", "keep-alive", "transfer-encoding", "upgrade", "host", "content-length"}
+
+
+class RelayService:
+    """远程中继服务"""
+    
+    def __init__(self):
+        self.connected = False
+        self.connected_since: Optional[datetime] = None
+        self.last_error: Optional[str] = None
+        self.stats = {
+            "connections": 0,
+            "requests_forwarded": 0,
+            "requests_rejected": 0,
+            "requests_failed": 0,
+        }
+        
+        self.relay_task = None
+        self.http_client: Optional[httpx.AsyncClient] = None
+        self.semaphore = asyncio.Semaphore(config.relay.MAX_CONCURRENT_REQUESTS)
+    
+    @property
+    def is_running(self) -> bool:
+        return self.relay_task is not None and not self.relay_task.done()
+    
+    def _local_api_url(self) -> str:
+        if config.relay.LOCAL_API_URL:
+            return config.relay.LOCAL_API_URL
+        host = "127.0.0.1" if config.HOST in ("0.0.0.0", "::") else config.HOST
+        return f"http://{host}:{config.PORT}"
+    
+    def _validate_settings(self):
+        relay = config.relay
+        if not relay.BROKER_URL:
+            raise ValueError("未配置中继服务器地址")
+        if not relay.TOKEN:
+            raise ValueError("未配置中继认证令牌")
+        if not relay.BROKER_URL.startswith("wss://") and not relay.ALLOW_INSECURE:
+            raise ValueError("中继服务器必须使用wss://（TLS）连接")
+    
+    async def start(self) -> bool:
+        """启动中继连接"""
+        try:
+            if self.is_running:
+                logger.info("远程中继已经在运行")
+                return True
+            
+            self._validate_settings()
+            
+            self.http_client = httpx.AsyncClient(
+                base_url=self._local_api_url(),
+                timeout=config.relay.REQUEST_TIMEOUT
+            )
+            self.relay_task = asyncio.create_task(self._relay_loop())
+            
+            logger.info(f"远程中继已启动，服务器: {config.relay.BROKER_URL}")
+            return True
+            
+        except Exception as e:
+            self.last_error = str(e)
+            logger.error(f"启动远程中继失败: {e}")
+            return False
+    
+    async def stop(self):
+        """停止中继连接"""
+        try:
+            if self.relay_task and not self.relay_task.done():
+                self.relay_task.cancel()
+                try:
+                    await self.relay_task
+                except asyncio.CancelledError:
+                    pass
+            self.relay_task = None
+            
+            if self.http_client:
+                await self.http_client.aclose()
+                self.http_client = None
+            
+            self.connected = False
+            self.connected_since = None
+            logger.info("远程中继已停止")
+            
+        except Exception as e:
+            logger.error(f"停止远程中继时出错: {e}")
+    
+    async def _relay_loop(self):
+        """连接中继服务器，断线后按指数退避重连"""
+        relay = config.relay
+        delay = relay.RECONNECT_INTERVAL
+        headers = {
+            "Authorization": f"Bearer {relay.TOKEN}",
+            "X-Robot-Id": relay.ROBOT_ID,
+        }
+        ssl_context = ssl.create_default_context() if relay.BROKER_URL.startswith("wss://") else None
+        
+        while True:
+            try:
+                async with websockets.connect(relay.BROKER_URL, extra_headers=headers, ssl=ssl_context) as websocket:
+                    self.connected = True
+                    self.connected_since = datetime.now()
+                    self.last_error = None
+                    self.stats["connections"] += 1
+                    delay = relay.RECONNECT_INTERVAL
+                    logger.info("已连接到中继服务器")
+                    
+                    await self._serve(websocket)
+                    
+            except asyncio.CancelledError:
+                break
+            except Exception as e:
+                self.last_error = str(e)
+                logger.warning(f"中继连接断开: {e}，{delay:.0f}秒后重连")
+            finally:
+                self.connected = False
+                self.connected_since = None
+            
+            await asyncio.sleep(delay)
+            delay = min(delay * 2, relay.MAX_RECONNECT_INTERVAL)
+    
+    async def _serve(self, websocket):
+        """处理中继服务器发来的消息"""
+        pending = set()
+        try:
+            async for raw in websocket:
+                message = json.loads(raw)
+                message_type = message.get("type")
+                
+                if message_type == "ping":
+                    await websocket.send(json.dumps({"type": "pong", "timestamp": datetime.now().isoformat()}))
+                    
+                elif message_type == "request":
+                    task = asyncio.create_task(self._handle_request(websocket, message))
+                    pending.add(task)
+                    task.add_done_callback(pending.discard)
+                    
+                else:
+                    logger.debug(f"忽略未知的中继消息类型: {message_type}")
+        finally:
+            for task in pending:
+                task.cancel()
+    
+    def _is_forwardable(self, path: str) -> bool:
+        return any(path.startswith(prefix) for prefix in config.relay.FORWARD_PREFIXES)
+    
+    async def _handle_request(self, websocket, message: Dict[str, Any]):
+        """把一条隧道请求转发给本地API并回传响应"""
+        request_id = message.get("id")
+        path = message.get("path", "/")
+        
+        async with self.semaphore:
+            if not self._is_forwardable(path):
+                self.stats["requests_rejected"] += 1
+                response = self._response(request_id, 403, {}, b'{"detail": "path not allowed through relay"}')
+            else:
+                response = await self._forward(request_id, message)
+        
+        try:
+            await websocket.send(json.dumps(response))
+        except Exception as e:
+            logger.error(f"回传中继响应失败: {e}")
+    
+    async def _forward(self, request_id: Any, message: Dict[str, Any]) -> Dict[str, Any]:
+        try:
+            headers = {
+                k: v for k, v in (message.get("headers") or {}).items()
+                if k.lower() not in HOP_BY_HOP_HEADERS
+            }
+            body = base64.b64decode(message["body"]) if message.get("body") else None
+            
+            result = await self.http_client.request(
+                message.get("method", "GET"),
+                message.get("path", "/"),
+                params=message.get("query"),
+                headers=headers,
+                content=body
+            )
+            self.stats["requests_forwarded"] += 1
+            
+            response_headers = {
+                k: v for k, v in result.headers.items()
+                if k.lower() not in HOP_BY_HOP_HEADERS
+            }
+            return self._response(request_id, result.status_code, response_headers, result.content)
+            
+        except Exception as e:
+            self.stats["requests_failed"] += 1
+            logger.error(f"转发中继请求失败: {e}")
+            return self._response(request_id, 502, {}, json.dumps({"detail": str(e)}).encode())
+    
+    def _response(self, request_id: Any, status: int, headers: Dict[str, str], body: bytes) -> Dict[str, Any]:
+        return {
+            "type": "response",
+            "id": request_id,
+            "status": status,
+            "headers": headers,
+            "body": base64.b64encode(body).decode("ascii"),
+        }
+    
+    def get_status(self) -> Dict[str, Any]:
+        """获取中继状态（不包含令牌）"""
+        return {
+            "enabled": config.relay.ENABLED,
+            "running": self.is_running,
+            "connected": self.connected,
+            "connected_since": self.connected_since.isoformat() if self.connected_since else None,
+            "broker_url": config.relay.BROKER_URL,
+            "robot_id": config.relay.ROBOT_ID,
+            "last_error": self.last_error,
+            "stats": self.stats.copy(),
+        }
+
+
+# 全局远程中继服务实例
+relay_service = RelayService()
@@ -0,0 +1,331 @@
+#!/usr/bin/env python3
+"""
+数据保留服务
+按保留策略（最长期限、最大空间）定期清理录像、快照、日志和黑匣子记录，
+并提供"清除全部用户数据"功能，用于把机器人交给其他人之前使用
+"""
+
+import asyncio
+import shutil
+import time
+from dataclasses import dataclass
+from pathlib import Path
+from typing import Dict, List, Any
+from datetime import datetime, timedelta
+
+from core.config import get_config
+from core.database import get_database_manager
+from core.models import (
+    User, UserSession, Task, TaskLog, SystemLog, Configuration, FileStorage,
+    InteractionSession, InteractionEvent, ConversationMemory, JournalEntry
+)
+from services.analytics_service import analytics_service
+from services.presence_service import presence_service
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+
+@dataclass
+class RetentionPolicy:
+    """单类数据的保留策略，0表示不限制"""
+    name: str
+    directory: Path
+    max_age_days: float = 0.0
+    max_size_mb: int = 0
+
+
+class RetentionService:
+    """数据保留服务"""
+    
+    def __init__(self):
+        retention = config.retention
+        data_dir = Path(config.DATA_DIR)
+        
+        self.policies: Dict[str, RetentionPolicy] = {
+            "recordings": RetentionPolicy(
+                "recordings", data_dir / "recordings",
+                retention.RECORDINGS_MAX_AGE_DAYS, retention.RECORDINGS_MAX_SIZE_MB
+            ),
+            "snapshots": RetentionPolicy(
+                "snapshots", data_dir / "snapshots",
+                retention.SNAPSHOTS_MAX_AGE_DAYS, retention.SNAPSHOTS_MAX_SIZE_MB
+            ),
+            "logs": RetentionPolicy(
+                "logs", Path(config.LOGS_DIR),
+                retention.LOGS_MAX_AGE_DAYS, retention.LOGS_MAX_SIZE_MB
+            ),
+            "blackbox": RetentionPolicy(
+                "blackbox", data_dir / "blackbox",
+                retention.BLACKBOX_MAX_AGE_DAYS, retention.BLACKBOX_MAX_SIZE_MB
+            ),
+        }
+        
+        self.cleanup_task = None
+        self.last_cleanup: Dict[str, Any] = {}
+        self.lock = asyncio.Lock()
+        self.is_initialized = False
+    
+    async def initialize(self) -> bool:
+        """初始化数据保留服务，启动定期清理任务"""
+        try:
+            logger.info("正在初始化数据保留服务...")
+            
+            for policy in self.policies.values():
+                policy.directory.mkdir(parents=True, exist_ok=True)
+            
+            if config.retention.ENABLE_CLEANUP:
+                self.cleanup_task = asyncio.create_task(self._cleanup_loop())
+            else:
+                logger.info("定期清理已禁用")
+            
+            self.is_initialized = True
+            logger.info("数据保留服务初始化完成")
+            return True
+            
+        except Exception as e:
+            logger.error(f"数据保留服务初始化失败: {e}")
+            return False
+    
+    def _list_files(self, directory: Path) -> List[Path]:
+        if not directory.exists():
+            return []
+        return [path for path in directory.rglob("*") if path.is_file()]
+    
+    def _delete_file(self, path: Path) -> int:
+        """删除文件，返回释放的字节数"""
+        try:
+            size = path.stat().st_size
+            path.unlink()
+            return size
+        except FileNotFoundError:
+            return 0
+    
+    def _enforce_policy(self, policy: RetentionPolicy) -> Dict[str, Any]:
+        """按期限和空间限制清理一个目录
+        
+        先删除过期文件，再从最旧的文件开始删除直到满足空间限制。
+        最新的文件（例如正在写入的日志）不会因为空间限制被删除。
+        """
+        deleted: List[str] = []
+        freed = 0
+        
+        files = sorted(self._list_files(policy.directory), key=lambda p: p.stat().st_mtime)
+        
+        if policy.max_age_days > 0:
+            cutoff = time.time() - policy.max_age_days * 86400
+            expired = [path for path in files if path.stat().st_mtime < cutoff]
+            for path in expired:
+                freed += self._delete_file(path)
+                deleted.append(str(path))
+            files = [path for path in files if path not in expired]
+        
+        if policy.max_size_mb > 0:
+            limit = policy.max_size_mb * 1024 * 1024
+            total = sum(path.stat().st_size for path in files)
+            for path in files[:-1]:
+                if total <= limit:
+                    break
+                size = self._delete_file(path)
+                total -= size
+                freed += size
+                deleted.append(str(path))
+        
+        return {"deleted_files": deleted, "freed_bytes": freed}
+    
+    def _cleanup_database(self) -> Dict[str, int]:
+        """清理过期的日志、交互记录、事件日志和对话记忆，以及文件已被删除的文件记录"""
+        retention = config.retention
+        now = datetime.now()
+        result: Dict[str, int] = {}
+        
+        with get_database_manager().get_session() as session:
+            if retention.LOGS_MAX_AGE_DAYS > 0:
+                cutoff = now - timedelta(days=retention.LOGS_MAX_AGE_DAYS)
+                result["system_logs"] = session.query(SystemLog).filter(
+                    SystemLog.created_at < cutoff
+                ).delete(synchronize_session=False)
+                result["task_logs"] = session.query(TaskLog).filter(
+                    TaskLog.created_at < cutoff
+                ).delete(synchronize_session=False)
+            
+            if retention.INTERACTIONS_MAX_AGE_DAYS > 0:
+                cutoff = now - timedelta(days=retention.INTERACTIONS_MAX_AGE_DAYS)
+                result["interaction_events"] = session.query(InteractionEvent).filter(
+                    InteractionEvent.created_at < cutoff
+                ).delete(synchronize_session=False)
+                
+                # 当前会话没有结束时间，不会被删除
+                old_sessions = [
+                    row.id for row in session.query(InteractionSession.id).filter(
+                        InteractionSession.ended_at < cutoff
+                    )
+                ]
+                if old_sessions:
+                    session.query(InteractionEvent).filter(
+                        InteractionEvent.session_id.in_(old_sessions)
+                    ).delete(synchronize_session=False)
+                    session.query(InteractionSession).filter(
+                        InteractionSession.id.in_(old_sessions)
+                    ).delete(synchronize_session=False)
+                result["interaction_sessions"] = len(old_sessions)
+            
+            if retention.JOURNAL_MAX_AGE_DAYS > 0:
+                cutoff = now - timedelta(days=retention.JOURNAL_MAX_AGE_DAYS)
+                result["event_journal"] = session.query(JournalEntry).filter(
+                    JournalEntry.occurred_at < cutoff
+                ).delete(synchronize_session=False)
+            
+            if retention.MEMORIES_MAX_AGE_DAYS > 0:
+                cutoff = now - timedelta(days=retention.MEMORIES_MAX_AGE_DAYS)
+                result["conversation_memories"] = session.query(ConversationMemory).filter(
+                    ConversationMemory.last_seen_at < cutoff
+                ).delete(synchronize_session=False)
+            
+            orphaned = [
+                record for record in session.query(FileStorage).all()
+                if not Path(record.file_path).exists()
+            ]
+            for record in orphaned:
+                session.delete(record)
+            result["file_records"] = len(orphaned)
+        
+        return result
+    
+    def _run_cleanup(self) -> Dict[str, Any]:
+        report: Dict[str, Any] = {"timestamp": datetime.now().isoformat(), "policies": {}}
+        
+        for name, policy in self.policies.items():
+            result = self._enforce_policy(policy)
+            report["policies"][name] = {
+                "deleted_files": len(result["deleted_files"]),
+                "freed_bytes": result["freed_bytes"],
+            }
+        
+        try:
+            report["database"] = self._cleanup_database()
+        except Exception as e:
+            logger.error(f"清理数据库记录失败: {e}")
+            report["database"] = {}
+        
+        return report
+    
+    async def run_cleanup(self) -> Dict[str, Any]:
+        """立即执行一次清理"""
+        async with self.lock:
+            report = await asyncio.to_thread(self._run_cleanup)
+        
+        self.last_cleanup = report
+        deleted = sum(item["deleted_files"] for item in report["policies"].values())
+        logger.info(f"数据清理完成，删除 {deleted} 个文件")
+        return report
+    
+    async def _cleanup_loop(self):
+        """定期清理循环"""
+        while True:
+            try:
+                await self.run_cleanup()
+                await asyncio.sleep(config.retention.CLEANUP_INTERVAL)
+            except asyncio.CancelledError:
+                break
+            except Exception as e:
+                logger.error(f"定期数据清理出错: {e}")
+                await asyncio.sleep(config.retention.CLEANUP_INTERVAL)
+    
+    def get_usage(self) -> Dict[str, Any]:
+        """获取各类数据的占用情况和保留策略"""
+        usage = {}
+        for name, policy in self.policies.items():
+            files = self._list_files(policy.directory)
+            usage[name] = {
+                "directory": str(policy.directory),
+                "files": len(files),
+                "size_bytes": sum(path.stat().st_size for path in files),
+                "max_age_days": policy.max_age_days,
+                "max_size_mb": policy.max_size_mb,
+            }
+        
+        return {
+            "policies": usage,
+            "interactions_max_age_days": config.retention.INTERACTIONS_MAX_AGE_DAYS,
+            "cleanup_enabled": self.cleanup_task is not None,
+            "cleanup_interval": config.retention.CLEANUP_INTERVAL,
+            "last_cleanup": self.last_cleanup,
+        }
+    
+    def _wipe(self) -> Dict[str, Any]:
+        """删除所有用户数据文件和数据库记录，系统配置保留"""
+        report: Dict[str, Any] = {"files": {}, "database": {}}
+        
+        for name, policy in self.policies.items():
+            files = self._list_files(policy.directory)
+            report["files"][name] = len(files)
+            
+            if name == "logs":
+                # 日志处理器仍然持有当前日志文件，清空内容而不是删除
+                for path in files:
+                    if path.suffix == ".log":
+                        path.write_bytes(b"")
+                    else:
+                        self._delete_file(path)
+                continue
+            
+            if policy.directory.exists():
+                shutil.rmtree(policy.directory, ignore_errors=True)
+            policy.directory.mkdir(parents=True, exist_ok=True)
+        
+        with get_database_manager().get_session() as session:
+            stored_files = session.query(FileStorage).all()
+            for record in stored_files:
+                self._delete_file(Path(record.file_path))
+            report["files"]["stored"] = len(stored_files)
+            
+            # 按外键依赖顺序删除
+            for model in (InteractionEvent, InteractionSession, ConversationMemory, JournalEntry, TaskLog, Task,
+                          FileStorage, SystemLog, UserSession, User):
+                report["database"][model.__tablename__] = session.query(model).delete(
+                    synchronize_session=False
+                )
+            
+            report["database"]["configurations"] = session.query(Configuration).filter(
+                Configuration.is_system.is_(False)
+            ).delete(synchronize_session=False)
+        
+        return report
+    
+    async def wipe_user_data(self) -> Dict[str, Any]:
+        """清除全部用户数据"""
+        logger.warning("正在清除全部用户数据...")
+        
+        async with self.lock:
+            report = await asyncio.to_thread(self._wipe)
+        
+        await analytics_service.reset()
+        report["presence_cells"] = presence_service.clear()
+        
+        report["timestamp"] = datetime.now().isoformat()
+        logger.warning("全部用户数据已清除")
+        return report
+    
+    async def cleanup(self):
+        """清理资源"""
+        try:
+            if self.cleanup_task and not self.cleanup_task.done():
+                self.cleanup_task.cancel()
+                try:
+                    await self.cleanup_task
+                except asyncio.CancelledError:
+                    pass
+            
+            self.is_initialized = False
+            logger.info("数据保留服务清理完成")
+            
+        except Exception as e:
+            logger.error(f"数据保留服务清理时出错: {e}")
+
+
+# 全局数据保留服务实例
+retention_service = RetentionService()
@@ -13,6 +13,7 @@ import numpy as np
 from dataclasses import dataclass
 
 from core.config import get_config
+from services.analytics_service import analytics_service
 from utils.logger import setup_logger
 
 # 获取配置
@@ -121,7 +122,7 @@ class RobotService:
                     logger.info("机器人已经连接")
                     return True
                 
-                logger.info(f"正在连接到机器人: {settings.REACHY_HOST}:{settings.REACHY_PORT}")
+                logger.info(f"正在连接到机器人: {config.robot.SERIAL_PORT}")
                 
                 # 实际连接代码
                 # if self.reachy_sdk:
@@ -181,6 +182,7 @@ class RobotService:
             
             # 模拟运动
             await self._simulate_head_movement(pan, tilt, speed)
+            await analytics_service.record_command("move_head", {"pan": pan, "tilt": tilt, "speed": speed})
             
             return True
             
@@ -208,6 +210,7 @@ class RobotService:
             
             # 模拟运动
             await self._simulate_body_movement(x, y, z, speed)
+            await analytics_service.record_command("move_body", {"x": x, "y": y, "z": z, "speed": speed})
             
             return True
             
@@ -229,6 +232,7 @@ class RobotService:
             
             # 模拟停止
             await asyncio.sleep(0.1)
+            await analytics_service.record_event("emergency_stop", {"source": "api"})
             
             return True
             
@@ -236,6 +240,28 @@ class RobotService:
             logger.error(f"停止运动失败: {e}")
             return False
     
+    async def set_pose(self, head: Dict[str, float], body: Dict[str, float]) -> bool:
+        """直接设置目标姿态（用于跟随等连续流式控制，不做插值，不记录分析事件）
+        
+        未给出的关节保持不变，超出范围的值被截断
+        """
+        if not self.is_connected:
+            return False
+        
+        for joint, value in head.items():
+            if joint in self.head_limits:
+                self.robot_state.head_position[joint] = self._clamp_value(value, *self.head_limits[joint])
+        for axis, value in body.items():
+            if axis in self.body_limits:
+                self.robot_state.body_position[axis] = self._clamp_value(value, *self.body_limits[axis])
+        
+        # 实际控制代码
+        # if self.reachy_sdk:
+        #     await self.reachy_sdk.set_goal_positions(self.robot_state.head_position, self.robot_state.body_position)
+        
+        self.robot_state.last_update = datetime.now()
+        return True
+    
     async def get_robot_state(self) -> RobotState:
         """获取机器人状态"""
         return self.robot_state
@@ -18,6 +18,7 @@ import threading
 from queue import Queue, Empty
 
 from core.config import get_config
+from services.privacy_service import privacy_service
 from utils.logger import setup_logger
 
 # 获取配置
@@ -132,6 +133,13 @@ class VideoCapture:
             self.cap.release()
             self.cap = None
         
+        # 丢弃已缓存的帧
+        while not self.frame_queue.empty():
+            try:
+                self.frame_queue.get_nowait()
+            except Empty:
+                break
+        
         self.is_opened = False
         logger.info("摄像头已关闭")
 
@@ -162,9 +170,16 @@ class StreamService:
         # 帧处理回调
         self.frame_callbacks = []
         
+        # 隐私模式开启时立即停止采集并释放摄像头
+        privacy_service.register_pipeline("camera", self._stop_for_privacy)
+        
     async def initialize(self, camera_source: int = 0) -> bool:
         """初始化视频流服务"""
         try:
+            if not privacy_service.is_capture_allowed():
+                logger.warning("隐私模式已开启，不打开摄像头")
+                return False
+            
             logger.info(f"正在初始化视频流服务，摄像头源: {camera_source}")
             
             # 创建视频捕获对象
@@ -195,6 +210,9 @@ class StreamService:
                 logger.info("视频流已经在运行")
                 return True
             
+            if not privacy_service.is_capture_allowed():
+                raise Exception("隐私模式已开启")
+            
             if not self.video_capture or not self.video_capture.is_opened:
                 raise Exception("摄像头未初始化或未打开")
             
@@ -248,14 +266,25 @@ class StreamService:
         except Exception as e:
             logger.error(f"停止视频流时出错: {e}")
     
+    async def _stop_for_privacy(self):
+        """隐私模式：停止视频流并关闭摄像头"""
+        await self.stop_streaming()
+        
+        if self.video_capture:
+            self.video_capture.close()
+    
     async def _stream_loop(self):
         """视频流处理循环"""
-        frame_interval = 1.0 / settings.STREAM_FPS
+        frame_interval = 1.0 / config.stream.CAMERA_FPS
         
         while self.is_streaming:
             try:
                 start_time = asyncio.get_event_loop().time()
                 
+                # 隐私模式下不再分发任何帧
+                if not privacy_service.is_capture_allowed():
+                    break
+                
                 # 获取最新帧
                 frame_data = self.video_capture.get_frame()
                 if frame_data is None:
@@ -314,7 +343,7 @@ class StreamService:
         """发送帧给所有客户端"""
         try:
             # 编码为JPEG
-            _, buffer = cv2.imencode('.jpg', frame, [cv2.IMWRITE_JPEG_QUALITY, settings.STREAM_QUALITY])
+            _, buffer = cv2.imencode('.jpg', frame, [cv2.IMWRITE_JPEG_QUALITY, config.stream.JPEG_QUALITY])
             
             # 转换为base64
             frame_base64 = base64.b64encode(buffer).decode('utf-8')
@@ -405,6 +434,9 @@ class StreamService:
     async def capture_snapshot(self) -> Optional[str]:
         """捕获快照"""
         try:
+            if not privacy_service.is_capture_allowed():
+                raise Exception("隐私模式已开启")
+            
             if not self.video_capture:
                 raise Exception("摄像头未初始化")
             
@@ -432,11 +464,12 @@ class StreamService:
         return {
             "is_streaming": self.is_streaming,
             "camera_opened": self.video_capture.is_opened if self.video_capture else False,
+            "privacy_mode": privacy_service.enabled,
             "stream_settings": {
-                "width": settings.STREAM_WIDTH,
-                "height": settings.STREAM_HEIGHT,
-                "fps": settings.STREAM_FPS,
-                "quality": settings.STREAM_QUALITY
+                "width": config.stream.CAMERA_WIDTH,
+                "height": config.stream.CAMERA_HEIGHT,
+                "fps": config.stream.CAMERA_FPS,
+                "quality": config.stream.JPEG_QUALITY
             },
             "stats": self.stream_stats.copy(),
             "processors_count": len(self.frame_processors),
@@ -0,0 +1,248 @@
+#!/usr/bin/env python3
+"""
+主从同步服务
+本机作为从机连接主机器人的控制WebSocket，协商 topics 能力后订阅 joints 主题，
+把收到的关节轨迹按缩放系数映射后在本机复现，可用于演示或用本地机器人操纵远程机器人。
+主机器人不需要额外设置，joints 主题按从机订阅的频率推送
+
+网络延迟由延迟补偿抵消：按主机相邻两次数据的时间戳估算关节速度，向前预测
+LATENCY_COMPENSATION 秒（不超过 MAX_EXTRAPOLATION）；每次移动不超过本机速度限制
+"""
+
+import asyncio
+import json
+import ssl
+import time
+from datetime import datetime
+from typing import Any, Dict, Optional
+
+import websockets
+
+from core.config import get_config
+from core.identity import get_identity
+from core.protocol import PROTOCOL_VERSION
+from services.robot_service import robot_service
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+
+class JointFollower:
+    """把主机的关节数据换算成本机的目标姿态"""
+    
+    def __init__(self, latency_compensation: float, max_extrapolation: float,
+                 head_scale: float, body_scale: float):
+        self.latency_compensation = min(latency_compensation, max_extrapolation)
+        self.head_scale = head_scale
+        self.body_scale = body_scale
+        self.last_timestamp: Optional[float] = None
+        self.last_joints: Optional[Dict[str, Dict[str, float]]] = None
+    
+    def update(self, timestamp_ms: float, joints: Dict[str, Dict[str, float]]) -> Optional[Dict[str, Dict[str, float]]]:
+        """处理一条关节数据，返回目标姿态；乱序到达的旧数据返回None"""
+        timestamp = timestamp_ms / 1000.0
+        if self.last_timestamp is not None and timestamp <= self.last_timestamp:
+            return None
+        
+        dt = timestamp - self.last_timestamp if self.last_timestamp is not None else None
+        previous = self.last_joints or {}
+        self.last_timestamp, self.last_joints = timestamp, joints
+        
+        target = {}
+        for part, scale in (("head", self.head_scale), ("body", self.body_scale)):
+            values = joints.get(part) or {}
+            target[part] = {}
+            for joint, value in values.items():
+                last = (previous.get(part) or {}).get(joint)
+                if dt and last is not None and self.latency_compensation > 0:
+                    value = value + (value - last) / dt * self.latency_compensation
+                target[part][joint] = value * scale
+        return target
+
+
+def _limit_step(current: Dict[str, float], target: Dict[str, float], max_step: float) -> Dict[str, float]:
+    """每个关节朝目标移动不超过max_step"""
+    return {
+        joint: current.get(joint, value) + max(-max_step, min(max_step, value - current.get(joint, value)))
+        for joint, value in target.items()
+    }
+
+
+class SyncService:
+    """主从同步服务（从机端）"""
+    
+    def __init__(self):
+        self.leader_url: Optional[str] = None
+        self.leader: Optional[Dict[str, Any]] = None
+        self.connected = False
+        self.last_error: Optional[str] = None
+        self.last_sample: Optional[float] = None
+        self.settings: Dict[str, float] = {}
+        self.stats = {
+            "connections": 0,
+            "samples": 0,
+            "applied": 0,
+            "out_of_order": 0,
+        }
+        
+        self.follow_task = None
+        self._last_apply: Optional[float] = None
+    
+    @property
+    def is_running(self) -> bool:
+        return self.follow_task is not None and not self.follow_task.done()
+    
+    @property
+    def is_stale(self) -> bool:
+        return self.last_sample is None or time.monotonic() - self.last_sample > config.sync.STALE_TIMEOUT
+    
+    async def follow(self, leader_url: str, rate_hz: Optional[float] = None,
+                     latency_compensation: Optional[float] = None,
+                     head_scale: Optional[float] = None, body_scale: Optional[float] = None) -> bool:
+        """开始跟随主机器人，已在跟随时先断开原来的连接"""
+        if not leader_url.startswith(("ws://", "wss://")):
+            self.last_error = "主机器人地址必须以ws://或wss://开头"
+            return False
+        if self.is_running:
+            await self.stop()
+        
+        sync = config.sync
+        rate_hz = sync.RATE_HZ if rate_hz is None else rate_hz
+        if not 0 < rate_hz <= 100:
+            self.last_error = "推送频率必须在0到100Hz之间"
+            return False
+        
+        self.leader_url = leader_url
+        self.settings = {
+            "rate_hz": rate_hz,
+            "latency_compensation": sync.LATENCY_COMPENSATION if latency_compensation is None else max(0.0, latency_compensation),
+            "head_scale": sync.HEAD_SCALE if head_scale is None else head_scale,
+            "body_scale": sync.BODY_SCALE if body_scale is None else body_scale,
+        }
+        self.last_error = None
+        self.follow_task = asyncio.create_task(self._follow_loop())
+        logger.info(f"开始跟随主机器人 {leader_url}，参数: {self.settings}")
+        return True
+    
+    async def stop(self):
+        """停止跟随，本机保持当前姿态"""
+        if self.follow_task and not self.follow_task.done():
+            self.follow_task.cancel()
+            try:
+                await self.follow_task
+            except asyncio.CancelledError:
+                pass
+        self.follow_task = None
+        self.connected = False
+        self.leader = None
+        self.last_sample = None
+        logger.info("主从同步已停止")
+    
+    async def _follow_loop(self):
+        """连接主机器人，断线后按指数退避重连"""
+        sync = config.sync
+        delay = sync.RECONNECT_INTERVAL
+        ssl_context = ssl.create_default_context() if self.leader_url.startswith("wss://") else None
+        
+        while True:
+            try:
+                async with websockets.connect(self.leader_url, ssl=ssl_context) as websocket:
+                    await self._handshake(websocket)
+                    self.connected = True
+                    self.stats["connections"] += 1
+                    self.last_error = None
+                    delay = sync.RECONNECT_INTERVAL
+                    
+                    await self._receive(websocket)
+                    
+            except asyncio.CancelledError:
+                break
+            except Exception as e:
+                self.last_error = str(e)
+                logger.warning(f"与主机器人的连接断开: {e}，{delay:.0f}秒后重连")
+            finally:
+                self.connected = False
+            
+            await asyncio.sleep(delay)
+            delay = min(delay * 2, sync.MAX_RECONNECT_INTERVAL)
+    
+    async def _handshake(self, websocket):
+        """协商协议版本并订阅 joints 主题"""
+        identity = get_identity()
+        await websocket.send(json.dumps({
+            "type": "hello",
+            "protocol_version": PROTOCOL_VERSION,
+            "client": f"reachy-follower/{identity.uuid}",
+            "capabilities": ["topics"],
+        }))
+        
+        reply = json.loads(await websocket.recv())
+        if reply.get("type") != "hello":
+            raise ConnectionError(f"主机器人拒绝握手: {reply.get('message', reply)}")
+        if "topics" not in reply.get("capabilities", []):
+            raise ConnectionError("主机器人不支持主题订阅")
+        
+        self.leader = reply.get("robot")
+        if self.leader and self.leader.get("uuid") == identity.uuid:
+            raise ValueError("不能跟随本机")
+        
+        await websocket.send(json.dumps({"type": "subscribe", "topic": "joints", "rate_hz": self.settings["rate_hz"]}))
+        leader_name = self.leader.get("name") if self.leader else self.leader_url
+        logger.info(f"已连接主机器人 {leader_name}")
+    
+    async def _receive(self, websocket):
+        follower = JointFollower(
+            self.settings["latency_compensation"],
+            config.sync.MAX_EXTRAPOLATION,
+            self.settings["head_scale"],
+            self.settings["body_scale"],
+        )
+        self._last_apply = None
+        
+        async for raw in websocket:
+            message = json.loads(raw)
+            message_type = message.get("type")
+            
+            if message_type == "topic" and message.get("topic") == "joints":
+                self.stats["samples"] += 1
+                self.last_sample = time.monotonic()
+                target = follower.update(message.get("timestamp", 0), message.get("data") or {})
+                if target is None:
+                    self.stats["out_of_order"] += 1
+                    continue
+                await self._apply(target)
+                
+            elif message_type == "error":
+                raise ConnectionError(f"主机器人返回错误: {message.get('message')}")
+    
+    async def _apply(self, target: Dict[str, Dict[str, float]]):
+        """按本机速度限制移向目标姿态"""
+        now = time.monotonic()
+        dt = now - self._last_apply if self._last_apply is not None else 1.0 / self.settings["rate_hz"]
+        self._last_apply = now
+        
+        state = await robot_service.get_robot_state()
+        head = _limit_step(state.head_position, target.get("head", {}), robot_service.max_speed["head"] * dt)
+        body = _limit_step(state.body_position, target.get("body", {}), robot_service.max_speed["body"] * dt)
+        if await robot_service.set_pose(head, body):
+            self.stats["applied"] += 1
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "running": self.is_running,
+            "connected": self.connected,
+            "leader_url": self.leader_url,
+            "leader": self.leader,
+            "stale": self.is_running and self.is_stale,
+            "settings": self.settings,
+            "last_error": self.last_error,
+            "timestamp": datetime.now().isoformat(),
+            **self.stats,
+        }
+
+
+# 全局主从同步服务实例
+sync_service = SyncService()
@@ -0,0 +1,101 @@
+#!/usr/bin/env python3
+"""
+systemd集成
+以Type=notify服务运行时通过NOTIFY_SOCKET向systemd报告状态：启动自检通过后发送READY=1，
+健康检查正常时喂看门狗（WATCHDOG=1），并用STATUS=报告当前状态；
+没有NOTIFY_SOCKET（不是由systemd启动）时所有调用都不做任何事
+
+对应的服务单元示例：
+    [Service]
+    Type=notify
+    NotifyAccess=main
+    WatchdogSec=30
+    TimeoutStartSec=120
+    Restart=on-failure
+"""
+
+import os
+import socket
+from typing import Optional
+
+from core.config import get_config
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+
+class SystemdNotifier:
+    """sd_notify协议的实现（向NOTIFY_SOCKET发送数据报）"""
+    
+    def __init__(self):
+        self.address: Optional[str] = None
+        self.watchdog_usec: Optional[int] = None
+        self.ready_sent = False
+        self.last_status: Optional[str] = None
+    
+    @property
+    def enabled(self) -> bool:
+        return self.address is not None
+    
+    def initialize(self) -> bool:
+        """读取systemd传入的环境变量，返回是否由systemd以通知方式启动"""
+        if not config.systemd.ENABLED:
+            return False
+        
+        address = os.environ.get("NOTIFY_SOCKET")
+        if not address:
+            logger.debug("未设置NOTIFY_SOCKET，不启用systemd通知")
+            return False
+        # '@'开头表示抽象命名空间
+        self.address = "\0" + address[1:] if address.startswith("@") else address
+        
+        # WATCHDOG_PID不是本进程时看门狗是给其他进程的
+        watchdog_pid = os.environ.get("WATCHDOG_PID")
+        watchdog_usec = os.environ.get("WATCHDOG_USEC")
+        if watchdog_usec and (not watchdog_pid or int(watchdog_pid) == os.getpid()):
+            self.watchdog_usec = int(watchdog_usec)
+        
+        logger.info(f"已启用systemd通知（看门狗: {self.watchdog_interval() or '未启用'}）")
+        return True
+    
+    def notify(self, message: str) -> bool:
+        if not self.enabled:
+            return False
+        try:
+            with socket.socket(socket.AF_UNIX, socket.SOCK_DGRAM) as sock:
+                sock.sendto(message.encode("utf-8"), self.address)
+            return True
+        except OSError as e:
+            logger.warning(f"发送systemd通知失败: {e}")
+            return False
+    
+    def watchdog_interval(self) -> Optional[float]:
+        """喂看门狗的间隔（秒），按systemd的建议取超时的一部分"""
+        if self.watchdog_usec is None:
+            return None
+        return self.watchdog_usec / 1_000_000 * config.systemd.WATCHDOG_FRACTION
+    
+    def status(self, text: str):
+        """报告状态字符串（systemctl status中显示），内容不变时不重复发送"""
+        if text != self.last_status and self.notify(f"STATUS={text}"):
+            self.last_status = text
+    
+    def ready(self, text: str):
+        if self.notify(f"READY=1\nSTATUS={text}\nMAINPID={os.getpid()}"):
+            self.ready_sent = True
+            self.last_status = text
+            logger.info("已向systemd报告就绪")
+    
+    def watchdog(self):
+        if self.watchdog_usec is not None:
+            self.notify("WATCHDOG=1")
+    
+    def stopping(self):
+        self.notify("STOPPING=1\nSTATUS=正在停止")
+
+
+# 全局systemd通知实例
+systemd_notifier = SystemdNotifier()
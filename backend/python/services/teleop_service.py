@@ -0,0 +1,210 @@
+#!/usr/bin/env python3
+"""
+网页遥操作服务
+与Rust端 teleop 模块使用相同的消息格式：控制WebSocket协商出 teleop 能力后，客户端发送
+teleop 消息（递增序号 seq、死人开关 deadman、归一化摇杆轴 axes），每条消息同时是心跳。
+轴的值乘以机器人服务的最大速度得到速度，按固定频率积分为目标姿态
+
+超过 TELEOP_TIMEOUT 收不到心跳或松开死人开关时速度立即归零，并向客户端发送一次
+teleop_stopped 消息说明原因，积分循环随即退出，机器人停在当前姿态。同一时间只有一个连接
+可以遥操作，当前连接停止后其他连接才能接管
+
+每条要让机器人运动的teleop消息都经过Rust端的命令准入（角色检查、按来源优先级仲裁、写入
+命令审计日志），被拒绝时速度归零；Rust模块不可用时不能遥操作
+"""
+
+import asyncio
+import math
+import time
+from typing import Any, Dict, Optional, Tuple
+
+from fastapi import WebSocket
+
+from core.config import get_config
+from core.models import UserRole
+from rust_bindings import get_rust_bindings_manager, is_rust_available
+from services.robot_service import robot_service
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 心跳超时（秒），与Rust端 TELEOP_TIMEOUT 保持一致
+TELEOP_TIMEOUT = 0.3
+
+# 速度积分频率（Hz）
+CONTROL_RATE_HZ = 50.0
+
+# 摇杆轴到机器人服务关节的映射：轴 -> (部位, 关节)
+AXES = {
+    "head_pan": ("head", "pan"),
+    "head_tilt": ("head", "tilt"),
+    "body_x": ("body", "x"),
+    "body_y": ("body", "y"),
+    "body_z": ("body", "z"),
+}
+
+
+def normalize_axes(axes: Any) -> Dict[str, float]:
+    """截断到[-1, 1]，未给出的轴和非有限值按0处理"""
+    axes = axes if isinstance(axes, dict) else {}
+    result = {}
+    for axis in AXES:
+        value = axes.get(axis, 0.0)
+        valid = isinstance(value, (int, float)) and not isinstance(value, bool) and math.isfinite(value)
+        result[axis] = max(-1.0, min(1.0, float(value))) if valid else 0.0
+    return result
+
+
+ZERO_AXES = normalize_axes({})
+
+
+def teleop_origin(websocket: WebSocket, role: UserRole) -> Dict[str, Any]:
+    """命令来源，与Rust端 CommandOrigin 的JSON格式一致"""
+    client = websocket.client
+    client_id = f"{client.host}:{client.port}" if client else "unknown"
+    return {"source": "Teleop", "client_id": client_id, "role": role.value}
+
+
+class TeleopWatchdog:
+    """记录最近一次输入，按心跳和死人开关给出当前有效的轴"""
+    
+    def __init__(self):
+        self.seq: Optional[int] = None
+        self.deadman = False
+        self.axes = ZERO_AXES
+        self.last_heartbeat: Optional[float] = None
+        self.active = False
+    
+    def update(self, message: Dict[str, Any], now: float) -> bool:
+        """处理一条teleop消息（同时是心跳），乱序到达的旧消息返回False"""
+        seq = message["seq"]
+        if self.seq is not None and seq <= self.seq:
+            return False
+        self.seq = seq
+        self.deadman = bool(message.get("deadman", False))
+        self.axes = normalize_axes(message.get("axes"))
+        self.last_heartbeat = now
+        return True
+    
+    def is_engaged(self, now: float) -> bool:
+        """按住死人开关且心跳没有超时"""
+        return self.deadman and self.last_heartbeat is not None and now - self.last_heartbeat <= TELEOP_TIMEOUT
+    
+    def poll(self, now: float) -> Tuple[Dict[str, float], Optional[str]]:
+        """now时刻有效的轴；从遥操作转为停止时同时返回停止原因（每次停止只返回一次）"""
+        if self.last_heartbeat is None:
+            return ZERO_AXES, None
+        if now - self.last_heartbeat > TELEOP_TIMEOUT:
+            reason = "timeout"
+        elif not self.deadman:
+            reason = "deadman_released"
+        else:
+            self.active = True
+            return self.axes, None
+        
+        was_active, self.active = self.active, False
+        return ZERO_AXES, reason if was_active else None
+
+
+class TeleopService:
+    """网页遥操作服务"""
+    
+    def __init__(self):
+        self.websocket: Optional[WebSocket] = None
+        self.watchdog = TeleopWatchdog()
+        self.control_task: Optional[asyncio.Task] = None
+    
+    async def handle(self, websocket: WebSocket, message: Dict[str, Any], role: UserRole) -> Optional[Dict[str, Any]]:
+        """处理一条teleop消息，出错时返回要发送给客户端的错误消息"""
+        seq = message.get("seq")
+        if not isinstance(seq, int) or isinstance(seq, bool) or seq < 0:
+            return {"type": "error", "message": "teleop消息缺少有效的seq序号"}
+        
+        if not is_rust_available():
+            return {"type": "error", "message": "Rust模块不可用，遥操作命令无法仲裁和审计"}
+        
+        if self.websocket is not websocket:
+            # 当前连接停止遥操作后其他连接才能接管
+            if self.websocket is not None and self.watchdog.is_engaged(time.monotonic()):
+                return {"type": "error", "message": "另一个客户端正在遥操作"}
+            await self.release(self.websocket)
+            self.websocket = websocket
+            self.watchdog = TeleopWatchdog()
+            logger.info(f"遥操作连接: {websocket.client}")
+        
+        if not self.watchdog.update(message, time.monotonic()):
+            logger.debug(f"丢弃乱序的teleop消息: {seq}")
+            return None
+        
+        # 只有要运动的消息需要准入，松开死人开关或摇杆回中的心跳不产生命令
+        if self.watchdog.deadman and self.watchdog.axes != ZERO_AXES:
+            payload = {"type": "teleop", "seq": seq, "axes": self.watchdog.axes}
+            gate = get_rust_bindings_manager().get_command_gate(config.audit.DIRECTORY)
+            try:
+                await asyncio.to_thread(gate.submit, teleop_origin(websocket, role), payload)
+            except PermissionError as e:
+                self.watchdog.axes = ZERO_AXES
+                logger.warning(f"拒绝遥操作命令（{websocket.client}）: {e}")
+                return {"type": "error", "message": str(e)}
+        
+        if self.control_task is None or self.control_task.done():
+            self.control_task = asyncio.create_task(self._control_loop())
+        return None
+    
+    async def release(self, websocket: Optional[WebSocket]):
+        """连接断开或被接管时停止遥操作，机器人保持当前姿态"""
+        if websocket is None or self.websocket is not websocket:
+            return
+        
+        self.websocket = None
+        self.watchdog = TeleopWatchdog()
+        if self.control_task and not self.control_task.done():
+            self.control_task.cancel()
+            try:
+                await self.control_task
+            except asyncio.CancelledError:
+                pass
+        self.control_task = None
+    
+    async def _control_loop(self):
+        period = 1.0 / CONTROL_RATE_HZ
+        last = time.monotonic()
+        
+        while True:
+            await asyncio.sleep(period)
+            now = time.monotonic()
+            dt, last = now - last, now
+            
+            axes, reason = self.watchdog.poll(now)
+            if reason is not None:
+                if reason == "timeout":
+                    logger.warning(f"遥操作心跳超过 {TELEOP_TIMEOUT * 1000:.0f}ms 未收到，速度归零")
+                await self._notify({"type": "teleop_stopped", "reason": reason})
+            if not self.watchdog.active:
+                # 停止后不再积分，机器人停在当前姿态；下一条teleop消息重新启动循环
+                return
+            if axes == ZERO_AXES:
+                continue
+            
+            state = await robot_service.get_robot_state()
+            target = {"head": {}, "body": {}}
+            for axis, value in axes.items():
+                if value:
+                    part, joint = AXES[axis]
+                    current = (state.head_position if part == "head" else state.body_position)[joint]
+                    target[part][joint] = current + value * robot_service.max_speed[part] * dt
+            await robot_service.set_pose(target["head"], target["body"])
+    
+    async def _notify(self, message: Dict[str, Any]):
+        try:
+            if self.websocket is not None:
+                await self.websocket.send_json(message)
+        except Exception as e:
+            logger.debug(f"发送遥操作状态失败: {e}")
+
+
+# 全局遥操作服务实例
+teleop_service = TeleopService()
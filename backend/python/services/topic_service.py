@@ -0,0 +1,161 @@
+#!/usr/bin/env python3
+"""
+主题推送服务
+控制WebSocket协商出 topics 能力后，客户端按主题（joints、imu、detections、logs、events）
+订阅需要显示的数据并设置推送频率。各服务把数据发布到主题，本服务只推送给订阅了该主题
+且到了推送时间的连接；关节等状态类主题由注册的数据源按订阅的最高频率轮询
+"""
+
+import asyncio
+import logging
+from typing import Any, Awaitable, Callable, Dict, List, Optional
+
+from fastapi import WebSocket
+
+from core.protocol import TOPICS, ProtocolSession
+from utils.logger import setup_logger
+
+logger = setup_logger(__name__)
+
+# 没有连接订阅时数据源的检查间隔（秒）
+IDLE_POLL_INTERVAL = 0.5
+
+
+class TopicLogHandler(logging.Handler):
+    """把日志发布到 logs 主题"""
+    
+    def __init__(self, service: "TopicService"):
+        super().__init__(level=logging.INFO)
+        self.service = service
+    
+    def emit(self, record: logging.LogRecord):
+        # 推送失败时记录的日志不再推送，避免循环
+        if record.name == logger.name or not self.service.has_subscribers("logs"):
+            return
+        
+        self.service.publish_threadsafe("logs", {
+            "level": record.levelname,
+            "logger": record.name,
+            "message": record.getMessage(),
+            "time": record.created,
+        })
+
+
+class TopicService:
+    """主题推送服务"""
+    
+    def __init__(self):
+        self.clients: Dict[int, tuple] = {}
+        self.sources: Dict[str, Callable[[], Awaitable[Any]]] = {}
+        self.log_handler = TopicLogHandler(self)
+        self.stats = {
+            "published": 0,
+            "sent": 0,
+            "send_failed": 0,
+        }
+        
+        self._loop: Optional[asyncio.AbstractEventLoop] = None
+        self._tasks: List[asyncio.Task] = []
+    
+    def attach(self, websocket: WebSocket, session: ProtocolSession):
+        """登记一条控制连接，连接上的订阅保存在session中"""
+        self.clients[id(websocket)] = (websocket, session)
+    
+    def detach(self, websocket: WebSocket):
+        self.clients.pop(id(websocket), None)
+    
+    def has_subscribers(self, topic: str) -> bool:
+        return any(session.subscriptions.is_subscribed(topic) for _, session in list(self.clients.values()))
+    
+    def max_rate(self, topic: str) -> Optional[float]:
+        """所有连接中该主题的最高推送频率，没有订阅时为None"""
+        rates = [session.subscriptions.topics.get(topic) for _, session in list(self.clients.values())
+                 if session.subscriptions.is_subscribed(topic)]
+        rates = [rate for rate in rates if rate]
+        return max(rates) if rates else None
+    
+    def register_source(self, topic: str, source: Callable[[], Awaitable[Any]]):
+        """注册状态类主题的数据源，启动后按订阅的最高频率轮询"""
+        if topic not in TOPICS:
+            raise ValueError(f"未知的主题: {topic}")
+        self.sources[topic] = source
+    
+    async def publish(self, topic: str, data: Any):
+        """发布一条主题数据，只发给订阅了该主题且到了推送时间的连接"""
+        self.stats["published"] += 1
+        for websocket, session in list(self.clients.values()):
+            message = session.publish(topic, data)
+            if message is None:
+                continue
+            
+            try:
+                await websocket.send_json(message)
+                self.stats["sent"] += 1
+            except Exception as e:
+                self.stats["send_failed"] += 1
+                logger.debug(f"推送主题 {topic} 失败: {e}")
+                self.detach(websocket)
+    
+    def publish_threadsafe(self, topic: str, data: Any):
+        """从任意线程发布（用于日志），服务未启动时丢弃"""
+        loop = self._loop
+        if loop is None or loop.is_closed():
+            return
+        loop.call_soon_threadsafe(lambda: asyncio.ensure_future(self.publish(topic, data)))
+    
+    async def _poll_source(self, topic: str, source: Callable[[], Awaitable[Any]]):
+        while True:
+            rate = self.max_rate(topic)
+            if rate is None:
+                await asyncio.sleep(IDLE_POLL_INTERVAL)
+                continue
+            
+            try:
+                await self.publish(topic, await source())
+            except Exception as e:
+                logger.error(f"读取主题 {topic} 的数据失败: {e}")
+            await asyncio.sleep(1.0 / rate)
+    
+    def _loggers(self):
+        """根日志器以及不向上传播的日志器（setup_logger创建的日志器都不传播）"""
+        loggers = [logging.getLogger()]
+        for item in logging.root.manager.loggerDict.values():
+            if isinstance(item, logging.Logger) and not item.propagate:
+                loggers.append(item)
+        return loggers
+    
+    async def start(self):
+        """启动数据源轮询并开始转发日志"""
+        self._loop = asyncio.get_running_loop()
+        for topic, source in self.sources.items():
+            self._tasks.append(asyncio.create_task(self._poll_source(topic, source)))
+        
+        for item in self._loggers():
+            if self.log_handler not in item.handlers:
+                item.addHandler(self.log_handler)
+        
+        logger.info(f"主题推送已启动，数据源: {', '.join(self.sources) or '无'}")
+    
+    async def stop(self):
+        for item in self._loggers():
+            item.removeHandler(self.log_handler)
+        
+        for task in self._tasks:
+            task.cancel()
+        await asyncio.gather(*self._tasks, return_exceptions=True)
+        self._tasks.clear()
+        self._loop = None
+        self.clients.clear()
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "clients": len(self.clients),
+            "subscribers": {topic: sum(session.subscriptions.is_subscribed(topic) for _, session in list(self.clients.values()))
+                            for topic in TOPICS},
+            "sources": list(self.sources),
+            **self.stats,
+        }
+
+
+# 全局主题推送服务实例
+topic_service = TopicService()
@@ -0,0 +1,364 @@
+#!/usr/bin/env python3
+"""
+上传服务
+把较大的ONNX模型和动画包分块上传到数据目录：上传会话持久化在磁盘上，
+连接中断或服务重启后客户端可以从已接收的字节继续；每个分块可以附带校验和，
+完成时校验整个文件的SHA-256，通过后移入模型/动画目录并登记到文件存储表
+"""
+
+import asyncio
+import hashlib
+import json
+import os
+import re
+import time
+import uuid
+import zipfile
+from pathlib import Path
+from typing import Dict, List, Optional, Any
+
+from core.config import get_config
+from core.database import get_database_manager
+from core.models import FileStorage
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 可上传的资源类型，同时作为文件存储表中的related_type
+ASSET_KINDS = ("model", "animation")
+
+MIME_TYPES = {
+    ".onnx": "application/octet-stream",
+    ".json": "application/json",
+    ".zip": "application/zip",
+}
+
+UPLOAD_ID_PATTERN = re.compile(r"^[0-9a-f]{32}$")
+SHA256_PATTERN = re.compile(r"^[0-9a-f]{64}$")
+
+MB = 1024 * 1024
+
+
+class UploadOffsetError(Exception):
+    """分块偏移与已接收的字节数不一致，客户端应从expected_offset继续"""
+    
+    def __init__(self, expected_offset: int):
+        super().__init__(f"分块偏移不正确，应从 {expected_offset} 字节继续")
+        self.expected_offset = expected_offset
+
+
+def _file_sha256(path: Path) -> str:
+    digest = hashlib.sha256()
+    with open(path, "rb") as f:
+        for block in iter(lambda: f.read(MB), b""):
+            digest.update(block)
+    return digest.hexdigest()
+
+
+def _storage_to_dict(record: FileStorage) -> Dict[str, Any]:
+    return {
+        "id": record.id,
+        "kind": record.related_type,
+        "filename": record.filename,
+        "original_filename": record.original_filename,
+        "path": record.file_path,
+        "size": record.file_size,
+        "mime_type": record.mime_type,
+        "sha256": record.file_hash,
+        "created_at": record.created_at.isoformat() if record.created_at else None,
+    }
+
+
+class UploadService:
+    """上传服务"""
+    
+    def __init__(self):
+        data_dir = Path(config.DATA_DIR)
+        self.upload_dir = data_dir / "uploads"
+        self.asset_dirs = {
+            "model": data_dir / "models",
+            "animation": data_dir / "animations",
+        }
+        # 同一个上传的分块按顺序写入
+        self.locks: Dict[str, asyncio.Lock] = {}
+    
+    @property
+    def max_file_size(self) -> int:
+        return config.upload.MAX_FILE_SIZE_MB * MB
+    
+    @property
+    def max_chunk_size(self) -> int:
+        return config.upload.MAX_CHUNK_SIZE_MB * MB
+    
+    def _extensions(self, kind: str) -> List[str]:
+        if kind == "model":
+            return config.upload.MODEL_EXTENSIONS
+        return config.upload.ANIMATION_EXTENSIONS
+    
+    def _session_path(self, upload_id: str) -> Path:
+        if not UPLOAD_ID_PATTERN.match(upload_id):
+            raise KeyError(f"上传不存在: {upload_id}")
+        return self.upload_dir / f"{upload_id}.json"
+    
+    def _part_path(self, upload_id: str) -> Path:
+        return self.upload_dir / f"{upload_id}.part"
+    
+    def _load_session(self, upload_id: str) -> Dict[str, Any]:
+        path = self._session_path(upload_id)
+        try:
+            with open(path, encoding="utf-8") as f:
+                return json.load(f)
+        except FileNotFoundError:
+            raise KeyError(f"上传不存在: {upload_id}")
+    
+    def _save_session(self, session: Dict[str, Any]):
+        session["updated_at"] = time.time()
+        path = self._session_path(session["upload_id"])
+        tmp = path.with_suffix(".tmp")
+        with open(tmp, "w", encoding="utf-8") as f:
+            json.dump(session, f, ensure_ascii=False)
+        os.replace(tmp, path)
+    
+    def _remove_session(self, upload_id: str):
+        for path in (self._session_path(upload_id), self._part_path(upload_id)):
+            path.unlink(missing_ok=True)
+        self.locks.pop(upload_id, None)
+    
+    def _lock(self, upload_id: str) -> asyncio.Lock:
+        if not self._session_path(upload_id).exists():
+            raise KeyError(f"上传不存在: {upload_id}")
+        return self.locks.setdefault(upload_id, asyncio.Lock())
+    
+    def _sessions(self) -> List[Dict[str, Any]]:
+        if not self.upload_dir.exists():
+            return []
+        
+        sessions = []
+        for path in self.upload_dir.glob("*.json"):
+            try:
+                sessions.append(self._load_session(path.stem))
+            except (KeyError, ValueError) as e:
+                logger.warning(f"忽略无效的上传会话 {path.name}: {e}")
+        return sessions
+    
+    def _cleanup_expired(self):
+        """删除超过保留时长仍未完成的上传"""
+        cutoff = time.time() - config.upload.SESSION_TTL_HOURS * 3600
+        for session in self._sessions():
+            if session["updated_at"] < cutoff and not self._lock(session["upload_id"]).locked():
+                logger.info(f"删除过期的上传: {session['filename']} ({session['upload_id']})")
+                self._remove_session(session["upload_id"])
+    
+    def _validate_request(self, filename: str, kind: str, size: int, sha256: str) -> str:
+        if kind not in ASSET_KINDS:
+            raise ValueError(f"不支持的资源类型: {kind}，可选 {list(ASSET_KINDS)}")
+        
+        name = Path(filename).name
+        if not name or name.startswith(".") or name != filename:
+            raise ValueError(f"文件名无效: {filename}")
+        
+        extension = Path(name).suffix.lower()
+        if extension not in self._extensions(kind):
+            raise ValueError(f"{kind} 只接受 {self._extensions(kind)} 文件")
+        
+        if not 0 < size <= self.max_file_size:
+            raise ValueError(f"文件大小必须在1字节到 {config.upload.MAX_FILE_SIZE_MB} MB之间")
+        
+        if not SHA256_PATTERN.match(sha256):
+            raise ValueError("sha256必须是64位小写十六进制字符串")
+        
+        return name
+    
+    async def create_upload(self, filename: str, kind: str, size: int, sha256: str) -> Dict[str, Any]:
+        """创建上传；同一文件已有未完成的上传时返回该上传，客户端从其offset继续"""
+        sha256 = sha256.lower()
+        filename = self._validate_request(filename, kind, size, sha256)
+        
+        self.upload_dir.mkdir(parents=True, exist_ok=True)
+        self._cleanup_expired()
+        
+        for session in self._sessions():
+            if (session["kind"], session["filename"], session["size"], session["sha256"]) == (kind, filename, size, sha256):
+                logger.info(f"继续上传 {filename}，已接收 {session['offset']}/{size} 字节")
+                return session
+        
+        upload_id = uuid.uuid4().hex
+        session = {
+            "upload_id": upload_id,
+            "kind": kind,
+            "filename": filename,
+            "size": size,
+            "sha256": sha256,
+            "offset": 0,
+            "chunk_size": config.upload.CHUNK_SIZE_MB * MB,
+            "created_at": time.time(),
+        }
+        self._part_path(upload_id).touch()
+        self._save_session(session)
+        
+        logger.info(f"开始上传 {kind} {filename}（{size} 字节）: {upload_id}")
+        return session
+    
+    def get_upload(self, upload_id: str) -> Dict[str, Any]:
+        """查询上传进度"""
+        return self._load_session(upload_id)
+    
+    def list_uploads(self) -> List[Dict[str, Any]]:
+        """未完成的上传"""
+        return sorted(self._sessions(), key=lambda session: session["created_at"])
+    
+    async def write_chunk(self, upload_id: str, offset: int, data: bytes,
+                          chunk_sha256: Optional[str] = None) -> Dict[str, Any]:
+        """在offset处追加一个分块，offset必须等于已接收的字节数"""
+        if len(data) > self.max_chunk_size:
+            raise ValueError(f"分块不能超过 {config.upload.MAX_CHUNK_SIZE_MB} MB")
+        
+        async with self._lock(upload_id):
+            session = self._load_session(upload_id)
+            
+            if offset != session["offset"]:
+                raise UploadOffsetError(session["offset"])
+            
+            if offset + len(data) > session["size"]:
+                raise ValueError("分块超出文件大小")
+            
+            if chunk_sha256 and hashlib.sha256(data).hexdigest() != chunk_sha256.lower():
+                raise ValueError("分块校验和不匹配")
+            
+            def append():
+                with open(self._part_path(upload_id), "r+b") as f:
+                    # 截断上次中断时可能写入的不完整数据
+                    f.truncate(offset)
+                    f.seek(offset)
+                    f.write(data)
+            
+            await asyncio.to_thread(append)
+            
+            session["offset"] = offset + len(data)
+            self._save_session(session)
+            return session
+    
+    def _validate_content(self, kind: str, path: Path, filename: str):
+        """检查文件内容与类型相符"""
+        extension = Path(filename).suffix.lower()
+        
+        if extension == ".json":
+            try:
+                with open(path, encoding="utf-8") as f:
+                    content = json.load(f)
+            except (UnicodeDecodeError, json.JSONDecodeError) as e:
+                raise ValueError(f"动画文件不是有效的JSON: {e}")
+            if not isinstance(content, dict):
+                raise ValueError("动画文件必须是JSON对象")
+                
+        elif extension == ".zip":
+            if not zipfile.is_zipfile(path):
+                raise ValueError("动画包不是有效的zip文件")
+    
+    def _register(self, session: Dict[str, Any], part: Path) -> Dict[str, Any]:
+        """移入资源目录并登记；内容相同的资源已存在时不重复保存"""
+        kind = session["kind"]
+        destination = self.asset_dirs[kind] / session["filename"]
+        
+        with get_database_manager().get_session() as db:
+            existing = db.query(FileStorage).filter(
+                FileStorage.related_type == kind,
+                FileStorage.file_hash == session["sha256"],
+            ).first()
+            if existing and Path(existing.file_path).exists():
+                part.unlink(missing_ok=True)
+                return {**_storage_to_dict(existing), "deduplicated": True}
+            
+            destination.parent.mkdir(parents=True, exist_ok=True)
+            os.replace(part, destination)
+            
+            # 同名文件被覆盖时更新原有记录
+            record = db.query(FileStorage).filter(FileStorage.file_path == str(destination)).first()
+            if record is None:
+                record = FileStorage(file_path=str(destination))
+                db.add(record)
+            
+            record.filename = destination.name
+            record.original_filename = session["filename"]
+            record.file_size = session["size"]
+            record.mime_type = MIME_TYPES.get(destination.suffix.lower(), "application/octet-stream")
+            record.file_hash = session["sha256"]
+            record.related_type = kind
+            db.flush()
+            
+            return {**_storage_to_dict(record), "deduplicated": False}
+    
+    async def complete_upload(self, upload_id: str) -> Dict[str, Any]:
+        """校验整个文件后登记为模型或动画"""
+        async with self._lock(upload_id):
+            session = self._load_session(upload_id)
+            
+            if session["offset"] != session["size"]:
+                raise UploadOffsetError(session["offset"])
+            
+            part = self._part_path(upload_id)
+            digest = await asyncio.to_thread(_file_sha256, part)
+            if digest != session["sha256"]:
+                # 已接收的数据不可信，从头重新上传
+                await asyncio.to_thread(part.write_bytes, b"")
+                session["offset"] = 0
+                self._save_session(session)
+                raise ValueError("文件校验和不匹配，需要重新上传")
+            
+            await asyncio.to_thread(self._validate_content, session["kind"], part, session["filename"])
+            asset = await asyncio.to_thread(self._register, session, part)
+            self._remove_session(upload_id)
+        
+        logger.info(f"上传完成，已登记 {asset['kind']} {asset['filename']}"
+                    f"{'（内容相同的资源已存在）' if asset['deduplicated'] else ''}")
+        return asset
+    
+    async def abort_upload(self, upload_id: str):
+        """放弃上传并删除已接收的数据"""
+        async with self._lock(upload_id):
+            self._load_session(upload_id)
+            self._remove_session(upload_id)
+        logger.info(f"上传已取消: {upload_id}")
+    
+    def list_assets(self, kind: Optional[str] = None) -> List[Dict[str, Any]]:
+        """已登记的模型和动画"""
+        kinds = [kind] if kind else list(ASSET_KINDS)
+        with get_database_manager().get_session() as db:
+            records = db.query(FileStorage).filter(
+                FileStorage.related_type.in_(kinds)
+            ).order_by(FileStorage.created_at).all()
+            return [_storage_to_dict(record) for record in records]
+    
+    def delete_asset(self, asset_id: str) -> Dict[str, Any]:
+        """删除已登记的模型或动画"""
+        with get_database_manager().get_session() as db:
+            record = db.query(FileStorage).filter(
+                FileStorage.id == asset_id,
+                FileStorage.related_type.in_(ASSET_KINDS),
+            ).first()
+            if record is None:
+                raise KeyError(f"资源不存在: {asset_id}")
+            
+            asset = _storage_to_dict(record)
+            Path(record.file_path).unlink(missing_ok=True)
+            db.delete(record)
+        
+        logger.info(f"已删除 {asset['kind']} {asset['filename']}")
+        return asset
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "pending_uploads": len(self._sessions()),
+            "max_file_size_mb": config.upload.MAX_FILE_SIZE_MB,
+            "chunk_size_mb": config.upload.CHUNK_SIZE_MB,
+            "max_chunk_size_mb": config.upload.MAX_CHUNK_SIZE_MB,
+            "model_extensions": config.upload.MODEL_EXTENSIONS,
+            "animation_extensions": config.upload.ANIMATION_EXTENSIONS,
+        }
+
+
+# 全局上传服务实例
+upload_service = UploadService()
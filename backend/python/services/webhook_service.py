@@ -0,0 +1,317 @@
+#!/usr/bin/env python3
+"""
+Webhook服务
+用户登记Webhook地址并选择事件类型（人脸识别、急停、行为结束等），事件发生时把事件
+POST到这些地址，无需编写代码即可触发外部自动化。请求体用登记时生成的密钥做HMAC-SHA256
+签名，失败时按指数退避重试
+
+签名方式：X-Reachy-Signature = "sha256=" + HMAC-SHA256(密钥, 时间戳 + "." + 请求体)，
+时间戳在X-Reachy-Timestamp请求头中，接收方应同时校验时间戳防止重放。
+事件详情与交互分析一样按隐私设置去掉可识别个人的字段
+"""
+
+import asyncio
+import hashlib
+import hmac
+import json
+import secrets
+import time
+import uuid
+from collections import deque
+from datetime import datetime
+from typing import Any, Deque, Dict, List, Optional
+from urllib.parse import urlparse
+
+import httpx
+
+from core.config import get_config
+from core.database import get_database_manager
+from core.identity import get_identity
+from core.models import Webhook
+from utils.logger import setup_logger
+
+# 获取配置
+config = get_config()
+
+logger = setup_logger(__name__)
+
+# 常用事件类型（其他交互事件类型同样可以订阅）
+KNOWN_EVENTS = {
+    "face_recognized": "认出已登记的人脸",
+    "face_seen": "出现新的人脸",
+    "emergency_stop": "急停",
+    "behavior_finished": "行为执行结束",
+    "greeting": "完成一次问候",
+    "command": "执行了一条运动命令",
+}
+
+ALL_EVENTS = "*"
+
+
+def sign(secret: str, timestamp: str, body: bytes) -> str:
+    """计算请求签名"""
+    digest = hmac.new(secret.encode(), timestamp.encode() + b"." + body, hashlib.sha256).hexdigest()
+    return f"sha256={digest}"
+
+
+def _to_dict(record: Webhook, include_secret: bool = False) -> Dict[str, Any]:
+    data = {
+        "id": record.id,
+        "url": record.url,
+        "description": record.description,
+        "event_types": list(record.event_types or []),
+        "enabled": record.enabled,
+        "last_delivery_at": record.last_delivery_at.isoformat() if record.last_delivery_at else None,
+        "last_status": record.last_status,
+        "failure_count": record.failure_count,
+        "created_at": record.created_at.isoformat() if record.created_at else None,
+    }
+    if include_secret:
+        data["secret"] = record.secret
+    return data
+
+
+class WebhookService:
+    """Webhook服务"""
+    
+    def __init__(self):
+        # 已启用的Webhook缓存，事件投递时不查询数据库
+        self.webhooks: List[Dict[str, Any]] = []
+        self.deliveries: Deque[Dict[str, Any]] = deque(maxlen=200)
+        self.pending = 0
+        self.stats = {
+            "delivered": 0,
+            "retried": 0,
+            "failed": 0,
+            "dropped": 0,
+        }
+        
+        self.http_client: Optional[httpx.AsyncClient] = None
+        self._tasks = set()
+    
+    def _validate_url(self, url: str):
+        parsed = urlparse(url)
+        if parsed.scheme not in ("http", "https") or not parsed.netloc:
+            raise ValueError("Webhook地址必须是http://或https://开头的完整地址")
+        if parsed.scheme == "http" and not config.webhook.ALLOW_INSECURE:
+            raise ValueError("Webhook地址必须使用https://（可通过WEBHOOK_ALLOW_INSECURE允许http）")
+    
+    def _validate_events(self, event_types: List[str]) -> List[str]:
+        event_types = sorted({event.strip() for event in event_types if event and event.strip()})
+        if not event_types:
+            raise ValueError("至少选择一种事件类型")
+        return event_types
+    
+    def _refresh_cache(self, session):
+        records = session.query(Webhook).filter(Webhook.enabled.is_(True)).all()
+        self.webhooks = [
+            {"id": r.id, "url": r.url, "secret": r.secret, "event_types": set(r.event_types or [])}
+            for r in records
+        ]
+    
+    def _load(self):
+        with get_database_manager().get_session() as session:
+            self._refresh_cache(session)
+    
+    def _list(self) -> List[Dict[str, Any]]:
+        with get_database_manager().get_session() as session:
+            return [_to_dict(r) for r in session.query(Webhook).order_by(Webhook.created_at).all()]
+    
+    async def list_webhooks(self) -> List[Dict[str, Any]]:
+        """登记的Webhook（不含密钥）"""
+        return await asyncio.to_thread(self._list)
+    
+    def _create(self, url: str, event_types: List[str], description: Optional[str]) -> Dict[str, Any]:
+        with get_database_manager().get_session() as session:
+            if session.query(Webhook).count() >= config.webhook.MAX_WEBHOOKS:
+                raise ValueError(f"最多登记{config.webhook.MAX_WEBHOOKS}个Webhook")
+            
+            record = Webhook(url=url, event_types=event_types, description=description,
+                             secret=secrets.token_hex(32), enabled=True, failure_count=0)
+            session.add(record)
+            session.flush()
+            self._refresh_cache(session)
+            return _to_dict(record, include_secret=True)
+    
+    async def create_webhook(self, url: str, event_types: List[str], description: Optional[str] = None) -> Dict[str, Any]:
+        """登记Webhook，返回结果中包含签名密钥（只返回这一次）"""
+        self._validate_url(url)
+        event_types = self._validate_events(event_types)
+        webhook = await asyncio.to_thread(self._create, url, event_types, description)
+        logger.info(f"登记Webhook {webhook['id']}: {url}，事件: {', '.join(event_types)}")
+        return webhook
+    
+    def _update(self, webhook_id: str, event_types: Optional[List[str]], enabled: Optional[bool],
+                description: Optional[str]) -> Optional[Dict[str, Any]]:
+        with get_database_manager().get_session() as session:
+            record = session.query(Webhook).filter(Webhook.id == webhook_id).first()
+            if record is None:
+                return None
+            if event_types is not None:
+                record.event_types = event_types
+            if enabled is not None:
+                record.enabled = enabled
+                if enabled:
+                    record.failure_count = 0
+            if description is not None:
+                record.description = description or None
+            session.flush()
+            self._refresh_cache(session)
+            return _to_dict(record)
+    
+    async def update_webhook(self, webhook_id: str, event_types: Optional[List[str]] = None,
+                             enabled: Optional[bool] = None, description: Optional[str] = None) -> Optional[Dict[str, Any]]:
+        """修改订阅的事件、启用状态或说明，Webhook不存在时返回None"""
+        if event_types is not None:
+            event_types = self._validate_events(event_types)
+        return await asyncio.to_thread(self._update, webhook_id, event_types, enabled, description)
+    
+    def _delete(self, webhook_id: str) -> bool:
+        with get_database_manager().get_session() as session:
+            deleted = session.query(Webhook).filter(Webhook.id == webhook_id).delete()
+            self._refresh_cache(session)
+            return deleted > 0
+    
+    async def delete_webhook(self, webhook_id: str) -> bool:
+        return await asyncio.to_thread(self._delete, webhook_id)
+    
+    def _record_result(self, webhook_id: str, status: str, success: bool):
+        with get_database_manager().get_session() as session:
+            record = session.query(Webhook).filter(Webhook.id == webhook_id).first()
+            if record is None:
+                return
+            record.last_delivery_at = datetime.now()
+            record.last_status = status[:200]
+            record.failure_count = 0 if success else (record.failure_count or 0) + 1
+    
+    def dispatch(self, event_type: str, data: Optional[Dict[str, Any]] = None) -> int:
+        """把事件投递给订阅了该类型的Webhook，返回投递的Webhook数"""
+        if not config.webhook.ENABLED or self.http_client is None:
+            return 0
+        
+        targets = [w for w in self.webhooks if event_type in w["event_types"] or ALL_EVENTS in w["event_types"]]
+        for webhook in targets:
+            if self.pending >= config.webhook.MAX_PENDING:
+                self.stats["dropped"] += 1
+                logger.warning(f"等待投递的事件过多，丢弃 {event_type} 事件")
+                continue
+            
+            payload = {
+                "id": str(uuid.uuid4()),
+                "event": event_type,
+                "robot": get_identity().to_dict(),
+                "timestamp": time.time(),
+                "data": data or {},
+            }
+            self.pending += 1
+            task = asyncio.ensure_future(self._deliver(webhook, payload))
+            self._tasks.add(task)
+            task.add_done_callback(self._tasks.discard)
+        return len(targets)
+    
+    async def _deliver(self, webhook: Dict[str, Any], payload: Dict[str, Any]):
+        """投递一个事件，失败时按指数退避重试"""
+        body = json.dumps(payload, ensure_ascii=False, default=str).encode()
+        backoff = config.webhook.INITIAL_BACKOFF
+        status = ""
+        success = False
+        
+        try:
+            for attempt in range(1, config.webhook.MAX_ATTEMPTS + 1):
+                # 每次尝试重新签名，时间戳反映实际发送时间
+                timestamp = str(int(time.time()))
+                headers = {
+                    "Content-Type": "application/json",
+                    "X-Reachy-Event": payload["event"],
+                    "X-Reachy-Robot": payload["robot"]["uuid"],
+                    "X-Reachy-Delivery": payload["id"],
+                    "X-Reachy-Timestamp": timestamp,
+                    "X-Reachy-Signature": sign(webhook["secret"], timestamp, body),
+                }
+                try:
+                    response = await self.http_client.post(webhook["url"], content=body, headers=headers)
+                    status = f"HTTP {response.status_code}"
+                    # 4xx（除超时和限流外）说明请求本身被拒绝，重试没有意义
+                    if response.status_code < 300:
+                        success = True
+                        break
+                    if 400 <= response.status_code < 500 and response.status_code not in (408, 429):
+                        break
+                except httpx.HTTPError as e:
+                    status = f"{type(e).__name__}: {e}"
+                
+                if attempt < config.webhook.MAX_ATTEMPTS:
+                    self.stats["retried"] += 1
+                    await asyncio.sleep(backoff)
+                    backoff = min(backoff * 2, config.webhook.MAX_BACKOFF)
+        finally:
+            self.pending -= 1
+        
+        self.stats["delivered" if success else "failed"] += 1
+        self.deliveries.append({
+            "webhook_id": webhook["id"],
+            "delivery_id": payload["id"],
+            "event": payload["event"],
+            "success": success,
+            "status": status,
+            "attempts": attempt,
+            "timestamp": datetime.now().isoformat(),
+        })
+        if not success:
+            logger.warning(f"Webhook {webhook['id']} 投递 {payload['event']} 事件失败: {status}")
+        
+        try:
+            await asyncio.to_thread(self._record_result, webhook["id"], status, success)
+        except Exception as e:
+            logger.debug(f"保存Webhook投递结果失败: {e}")
+    
+    async def send_test(self, webhook_id: str) -> bool:
+        """向一个Webhook发送测试事件（即使未订阅test事件），Webhook不存在或未启用时返回False"""
+        webhook = next((w for w in self.webhooks if w["id"] == webhook_id), None)
+        if webhook is None or self.http_client is None:
+            return False
+        
+        self.pending += 1
+        await self._deliver(webhook, {"id": str(uuid.uuid4()), "event": "test", "robot": get_identity().to_dict(),
+                                      "timestamp": time.time(), "data": {}})
+        return True
+    
+    async def start(self) -> bool:
+        """加载已登记的Webhook并开始投递"""
+        if not config.webhook.ENABLED:
+            logger.info("Webhook未启用")
+            return False
+        
+        try:
+            await asyncio.to_thread(self._load)
+        except Exception as e:
+            logger.error(f"加载Webhook失败: {e}")
+            return False
+        
+        self.http_client = httpx.AsyncClient(timeout=config.webhook.REQUEST_TIMEOUT)
+        logger.info(f"Webhook已启动，已启用 {len(self.webhooks)} 个")
+        return True
+    
+    async def stop(self):
+        """停止投递，正在重试的事件被丢弃"""
+        for task in list(self._tasks):
+            task.cancel()
+        await asyncio.gather(*self._tasks, return_exceptions=True)
+        
+        if self.http_client:
+            await self.http_client.aclose()
+            self.http_client = None
+        logger.info("Webhook已停止")
+    
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            "enabled": config.webhook.ENABLED,
+            "active_webhooks": len(self.webhooks),
+            "pending": self.pending,
+            "known_events": KNOWN_EVENTS,
+            **self.stats,
+        }
+
+
+# 全局Webhook服务实例
+webhook_service = WebhookService()
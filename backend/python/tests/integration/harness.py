@@ -0,0 +1,196 @@
+#!/usr/bin/env python3
+"""
+端到端集成测试
+在仿真环境中启动完整系统（仿真硬件、图片回放摄像头、mock AI推理、临时SQLite数据库），
+只通过公开的HTTP/WebSocket API驱动，检查状态切换、运动完成和事件顺序
+
+用法（在backend/python目录下）:
+    python tests/integration/harness.py            # 运行全部场景
+    python tests/integration/harness.py -k motion  # 只运行名称包含motion的场景
+    python tests/integration/harness.py --keep     # 保留临时目录（数据库、日志）便于排查
+
+任何场景失败时退出码为1
+"""
+
+import argparse
+import asyncio
+import os
+import shutil
+import socket
+import sys
+import tempfile
+import time
+import traceback
+from pathlib import Path
+from typing import Any, Dict, List, Optional
+
+from support import ScenarioFailure, expect
+
+BACKEND_DIR = Path(__file__).resolve().parents[2]
+
+
+def _free_port() -> int:
+    with socket.socket() as sock:
+        sock.bind(("127.0.0.1", 0))
+        return sock.getsockname()[1]
+
+
+def _write_playback_frames(directory: Path, count: int = 3):
+    """生成回放摄像头使用的纯色帧"""
+    from PIL import Image
+    
+    directory.mkdir(parents=True, exist_ok=True)
+    for index in range(count):
+        value = 40 + index * 80
+        Image.new("RGB", (64, 48), (value, value, value)).save(directory / f"frame_{index:03d}.png")
+
+
+def simulation_environment(work_dir: Path, port: int) -> Dict[str, str]:
+    """仿真运行的环境变量；配置在导入core.config时读取，必须先设置"""
+    return {
+        "HOST": "127.0.0.1",
+        "PORT": str(port),
+        "ENVIRONMENT": "testing",
+        "TESTING": "true",
+        "DATA_DIR": str(work_dir / "data"),
+        "LOGS_DIR": str(work_dir / "logs"),
+        "MODELS_DIR": str(work_dir / "models"),
+        "TEMP_DIR": str(work_dir / "temp"),
+        "DB_SQLITE_URL": f"sqlite:///{work_dir / 'harness.db'}",
+        "AUDIT_DIRECTORY": str(work_dir / "data" / "audit"),
+        "HARDWARE_ENABLE_HARDWARE": "false",
+        "HARDWARE_HARDWARE_TYPE": "simulation",
+        "VISION_PLAYBACK_PATH": str(work_dir / "frames"),
+        "AI_AI_DEVICE": "mock",
+        "AI_AI_MODEL_PATH": str(work_dir / "models"),
+        "SETUP_ENABLED": "false",
+        "NETWORK_ENABLED": "false",
+        "SYSTEMD_ENABLED": "false",
+    }
+
+
+class SimulationHarness:
+    """启动和停止仿真系统，提供访问公开API的客户端"""
+    
+    def __init__(self, work_dir: Path, port: int, startup_timeout: float = 30.0):
+        self.work_dir = work_dir
+        self.port = port
+        self.startup_timeout = startup_timeout
+        self.base_url = f"http://127.0.0.1:{port}"
+        self.ws_url = f"ws://127.0.0.1:{port}"
+        self.http = None
+        self._manager = None
+    
+    async def start(self):
+        import httpx
+        from service_manager import get_service_manager
+        
+        self._manager = get_service_manager()
+        await self._manager.start()
+        self.http = httpx.AsyncClient(base_url=self.base_url, timeout=10.0)
+        
+        # start()返回时服务器任务刚创建，等待端口可以访问
+        deadline = time.monotonic() + self.startup_timeout
+        while True:
+            try:
+                response = await self.http.get("/health")
+                if response.status_code == 200:
+                    return
+            except httpx.TransportError:
+                pass
+            if time.monotonic() > deadline:
+                raise RuntimeError(f"系统在{self.startup_timeout}秒内没有就绪")
+            await asyncio.sleep(0.1)
+    
+    async def stop(self):
+        if self.http is not None:
+            await self.http.aclose()
+        if self._manager is not None and self._manager.is_running():
+            await self._manager.stop()
+    
+    async def get_json(self, path: str, expected_status: int = 200, **kwargs) -> Any:
+        response = await self.http.get(path, **kwargs)
+        expect(response.status_code == expected_status,
+               f"GET {path} 返回 {response.status_code}（期望 {expected_status}）: {response.text}")
+        return response.json()
+    
+    async def post_json(self, path: str, body: Optional[Dict[str, Any]] = None, expected_status: int = 200) -> Any:
+        response = await self.http.post(path, json=body)
+        expect(response.status_code == expected_status,
+               f"POST {path} 返回 {response.status_code}（期望 {expected_status}）: {response.text}")
+        return response.json()
+    
+    async def wait_for(self, description: str, predicate, timeout: float = 5.0, interval: float = 0.05):
+        """轮询直到predicate()返回真值，超时则失败；predicate可以是协程函数"""
+        deadline = time.monotonic() + timeout
+        while True:
+            result = predicate()
+            if asyncio.iscoroutine(result):
+                result = await result
+            if result:
+                return result
+            if time.monotonic() > deadline:
+                raise ScenarioFailure(f"等待超时（{timeout}秒）: {description}")
+            await asyncio.sleep(interval)
+
+
+async def run_scenarios(harness: SimulationHarness, selected: List[Any]) -> List[Dict[str, Any]]:
+    results = []
+    for scenario in selected:
+        name = scenario.__name__
+        start = time.monotonic()
+        try:
+            await scenario(harness)
+            results.append({"name": name, "passed": True, "duration": time.monotonic() - start})
+            print(f"  ✅ {name} ({time.monotonic() - start:.2f}s)")
+        except Exception as e:
+            results.append({"name": name, "passed": False, "duration": time.monotonic() - start, "error": str(e)})
+            print(f"  ❌ {name} ({time.monotonic() - start:.2f}s): {e}")
+            if not isinstance(e, ScenarioFailure):
+                traceback.print_exc()
+    return results
+
+
+async def main_async(args) -> int:
+    work_dir = Path(tempfile.mkdtemp(prefix="reachy_integration_"))
+    port = args.port or _free_port()
+    
+    # 配置、alembic.ini和各服务模块都按backend/python目录解析相对路径
+    os.environ.update(simulation_environment(work_dir, port))
+    os.chdir(BACKEND_DIR)
+    sys.path.insert(0, str(BACKEND_DIR))
+    _write_playback_frames(work_dir / "frames")
+    
+    from scenarios import SCENARIOS
+    
+    selected = [scenario for scenario in SCENARIOS if not args.k or args.k in scenario.__name__]
+    if not selected:
+        print(f"没有名称包含 '{args.k}' 的场景")
+        return 1
+    
+    print(f"🧪 集成测试: {len(selected)} 个场景，工作目录 {work_dir}，端口 {port}")
+    harness = SimulationHarness(work_dir, port, startup_timeout=args.startup_timeout)
+    try:
+        await harness.start()
+        results = await run_scenarios(harness, selected)
+    finally:
+        await harness.stop()
+        if not args.keep:
+            shutil.rmtree(work_dir, ignore_errors=True)
+    
+    failed = [result for result in results if not result["passed"]]
+    print(f"\n{len(results) - len(failed)} 通过，{len(failed)} 失败")
+    return 1 if failed else 0
+
+
+def main():
+    parser = argparse.ArgumentParser(description="Reachy Mini 端到端集成测试")
+    parser.add_argument("-k", default=None, help="只运行名称包含该字符串的场景")
+    parser.add_argument("--port", type=int, default=None, help="API端口，默认随机选择空闲端口")
+    parser.add_argument("--keep", action="store_true", help="保留临时工作目录")
+    parser.add_argument("--startup-timeout", type=float, default=30.0, help="等待系统就绪的时间（秒）")
+    sys.exit(asyncio.run(main_async(parser.parse_args())))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,104 @@
+#!/usr/bin/env python3
+"""
+集成测试场景
+每个场景是一个接收SimulationHarness的协程函数，只通过HTTP/WebSocket访问系统；
+场景按SCENARIOS中的顺序在同一个系统实例上依次运行，结束时要把机器人恢复到已连接状态
+"""
+
+import asyncio
+import json
+
+import websockets
+
+from support import close_to, expect
+
+
+async def startup_reaches_ready(harness):
+    """启动完成后各组件就绪，机器人已连接"""
+    health = await harness.get_json("/health")
+    for component in ("database", "robot", "api_server", "websocket"):
+        expect(health["components"].get(component), f"组件 {component} 没有就绪: {health['components']}")
+    
+    status = await harness.get_json("/api/robot/status")
+    expect(status["connected"], "启动后机器人应为已连接")
+    expect(not status["privacy_mode"], "仿真环境不应处于隐私模式")
+
+
+async def connection_state_transitions(harness):
+    """断开后状态和健康检查反映断开，运动命令被拒绝；重新连接后恢复"""
+    await harness.post_json("/api/robot/disconnect")
+    status = await harness.get_json("/api/robot/status")
+    expect(not status["connected"], "断开后机器人仍显示已连接")
+    
+    health = await harness.get_json("/api/robot/health", expected_status=503)
+    expect(health["status"] == "disconnected", f"断开后健康状态应为disconnected: {health['status']}")
+    
+    result = await harness.post_json("/api/robot/head/move", {"pan": 10.0, "tilt": 0.0, "speed": 30.0})
+    expect(not result["success"], "未连接时头部运动命令不应成功")
+    
+    result = await harness.post_json("/api/robot/connect")
+    expect(result["success"], f"重新连接失败: {result['message']}")
+    status = await harness.get_json("/api/robot/status")
+    expect(status["connected"], "重新连接后机器人应为已连接")
+
+
+async def head_motion_completes(harness):
+    """运动过程中位置逐步接近目标，命令返回时到达目标"""
+    start = (await harness.get_json("/api/robot/status"))["head_position"]
+    target = {"pan": start["pan"] + 24.0, "tilt": 12.0}
+    
+    move = asyncio.create_task(
+        harness.post_json("/api/robot/head/move", {**target, "speed": 30.0})
+    )
+    
+    # 移动24°需要约0.8秒，期间应能观察到中间位置
+    async def in_progress():
+        pan = (await harness.get_json("/api/robot/status"))["head_position"]["pan"]
+        return start["pan"] < pan < target["pan"]
+    
+    await harness.wait_for("观察到运动中的头部位置", in_progress, timeout=2.0, interval=0.02)
+    
+    result = await move
+    expect(result["success"], f"头部运动失败: {result['message']}")
+    position = (await harness.get_json("/api/robot/status"))["head_position"]
+    expect(close_to(position["pan"], target["pan"]) and close_to(position["tilt"], target["tilt"]),
+           f"运动完成后位置 {position} 与目标 {target} 不一致")
+    
+    # 回到零位，后续场景从相同状态开始
+    result = await harness.post_json("/api/robot/head/move", {"pan": 0.0, "tilt": 0.0, "speed": 30.0})
+    expect(result["success"], "回到零位失败")
+
+
+async def command_events_in_order(harness):
+    """交互分析记录的命令事件与发出命令的顺序一致"""
+    await harness.http.delete("/api/analytics/events")
+    
+    pans = [10.0, -10.0, 0.0]
+    for pan in pans:
+        result = await harness.post_json("/api/robot/head/move", {"pan": pan, "tilt": 0.0, "speed": 30.0})
+        expect(result["success"], f"头部运动到 {pan}° 失败")
+    
+    events = await harness.get_json("/api/analytics/events", params={"event_type": "command", "limit": 10})
+    # 接口按时间倒序返回
+    recorded = [event["details"]["pan"] for event in reversed(events) if event["details"]["command"] == "move_head"]
+    expect(recorded == pans, f"命令事件顺序 {recorded} 与发出顺序 {pans} 不一致")
+
+
+async def websocket_replies_in_order(harness):
+    """WebSocket按收到消息的顺序逐条回复"""
+    messages = [json.dumps({"seq": seq}) for seq in range(5)]
+    async with websockets.connect(f"{harness.ws_url}/ws") as websocket:
+        for message in messages:
+            await websocket.send(message)
+        replies = [await asyncio.wait_for(websocket.recv(), timeout=2.0) for _ in messages]
+    
+    expect(replies == [f"Echo: {message}" for message in messages], f"WebSocket回复顺序错误: {replies}")
+
+
+SCENARIOS = [
+    startup_reaches_ready,
+    connection_state_transitions,
+    head_motion_completes,
+    command_events_in_order,
+    websocket_replies_in_order,
+]
@@ -0,0 +1,17 @@
+#!/usr/bin/env python3
+"""
+集成测试场景共用的断言
+"""
+
+
+class ScenarioFailure(AssertionError):
+    """场景断言失败"""
+
+
+def expect(condition: bool, message: str):
+    if not condition:
+        raise ScenarioFailure(message)
+
+
+def close_to(actual: float, expected: float, tolerance: float = 0.01) -> bool:
+    return abs(actual - expected) <= tolerance
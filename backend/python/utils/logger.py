@@ -13,6 +13,10 @@ from pathlib import Path
 from typing import Optional
 
 from config import settings
+from core.correlation import install_log_record_factory
+
+# 日志行带上当前请求的关联ID
+install_log_record_factory()
 
 
 class ColoredFormatter(logging.Formatter):
@@ -96,7 +100,7 @@ class CustomLogger:
             
             # 使用彩色格式化器
             console_formatter = ColoredFormatter(
-                fmt='%(asctime)s | %(levelname)-8s | %(name)s | %(message)s',
+                fmt='%(asctime)s | %(levelname)-8s | %(correlation_id)s | %(name)s | %(message)s',
                 datefmt='%Y-%m-%d %H:%M:%S'
             )
             console_handler.setFormatter(console_formatter)
@@ -136,7 +140,7 @@ class CustomLogger:
                 debug_handler.setLevel(logging.DEBUG)
                 
                 debug_formatter = logging.Formatter(
-                    fmt='%(asctime)s | %(levelname)-8s | %(name)s:%(lineno)d | %(funcName)s | %(message)s',
+                    fmt='%(asctime)s | %(levelname)-8s | %(correlation_id)s | %(name)s:%(lineno)d | %(funcName)s | %(message)s',
                     datefmt='%Y-%m-%d %H:%M:%S'
                 )
                 debug_handler.setFormatter(debug_formatter)
@@ -144,7 +148,7 @@ class CustomLogger:
             
             # 文件格式化器
             file_formatter = logging.Formatter(
-                fmt='%(asctime)s | %(levelname)-8s | %(name)s | %(message)s',
+                fmt='%(asctime)s | %(levelname)-8s | %(correlation_id)s | %(name)s | %(message)s',
                 datefmt='%Y-%m-%d %H:%M:%S'
             )
             
@@ -0,0 +1,146 @@
+#!/usr/bin/env python3
+"""
+资源上传客户端
+把本地的ONNX模型或动画包分块上传到机器人（/api/assets），
+每个分块附带SHA-256；连接中断时重新查询已接收的字节数并从那里继续
+
+用法：
+    python -m utils.upload_client http://reachy-mini.local:8000 model ./yolo.onnx
+"""
+
+import argparse
+import hashlib
+import logging
+import time
+from pathlib import Path
+from typing import Callable, Dict, Optional, Any
+
+import httpx
+
+logger = logging.getLogger(__name__)
+
+
+def file_sha256(path: Path) -> str:
+    digest = hashlib.sha256()
+    with open(path, "rb") as f:
+        for block in iter(lambda: f.read(1024 * 1024), b""):
+            digest.update(block)
+    return digest.hexdigest()
+
+
+class AssetUploader:
+    """分块上传客户端"""
+    
+    def __init__(self, base_url: str, timeout: float = 60.0, max_retries: int = 5,
+                 retry_delay: float = 2.0, headers: Optional[Dict[str, str]] = None):
+        self.client = httpx.Client(base_url=base_url.rstrip("/"), timeout=timeout, headers=headers)
+        self.max_retries = max_retries
+        self.retry_delay = retry_delay
+    
+    def close(self):
+        self.client.close()
+    
+    def __enter__(self):
+        return self
+    
+    def __exit__(self, *exc):
+        self.close()
+    
+    def _offset(self, upload_id: str) -> int:
+        response = self.client.get(f"/api/assets/uploads/{upload_id}")
+        response.raise_for_status()
+        return response.json()["offset"]
+    
+    def upload(self, path: str, kind: str, chunk_size: Optional[int] = None,
+               progress: Optional[Callable[[int, int], None]] = None) -> Dict[str, Any]:
+        """上传文件并返回登记后的资源信息；同一文件的未完成上传会被续传"""
+        path = Path(path)
+        size = path.stat().st_size
+        sha256 = file_sha256(path)
+        
+        response = self.client.post("/api/assets/uploads", json={
+            "filename": path.name,
+            "kind": kind,
+            "size": size,
+            "sha256": sha256,
+        })
+        response.raise_for_status()
+        session = response.json()
+        upload_id = session["upload_id"]
+        offset = session["offset"]
+        chunk_size = chunk_size or session["chunk_size"]
+        
+        if offset:
+            logger.info(f"从 {offset}/{size} 字节继续上传 {path.name}")
+        
+        retries = 0
+        with open(path, "rb") as f:
+            while offset < size:
+                f.seek(offset)
+                chunk = f.read(chunk_size)
+                
+                try:
+                    response = self.client.put(
+                        f"/api/assets/uploads/{upload_id}",
+                        params={"offset": offset},
+                        content=chunk,
+                        headers={
+                            "Content-Type": "application/octet-stream",
+                            "X-Chunk-SHA256": hashlib.sha256(chunk).hexdigest(),
+                        },
+                    )
+                    if response.status_code == 409:
+                        # 服务端已接收的字节数与本地不一致，按服务端的偏移继续
+                        offset = response.json()["detail"]["offset"]
+                        continue
+                    response.raise_for_status()
+                    offset = response.json()["offset"]
+                    retries = 0
+                    
+                except (httpx.TransportError, httpx.HTTPStatusError) as e:
+                    retries += 1
+                    if retries > self.max_retries:
+                        raise
+                    logger.warning(f"分块上传失败（第 {retries} 次重试）: {e}")
+                    time.sleep(self.retry_delay * retries)
+                    try:
+                        offset = self._offset(upload_id)
+                    except httpx.HTTPError:
+                        pass
+                    continue
+                
+                if progress:
+                    progress(offset, size)
+        
+        response = self.client.post(f"/api/assets/uploads/{upload_id}/complete")
+        response.raise_for_status()
+        return response.json()
+
+
+def upload_asset(base_url: str, path: str, kind: str, **kwargs) -> Dict[str, Any]:
+    """上传单个模型或动画文件"""
+    with AssetUploader(base_url) as uploader:
+        return uploader.upload(path, kind, **kwargs)
+
+
+def main():
+    parser = argparse.ArgumentParser(description="上传模型或动画到Reachy Mini")
+    parser.add_argument("base_url", help="机器人API地址，例如 http://reachy-mini.local:8000")
+    parser.add_argument("kind", choices=["model", "animation"], help="资源类型")
+    parser.add_argument("path", help="本地文件")
+    parser.add_argument("--chunk-size-mb", type=int, default=None, help="分块大小（MB），默认由服务端决定")
+    args = parser.parse_args()
+    
+    logging.basicConfig(level=logging.INFO, format="%(message)s")
+    
+    def progress(done: int, total: int):
+        print(f"\r{done * 100 // total:3d}% ({done}/{total} 字节)", end="", flush=True)
+    
+    chunk_size = args.chunk_size_mb * 1024 * 1024 if args.chunk_size_mb else None
+    asset = upload_asset(args.base_url, args.path, args.kind, chunk_size=chunk_size, progress=progress)
+    print()
+    print(f"已登记 {asset['kind']} {asset['filename']} ({asset['id']})")
+
+
+if __name__ == "__main__":
+    main()
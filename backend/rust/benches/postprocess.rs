@@ -0,0 +1,75 @@
+//! 后处理向量化版本与标量版本的对比：cargo bench --bench postprocess
+//! 
+//! 数据规模按YOLOv8n在640x640输入下的输出（80类，8400个先验框）
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use reachy_mini_rust::postprocess::{self, Candidate};
+
+const NUM_CLASSES: usize = 80;
+const ANCHORS: usize = 8400;
+
+fn random_values(seed: u64, count: usize) -> Vec<f32> {
+    let mut state = seed;
+    (0..count).map(|_| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 40) as f32 / (1u64 << 24) as f32
+    }).collect()
+}
+
+/// YOLOv8格式的输出，大部分类别得分很低
+fn yolo_output() -> Vec<f32> {
+    let mut output = random_values(1, (4 + NUM_CLASSES) * ANCHORS);
+    for value in &mut output[..4 * ANCHORS] {
+        *value *= 640.0;
+    }
+    for value in &mut output[4 * ANCHORS..] {
+        *value = (*value - 0.95) * 40.0;
+    }
+    output
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let output = yolo_output();
+    let mut group = c.benchmark_group("decode_yolo");
+    group.bench_function("scalar", |b| {
+        b.iter(|| postprocess::decode_yolo_scalar(black_box(&output), NUM_CLASSES, 0.25, true))
+    });
+    group.bench_function(format!("{:?}", postprocess::simd_level()), |b| {
+        b.iter(|| postprocess::decode_yolo(black_box(&output), NUM_CLASSES, 0.25, true))
+    });
+    group.finish();
+}
+
+fn bench_nms(c: &mut Criterion) {
+    let candidates: Vec<Candidate> = postprocess::decode_yolo(&yolo_output(), NUM_CLASSES, 0.25, true);
+    let mut group = c.benchmark_group("nms");
+    for count in [100, 1000, candidates.len().min(3000)] {
+        let boxes: Vec<[f32; 4]> = candidates[..count].iter().map(|c| c.bbox).collect();
+        let scores: Vec<f32> = candidates[..count].iter().map(|c| c.score).collect();
+        group.bench_with_input(BenchmarkId::new("scalar", count), &count, |b, _| {
+            b.iter(|| postprocess::nms_scalar(black_box(&boxes), black_box(&scores), 0.45))
+        });
+        group.bench_with_input(BenchmarkId::new(format!("{:?}", postprocess::simd_level()), count), &count, |b, _| {
+            b.iter(|| postprocess::nms(black_box(&boxes), black_box(&scores), 0.45))
+        });
+    }
+    group.finish();
+}
+
+fn bench_activations(c: &mut Criterion) {
+    let logits: Vec<f32> = random_values(2, NUM_CLASSES * ANCHORS).iter().map(|v| (v - 0.5) * 20.0).collect();
+    let level = format!("{:?}", postprocess::simd_level());
+    
+    let mut group = c.benchmark_group("sigmoid");
+    group.bench_function("scalar", |b| b.iter(|| postprocess::sigmoid_scalar(&mut black_box(logits.clone()))));
+    group.bench_function(&level, |b| b.iter(|| postprocess::sigmoid(&mut black_box(logits.clone()))));
+    group.finish();
+    
+    let mut group = c.benchmark_group("softmax");
+    group.bench_function("scalar", |b| b.iter(|| postprocess::softmax_scalar(&mut black_box(logits.clone()))));
+    group.bench_function(&level, |b| b.iter(|| postprocess::softmax(&mut black_box(logits.clone()))));
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode, bench_nms, bench_activations);
+criterion_main!(benches);
@@ -0,0 +1,366 @@
+"""
+Reachy Mini Rust扩展模块的类型存根
+
+与 src/python_bindings.rs 中导出的类和函数一一对应，修改绑定时同步更新。
+返回JSON字符串的接口在注释中给出解析后的结构。
+"""
+
+from types import TracebackType
+from typing import Dict, List, Optional, Type, TypedDict
+
+class SystemStatus(TypedDict):
+    """PyReachyMiniSystem.get_status() 返回的JSON结构"""
+    is_running: bool
+    name: str
+    version: str
+    log_filter: str
+    timestamp: str
+
+class SystemInfo(TypedDict):
+    """get_system_info() 返回的JSON结构"""
+    name: str
+    version: str
+    status: str
+    features: List[str]
+    hardware: Optional[dict]
+    protocol_version: str
+    log_filter: str
+    timestamp: str
+
+class ServerHello(TypedDict):
+    """negotiate_protocol() 返回的JSON结构"""
+    protocol_version: str
+    server_protocol_version: str
+    server_version: str
+    capabilities: List[str]
+
+class ConfigChange(TypedDict):
+    """ConfigDryRun.changes 中的元素，新增或删除的字段对应一侧为None"""
+    path: str
+    old: Optional[object]
+    new: Optional[object]
+
+class ConfigDryRun(TypedDict):
+    """dry_run_config() 返回的JSON结构"""
+    valid: bool
+    errors: List[str]
+    changes: List[ConfigChange]
+    summary: List[str]
+    restart_required: List[str]
+    warnings: List[str]
+
+class JointLimitHit(TypedDict):
+    """GazeUnreachable.blocking 中的元素（rad）"""
+    joint: str
+    required: float
+    limit: float
+
+class GazeUnreachable(TypedDict):
+    """注视目标不可达时最接近的可达方向和挡住目标的关节限位（rad）"""
+    azimuth: float
+    elevation: float
+    nearest_azimuth: float
+    nearest_elevation: float
+    nearest_targets: Dict[str, float]
+    blocking: List[JointLimitHit]
+
+class GazeSolution(TypedDict, total=False):
+    """solve_gaze() 返回的JSON结构，可达时给出targets，否则给出message和unreachable"""
+    reachable: bool
+    targets: Dict[str, float]
+    message: str
+    unreachable: GazeUnreachable
+
+class CpuProfile(TypedDict):
+    """capture_cpu_profile() 返回的JSON结构，folded每行为 "线程;根帧;...;叶帧 次数"，flamegraph为SVG"""
+    seconds: int
+    frequency_hz: int
+    samples: int
+    folded: str
+    flamegraph: str
+
+class Point3(TypedDict):
+    x: float
+    y: float
+    z: float
+
+class GazeWorkspace(TypedDict):
+    """gaze_workspace() 返回的JSON结构（底座坐标系，rad/米）"""
+    full_turn: bool
+    azimuth_min: float
+    azimuth_max: float
+    elevation_min: float
+    elevation_max: float
+    boundary: List[Point3]
+
+class JointSeries(TypedDict):
+    """TrajectoryPlot.joints 中的元素，数值与times一一对应"""
+    joint: str
+    positions: List[float]
+    velocities: List[float]
+
+class TrajectoryPlot(TypedDict):
+    """sample_trajectory() 返回的JSON结构（秒、rad、米）"""
+    name: str
+    duration: float
+    times: List[float]
+    joints: List[JointSeries]
+    camera_path: List[Point3]
+    gaze_path: List[Point3]
+
+class ServoIdentity(TypedDict):
+    """PyHardwareInterface.scan_bus() 返回列表中的元素"""
+    id: int
+    model_number: int
+    firmware_version: int
+
+class ServoStatus(TypedDict):
+    """PyHardwareInterface.get_servo_status() 返回的JSON结构（舵机原始单位）"""
+    id: int
+    position: int
+    speed: int
+    load: int
+    voltage: float
+    temperature: int
+    is_moving: bool
+    error_flags: int
+    last_update: int
+    torque_enabled: bool
+    torque_limit: int
+
+class PyReachyMiniSystem:
+    """Reachy Mini系统，各方法在内部的tokio运行时上阻塞执行
+
+    start/stop执行期间释放GIL，可以通过线程池在asyncio中调用。
+    """
+
+    def __init__(self, name: str, version: str, hardware_config_json: Optional[str] = None) -> None:
+        """给出hardware_config_json（只需要覆盖默认配置的字段）时挂接硬件接口"""
+    def start(self) -> None:
+        """启动系统和挂接的硬件接口，已在运行时直接返回"""
+    def stop(self) -> None:
+        """停止系统；挂接了硬件接口时先回到停靠姿态并卸掉扭矩，停靠失败时抛出RuntimeError"""
+    def is_running(self) -> bool: ...
+    def get_status(self) -> str:
+        """系统状态JSON，结构见 SystemStatus"""
+    def __enter__(self) -> "PyReachyMiniSystem":
+        """启动系统"""
+    def __exit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_value: Optional[BaseException],
+        traceback: Optional[TracebackType],
+    ) -> bool:
+        """停止系统（挂接了硬件接口时先停靠），不吞掉异常"""
+
+class PyHardwareInterface:
+    """硬件接口的底层访问，用于装配调试
+
+    舵机命令与控制层经过同一条处理路径，位置和速度限制同样生效。
+    """
+
+    def __init__(self, config_json: Optional[str] = None) -> None:
+        """config_json只需给出要覆盖默认配置的字段"""
+    def start(self) -> None: ...
+    def stop(self) -> None:
+        """停止接口，按配置先回到停靠姿态"""
+    def is_running(self) -> bool: ...
+    def send_command(self, command_json: str, correlation_id: Optional[str] = None) -> str:
+        """发送硬件命令JSON，如 {"type": "servo_move", "id": 1, "position": 100, "speed": null}
+
+        correlation_id为上层（如API请求）的关联ID，未指定时生成新的ULID；返回该ID
+
+        Raises:
+            ValueError: 命令格式错误
+        """
+    def get_servo_status(self, id: int) -> Optional[str]:
+        """舵机状态JSON，结构见 ServoStatus；舵机不存在时返回None"""
+    def set_led(self, pin: int, state: bool) -> None:
+        """设置GPIO输出
+
+        Raises:
+            ValueError: 引脚未在配置的gpio_pins中声明
+        """
+    def scan_bus(self, first: int = 0, last: int = 252) -> str:
+        """向ID范围内的每个ID发送PING，返回应答舵机的 ServoIdentity 列表JSON；
+        串口为模拟后端时返回配置中的舵机
+
+        Raises:
+            OSError: 串口读写失败
+        """
+    def get_status(self) -> str:
+        """完整硬件状态JSON"""
+
+class PyCommandGate:
+    """不经过实时控制器执行的运动命令的准入
+
+    先检查角色并按来源优先级仲裁，再把命令和结果写入审计日志；规则和审计文件与实时控制器相同。
+    """
+
+    def __init__(self, config_json: Optional[str] = None) -> None:
+        """config_json形如 {"arbitration": {...}, "audit": {"directory": "data/audit"}}，只需给出要覆盖默认配置的字段"""
+    def submit(self, origin_json: str, payload_json: str, emergency: bool = False) -> None:
+        """提交一条命令，origin_json如 {"source": "Teleop", "client_id": "browser", "role": "operator"}；
+        急停不受角色和仲裁限制
+
+        Raises:
+            ValueError: 命令来源格式错误
+            PermissionError: 角色没有控制权限或被仲裁拒绝（拒绝同样写入审计日志）
+        """
+
+def init_logging() -> None:
+    """初始化Rust端日志（读取RUST_LOG环境变量），进程内只能调用一次"""
+
+def get_log_filter() -> str:
+    """当前生效的日志过滤规则，写法与RUST_LOG相同，如 "info,reachy_mini_rust::hardware=debug" """
+
+def set_log_filter(spec: str) -> None:
+    """整体替换日志过滤规则，立即生效
+
+    Raises:
+        ValueError: 规则格式错误或级别无效
+    """
+
+def set_log_level(level: str, module: Optional[str] = None) -> None:
+    """修改全局级别（不指定module）或某个模块及其子模块的级别，立即生效
+
+    Raises:
+        ValueError: 级别不是 off、error、warn、info、debug、trace 之一
+    """
+
+def clear_log_level(module: str) -> bool:
+    """移除模块级别，恢复使用上级模块或全局级别；模块原本没有单独设置时返回False"""
+
+def get_system_info() -> str:
+    """系统信息JSON，结构见 SystemInfo"""
+
+def validate_config(config_json: str) -> bool:
+    """检查配置JSON是否包含vision、realtime、hardware和ai各节"""
+
+def negotiate_protocol(hello_json: str) -> str:
+    """按客户端hello消息协商协议版本，返回ServerHello JSON
+
+    Raises:
+        ValueError: 消息格式错误或协议版本不兼容
+    """
+
+def build_motion_command(command_json: str) -> str:
+    """按MotionCommand JSON构建并验证运动命令，返回补全后的命令JSON
+
+    未给出的timestamp取当前时间；Position、Velocity、Torque命令必须带对应的
+    target_position、target_velocity、target_torque，且不能带其它目标。
+
+    Raises:
+        ValueError: 格式错误或命令无效
+    """
+
+def build_inference_request(request_json: str) -> str:
+    """按InferenceRequest JSON构建并验证推理请求，返回补全后的请求JSON
+
+    未给出的request_id生成ULID，timestamp取当前时间，options使用默认值；
+    张量形状必须与数据长度一致，图像尺寸必须与数据长度一致。
+
+    Raises:
+        ValueError: 格式错误或输入数据无效
+    """
+
+def inspect_model(path: str, input_shape: Optional[List[int]] = None,
+                  output_names: Optional[List[str]] = None) -> str:
+    """加载ONNX模型，返回实际的输入输出，仅在启用vision特性构建时存在
+
+    返回{"inputs", "outputs", "mismatches"}，inputs和outputs的每项为{name, shape, dtype}，
+    动态维度为-1；给出input_shape时mismatches列出与之及output_names的差异。
+
+    Raises:
+        FileNotFoundError: 模型文件不存在
+        RuntimeError: ONNX Runtime不可用或模型无法加载
+    """
+
+def solve_gaze(x: float, y: float, z: float) -> str:
+    """求解看向底座坐标系中一点（米）的关节目标，返回GazeSolution JSON
+
+    目标超出关节限位时不截断，而是返回最接近的可达方向和挡住目标的关节。
+    """
+
+def gaze_workspace(radius: float = 1.0, samples: int = 16) -> str:
+    """可达注视范围，返回GazeWorkspace JSON；boundary为半径radius的球面上每条边samples个采样点"""
+
+def export_robot_model(format: str = "urdf", dynamics_json: Optional[str] = None) -> str:
+    """由关节配置生成仿真模型，format为"urdf"或"sdf"，返回XML文本
+
+    dynamics_json为各关节辨识得到的DynamicsParams（inertia、viscous_friction、coulomb_friction），
+    写入模型的阻尼、摩擦和连杆惯量。
+
+    Raises:
+        ValueError: 格式不支持或关节配置无效
+    """
+
+def sample_trajectory(trajectory_json: str, sample_rate: float = 50.0) -> str:
+    """按采样率展开轨迹，返回TrajectoryPlot JSON，包含关节曲线和相机、视线落点的路径
+
+    Raises:
+        ValueError: 轨迹格式错误或采样点过多
+    """
+
+def install_behavior_pack(path: str) -> str:
+    """检查并安装zip格式的行为包，返回PackInfo JSON；新安装的包默认禁用，重新安装保留启用状态
+
+    Raises:
+        ValueError: 包不是有效的zip、含有不允许的文件或引用了包内不存在的资源
+    """
+
+def inspect_behavior_pack(path: str) -> str:
+    """检查zip格式的行为包但不安装，返回PackInfo JSON，其中permissions为包内容实际需要的权限
+
+    Raises:
+        ValueError: 包内容未通过检查
+    """
+
+def list_behavior_packs() -> str:
+    """已安装的行为包，返回PackInfo JSON数组"""
+
+def set_behavior_pack_enabled(pack_id: str, enabled: bool) -> str:
+    """启用或禁用行为包，返回PackInfo JSON；启用前重新检查安装目录
+
+    Raises:
+        ValueError: 包未安装或检查不通过
+    """
+
+def uninstall_behavior_pack(pack_id: str) -> None:
+    """卸载行为包并删除其文件
+
+    Raises:
+        ValueError: 包未安装
+    """
+
+def capture_cpu_profile(seconds: int = 10) -> str:
+    """用pprof在进程内采集seconds秒的CPU调用栈，返回CpuProfile JSON；阻塞期间释放GIL
+
+    由Rust端配置文件的performance.profiling_enabled控制是否允许，
+    采样频率和最长时间分别为profiling_frequency_hz和profiling_max_seconds。
+
+    Raises:
+        PermissionError: 性能剖析未启用
+        ValueError: 采样时长超出范围
+        RuntimeError: 未启用profiling特性构建，或已有一次采样正在进行
+        OSError: 采样失败
+    """
+
+def plot_trajectory_png(trajectory_json: str, sample_rate: float = 50.0, width: int = 1200,
+                        height: int = 800, font_path: Optional[str] = None) -> bytes:
+    """把轨迹的关节位置和速度曲线渲染为PNG，仅在启用plot特性构建时存在
+
+    不指定font_path时图中没有文字，只有曲线和网格。
+
+    Raises:
+        ValueError: 轨迹格式错误、字体文件无效或图片尺寸过小
+    """
+
+def dry_run_config(proposed_json: str, active_json: Optional[str] = None) -> str:
+    """校验新配置并与当前配置比较，返回ConfigDryRun JSON，不修改任何配置
+
+    未给出active_json时与Rust端全局配置（未初始化时为默认配置）比较。
+
+    Raises:
+        ValueError: 配置JSON不是完整的Config
+    """
@@ -0,0 +1,251 @@
+//! 视觉分辨率自适应
+//! 
+//! AI负载较重时视觉处理会占满CPU，帧延迟越来越大。这里按时间窗口统计帧延迟和系统CPU占用：
+//! 任一项超过上限时把采集/处理分辨率再缩小一半（最多到`max_downscale`），之后连续多个窗口
+//! 两项都低于下限时再恢复一档。上下限之间留有间隔，恢复需要连续多个空闲窗口，避免来回切换
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// 视觉分辨率自适应配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdaptiveResolutionConfig {
+    pub enabled: bool,
+    /// 最大缩小倍数，逐档加倍（1、2、4...）直到不超过该值
+    pub max_downscale: u32,
+    /// 系统CPU占用（百分比）超过该值时降低分辨率
+    pub cpu_high_percent: f64,
+    /// 系统CPU占用低于该值才算空闲
+    pub cpu_low_percent: f64,
+    /// 窗口内平均帧延迟（毫秒）超过该值时降低分辨率
+    pub latency_high_ms: f64,
+    /// 窗口内平均帧延迟低于该值才算空闲
+    pub latency_low_ms: f64,
+    /// 统计窗口（毫秒）
+    pub window_ms: u64,
+    /// 连续多少个空闲窗口后恢复一档分辨率
+    pub recover_windows: usize,
+}
+
+impl Default for AdaptiveResolutionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_downscale: 4,
+            cpu_high_percent: 85.0,
+            cpu_low_percent: 50.0,
+            latency_high_ms: 200.0,
+            latency_low_ms: 80.0,
+            window_ms: 2000,
+            recover_windows: 5,
+        }
+    }
+}
+
+impl ConfigValidation for AdaptiveResolutionConfig {
+    fn validate(&self) -> Result<()> {
+        if !(1..=8).contains(&self.max_downscale) {
+            return Err(anyhow::anyhow!("最大分辨率缩小倍数必须在1-8之间"));
+        }
+        
+        if !(0.0 < self.cpu_low_percent && self.cpu_low_percent < self.cpu_high_percent && self.cpu_high_percent <= 100.0) {
+            return Err(anyhow::anyhow!("CPU占用阈值必须满足 0 < 空闲 < 过载 <= 100"));
+        }
+        
+        if !(0.0 < self.latency_low_ms && self.latency_low_ms < self.latency_high_ms) {
+            return Err(anyhow::anyhow!("帧延迟阈值必须满足 0 < 空闲 < 过载"));
+        }
+        
+        if self.window_ms == 0 || self.recover_windows == 0 {
+            return Err(anyhow::anyhow!("统计窗口和恢复窗口数不能为0"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 一次分辨率调整
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolutionChange {
+    pub from_downscale: u32,
+    pub to_downscale: u32,
+    /// 触发调整的窗口内系统CPU占用（百分比），无法读取时为None
+    pub cpu_percent: Option<f64>,
+    /// 触发调整的窗口内平均帧延迟（毫秒）
+    pub latency_ms: f64,
+}
+
+impl ResolutionChange {
+    pub fn is_degraded(&self) -> bool {
+        self.to_downscale > self.from_downscale
+    }
+}
+
+/// 按窗口统计帧延迟和CPU占用并决定分辨率档位
+#[derive(Debug, Clone)]
+pub struct ResolutionAdapter {
+    config: AdaptiveResolutionConfig,
+    downscale: u32,
+    window_start: Option<Instant>,
+    frames: usize,
+    latency_sum_ms: f64,
+    idle_windows: usize,
+}
+
+impl ResolutionAdapter {
+    pub fn new(config: AdaptiveResolutionConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            downscale: 1,
+            window_start: None,
+            frames: 0,
+            latency_sum_ms: 0.0,
+            idle_windows: 0,
+        })
+    }
+    
+    /// 当前缩小倍数
+    pub fn downscale(&self) -> u32 {
+        self.downscale
+    }
+    
+    /// 记录一帧的端到端延迟；窗口结束时调用cpu_percent读取窗口内的CPU占用，需要调整时返回调整信息
+    pub fn record(&mut self, latency_ms: f64, now: Instant, cpu_percent: impl FnOnce() -> Option<f64>) -> Option<ResolutionChange> {
+        if !self.config.enabled {
+            return None;
+        }
+        
+        let start = *self.window_start.get_or_insert(now);
+        self.frames += 1;
+        self.latency_sum_ms += latency_ms;
+        if now.duration_since(start) < Duration::from_millis(self.config.window_ms) {
+            return None;
+        }
+        
+        let latency_ms = self.latency_sum_ms / self.frames as f64;
+        let cpu = cpu_percent();
+        self.window_start = Some(now);
+        self.frames = 0;
+        self.latency_sum_ms = 0.0;
+        
+        let from_downscale = self.downscale;
+        let overloaded = latency_ms > self.config.latency_high_ms
+            || cpu.is_some_and(|cpu| cpu > self.config.cpu_high_percent);
+        if overloaded {
+            self.idle_windows = 0;
+            if self.downscale * 2 <= self.config.max_downscale {
+                self.downscale *= 2;
+                return Some(ResolutionChange { from_downscale, to_downscale: self.downscale, cpu_percent: cpu, latency_ms });
+            }
+            return None;
+        }
+        
+        let idle = latency_ms < self.config.latency_low_ms
+            && cpu.is_none_or(|cpu| cpu < self.config.cpu_low_percent);
+        if !idle || self.downscale == 1 {
+            self.idle_windows = 0;
+            return None;
+        }
+        
+        self.idle_windows += 1;
+        if self.idle_windows < self.config.recover_windows {
+            return None;
+        }
+        
+        self.idle_windows = 0;
+        self.downscale /= 2;
+        Some(ResolutionChange { from_downscale, to_downscale: self.downscale, cpu_percent: cpu, latency_ms })
+    }
+}
+
+/// 由/proc/stat计算两次采样之间的系统CPU占用
+#[derive(Debug, Default)]
+pub struct CpuSampler {
+    last: Option<(u64, u64)>,
+}
+
+impl CpuSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 距上次采样的CPU占用（百分比），第一次采样或无法读取时为None
+    pub fn sample(&mut self) -> Option<f64> {
+        let current = parse_cpu_times(&std::fs::read_to_string("/proc/stat").ok()?)?;
+        let previous = self.last.replace(current)?;
+        let (busy, total) = (current.0.saturating_sub(previous.0), current.1.saturating_sub(previous.1));
+        (total > 0).then(|| busy as f64 / total as f64 * 100.0)
+    }
+}
+
+/// 解析/proc/stat第一行的汇总CPU时间，返回(忙碌时间, 总时间)，空闲时间包括idle和iowait
+fn parse_cpu_times(stat: &str) -> Option<(u64, u64)> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let times: Vec<u64> = line.split_whitespace().skip(1).map(|v| v.parse().ok()).collect::<Option<_>>()?;
+    if times.len() < 4 {
+        return None;
+    }
+    
+    // guest和guest_nice已经计入user和nice
+    let total: u64 = times.iter().take(8).sum();
+    let idle = times[3] + times.get(4).copied().unwrap_or(0);
+    Some((total - idle, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn config() -> AdaptiveResolutionConfig {
+        AdaptiveResolutionConfig { enabled: true, window_ms: 1000, recover_windows: 2, ..AdaptiveResolutionConfig::default() }
+    }
+    
+    /// 每100ms一帧，运行frames帧，返回发生的调整
+    fn run(adapter: &mut ResolutionAdapter, start: &mut Instant, frames: usize, latency_ms: f64, cpu: Option<f64>) -> Vec<ResolutionChange> {
+        (0..frames).filter_map(|_| {
+            *start += Duration::from_millis(100);
+            adapter.record(latency_ms, *start, || cpu)
+        }).collect()
+    }
+    
+    #[test]
+    fn test_downscale_under_load_and_recover_with_hysteresis() {
+        let mut adapter = ResolutionAdapter::new(config()).unwrap();
+        let mut now = Instant::now();
+        
+        // 帧延迟过高：每个窗口缩小一档，到最大倍数后不再缩小
+        let changes = run(&mut adapter, &mut now, 50, 300.0, Some(40.0));
+        assert_eq!(changes.iter().map(|c| c.to_downscale).collect::<Vec<_>>(), vec![2, 4]);
+        assert!(changes[0].is_degraded());
+        assert_eq!(adapter.downscale(), 4);
+        
+        // 介于上下限之间：保持不变
+        assert!(run(&mut adapter, &mut now, 50, 120.0, Some(60.0)).is_empty());
+        
+        // CPU过载同样触发；恢复空闲后连续两个窗口才升高一档
+        let mut adapter = ResolutionAdapter::new(config()).unwrap();
+        assert_eq!(run(&mut adapter, &mut now, 11, 30.0, Some(95.0)).len(), 1);
+        let changes = run(&mut adapter, &mut now, 40, 30.0, Some(20.0));
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].from_downscale, changes[0].to_downscale), (2, 1));
+        assert!(!changes[0].is_degraded());
+        assert!(run(&mut adapter, &mut now, 50, 30.0, None).is_empty());
+    }
+    
+    #[test]
+    fn test_disabled_and_cpu_parsing() {
+        let mut adapter = ResolutionAdapter::new(AdaptiveResolutionConfig::default()).unwrap();
+        let mut now = Instant::now();
+        assert!(run(&mut adapter, &mut now, 50, 1000.0, Some(100.0)).is_empty());
+        
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\n";
+        assert_eq!(parse_cpu_times(stat), Some((150, 1000)));
+        assert_eq!(parse_cpu_times("intr 1 2 3\n"), None);
+        
+        let invalid = AdaptiveResolutionConfig { cpu_low_percent: 90.0, ..config() };
+        assert!(invalid.validate().is_err());
+    }
+}
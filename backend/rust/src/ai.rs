@@ -3,10 +3,18 @@
 //! 提供高性能的AI推理功能，包括深度学习模型推理、计算机视觉、自然语言处理等。
 
 use crate::common::*;
+use crate::config::PerformanceConfig;
+use crate::events::{EventBus, RobotEvent};
+use crate::model_signature::ModelSignature;
+use crate::postprocess::softmax;
+use crate::segmentation::{self, ClassFraction, SegmentationCoverage};
+use crate::sound_events::{self, SoundEvent, SoundEventConfig};
+use crate::shutdown::{CancellationToken, TaskGroup};
+use crate::tensor_pool::{TensorPool, TensorPoolStats};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{RwLock, mpsc, Mutex};
@@ -15,6 +23,7 @@ use log::{info, warn, error, debug};
 /// AI配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIConfig {
+    pub enabled: bool,
     pub model_path: String,
     pub device: DeviceType,
     pub batch_size: usize,
@@ -26,6 +35,9 @@ pub struct AIConfig {
     pub cache_size: usize,
     pub enable_tensorrt: bool,
     pub enable_quantization: bool,
+    /// Mock设备的脚本化响应
+    #[serde(default)]
+    pub mock: MockBackendConfig,
 }
 
 impl Default for AIConfig {
@@ -39,6 +51,8 @@ impl Default for AIConfig {
             output_names: vec!["output0".to_string()],
             confidence_threshold: 0.5,
             nms_threshold: 0.4,
+            preprocessing: None,
+            task: ModelTask::default(),
             class_names: vec![
                 "person".to_string(), "bicycle".to_string(), "car".to_string(),
                 "motorcycle".to_string(), "airplane".to_string(), "bus".to_string(),
@@ -53,7 +67,12 @@ impl Default for AIConfig {
             output_names: vec!["boxes".to_string(), "scores".to_string()],
             confidence_threshold: 0.7,
             nms_threshold: 0.3,
+            preprocessing: Some(PreprocessingConfig {
+                target_size: (320, 320),
+                ..PreprocessingConfig::default()
+            }),
             class_names: vec!["face".to_string()],
+            task: ModelTask::default(),
         });
         
         model_configs.insert("pose_estimation".to_string(), ModelConfig {
@@ -62,6 +81,12 @@ impl Default for AIConfig {
             output_names: vec!["keypoints".to_string()],
             confidence_threshold: 0.3,
             nms_threshold: 0.5,
+            preprocessing: Some(PreprocessingConfig {
+                target_size: (192, 256),
+                keep_aspect_ratio: false,
+                ..PreprocessingConfig::default()
+            }),
+            task: ModelTask::default(),
             class_names: vec![
                 "nose".to_string(), "left_eye".to_string(), "right_eye".to_string(),
                 "left_ear".to_string(), "right_ear".to_string(), "left_shoulder".to_string(),
@@ -73,6 +98,7 @@ impl Default for AIConfig {
         });
         
         Self {
+            enabled: true,
             model_path: "models/".to_string(),
             device: DeviceType::CPU,
             batch_size: 1,
@@ -84,6 +110,7 @@ impl Default for AIConfig {
             cache_size: 100,
             enable_tensorrt: false,
             enable_quantization: false,
+            mock: MockBackendConfig::default(),
         }
     }
 }
@@ -106,16 +133,35 @@ impl ConfigValidation for AIConfig {
             return Err(anyhow::anyhow!("推理超时时间必须大于0"));
         }
         
+        self.preprocessing_config.validate().map_err(|e| {
+            anyhow::anyhow!("预处理配置验证失败: {}", e)
+        })?;
+        
         for (name, config) in &self.model_configs {
             config.validate().map_err(|e| {
                 anyhow::anyhow!("模型配置 '{}' 验证失败: {}", name, e)
             })?;
         }
         
+        for name in self.mock.responses.keys().chain(self.mock.signatures.keys()) {
+            if !self.model_configs.contains_key(name) {
+                return Err(anyhow::anyhow!("Mock脚本中的模型 '{}' 没有对应的模型配置", name));
+            }
+        }
+        
         Ok(())
     }
 }
 
+impl AIConfig {
+    /// 模型使用的预处理配置：模型配置中有`preprocessing`时使用它，否则使用全局的`preprocessing_config`
+    pub fn preprocessing_for(&self, model_name: &str) -> &PreprocessingConfig {
+        self.model_configs.get(model_name)
+            .and_then(|config| config.preprocessing.as_ref())
+            .unwrap_or(&self.preprocessing_config)
+    }
+}
+
 /// 设备类型
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DeviceType {
@@ -123,6 +169,24 @@ pub enum DeviceType {
     CUDA(u32), // GPU ID
     OpenCL(u32),
     Metal,
+    /// 不加载模型文件，按`MockBackendConfig`返回脚本化结果，用于测试和无模型开发
+    Mock,
+}
+
+/// Mock推理后端配置
+///
+/// `responses`按模型名给出结果序列，每次推理依次返回下一个，到结尾后从头循环；
+/// 没有脚本的模型返回内置的模拟输出经后处理得到的结果。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MockBackendConfig {
+    /// 每次推理的模拟延迟
+    #[serde(default)]
+    pub latency_ms: u64,
+    #[serde(default)]
+    pub responses: HashMap<String, Vec<InferenceResult>>,
+    /// 按模型名给出加载后报告的输入输出签名，没有时不报告
+    #[serde(default)]
+    pub signatures: HashMap<String, ModelSignature>,
 }
 
 /// 模型配置
@@ -134,6 +198,66 @@ pub struct ModelConfig {
     pub confidence_threshold: f32,
     pub nms_threshold: f32,
     pub class_names: Vec<String>,
+    /// 该模型的预处理配置，为空时使用全局的`preprocessing_config`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preprocessing: Option<PreprocessingConfig>,
+    /// 输出的后处理方式，默认按模型名使用内置的检测、人脸、姿态后处理
+    #[serde(default)]
+    pub task: ModelTask,
+}
+
+/// 模型输出的后处理任务
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ModelTask {
+    /// 按模型名选择内置的后处理
+    #[default]
+    Builtin,
+    /// 输出为每个类别一个得分，按得分取前top_k个类别，低于confidence_threshold的丢弃
+    Classification {
+        #[serde(default = "default_top_k")]
+        top_k: usize,
+        /// 输出为logit时先做softmax，模型已输出概率时设为false
+        #[serde(default = "default_true")]
+        softmax: bool,
+    },
+    /// 输出即特征向量，normalize为true时归一化为单位长度，便于用内积比较相似度
+    Embedding {
+        #[serde(default = "default_true")]
+        normalize: bool,
+    },
+    /// 语义分割：输出为[1, 类别数, 高, 宽]的得分或[1, 高, 宽]的类别图，解码为类别掩码，
+    /// 各类别的占比以SegmentationCoverage事件发布
+    Segmentation {
+        /// 占比低于该值的类别不出现在占比列表和事件中
+        #[serde(default = "default_min_fraction")]
+        min_fraction: f32,
+        /// 输入为图像时生成彩色叠加图，供视频流显示
+        #[serde(default)]
+        overlay: bool,
+        /// 叠加图中掩码颜色的权重
+        #[serde(default = "default_overlay_alpha")]
+        overlay_alpha: f32,
+    },
+    /// 声音事件分类：输入为一个单声道音频窗口，输出每个事件类别一个得分，
+    /// 得分最高的top_k个且不低于confidence_threshold的类别以SoundEventDetected事件发布
+    SoundEvent(SoundEventConfig),
+}
+
+fn default_top_k() -> usize {
+    5
+}
+
+fn default_min_fraction() -> f32 {
+    0.01
+}
+
+fn default_overlay_alpha() -> f32 {
+    0.5
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl ConfigValidation for ModelConfig {
@@ -158,19 +282,52 @@ impl ConfigValidation for ModelConfig {
             return Err(anyhow::anyhow!("NMS阈值必须在0-1之间"));
         }
         
+        if let Some(preprocessing) = &self.preprocessing {
+            preprocessing.validate()?;
+        }
+        
+        match self.task {
+            ModelTask::Classification { top_k: 0, .. } => {
+                return Err(anyhow::anyhow!("分类任务的top_k必须大于0"));
+            },
+            ModelTask::Segmentation { min_fraction, overlay_alpha, .. }
+                if !(0.0..=1.0).contains(&min_fraction) || !(0.0..=1.0).contains(&overlay_alpha) => {
+                return Err(anyhow::anyhow!("分割任务的最小占比和叠加权重必须在0-1之间"));
+            },
+            ModelTask::SoundEvent(ref sound) => {
+                sound.validate()?;
+                let samples = sound.window_samples() as i64;
+                if self.input_shape.last().is_some_and(|&dim| dim > 0 && dim != samples) {
+                    return Err(anyhow::anyhow!(
+                        "声音事件模型的输入形状 {:?} 与窗口样本数 {} 不符", self.input_shape, samples
+                    ));
+                }
+            },
+            _ => {},
+        }
+        
         Ok(())
     }
 }
 
 /// 预处理配置
+///
+/// 图像先缩放到`target_size`（宽, 高），`keep_aspect_ratio`为true时等比缩放并在两侧填充灰色；
+/// 之后按`color`转换通道顺序或转为灰度，像素值缩放到0-1，按`mean`/`std`逐通道归一化，
+/// 最后按`layout`排列为NCHW或NHWC
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PreprocessingConfig {
     pub normalize: bool,
+    /// 每个通道的均值和标准差，只有一个值时用于所有通道
     pub mean: Vec<f32>,
     pub std: Vec<f32>,
     pub resize_method: ResizeMethod,
     pub target_size: (u32, u32),
     pub keep_aspect_ratio: bool,
+    #[serde(default)]
+    pub layout: TensorLayout,
+    #[serde(default)]
+    pub color: ColorMode,
 }
 
 impl Default for PreprocessingConfig {
@@ -182,6 +339,97 @@ impl Default for PreprocessingConfig {
             resize_method: ResizeMethod::Bilinear,
             target_size: (640, 640),
             keep_aspect_ratio: true,
+            layout: TensorLayout::default(),
+            color: ColorMode::default(),
+        }
+    }
+}
+
+impl ConfigValidation for PreprocessingConfig {
+    fn validate(&self) -> Result<()> {
+        if self.target_size.0 == 0 || self.target_size.1 == 0 {
+            return Err(anyhow::anyhow!("预处理目标尺寸必须大于0"));
+        }
+        
+        if self.normalize {
+            let channels = self.color.channels();
+            for (name, values) in [("均值", &self.mean), ("标准差", &self.std)] {
+                if values.len() != 1 && values.len() != channels {
+                    return Err(anyhow::anyhow!(
+                        "{}有 {} 个值，{:?}输入需要1个或 {} 个", name, values.len(), self.color, channels
+                    ));
+                }
+            }
+            if self.std.iter().any(|&std| std <= 0.0 || !std.is_finite()) {
+                return Err(anyhow::anyhow!("标准差必须为正数"));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+impl PreprocessingConfig {
+    /// 预处理输出的张量形状
+    pub fn output_shape(&self) -> Vec<i64> {
+        let (width, height) = (self.target_size.0 as i64, self.target_size.1 as i64);
+        let channels = self.color.channels() as i64;
+        match self.layout {
+            TensorLayout::NCHW => vec![1, channels, height, width],
+            TensorLayout::NHWC => vec![1, height, width, channels],
+        }
+    }
+    
+    /// 检查预处理输出与模型声明的输入形状是否一致，形状中的负数表示动态维度，不做检查
+    pub fn check_input_shape(&self, input_shape: &[i64]) -> Result<()> {
+        let expected = self.output_shape();
+        let matches = input_shape.len() == expected.len()
+            && input_shape.iter().zip(&expected).all(|(&declared, &actual)| declared < 0 || declared == actual);
+        if !matches {
+            return Err(AIError::Preprocessing(format!(
+                "预处理输出形状 {:?}（{:?}，{:?}）与模型输入形状 {:?} 不符",
+                expected, self.layout, self.color, input_shape
+            )).into());
+        }
+        Ok(())
+    }
+    
+    /// 第channel个通道的均值和标准差
+    fn channel_stats(&self, channel: usize) -> (f32, f32) {
+        let pick = |values: &[f32], default: f32| match values {
+            [] => default,
+            [value] => *value,
+            values => values.get(channel).copied().unwrap_or(default),
+        };
+        (pick(&self.mean, 0.0), pick(&self.std, 1.0))
+    }
+}
+
+/// 张量的维度排列
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TensorLayout {
+    /// [批次, 通道, 高, 宽]，PyTorch导出的模型通常使用
+    #[default]
+    NCHW,
+    /// [批次, 高, 宽, 通道]，TensorFlow/TFLite导出的模型通常使用
+    NHWC,
+}
+
+/// 模型期望的输入通道
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMode {
+    #[default]
+    RGB,
+    BGR,
+    /// 单通道灰度，按ITU-R BT.601的权重由RGB计算
+    Grayscale,
+}
+
+impl ColorMode {
+    pub fn channels(&self) -> usize {
+        match self {
+            ColorMode::Grayscale => 1,
+            ColorMode::RGB | ColorMode::BGR => 3,
         }
     }
 }
@@ -215,7 +463,7 @@ pub enum ResizeMethod {
 }
 
 /// AI推理状态
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AIStatus {
     pub is_running: bool,
     pub loaded_models: Vec<String>,
@@ -223,19 +471,12 @@ pub struct AIStatus {
     pub inference_stats: InferenceStats,
     pub memory_usage: MemoryUsage,
     pub performance_stats: PerformanceStats,
-}
-
-impl Default for AIStatus {
-    fn default() -> Self {
-        Self {
-            is_running: false,
-            loaded_models: Vec::new(),
-            device_info: DeviceInfo::default(),
-            inference_stats: InferenceStats::default(),
-            memory_usage: MemoryUsage::default(),
-            performance_stats: PerformanceStats::new(),
-        }
-    }
+    /// 输入、输出张量缓冲区池的复用情况
+    #[serde(default)]
+    pub tensor_pool: TensorPoolStats,
+    /// 已加载模型实际的输入输出，无法读取的模型不在其中
+    #[serde(default)]
+    pub model_signatures: HashMap<String, ModelSignature>,
 }
 
 /// 设备信息
@@ -266,6 +507,9 @@ pub struct InferenceStats {
     pub total_inferences: u64,
     pub successful_inferences: u64,
     pub failed_inferences: u64,
+    /// 被取消的请求数，不计入total_inferences
+    #[serde(default)]
+    pub cancelled_inferences: u64,
     pub average_inference_time_ms: f64,
     pub throughput_fps: f64,
     pub last_inference_time: u64,
@@ -277,6 +521,7 @@ impl Default for InferenceStats {
             total_inferences: 0,
             successful_inferences: 0,
             failed_inferences: 0,
+            cancelled_inferences: 0,
             average_inference_time_ms: 0.0,
             throughput_fps: 0.0,
             last_inference_time: 0,
@@ -309,6 +554,7 @@ impl Default for MemoryUsage {
 pub struct InferenceRequest {
     pub model_name: String,
     pub input_data: InputData,
+    /// 请求ID，为空时提交时生成ULID
     pub request_id: String,
     pub timestamp: u64,
     pub options: InferenceOptions,
@@ -365,6 +611,169 @@ impl Default for InferenceOptions {
     }
 }
 
+impl InferenceRequest {
+    /// 创建请求构建器，时间戳取当前时间，选项使用默认值
+    pub fn builder(model_name: impl Into<String>) -> InferenceRequestBuilder {
+        InferenceRequestBuilder::new(model_name)
+    }
+    
+    /// 检查模型名和输入数据：张量形状与数据长度一致，图像尺寸与数据长度一致，批次不为空
+    pub fn validate(&self) -> Result<()> {
+        if self.model_name.is_empty() {
+            return Err(AIError::InvalidInput("模型名称不能为空".to_string()).into());
+        }
+        
+        if self.options.batch_size == Some(0) {
+            return Err(AIError::InvalidInput("批大小必须大于0".to_string()).into());
+        }
+        
+        if self.options.timeout_ms == Some(0) {
+            return Err(AIError::InvalidInput("超时时间必须大于0".to_string()).into());
+        }
+        
+        if let Some(threshold) = self.options.confidence_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(AIError::InvalidInput(format!("置信度阈值必须在0到1之间: {}", threshold)).into());
+            }
+        }
+        
+        self.input_data.validate()
+    }
+}
+
+impl InputData {
+    /// 检查输入数据的形状
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            InputData::Image(image) => {
+                if image.width == 0 || image.height == 0 || !image.is_valid() {
+                    return Err(AIError::InvalidInput(format!(
+                        "图像数据长度 {} 与尺寸 {}x{}x{} 不符",
+                        image.data.len(), image.width, image.height, image.channels
+                    )).into());
+                }
+            },
+            InputData::Text(text) => {
+                if text.is_empty() {
+                    return Err(AIError::InvalidInput("文本输入不能为空".to_string()).into());
+                }
+            },
+            InputData::Audio(samples) => {
+                if samples.is_empty() {
+                    return Err(AIError::InvalidInput("音频输入不能为空".to_string()).into());
+                }
+            },
+            InputData::Tensor(tensor) => tensor.validate()?,
+            InputData::Batch(items) => {
+                if items.is_empty() {
+                    return Err(AIError::InvalidInput("批次输入不能为空".to_string()).into());
+                }
+                for item in items {
+                    item.validate()?;
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+impl TensorData {
+    /// 形状中的各维都必须为正数，乘积等于数据长度
+    pub fn validate(&self) -> Result<()> {
+        if self.shape.is_empty() || self.shape.iter().any(|&dim| dim <= 0) {
+            return Err(AIError::InvalidInput(format!("张量形状无效: {:?}", self.shape)).into());
+        }
+        
+        let elements = self.shape.iter().try_fold(1usize, |total, &dim| total.checked_mul(dim as usize));
+        if elements != Some(self.data.len()) {
+            return Err(AIError::InvalidInput(format!(
+                "张量形状 {:?} 与数据长度 {} 不符", self.shape, self.data.len()
+            )).into());
+        }
+        Ok(())
+    }
+}
+
+/// 推理请求构建器
+pub struct InferenceRequestBuilder {
+    request: InferenceRequest,
+    input: Option<InputData>,
+}
+
+impl InferenceRequestBuilder {
+    pub fn new(model_name: impl Into<String>) -> Self {
+        Self {
+            request: InferenceRequest {
+                model_name: model_name.into(),
+                input_data: InputData::Batch(Vec::new()),
+                request_id: String::new(),
+                timestamp: current_timestamp(),
+                options: InferenceOptions::default(),
+            },
+            input: None,
+        }
+    }
+    
+    /// 设置输入数据
+    pub fn input(mut self, input: InputData) -> Self {
+        self.input = Some(input);
+        self
+    }
+    
+    /// 图像输入
+    pub fn image(self, image: ImageData) -> Self {
+        self.input(InputData::Image(image))
+    }
+    
+    /// 文本输入
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.input(InputData::Text(text.into()))
+    }
+    
+    /// 音频输入（单声道采样）
+    pub fn audio(self, samples: Vec<f32>) -> Self {
+        self.input(InputData::Audio(samples))
+    }
+    
+    /// Float32张量输入
+    pub fn tensor(self, data: Vec<f32>, shape: Vec<i64>) -> Self {
+        self.input(InputData::Tensor(TensorData { data, shape, dtype: DataType::Float32 }))
+    }
+    
+    /// 指定请求ID，不指定时提交时生成
+    pub fn request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request.request_id = request_id.into();
+        self
+    }
+    
+    /// 设置推理选项
+    pub fn options(mut self, options: InferenceOptions) -> Self {
+        self.request.options = options;
+        self
+    }
+    
+    /// 设置超时时间
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.request.options.timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+    
+    /// 设置置信度阈值
+    pub fn confidence_threshold(mut self, threshold: f32) -> Self {
+        self.request.options.confidence_threshold = Some(threshold);
+        self
+    }
+    
+    /// 构建并验证请求
+    pub fn build(self) -> Result<InferenceRequest> {
+        let mut request = self.request;
+        request.input_data = self.input
+            .ok_or_else(|| AIError::InvalidInput(format!("模型 {} 的推理请求缺少输入数据", request.model_name)))?;
+        request.validate()?;
+        Ok(request)
+    }
+}
+
 /// 推理响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InferenceResponse {
@@ -376,6 +785,20 @@ pub struct InferenceResponse {
     pub metadata: ResponseMetadata,
 }
 
+impl InferenceResponse {
+    /// 没有经过推理流水线的响应（取消、引擎停止）
+    fn without_result(request_id: String, model_name: String, result: InferenceResult) -> Self {
+        Self {
+            request_id,
+            model_name,
+            result,
+            inference_time_ms: 0.0,
+            timestamp: current_timestamp(),
+            metadata: ResponseMetadata::default(),
+        }
+    }
+}
+
 /// 推理结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum InferenceResult {
@@ -384,9 +807,13 @@ pub enum InferenceResult {
     PoseEstimation(Vec<PoseKeypoint>),
     Classification(Vec<ClassificationResult>),
     Segmentation(SegmentationResult),
+    /// 特征向量
+    Embedding(Vec<f32>),
     Text(String),
     Tensor(TensorData),
     Error(String),
+    /// 请求在返回结果前被取消
+    Cancelled,
 }
 
 /// 物体检测结果
@@ -434,10 +861,18 @@ pub struct ClassificationResult {
 /// 分割结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SegmentationResult {
+    /// 按行存放，每像素一个类别ID
     pub mask: Vec<u8>,
     pub width: u32,
     pub height: u32,
+    /// 掩码中出现的类别
     pub classes: Vec<u32>,
+    /// 各类别占画面的比例，按占比从大到小
+    #[serde(default)]
+    pub class_fractions: Vec<ClassFraction>,
+    /// 与输入图像混合的彩色叠加图（RGB8），模型配置了overlay且输入为图像时才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlay: Option<ImageData>,
 }
 
 /// 边界框
@@ -457,7 +892,7 @@ pub struct Point2D {
 }
 
 /// 响应元数据
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ResponseMetadata {
     pub preprocessing_time_ms: f64,
     pub inference_time_ms: f64,
@@ -505,16 +940,34 @@ pub struct AIEngine {
     models: Arc<RwLock<HashMap<String, ModelInstance>>>,
     inference_queue: Arc<Mutex<mpsc::UnboundedReceiver<InferenceRequest>>>,
     inference_sender: mpsc::UnboundedSender<InferenceRequest>,
-    response_handlers: Arc<RwLock<HashMap<String, mpsc::UnboundedSender<InferenceResponse>>>>,
-    inference_handle: Option<tokio::task::JoinHandle<()>>,
+    pending: PendingRequests,
+    tasks: TaskGroup,
     is_running: Arc<RwLock<bool>>,
+    tensor_pool: Arc<TensorPool>,
+    event_bus: Option<EventBus>,
+}
+
+/// 已提交、尚未返回响应的请求
+///
+/// 推理循环完成请求或cancel取消请求时从表中移除，移除的一方负责发送响应，每个请求只收到一次响应
+struct PendingRequest {
+    model_name: String,
+    sender: mpsc::UnboundedSender<InferenceResponse>,
+    cancel: CancellationToken,
 }
 
+type PendingRequests = Arc<RwLock<HashMap<String, PendingRequest>>>;
+
 /// 模型实例
 #[derive(Debug)]
+#[allow(dead_code)]
 struct ModelInstance {
     name: String,
     config: ModelConfig,
+    /// 加载时确定并与输入形状核对过的预处理配置
+    preprocessing: PreprocessingConfig,
+    /// 加载后读到的实际输入输出
+    signature: Option<ModelSignature>,
     loaded_at: Instant,
     inference_count: u64,
     last_used: Instant,
@@ -534,7 +987,7 @@ impl AIEngine {
         let (inference_sender, inference_receiver) = mpsc::unbounded_channel();
         let inference_queue = Arc::new(Mutex::new(inference_receiver));
         
-        let response_handlers = Arc::new(RwLock::new(HashMap::new()));
+        let pending = Arc::new(RwLock::new(HashMap::new()));
         
         let engine = Self {
             config,
@@ -542,18 +995,32 @@ impl AIEngine {
             models,
             inference_queue,
             inference_sender,
-            response_handlers,
-            inference_handle: None,
+            pending,
+            tasks: TaskGroup::new("AI推理引擎"),
             is_running,
+            tensor_pool: Arc::new(TensorPool::new(&PerformanceConfig::default())),
+            event_bus: None,
         };
         
         info!("AI推理引擎初始化完成");
         Ok(engine)
     }
     
+    /// 使用按系统PerformanceConfig创建的共享张量缓冲区池，需在start之前调用
+    pub fn with_tensor_pool(mut self, tensor_pool: Arc<TensorPool>) -> Self {
+        self.tensor_pool = tensor_pool;
+        self
+    }
+    
+    /// 设置发布分割占比事件的事件总线，需要在启动前调用
+    pub fn set_event_bus(&mut self, bus: EventBus) {
+        self.event_bus = Some(bus);
+    }
+    
     /// 启动AI引擎
     pub async fn start(&mut self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
+        let is_running_lock = Arc::clone(&self.is_running);
+        let mut is_running = is_running_lock.write().await;
         if *is_running {
             return Ok(());
         }
@@ -583,7 +1050,8 @@ impl AIEngine {
     
     /// 停止AI引擎
     pub async fn stop(&mut self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
+        let is_running_lock = Arc::clone(&self.is_running);
+        let mut is_running = is_running_lock.write().await;
         if !*is_running {
             return Ok(());
         }
@@ -592,10 +1060,8 @@ impl AIEngine {
         
         *is_running = false;
         
-        // 停止推理循环
-        if let Some(handle) = self.inference_handle.take() {
-            handle.abort();
-        }
+        // 推理循环完成当前请求后退出，排队中的请求收到错误响应
+        self.tasks.shutdown().await;
         
         // 卸载模型
         self.unload_models().await?;
@@ -631,6 +1097,11 @@ impl AIEngine {
                 memory_total: 8 * 1024 * 1024 * 1024, // 8GB
                 memory_available: 6 * 1024 * 1024 * 1024, // 6GB
             },
+            DeviceType::Mock => DeviceInfo {
+                device_type: "Mock".to_string(),
+                device_name: "脚本化推理后端".to_string(),
+                ..DeviceInfo::default()
+            },
             _ => DeviceInfo::default(),
         };
         
@@ -678,20 +1149,40 @@ impl AIEngine {
     async fn load_model(&self, name: &str, config: &ModelConfig) -> Result<ModelInstance> {
         debug!("加载模型: {}", name);
         
-        // 检查模型文件是否存在
+        // 检查模型文件是否存在，Mock设备不需要模型文件
         let model_path = PathBuf::from(&self.config.model_path).join(&config.model_path);
-        if !model_path.exists() {
+        let is_mock = matches!(self.config.device, DeviceType::Mock);
+        if !is_mock && !model_path.exists() {
             return Err(AIError::ModelNotFound(format!(
                 "模型文件不存在: {}", model_path.display()
             )).into());
         }
         
+        // 预处理输出必须与模型声明的输入形状一致，否则推理时才会出错；
+        // 声音事件模型的输入是音频窗口，长度在配置校验时已经检查
+        let preprocessing = config.preprocessing.as_ref().unwrap_or(&self.config.preprocessing_config);
+        if !matches!(config.task, ModelTask::SoundEvent(_)) {
+            preprocessing.check_input_shape(&config.input_shape)?;
+        }
+        
         // 模拟模型加载
-        tokio::time::sleep(Duration::from_millis(100)).await;
+        if !is_mock {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        
+        // 配置与模型实际的输入输出不符时只警告，由用户根据签名修正配置
+        let signature = self.read_signature(name, &model_path);
+        if let Some(signature) = &signature {
+            for mismatch in signature.mismatches(&config.input_shape, &config.output_names) {
+                warn!("模型 '{}' 与配置不符: {}", name, mismatch);
+            }
+        }
         
         let model_instance = ModelInstance {
             name: name.to_string(),
             config: config.clone(),
+            preprocessing: preprocessing.clone(),
+            signature,
             loaded_at: Instant::now(),
             inference_count: 0,
             last_used: Instant::now(),
@@ -700,6 +1191,24 @@ impl AIEngine {
         Ok(model_instance)
     }
     
+    /// 读取模型实际的输入输出：Mock设备使用脚本中的签名，ONNX模型需要vision特性，读取失败时只记录日志
+    #[cfg_attr(not(feature = "vision"), allow(unused_variables))]
+    fn read_signature(&self, name: &str, model_path: &Path) -> Option<ModelSignature> {
+        if matches!(self.config.device, DeviceType::Mock) {
+            return self.config.mock.signatures.get(name).cloned();
+        }
+        
+        #[cfg(feature = "vision")]
+        if model_path.extension().is_some_and(|extension| extension == "onnx") {
+            match ModelSignature::read_onnx(model_path) {
+                Ok(signature) => return Some(signature),
+                Err(e) => warn!("读取模型 '{}' 的输入输出失败: {}", name, e),
+            }
+        }
+        
+        None
+    }
+    
     /// 卸载模型
     async fn unload_models(&self) -> Result<()> {
         info!("卸载AI模型...");
@@ -716,41 +1225,57 @@ impl AIEngine {
         let inference_queue = Arc::clone(&self.inference_queue);
         let models = Arc::clone(&self.models);
         let status = Arc::clone(&self.status);
-        let response_handlers = Arc::clone(&self.response_handlers);
-        let is_running = Arc::clone(&self.is_running);
+        let pending = Arc::clone(&self.pending);
+        let shutdown = self.tasks.token();
         let config = self.config.clone();
+        let tensor_pool = Arc::clone(&self.tensor_pool);
+        let event_bus = self.event_bus.clone();
         
-        let handle = tokio::spawn(async move {
+        self.tasks.spawn("推理循环", async move {
             Self::inference_loop(
                 inference_queue,
                 models,
                 status,
-                response_handlers,
-                is_running,
+                pending,
+                shutdown,
                 config,
+                tensor_pool,
+                event_bus,
             ).await
         });
         
-        self.inference_handle = Some(handle);
         Ok(())
     }
     
     /// 推理循环
+    #[allow(clippy::too_many_arguments)]
     async fn inference_loop(
         inference_queue: Arc<Mutex<mpsc::UnboundedReceiver<InferenceRequest>>>,
         models: Arc<RwLock<HashMap<String, ModelInstance>>>,
         status: Arc<RwLock<AIStatus>>,
-        response_handlers: Arc<RwLock<HashMap<String, mpsc::UnboundedSender<InferenceResponse>>>>,
-        is_running: Arc<RwLock<bool>>,
+        pending: PendingRequests,
+        shutdown: CancellationToken,
         config: AIConfig,
+        tensor_pool: Arc<TensorPool>,
+        event_bus: Option<EventBus>,
     ) {
         let mut queue = inference_queue.lock().await;
         
-        while let Some(request) = queue.recv().await {
-            // 检查是否应该停止
-            if !*is_running.read().await {
-                break;
-            }
+        loop {
+            let request = tokio::select! {
+                biased;
+                _ = shutdown.cancelled() => break,
+                request = queue.recv() => match request {
+                    Some(request) => request,
+                    None => break,
+                },
+            };
+            
+            // 排队期间已被取消的请求已经收到响应，直接丢弃
+            let cancel = match pending.read().await.get(&request.request_id) {
+                Some(entry) if !entry.cancel.is_cancelled() => entry.cancel.clone(),
+                _ => continue,
+            };
             
             let start_time = Instant::now();
             
@@ -759,10 +1284,18 @@ impl AIEngine {
                 request,
                 &models,
                 &config,
+                &tensor_pool,
+                &cancel,
             ).await;
             
             let total_time = start_time.elapsed();
             
+            // 执行期间被取消：cancel已经发送了响应并计入统计
+            let Some(entry) = pending.write().await.remove(&response.request_id) else {
+                debug!("[{}] 推理请求已取消，丢弃结果", response.request_id);
+                continue;
+            };
+            
             // 更新统计
             {
                 let mut status = status.write().await;
@@ -790,23 +1323,66 @@ impl AIEngine {
                 status.inference_stats.throughput_fps = status.performance_stats.fps;
             }
             
+            if let InferenceResult::Error(e) = &response.result {
+                warn!("[{}] 推理请求返回错误: {}", response.request_id, e);
+            }
+            
+            if let Some(bus) = &event_bus {
+                Self::publish_result_events(bus, &config, &response);
+            }
+            
             // 发送响应
-            let handlers = response_handlers.read().await;
-            if let Some(sender) = handlers.get(&response.request_id) {
-                if let Err(e) = sender.send(response) {
-                    error!("发送推理响应失败: {}", e);
-                }
+            let request_id = response.request_id.clone();
+            if let Err(e) = entry.sender.send(response) {
+                error!("[{}] 发送推理响应失败: {}", request_id, e);
+            }
+        }
+        
+        // 排空队列：未处理的请求返回错误，调用方不会一直等待响应
+        let mut pending = pending.write().await;
+        while let Ok(request) = queue.try_recv() {
+            if let Some(entry) = pending.remove(&request.request_id) {
+                let _ = entry.sender.send(InferenceResponse::without_result(
+                    request.request_id,
+                    request.model_name,
+                    InferenceResult::Error("推理引擎已停止".to_string()),
+                ));
             }
         }
         
         info!("推理循环结束");
     }
     
+    /// 把分割占比和声音事件发布到事件总线
+    fn publish_result_events(bus: &EventBus, config: &AIConfig, response: &InferenceResponse) {
+        match &response.result {
+            InferenceResult::Segmentation(segmentation) => {
+                bus.publish("ai", RobotEvent::SegmentationCoverage(SegmentationCoverage {
+                    model_name: response.model_name.clone(),
+                    classes: segmentation.class_fractions.clone(),
+                }));
+            },
+            InferenceResult::Classification(classes) if sound_event_config(config, &response.model_name).is_some() => {
+                for class in classes {
+                    bus.publish("ai", RobotEvent::SoundEventDetected(SoundEvent {
+                        model_name: response.model_name.clone(),
+                        class_id: class.class_id,
+                        class_name: class.class_name.clone(),
+                        confidence: class.confidence as f64,
+                    }));
+                }
+            },
+            _ => {},
+        }
+    }
+    
     /// 处理推理请求
     async fn process_inference_request(
         request: InferenceRequest,
         models: &Arc<RwLock<HashMap<String, ModelInstance>>>,
         config: &AIConfig,
+        tensor_pool: &TensorPool,
+        cancel: &CancellationToken,
     ) -> InferenceResponse {
         let start_time = Instant::now();
         let mut preprocessing_time = Duration::ZERO;
@@ -816,47 +1392,80 @@ impl AIEngine {
         let result = async {
             // 检查模型是否存在
             let models_guard = models.read().await;
-            if !models_guard.contains_key(&request.model_name) {
+            let Some(preprocessing) = models_guard.get(&request.model_name).map(|model| model.preprocessing.clone()) else {
                 return InferenceResult::Error(
                     format!("模型未找到: {}", request.model_name)
                 );
-            }
+            };
             drop(models_guard);
             
+            // Mock设备有脚本时直接返回脚本结果
+            if let Some(result) = Self::scripted_result(&request.model_name, models, config).await {
+                return if cancel.is_cancelled() { InferenceResult::Cancelled } else { result };
+            }
+            
             // 预处理
             let preprocess_start = Instant::now();
             let preprocessed_data = match Self::preprocess_input(
                 &request.input_data,
-                &config.preprocessing_config,
+                &preprocessing,
+                sound_event_config(config, &request.model_name),
+                tensor_pool,
             ).await {
                 Ok(data) => data,
                 Err(e) => return InferenceResult::Error(format!("预处理失败: {}", e)),
             };
             preprocessing_time = preprocess_start.elapsed();
             
+            // 各阶段之间检查是否已被取消
+            if cancel.is_cancelled() {
+                tensor_pool.release(preprocessed_data.data);
+                return InferenceResult::Cancelled;
+            }
+            
             // 推理
             let inference_start = Instant::now();
             let raw_output = match Self::run_inference(
                 &request.model_name,
                 &preprocessed_data,
                 models,
+                config,
+                tensor_pool,
             ).await {
                 Ok(output) => output,
                 Err(e) => return InferenceResult::Error(format!("推理失败: {}", e)),
             };
             inference_time = inference_start.elapsed();
+            tensor_pool.release(preprocessed_data.data);
+            
+            if cancel.is_cancelled() {
+                tensor_pool.release(raw_output.data);
+                return InferenceResult::Cancelled;
+            }
             
             // 后处理
             let postprocess_start = Instant::now();
-            let result = match Self::postprocess_output(
+            let result = Self::postprocess_output(
                 &request.model_name,
-                raw_output,
+                &raw_output,
                 &config.postprocessing_config,
                 config,
-            ).await {
+            ).await;
+            tensor_pool.release(raw_output.data);
+            let mut result = match result {
                 Ok(result) => result,
                 Err(e) => return InferenceResult::Error(format!("后处理失败: {}", e)),
             };
+            
+            if let (InferenceResult::Segmentation(segmentation), InputData::Image(image)) = (&mut result, &request.input_data) {
+                let task = config.model_configs.get(&request.model_name).map(|model_config| &model_config.task);
+                if let Some(&ModelTask::Segmentation { overlay: true, overlay_alpha, .. }) = task {
+                    match segmentation::overlay(image, &segmentation.mask, segmentation.width, segmentation.height, overlay_alpha) {
+                        Ok(overlay) => segmentation.overlay = Some(overlay),
+                        Err(e) => warn!("[{}] 生成分割叠加图失败: {}", request.request_id, e),
+                    }
+                }
+            }
             postprocessing_time = postprocess_start.elapsed();
             
             result
@@ -881,17 +1490,55 @@ impl AIEngine {
         }
     }
     
+    /// Mock设备下取模型的下一个脚本结果，按推理次数循环；非Mock设备或没有脚本时为None
+    async fn scripted_result(
+        model_name: &str,
+        models: &Arc<RwLock<HashMap<String, ModelInstance>>>,
+        config: &AIConfig,
+    ) -> Option<InferenceResult> {
+        if !matches!(config.device, DeviceType::Mock) {
+            return None;
+        }
+        let script = config.mock.responses.get(model_name).filter(|script| !script.is_empty())?;
+        
+        tokio::time::sleep(Duration::from_millis(config.mock.latency_ms)).await;
+        
+        let mut models_guard = models.write().await;
+        let model = models_guard.get_mut(model_name)?;
+        let result = script[(model.inference_count % script.len() as u64) as usize].clone();
+        model.inference_count += 1;
+        model.last_used = Instant::now();
+        Some(result)
+    }
+    
     /// 预处理输入数据
     async fn preprocess_input(
         input_data: &InputData,
         config: &PreprocessingConfig,
+        sound: Option<&SoundEventConfig>,
+        tensor_pool: &TensorPool,
     ) -> Result<TensorData> {
         match input_data {
             InputData::Image(image_data) => {
-                Self::preprocess_image(image_data, config).await
+                Self::preprocess_image(image_data, config, tensor_pool).await
             },
             InputData::Tensor(tensor_data) => {
-                Ok(tensor_data.clone())
+                let mut data = tensor_pool.acquire(tensor_data.data.len());
+                data.copy_from_slice(&tensor_data.data);
+                Ok(TensorData { data, ..tensor_data.clone() })
+            },
+            InputData::Audio(samples) => {
+                let Some(sound) = sound else {
+                    return Err(AIError::Preprocessing("音频输入只支持声音事件任务的模型".to_string()).into());
+                };
+                // 样本应已是模型采样率，长度不符时截取最后一个窗口或在前面补零
+                let mut data = tensor_pool.acquire(sound.window_samples());
+                sound_events::fit_window(samples, &mut data);
+                Ok(TensorData {
+                    shape: vec![1, data.len() as i64],
+                    data,
+                    dtype: DataType::Float32,
+                })
             },
             _ => {
                 Err(AIError::Preprocessing("不支持的输入数据类型".to_string()).into())
@@ -899,32 +1546,21 @@ impl AIEngine {
         }
     }
     
-    /// 预处理图像数据
+    /// 预处理图像数据：缩放、转换通道、归一化并按布局排列
     async fn preprocess_image(
         image_data: &ImageData,
         config: &PreprocessingConfig,
+        tensor_pool: &TensorPool,
     ) -> Result<TensorData> {
-        // 模拟图像预处理
-        let (target_width, target_height) = config.target_size;
-        let channels = 3;
-        
-        // 创建模拟的预处理数据
-        let data_size = (target_width * target_height * channels) as usize;
-        let mut data = vec![0.5f32; data_size]; // 模拟归一化后的数据
-        
-        // 模拟归一化
-        if config.normalize {
-            for (i, value) in data.iter_mut().enumerate() {
-                let channel = i % channels as usize;
-                if channel < config.mean.len() && channel < config.std.len() {
-                    *value = (*value - config.mean[channel]) / config.std[channel];
-                }
-            }
+        let mut data = tensor_pool.acquire(config.output_shape().iter().product::<i64>() as usize);
+        if let Err(e) = preprocess_image_into(image_data, config, &mut data) {
+            tensor_pool.release(data);
+            return Err(e);
         }
         
         Ok(TensorData {
             data,
-            shape: vec![1, channels as i64, target_height as i64, target_width as i64],
+            shape: config.output_shape(),
             dtype: DataType::Float32,
         })
     }
@@ -932,11 +1568,17 @@ impl AIEngine {
     /// 运行推理
     async fn run_inference(
         model_name: &str,
-        input_data: &TensorData,
+        _input_data: &TensorData,
         models: &Arc<RwLock<HashMap<String, ModelInstance>>>,
+        config: &AIConfig,
+        tensor_pool: &TensorPool,
     ) -> Result<TensorData> {
         // 模拟推理过程
-        tokio::time::sleep(Duration::from_millis(50)).await;
+        let latency_ms = match config.device {
+            DeviceType::Mock => config.mock.latency_ms,
+            _ => 50,
+        };
+        tokio::time::sleep(Duration::from_millis(latency_ms)).await;
         
         // 更新模型使用统计
         {
@@ -952,7 +1594,10 @@ impl AIEngine {
             "object_detection" => {
                 // YOLO输出格式: [batch, 84, 8400] (80类 + 4坐标)
                 let output_size = 84 * 8400;
-                let data = (0..output_size).map(|i| (i as f32) * 0.001).collect();
+                let mut data = tensor_pool.acquire(output_size);
+                for (i, value) in data.iter_mut().enumerate() {
+                    *value = (i as f32) * 0.001;
+                }
                 TensorData {
                     data,
                     shape: vec![1, 84, 8400],
@@ -978,7 +1623,44 @@ impl AIEngine {
                 }
             },
             _ => {
-                return Err(AIError::ModelNotFound(model_name.to_string()).into());
+                let Some(model_config) = config.model_configs.get(model_name) else {
+                    return Err(AIError::ModelNotFound(model_name.to_string()).into());
+                };
+                // 分类输出每个类别一个logit，嵌入输出固定维数的特征向量，分割按类别数把画面分成竖条，
+                // 声音事件输出各类别的概率，第一个类别最高
+                let vector = |len: usize| TensorData {
+                    data: (0..len).map(|i| ((i * 37) % 11) as f32 * 0.3).collect(),
+                    shape: vec![1, len as i64],
+                    dtype: DataType::Float32,
+                };
+                match model_config.task {
+                    ModelTask::Classification { .. } => vector(model_config.class_names.len().max(1)),
+                    ModelTask::Embedding { .. } => vector(SIMULATED_EMBEDDING_DIM),
+                    ModelTask::Segmentation { .. } => {
+                        let classes = model_config.class_names.len().max(2);
+                        let (width, height) = config.preprocessing_for(model_name).target_size;
+                        let (width, height) = (width as usize, height as usize);
+                        let mut data = tensor_pool.acquire(classes * width * height);
+                        for (i, value) in data.iter_mut().enumerate() {
+                            let (class_id, x) = (i / (width * height), i % width);
+                            *value = if x * classes / width == class_id { 1.0 } else { 0.0 };
+                        }
+                        TensorData {
+                            data,
+                            shape: vec![1, classes as i64, height as i64, width as i64],
+                            dtype: DataType::Float32,
+                        }
+                    },
+                    ModelTask::SoundEvent(_) => {
+                        let classes = model_config.class_names.len().max(1);
+                        TensorData {
+                            data: (0..classes).map(|i| if i == 0 { 0.9 } else { 0.05 }).collect(),
+                            shape: vec![1, classes as i64],
+                            dtype: DataType::Float32,
+                        }
+                    },
+                    ModelTask::Builtin => return Err(AIError::ModelNotFound(model_name.to_string()).into()),
+                }
             }
         };
         
@@ -988,10 +1670,43 @@ impl AIEngine {
     /// 后处理输出数据
     async fn postprocess_output(
         model_name: &str,
-        output_data: TensorData,
+        output_data: &TensorData,
         config: &PostprocessingConfig,
         ai_config: &AIConfig,
     ) -> Result<InferenceResult> {
+        if let Some(model_config) = ai_config.model_configs.get(model_name) {
+            match model_config.task {
+                ModelTask::Classification { top_k, softmax } => {
+                    let classes = classify(&output_data.data, model_config, top_k, softmax)?;
+                    return Ok(InferenceResult::Classification(classes));
+                },
+                ModelTask::Embedding { normalize } => {
+                    return Ok(InferenceResult::Embedding(embedding(&output_data.data, normalize)));
+                },
+                ModelTask::Segmentation { min_fraction, .. } => {
+                    let (mask, width, height) = segmentation::decode_mask(&output_data.data, &output_data.shape)
+                        .map_err(|e| AIError::Postprocessing(e.to_string()))?;
+                    let all = segmentation::class_fractions(&mask, &model_config.class_names, 0.0);
+                    let mut classes: Vec<u32> = all.iter().map(|class| class.class_id).collect();
+                    classes.sort_unstable();
+                    let class_fractions = all.into_iter().filter(|class| class.fraction >= min_fraction).collect();
+                    return Ok(InferenceResult::Segmentation(SegmentationResult {
+                        mask,
+                        width,
+                        height,
+                        classes,
+                        class_fractions,
+                        overlay: None,
+                    }));
+                },
+                ModelTask::SoundEvent(ref sound) => {
+                    let classes = classify(&output_data.data, model_config, sound.top_k, sound.softmax)?;
+                    return Ok(InferenceResult::Classification(classes));
+                },
+                ModelTask::Builtin => {},
+            }
+        }
+        
         match model_name {
             "object_detection" => {
                 let detections = Self::postprocess_object_detection(
@@ -1017,7 +1732,7 @@ impl AIEngine {
     
     /// 后处理物体检测结果
     async fn postprocess_object_detection(
-        output_data: TensorData,
+        output_data: &TensorData,
         config: &PostprocessingConfig,
         ai_config: &AIConfig,
     ) -> Result<Vec<ObjectDetection>> {
@@ -1029,7 +1744,7 @@ impl AIEngine {
             if output_data.data.len() > 5 && output_data.data[4] > config.score_threshold {
                 detections.push(ObjectDetection {
                     class_id: 0,
-                    class_name: model_config.class_names.get(0)
+                    class_name: model_config.class_names.first()
                         .unwrap_or(&"unknown".to_string()).clone(),
                     confidence: output_data.data[4],
                     bbox: BoundingBox {
@@ -1047,7 +1762,7 @@ impl AIEngine {
     
     /// 后处理人脸检测结果
     async fn postprocess_face_detection(
-        output_data: TensorData,
+        output_data: &TensorData,
     ) -> Result<Vec<FaceDetection>> {
         let mut faces = Vec::new();
         
@@ -1069,7 +1784,7 @@ impl AIEngine {
     
     /// 后处理姿态估计结果
     async fn postprocess_pose_estimation(
-        output_data: TensorData,
+        output_data: &TensorData,
     ) -> Result<Vec<PoseKeypoint>> {
         let mut poses = Vec::new();
         
@@ -1096,30 +1811,75 @@ impl AIEngine {
         Ok(poses)
     }
     
-    /// 提交推理请求
+    /// 提交推理请求，request_id为空时生成ULID，响应中带有同一个ID
     pub async fn submit_inference(
         &self,
-        request: InferenceRequest,
+        mut request: InferenceRequest,
     ) -> Result<mpsc::UnboundedReceiver<InferenceResponse>> {
+        request.validate()?;
         let (response_sender, response_receiver) = mpsc::unbounded_channel();
         
+        if request.request_id.is_empty() {
+            request.request_id = new_id();
+        }
+        debug!("[{}] 提交推理请求: {}", request.request_id, request.model_name);
+        
         // 注册响应处理器
         {
-            let mut handlers = self.response_handlers.write().await;
-            handlers.insert(request.request_id.clone(), response_sender);
+            let mut pending = self.pending.write().await;
+            if pending.contains_key(&request.request_id) {
+                return Err(AIError::InvalidInput(format!("请求ID {} 已有未完成的请求", request.request_id)).into());
+            }
+            pending.insert(request.request_id.clone(), PendingRequest {
+                model_name: request.model_name.clone(),
+                sender: response_sender,
+                cancel: CancellationToken::new(),
+            });
         }
         
         // 提交请求
-        self.inference_sender.send(request)
-            .map_err(|e| AIError::Inference(format!("提交推理请求失败: {}", e)))?;
+        let request_id = request.request_id.clone();
+        if let Err(e) = self.inference_sender.send(request) {
+            self.pending.write().await.remove(&request_id);
+            return Err(AIError::Inference(format!("提交推理请求失败: {}", e)).into());
+        }
         
         Ok(response_receiver)
     }
     
+    /// 取消尚未返回响应的推理请求
+    ///
+    /// 排队中的请求不再执行，正在执行的请求在下一个处理阶段之前停止；调用方立即收到结果为
+    /// Cancelled的响应。请求不存在或已经返回响应时返回false
+    pub async fn cancel(&self, request_id: &str) -> bool {
+        let Some(entry) = self.pending.write().await.remove(request_id) else {
+            return false;
+        };
+        entry.cancel.cancel();
+        debug!("[{}] 取消推理请求: {}", request_id, entry.model_name);
+        
+        self.status.write().await.inference_stats.cancelled_inferences += 1;
+        let _ = entry.sender.send(InferenceResponse::without_result(
+            request_id.to_string(),
+            entry.model_name,
+            InferenceResult::Cancelled,
+        ));
+        true
+    }
+    
     /// 获取状态
     pub async fn get_status(&self) -> Result<AIStatus> {
-        let status = self.status.read().await;
-        Ok(status.clone())
+        let mut status = self.status.read().await.clone();
+        status.tensor_pool = self.tensor_pool.stats();
+        status.model_signatures = self.models.read().await.iter()
+            .filter_map(|(name, model)| Some((name.clone(), model.signature.clone()?)))
+            .collect();
+        Ok(status)
+    }
+    
+    /// 已加载模型实际的输入输出，模型未加载或无法读取时为None
+    pub async fn model_signature(&self, model_name: &str) -> Option<ModelSignature> {
+        self.models.read().await.get(model_name)?.signature.clone()
     }
     
     /// 获取已加载的模型列表
@@ -1132,6 +1892,54 @@ impl AIEngine {
     pub async fn is_running(&self) -> bool {
         *self.is_running.read().await
     }
+    
+    /// 卸载超过idle未使用的模型，返回卸载的模型名称；之后请求这些模型会失败，直到reload_models
+    pub async fn unload_idle_models(&self, idle: Duration) -> Vec<String> {
+        let mut models = self.models.write().await;
+        let unloaded: Vec<String> = models.values()
+            .filter(|model| model.last_used.elapsed() >= idle)
+            .map(|model| model.name.clone())
+            .collect();
+        for name in &unloaded {
+            models.remove(name);
+            info!("模型 '{}' 空闲，已卸载", name);
+        }
+        
+        if !unloaded.is_empty() {
+            self.status.write().await.loaded_models = models.keys().cloned().collect();
+        }
+        unloaded
+    }
+    
+    /// 释放张量缓冲区池中的空闲缓冲区，返回释放的内存（MB）
+    pub fn release_tensor_buffers(&self) -> f64 {
+        let pooled_mb = self.tensor_pool.stats().pooled_mb;
+        self.tensor_pool.clear();
+        pooled_mb
+    }
+    
+    /// 重新加载配置中尚未加载的模型，返回加载成功的模型名称
+    pub async fn reload_models(&self) -> Vec<String> {
+        let mut models = self.models.write().await;
+        let mut reloaded = Vec::new();
+        for (name, config) in &self.config.model_configs {
+            if models.contains_key(name) {
+                continue;
+            }
+            match self.load_model(name, config).await {
+                Ok(model_instance) => {
+                    models.insert(name.clone(), model_instance);
+                    reloaded.push(name.clone());
+                },
+                Err(e) => warn!("模型 '{}' 重新加载失败: {}", name, e),
+            }
+        }
+        
+        if !reloaded.is_empty() {
+            self.status.write().await.loaded_models = models.keys().cloned().collect();
+        }
+        reloaded
+    }
 }
 
 impl LifecycleManager for AIEngine {
@@ -1149,6 +1957,164 @@ impl LifecycleManager for AIEngine {
     }
 }
 
+/// 声音事件模型的配置，其他模型为None
+fn sound_event_config<'a>(config: &'a AIConfig, model_name: &str) -> Option<&'a SoundEventConfig> {
+    match &config.model_configs.get(model_name)?.task {
+        ModelTask::SoundEvent(sound) => Some(sound),
+        _ => None,
+    }
+}
+
+/// 模拟推理时嵌入模型输出的维数
+const SIMULATED_EMBEDDING_DIM: usize = 128;
+
+/// 分类后处理：按得分从高到低取前top_k个类别，丢弃低于模型置信度阈值的类别
+///
+/// 配置了类别名称时得分个数必须与之相同，没有配置时类别名为class_<序号>
+fn classify(scores: &[f32], config: &ModelConfig, top_k: usize, apply_softmax: bool) -> Result<Vec<ClassificationResult>> {
+    if !config.class_names.is_empty() && config.class_names.len() != scores.len() {
+        return Err(AIError::Postprocessing(format!(
+            "模型输出 {} 个得分，配置了 {} 个类别名称", scores.len(), config.class_names.len()
+        )).into());
+    }
+    
+    let mut scores = scores.to_vec();
+    if apply_softmax {
+        softmax(&mut scores);
+    }
+    
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+    Ok(order.into_iter()
+        .take(top_k)
+        .filter(|&i| scores[i] >= config.confidence_threshold)
+        .map(|i| ClassificationResult {
+            class_id: i as u32,
+            class_name: config.class_names.get(i).cloned().unwrap_or_else(|| format!("class_{}", i)),
+            confidence: scores[i],
+        })
+        .collect())
+}
+
+/// 嵌入后处理：normalize为true时归一化为单位长度，全零向量保持不变
+fn embedding(values: &[f32], normalize: bool) -> Vec<f32> {
+    let mut vector = values.to_vec();
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if normalize && norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+/// 等比缩放后两侧的填充值，与YOLO的letterbox相同
+const PAD_VALUE: f32 = 114.0 / 255.0;
+
+/// 每个像素占用的字节数由图像格式决定
+fn bytes_per_pixel(format: ImageFormat) -> u32 {
+    match format {
+        ImageFormat::RGB8 | ImageFormat::BGR8 => 3,
+        ImageFormat::RGBA8 | ImageFormat::BGRA8 => 4,
+        ImageFormat::Gray8 => 1,
+        ImageFormat::Gray16 => 2,
+    }
+}
+
+/// (x, y)处像素的RGB值，范围0-1
+fn pixel_rgb(image: &ImageData, x: usize, y: usize) -> [f32; 3] {
+    let stride = image.channels as usize;
+    let offset = (y * image.width as usize + x) * stride;
+    let p = &image.data[offset..offset + stride];
+    let byte = |i: usize| p[i] as f32 / 255.0;
+    match image.format {
+        ImageFormat::RGB8 | ImageFormat::RGBA8 => [byte(0), byte(1), byte(2)],
+        ImageFormat::BGR8 | ImageFormat::BGRA8 => [byte(2), byte(1), byte(0)],
+        ImageFormat::Gray8 => [byte(0); 3],
+        ImageFormat::Gray16 => [u16::from_le_bytes([p[0], p[1]]) as f32 / 65535.0; 3],
+    }
+}
+
+/// 在源图像坐标(x, y)处取样，坐标以像素中心为准
+fn sample_rgb(image: &ImageData, x: f32, y: f32, method: &ResizeMethod) -> [f32; 3] {
+    let (max_x, max_y) = (image.width as usize - 1, image.height as usize - 1);
+    match method {
+        ResizeMethod::Nearest => {
+            let nx = (x + 0.5).floor().clamp(0.0, max_x as f32) as usize;
+            let ny = (y + 0.5).floor().clamp(0.0, max_y as f32) as usize;
+            pixel_rgb(image, nx, ny)
+        },
+        // 双三次插值对模型输入的影响很小，按双线性处理
+        ResizeMethod::Bilinear | ResizeMethod::Bicubic => {
+            let (x, y) = (x.clamp(0.0, max_x as f32), y.clamp(0.0, max_y as f32));
+            let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+            let (x1, y1) = ((x0 + 1).min(max_x), (y0 + 1).min(max_y));
+            let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+            let (a, b) = (pixel_rgb(image, x0, y0), pixel_rgb(image, x1, y0));
+            let (c, d) = (pixel_rgb(image, x0, y1), pixel_rgb(image, x1, y1));
+            std::array::from_fn(|i| {
+                let top = a[i] + (b[i] - a[i]) * fx;
+                let bottom = c[i] + (d[i] - c[i]) * fx;
+                top + (bottom - top) * fy
+            })
+        },
+    }
+}
+
+/// 把图像按预处理配置写入out，out的长度必须等于输出形状的元素个数
+fn preprocess_image_into(image: &ImageData, config: &PreprocessingConfig, out: &mut [f32]) -> Result<()> {
+    if image.width == 0 || image.height == 0 || !image.is_valid() || image.channels != bytes_per_pixel(image.format) {
+        return Err(AIError::Preprocessing(format!(
+            "图像数据与尺寸 {}x{}x{}（{:?}）不符", image.width, image.height, image.channels, image.format
+        )).into());
+    }
+    
+    let (target_w, target_h) = (config.target_size.0 as usize, config.target_size.1 as usize);
+    let (src_w, src_h) = (image.width as f32, image.height as f32);
+    
+    // 图像内容在输出中占据的区域
+    let (content_w, content_h) = if config.keep_aspect_ratio {
+        let scale = (target_w as f32 / src_w).min(target_h as f32 / src_h);
+        (((src_w * scale).round() as usize).clamp(1, target_w), ((src_h * scale).round() as usize).clamp(1, target_h))
+    } else {
+        (target_w, target_h)
+    };
+    let (offset_x, offset_y) = ((target_w - content_w) / 2, (target_h - content_h) / 2);
+    let (scale_x, scale_y) = (src_w / content_w as f32, src_h / content_h as f32);
+    
+    let channels = config.color.channels();
+    let stats: Vec<(f32, f32)> = (0..channels).map(|c| config.channel_stats(c)).collect();
+    let plane = target_w * target_h;
+    
+    for y in 0..target_h {
+        for x in 0..target_w {
+            let inside = (offset_x..offset_x + content_w).contains(&x) && (offset_y..offset_y + content_h).contains(&y);
+            let [r, g, b] = if inside {
+                let sx = (x - offset_x) as f32 * scale_x + 0.5 * scale_x - 0.5;
+                let sy = (y - offset_y) as f32 * scale_y + 0.5 * scale_y - 0.5;
+                sample_rgb(image, sx, sy, &config.resize_method)
+            } else {
+                [PAD_VALUE; 3]
+            };
+            
+            let values = match config.color {
+                ColorMode::RGB => [r, g, b],
+                ColorMode::BGR => [b, g, r],
+                ColorMode::Grayscale => [0.299 * r + 0.587 * g + 0.114 * b, 0.0, 0.0],
+            };
+            
+            for (c, &(mean, std)) in stats.iter().enumerate() {
+                let value = if config.normalize { (values[c] - mean) / std } else { values[c] };
+                let index = match config.layout {
+                    TensorLayout::NCHW => c * plane + y * target_w + x,
+                    TensorLayout::NHWC => (y * target_w + x) * channels + c,
+                };
+                out[index] = value;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1172,6 +2138,8 @@ mod tests {
             confidence_threshold: 0.5,
             nms_threshold: 0.4,
             class_names: vec!["test".to_string()],
+            preprocessing: None,
+            task: ModelTask::default(),
         };
         assert!(config.validate().is_ok());
         
@@ -1180,6 +2148,258 @@ mod tests {
         assert!(invalid_config.validate().is_err());
     }
     
+    #[tokio::test]
+    async fn test_per_model_preprocessing_layouts_and_shape_check() {
+        let gray_nhwc = PreprocessingConfig {
+            normalize: false,
+            resize_method: ResizeMethod::Nearest,
+            target_size: (2, 2),
+            keep_aspect_ratio: true,
+            layout: TensorLayout::NHWC,
+            color: ColorMode::Grayscale,
+            ..PreprocessingConfig::default()
+        };
+        // 灰度输入的均值和标准差只能有1个值
+        assert!(PreprocessingConfig { normalize: true, ..gray_nhwc.clone() }.validate().is_err());
+        let gray_nhwc = PreprocessingConfig { mean: vec![0.0], std: vec![1.0], ..gray_nhwc };
+        assert!(gray_nhwc.validate().is_ok());
+        assert!(gray_nhwc.check_input_shape(&[1, 2, 2, 1]).is_ok());
+        assert!(gray_nhwc.check_input_shape(&[-1, 2, 2, 1]).is_ok());
+        assert!(gray_nhwc.check_input_shape(&[1, 1, 2, 2]).is_err());
+        
+        // 2x1的BGR图像：等比缩放后占据第一行，第二行填充
+        let image = ImageData::from_raw(2, 1, 3, vec![0, 0, 255, 255, 255, 255], ImageFormat::BGR8);
+        let mut out = vec![0.0; 4];
+        preprocess_image_into(&image, &gray_nhwc, &mut out).unwrap();
+        assert!((out[0] - 0.299).abs() < 1e-5 && (out[1] - 1.0).abs() < 1e-5);
+        assert!(out[2..].iter().all(|v| (v - PAD_VALUE).abs() < 1e-5));
+        
+        // NCHW的RGB：通道按平面排列，归一化使用逐通道的均值和标准差
+        let rgb_nchw = PreprocessingConfig {
+            mean: vec![0.5, 0.0, 0.0],
+            std: vec![0.5, 1.0, 2.0],
+            target_size: (2, 1),
+            ..gray_nhwc.clone()
+        };
+        let rgb_nchw = PreprocessingConfig { layout: TensorLayout::NCHW, color: ColorMode::RGB, normalize: true, ..rgb_nchw };
+        assert_eq!(rgb_nchw.output_shape(), vec![1, 3, 1, 2]);
+        let mut out = vec![0.0; 6];
+        preprocess_image_into(&image, &rgb_nchw, &mut out).unwrap();
+        assert_eq!(out, vec![1.0, 1.0, 0.0, 1.0, 0.0, 0.5]);
+        
+        // 格式与通道数不符的图像被拒绝
+        let rgba = ImageData::from_raw(1, 1, 3, vec![0, 0, 0], ImageFormat::RGBA8);
+        assert!(preprocess_image_into(&rgba, &rgb_nchw, &mut out).is_err());
+        
+        // 加载时核对输入形状：与全局预处理不符的模型不加载，有自己配置的模型正常加载
+        let mut config = AIConfig { device: DeviceType::Mock, ..AIConfig::default() };
+        assert_eq!(config.preprocessing_for("pose_estimation").target_size, (192, 256));
+        let model = |input_shape: Vec<i64>, preprocessing: Option<PreprocessingConfig>| ModelConfig {
+            model_path: "gray.tflite".to_string(),
+            input_shape,
+            output_names: vec!["output".to_string()],
+            confidence_threshold: 0.5,
+            nms_threshold: 0.4,
+            class_names: Vec::new(),
+            preprocessing,
+            task: ModelTask::default(),
+        };
+        config.model_configs.insert("gray".to_string(), model(vec![1, 2, 2, 1], Some(gray_nhwc)));
+        config.model_configs.insert("mismatched".to_string(), model(vec![1, 3, 224, 224], None));
+        
+        let mut engine = AIEngine::new(config).await.unwrap();
+        engine.start().await.unwrap();
+        let mut loaded = engine.get_loaded_models().await;
+        loaded.sort();
+        assert_eq!(loaded, vec!["face_detection", "gray", "object_detection", "pose_estimation"]);
+        engine.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_model_signatures_are_reported_in_status() {
+        let tensor = |name: &str, shape: Vec<i64>| crate::model_signature::TensorInfo {
+            name: name.to_string(),
+            shape,
+            dtype: "f32".to_string(),
+        };
+        let mut config = AIConfig { device: DeviceType::Mock, ..AIConfig::default() };
+        // 模型实际的输出名为output，配置中为output0：只警告，模型照常加载
+        config.mock.signatures.insert("object_detection".to_string(), ModelSignature {
+            inputs: vec![tensor("images", vec![1, 3, 640, 640])],
+            outputs: vec![tensor("output", vec![1, 84, 8400])],
+        });
+        
+        let mut engine = AIEngine::new(config.clone()).await.unwrap();
+        engine.start().await.unwrap();
+        let signature = engine.model_signature("object_detection").await.unwrap();
+        assert_eq!(signature.output_names(), vec!["output"]);
+        assert_eq!(signature.mismatches(&[1, 3, 640, 640], &["output0".to_string()]).len(), 1);
+        assert!(engine.model_signature("face_detection").await.is_none());
+        
+        let status = engine.get_status().await.unwrap();
+        assert_eq!(status.model_signatures.len(), 1);
+        assert!(status.loaded_models.contains(&"object_detection".to_string()));
+        engine.stop().await.unwrap();
+        
+        // 签名只能对应已配置的模型
+        config.mock.signatures.insert("unknown".to_string(), ModelSignature::default());
+        assert!(config.validate().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_classification_and_embedding_tasks() {
+        let task: ModelTask = serde_json::from_value(serde_json::json!({"type": "classification", "top_k": 2})).unwrap();
+        assert_eq!(task, ModelTask::Classification { top_k: 2, softmax: true });
+        
+        let mut config = AIConfig { device: DeviceType::Mock, ..AIConfig::default() };
+        let model = |class_names: Vec<String>, task: ModelTask| ModelConfig {
+            model_path: "model.onnx".to_string(),
+            input_shape: vec![1, 3, 640, 640],
+            output_names: vec!["output".to_string()],
+            confidence_threshold: 0.1,
+            nms_threshold: 0.5,
+            class_names,
+            preprocessing: None,
+            task,
+        };
+        let names = vec!["cat".to_string(), "dog".to_string(), "bird".to_string()];
+        config.model_configs.insert("scene".to_string(), model(names.clone(), task));
+        config.model_configs.insert("reid".to_string(), model(Vec::new(), ModelTask::Embedding { normalize: true }));
+        
+        // softmax之后按得分取前两个，丢弃低于阈值的
+        let scores = classify(&[1.0, 3.0, 0.0], &config.model_configs["scene"], 2, true).unwrap();
+        assert_eq!(scores.iter().map(|c| c.class_name.as_str()).collect::<Vec<_>>(), vec!["dog", "cat"]);
+        assert!((scores[0].confidence - 0.844).abs() < 1e-3);
+        let scores = classify(&[0.05, 0.9, 0.05], &config.model_configs["scene"], 3, false).unwrap();
+        assert_eq!(scores.len(), 1);
+        assert!(classify(&[0.5, 0.5], &config.model_configs["scene"], 2, false).is_err());
+        assert_eq!(embedding(&[3.0, 4.0], true), vec![0.6, 0.8]);
+        assert_eq!(embedding(&[0.0, 0.0], true), vec![0.0, 0.0]);
+        
+        let mut engine = AIEngine::new(config).await.unwrap();
+        engine.start().await.unwrap();
+        for model_name in ["scene", "reid"] {
+            let request = InferenceRequest::builder(model_name).tensor(vec![0.0; 4], vec![1, 4]).build().unwrap();
+            let mut receiver = engine.submit_inference(request).await.unwrap();
+            match receiver.recv().await.unwrap().result {
+                InferenceResult::Classification(classes) => {
+                    assert_eq!(classes.len(), 2);
+                    assert!(names.contains(&classes[0].class_name));
+                    assert!(classes[0].confidence >= classes[1].confidence);
+                },
+                InferenceResult::Embedding(vector) => {
+                    assert_eq!(vector.len(), SIMULATED_EMBEDDING_DIM);
+                    assert!((vector.iter().map(|v| v * v).sum::<f32>() - 1.0).abs() < 1e-4);
+                },
+                other => panic!("{} 的结果不符: {:?}", model_name, other),
+            }
+        }
+        engine.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_segmentation_result_overlay_and_coverage_event() {
+        let task = ModelTask::Segmentation { min_fraction: 0.3, overlay: true, overlay_alpha: 0.5 };
+        let mut model_config = ModelConfig {
+            model_path: "segmentation.onnx".to_string(),
+            input_shape: vec![1, 3, 4, 8],
+            output_names: vec!["logits".to_string()],
+            confidence_threshold: 0.5,
+            nms_threshold: 0.5,
+            class_names: vec!["background".to_string(), "table".to_string(), "cup".to_string()],
+            preprocessing: Some(PreprocessingConfig { target_size: (8, 4), ..PreprocessingConfig::default() }),
+            task: ModelTask::Segmentation { min_fraction: 0.3, overlay: true, overlay_alpha: 1.5 },
+        };
+        assert!(model_config.validate().is_err());
+        model_config.task = task;
+        
+        let mut config = AIConfig { device: DeviceType::Mock, ..AIConfig::default() };
+        config.model_configs.insert("tabletop".to_string(), model_config);
+        let bus = EventBus::new(16);
+        let mut events = bus.subscribe();
+        let mut engine = AIEngine::new(config).await.unwrap();
+        engine.set_event_bus(bus);
+        engine.start().await.unwrap();
+        
+        let image = ImageData::from_raw(8, 4, 3, vec![100; 8 * 4 * 3], ImageFormat::RGB8);
+        let request = InferenceRequest::builder("tabletop").image(image).build().unwrap();
+        let mut receiver = engine.submit_inference(request).await.unwrap();
+        let InferenceResult::Segmentation(result) = receiver.recv().await.unwrap().result else {
+            panic!("应返回分割结果");
+        };
+        
+        // 模拟输出把画面分成3条竖条：3、3、2列，占比低于0.3的cup不列出
+        assert_eq!((result.width, result.height, result.mask.len()), (8, 4, 32));
+        assert_eq!(result.classes, vec![0, 1, 2]);
+        assert_eq!(result.class_fractions.iter().map(|c| c.class_name.as_str()).collect::<Vec<_>>(), vec!["background", "table"]);
+        let overlay = result.overlay.unwrap();
+        assert_eq!((overlay.width, overlay.height, overlay.format), (8, 4, ImageFormat::RGB8));
+        assert_eq!(&overlay.data[..3], &[100, 100, 100]);
+        assert_eq!(&overlay.data[3 * 3..3 * 3 + 3], &[114, 50, 50]);
+        
+        let envelope = events.recv().await.unwrap();
+        match envelope.event {
+            RobotEvent::SegmentationCoverage(coverage) => {
+                assert_eq!(coverage.model_name, "tabletop");
+                assert_eq!(coverage.fraction("table"), 0.375);
+            },
+            other => panic!("事件类型不符: {:?}", other),
+        }
+        engine.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_sound_event_model_tags_audio_windows() {
+        let sound = SoundEventConfig { window_ms: 500, ..SoundEventConfig::default() };
+        let mut model_config = ModelConfig {
+            model_path: "sound_events.onnx".to_string(),
+            input_shape: vec![1, 16000],
+            output_names: vec!["scores".to_string()],
+            confidence_threshold: 0.3,
+            nms_threshold: 0.5,
+            class_names: vec!["clap".to_string(), "doorbell".to_string(), "name_call".to_string()],
+            preprocessing: None,
+            task: ModelTask::SoundEvent(sound.clone()),
+        };
+        // 输入长度必须等于窗口样本数
+        assert!(model_config.validate().is_err());
+        model_config.input_shape = vec![1, 8000];
+        assert!(model_config.validate().is_ok());
+        
+        let task: ModelTask = serde_json::from_value(serde_json::json!({"type": "sound_event", "window_ms": 500})).unwrap();
+        assert_eq!(task, model_config.task);
+        
+        let mut config = AIConfig { device: DeviceType::Mock, ..AIConfig::default() };
+        config.model_configs.insert("sound_events".to_string(), model_config);
+        let bus = EventBus::new(16);
+        let mut events = bus.subscribe();
+        let mut engine = AIEngine::new(config).await.unwrap();
+        engine.set_event_bus(bus);
+        engine.start().await.unwrap();
+        
+        let mut windower = sound_events::SoundWindower::new(16000, &sound).unwrap();
+        assert!(windower.push(&[0.0; 8000]).is_empty());
+        let windows = windower.push(&[0.2; 8000]);
+        assert_eq!(windows.len(), 1);
+        let request = InferenceRequest::builder("sound_events").audio(windows[0].clone()).build().unwrap();
+        let mut receiver = engine.submit_inference(request).await.unwrap();
+        let InferenceResult::Classification(classes) = receiver.recv().await.unwrap().result else {
+            panic!("应返回分类结果");
+        };
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0].class_name, "clap");
+        
+        let envelope = events.recv().await.unwrap();
+        assert_eq!(envelope.event.kind(), "SoundEventDetected");
+        assert!((envelope.event.confidence().unwrap() - 0.9).abs() < 1e-6);
+        
+        // 其他模型不接受音频输入
+        let request = InferenceRequest::builder("face_detection").audio(vec![0.2; 100]).build().unwrap();
+        let mut receiver = engine.submit_inference(request).await.unwrap();
+        assert!(matches!(receiver.recv().await.unwrap().result, InferenceResult::Error(_)));
+        engine.stop().await.unwrap();
+    }
+    
     #[tokio::test]
     async fn test_ai_engine_creation() {
         let config = AIConfig::default();
@@ -1187,6 +2407,153 @@ mod tests {
         assert!(engine.is_ok());
     }
     
+    #[tokio::test]
+    async fn test_mock_backend_cycles_scripted_results_without_model_files() {
+        let face = |confidence: f32| InferenceResult::FaceDetection(vec![FaceDetection {
+            confidence,
+            bbox: BoundingBox { x: 10.0, y: 20.0, width: 30.0, height: 40.0 },
+            landmarks: None,
+        }]);
+        let mut config = AIConfig {
+            model_path: "不存在的模型目录/".to_string(),
+            device: DeviceType::Mock,
+            ..AIConfig::default()
+        };
+        config.mock.responses.insert("face_detection".to_string(), vec![face(0.9), face(0.6)]);
+        
+        let mut engine = AIEngine::new(config).await.unwrap();
+        engine.start().await.unwrap();
+        assert_eq!(engine.get_loaded_models().await.len(), 3);
+        
+        let mut confidences = Vec::new();
+        for i in 0..3 {
+            let request = InferenceRequest {
+                model_name: "face_detection".to_string(),
+                input_data: InputData::Tensor(TensorData { data: vec![0.0], shape: vec![1], dtype: DataType::Float32 }),
+                request_id: format!("mock-{}", i),
+                timestamp: 0,
+                options: InferenceOptions::default(),
+            };
+            let mut receiver = engine.submit_inference(request).await.unwrap();
+            match receiver.recv().await.unwrap().result {
+                InferenceResult::FaceDetection(faces) => confidences.push(faces[0].confidence),
+                other => panic!("意外的结果: {:?}", other),
+            }
+        }
+        assert_eq!(confidences, vec![0.9, 0.6, 0.9]);
+        
+        // 没有脚本的模型走内置模拟输出
+        let request = InferenceRequest {
+            model_name: "pose_estimation".to_string(),
+            input_data: InputData::Tensor(TensorData { data: vec![0.0], shape: vec![1], dtype: DataType::Float32 }),
+            request_id: "mock-pose".to_string(),
+            timestamp: 0,
+            options: InferenceOptions::default(),
+        };
+        let mut receiver = engine.submit_inference(request).await.unwrap();
+        assert!(matches!(receiver.recv().await.unwrap().result, InferenceResult::PoseEstimation(_)));
+        
+        engine.stop().await.unwrap();
+    }
+    
+    #[test]
+    fn test_inference_request_builder_checks_input_shapes() {
+        let request = InferenceRequest::builder("object_detection")
+            .tensor(vec![0.0; 6], vec![1, 2, 3])
+            .timeout(Duration::from_millis(500))
+            .build()
+            .unwrap();
+        assert!(request.request_id.is_empty());
+        assert_eq!(request.options.timeout_ms, Some(500));
+        assert!(request.options.use_cache);
+        assert!(request.timestamp > 0);
+        
+        let error = InferenceRequest::builder("object_detection").tensor(vec![0.0; 5], vec![1, 2, 3]).build().unwrap_err();
+        assert!(error.to_string().contains("[1, 2, 3]"));
+        assert!(InferenceRequest::builder("object_detection").tensor(vec![], vec![0]).build().is_err());
+        assert!(InferenceRequest::builder("object_detection").build().is_err());
+        assert!(InferenceRequest::builder("").text("hello").build().is_err());
+        assert!(InferenceRequest::builder("face_detection").text("hello").confidence_threshold(1.5).build().is_err());
+        
+        let image = ImageData::from_raw(2, 2, 3, vec![0; 12], ImageFormat::RGB8);
+        assert!(InferenceRequest::builder("face_detection").image(image.clone()).build().is_ok());
+        let truncated = ImageData { data: vec![0; 11], ..image };
+        assert!(InferenceRequest::builder("face_detection").image(truncated).build().is_err());
+        assert!(InferenceRequest::builder("face_detection").input(InputData::Batch(vec![])).build().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_cancel_queued_and_running_requests() {
+        let mut config = AIConfig {
+            model_path: "不存在的模型目录/".to_string(),
+            device: DeviceType::Mock,
+            ..AIConfig::default()
+        };
+        config.mock.latency_ms = 200;
+        let mut engine = AIEngine::new(config).await.unwrap();
+        engine.start().await.unwrap();
+        
+        let request = |id: &str| InferenceRequest::builder("object_detection")
+            .tensor(vec![0.5; 4], vec![4])
+            .request_id(id)
+            .build()
+            .unwrap();
+        let mut running = engine.submit_inference(request("running")).await.unwrap();
+        let mut queued = engine.submit_inference(request("queued")).await.unwrap();
+        assert!(engine.submit_inference(request("queued")).await.is_err());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        
+        // 排队和执行中的请求都立即收到Cancelled响应，只收到一次
+        assert!(engine.cancel("queued").await);
+        assert!(matches!(queued.recv().await.unwrap().result, InferenceResult::Cancelled));
+        assert!(engine.cancel("running").await);
+        assert!(matches!(running.recv().await.unwrap().result, InferenceResult::Cancelled));
+        assert!(!engine.cancel("running").await);
+        assert!(!engine.cancel("unknown").await);
+        
+        // 被取消的请求不影响之后的请求，执行中的那个在推理阶段结束后停止
+        let mut next = engine.submit_inference(request("next")).await.unwrap();
+        assert!(matches!(next.recv().await.unwrap().result, InferenceResult::ObjectDetection(_)));
+        assert!(running.recv().await.is_none());
+        assert!(!engine.cancel("next").await);
+        
+        let stats = engine.get_status().await.unwrap().inference_stats;
+        assert_eq!((stats.total_inferences, stats.cancelled_inferences), (1, 2));
+        
+        engine.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_inference_reuses_pooled_tensor_buffers() {
+        let config = AIConfig {
+            model_path: "不存在的模型目录/".to_string(),
+            device: DeviceType::Mock,
+            ..AIConfig::default()
+        };
+        let mut engine = AIEngine::new(config).await.unwrap()
+            .with_tensor_pool(Arc::new(TensorPool::with_capacity_mb(64)));
+        engine.start().await.unwrap();
+        
+        for i in 0..2 {
+            let request = InferenceRequest {
+                model_name: "object_detection".to_string(),
+                input_data: InputData::Tensor(TensorData { data: vec![0.5; 2048], shape: vec![2048], dtype: DataType::Float32 }),
+                request_id: format!("pool-{}", i),
+                timestamp: 0,
+                options: InferenceOptions::default(),
+            };
+            let mut receiver = engine.submit_inference(request).await.unwrap();
+            assert!(matches!(receiver.recv().await.unwrap().result, InferenceResult::ObjectDetection(_)));
+        }
+        
+        // 第一次请求分配输入和输出缓冲区，第二次全部复用
+        let stats = engine.get_status().await.unwrap().tensor_pool;
+        assert_eq!((stats.hits, stats.misses), (2, 2));
+        assert_eq!(stats.pooled_buffers, 2);
+        
+        engine.stop().await.unwrap();
+    }
+    
     #[tokio::test]
     async fn test_tensor_data_creation() {
         let tensor = TensorData {
@@ -0,0 +1,25 @@
+//! AI推理模块（未启用ai特性时的替身）
+//! 
+//! 只保留配置结构，使包含`ai`段的完整配置文件在最小构建中仍能加载。
+//! 未知字段在反序列化时被忽略，启用AI时仅输出警告。
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use log::warn;
+
+/// AI配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AIConfig {
+    pub enabled: bool,
+}
+
+impl ConfigValidation for AIConfig {
+    fn validate(&self) -> Result<()> {
+        if self.enabled {
+            warn!("配置启用了AI推理，但当前构建未包含ai特性，AI功能不可用");
+        }
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+//! 天线动画模块
+//! 
+//! 为左右天线通道提供预设动画（摆动、竖起、耷拉等），
+//! 输出的目标位置会被限制在各天线自身的关节限位内。
+
+use crate::common::*;
+use crate::joints::{JointGroup, JointSetConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+/// 天线预设动画
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum AntennaAnimation {
+    /// 同向快速摆动
+    Wiggle,
+    /// 两侧交替摆动
+    Alternate,
+    /// 竖起（好奇、开心）
+    Perk,
+    /// 耷拉（沮丧、困倦）
+    Droop,
+    /// 单次快速抖动
+    Twitch,
+}
+
+impl AntennaAnimation {
+    /// 动画时长（秒）
+    pub fn duration(&self) -> f64 {
+        match self {
+            AntennaAnimation::Wiggle => 1.2,
+            AntennaAnimation::Alternate => 1.6,
+            AntennaAnimation::Perk => 0.6,
+            AntennaAnimation::Droop => 1.0,
+            AntennaAnimation::Twitch => 0.3,
+        }
+    }
+    
+    /// 归一化位置（-1到1，相对于天线行程），返回(左, 右)
+    fn normalized(&self, progress: f64) -> (f64, f64) {
+        match self {
+            AntennaAnimation::Wiggle => {
+                let v = 0.5 * (progress * 4.0 * PI).sin() * (1.0 - progress);
+                (v, v)
+            }
+            AntennaAnimation::Alternate => {
+                let v = 0.5 * (progress * 2.0 * PI).sin();
+                (v, -v)
+            }
+            AntennaAnimation::Perk => {
+                let v = 0.8 * smooth_step(0.0, 1.0, progress);
+                (v, v)
+            }
+            AntennaAnimation::Droop => {
+                let v = -0.8 * smooth_step(0.0, 1.0, progress);
+                (v, v)
+            }
+            AntennaAnimation::Twitch => {
+                let v = 0.3 * (progress * PI).sin();
+                (v, 0.0)
+            }
+        }
+    }
+}
+
+/// 天线动画播放器
+#[derive(Debug, Clone)]
+pub struct AntennaAnimator {
+    animation: AntennaAnimation,
+    /// (关节名称, 最小位置, 最大位置)
+    left: (String, f64, f64),
+    right: (String, f64, f64),
+}
+
+impl AntennaAnimator {
+    /// 根据关节集合中的天线通道创建播放器，缺少左右天线时返回None
+    pub fn new(animation: AntennaAnimation, joints: &JointSetConfig) -> Option<Self> {
+        let mut antennas = joints.group(JointGroup::Antenna)
+            .map(|j| (j.name.clone(), j.min_position.radians(), j.max_position.radians()));
+        let left = antennas.next()?;
+        let right = antennas.next()?;
+        
+        Some(Self { animation, left, right })
+    }
+    
+    pub fn animation(&self) -> AntennaAnimation {
+        self.animation
+    }
+    
+    /// 采样动画，返回各天线的目标位置；动画结束后返回None
+    pub fn sample(&self, elapsed_s: f64) -> Option<HashMap<String, f64>> {
+        let duration = self.animation.duration();
+        if elapsed_s > duration {
+            return None;
+        }
+        
+        let progress = clamp(elapsed_s / duration, 0.0, 1.0);
+        let (left, right) = self.animation.normalized(progress);
+        
+        let mut targets = HashMap::new();
+        for ((name, min, max), value) in [(&self.left, left), (&self.right, right)] {
+            let center = (min + max) * 0.5;
+            let half_range = (max - min) * 0.5;
+            targets.insert(name.clone(), clamp(center + value * half_range, *min, *max));
+        }
+        
+        Some(targets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_antenna_animation_respects_limits() {
+        let joints = JointSetConfig::default();
+        for animation in [
+            AntennaAnimation::Wiggle,
+            AntennaAnimation::Alternate,
+            AntennaAnimation::Perk,
+            AntennaAnimation::Droop,
+            AntennaAnimation::Twitch,
+        ] {
+            let animator = AntennaAnimator::new(animation, &joints).unwrap();
+            let mut t = 0.0;
+            while let Some(targets) = animator.sample(t) {
+                for (name, position) in &targets {
+                    let joint = joints.get(name).unwrap();
+                    assert_eq!(joint.group, JointGroup::Antenna);
+                    assert!(*position >= joint.min_position.radians() && *position <= joint.max_position.radians());
+                }
+                t += 0.05;
+            }
+            assert!(t >= animation.duration());
+        }
+    }
+    
+    #[test]
+    fn test_antenna_animator_requires_antennas() {
+        let mut joints = JointSetConfig::default();
+        joints.joints.retain(|j| j.group != JointGroup::Antenna);
+        assert!(AntennaAnimator::new(AntennaAnimation::Perk, &joints).is_none());
+    }
+}
@@ -0,0 +1,376 @@
+//! 命令仲裁模块
+//! 
+//! WebSocket、Python、遥操作和行为规则都可能同时发送运动命令。
+//! 仲裁器按来源优先级决定接受哪条命令，并提供独占控制租约：
+//! 持有租约的客户端在租约有效期内独占控制，超时后自动释放。
+//! 
+//! 每个发送方带有角色：查看者只能读取遥测数据，操作员可以在当前安全档位内
+//! 控制关节，管理员还可以修改配置和安全档位。权限在仲裁之前检查。
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use log::{info, warn, debug};
+
+/// 命令来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CommandSource {
+    Teleop,
+    WebSocket,
+    Python,
+    Behavior,
+    Idle,
+}
+
+impl CommandSource {
+    pub const ALL: [CommandSource; 5] = [
+        CommandSource::Teleop,
+        CommandSource::WebSocket,
+        CommandSource::Python,
+        CommandSource::Behavior,
+        CommandSource::Idle,
+    ];
+}
+
+/// 发送方角色，与后端用户角色一致
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// 只能读取遥测数据，以及发送急停
+    Viewer,
+    /// 可以在当前安全档位内控制关节
+    #[default]
+    Operator,
+    /// 还可以修改配置和安全档位
+    Admin,
+}
+
+/// 需要权限的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Telemetry,
+    Command,
+    Configure,
+}
+
+impl Role {
+    /// 该角色是否拥有权限
+    pub fn allows(self, permission: Permission) -> bool {
+        match permission {
+            Permission::Telemetry => true,
+            Permission::Command => self >= Role::Operator,
+            Permission::Configure => self == Role::Admin,
+        }
+    }
+}
+
+/// 命令发送方（来源 + 客户端标识 + 角色）
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CommandOrigin {
+    pub source: CommandSource,
+    pub client_id: String,
+    /// 未声明角色的内部来源按操作员处理
+    #[serde(default)]
+    pub role: Role,
+}
+
+impl CommandOrigin {
+    pub fn new(source: CommandSource, client_id: &str) -> Self {
+        Self {
+            source,
+            client_id: client_id.to_string(),
+            role: Role::default(),
+        }
+    }
+    
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+    
+    /// 检查发送方的角色是否拥有权限
+    pub fn authorize(&self, permission: Permission) -> Result<()> {
+        if self.role.allows(permission) {
+            return Ok(());
+        }
+        
+        Err(anyhow::anyhow!(
+            "{:?}:{} 的角色 {:?} 没有{}权限",
+            self.source, self.client_id, self.role,
+            match permission {
+                Permission::Telemetry => "读取遥测",
+                Permission::Command => "控制",
+                Permission::Configure => "修改配置",
+            }
+        ))
+    }
+}
+
+/// 仲裁配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbiterConfig {
+    /// 来源优先级，数值越大优先级越高
+    pub priorities: HashMap<CommandSource, u8>,
+    /// 高优先级来源发送命令后，低优先级来源被压制的时间（毫秒）
+    pub hold_time_ms: u64,
+    /// 未指定时长时的默认租约时长（毫秒）
+    pub default_lease_ms: u64,
+    /// 租约最长时长（毫秒）
+    pub max_lease_ms: u64,
+}
+
+impl Default for ArbiterConfig {
+    fn default() -> Self {
+        let priorities = HashMap::from([
+            (CommandSource::Teleop, 100),
+            (CommandSource::WebSocket, 80),
+            (CommandSource::Python, 60),
+            (CommandSource::Behavior, 40),
+            (CommandSource::Idle, 0),
+        ]);
+        
+        Self {
+            priorities,
+            hold_time_ms: 500,
+            default_lease_ms: 10000,
+            max_lease_ms: 300000,
+        }
+    }
+}
+
+impl ConfigValidation for ArbiterConfig {
+    fn validate(&self) -> Result<()> {
+        if let Some(source) = CommandSource::ALL.iter().find(|s| !self.priorities.contains_key(s)) {
+            return Err(anyhow::anyhow!("缺少命令来源 {:?} 的优先级", source));
+        }
+        
+        if self.default_lease_ms == 0 || self.max_lease_ms == 0 {
+            return Err(anyhow::anyhow!("租约时长必须大于0"));
+        }
+        
+        if self.default_lease_ms > self.max_lease_ms {
+            return Err(anyhow::anyhow!("默认租约时长不能超过最长租约时长"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 独占控制租约
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlLease {
+    pub lease_id: u64,
+    pub owner: CommandOrigin,
+    pub acquired_at: u64,
+    pub expires_at: u64,
+}
+
+/// 仲裁结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arbitration {
+    Accepted,
+    Rejected(String),
+}
+
+/// 命令仲裁器
+#[derive(Debug)]
+pub struct CommandArbiter {
+    config: ArbiterConfig,
+    lease: Option<ControlLease>,
+    next_lease_id: u64,
+    /// 各来源最近一次被接受的命令时间
+    last_accepted: HashMap<CommandSource, u64>,
+}
+
+impl CommandArbiter {
+    pub fn new(config: ArbiterConfig) -> Result<Self> {
+        config.validate()?;
+        
+        Ok(Self {
+            config,
+            lease: None,
+            next_lease_id: 1,
+            last_accepted: HashMap::new(),
+        })
+    }
+    
+    fn priority(&self, source: CommandSource) -> u8 {
+        self.config.priorities.get(&source).copied().unwrap_or(0)
+    }
+    
+    /// 当前有效租约（过期的租约会被清除）
+    fn active_lease(&mut self, now: u64) -> Option<&ControlLease> {
+        if self.lease.as_ref().is_some_and(|lease| now >= lease.expires_at) {
+            let expired = self.lease.take().unwrap();
+            info!("控制租约 {} 已超时释放（{}）", expired.lease_id, expired.owner.client_id);
+        }
+        self.lease.as_ref()
+    }
+    
+    /// 当前控制权持有者
+    pub fn owner(&mut self) -> Option<ControlLease> {
+        self.active_lease(current_timestamp()).cloned()
+    }
+    
+    /// 申请独占控制，同一客户端重复申请视为续约，更高优先级来源可以抢占
+    pub fn acquire(&mut self, origin: &CommandOrigin, duration_ms: Option<u64>) -> Result<ControlLease> {
+        self.acquire_at(origin, duration_ms, current_timestamp())
+    }
+    
+    fn acquire_at(&mut self, origin: &CommandOrigin, duration_ms: Option<u64>, now: u64) -> Result<ControlLease> {
+        origin.authorize(Permission::Command)?;
+        
+        let duration = duration_ms.unwrap_or(self.config.default_lease_ms).min(self.config.max_lease_ms);
+        let requester_priority = self.priority(origin.source);
+        
+        if let Some(current) = self.active_lease(now).cloned() {
+            if current.owner == *origin {
+                let lease = self.lease.as_mut().unwrap();
+                lease.expires_at = now + duration;
+                debug!("控制租约 {} 已续约", lease.lease_id);
+                return Ok(lease.clone());
+            }
+            
+            if requester_priority <= self.priority(current.owner.source) {
+                return Err(anyhow::anyhow!(
+                    "控制权已被 {:?}:{} 占用", current.owner.source, current.owner.client_id
+                ));
+            }
+            
+            warn!("{:?}:{} 抢占了 {:?}:{} 的控制权",
+                  origin.source, origin.client_id, current.owner.source, current.owner.client_id);
+        }
+        
+        let lease = ControlLease {
+            lease_id: self.next_lease_id,
+            owner: origin.clone(),
+            acquired_at: now,
+            expires_at: now + duration,
+        };
+        self.next_lease_id += 1;
+        self.lease = Some(lease.clone());
+        
+        info!("{:?}:{} 获得控制权（租约 {}）", origin.source, origin.client_id, lease.lease_id);
+        Ok(lease)
+    }
+    
+    /// 释放控制权，只有持有者可以释放
+    pub fn release(&mut self, origin: &CommandOrigin) -> Result<()> {
+        match self.active_lease(current_timestamp()) {
+            Some(lease) if lease.owner == *origin => {
+                info!("{:?}:{} 释放了控制权", origin.source, origin.client_id);
+                self.lease = None;
+                Ok(())
+            }
+            Some(_) => Err(anyhow::anyhow!("只有控制权持有者可以释放控制权")),
+            None => Ok(()),
+        }
+    }
+    
+    /// 对一条命令进行仲裁
+    pub fn arbitrate(&mut self, origin: &CommandOrigin, emergency: bool) -> Arbitration {
+        self.arbitrate_at(origin, emergency, current_timestamp())
+    }
+    
+    fn arbitrate_at(&mut self, origin: &CommandOrigin, emergency: bool, now: u64) -> Arbitration {
+        // 急停命令任何已认证的来源都可以发送，包括查看者
+        if emergency {
+            return Arbitration::Accepted;
+        }
+        
+        if let Err(e) = origin.authorize(Permission::Command) {
+            return Arbitration::Rejected(e.to_string());
+        }
+        
+        if let Some(lease) = self.active_lease(now) {
+            if lease.owner != *origin {
+                return Arbitration::Rejected(format!(
+                    "控制权由 {:?}:{} 独占", lease.owner.source, lease.owner.client_id
+                ));
+            }
+        } else {
+            let priority = self.priority(origin.source);
+            let blocking = self.last_accepted.iter().find(|(source, &at)| {
+                self.priority(**source) > priority && now.saturating_sub(at) < self.config.hold_time_ms
+            });
+            
+            if let Some((source, _)) = blocking {
+                return Arbitration::Rejected(format!("更高优先级的来源 {:?} 正在控制", source));
+            }
+        }
+        
+        self.last_accepted.insert(origin.source, now);
+        Arbitration::Accepted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_priority_hold() {
+        let mut arbiter = CommandArbiter::new(ArbiterConfig::default()).unwrap();
+        let teleop = CommandOrigin::new(CommandSource::Teleop, "joystick");
+        let behavior = CommandOrigin::new(CommandSource::Behavior, "rules");
+        
+        assert_eq!(arbiter.arbitrate_at(&behavior, false, 1000), Arbitration::Accepted);
+        assert_eq!(arbiter.arbitrate_at(&teleop, false, 1100), Arbitration::Accepted);
+        
+        // 遥操作刚发过命令，行为命令被压制
+        assert!(matches!(arbiter.arbitrate_at(&behavior, false, 1200), Arbitration::Rejected(_)));
+        assert_eq!(arbiter.arbitrate_at(&behavior, false, 1700), Arbitration::Accepted);
+        
+        // 急停不受仲裁限制
+        assert_eq!(arbiter.arbitrate_at(&behavior, true, 1150), Arbitration::Accepted);
+    }
+    
+    #[test]
+    fn test_exclusive_lease() {
+        let mut arbiter = CommandArbiter::new(ArbiterConfig::default()).unwrap();
+        let python = CommandOrigin::new(CommandSource::Python, "script");
+        let other = CommandOrigin::new(CommandSource::Python, "notebook");
+        let teleop = CommandOrigin::new(CommandSource::Teleop, "joystick");
+        
+        let lease = arbiter.acquire_at(&python, Some(5000), 1000).unwrap();
+        assert_eq!(lease.expires_at, 6000);
+        
+        // 同优先级的其他客户端不能抢占，命令也被拒绝
+        assert!(arbiter.acquire_at(&other, None, 2000).is_err());
+        assert!(matches!(arbiter.arbitrate_at(&other, false, 2000), Arbitration::Rejected(_)));
+        assert_eq!(arbiter.arbitrate_at(&python, false, 2000), Arbitration::Accepted);
+        
+        // 租约超时后自动释放
+        assert_eq!(arbiter.arbitrate_at(&other, false, 7000), Arbitration::Accepted);
+        
+        // 更高优先级来源可以抢占
+        arbiter.acquire_at(&python, None, 8000).unwrap();
+        let preempted = arbiter.acquire_at(&teleop, None, 8100).unwrap();
+        assert_eq!(preempted.owner, teleop);
+        assert!(matches!(arbiter.arbitrate_at(&python, false, 8200), Arbitration::Rejected(_)));
+    }
+    
+    #[test]
+    fn test_role_permissions() {
+        let mut arbiter = CommandArbiter::new(ArbiterConfig::default()).unwrap();
+        let viewer = CommandOrigin::new(CommandSource::WebSocket, "guest").with_role(Role::Viewer);
+        let admin = CommandOrigin::new(CommandSource::WebSocket, "root").with_role(Role::Admin);
+        
+        // 查看者的普通命令被拒绝，也不能申请控制权
+        assert!(matches!(arbiter.arbitrate_at(&viewer, false, 1000), Arbitration::Rejected(_)));
+        assert!(arbiter.acquire_at(&viewer, None, 1000).is_err());
+        
+        // 急停任何人都可以发送，即使控制权被别人独占
+        arbiter.acquire_at(&admin, None, 1000).unwrap();
+        assert_eq!(arbiter.arbitrate_at(&viewer, true, 1100), Arbitration::Accepted);
+        
+        assert!(CommandOrigin::new(CommandSource::Python, "script").authorize(Permission::Configure).is_err());
+        assert!(admin.authorize(Permission::Configure).is_ok());
+        assert_eq!(arbiter.arbitrate_at(&admin, false, 1000), Arbitration::Accepted);
+        
+        // 旧格式的发送方没有角色字段，按操作员处理
+        let origin: CommandOrigin = serde_json::from_str(r#"{"source": "Python", "client_id": "script"}"#).unwrap();
+        assert_eq!(origin.role, Role::Operator);
+    }
+}
@@ -0,0 +1,257 @@
+//! 聆听时的扬声器避让
+//! 
+//! 唤醒词触发或语音识别正在聆听时，降低（或暂停）TTS等播放音量，结束后再平滑恢复，
+//! 避免机器人自己的声音盖过用户说话。唤醒词和语音识别一侧调用`wake_word_detected`、
+//! `start_listening`、`stop_listening`，播放一侧对每块输出样本调用`process`；
+//! 状态变化以`audio.ducking`事件发布到事件总线，供界面显示“正在聆听”。
+
+use crate::common::*;
+use crate::events::{EventBus, RobotEvent};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use log::debug;
+
+/// 避让方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuckingMode {
+    /// 降低音量继续播放
+    #[default]
+    Duck,
+    /// 淡出后暂停，恢复时从暂停处继续
+    Pause,
+}
+
+/// 避让配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuckingConfig {
+    pub enabled: bool,
+    pub mode: DuckingMode,
+    /// 避让时的播放增益（0-1）
+    pub duck_gain: f32,
+    /// 淡出时间（毫秒）
+    pub attack_ms: u32,
+    /// 恢复时间（毫秒）
+    pub release_ms: u32,
+    /// 唤醒词触发后保持避让的时间（毫秒），期间开始语音识别则一直保持到识别结束
+    pub wake_word_hold_ms: u64,
+}
+
+impl Default for DuckingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mode: DuckingMode::Duck,
+            duck_gain: 0.15,
+            attack_ms: 50,
+            release_ms: 400,
+            wake_word_hold_ms: 3000,
+        }
+    }
+}
+
+impl ConfigValidation for DuckingConfig {
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.duck_gain) {
+            return Err(anyhow::anyhow!("避让增益必须在0-1之间"));
+        }
+        
+        if self.attack_ms == 0 || self.release_ms == 0 {
+            return Err(anyhow::anyhow!("淡出和恢复时间必须大于0"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 当前的避让状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuckingState {
+    Normal,
+    Ducked,
+    Paused,
+}
+
+#[derive(Debug)]
+struct DuckerState {
+    wake_word_until: Option<Instant>,
+    listening: bool,
+    state: DuckingState,
+    gain: f32,
+}
+
+/// 扬声器避让控制器，克隆后在采集线程和播放线程之间共享
+#[derive(Debug, Clone)]
+pub struct AudioDucker {
+    config: DuckingConfig,
+    sample_rate: u32,
+    state: Arc<Mutex<DuckerState>>,
+    bus: Option<EventBus>,
+}
+
+impl AudioDucker {
+    pub fn new(config: DuckingConfig, sample_rate: u32, bus: Option<EventBus>) -> Result<Self> {
+        config.validate()?;
+        if sample_rate == 0 {
+            return Err(anyhow::anyhow!("采样率必须大于0"));
+        }
+        
+        Ok(Self {
+            config,
+            sample_rate,
+            state: Arc::new(Mutex::new(DuckerState {
+                wake_word_until: None,
+                listening: false,
+                state: DuckingState::Normal,
+                gain: 1.0,
+            })),
+            bus,
+        })
+    }
+    
+    /// 唤醒词触发
+    pub fn wake_word_detected(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.wake_word_until = Some(Instant::now() + Duration::from_millis(self.config.wake_word_hold_ms));
+        self.refresh(&mut state);
+    }
+    
+    /// 语音识别开始聆听
+    pub fn start_listening(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.listening = true;
+        self.refresh(&mut state);
+    }
+    
+    /// 语音识别结束，同时结束唤醒词的保持
+    pub fn stop_listening(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.listening = false;
+        state.wake_word_until = None;
+        self.refresh(&mut state);
+    }
+    
+    /// 当前状态；唤醒词保持到期后在这里恢复，没有播放时采集循环应定期调用
+    pub fn state(&self) -> DuckingState {
+        let mut state = self.state.lock().unwrap();
+        self.refresh(&mut state);
+        state.state
+    }
+    
+    /// 对一块播放样本应用避让增益，返回播放器是否应继续推进播放位置
+    ///
+    /// 暂停模式下淡出完成后返回false，样本已被清零，播放器应保持当前位置输出静音。
+    pub fn process(&self, samples: &mut [f32]) -> bool {
+        let mut state = self.state.lock().unwrap();
+        self.refresh(&mut state);
+        
+        let target = match state.state {
+            DuckingState::Normal => 1.0,
+            DuckingState::Ducked => self.config.duck_gain,
+            DuckingState::Paused => 0.0,
+        };
+        let step = |ms: u32| 1.0 / (self.sample_rate as f32 * ms as f32 / 1000.0).max(1.0);
+        let (attack, release) = (step(self.config.attack_ms), step(self.config.release_ms));
+        
+        for sample in samples.iter_mut() {
+            state.gain = if state.gain > target {
+                (state.gain - attack).max(target)
+            } else {
+                (state.gain + release).min(target)
+            };
+            *sample *= state.gain;
+        }
+        
+        !(state.state == DuckingState::Paused && state.gain == 0.0)
+    }
+    
+    fn refresh(&self, state: &mut DuckerState) {
+        if state.wake_word_until.is_some_and(|until| Instant::now() >= until) {
+            state.wake_word_until = None;
+        }
+        
+        let wake_word = state.wake_word_until.is_some();
+        let next = if !self.config.enabled || !(wake_word || state.listening) {
+            DuckingState::Normal
+        } else if self.config.mode == DuckingMode::Pause {
+            DuckingState::Paused
+        } else {
+            DuckingState::Ducked
+        };
+        if next == state.state {
+            return;
+        }
+        
+        debug!("扬声器避让 {:?} -> {:?}", state.state, next);
+        state.state = next;
+        if let Some(bus) = &self.bus {
+            bus.publish("audio", RobotEvent::Custom {
+                name: "audio.ducking".to_string(),
+                data: serde_json::json!({
+                    "state": next,
+                    "wake_word": wake_word,
+                    "listening": state.listening,
+                }),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_duck_and_restore_with_events() {
+        let bus = EventBus::new(16);
+        let mut events = bus.subscribe();
+        let ducker = AudioDucker::new(DuckingConfig::default(), 16000, Some(bus)).unwrap();
+        
+        ducker.wake_word_detected();
+        ducker.start_listening();
+        assert_eq!(ducker.state(), DuckingState::Ducked);
+        
+        // 50ms淡出后保持在避让增益
+        let mut block = vec![1.0f32; 1600];
+        assert!(ducker.process(&mut block));
+        assert!((block[1599] - 0.15).abs() < 1e-6);
+        
+        ducker.stop_listening();
+        assert_eq!(ducker.state(), DuckingState::Normal);
+        let mut block = vec![1.0f32; 8000];
+        ducker.process(&mut block);
+        assert!(block[100] < 0.5);
+        assert_eq!(block[7999], 1.0);
+        
+        let states: Vec<_> = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|envelope| match envelope.event {
+                RobotEvent::Custom { name, data } if name == "audio.ducking" => data["state"].clone(),
+                other => panic!("意外的事件 {:?}", other),
+            })
+            .collect();
+        assert_eq!(states, vec!["ducked", "normal"]);
+    }
+    
+    #[test]
+    fn test_pause_mode_holds_playback_until_hold_expires() {
+        let config = DuckingConfig {
+            mode: DuckingMode::Pause,
+            wake_word_hold_ms: 20,
+            ..DuckingConfig::default()
+        };
+        let ducker = AudioDucker::new(config, 16000, None).unwrap();
+        
+        ducker.wake_word_detected();
+        let mut block = vec![1.0f32; 1600];
+        assert!(!ducker.process(&mut block));
+        assert!(block[1599] == 0.0);
+        
+        std::thread::sleep(Duration::from_millis(30));
+        let mut block = vec![1.0f32; 160];
+        assert!(ducker.process(&mut block));
+        assert_eq!(ducker.state(), DuckingState::Normal);
+    }
+}
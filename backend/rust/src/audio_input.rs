@@ -0,0 +1,256 @@
+//! 音频采集模块
+//! 
+//! 为声源定位和跳舞模式提供麦克风数据。真实设备通过ALSA的`arecord`读取
+//! 交错的S16_LE原始PCM，不链接libasound，交叉编译时不需要额外的系统库；
+//! 模拟后端生成带固定声道间延迟的短促音，在开发机上也能跑通整条音频链路。
+
+use crate::audio_processing::{AudioProcessingConfig, AudioProcessor, EchoReference};
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::time::{Duration, Instant};
+use log::{debug, info, warn};
+
+/// 模拟音频中短促音的频率（Hz）
+const MOCK_TONE_HZ: f64 = 1000.0;
+
+/// 模拟音频中短促音的周期和持续时间（毫秒）
+const MOCK_BURST_PERIOD_MS: u64 = 500;
+const MOCK_BURST_LENGTH_MS: u64 = 100;
+
+/// 音频采集配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioInputConfig {
+    /// 采集后端，auto时在/dev/snd不存在的情况下使用模拟音频
+    #[serde(default)]
+    pub backend: BackendMode,
+    /// ALSA设备名
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// 每帧每声道的样本数
+    pub frame_size: usize,
+    /// 模拟音频中右声道相对左声道的延迟（样本数）
+    pub mock_delay_samples: usize,
+    /// 回声消除和降噪，默认关闭
+    #[serde(default)]
+    pub processing: AudioProcessingConfig,
+}
+
+impl Default for AudioInputConfig {
+    fn default() -> Self {
+        Self {
+            backend: BackendMode::default(),
+            device: "default".to_string(),
+            sample_rate: 16000,
+            channels: 2,
+            frame_size: 512,
+            mock_delay_samples: 3,
+            processing: AudioProcessingConfig::default(),
+        }
+    }
+}
+
+impl ConfigValidation for AudioInputConfig {
+    fn validate(&self) -> Result<()> {
+        if self.sample_rate == 0 {
+            return Err(anyhow::anyhow!("采样率必须大于0"));
+        }
+        
+        if self.channels == 0 {
+            return Err(anyhow::anyhow!("声道数必须大于0"));
+        }
+        
+        if self.frame_size == 0 {
+            return Err(anyhow::anyhow!("帧长度必须大于0"));
+        }
+        
+        if self.mock_delay_samples >= self.frame_size {
+            return Err(anyhow::anyhow!("模拟声道延迟必须小于帧长度"));
+        }
+        
+        self.processing.validate()?;
+        
+        Ok(())
+    }
+}
+
+/// 一帧音频，按声道分开，样本归一化到[-1, 1]
+#[derive(Debug, Clone)]
+pub struct AudioFrame {
+    pub channels: Vec<Vec<f32>>,
+    pub timestamp: u64,
+}
+
+enum Source {
+    Device {
+        child: Child,
+        stdout: ChildStdout,
+    },
+    Mock {
+        started: Instant,
+        samples_read: u64,
+    },
+}
+
+/// 音频采集
+pub struct AudioInput {
+    config: AudioInputConfig,
+    source: Source,
+    processor: Option<AudioProcessor>,
+}
+
+impl AudioInput {
+    /// 按配置打开真实麦克风或模拟音频
+    pub fn open(config: AudioInputConfig) -> Result<Self> {
+        config.validate()?;
+        
+        let source = if config.backend.use_real("/dev/snd") {
+            let mut child = Command::new("arecord")
+                .args(["-q", "-t", "raw", "-f", "S16_LE"])
+                .arg("-D").arg(&config.device)
+                .arg("-r").arg(config.sample_rate.to_string())
+                .arg("-c").arg(config.channels.to_string())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .map_err(|e| anyhow::anyhow!("启动arecord失败: {}", e))?;
+            let stdout = child.stdout.take()
+                .ok_or_else(|| anyhow::anyhow!("无法读取arecord输出"))?;
+            
+            info!("打开音频设备 {} ({} Hz, {} 声道)", config.device, config.sample_rate, config.channels);
+            Source::Device { child, stdout }
+        } else {
+            info!("音频采集使用模拟后端");
+            Source::Mock {
+                started: Instant::now(),
+                samples_read: 0,
+            }
+        };
+        
+        let processor = if config.processing.is_enabled() {
+            info!("启用音频前处理（回声消除: {}, 降噪: {}）",
+                  config.processing.echo_cancellation, config.processing.noise_suppression);
+            Some(AudioProcessor::new(&config.processing, config.sample_rate, config.channels as usize)?)
+        } else {
+            None
+        };
+        
+        Ok(Self { config, source, processor })
+    }
+    
+    pub fn is_mock(&self) -> bool {
+        matches!(self.source, Source::Mock { .. })
+    }
+    
+    /// 回声参考信号句柄，播放端把送往扬声器的样本push进去；未启用回声消除时为None
+    pub fn echo_reference(&self) -> Option<EchoReference> {
+        self.processor.as_ref()
+            .filter(|_| self.config.processing.echo_cancellation)
+            .map(AudioProcessor::reference)
+    }
+    
+    /// 阻塞读取一帧音频
+    pub fn read_frame(&mut self) -> Result<AudioFrame> {
+        let channels = self.config.channels as usize;
+        let frame_size = self.config.frame_size;
+        
+        let mut samples: Vec<Vec<f32>> = match &mut self.source {
+            Source::Device { stdout, .. } => {
+                let mut bytes = vec![0u8; frame_size * channels * 2];
+                stdout.read_exact(&mut bytes)
+                    .map_err(|e| anyhow::anyhow!("读取音频数据失败: {}", e))?;
+                
+                let mut samples = vec![Vec::with_capacity(frame_size); channels];
+                for (i, pair) in bytes.chunks_exact(2).enumerate() {
+                    let value = i16::from_le_bytes([pair[0], pair[1]]);
+                    samples[i % channels].push(value as f32 / 32768.0);
+                }
+                samples
+            },
+            Source::Mock { started, samples_read } => {
+                let start = *samples_read;
+                *samples_read += frame_size as u64;
+                
+                // 按采样率节流，模拟真实设备的读取节奏
+                let due = Duration::from_secs_f64(*samples_read as f64 / self.config.sample_rate as f64);
+                if let Some(wait) = due.checked_sub(started.elapsed()) {
+                    std::thread::sleep(wait);
+                }
+                
+                (0..channels)
+                    .map(|channel| {
+                        let delay = if channel == 1 { self.config.mock_delay_samples as u64 } else { 0 };
+                        (start..start + frame_size as u64)
+                            .map(|n| Self::mock_sample(n.saturating_sub(delay), self.config.sample_rate))
+                            .collect()
+                    })
+                    .collect()
+            },
+        };
+        
+        if let Some(processor) = &mut self.processor {
+            processor.process(&mut samples);
+        }
+        
+        Ok(AudioFrame {
+            channels: samples,
+            timestamp: current_timestamp(),
+        })
+    }
+    
+    /// 周期性短促音，其余时间静音
+    fn mock_sample(n: u64, sample_rate: u32) -> f32 {
+        let t_ms = n * 1000 / sample_rate as u64;
+        if t_ms % MOCK_BURST_PERIOD_MS >= MOCK_BURST_LENGTH_MS {
+            return 0.0;
+        }
+        
+        let t = n as f64 / sample_rate as f64;
+        (0.5 * (2.0 * std::f64::consts::PI * MOCK_TONE_HZ * t).sin()) as f32
+    }
+    
+    /// 关闭音频设备
+    pub fn close(&mut self) {
+        if let Source::Device { child, .. } = &mut self.source {
+            if let Err(e) = child.kill() {
+                warn!("停止arecord失败: {}", e);
+            }
+            let _ = child.wait();
+            debug!("音频设备已关闭");
+        }
+    }
+}
+
+impl Drop for AudioInput {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound_localization::{SoundLocalizationConfig, SoundLocalizer};
+    
+    #[test]
+    fn test_mock_input_feeds_localizer() {
+        let config = AudioInputConfig {
+            backend: BackendMode::Mock,
+            ..AudioInputConfig::default()
+        };
+        let mut input = AudioInput::open(config).unwrap();
+        assert!(input.is_mock());
+        
+        let frame = input.read_frame().unwrap();
+        assert_eq!(frame.channels.len(), 2);
+        assert!(frame.channels.iter().all(|samples| samples.len() == 512));
+        
+        // 右声道滞后，声源应位于左侧
+        let mut localizer = SoundLocalizer::new(SoundLocalizationConfig::default()).unwrap();
+        let bearing = localizer.process(&frame.channels[0], &frame.channels[1]).unwrap();
+        assert!(bearing.azimuth > 0.0);
+    }
+}
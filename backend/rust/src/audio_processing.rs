@@ -0,0 +1,416 @@
+//! 麦克风音频前处理（回声消除和降噪）
+//! 
+//! 机器人自己的扬声器在播放时，麦克风会收到这部分声音，唤醒词和语音识别因此失效。
+//! 回声消除对每个麦克风声道运行一个NLMS自适应滤波器，以播放出去的信号为参考估计回声并减去；
+//! 参考信号由播放端通过EchoReference送入。降噪在STFT域做谱减法，噪声谱按最小值跟踪估计。
+//! 全部为纯Rust实现，不依赖webrtc-audio-processing等系统库。
+
+use crate::common::*;
+use crate::dance::fft;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::{Arc, Mutex};
+
+/// NLMS归一化时防止除零
+const NLMS_EPSILON: f32 = 1e-6;
+
+/// 最小值跟踪得到的是噪声功率的下沿，乘以这个系数补偿到均值附近
+const NOISE_BIAS: f64 = 2.5;
+
+/// 音频前处理配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioProcessingConfig {
+    pub echo_cancellation: bool,
+    pub noise_suppression: bool,
+    /// 回声路径长度（毫秒），即自适应滤波器覆盖的时长
+    pub echo_filter_ms: u32,
+    /// NLMS步长（0-1），越大收敛越快但残留越多
+    pub echo_step_size: f32,
+    /// 双讲检测阈值：麦克风幅度超过近期参考峰值的这个比例时暂停滤波器更新
+    pub double_talk_threshold: f32,
+    /// 参考信号缓冲上限（毫秒），播放端超前太多时丢弃最旧的样本
+    pub reference_buffer_ms: u32,
+    /// 降噪的FFT长度（2的幂），帧移为一半
+    pub noise_fft_size: usize,
+    /// 过减系数
+    pub noise_over_subtraction: f32,
+    /// 每个频点增益的下限，避免音乐噪声
+    pub noise_gain_floor: f32,
+    /// 噪声估计每帧允许上升的比例
+    pub noise_adapt_rate: f32,
+}
+
+impl Default for AudioProcessingConfig {
+    fn default() -> Self {
+        Self {
+            echo_cancellation: false,
+            noise_suppression: false,
+            echo_filter_ms: 64,
+            echo_step_size: 0.3,
+            double_talk_threshold: 0.6,
+            reference_buffer_ms: 500,
+            noise_fft_size: 512,
+            noise_over_subtraction: 1.5,
+            noise_gain_floor: 0.1,
+            noise_adapt_rate: 0.005,
+        }
+    }
+}
+
+impl ConfigValidation for AudioProcessingConfig {
+    fn validate(&self) -> Result<()> {
+        if self.echo_filter_ms == 0 || self.echo_filter_ms > 500 {
+            return Err(anyhow::anyhow!("回声路径长度必须在1-500毫秒之间"));
+        }
+        
+        if !(self.echo_step_size > 0.0 && self.echo_step_size <= 1.0) {
+            return Err(anyhow::anyhow!("回声消除步长必须在(0, 1]之间"));
+        }
+        
+        if self.double_talk_threshold <= 0.0 {
+            return Err(anyhow::anyhow!("双讲检测阈值必须大于0"));
+        }
+        
+        if self.reference_buffer_ms < self.echo_filter_ms {
+            return Err(anyhow::anyhow!("参考信号缓冲不能短于回声路径长度"));
+        }
+        
+        if !self.noise_fft_size.is_power_of_two() || !(64..=4096).contains(&self.noise_fft_size) {
+            return Err(anyhow::anyhow!("降噪FFT长度必须是64-4096之间的2的幂"));
+        }
+        
+        if self.noise_over_subtraction < 1.0 {
+            return Err(anyhow::anyhow!("过减系数不能小于1"));
+        }
+        
+        if !(0.0..=1.0).contains(&self.noise_gain_floor) {
+            return Err(anyhow::anyhow!("降噪增益下限必须在0-1之间"));
+        }
+        
+        if !(self.noise_adapt_rate > 0.0 && self.noise_adapt_rate < 1.0) {
+            return Err(anyhow::anyhow!("噪声估计上升比例必须在(0, 1)之间"));
+        }
+        
+        Ok(())
+    }
+}
+
+impl AudioProcessingConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.echo_cancellation || self.noise_suppression
+    }
+}
+
+/// 回声参考信号（扬声器实际播放的单声道样本，采样率与麦克风相同）
+///
+/// 播放端在把样本写给声卡的同时push，采集端按麦克风的节奏取出，
+/// 可以在线程之间克隆共享。
+#[derive(Debug, Clone)]
+pub struct EchoReference {
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    capacity: usize,
+}
+
+impl EchoReference {
+    pub fn new(capacity: usize) -> Self {
+        Self { buffer: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))), capacity }
+    }
+    
+    /// 送入正在播放的样本
+    pub fn push(&self, samples: &[f32]) {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.extend(samples);
+        let excess = buffer.len().saturating_sub(self.capacity);
+        buffer.drain(..excess);
+    }
+    
+    /// 取出与麦克风帧对齐的n个样本，没有播放时补0
+    fn take(&self, n: usize) -> Vec<f32> {
+        let mut buffer = self.buffer.lock().unwrap();
+        let available = n.min(buffer.len());
+        let mut samples: Vec<f32> = buffer.drain(..available).collect();
+        samples.resize(n, 0.0);
+        samples
+    }
+}
+
+/// 单声道NLMS回声消除器
+struct EchoCanceller {
+    weights: Vec<f32>,
+    /// 参考信号历史，写两份以便取连续切片
+    history: Vec<f32>,
+    position: usize,
+    energy: f32,
+    step_size: f32,
+    double_talk_threshold: f32,
+}
+
+impl EchoCanceller {
+    fn new(taps: usize, step_size: f32, double_talk_threshold: f32) -> Self {
+        Self {
+            weights: vec![0.0; taps],
+            history: vec![0.0; taps * 2],
+            position: 0,
+            energy: 0.0,
+            step_size,
+            double_talk_threshold,
+        }
+    }
+    
+    fn process(&mut self, mic: &mut [f32], reference: &[f32]) {
+        let taps = self.weights.len();
+        
+        for (sample, &far) in mic.iter_mut().zip(reference) {
+            // 最新的样本在切片开头
+            self.position = if self.position == 0 { taps - 1 } else { self.position - 1 };
+            let oldest = self.history[self.position];
+            self.history[self.position] = far;
+            self.history[self.position + taps] = far;
+            self.energy = (self.energy + far * far - oldest * oldest).max(0.0);
+            
+            let x = &self.history[self.position..self.position + taps];
+            let echo: f32 = self.weights.iter().zip(x).map(|(w, x)| w * x).sum();
+            let error = *sample - echo;
+            
+            // Geigel双讲检测：近端有人说话时不更新滤波器，避免把人声当成回声学走
+            let far_peak = x.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+            let double_talk = sample.abs() > self.double_talk_threshold * far_peak;
+            if !double_talk && far_peak > 0.0 {
+                let gain = self.step_size * error / (self.energy + NLMS_EPSILON);
+                for (w, x) in self.weights.iter_mut().zip(x) {
+                    *w += gain * x;
+                }
+            }
+            
+            *sample = error;
+        }
+    }
+}
+
+/// 单声道谱减降噪（50%重叠的STFT，分析和合成都用平方根Hann窗）
+struct NoiseSuppressor {
+    fft_size: usize,
+    window: Vec<f64>,
+    input: Vec<f64>,
+    pending: Vec<f64>,
+    overlap: Vec<f64>,
+    output: VecDeque<f32>,
+    smoothed: Vec<f64>,
+    noise: Vec<f64>,
+    over_subtraction: f64,
+    gain_floor: f64,
+    adapt_rate: f64,
+}
+
+impl NoiseSuppressor {
+    fn new(config: &AudioProcessingConfig) -> Self {
+        let n = config.noise_fft_size;
+        let hop = n / 2;
+        let window = (0..n)
+            .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f64 / n as f64).cos()).sqrt())
+            .collect();
+        
+        Self {
+            fft_size: n,
+            window,
+            input: vec![0.0; n],
+            pending: Vec::with_capacity(hop),
+            overlap: vec![0.0; hop],
+            // 预先填充一个帧移的静音，任意帧长都能立即输出
+            output: std::iter::repeat_n(0.0, hop).collect(),
+            smoothed: vec![0.0; hop + 1],
+            noise: vec![f64::MAX; hop + 1],
+            over_subtraction: config.noise_over_subtraction as f64,
+            gain_floor: config.noise_gain_floor as f64,
+            adapt_rate: config.noise_adapt_rate as f64,
+        }
+    }
+    
+    fn process(&mut self, samples: &mut [f32]) {
+        let hop = self.fft_size / 2;
+        for &sample in samples.iter() {
+            self.pending.push(sample as f64);
+            if self.pending.len() == hop {
+                self.process_hop();
+            }
+        }
+        
+        for sample in samples.iter_mut() {
+            *sample = self.output.pop_front().unwrap_or(0.0);
+        }
+    }
+    
+    fn process_hop(&mut self) {
+        let n = self.fft_size;
+        let hop = n / 2;
+        self.input.copy_within(hop.., 0);
+        self.input[hop..].copy_from_slice(&self.pending);
+        self.pending.clear();
+        
+        let mut re: Vec<f64> = self.input.iter().zip(&self.window).map(|(x, w)| x * w).collect();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        
+        for k in 0..=hop {
+            let power = re[k] * re[k] + im[k] * im[k];
+            // 第一帧直接作为初值，否则噪声估计从0开始要很久才能升上来
+            self.smoothed[k] = if self.noise[k] == f64::MAX { power } else { 0.8 * self.smoothed[k] + 0.2 * power };
+            // 最小值跟踪：平滑功率低于估计时立即跟随，高于时缓慢上升
+            self.noise[k] = self.smoothed[k].min(self.noise[k] * (1.0 + self.adapt_rate));
+            
+            // 用平滑后的功率计算增益，单帧功率的随机起伏不会漏过残留噪声
+            let gain = if self.smoothed[k] > 0.0 {
+                (1.0 - self.over_subtraction * NOISE_BIAS * self.noise[k] / self.smoothed[k]).max(self.gain_floor)
+            } else {
+                self.gain_floor
+            };
+            re[k] *= gain;
+            im[k] *= gain;
+            // 实信号的频谱共轭对称
+            if k > 0 && k < hop {
+                re[n - k] *= gain;
+                im[n - k] *= gain;
+            }
+        }
+        
+        // 逆变换：对共轭做正变换再取共轭并除以n
+        for value in im.iter_mut() {
+            *value = -*value;
+        }
+        fft(&mut re, &mut im);
+        
+        for i in 0..hop {
+            let sample = re[i] / n as f64 * self.window[i] + self.overlap[i];
+            self.output.push_back(sample as f32);
+            self.overlap[i] = re[i + hop] / n as f64 * self.window[i + hop];
+        }
+    }
+}
+
+/// 多声道麦克风前处理，每个声道独立的回声消除器和降噪器，共用一路参考信号
+pub struct AudioProcessor {
+    echo: Vec<EchoCanceller>,
+    noise: Vec<NoiseSuppressor>,
+    reference: EchoReference,
+}
+
+impl AudioProcessor {
+    pub fn new(config: &AudioProcessingConfig, sample_rate: u32, channels: usize) -> Result<Self> {
+        config.validate()?;
+        
+        let samples = |ms: u32| (sample_rate as u64 * ms as u64 / 1000) as usize;
+        let echo = if config.echo_cancellation {
+            (0..channels)
+                .map(|_| EchoCanceller::new(samples(config.echo_filter_ms).max(1), config.echo_step_size, config.double_talk_threshold))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let noise = if config.noise_suppression {
+            (0..channels).map(|_| NoiseSuppressor::new(config)).collect()
+        } else {
+            Vec::new()
+        };
+        
+        Ok(Self {
+            echo,
+            noise,
+            reference: EchoReference::new(samples(config.reference_buffer_ms)),
+        })
+    }
+    
+    /// 播放端使用的参考信号句柄
+    pub fn reference(&self) -> EchoReference {
+        self.reference.clone()
+    }
+    
+    /// 原地处理一帧，每个声道样本数相同
+    pub fn process(&mut self, channels: &mut [Vec<f32>]) {
+        if !self.echo.is_empty() {
+            let frame_size = channels.first().map_or(0, Vec::len);
+            let reference = self.reference.take(frame_size);
+            for (samples, canceller) in channels.iter_mut().zip(&mut self.echo) {
+                canceller.process(samples, &reference);
+            }
+        }
+        
+        for (samples, suppressor) in channels.iter_mut().zip(&mut self.noise) {
+            suppressor.process(samples);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    
+    fn energy(samples: &[f32]) -> f32 {
+        samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32
+    }
+    
+    #[test]
+    fn test_echo_cancellation_converges() {
+        let config = AudioProcessingConfig { echo_cancellation: true, echo_filter_ms: 4, ..AudioProcessingConfig::default() };
+        let mut processor = AudioProcessor::new(&config, 16000, 2).unwrap();
+        let reference = processor.reference();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        
+        // 两个声道收到不同延迟和衰减的回声
+        let mut far = vec![0.0f32; 16];
+        let mut residual = 0.0;
+        for frame in 0..100 {
+            let played: Vec<f32> = (0..256).map(|_| rng.gen_range(-0.5..0.5)).collect();
+            far.extend(&played);
+            let offset = far.len() - 256;
+            let mut mic = vec![
+                (0..256).map(|i| 0.6 * far[offset + i - 10]).collect::<Vec<f32>>(),
+                (0..256).map(|i| 0.4 * far[offset + i - 14]).collect::<Vec<f32>>(),
+            ];
+            let echo = energy(&mic[0]);
+            
+            reference.push(&played);
+            processor.process(&mut mic);
+            if frame == 99 {
+                residual = energy(&mic[0]) / echo;
+                assert!(energy(&mic[1]) < 0.01 * energy(&played));
+            }
+        }
+        
+        // 收敛后回声衰减超过20dB
+        assert!(residual < 0.01, "残留比例 {}", residual);
+    }
+    
+    #[test]
+    fn test_noise_suppression_keeps_tone() {
+        let config = AudioProcessingConfig { noise_suppression: true, ..AudioProcessingConfig::default() };
+        let mut processor = AudioProcessor::new(&config, 16000, 1).unwrap();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let mut noise = || rng.gen_range(-0.05f32..0.05);
+        
+        // 先让噪声估计收敛
+        for _ in 0..100 {
+            let mut frame = vec![(0..512).map(|_| noise()).collect::<Vec<f32>>()];
+            processor.process(&mut frame);
+        }
+        
+        let mut silent = vec![(0..512).map(|_| noise()).collect::<Vec<f32>>()];
+        let input_noise = energy(&silent[0]);
+        processor.process(&mut silent);
+        assert!(energy(&silent[0]) < 0.2 * input_noise);
+        
+        let mut output = Vec::new();
+        for frame in 0..4 {
+            let tone: Vec<f32> = (0..512)
+                .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 1000.0 * (frame * 512 + i) as f32 / 16000.0).sin() + noise())
+                .collect();
+            let mut channels = vec![tone];
+            processor.process(&mut channels);
+            output.extend(channels.remove(0));
+        }
+        // 跳过延迟和起始过渡，正弦能量基本保留（0.5幅度的能量为0.125）
+        assert!(energy(&output[1024..]) > 0.1);
+    }
+}
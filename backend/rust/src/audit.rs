@@ -0,0 +1,411 @@
+//! 命令审计日志
+//! 
+//! 外部接口提交的每条运动命令都记录为一条不可修改的审计事件（来源、客户端、
+//! 角色、原始内容和处理结果），按行追加到JSON Lines文件中，便于事后追查是谁让机器人
+//! 在什么时候动了起来。当前文件超过大小上限时轮转为`commands.jsonl.1`，
+//! 已有的轮转文件依次后移，超出保留数量的最旧文件被删除。
+//!
+//! 每条记录带有单调递增的序号，重启后从已有文件的最后一条记录继续编号。
+//!
+//! `CommandGate`把仲裁和审计组合在一起，供不经过实时控制器执行的命令（例如Python端的
+//! 网页遥操作）使用，与实时控制器使用相同的角色检查、仲裁规则和审计文件。
+
+use crate::arbiter::{ArbiterConfig, Arbitration, CommandArbiter, CommandOrigin, CommandSource, Role};
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use log::warn;
+
+/// 当前审计文件名
+pub const AUDIT_FILE_NAME: &str = "commands.jsonl";
+
+/// 审计日志配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    /// 审计文件目录，为None时只保留在内存中
+    pub directory: Option<PathBuf>,
+    /// 单个文件的大小上限（字节），超过后轮转
+    pub max_file_bytes: u64,
+    /// 保留的轮转文件数量
+    pub max_files: usize,
+    /// 内存中保留的最近记录数
+    pub memory_records: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            directory: Some(PathBuf::from("data/audit")),
+            max_file_bytes: 4 * 1024 * 1024,
+            max_files: 8,
+            memory_records: 1000,
+        }
+    }
+}
+
+impl ConfigValidation for AuditConfig {
+    fn validate(&self) -> Result<()> {
+        if self.max_file_bytes == 0 {
+            return Err(anyhow::anyhow!("审计文件大小上限必须为正数"));
+        }
+        
+        if self.memory_records == 0 {
+            return Err(anyhow::anyhow!("审计日志内存记录数必须为正数"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 命令的处理结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuditResult {
+    Accepted,
+    Rejected {
+        reason: String,
+    },
+}
+
+impl AuditResult {
+    pub fn from_result<T>(result: &Result<T>) -> Self {
+        match result {
+            Ok(_) => AuditResult::Accepted,
+            Err(e) => AuditResult::Rejected { reason: e.to_string() },
+        }
+    }
+    
+    pub fn is_accepted(&self) -> bool {
+        matches!(self, AuditResult::Accepted)
+    }
+}
+
+/// 一条审计记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: u64,
+    pub source: CommandSource,
+    pub client_id: String,
+    #[serde(default)]
+    pub role: Role,
+    /// 客户端提交的命令内容，无法解析为JSON时保存原始字符串
+    pub payload: serde_json::Value,
+    pub result: AuditResult,
+}
+
+/// 审计记录查询条件，各条件同时满足才匹配
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditQuery {
+    pub source: Option<CommandSource>,
+    pub client_id: Option<String>,
+    pub role: Option<Role>,
+    /// 时间范围（毫秒时间戳，含两端）
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// 只返回被接受（true）或被拒绝（false）的命令
+    pub accepted: Option<bool>,
+    /// 最多返回的记录数，取最新的记录
+    pub limit: usize,
+}
+
+impl Default for AuditQuery {
+    fn default() -> Self {
+        Self {
+            source: None,
+            client_id: None,
+            role: None,
+            since: None,
+            until: None,
+            accepted: None,
+            limit: 100,
+        }
+    }
+}
+
+impl AuditQuery {
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.source.is_none_or(|source| record.source == source)
+            && self.client_id.as_ref().is_none_or(|client_id| record.client_id == *client_id)
+            && self.role.is_none_or(|role| record.role == role)
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp <= until)
+            && self.accepted.is_none_or(|accepted| record.result.is_accepted() == accepted)
+    }
+}
+
+/// 只追加的命令审计日志
+#[derive(Debug)]
+pub struct AuditLog {
+    config: AuditConfig,
+    next_seq: u64,
+    recent: VecDeque<AuditRecord>,
+}
+
+impl AuditLog {
+    pub fn new(config: AuditConfig) -> Result<Self> {
+        config.validate()?;
+        
+        // 从已有文件的最后一条记录继续编号
+        let next_seq = config.directory.as_deref()
+            .and_then(|directory| last_record(&directory.join(AUDIT_FILE_NAME)))
+            .map(|record| record.seq + 1)
+            .unwrap_or(1);
+        
+        Ok(Self {
+            config,
+            next_seq,
+            recent: VecDeque::new(),
+        })
+    }
+    
+    /// 记录一条命令；写文件失败只记录警告，不影响命令本身
+    pub fn record(&mut self, origin: &CommandOrigin, payload: serde_json::Value, result: AuditResult) -> Option<AuditRecord> {
+        if !self.config.enabled {
+            return None;
+        }
+        
+        let record = AuditRecord {
+            seq: self.next_seq,
+            timestamp: current_timestamp(),
+            source: origin.source,
+            client_id: origin.client_id.clone(),
+            role: origin.role,
+            payload,
+            result,
+        };
+        self.next_seq += 1;
+        
+        if let Some(directory) = &self.config.directory {
+            if let Err(e) = self.append(directory, &record) {
+                warn!("写入命令审计日志失败: {}", e);
+            }
+        }
+        
+        if self.recent.len() >= self.config.memory_records {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(record.clone());
+        
+        Some(record)
+    }
+    
+    fn append(&self, directory: &Path, record: &AuditRecord) -> Result<()> {
+        fs::create_dir_all(directory)?;
+        
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        
+        let path = directory.join(AUDIT_FILE_NAME);
+        let size = fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 > self.config.max_file_bytes {
+            self.rotate(directory)?;
+        }
+        
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+    
+    /// 轮转：commands.jsonl -> commands.jsonl.1 -> commands.jsonl.2 ...
+    fn rotate(&self, directory: &Path) -> Result<()> {
+        let rotated = |index: usize| directory.join(format!("{}.{}", AUDIT_FILE_NAME, index));
+        
+        if self.config.max_files == 0 {
+            fs::remove_file(directory.join(AUDIT_FILE_NAME))?;
+            return Ok(());
+        }
+        
+        let _ = fs::remove_file(rotated(self.config.max_files));
+        for index in (1..self.config.max_files).rev() {
+            if rotated(index).exists() {
+                fs::rename(rotated(index), rotated(index + 1))?;
+            }
+        }
+        fs::rename(directory.join(AUDIT_FILE_NAME), rotated(1))?;
+        Ok(())
+    }
+    
+    /// 按条件查询，结果按序号升序排列；配置了目录时查询全部保留的文件
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>> {
+        if query.limit == 0 {
+            return Ok(Vec::new());
+        }
+        
+        let mut matched: VecDeque<AuditRecord> = VecDeque::new();
+        let mut keep = |record: AuditRecord| {
+            if query.matches(&record) {
+                if matched.len() >= query.limit {
+                    matched.pop_front();
+                }
+                matched.push_back(record);
+            }
+        };
+        
+        match &self.config.directory {
+            Some(directory) => {
+                // 从最旧的轮转文件读到当前文件
+                let mut files: Vec<PathBuf> = (1..=self.config.max_files)
+                    .rev()
+                    .map(|index| directory.join(format!("{}.{}", AUDIT_FILE_NAME, index)))
+                    .collect();
+                files.push(directory.join(AUDIT_FILE_NAME));
+                
+                for path in files.iter().filter(|path| path.exists()) {
+                    for record in read_records(path)? {
+                        keep(record);
+                    }
+                }
+            }
+            None => self.recent.iter().cloned().for_each(keep),
+        }
+        
+        Ok(matched.into())
+    }
+    
+    /// 内存中的最近记录
+    pub fn recent(&self) -> impl Iterator<Item = &AuditRecord> {
+        self.recent.iter()
+    }
+}
+
+/// 读取审计文件，跳过无法解析的行（例如断电时写了一半的最后一行）
+fn read_records(path: &Path) -> Result<Vec<AuditRecord>> {
+    let content = fs::read_to_string(path)?;
+    Ok(content.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn last_record(path: &Path) -> Option<AuditRecord> {
+    read_records(path).ok()?.pop()
+}
+
+/// 命令准入：先检查角色并仲裁，再把命令和结果写入审计日志
+#[derive(Debug)]
+pub struct CommandGate {
+    arbiter: CommandArbiter,
+    audit_log: AuditLog,
+}
+
+impl CommandGate {
+    pub fn new(arbitration: ArbiterConfig, audit: AuditConfig) -> Result<Self> {
+        Ok(Self {
+            arbiter: CommandArbiter::new(arbitration)?,
+            audit_log: AuditLog::new(audit)?,
+        })
+    }
+    
+    /// 仲裁一条命令并记录审计，被拒绝时返回错误；急停不受角色和仲裁限制
+    pub fn submit(&mut self, origin: &CommandOrigin, payload: serde_json::Value, emergency: bool) -> Result<()> {
+        let result = match self.arbiter.arbitrate(origin, emergency) {
+            Arbitration::Accepted => Ok(()),
+            Arbitration::Rejected(reason) => Err(anyhow::anyhow!("命令被拒绝: {}", reason)),
+        };
+        self.audit_log.record(origin, payload, AuditResult::from_result(&result));
+        result
+    }
+    
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit_log
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn temp_config(name: &str) -> AuditConfig {
+        let directory = std::env::temp_dir().join(format!("reachy_audit_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        AuditConfig {
+            directory: Some(directory),
+            max_file_bytes: 600,
+            max_files: 2,
+            ..AuditConfig::default()
+        }
+    }
+    
+    #[test]
+    fn test_rotation_and_sequence_recovery() {
+        let config = temp_config("rotation");
+        let directory = config.directory.clone().unwrap();
+        let origin = CommandOrigin::new(CommandSource::WebSocket, "dashboard");
+        
+        let mut log = AuditLog::new(config.clone()).unwrap();
+        for i in 0..20 {
+            log.record(&origin, serde_json::json!({"joint": "head_pan", "position": i}), AuditResult::Accepted);
+        }
+        
+        // 超出保留数量的旧文件被删除，只能查到较新的记录
+        assert!(directory.join("commands.jsonl.2").exists());
+        assert!(!directory.join("commands.jsonl.3").exists());
+        let records = log.query(&AuditQuery { limit: 100, ..AuditQuery::default() }).unwrap();
+        assert!(records.len() < 20);
+        assert_eq!(records.last().unwrap().seq, 20);
+        assert!(records.windows(2).all(|pair| pair[1].seq == pair[0].seq + 1));
+        
+        // 重启后继续编号
+        let mut reopened = AuditLog::new(config).unwrap();
+        let record = reopened.record(&origin, serde_json::json!({}), AuditResult::Accepted).unwrap();
+        assert_eq!(record.seq, 21);
+        
+        let _ = fs::remove_dir_all(directory);
+    }
+    
+    #[test]
+    fn test_query_filters() {
+        let mut log = AuditLog::new(AuditConfig { directory: None, ..AuditConfig::default() }).unwrap();
+        let browser = CommandOrigin::new(CommandSource::WebSocket, "browser");
+        let script = CommandOrigin::new(CommandSource::Python, "script");
+        
+        log.record(&browser, serde_json::json!({"type": "stop"}), AuditResult::Accepted);
+        log.record(&script, serde_json::json!({"type": "stop"}), AuditResult::Rejected { reason: "限流".to_string() });
+        log.record(&browser, serde_json::json!({"type": "position"}), AuditResult::Accepted);
+        
+        let by_client = log.query(&AuditQuery { client_id: Some("browser".to_string()), ..AuditQuery::default() }).unwrap();
+        assert_eq!(by_client.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![1, 3]);
+        
+        let rejected = log.query(&AuditQuery { accepted: Some(false), ..AuditQuery::default() }).unwrap();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].source, CommandSource::Python);
+        
+        let latest = log.query(&AuditQuery { limit: 1, ..AuditQuery::default() }).unwrap();
+        assert_eq!(latest[0].seq, 3);
+        
+        let value = serde_json::to_value(&rejected[0]).unwrap();
+        assert_eq!(value["result"]["status"], "rejected");
+    }
+    
+    #[test]
+    fn test_command_gate_checks_role_and_audits() {
+        let audit = AuditConfig { directory: None, ..AuditConfig::default() };
+        let mut gate = CommandGate::new(ArbiterConfig::default(), audit).unwrap();
+        let viewer = CommandOrigin::new(CommandSource::Teleop, "guest").with_role(Role::Viewer);
+        let operator = CommandOrigin::new(CommandSource::Teleop, "browser");
+        let script = CommandOrigin::new(CommandSource::Python, "script");
+        let axes = serde_json::json!({"type": "teleop", "axes": {"head_pan": 0.5}});
+        
+        assert!(gate.submit(&viewer, axes.clone(), false).is_err());
+        assert!(gate.submit(&viewer, serde_json::json!({"type": "emergency_stop"}), true).is_ok());
+        assert!(gate.submit(&operator, axes.clone(), false).is_ok());
+        // 遥操作刚发过命令，低优先级的脚本被压制
+        assert!(gate.submit(&script, axes, false).is_err());
+        
+        let records: Vec<_> = gate.audit_log().recent().collect();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].role, Role::Viewer);
+        assert!(!records[0].result.is_accepted());
+        assert!(records[1].result.is_accepted() && records[2].result.is_accepted());
+        assert!(!records[3].result.is_accepted());
+    }
+}
@@ -0,0 +1,185 @@
+//! 齿隙与死区补偿模块
+//! 
+//! 廉价舵机的齿轮存在明显的齿隙：换向时输出轴要先走完空程才会跟着动。
+//! 本模块在控制输出路径上按关节补偿齿隙（换向时给设定点加上半个齿隙的偏移）
+//! 和死区（把很小的非零输出推过死区），并提供根据往返扫描数据估计参数的辨识方法。
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// 判断设定点移动方向的最小变化量（rad）
+const DIRECTION_EPSILON: f64 = 1e-5;
+
+/// 单个关节的补偿参数
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BacklashParams {
+    /// 齿隙总宽度（rad）
+    pub backlash: f64,
+    /// 死区宽度（控制输出单位），非零输出至少要达到此值才能让关节动起来
+    pub deadband: f64,
+}
+
+impl ConfigValidation for BacklashParams {
+    fn validate(&self) -> Result<()> {
+        if self.backlash < 0.0 || self.deadband < 0.0 {
+            return Err(anyhow::anyhow!("齿隙和死区不能为负数"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 单个关节的齿隙/死区补偿器
+#[derive(Debug, Clone, Default)]
+pub struct BacklashCompensator {
+    params: BacklashParams,
+    last_setpoint: Option<f64>,
+    /// 最近的移动方向：1、-1，尚未移动时为0
+    direction: f64,
+}
+
+impl BacklashCompensator {
+    pub fn new(params: BacklashParams) -> Self {
+        Self {
+            params,
+            ..Default::default()
+        }
+    }
+    
+    pub fn params(&self) -> &BacklashParams {
+        &self.params
+    }
+    
+    pub fn set_params(&mut self, params: BacklashParams) {
+        self.params = params;
+    }
+    
+    /// 补偿齿隙：按设定点的移动方向偏移半个齿隙，方向不变时偏移保持不变
+    pub fn compensate_setpoint(&mut self, setpoint: f64) -> f64 {
+        if let Some(last) = self.last_setpoint {
+            let delta = setpoint - last;
+            if delta.abs() > DIRECTION_EPSILON {
+                self.direction = delta.signum();
+            }
+        }
+        self.last_setpoint = Some(setpoint);
+        
+        setpoint + self.direction * self.params.backlash / 2.0
+    }
+    
+    /// 补偿死区：非零输出加上死区宽度，输出为0时保持为0
+    pub fn compensate_output(&self, output: f64) -> f64 {
+        if output == 0.0 {
+            0.0
+        } else {
+            output + output.signum() * self.params.deadband
+        }
+    }
+    
+    /// 清除方向记忆（例如急停之后）
+    pub fn reset(&mut self) {
+        self.last_setpoint = None;
+        self.direction = 0.0;
+    }
+}
+
+/// 根据往返扫描估计齿隙
+///
+/// samples为 (命令位置, 稳定后的实测位置)，按时间顺序。
+/// 正向移动时实测值滞后于命令半个齿隙，反向时超前半个齿隙，
+/// 两个方向上"命令-实测"的均值之差即为齿隙宽度。
+pub fn estimate_backlash(samples: &[(f64, f64)]) -> Option<f64> {
+    let (mut forward, mut backward) = (Vec::new(), Vec::new());
+    
+    for pair in samples.windows(2) {
+        let (previous_command, _) = pair[0];
+        let (command, measured) = pair[1];
+        let delta = command - previous_command;
+        
+        if delta > DIRECTION_EPSILON {
+            forward.push(command - measured);
+        } else if delta < -DIRECTION_EPSILON {
+            backward.push(command - measured);
+        }
+    }
+    
+    if forward.is_empty() || backward.is_empty() {
+        return None;
+    }
+    
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    Some((mean(&forward) - mean(&backward)).max(0.0))
+}
+
+/// 根据逐步增大的输出估计死区
+///
+/// samples为 (控制输出, 关节速度)，返回使关节速度超过阈值的最小输出幅值。
+pub fn estimate_deadband(samples: &[(f64, f64)], velocity_threshold: f64) -> Option<f64> {
+    samples.iter()
+        .filter(|(output, velocity)| *output != 0.0 && velocity.abs() > velocity_threshold)
+        .map(|(output, _)| output.abs())
+        .min_by(|a, b| a.total_cmp(b))
+}
+
+/// 生成齿隙辨识用的往返扫描位置：从center出发正向走到center+amplitude，再反向到center-amplitude，最后回到center
+pub fn backlash_sweep(center: f64, amplitude: f64, steps: usize) -> Vec<f64> {
+    let steps = steps.max(1);
+    let step = amplitude / steps as f64;
+    
+    let forward = (0..=steps).map(|i| center + step * i as f64);
+    let backward = (1..=2 * steps).map(|i| center + amplitude - step * i as f64);
+    let back_to_center = (1..=steps).map(|i| center - amplitude + step * i as f64);
+    
+    forward.chain(backward).chain(back_to_center).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 模拟带齿隙的关节：输出轴只有在输入超出空程后才会被带动
+    fn simulate(commands: &[f64], backlash: f64) -> Vec<(f64, f64)> {
+        let mut output: f64 = 0.0;
+        commands.iter().map(|&command| {
+            if command - output > backlash / 2.0 {
+                output = command - backlash / 2.0;
+            } else if output - command > backlash / 2.0 {
+                output = command + backlash / 2.0;
+            }
+            (command, output)
+        }).collect()
+    }
+    
+    #[test]
+    fn test_backlash_identification() {
+        let sweep = backlash_sweep(0.0, 0.2, 10);
+        assert_eq!(sweep.first(), Some(&0.0));
+        assert!((sweep.iter().cloned().fold(f64::MIN, f64::max) - 0.2).abs() < 1e-9);
+        
+        let samples = simulate(&sweep, 0.02);
+        let estimated = estimate_backlash(&samples).unwrap();
+        assert!((estimated - 0.02).abs() < 0.005, "estimated {}", estimated);
+        
+        // 只朝一个方向移动时无法估计
+        assert!(estimate_backlash(&samples[..5]).is_none());
+        
+        let deadband = estimate_deadband(&[(0.01, 0.0), (0.02, 0.0), (0.03, 0.05), (0.04, 0.1)], 0.01);
+        assert_eq!(deadband, Some(0.03));
+    }
+    
+    #[test]
+    fn test_compensation() {
+        let mut compensator = BacklashCompensator::new(BacklashParams { backlash: 0.02, deadband: 0.1 });
+        
+        // 第一次设定点没有方向信息
+        assert_eq!(compensator.compensate_setpoint(0.0), 0.0);
+        assert!((compensator.compensate_setpoint(0.1) - 0.11).abs() < 1e-9);
+        // 保持不动时仍按正向偏移
+        assert!((compensator.compensate_setpoint(0.1) - 0.11).abs() < 1e-9);
+        assert!((compensator.compensate_setpoint(0.05) - 0.04).abs() < 1e-9);
+        
+        assert_eq!(compensator.compensate_output(0.0), 0.0);
+        assert!((compensator.compensate_output(-0.2) + 0.3).abs() < 1e-9);
+    }
+}
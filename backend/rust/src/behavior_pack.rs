@@ -0,0 +1,933 @@
+//! 行为包
+//! 
+//! 社区分享交互行为用的zip包，结构如下：
+//!
+//! ```text
+//! manifest.json            包信息、命名行为树和触发规则
+//! animations/<名称>.json   轨迹文件（见trajectory_file模块）
+//! sounds/<名称>.wav|ogg|mp3
+//! leds/<名称>.json         灯效
+//! ```
+//!
+//! 包中没有可执行代码：行为树只由固定的节点类型组成，规则复用规则引擎的条件和动作。
+//! 加载时在沙箱中逐项检查——条目路径、数量和解压后大小、文件类型、资源引用、行为树规模，
+//! 任何一项不通过都拒绝整个包。包内名称加上"包ID/"前缀后才对外可见，
+//! 不会覆盖内置资源或其他包的资源。安装后默认禁用，启用后其规则和行为树才生效。
+
+use crate::common::*;
+use crate::events::{EventBus, EventEnvelope};
+use crate::rules::{Rule, RuleAction, RuleCondition};
+use crate::trajectory_file::TrajectoryFile;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use log::{info, warn};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const STATE_FILE: &str = "packs.json";
+const SOUND_EXTENSIONS: [&str; 3] = ["wav", "ogg", "mp3"];
+
+/// 行为包配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BehaviorPackConfig {
+    /// 已安装的包解压在该目录下
+    pub directory: PathBuf,
+    /// 单个包解压后的最大总大小（字节）
+    pub max_pack_size: u64,
+    /// 包内单个文件的最大大小（字节）
+    pub max_file_size: u64,
+    pub max_entries: usize,
+    /// 解压后大小与压缩后大小之比的上限，防止压缩炸弹
+    pub max_compression_ratio: u64,
+    pub max_tree_depth: usize,
+    pub max_tree_nodes: usize,
+    /// 一棵行为树中等待节点的总时长上限（毫秒）
+    pub max_total_wait_ms: u64,
+}
+
+impl Default for BehaviorPackConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("./data/behavior_packs"),
+            max_pack_size: 64 * 1024 * 1024,
+            max_file_size: 16 * 1024 * 1024,
+            max_entries: 256,
+            max_compression_ratio: 100,
+            max_tree_depth: 16,
+            max_tree_nodes: 256,
+            max_total_wait_ms: 60_000,
+        }
+    }
+}
+
+impl ConfigValidation for BehaviorPackConfig {
+    fn validate(&self) -> Result<()> {
+        if self.directory.as_os_str().is_empty() {
+            return Err(anyhow::anyhow!("行为包目录不能为空"));
+        }
+        
+        if self.max_file_size == 0 || self.max_pack_size < self.max_file_size {
+            return Err(anyhow::anyhow!("行为包大小限制无效"));
+        }
+        
+        if self.max_entries == 0 || self.max_compression_ratio == 0 {
+            return Err(anyhow::anyhow!("行为包条目数和压缩比限制必须大于0"));
+        }
+        
+        if self.max_tree_depth == 0 || self.max_tree_nodes == 0 {
+            return Err(anyhow::anyhow!("行为树规模限制必须大于0"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 灯效中的一帧
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedFrame {
+    /// RGB颜色
+    pub color: [u8; 3],
+    /// 亮度（0-1）
+    #[serde(default = "full_brightness")]
+    pub brightness: f64,
+    pub duration_ms: u64,
+}
+
+fn full_brightness() -> f64 {
+    1.0
+}
+
+/// 灯效
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedPattern {
+    pub frames: Vec<LedFrame>,
+    /// 循环播放，直到被其他灯效替换
+    #[serde(default)]
+    pub repeat: bool,
+}
+
+impl LedPattern {
+    fn validate(&self) -> Result<()> {
+        if self.frames.is_empty() || self.frames.len() > 1000 {
+            return Err(anyhow::anyhow!("灯效帧数必须在1-1000之间"));
+        }
+        
+        for (index, frame) in self.frames.iter().enumerate() {
+            if frame.duration_ms == 0 {
+                return Err(anyhow::anyhow!("灯效第 {} 帧的时长必须大于0", index));
+            }
+            if !(0.0..=1.0).contains(&frame.brightness) {
+                return Err(anyhow::anyhow!("灯效第 {} 帧的亮度必须在0-1之间", index));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 行为树节点
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BehaviorNode {
+    /// 依次执行子节点，任一子节点失败则整体失败
+    Sequence { children: Vec<BehaviorNode> },
+    /// 依次尝试子节点，执行第一个成功的
+    Selector { children: Vec<BehaviorNode> },
+    /// 条件成立时成功，不产生动作
+    Condition { condition: RuleCondition },
+    PlayAnimation { name: String },
+    PlaySound { name: String },
+    LedPattern { name: String },
+    /// 看向触发事件的位置或方位
+    LookAtEvent,
+    SetState { key: String, value: String },
+    Wait { ms: u64 },
+}
+
+impl BehaviorNode {
+    fn children(&self) -> &[BehaviorNode] {
+        match self {
+            BehaviorNode::Sequence { children } | BehaviorNode::Selector { children } => children,
+            _ => &[],
+        }
+    }
+    
+    /// 节点数、最大深度和等待总时长
+    fn measure(&self) -> (usize, usize, u64) {
+        let wait = match self {
+            BehaviorNode::Wait { ms } => *ms,
+            _ => 0,
+        };
+        
+        self.children().iter().fold((1, 1, wait), |(nodes, depth, wait), child| {
+            let (child_nodes, child_depth, child_wait) = child.measure();
+            (nodes + child_nodes, depth.max(child_depth + 1), wait.saturating_add(child_wait))
+        })
+    }
+    
+    fn for_each(&self, f: &mut impl FnMut(&BehaviorNode)) {
+        f(self);
+        for child in self.children() {
+            child.for_each(f);
+        }
+    }
+    
+    fn qualify(&mut self, pack_id: &str) {
+        match self {
+            BehaviorNode::Sequence { children } | BehaviorNode::Selector { children } => {
+                children.iter_mut().for_each(|child| child.qualify(pack_id));
+            }
+            BehaviorNode::PlayAnimation { name } | BehaviorNode::PlaySound { name } | BehaviorNode::LedPattern { name } => {
+                *name = format!("{}/{}", pack_id, name);
+            }
+            _ => {}
+        }
+    }
+    
+    /// 按触发事件和状态展开为依次执行的动作节点，失败时返回None；
+    /// 计划中的SetState对后续条件可见
+    pub fn plan(&self, envelope: &EventEnvelope, state: &HashMap<String, String>) -> Option<Vec<BehaviorNode>> {
+        let mut state = state.clone();
+        let mut steps = Vec::new();
+        self.plan_into(envelope, &mut state, &mut steps).then_some(steps)
+    }
+    
+    fn plan_into(&self, envelope: &EventEnvelope, state: &mut HashMap<String, String>, steps: &mut Vec<BehaviorNode>) -> bool {
+        match self {
+            BehaviorNode::Sequence { children } => {
+                let (saved_state, saved_len) = (state.clone(), steps.len());
+                if children.iter().all(|child| child.plan_into(envelope, state, steps)) {
+                    return true;
+                }
+                *state = saved_state;
+                steps.truncate(saved_len);
+                false
+            }
+            BehaviorNode::Selector { children } => {
+                children.iter().any(|child| child.plan_into(envelope, state, steps))
+            }
+            BehaviorNode::Condition { condition } => condition.matches(envelope, state),
+            BehaviorNode::SetState { key, value } => {
+                state.insert(key.clone(), value.clone());
+                steps.push(self.clone());
+                true
+            }
+            action => {
+                steps.push(action.clone());
+                true
+            }
+        }
+    }
+}
+
+/// 在事件总线上依次发布行为计划中的动作，动画、声音和灯效交给对应模块执行
+pub async fn execute_plan(steps: &[BehaviorNode], bus: &EventBus, source: &str, trigger: &EventEnvelope) {
+    for step in steps {
+        let (name, data) = match step {
+            BehaviorNode::Wait { ms } => {
+                tokio::time::sleep(Duration::from_millis(*ms)).await;
+                continue;
+            }
+            BehaviorNode::PlayAnimation { name } => ("behavior.animation", serde_json::json!({ "animation": name })),
+            BehaviorNode::PlaySound { name } => ("behavior.sound", serde_json::json!({ "sound": name })),
+            BehaviorNode::LedPattern { name } => ("behavior.led", serde_json::json!({ "pattern": name })),
+            BehaviorNode::LookAtEvent => ("behavior.look_at", serde_json::to_value(&trigger.event).unwrap_or_default()),
+            BehaviorNode::SetState { key, value } => ("behavior.state", serde_json::json!({ "key": key, "value": value })),
+            _ => continue,
+        };
+        bus.publish(source, crate::events::RobotEvent::Custom { name: name.to_string(), data });
+    }
+}
+
+/// 行为包清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackManifest {
+    /// 包ID：小写字母、数字、'-'和'_'
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub description: String,
+    /// 命名行为树
+    #[serde(default)]
+    pub behaviors: BTreeMap<String, BehaviorNode>,
+    /// 触发规则，动作中的动画和行为树名称指向包内资源
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+/// 行为包需要的权限，由包内容推断，安装前展示给用户确认
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackPermission {
+    /// 驱动关节：播放动画、看向事件
+    Motion,
+    Audio,
+    Leds,
+    /// 修改规则引擎的状态
+    State,
+    /// 包含规则，收到事件时自动执行
+    Triggers,
+    /// 发布自定义事件
+    Events,
+}
+
+/// 检查过的行为包，资源名称已加上包ID前缀
+#[derive(Debug, Clone)]
+pub struct BehaviorPack {
+    pub manifest: PackManifest,
+    pub animations: BTreeMap<String, TrajectoryFile>,
+    /// 声音名称到包内相对路径
+    pub sounds: BTreeMap<String, String>,
+    pub led_patterns: BTreeMap<String, LedPattern>,
+}
+
+fn valid_name(name: &str, allow_upper: bool) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_'
+            || (allow_upper && c.is_ascii_uppercase()))
+}
+
+/// 解压后的文件内容，键为包内相对路径
+type PackFiles = BTreeMap<String, Vec<u8>>;
+
+fn check_audio(path: &str, bytes: &[u8]) -> Result<()> {
+    let valid = match path.rsplit('.').next() {
+        Some("wav") => bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WAVE",
+        Some("ogg") => bytes.starts_with(b"OggS"),
+        Some("mp3") => bytes.starts_with(b"ID3") || (bytes.len() >= 2 && bytes[0] == 0xFF && bytes[1] & 0xE0 == 0xE0),
+        _ => false,
+    };
+    
+    if !valid {
+        return Err(anyhow::anyhow!("声音文件 {} 的内容与扩展名不符", path));
+    }
+    Ok(())
+}
+
+impl BehaviorPack {
+    /// 行为树和规则实际用到的权限，包内未被引用的资源不计入
+    pub fn permissions(&self) -> BTreeSet<PackPermission> {
+        let mut permissions = BTreeSet::new();
+        
+        for tree in self.manifest.behaviors.values() {
+            tree.for_each(&mut |node| {
+                let permission = match node {
+                    BehaviorNode::PlayAnimation { .. } | BehaviorNode::LookAtEvent => PackPermission::Motion,
+                    BehaviorNode::PlaySound { .. } => PackPermission::Audio,
+                    BehaviorNode::LedPattern { .. } => PackPermission::Leds,
+                    BehaviorNode::SetState { .. } => PackPermission::State,
+                    _ => return,
+                };
+                permissions.insert(permission);
+            });
+        }
+        
+        for rule in &self.manifest.rules {
+            permissions.insert(PackPermission::Triggers);
+            for action in &rule.actions {
+                match action {
+                    RuleAction::PlayAnimation(_) | RuleAction::LookAtEvent => permissions.insert(PackPermission::Motion),
+                    RuleAction::SetState { .. } => permissions.insert(PackPermission::State),
+                    RuleAction::EmitEvent(_) => permissions.insert(PackPermission::Events),
+                    RuleAction::RunBehavior(_) => false,
+                };
+            }
+        }
+        
+        permissions
+    }
+    
+    /// 检查包内全部文件并加上包ID前缀
+    fn from_files(files: &PackFiles, config: &BehaviorPackConfig) -> Result<Self> {
+        let manifest_bytes = files.get(MANIFEST_FILE)
+            .ok_or_else(|| anyhow::anyhow!("行为包缺少 {}", MANIFEST_FILE))?;
+        let mut manifest: PackManifest = serde_json::from_slice(manifest_bytes)
+            .map_err(|e| anyhow::anyhow!("{} 格式错误: {}", MANIFEST_FILE, e))?;
+        
+        if !valid_name(&manifest.id, false) {
+            return Err(anyhow::anyhow!("包ID无效: '{}'，只能包含小写字母、数字、'-'和'_'", manifest.id));
+        }
+        if manifest.name.is_empty() || manifest.version.is_empty() {
+            return Err(anyhow::anyhow!("行为包名称和版本不能为空"));
+        }
+        
+        let mut animations = BTreeMap::new();
+        let mut sounds = BTreeMap::new();
+        let mut led_patterns = BTreeMap::new();
+        
+        for (path, bytes) in files {
+            if path == MANIFEST_FILE {
+                continue;
+            }
+            
+            let (directory, file) = path.split_once('/')
+                .ok_or_else(|| anyhow::anyhow!("行为包根目录下不允许文件 {}", path))?;
+            let (stem, extension) = file.rsplit_once('.')
+                .ok_or_else(|| anyhow::anyhow!("文件 {} 没有扩展名", path))?;
+            if !valid_name(stem, true) {
+                return Err(anyhow::anyhow!("资源名称无效: {}", path));
+            }
+            
+            match (directory, extension) {
+                ("animations", "json") => {
+                    let json = std::str::from_utf8(bytes).map_err(|_| anyhow::anyhow!("动画 {} 不是UTF-8文本", path))?;
+                    let animation = TrajectoryFile::from_json(json)
+                        .map_err(|e| anyhow::anyhow!("动画 {} 无效: {}", path, e))?;
+                    animations.insert(stem.to_string(), animation);
+                }
+                ("sounds", extension) if SOUND_EXTENSIONS.contains(&extension) => {
+                    check_audio(path, bytes)?;
+                    if sounds.insert(stem.to_string(), path.clone()).is_some() {
+                        return Err(anyhow::anyhow!("声音 {} 重复", stem));
+                    }
+                }
+                ("leds", "json") => {
+                    let pattern: LedPattern = serde_json::from_slice(bytes)
+                        .map_err(|e| anyhow::anyhow!("灯效 {} 格式错误: {}", path, e))?;
+                    pattern.validate().map_err(|e| anyhow::anyhow!("灯效 {} 无效: {}", path, e))?;
+                    led_patterns.insert(stem.to_string(), pattern);
+                }
+                _ => return Err(anyhow::anyhow!("不允许的文件: {}", path)),
+            }
+        }
+        
+        let pack = Self { manifest: manifest.clone(), animations, sounds, led_patterns };
+        pack.check_references(config)?;
+        
+        // 检查通过后再加前缀，对外只暴露"包ID/名称"
+        let id = manifest.id.clone();
+        let qualify = |name: &str| format!("{}/{}", id, name);
+        for tree in manifest.behaviors.values_mut() {
+            tree.qualify(&id);
+        }
+        manifest.behaviors = std::mem::take(&mut manifest.behaviors).into_iter()
+            .map(|(name, tree)| (qualify(&name), tree))
+            .collect();
+        for rule in &mut manifest.rules {
+            rule.name = qualify(&rule.name);
+            for action in &mut rule.actions {
+                if let RuleAction::PlayAnimation(name) | RuleAction::RunBehavior(name) = action {
+                    *name = qualify(name);
+                }
+            }
+        }
+        
+        Ok(Self {
+            manifest,
+            animations: pack.animations.into_iter().map(|(name, a)| (qualify(&name), a)).collect(),
+            sounds: pack.sounds.into_iter().map(|(name, path)| (qualify(&name), path)).collect(),
+            led_patterns: pack.led_patterns.into_iter().map(|(name, p)| (qualify(&name), p)).collect(),
+        })
+    }
+    
+    /// 行为树规模和所有名称引用都在包内
+    fn check_references(&self, config: &BehaviorPackConfig) -> Result<()> {
+        let manifest = &self.manifest;
+        
+        for (name, tree) in &manifest.behaviors {
+            if !valid_name(name, true) {
+                return Err(anyhow::anyhow!("行为树名称无效: '{}'", name));
+            }
+            
+            let (nodes, depth, wait) = tree.measure();
+            if nodes > config.max_tree_nodes || depth > config.max_tree_depth {
+                return Err(anyhow::anyhow!("行为树 '{}' 过大（{} 个节点，深度 {}）", name, nodes, depth));
+            }
+            if wait > config.max_total_wait_ms {
+                return Err(anyhow::anyhow!("行为树 '{}' 的等待总时长 {} ms 超过上限", name, wait));
+            }
+            
+            let mut missing = None;
+            tree.for_each(&mut |node| {
+                let found = match node {
+                    BehaviorNode::PlayAnimation { name } => self.animations.contains_key(name),
+                    BehaviorNode::PlaySound { name } => self.sounds.contains_key(name),
+                    BehaviorNode::LedPattern { name } => self.led_patterns.contains_key(name),
+                    _ => true,
+                };
+                if !found && missing.is_none() {
+                    missing = Some(format!("{:?}", node));
+                }
+            });
+            if let Some(node) = missing {
+                return Err(anyhow::anyhow!("行为树 '{}' 引用了包内不存在的资源: {}", name, node));
+            }
+        }
+        
+        let mut rule_names = HashSet::new();
+        for rule in &manifest.rules {
+            if !valid_name(&rule.name, true) || !rule_names.insert(rule.name.as_str()) {
+                return Err(anyhow::anyhow!("规则名称无效或重复: '{}'", rule.name));
+            }
+            if rule.on.is_empty() || rule.actions.is_empty() {
+                return Err(anyhow::anyhow!("规则 '{}' 缺少触发事件或动作", rule.name));
+            }
+            
+            for action in &rule.actions {
+                let found = match action {
+                    RuleAction::PlayAnimation(name) => self.animations.contains_key(name),
+                    RuleAction::RunBehavior(name) => manifest.behaviors.contains_key(name),
+                    _ => true,
+                };
+                if !found {
+                    return Err(anyhow::anyhow!("规则 '{}' 引用了包内不存在的资源: {:?}", rule.name, action));
+                }
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 包内相对路径：只允许普通路径段，统一为'/'分隔
+fn normalize_entry(path: &Path) -> Result<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(
+                part.to_str().ok_or_else(|| anyhow::anyhow!("条目路径不是UTF-8: {:?}", path))?
+            ),
+            _ => return Err(anyhow::anyhow!("条目路径不安全: {:?}", path)),
+        }
+    }
+    
+    if parts.is_empty() || parts.len() > 2 {
+        return Err(anyhow::anyhow!("条目路径层级不允许: {:?}", path));
+    }
+    Ok(parts.join("/"))
+}
+
+/// 按限制读取zip中的全部文件
+fn read_archive<R: Read + Seek>(reader: R, config: &BehaviorPackConfig) -> Result<PackFiles> {
+    let mut archive = zip::ZipArchive::new(reader)
+        .map_err(|e| anyhow::anyhow!("不是有效的zip文件: {}", e))?;
+    if archive.len() > config.max_entries {
+        return Err(anyhow::anyhow!("行为包条目过多（{} > {}）", archive.len(), config.max_entries));
+    }
+    
+    let mut files = PackFiles::new();
+    let mut total = 0u64;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        
+        let name = entry.name().to_string();
+        // 符号链接在zip中以unix权限位标记，解压后可能指向包外
+        if entry.unix_mode().is_some_and(|mode| mode & 0o170000 == 0o120000) {
+            return Err(anyhow::anyhow!("行为包不允许符号链接: {}", name));
+        }
+        let path = normalize_entry(Path::new(&name))?;
+        
+        let declared = entry.size();
+        if declared > config.max_file_size {
+            return Err(anyhow::anyhow!("文件 {} 超过大小上限", name));
+        }
+        if declared > entry.compressed_size().max(1).saturating_mul(config.max_compression_ratio) {
+            return Err(anyhow::anyhow!("文件 {} 的压缩比异常", name));
+        }
+        
+        // 不信任头部声明的大小，读取时再限制一次
+        let mut bytes = Vec::new();
+        (&mut entry).take(config.max_file_size + 1).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 > config.max_file_size {
+            return Err(anyhow::anyhow!("文件 {} 超过大小上限", name));
+        }
+        
+        total += bytes.len() as u64;
+        if total > config.max_pack_size {
+            return Err(anyhow::anyhow!("行为包解压后超过 {} 字节", config.max_pack_size));
+        }
+        
+        if files.insert(path.clone(), bytes).is_some() {
+            return Err(anyhow::anyhow!("行为包中有重复的文件: {}", path));
+        }
+    }
+    
+    Ok(files)
+}
+
+/// 从安装目录读取文件，同样不跟随符号链接并限制大小
+fn read_directory(root: &Path, config: &BehaviorPackConfig) -> Result<PackFiles> {
+    fn visit(root: &Path, directory: &Path, depth: usize, files: &mut PackFiles,
+             total: &mut u64, config: &BehaviorPackConfig) -> Result<()> {
+        for entry in std::fs::read_dir(directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = std::fs::symlink_metadata(&path)?;
+            
+            if metadata.is_dir() && depth == 0 {
+                visit(root, &path, depth + 1, files, total, config)?;
+                continue;
+            }
+            if !metadata.is_file() {
+                return Err(anyhow::anyhow!("安装目录中有不允许的条目: {}", path.display()));
+            }
+            if metadata.len() > config.max_file_size {
+                return Err(anyhow::anyhow!("文件 {} 超过大小上限", path.display()));
+            }
+            *total += metadata.len();
+            if *total > config.max_pack_size || files.len() >= config.max_entries {
+                return Err(anyhow::anyhow!("行为包超过大小或条目数上限"));
+            }
+            
+            let relative = normalize_entry(path.strip_prefix(root)?)?;
+            files.insert(relative, std::fs::read(&path)?);
+        }
+        Ok(())
+    }
+    
+    let mut files = PackFiles::new();
+    let mut total = 0;
+    visit(root, root, 0, &mut files, &mut total, config)?;
+    Ok(files)
+}
+
+fn open_archive(path: &Path) -> Result<std::io::BufReader<std::fs::File>> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow::anyhow!("无法打开行为包 {}: {}", path.display(), e))?;
+    Ok(std::io::BufReader::new(file))
+}
+
+/// 已安装行为包的启用状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PackState {
+    enabled: bool,
+    installed_at: u64,
+}
+
+/// 已安装的行为包
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    pub description: String,
+    pub enabled: bool,
+    pub installed_at: u64,
+    pub animations: Vec<String>,
+    pub sounds: Vec<String>,
+    pub led_patterns: Vec<String>,
+    pub behaviors: Vec<String>,
+    pub rules: Vec<String>,
+    pub permissions: Vec<PackPermission>,
+}
+
+impl PackInfo {
+    fn new(pack: &BehaviorPack, state: &PackState) -> Self {
+        let manifest = &pack.manifest;
+        Self {
+            id: manifest.id.clone(),
+            name: manifest.name.clone(),
+            version: manifest.version.clone(),
+            author: manifest.author.clone(),
+            description: manifest.description.clone(),
+            enabled: state.enabled,
+            installed_at: state.installed_at,
+            animations: pack.animations.keys().cloned().collect(),
+            sounds: pack.sounds.keys().cloned().collect(),
+            led_patterns: pack.led_patterns.keys().cloned().collect(),
+            behaviors: manifest.behaviors.keys().cloned().collect(),
+            rules: manifest.rules.iter().map(|rule| rule.name.clone()).collect(),
+            permissions: pack.permissions().into_iter().collect(),
+        }
+    }
+}
+
+/// 行为包的安装、启用和加载
+pub struct PackStore {
+    config: BehaviorPackConfig,
+}
+
+impl PackStore {
+    pub fn new(config: BehaviorPackConfig) -> Result<Self> {
+        config.validate()?;
+        std::fs::create_dir_all(&config.directory)?;
+        Ok(Self { config })
+    }
+    
+    fn load_states(&self) -> Result<BTreeMap<String, PackState>> {
+        match std::fs::read_to_string(self.config.directory.join(STATE_FILE)) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+    
+    fn save_states(&self, states: &BTreeMap<String, PackState>) -> Result<()> {
+        let path = self.config.directory.join(STATE_FILE);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(states)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+    
+    fn pack_directory(&self, id: &str) -> Result<PathBuf> {
+        if !valid_name(id, false) {
+            return Err(anyhow::anyhow!("包ID无效: '{}'", id));
+        }
+        Ok(self.config.directory.join(id))
+    }
+    
+    /// 检查并安装zip包；已安装的同ID包被替换，保留其启用状态
+    pub fn install_archive<R: Read + Seek>(&self, reader: R) -> Result<PackInfo> {
+        let files = read_archive(reader, &self.config)?;
+        let pack = BehaviorPack::from_files(&files, &self.config)?;
+        let id = pack.manifest.id.clone();
+        let destination = self.pack_directory(&id)?;
+        
+        // 先解压到临时目录，完整写入后再替换
+        let staging = self.config.directory.join(format!(".staging-{}", id));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        for (path, bytes) in &files {
+            let target = staging.join(path);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(target, bytes)?;
+        }
+        if destination.exists() {
+            std::fs::remove_dir_all(&destination)?;
+        }
+        std::fs::rename(&staging, &destination)?;
+        
+        let mut states = self.load_states()?;
+        let enabled = states.get(&id).is_some_and(|state| state.enabled);
+        let state = PackState { enabled, installed_at: current_timestamp() };
+        states.insert(id.clone(), state.clone());
+        self.save_states(&states)?;
+        
+        info!("已安装行为包 {} {}（{}）", id, pack.manifest.version, if enabled { "已启用" } else { "未启用" });
+        Ok(PackInfo::new(&pack, &state))
+    }
+    
+    pub fn install_file(&self, path: &Path) -> Result<PackInfo> {
+        self.install_archive(open_archive(path)?)
+    }
+    
+    /// 只检查zip包并返回其信息和所需权限，不安装
+    pub fn inspect_archive<R: Read + Seek>(&self, reader: R) -> Result<PackInfo> {
+        let pack = BehaviorPack::from_files(&read_archive(reader, &self.config)?, &self.config)?;
+        Ok(PackInfo::new(&pack, &PackState { enabled: false, installed_at: 0 }))
+    }
+    
+    pub fn inspect_file(&self, path: &Path) -> Result<PackInfo> {
+        self.inspect_archive(open_archive(path)?)
+    }
+    
+    /// 从安装目录加载并重新检查
+    pub fn load(&self, id: &str) -> Result<BehaviorPack> {
+        let directory = self.pack_directory(id)?;
+        if !directory.is_dir() {
+            return Err(anyhow::anyhow!("行为包未安装: {}", id));
+        }
+        
+        let pack = BehaviorPack::from_files(&read_directory(&directory, &self.config)?, &self.config)?;
+        if pack.manifest.id != id {
+            return Err(anyhow::anyhow!("行为包目录 {} 中的包ID为 {}", id, pack.manifest.id));
+        }
+        Ok(pack)
+    }
+    
+    pub fn list(&self) -> Result<Vec<PackInfo>> {
+        let mut packs = Vec::new();
+        for (id, state) in self.load_states()? {
+            match self.load(&id) {
+                Ok(pack) => packs.push(PackInfo::new(&pack, &state)),
+                Err(e) => warn!("跳过无法加载的行为包 {}: {}", id, e),
+            }
+        }
+        Ok(packs)
+    }
+    
+    pub fn set_enabled(&self, id: &str, enabled: bool) -> Result<PackInfo> {
+        let mut states = self.load_states()?;
+        let state = states.get_mut(id).ok_or_else(|| anyhow::anyhow!("行为包未安装: {}", id))?;
+        // 启用前重新检查，安装目录被改动过的包不会生效
+        let pack = self.load(id)?;
+        
+        state.enabled = enabled;
+        let info = PackInfo::new(&pack, state);
+        self.save_states(&states)?;
+        
+        info!("行为包 {} 已{}", id, if enabled { "启用" } else { "禁用" });
+        Ok(info)
+    }
+    
+    pub fn uninstall(&self, id: &str) -> Result<()> {
+        let directory = self.pack_directory(id)?;
+        let mut states = self.load_states()?;
+        if states.remove(id).is_none() {
+            return Err(anyhow::anyhow!("行为包未安装: {}", id));
+        }
+        
+        if directory.exists() {
+            std::fs::remove_dir_all(&directory)?;
+        }
+        self.save_states(&states)?;
+        
+        info!("已卸载行为包 {}", id);
+        Ok(())
+    }
+    
+    /// 所有已启用且检查通过的包
+    pub fn load_enabled(&self) -> Result<Vec<BehaviorPack>> {
+        let mut packs = Vec::new();
+        for (id, state) in self.load_states()? {
+            if !state.enabled {
+                continue;
+            }
+            match self.load(&id) {
+                Ok(pack) => packs.push(pack),
+                Err(e) => warn!("已启用的行为包 {} 加载失败，不生效: {}", id, e),
+            }
+        }
+        Ok(packs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::RobotEvent;
+    use std::io::{Cursor, Write};
+    use zip::write::FileOptions;
+    
+    fn manifest() -> serde_json::Value {
+        serde_json::json!({
+            "id": "greetings",
+            "name": "问候",
+            "version": "1.0.0",
+            "behaviors": {
+                "wave": {
+                    "type": "selector",
+                    "children": [
+                        {
+                            "type": "sequence",
+                            "children": [
+                                { "type": "condition", "condition": { "MinConfidence": 0.8 } },
+                                { "type": "led_pattern", "name": "happy" },
+                                { "type": "play_animation", "name": "wave" },
+                                { "type": "play_sound", "name": "hello" }
+                            ]
+                        },
+                        { "type": "look_at_event" }
+                    ]
+                }
+            },
+            "rules": [
+                { "name": "greet", "on": "FaceDetected", "actions": [{ "RunBehavior": "wave" }] }
+            ]
+        })
+    }
+    
+    fn build_pack(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, bytes) in entries {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(bytes).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+    
+    fn valid_entries() -> Vec<(&'static str, Vec<u8>)> {
+        let mut animation = TrajectoryFile::new("wave", vec!["head_pan".to_string()]);
+        animation.push_frame(0.0, &HashMap::from([("head_pan".to_string(), 0.0)]), None);
+        animation.push_frame(1.0, &HashMap::from([("head_pan".to_string(), 0.5)]), None);
+        let led = serde_json::json!({ "frames": [{ "color": [255, 200, 0], "duration_ms": 200 }] });
+        
+        vec![
+            ("manifest.json", serde_json::to_vec(&manifest()).unwrap()),
+            ("animations/wave.json", animation.to_json().unwrap().into_bytes()),
+            ("sounds/hello.wav", b"RIFF\0\0\0\0WAVEfmt ".to_vec()),
+            ("leds/happy.json", serde_json::to_vec(&led).unwrap()),
+        ]
+    }
+    
+    fn store() -> (PackStore, PathBuf) {
+        let directory = std::env::temp_dir().join(format!("reachy_packs_{}", rand::random::<u64>()));
+        let config = BehaviorPackConfig { directory: directory.clone(), ..BehaviorPackConfig::default() };
+        (PackStore::new(config).unwrap(), directory)
+    }
+    
+    #[test]
+    fn test_install_enable_and_plan() {
+        let (store, directory) = store();
+        assert_eq!(store.inspect_archive(Cursor::new(build_pack(&valid_entries()))).unwrap().id, "greetings");
+        assert!(store.list().unwrap().is_empty());
+        let info = store.install_archive(Cursor::new(build_pack(&valid_entries()))).unwrap();
+        assert_eq!(info.id, "greetings");
+        assert!(!info.enabled);
+        assert_eq!(info.behaviors, vec!["greetings/wave"]);
+        assert_eq!(info.permissions, vec![
+            PackPermission::Motion, PackPermission::Audio, PackPermission::Leds, PackPermission::Triggers,
+        ]);
+        assert!(store.load_enabled().unwrap().is_empty());
+        
+        store.set_enabled("greetings", true).unwrap();
+        let packs = store.load_enabled().unwrap();
+        let pack = &packs[0];
+        assert_eq!(pack.manifest.rules[0].actions, vec![RuleAction::RunBehavior("greetings/wave".to_string())]);
+        
+        // 置信度足够时执行完整序列，否则退到只看向事件
+        let tree = &pack.manifest.behaviors["greetings/wave"];
+        let event = |confidence| EventEnvelope {
+            source: "vision".to_string(),
+            timestamp: 0,
+            event: RobotEvent::FaceDetected { confidence, position: None },
+        };
+        let steps = tree.plan(&event(0.9), &HashMap::new()).unwrap();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[1], BehaviorNode::PlayAnimation { name: "greetings/wave".to_string() });
+        assert_eq!(tree.plan(&event(0.5), &HashMap::new()).unwrap(), vec![BehaviorNode::LookAtEvent]);
+        
+        // 重新安装保留启用状态，卸载后不再列出
+        assert!(store.install_archive(Cursor::new(build_pack(&valid_entries()))).unwrap().enabled);
+        store.uninstall("greetings").unwrap();
+        assert!(store.list().unwrap().is_empty());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    
+    #[test]
+    fn test_sandbox_rejects_unsafe_packs() {
+        let (store, directory) = store();
+        let install = |entries: &[(&str, Vec<u8>)]| store.install_archive(Cursor::new(build_pack(entries)));
+        
+        let mut traversal = valid_entries();
+        traversal.push(("../escape.json", b"{}".to_vec()));
+        assert!(install(&traversal).is_err());
+        
+        let mut script = valid_entries();
+        script.push(("scripts/run.py", b"import os".to_vec()));
+        assert!(install(&script).is_err());
+        
+        // 引用了包内不存在的动画
+        let mut missing = valid_entries();
+        missing.retain(|(name, _)| *name != "animations/wave.json");
+        assert!(install(&missing).is_err());
+        
+        let mut fake_sound = valid_entries();
+        fake_sound[2].1 = b"#!/bin/sh".to_vec();
+        assert!(install(&fake_sound).is_err());
+        
+        // 压缩炸弹：解压后远大于压缩后
+        let config = BehaviorPackConfig { max_file_size: 1024, max_pack_size: 4096, ..BehaviorPackConfig::default() };
+        let mut bomb = valid_entries();
+        bomb.push(("leds/zeros.json", vec![b' '; 64 * 1024]));
+        assert!(read_archive(Cursor::new(build_pack(&bomb)), &config).is_err());
+        
+        assert!(store.list().unwrap().is_empty());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+//! reachy shell：交互式命令行
+//! 
+//! 用法: cargo run --features shell --bin reachy -- [API地址]
+//! API地址默认为 http://127.0.0.1:8000，命令历史保存在 ~/.reachy_history。
+
+use anyhow::Result;
+use reachy_mini_rust::shell::{head_position, ApiRequest, Method, ShellCommand, HELP};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const DEFAULT_API_URL: &str = "http://127.0.0.1:8000";
+
+struct Shell {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Shell {
+    async fn send(&self, request: &ApiRequest) -> Result<Value> {
+        let url = format!("{}{}", self.base_url, request.path);
+        let builder = match request.method {
+            Method::Get => self.client.get(&url),
+            Method::Post => self.client.post(&url),
+        };
+        let builder = match &request.body {
+            Some(body) => builder.json(body),
+            None => builder,
+        };
+        
+        let response = builder.send().await.map_err(|e| anyhow::anyhow!("请求 {} 失败: {}", url, e))?;
+        let status = response.status();
+        let text = response.text().await?;
+        let body = serde_json::from_str(&text).unwrap_or(Value::String(text));
+        if !status.is_success() {
+            // FastAPI的错误信息在detail字段
+            let detail = body.get("detail").cloned().unwrap_or(body);
+            return Err(anyhow::anyhow!("{} 返回 {}: {}", request.path, status, detail));
+        }
+        Ok(body)
+    }
+    
+    async fn run(&self, command: &ShellCommand) -> Result<()> {
+        let head = if command.needs_head_position() {
+            let status = self.send(&ApiRequest { method: Method::Get, path: "/api/robot/status".to_string(), body: None }).await?;
+            Some(head_position(&status)?)
+        } else {
+            None
+        };
+        
+        if let Some(request) = command.to_request(head)? {
+            let response = self.send(&request).await?;
+            println!("{}", command.format_response(&response));
+        }
+        Ok(())
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".reachy_history"))
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<()> {
+    let base_url = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_API_URL.to_string());
+    let shell = Shell {
+        client: reqwest::Client::builder().timeout(Duration::from_secs(10)).build()?,
+        base_url: base_url.trim_end_matches('/').to_string(),
+    };
+    
+    let mut editor = DefaultEditor::new()?;
+    let history = history_path();
+    if let Some(path) = &history {
+        let _ = editor.load_history(path);
+    }
+    
+    println!("Reachy Mini shell，API地址 {}，输入 help 查看命令", shell.base_url);
+    loop {
+        let line = match editor.readline("reachy> ") {
+            Ok(line) => line,
+            // Ctrl-C只取消当前输入
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        
+        let command = match ShellCommand::parse(&line) {
+            Ok(Some(command)) => command,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        let _ = editor.add_history_entry(line.trim());
+        
+        match command {
+            ShellCommand::Quit => break,
+            ShellCommand::Help => println!("{}", HELP),
+            command => {
+                if let Err(e) = shell.run(&command).await {
+                    eprintln!("错误: {}", e);
+                }
+            }
+        }
+    }
+    
+    if let Some(path) = &history {
+        let _ = editor.save_history(path);
+    }
+    Ok(())
+}
@@ -0,0 +1,461 @@
+//! 蓝牙LE控制通道
+//! 
+//! 还没有任何网络时（例如首次开机），手机通过BLE GATT服务读取机器人状态并发送受限的命令：
+//! 急停、播放动画和Wi-Fi配网。命令集是固定的，不能借此下发任意关节运动。
+//!
+//! 命令和响应都是JSON。客户端把命令按MTU分段写入命令特征，以换行结束；
+//! 执行结果写入响应特征并发出通知，通知只携带前 MTU-3 字节，完整响应通过（长）读取获得。
+//! GATT服务器在gatt子模块中，需要启用ble特性并运行BlueZ。
+
+#[cfg(feature = "ble")]
+pub mod gatt;
+
+use crate::common::*;
+use crate::events::{EventBus, RobotEvent};
+use crate::realtime::RealtimeController;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use log::info;
+
+pub const SERVICE_UUID: &str = "8d2a0001-5c6e-4b7a-9f3e-2d1c0b9a8e70";
+/// 状态：可读、可通知，内容为BleStatus JSON
+pub const STATUS_CHARACTERISTIC_UUID: &str = "8d2a0002-5c6e-4b7a-9f3e-2d1c0b9a8e70";
+/// 命令：可写，以换行结束的BleCommand JSON
+pub const COMMAND_CHARACTERISTIC_UUID: &str = "8d2a0003-5c6e-4b7a-9f3e-2d1c0b9a8e70";
+/// 响应：可读、可通知，最近一条命令的执行结果
+pub const RESPONSE_CHARACTERISTIC_UUID: &str = "8d2a0004-5c6e-4b7a-9f3e-2d1c0b9a8e70";
+
+/// ATT属性值的最大长度
+pub const MAX_VALUE_LEN: usize = 512;
+
+/// 蓝牙LE控制通道配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BleConfig {
+    pub enabled: bool,
+    /// 广播中的设备名称
+    pub device_name: String,
+    /// BlueZ适配器
+    pub adapter: String,
+    /// 配网使用的无线网卡
+    pub wifi_interface: String,
+    /// 读写特征需要经过认证的配对（加密链路）
+    pub require_pairing: bool,
+    /// 一条命令（含分段）的最大字节数
+    pub max_command_bytes: usize,
+    pub status_interval_ms: u64,
+    /// Wi-Fi扫描和加入的超时
+    pub wifi_timeout_ms: u64,
+}
+
+impl Default for BleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            device_name: "ReachyMini".to_string(),
+            adapter: "hci0".to_string(),
+            wifi_interface: "wlan0".to_string(),
+            require_pairing: true,
+            max_command_bytes: 1024,
+            status_interval_ms: 1000,
+            wifi_timeout_ms: 30_000,
+        }
+    }
+}
+
+impl ConfigValidation for BleConfig {
+    fn validate(&self) -> Result<()> {
+        if self.device_name.is_empty() || self.device_name.len() > 29 {
+            return Err(anyhow::anyhow!("BLE设备名称长度必须在1-29字节之间"));
+        }
+        
+        if self.adapter.is_empty() || !self.adapter.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(anyhow::anyhow!("BLE适配器名称无效: '{}'", self.adapter));
+        }
+        
+        if !(64..=8192).contains(&self.max_command_bytes) {
+            return Err(anyhow::anyhow!("BLE命令最大字节数必须在64-8192之间"));
+        }
+        
+        if self.status_interval_ms < 100 {
+            return Err(anyhow::anyhow!("BLE状态更新间隔不能小于100ms"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// BLE命令
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum BleCommand {
+    Status,
+    EmergencyStop,
+    PlayAnimation { name: String },
+    WifiScan,
+    WifiJoin {
+        ssid: String,
+        /// WPA密码，开放网络为空
+        #[serde(default)]
+        password: String,
+    },
+}
+
+impl BleCommand {
+    /// 命令名称，用于日志（不记录密码等参数）
+    pub fn name(&self) -> &'static str {
+        match self {
+            BleCommand::Status => "status",
+            BleCommand::EmergencyStop => "emergency_stop",
+            BleCommand::PlayAnimation { .. } => "play_animation",
+            BleCommand::WifiScan => "wifi_scan",
+            BleCommand::WifiJoin { .. } => "wifi_join",
+        }
+    }
+    
+    fn validate(&self) -> Result<()> {
+        match self {
+            BleCommand::PlayAnimation { name }
+                if name.is_empty() || name.len() > 128 || name.chars().any(char::is_control) =>
+            {
+                Err(anyhow::anyhow!("动画名称无效"))
+            }
+            BleCommand::WifiJoin { ssid, .. } if ssid.is_empty() || ssid.len() > 32 => {
+                Err(anyhow::anyhow!("SSID长度必须在1-32字节之间"))
+            }
+            BleCommand::WifiJoin { password, .. }
+                if !password.is_empty() && !(8..=63).contains(&password.chars().count()) =>
+            {
+                Err(anyhow::anyhow!("WPA密码长度必须在8-63个字符之间"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Wi-Fi连接状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WifiStatus {
+    pub connected: bool,
+    pub ssid: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+/// 扫描到的网络
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    /// 信号强度（0-100）
+    pub signal: u8,
+    pub secured: bool,
+}
+
+/// 状态特征的内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BleStatus {
+    pub running: bool,
+    pub emergency_stop: bool,
+    pub wifi: WifiStatus,
+}
+
+/// Wi-Fi配网接口
+#[allow(async_fn_in_trait)]
+pub trait WifiProvisioner {
+    async fn status(&mut self) -> Result<WifiStatus>;
+    /// 按信号强度从强到弱排列
+    async fn scan(&mut self) -> Result<Vec<WifiNetwork>>;
+    async fn join(&mut self, ssid: &str, password: &str) -> Result<WifiStatus>;
+}
+
+/// BLE命令执行器
+#[allow(async_fn_in_trait)]
+pub trait BleCommandExecutor {
+    async fn status(&mut self) -> Result<BleStatus>;
+    async fn execute(&mut self, command: BleCommand) -> Result<serde_json::Value>;
+}
+
+/// 按换行拼接分段写入的命令
+pub struct CommandAssembler {
+    buffer: Vec<u8>,
+    max_len: usize,
+}
+
+impl CommandAssembler {
+    pub fn new(max_len: usize) -> Self {
+        Self { buffer: Vec::new(), max_len }
+    }
+    
+    /// 追加一段写入，收到换行时返回一条完整的命令；超长时丢弃已缓存的内容
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.buffer.extend_from_slice(chunk);
+        
+        if let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let rest = self.buffer.split_off(end + 1);
+            let mut line = std::mem::replace(&mut self.buffer, rest);
+            line.pop();
+            return Ok(Some(line));
+        }
+        
+        if self.buffer.len() > self.max_len {
+            self.buffer.clear();
+            return Err(anyhow::anyhow!("命令超过 {} 字节", self.max_len));
+        }
+        Ok(None)
+    }
+}
+
+/// 编码响应特征的内容，保证不超过ATT属性长度
+pub fn encode_response(result: Result<serde_json::Value>) -> Vec<u8> {
+    let value = match result {
+        Ok(data) => serde_json::json!({ "ok": true, "data": data }),
+        Err(e) => serde_json::json!({ "ok": false, "error": e.to_string() }),
+    };
+    
+    match serde_json::to_vec(&value) {
+        Ok(bytes) if bytes.len() <= MAX_VALUE_LEN => bytes,
+        _ => serde_json::json!({ "ok": false, "error": "响应过长" }).to_string().into_bytes(),
+    }
+}
+
+/// 解析、检查并执行一条命令，返回响应特征的内容
+pub async fn handle_command<E: BleCommandExecutor>(executor: &mut E, line: &[u8]) -> Vec<u8> {
+    let command: BleCommand = match serde_json::from_slice(line) {
+        Ok(command) => command,
+        Err(e) => return encode_response(Err(anyhow::anyhow!("命令格式错误: {}", e))),
+    };
+    
+    if let Err(e) = command.validate() {
+        return encode_response(Err(e));
+    }
+    
+    info!("BLE命令: {}", command.name());
+    encode_response(executor.execute(command).await)
+}
+
+/// 保留信号最强的网络，使响应不超过ATT属性长度
+fn fit_networks(mut networks: Vec<WifiNetwork>) -> Result<serde_json::Value> {
+    loop {
+        let value = serde_json::to_value(&networks)?;
+        // 预留响应外层的 {"ok":true,"data":...}
+        if serde_json::to_vec(&value)?.len() + 24 <= MAX_VALUE_LEN || networks.is_empty() {
+            return Ok(value);
+        }
+        networks.pop();
+    }
+}
+
+/// 系统命令执行器：急停交给实时控制器，动画通过事件总线发布
+pub struct SystemBleExecutor<'a, W> {
+    pub realtime: &'a RealtimeController,
+    pub bus: &'a EventBus,
+    pub wifi: W,
+}
+
+impl<W: WifiProvisioner> BleCommandExecutor for SystemBleExecutor<'_, W> {
+    async fn status(&mut self) -> Result<BleStatus> {
+        let realtime = self.realtime.get_status().await?;
+        let wifi = self.wifi.status().await.unwrap_or_default();
+        
+        Ok(BleStatus {
+            running: realtime.is_running,
+            emergency_stop: realtime.emergency_stop,
+            wifi,
+        })
+    }
+    
+    async fn execute(&mut self, command: BleCommand) -> Result<serde_json::Value> {
+        match command {
+            BleCommand::Status => Ok(serde_json::to_value(self.status().await?)?),
+            BleCommand::EmergencyStop => {
+                self.realtime.set_emergency_stop(true).await?;
+                self.bus.publish("ble", RobotEvent::EmergencyStop);
+                Ok(serde_json::json!({ "emergency_stop": true }))
+            }
+            BleCommand::PlayAnimation { name } => {
+                let data = serde_json::json!({ "animation": name });
+                self.bus.publish("ble", RobotEvent::Custom { name: "ble.animation".to_string(), data: data.clone() });
+                Ok(data)
+            }
+            BleCommand::WifiScan => fit_networks(self.wifi.scan().await?),
+            BleCommand::WifiJoin { ssid, password } => Ok(serde_json::to_value(self.wifi.join(&ssid, &password).await?)?),
+        }
+    }
+}
+
+/// 拆分nmcli -t的输出行，字段中的':'和'\'以反斜杠转义
+fn split_nmcli_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// 通过nmcli（NetworkManager）配网
+pub struct NmcliWifi {
+    pub interface: String,
+    pub timeout: Duration,
+}
+
+impl NmcliWifi {
+    pub fn new(config: &BleConfig) -> Self {
+        Self {
+            interface: config.wifi_interface.clone(),
+            timeout: Duration::from_millis(config.wifi_timeout_ms),
+        }
+    }
+    
+    async fn nmcli(&self, args: &[&str], stdin: Option<&str>) -> Result<String> {
+        let mut child = tokio::process::Command::new("nmcli")
+            .args(args)
+            .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("无法执行nmcli: {}", e))?;
+        
+        if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            pipe.write_all(input.as_bytes()).await?;
+        }
+        
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output()).await
+            .map_err(|_| anyhow::anyhow!("nmcli {} 超时", args.join(" ")))??;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("nmcli执行失败: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl WifiProvisioner for NmcliWifi {
+    async fn status(&mut self) -> Result<WifiStatus> {
+        let output = self.nmcli(&["-t", "-f", "GENERAL.STATE,GENERAL.CONNECTION,IP4.ADDRESS",
+                                  "device", "show", &self.interface], None).await?;
+        
+        let mut status = WifiStatus::default();
+        for line in output.lines() {
+            let Some((key, value)) = line.split_once(':') else { continue };
+            match key {
+                "GENERAL.STATE" => status.connected = value.starts_with("100"),
+                "GENERAL.CONNECTION" if !value.is_empty() && value != "--" => status.ssid = Some(value.to_string()),
+                "IP4.ADDRESS[1]" => status.ip_address = value.split('/').next().map(str::to_string),
+                _ => {}
+            }
+        }
+        if !status.connected {
+            status.ssid = None;
+        }
+        Ok(status)
+    }
+    
+    async fn scan(&mut self) -> Result<Vec<WifiNetwork>> {
+        let output = self.nmcli(&["-t", "-f", "SSID,SIGNAL,SECURITY", "device", "wifi", "list",
+                                  "ifname", &self.interface, "--rescan", "yes"], None).await?;
+        
+        let mut networks: Vec<WifiNetwork> = Vec::new();
+        for fields in output.lines().map(split_nmcli_fields) {
+            let [ssid, signal, security] = &fields[..] else { continue };
+            if ssid.is_empty() || networks.iter().any(|n| &n.ssid == ssid) {
+                continue;
+            }
+            networks.push(WifiNetwork {
+                ssid: ssid.clone(),
+                signal: signal.parse().unwrap_or(0),
+                secured: !security.is_empty() && security != "--",
+            });
+        }
+        networks.sort_by_key(|network| std::cmp::Reverse(network.signal));
+        Ok(networks)
+    }
+    
+    async fn join(&mut self, ssid: &str, password: &str) -> Result<WifiStatus> {
+        let wait = self.timeout.as_secs().to_string();
+        if password.is_empty() {
+            self.nmcli(&["--wait", &wait, "device", "wifi", "connect", ssid, "ifname", &self.interface], None).await?;
+        } else {
+            // 密码经标准输入传给nmcli，不出现在进程参数中
+            self.nmcli(&["--wait", &wait, "--ask", "device", "wifi", "connect", ssid, "ifname", &self.interface],
+                       Some(&format!("{}\n", password))).await?;
+        }
+        
+        info!("已通过BLE配网加入 {}", ssid);
+        self.status().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[derive(Default)]
+    struct FakeExecutor {
+        stopped: bool,
+        joined: Option<(String, String)>,
+    }
+    
+    impl BleCommandExecutor for FakeExecutor {
+        async fn status(&mut self) -> Result<BleStatus> {
+            Ok(BleStatus { running: true, emergency_stop: self.stopped, wifi: WifiStatus::default() })
+        }
+        
+        async fn execute(&mut self, command: BleCommand) -> Result<serde_json::Value> {
+            match command {
+                BleCommand::EmergencyStop => self.stopped = true,
+                BleCommand::WifiJoin { ssid, password } => self.joined = Some((ssid, password)),
+                BleCommand::WifiScan => {
+                    let networks = (0..40).map(|i| WifiNetwork { ssid: format!("network-{:02}", i), signal: 90, secured: true });
+                    return fit_networks(networks.collect());
+                }
+                _ => {}
+            }
+            Ok(serde_json::Value::Null)
+        }
+    }
+    
+    fn response(bytes: &[u8]) -> serde_json::Value {
+        serde_json::from_slice(bytes).unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_assembled_commands() {
+        let mut executor = FakeExecutor::default();
+        let mut assembler = CommandAssembler::new(64);
+        
+        // 分两段写入，第二段同时带上下一条命令的开头
+        assert_eq!(assembler.push(br#"{"cmd":"emer"#).unwrap(), None);
+        let line = assembler.push(b"gency_stop\"}\n{\"cmd\"").unwrap().unwrap();
+        assert_eq!(response(&handle_command(&mut executor, &line).await)["ok"], true);
+        assert!(executor.stopped);
+        
+        let line = assembler.push(br#":"wifi_join","ssid":"lab","password":"short"}"#).unwrap();
+        assert_eq!(line, None, "超过64字节之前没有换行");
+        assert!(assembler.push(&[b' '; 64]).is_err());
+        
+        let join = br#"{"cmd":"wifi_join","ssid":"lab","password":"short"}"#;
+        let result = response(&handle_command(&mut executor, join).await);
+        assert_eq!(result["ok"], false);
+        assert!(executor.joined.is_none());
+        
+        let result = response(&handle_command(&mut executor, br#"{"cmd":"move_joint","joint":"head_pan"}"#).await);
+        assert_eq!(result["ok"], false);
+        
+        let scan = handle_command(&mut executor, br#"{"cmd":"wifi_scan"}"#).await;
+        assert!(scan.len() <= MAX_VALUE_LEN);
+        let networks = response(&scan)["data"].as_array().unwrap().len();
+        assert!(networks > 0 && networks < 40);
+    }
+    
+    #[test]
+    fn test_split_nmcli_fields() {
+        assert_eq!(split_nmcli_fields(r"My\:Net:80:WPA2"), vec!["My:Net", "80", "WPA2"]);
+        assert_eq!(split_nmcli_fields("::"), vec!["", "", ""]);
+    }
+}
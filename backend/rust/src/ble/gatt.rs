@@ -0,0 +1,266 @@
+//! BlueZ GATT服务器
+//! 
+//! 通过D-Bus向BlueZ注册GATT应用（org.bluez.GattManager1）和LE广播（org.bluez.LEAdvertisingManager1）。
+//! 命令特征收到的写入经通道交给调用方任务中的执行器处理，执行器不需要是Send；
+//! 状态按配置的间隔刷新，内容变化时才发出通知。
+
+use super::*;
+use crate::shutdown::CancellationToken;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use zbus::names::InterfaceName;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{fdo, interface, Connection};
+use log::{debug, warn};
+
+const APP_PATH: &str = "/org/reachymini/ble";
+const SERVICE_PATH: &str = "/org/reachymini/ble/service0";
+const STATUS_PATH: &str = "/org/reachymini/ble/service0/char0";
+const COMMAND_PATH: &str = "/org/reachymini/ble/service0/char1";
+const RESPONSE_PATH: &str = "/org/reachymini/ble/service0/char2";
+const ADVERTISEMENT_PATH: &str = "/org/reachymini/ble/advertisement0";
+
+/// 未处理的命令写入上限，超过时拒绝写入
+const COMMAND_QUEUE: usize = 32;
+
+fn service_path() -> OwnedObjectPath {
+    ObjectPath::from_static_str_unchecked(SERVICE_PATH).into()
+}
+
+struct GattService;
+
+#[interface(name = "org.bluez.GattService1")]
+impl GattService {
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> String {
+        SERVICE_UUID.to_string()
+    }
+    
+    #[zbus(property)]
+    fn primary(&self) -> bool {
+        true
+    }
+}
+
+/// 可读、可通知的特征（状态和响应）
+struct NotifyCharacteristic {
+    uuid: &'static str,
+    flags: Vec<String>,
+    value: Vec<u8>,
+    notifying: bool,
+}
+
+#[interface(name = "org.bluez.GattCharacteristic1")]
+impl NotifyCharacteristic {
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> String {
+        self.uuid.to_string()
+    }
+    
+    #[zbus(property)]
+    fn service(&self) -> OwnedObjectPath {
+        service_path()
+    }
+    
+    #[zbus(property)]
+    fn flags(&self) -> Vec<String> {
+        self.flags.clone()
+    }
+    
+    #[zbus(property)]
+    fn value(&self) -> Vec<u8> {
+        self.value.clone()
+    }
+    
+    #[zbus(property)]
+    fn notifying(&self) -> bool {
+        self.notifying
+    }
+    
+    /// 长读取时BlueZ在options中给出偏移
+    fn read_value(&self, options: HashMap<String, OwnedValue>) -> fdo::Result<Vec<u8>> {
+        let offset = options.get("offset")
+            .and_then(|offset| u16::try_from(offset).ok())
+            .unwrap_or(0) as usize;
+        if offset > self.value.len() {
+            return Err(fdo::Error::InvalidArgs("读取偏移超出范围".to_string()));
+        }
+        Ok(self.value[offset..].to_vec())
+    }
+    
+    fn start_notify(&mut self) {
+        self.notifying = true;
+    }
+    
+    fn stop_notify(&mut self) {
+        self.notifying = false;
+    }
+}
+
+/// 命令特征，只写
+struct CommandCharacteristic {
+    flags: Vec<String>,
+    sender: mpsc::Sender<Vec<u8>>,
+}
+
+#[interface(name = "org.bluez.GattCharacteristic1")]
+impl CommandCharacteristic {
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> String {
+        COMMAND_CHARACTERISTIC_UUID.to_string()
+    }
+    
+    #[zbus(property)]
+    fn service(&self) -> OwnedObjectPath {
+        service_path()
+    }
+    
+    #[zbus(property)]
+    fn flags(&self) -> Vec<String> {
+        self.flags.clone()
+    }
+    
+    fn write_value(&self, value: Vec<u8>, _options: HashMap<String, OwnedValue>) -> fdo::Result<()> {
+        if value.len() > MAX_VALUE_LEN {
+            return Err(fdo::Error::InvalidArgs("写入超过512字节".to_string()));
+        }
+        self.sender.try_send(value)
+            .map_err(|_| fdo::Error::LimitsExceeded("命令队列已满".to_string()))
+    }
+}
+
+struct Advertisement {
+    local_name: String,
+}
+
+#[interface(name = "org.bluez.LEAdvertisement1")]
+impl Advertisement {
+    #[zbus(property, name = "Type")]
+    fn advertisement_type(&self) -> String {
+        "peripheral".to_string()
+    }
+    
+    #[zbus(property, name = "ServiceUUIDs")]
+    fn service_uuids(&self) -> Vec<String> {
+        vec![SERVICE_UUID.to_string()]
+    }
+    
+    #[zbus(property)]
+    fn local_name(&self) -> String {
+        self.local_name.clone()
+    }
+    
+    /// BlueZ移除广播时调用
+    fn release(&self) {
+        debug!("BLE广播已被BlueZ释放");
+    }
+}
+
+/// 更新特征值并通知已订阅的客户端
+async fn set_value(connection: &Connection, path: &str, value: Vec<u8>, always_notify: bool) -> Result<()> {
+    let characteristic = connection.object_server().interface::<_, NotifyCharacteristic>(path).await?;
+    let mut inner = characteristic.get_mut().await;
+    if inner.value == value && !always_notify {
+        return Ok(());
+    }
+    
+    inner.value = value;
+    inner.value_changed(characteristic.signal_context()).await?;
+    Ok(())
+}
+
+async fn call_bluez(connection: &Connection, path: &str, interface: &'static str, method: &str, object: &str) -> Result<()> {
+    let proxy = zbus::Proxy::new(connection, "org.bluez", path.to_string(), interface).await?;
+    let options: HashMap<&str, Value> = HashMap::new();
+    proxy.call::<_, _, ()>(method, &(ObjectPath::try_from(object)?, options)).await
+        .map_err(|e| anyhow::anyhow!("{}.{} 失败: {}", interface, method, e))
+}
+
+/// 注册GATT服务和广播，处理命令直到shutdown被取消
+pub async fn run_gatt_server<E: BleCommandExecutor>(
+    config: &BleConfig,
+    executor: &mut E,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    config.validate()?;
+    
+    let connection = Connection::system().await
+        .map_err(|e| anyhow::anyhow!("无法连接系统D-Bus: {}", e))?;
+    let adapter = format!("/org/bluez/{}", config.adapter);
+    
+    let (read, write) = if config.require_pairing {
+        ("encrypt-authenticated-read", "encrypt-authenticated-write")
+    } else {
+        ("read", "write")
+    };
+    let notify_flags = vec![read.to_string(), "notify".to_string()];
+    let (sender, mut commands) = mpsc::channel(COMMAND_QUEUE);
+    
+    let server = connection.object_server();
+    server.at(APP_PATH, fdo::ObjectManager).await?;
+    server.at(SERVICE_PATH, GattService).await?;
+    server.at(STATUS_PATH, NotifyCharacteristic {
+        uuid: STATUS_CHARACTERISTIC_UUID,
+        flags: notify_flags.clone(),
+        value: Vec::new(),
+        notifying: false,
+    }).await?;
+    server.at(COMMAND_PATH, CommandCharacteristic { flags: vec![write.to_string()], sender }).await?;
+    server.at(RESPONSE_PATH, NotifyCharacteristic {
+        uuid: RESPONSE_CHARACTERISTIC_UUID,
+        flags: notify_flags,
+        value: Vec::new(),
+        notifying: false,
+    }).await?;
+    server.at(ADVERTISEMENT_PATH, Advertisement { local_name: config.device_name.clone() }).await?;
+    
+    let adapter_properties = fdo::PropertiesProxy::builder(&connection)
+        .destination("org.bluez")?
+        .path(adapter.clone())?
+        .build().await?;
+    adapter_properties.set(
+        InterfaceName::from_static_str_unchecked("org.bluez.Adapter1"), "Powered", &Value::from(true),
+    ).await.map_err(|e| anyhow::anyhow!("无法打开蓝牙适配器 {}: {}", config.adapter, e))?;
+    
+    call_bluez(&connection, &adapter, "org.bluez.GattManager1", "RegisterApplication", APP_PATH).await?;
+    call_bluez(&connection, &adapter, "org.bluez.LEAdvertisingManager1", "RegisterAdvertisement", ADVERTISEMENT_PATH).await?;
+    info!("BLE控制通道已启动: {}（{}）", config.device_name, config.adapter);
+    
+    let mut assembler = CommandAssembler::new(config.max_command_bytes);
+    let mut status_interval = tokio::time::interval(Duration::from_millis(config.status_interval_ms));
+    status_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            Some(chunk) = commands.recv() => {
+                let response = match assembler.push(&chunk) {
+                    Ok(Some(line)) => handle_command(executor, &line).await,
+                    Ok(None) => continue,
+                    Err(e) => encode_response(Err(e)),
+                };
+                // 相同的响应也要通知，客户端据此知道命令已处理
+                set_value(&connection, RESPONSE_PATH, response, true).await?;
+            }
+            _ = status_interval.tick() => {
+                match executor.status().await {
+                    Ok(status) => set_value(&connection, STATUS_PATH, serde_json::to_vec(&status)?, false).await?,
+                    Err(e) => debug!("获取BLE状态失败: {}", e),
+                }
+            }
+        }
+    }
+    
+    for (interface, method, object) in [
+        ("org.bluez.LEAdvertisingManager1", "UnregisterAdvertisement", ADVERTISEMENT_PATH),
+        ("org.bluez.GattManager1", "UnregisterApplication", APP_PATH),
+    ] {
+        let proxy = zbus::Proxy::new(&connection, "org.bluez", adapter.clone(), interface).await?;
+        if let Err(e) = proxy.call::<_, _, ()>(method, &(ObjectPath::try_from(object)?,)).await {
+            warn!("{}.{} 失败: {}", interface, method, e);
+        }
+    }
+    
+    info!("BLE控制通道已停止");
+    Ok(())
+}
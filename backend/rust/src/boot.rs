@@ -0,0 +1,326 @@
+//! 启动流程模块
+//! 
+//! 上电后机器人执行的一系列步骤（自检 → 回到初始姿态 → 就绪灯效 → 问候动画等）
+//! 由配置定义，集成方无需修改start()即可定制开机行为。
+//! 动画、灯效和行为树只以名称引用，通过事件总线交给对应模块执行。
+
+use crate::common::*;
+use crate::events::{EventBus, RobotEvent};
+use crate::realtime::RealtimeController;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+use log::{info, warn};
+
+/// 启动步骤
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BootStep {
+    /// 检查控制器运行且未处于急停
+    SelfTest,
+    /// 所有关节移动到初始姿态，positions可覆盖个别关节（弧度）
+    HomePose {
+        duration_ms: u64,
+        #[serde(default)]
+        positions: HashMap<String, f64>,
+    },
+    /// 播放灯效
+    LedPattern(String),
+    /// 播放动画
+    PlayAnimation(String),
+    /// 运行指定的行为树
+    Behavior(String),
+    /// 等待（毫秒）
+    Wait(u64),
+}
+
+impl BootStep {
+    /// 步骤名称，用于日志和报告
+    pub fn name(&self) -> String {
+        match self {
+            BootStep::SelfTest => "SelfTest".to_string(),
+            BootStep::HomePose { .. } => "HomePose".to_string(),
+            BootStep::LedPattern(pattern) => format!("LedPattern({})", pattern),
+            BootStep::PlayAnimation(animation) => format!("PlayAnimation({})", animation),
+            BootStep::Behavior(tree) => format!("Behavior({})", tree),
+            BootStep::Wait(ms) => format!("Wait({}ms)", ms),
+        }
+    }
+}
+
+/// 启动流程中的一步
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootStepConfig {
+    pub step: BootStep,
+    /// 可选步骤失败时继续执行后续步骤
+    #[serde(default)]
+    pub optional: bool,
+    /// 覆盖默认的步骤超时（毫秒）
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+impl BootStepConfig {
+    pub fn required(step: BootStep) -> Self {
+        Self { step, optional: false, timeout_ms: None }
+    }
+    
+    pub fn optional(step: BootStep) -> Self {
+        Self { step, optional: true, timeout_ms: None }
+    }
+}
+
+/// 启动流程配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootSequenceConfig {
+    pub enabled: bool,
+    pub steps: Vec<BootStepConfig>,
+    pub step_timeout_ms: u64,
+}
+
+impl Default for BootSequenceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            steps: vec![
+                BootStepConfig::required(BootStep::SelfTest),
+                BootStepConfig::required(BootStep::HomePose { duration_ms: 2000, positions: HashMap::new() }),
+                BootStepConfig::optional(BootStep::LedPattern("ready".to_string())),
+                BootStepConfig::optional(BootStep::PlayAnimation("greet".to_string())),
+            ],
+            step_timeout_ms: 10000,
+        }
+    }
+}
+
+impl ConfigValidation for BootSequenceConfig {
+    fn validate(&self) -> Result<()> {
+        if self.step_timeout_ms == 0 {
+            return Err(anyhow::anyhow!("启动步骤超时必须大于0"));
+        }
+        
+        for config in &self.steps {
+            match &config.step {
+                BootStep::LedPattern(name) | BootStep::PlayAnimation(name) | BootStep::Behavior(name)
+                    if name.is_empty() => {
+                    return Err(anyhow::anyhow!("启动步骤 {} 的名称不能为空", config.step.name()));
+                }
+                _ => {}
+            }
+            
+            let step_timeout = config.timeout_ms.unwrap_or(self.step_timeout_ms);
+            if step_timeout == 0 {
+                return Err(anyhow::anyhow!("启动步骤 {} 的超时必须大于0", config.step.name()));
+            }
+            
+            if let BootStep::HomePose { duration_ms, .. } = &config.step {
+                if *duration_ms >= step_timeout {
+                    return Err(anyhow::anyhow!("回到初始姿态的时长必须小于步骤超时"));
+                }
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 步骤执行结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BootStepOutcome {
+    Succeeded,
+    Failed(String),
+    TimedOut,
+    /// 前面的必需步骤失败，未执行
+    Skipped,
+}
+
+/// 单步执行报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootStepReport {
+    pub step: String,
+    pub outcome: BootStepOutcome,
+    pub duration_ms: u64,
+}
+
+/// 启动流程报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootReport {
+    /// 所有必需步骤都成功
+    pub success: bool,
+    pub steps: Vec<BootStepReport>,
+    pub started_at: u64,
+    pub finished_at: u64,
+}
+
+/// 启动步骤执行器
+#[allow(async_fn_in_trait)]
+pub trait BootStepExecutor {
+    async fn execute(&mut self, step: &BootStep) -> Result<()>;
+}
+
+/// 按配置执行启动流程，必需步骤失败后跳过剩余步骤
+pub async fn run_boot_sequence<E: BootStepExecutor>(config: &BootSequenceConfig, executor: &mut E) -> BootReport {
+    let started_at = current_timestamp();
+    let mut steps = Vec::with_capacity(config.steps.len());
+    let mut success = true;
+    
+    if !config.enabled {
+        info!("启动流程已禁用");
+        return BootReport { success, steps, started_at, finished_at: started_at };
+    }
+    
+    for step_config in &config.steps {
+        let name = step_config.step.name();
+        
+        if !success {
+            steps.push(BootStepReport { step: name, outcome: BootStepOutcome::Skipped, duration_ms: 0 });
+            continue;
+        }
+        
+        info!("启动步骤: {}", name);
+        let start = Instant::now();
+        let step_timeout = Duration::from_millis(step_config.timeout_ms.unwrap_or(config.step_timeout_ms));
+        
+        let outcome = match timeout(step_timeout, executor.execute(&step_config.step)).await {
+            Ok(Ok(())) => BootStepOutcome::Succeeded,
+            Ok(Err(e)) => BootStepOutcome::Failed(e.to_string()),
+            Err(_) => BootStepOutcome::TimedOut,
+        };
+        
+        if outcome != BootStepOutcome::Succeeded {
+            if step_config.optional {
+                warn!("可选启动步骤 {} 未成功: {:?}", name, outcome);
+            } else {
+                warn!("启动步骤 {} 失败: {:?}，跳过剩余步骤", name, outcome);
+                success = false;
+            }
+        }
+        
+        steps.push(BootStepReport {
+            step: name,
+            outcome,
+            duration_ms: start.elapsed().as_millis() as u64,
+        });
+    }
+    
+    BootReport {
+        success,
+        steps,
+        started_at,
+        finished_at: current_timestamp(),
+    }
+}
+
+/// 系统启动执行器：运动步骤交给实时控制器，灯效、动画和行为树通过事件总线发布
+pub struct SystemBootExecutor<'a> {
+    pub realtime: &'a RealtimeController,
+    pub bus: &'a EventBus,
+}
+
+impl SystemBootExecutor<'_> {
+    fn publish(&self, name: &str, data: serde_json::Value) {
+        self.bus.publish("boot", RobotEvent::Custom { name: name.to_string(), data });
+    }
+}
+
+impl BootStepExecutor for SystemBootExecutor<'_> {
+    async fn execute(&mut self, step: &BootStep) -> Result<()> {
+        match step {
+            BootStep::SelfTest => {
+                if !self.realtime.is_running().await {
+                    return Err(anyhow::anyhow!("实时控制器未运行"));
+                }
+                
+                if self.realtime.get_status().await?.emergency_stop {
+                    return Err(anyhow::anyhow!("紧急停止处于激活状态"));
+                }
+                
+                self.publish("boot.self_test", serde_json::json!({ "passed": true }));
+            }
+            BootStep::HomePose { duration_ms, positions } => {
+                self.realtime.move_to_home(*duration_ms as f64 / 1000.0, positions).await?;
+                tokio::time::sleep(Duration::from_millis(*duration_ms)).await;
+            }
+            BootStep::LedPattern(pattern) => {
+                self.publish("boot.led", serde_json::json!({ "pattern": pattern }));
+            }
+            BootStep::PlayAnimation(animation) => {
+                self.publish("boot.animation", serde_json::json!({ "animation": animation }));
+            }
+            BootStep::Behavior(tree) => {
+                self.publish("boot.behavior", serde_json::json!({ "tree": tree }));
+            }
+            BootStep::Wait(ms) => {
+                tokio::time::sleep(Duration::from_millis(*ms)).await;
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 记录执行过的步骤，指定的步骤返回错误
+    struct RecordingExecutor {
+        executed: Vec<String>,
+        failing: Vec<String>,
+    }
+    
+    impl BootStepExecutor for RecordingExecutor {
+        async fn execute(&mut self, step: &BootStep) -> Result<()> {
+            let name = step.name();
+            self.executed.push(name.clone());
+            
+            if let BootStep::Wait(ms) = step {
+                tokio::time::sleep(Duration::from_millis(*ms)).await;
+            }
+            
+            if self.failing.contains(&name) {
+                Err(anyhow::anyhow!("模拟失败"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_optional_and_required_failures() {
+        let config = BootSequenceConfig::default();
+        assert!(config.validate().is_ok());
+        
+        // 可选的灯效失败不影响后续步骤
+        let mut executor = RecordingExecutor { executed: Vec::new(), failing: vec!["LedPattern(ready)".to_string()] };
+        let report = run_boot_sequence(&config, &mut executor).await;
+        assert!(report.success);
+        assert_eq!(executor.executed.len(), 4);
+        
+        // 自检失败后跳过剩余步骤
+        let mut executor = RecordingExecutor { executed: Vec::new(), failing: vec!["SelfTest".to_string()] };
+        let report = run_boot_sequence(&config, &mut executor).await;
+        assert!(!report.success);
+        assert_eq!(executor.executed, vec!["SelfTest".to_string()]);
+        assert!(report.steps[1..].iter().all(|s| s.outcome == BootStepOutcome::Skipped));
+    }
+    
+    #[tokio::test]
+    async fn test_step_timeout() {
+        let config = BootSequenceConfig {
+            enabled: true,
+            steps: vec![
+                BootStepConfig { step: BootStep::Wait(500), optional: false, timeout_ms: Some(20) },
+                BootStepConfig::required(BootStep::Behavior("wave".to_string())),
+            ],
+            step_timeout_ms: 1000,
+        };
+        
+        let mut executor = RecordingExecutor { executed: Vec::new(), failing: Vec::new() };
+        let report = run_boot_sequence(&config, &mut executor).await;
+        assert!(!report.success);
+        assert_eq!(report.steps[0].outcome, BootStepOutcome::TimedOut);
+        assert_eq!(report.steps[1].outcome, BootStepOutcome::Skipped);
+    }
+}
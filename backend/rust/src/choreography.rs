@@ -0,0 +1,246 @@
+//! 多机器人编舞模块
+//! 
+//! 编舞文件在共享时间轴上为每台机器人安排一条轨道，轨道由若干提示点组成，
+//! 每个提示点在指定时刻开始播放一段动画（轨迹文件，见trajectory_file模块）。
+//! 演出由一个提示开始：提示中的开始时间是参考时钟（见time_sync模块）的时间戳，
+//! 各机器人把它换算成本地时间后等待开始，因此时钟已同步的机器人动作一致。
+//!
+//! JSON格式：
+//!
+//! ```json
+//! {
+//!   "format": "reachy-mini-choreography",
+//!   "version": 1,
+//!   "name": "finale",
+//!   "animations": {
+//!     "wave": { "format": "reachy-mini-trajectory", "version": 1, "joints": ["head_pan"], "frames": [] }
+//!   },
+//!   "tracks": [
+//!     { "robot": "Reachy Left", "cues": [{ "at": 0.0, "animation": "wave" }] },
+//!     { "robot": "*", "cues": [{ "at": 1.5, "animation": "wave" }] }
+//!   ]
+//! }
+//! ```
+//!
+//! 轨道的robot按UUID、名称的顺序匹配机器人，都不匹配时使用`*`轨道。
+//! 同一轨道上的提示点按时间排列，前一段动画结束之前不能开始下一段。
+
+use crate::protocol::RobotIdentity;
+use crate::time_sync::TimeSync;
+use crate::trajectory_file::TrajectoryFile;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// 文件格式标识
+pub const CHOREOGRAPHY_FORMAT: &str = "reachy-mini-choreography";
+/// 当前格式版本
+pub const CHOREOGRAPHY_VERSION: u32 = 1;
+/// 匹配其他所有机器人的轨道
+pub const ANY_ROBOT: &str = "*";
+
+/// 提示点：演出开始后at秒播放一段动画
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cue {
+    pub at: f64,
+    pub animation: String,
+}
+
+/// 一台机器人的轨道
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Track {
+    /// 机器人UUID、名称或`*`
+    pub robot: String,
+    pub cues: Vec<Cue>,
+}
+
+/// 编舞文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Choreography {
+    pub format: String,
+    pub version: u32,
+    #[serde(default)]
+    pub name: String,
+    pub animations: BTreeMap<String, TrajectoryFile>,
+    pub tracks: Vec<Track>,
+}
+
+/// 演出提示：按参考时钟在start_at（毫秒）开始
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ShowCue {
+    pub start_at: u64,
+}
+
+impl ShowCue {
+    /// 本地时钟上的开始时间
+    pub fn local_start(&self, time_sync: &TimeSync) -> u64 {
+        time_sync.to_local(self.start_at)
+    }
+}
+
+impl Choreography {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let choreography: Self = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("编舞文件格式错误: {}", e))?;
+        choreography.validate()?;
+        Ok(choreography)
+    }
+    
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+    
+    /// 检查格式、动画、轨道和提示点时间
+    pub fn validate(&self) -> Result<()> {
+        if self.format != CHOREOGRAPHY_FORMAT {
+            return Err(anyhow::anyhow!("不支持的编舞格式: {}", self.format));
+        }
+        
+        if self.version > CHOREOGRAPHY_VERSION {
+            return Err(anyhow::anyhow!("编舞格式版本 {} 高于支持的版本 {}", self.version, CHOREOGRAPHY_VERSION));
+        }
+        
+        for (name, animation) in &self.animations {
+            animation.validate().map_err(|e| anyhow::anyhow!("动画 '{}' 无效: {}", name, e))?;
+        }
+        
+        if self.tracks.is_empty() {
+            return Err(anyhow::anyhow!("编舞没有轨道"));
+        }
+        
+        let mut robots = HashSet::new();
+        for track in &self.tracks {
+            if !robots.insert(track.robot.as_str()) {
+                return Err(anyhow::anyhow!("机器人 '{}' 有多条轨道", track.robot));
+            }
+            
+            let mut free_at = 0.0;
+            for cue in &track.cues {
+                let animation = self.animations.get(&cue.animation)
+                    .ok_or_else(|| anyhow::anyhow!("轨道 '{}' 引用了不存在的动画 '{}'", track.robot, cue.animation))?;
+                
+                if !cue.at.is_finite() || cue.at < 0.0 {
+                    return Err(anyhow::anyhow!("轨道 '{}' 的提示点时间无效: {}", track.robot, cue.at));
+                }
+                if cue.at < free_at {
+                    return Err(anyhow::anyhow!(
+                        "轨道 '{}' 在 {:.2} 秒的动画 '{}' 与前一段动画重叠", track.robot, cue.at, cue.animation
+                    ));
+                }
+                free_at = cue.at + animation.duration();
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// 演出时长（秒）：最后一段动画结束的时间
+    pub fn duration(&self) -> f64 {
+        self.tracks.iter()
+            .flat_map(|track| &track.cues)
+            .filter_map(|cue| self.animations.get(&cue.animation).map(|a| cue.at + a.duration()))
+            .fold(0.0, f64::max)
+    }
+    
+    /// 机器人的轨道：先按UUID匹配，再按名称，最后使用`*`轨道
+    pub fn track_for(&self, robot: &RobotIdentity) -> Option<&Track> {
+        [robot.uuid.as_str(), robot.name.as_str(), ANY_ROBOT].into_iter()
+            .find_map(|key| self.tracks.iter().find(|track| track.robot == key))
+    }
+    
+    /// 机器人的动画安排：(相对演出开始的秒数, 动画)
+    pub fn schedule(&self, robot: &RobotIdentity) -> Result<Vec<(f64, &TrajectoryFile)>> {
+        let track = self.track_for(robot)
+            .ok_or_else(|| anyhow::anyhow!("编舞 '{}' 中没有机器人 '{}' 的轨道", self.name, robot.name))?;
+        
+        track.cues.iter()
+            .map(|cue| {
+                self.animations.get(&cue.animation)
+                    .map(|animation| (cue.at, animation))
+                    .ok_or_else(|| anyhow::anyhow!("动画 '{}' 不存在", cue.animation))
+            })
+            .collect()
+    }
+    
+    /// 演出开始后t秒时机器人各关节的位置，不在任何动画内时返回None（保持当前姿态）
+    pub fn sample(&self, robot: &RobotIdentity, t: f64) -> Option<HashMap<String, f64>> {
+        let track = self.track_for(robot)?;
+        let cue = track.cues.iter().rev().find(|cue| cue.at <= t)?;
+        self.animations.get(&cue.animation)?.sample(t - cue.at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn robot(name: &str) -> RobotIdentity {
+        RobotIdentity {
+            uuid: format!("{}-uuid", name),
+            name: name.to_string(),
+            group: "show".to_string(),
+        }
+    }
+    
+    fn nod() -> TrajectoryFile {
+        let mut animation = TrajectoryFile::new("nod", vec!["head_tilt".to_string()]);
+        for step in 0..=10 {
+            let t = step as f64 * 0.1;
+            animation.push_frame(t, &HashMap::from([("head_tilt".to_string(), t)]), None);
+        }
+        animation
+    }
+    
+    fn choreography(tracks: Vec<Track>) -> Choreography {
+        Choreography {
+            format: CHOREOGRAPHY_FORMAT.to_string(),
+            version: CHOREOGRAPHY_VERSION,
+            name: "finale".to_string(),
+            animations: BTreeMap::from([("nod".to_string(), nod())]),
+            tracks,
+        }
+    }
+    
+    fn track(robot: &str, times: &[f64]) -> Track {
+        Track {
+            robot: robot.to_string(),
+            cues: times.iter().map(|&at| Cue { at, animation: "nod".to_string() }).collect(),
+        }
+    }
+    
+    #[test]
+    fn test_track_selection_and_sampling() {
+        let show = choreography(vec![track("left", &[0.0, 2.0]), track(ANY_ROBOT, &[1.0])]);
+        assert!(show.validate().is_ok());
+        assert!((show.duration() - 3.0).abs() < 1e-9);
+        
+        // 名称匹配的机器人使用自己的轨道，其他机器人使用*轨道
+        let left = robot("left");
+        let right = robot("right");
+        assert_eq!(show.schedule(&left).unwrap().len(), 2);
+        assert_eq!(show.track_for(&right).unwrap().robot, ANY_ROBOT);
+        
+        let tilt = |robot: &RobotIdentity, t: f64| show.sample(robot, t).map(|p| p["head_tilt"]);
+        assert!((tilt(&left, 0.5).unwrap() - 0.5).abs() < 1e-9);
+        assert_eq!(tilt(&left, 1.5), None);
+        assert!((tilt(&left, 2.25).unwrap() - 0.25).abs() < 1e-9);
+        assert_eq!(tilt(&right, 0.5), None);
+        assert!((tilt(&right, 1.5).unwrap() - 0.5).abs() < 1e-9);
+        
+        let json = show.to_json().unwrap();
+        assert_eq!(Choreography::from_json(&json).unwrap(), show);
+    }
+    
+    #[test]
+    fn test_validation_errors() {
+        // 动画重叠、引用不存在的动画、重复的轨道
+        assert!(choreography(vec![track("left", &[0.0, 0.5])]).validate().is_err());
+        let mut missing = choreography(vec![track("left", &[0.0])]);
+        missing.tracks[0].cues[0].animation = "spin".to_string();
+        assert!(missing.validate().is_err());
+        assert!(choreography(vec![track("left", &[0.0]), track("left", &[2.0])]).validate().is_err());
+        
+        let show = choreography(vec![track("left", &[0.0])]);
+        assert!(show.schedule(&robot("right")).is_err());
+    }
+}
@@ -0,0 +1,350 @@
+//! 命令过滤模块
+//! 
+//! WebSocket和Python等外部接口可能以任意频率发送运动命令，容易把总线塞满。
+//! 本模块在命令进入仲裁之前做三件事：按来源和客户端限流（令牌桶），
+//! 拒绝NaN/无穷大等非法数值，并按各接口声明的角度单位（弧度/度）
+//! 严格解析外部命令JSON，给出可读的错误信息。
+
+use crate::arbiter::{CommandOrigin, CommandSource};
+use crate::common::*;
+use crate::realtime::{CommandType, MotionCommand};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use log::warn;
+
+/// 超过此数量的客户端时清理已经回满的令牌桶
+const MAX_TRACKED_CLIENTS: usize = 256;
+
+/// 外部接口使用的角度单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AngleUnit {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+impl AngleUnit {
+    /// 把该单位下的数值换算为弧度
+    pub fn to_radians(self, value: f64) -> f64 {
+        match self {
+            AngleUnit::Radians => value,
+            AngleUnit::Degrees => value.to_radians(),
+        }
+    }
+}
+
+/// 单个来源的限流参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// 每秒允许的命令数
+    pub max_rate: f64,
+    /// 允许的突发命令数
+    pub burst: u32,
+}
+
+/// 命令过滤配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandFilterConfig {
+    /// 各来源的限流参数，未配置的来源不限流
+    pub rate_limits: HashMap<CommandSource, RateLimit>,
+    /// 各来源声明的角度单位，未配置的来源使用弧度
+    pub angle_units: HashMap<CommandSource, AngleUnit>,
+}
+
+impl Default for CommandFilterConfig {
+    fn default() -> Self {
+        let rate_limits = HashMap::from([
+            (CommandSource::Teleop, RateLimit { max_rate: 200.0, burst: 20 }),
+            (CommandSource::WebSocket, RateLimit { max_rate: 50.0, burst: 10 }),
+            (CommandSource::Python, RateLimit { max_rate: 100.0, burst: 20 }),
+        ]);
+        
+        // 前端和REST接口沿用角度制
+        let angle_units = HashMap::from([
+            (CommandSource::WebSocket, AngleUnit::Degrees),
+        ]);
+        
+        Self { rate_limits, angle_units }
+    }
+}
+
+impl ConfigValidation for CommandFilterConfig {
+    fn validate(&self) -> Result<()> {
+        for (source, limit) in &self.rate_limits {
+            if !limit.max_rate.is_finite() || limit.max_rate <= 0.0 {
+                return Err(anyhow::anyhow!("来源 {:?} 的命令频率上限必须为正数", source));
+            }
+            
+            if limit.burst == 0 {
+                return Err(anyhow::anyhow!("来源 {:?} 的突发命令数必须大于0", source));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 外部命令类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalCommandType {
+    Position,
+    Velocity,
+    Torque,
+    Stop,
+    EmergencyStop,
+}
+
+/// 外部命令JSON的格式，不允许出现未知字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalCommand {
+    pub joint: String,
+    #[serde(rename = "type")]
+    pub command_type: ExternalCommandType,
+    #[serde(default)]
+    pub position: Option<f64>,
+    #[serde(default)]
+    pub velocity: Option<f64>,
+    #[serde(default)]
+    pub torque: Option<f64>,
+    /// 运动时长（秒）
+    #[serde(default)]
+    pub duration: Option<f64>,
+    /// 客户端指定的关联ID，未指定时入队时生成
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+impl ExternalCommand {
+    /// 检查字段组合并换算为内部命令（角度量统一为弧度）
+    pub fn into_motion_command(self, unit: AngleUnit) -> Result<MotionCommand> {
+        if self.joint.is_empty() {
+            return Err(anyhow::anyhow!("字段 'joint' 不能为空"));
+        }
+        
+        let (command_type, required) = match self.command_type {
+            ExternalCommandType::Position => (CommandType::Position, Some("position")),
+            ExternalCommandType::Velocity => (CommandType::Velocity, Some("velocity")),
+            ExternalCommandType::Torque => (CommandType::Torque, Some("torque")),
+            ExternalCommandType::Stop => (CommandType::Stop, None),
+            ExternalCommandType::EmergencyStop => (CommandType::EmergencyStop, None),
+        };
+        
+        let targets = [
+            ("position", self.position.is_some()),
+            ("velocity", self.velocity.is_some()),
+            ("torque", self.torque.is_some()),
+        ];
+        for (field, present) in targets {
+            if Some(field) == required && !present {
+                return Err(anyhow::anyhow!("{:?} 命令缺少字段 '{}'", self.command_type, field));
+            }
+            if Some(field) != required && present {
+                return Err(anyhow::anyhow!("{:?} 命令不接受字段 '{}'", self.command_type, field));
+            }
+        }
+        
+        if let Some(duration) = self.duration {
+            if !duration.is_finite() || duration <= 0.0 {
+                return Err(anyhow::anyhow!("字段 'duration' 必须为正数"));
+            }
+        }
+        
+        let command = MotionCommand {
+            joint_name: self.joint,
+            command_type,
+            target_position: self.position.map(|p| unit.to_radians(p)),
+            target_velocity: self.velocity.map(|v| unit.to_radians(v)),
+            target_torque: self.torque,
+            duration: self.duration,
+            timestamp: current_timestamp(),
+            correlation_id: self.correlation_id,
+        };
+        
+        command.validate()?;
+        Ok(command)
+    }
+}
+
+/// 拒绝包含NaN或无穷大的命令
+pub fn validate_finite(command: &MotionCommand) -> Result<()> {
+    let fields = [
+        ("position", command.target_position),
+        ("velocity", command.target_velocity),
+        ("torque", command.target_torque),
+        ("duration", command.duration),
+    ];
+    
+    for (field, value) in fields {
+        if value.is_some_and(|v| !v.is_finite()) {
+            return Err(anyhow::anyhow!("关节 {} 的命令字段 '{}' 不是有限数值", command.joint_name, field));
+        }
+    }
+    
+    Ok(())
+}
+
+/// 令牌桶
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: u64,
+}
+
+/// 命令过滤器
+#[derive(Debug)]
+pub struct CommandFilter {
+    config: CommandFilterConfig,
+    buckets: HashMap<CommandOrigin, TokenBucket>,
+    /// 各来源因超过频率被拒绝的命令数
+    rejected: HashMap<CommandSource, u64>,
+}
+
+impl CommandFilter {
+    pub fn new(config: CommandFilterConfig) -> Result<Self> {
+        config.validate()?;
+        
+        Ok(Self {
+            config,
+            buckets: HashMap::new(),
+            rejected: HashMap::new(),
+        })
+    }
+    
+    /// 该来源声明的角度单位
+    pub fn angle_unit(&self, source: CommandSource) -> AngleUnit {
+        self.config.angle_units.get(&source).copied().unwrap_or_default()
+    }
+    
+    /// 按来源声明的单位解析外部命令JSON
+    pub fn parse(&self, source: CommandSource, json: &str) -> Result<MotionCommand> {
+        let command: ExternalCommand = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("命令格式错误: {}", e))?;
+        command.into_motion_command(self.angle_unit(source))
+    }
+    
+    /// 检查发送方是否超过频率限制，急停命令不受限制
+    pub fn check_rate(&mut self, origin: &CommandOrigin, emergency: bool) -> Result<()> {
+        self.check_rate_at(origin, emergency, current_timestamp())
+    }
+    
+    fn check_rate_at(&mut self, origin: &CommandOrigin, emergency: bool, now: u64) -> Result<()> {
+        if emergency {
+            return Ok(());
+        }
+        
+        let Some(limit) = self.config.rate_limits.get(&origin.source).cloned() else {
+            return Ok(());
+        };
+        
+        if self.buckets.len() > MAX_TRACKED_CLIENTS {
+            self.prune(now);
+        }
+        
+        let bucket = self.buckets.entry(origin.clone()).or_insert(TokenBucket {
+            tokens: limit.burst as f64,
+            last_refill: now,
+        });
+        
+        let elapsed = now.saturating_sub(bucket.last_refill) as f64 / 1000.0;
+        bucket.tokens = (bucket.tokens + elapsed * limit.max_rate).min(limit.burst as f64);
+        bucket.last_refill = now;
+        
+        if bucket.tokens < 1.0 {
+            let rejected = self.rejected.entry(origin.source).or_insert(0);
+            *rejected += 1;
+            if *rejected % 100 == 1 {
+                warn!("{:?}:{} 发送命令过快，已拒绝 {} 条", origin.source, origin.client_id, rejected);
+            }
+            return Err(anyhow::anyhow!("命令频率超过限制（每秒 {} 条）", limit.max_rate));
+        }
+        
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+    
+    /// 移除已经回满的令牌桶（与从未发送过命令等价）
+    fn prune(&mut self, now: u64) {
+        let limits = &self.config.rate_limits;
+        self.buckets.retain(|origin, bucket| {
+            limits.get(&origin.source).is_some_and(|limit| {
+                let elapsed = now.saturating_sub(bucket.last_refill) as f64 / 1000.0;
+                bucket.tokens + elapsed * limit.max_rate < limit.burst as f64
+            })
+        });
+    }
+    
+    /// 各来源被限流拒绝的命令数
+    pub fn rejected_counts(&self) -> HashMap<CommandSource, u64> {
+        self.rejected.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_rate_limit() {
+        let mut filter = CommandFilter::new(CommandFilterConfig::default()).unwrap();
+        let client = CommandOrigin::new(CommandSource::WebSocket, "browser");
+        let other = CommandOrigin::new(CommandSource::WebSocket, "tablet");
+        
+        // 突发额度用完后被拒绝，其他客户端不受影响
+        for _ in 0..10 {
+            assert!(filter.check_rate_at(&client, false, 1000).is_ok());
+        }
+        assert!(filter.check_rate_at(&client, false, 1000).is_err());
+        assert!(filter.check_rate_at(&other, false, 1000).is_ok());
+        
+        // 急停不受限流
+        assert!(filter.check_rate_at(&client, true, 1000).is_ok());
+        
+        // 50条/秒，20毫秒补充一个令牌
+        assert!(filter.check_rate_at(&client, false, 1020).is_ok());
+        assert!(filter.check_rate_at(&client, false, 1020).is_err());
+        assert_eq!(filter.rejected_counts()[&CommandSource::WebSocket], 2);
+        
+        // 未配置限流的来源不受限制
+        let behavior = CommandOrigin::new(CommandSource::Behavior, "rules");
+        assert!((0..1000).all(|_| filter.check_rate_at(&behavior, false, 1000).is_ok()));
+    }
+    
+    #[test]
+    fn test_parse_external_command() {
+        let filter = CommandFilter::new(CommandFilterConfig::default()).unwrap();
+        
+        // WebSocket声明为角度制
+        let command = filter.parse(CommandSource::WebSocket, r#"{"joint": "head_pan", "type": "position", "position": 90.0}"#).unwrap();
+        assert!((command.target_position.unwrap() - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+        
+        let command = filter.parse(CommandSource::Python, r#"{"joint": "head_pan", "type": "position", "position": 0.5}"#).unwrap();
+        assert_eq!(command.target_position, Some(0.5));
+        
+        // 未知字段、缺少字段和多余字段都给出具体的错误
+        let error = filter.parse(CommandSource::Python, r#"{"joint": "head_pan", "type": "position", "pos": 0.5}"#).unwrap_err();
+        assert!(error.to_string().contains("pos"));
+        let error = filter.parse(CommandSource::Python, r#"{"joint": "head_pan", "type": "velocity"}"#).unwrap_err();
+        assert!(error.to_string().contains("velocity"));
+        assert!(filter.parse(CommandSource::Python, r#"{"joint": "head_pan", "type": "stop", "torque": 1.0}"#).is_err());
+        assert!(filter.parse(CommandSource::Python, r#"{"joint": "head_pan", "type": "jump"}"#).is_err());
+        
+        // 超出f64范围的数值同样被拒绝
+        assert!(filter.parse(CommandSource::Python, r#"{"joint": "head_pan", "type": "position", "position": 1e999}"#).is_err());
+        
+        let nan = MotionCommand {
+            joint_name: "head_pan".to_string(),
+            command_type: CommandType::Position,
+            target_position: Some(f64::NAN),
+            target_velocity: None,
+            target_torque: None,
+            duration: None,
+            timestamp: 0,
+            correlation_id: None,
+        };
+        assert!(validate_finite(&nan).is_err());
+        assert!(nan.validate().is_err());
+    }
+}
@@ -4,6 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use anyhow::Result;
 
@@ -108,6 +109,22 @@ impl Quaternion {
         )
     }
     
+    /// 转换为(roll, pitch, yaw)，与from_euler互逆；pitch为±π/2时roll取0
+    pub fn to_euler(&self) -> (f64, f64, f64) {
+        let q = self.normalize();
+        let sin_pitch = clamp(2.0 * (q.w * q.y - q.z * q.x), -1.0, 1.0);
+        let pitch = sin_pitch.asin();
+        
+        if sin_pitch.abs() > 1.0 - 1e-9 {
+            let yaw = -2.0 * sin_pitch.signum() * q.x.atan2(q.w);
+            return (0.0, pitch, normalize_angle(yaw));
+        }
+        
+        let roll = (2.0 * (q.w * q.x + q.y * q.z)).atan2(1.0 - 2.0 * (q.x * q.x + q.y * q.y));
+        let yaw = (2.0 * (q.w * q.z + q.x * q.y)).atan2(1.0 - 2.0 * (q.y * q.y + q.z * q.z));
+        (roll, pitch, yaw)
+    }
+    
     pub fn normalize(&self) -> Self {
         let norm = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
         if norm > 0.0 {
@@ -116,6 +133,78 @@ impl Quaternion {
             Self::identity()
         }
     }
+    
+    /// 绕指定轴旋转angle弧度
+    pub fn from_axis_angle(axis: &Vector3, angle: f64) -> Self {
+        let axis = axis.normalize();
+        let half = angle * 0.5;
+        let s = half.sin();
+        Self::new(half.cos(), axis.x * s, axis.y * s, axis.z * s)
+    }
+    
+    /// 共轭（单位四元数的逆）
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+    
+    pub fn dot(&self, other: &Self) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+    
+    /// 用该旋转变换向量
+    pub fn rotate_vector(&self, v: &Vector3) -> Vector3 {
+        let p = Self::new(0.0, v.x, v.y, v.z);
+        let r = *self * p * self.conjugate();
+        Vector3::new(r.x, r.y, r.z)
+    }
+    
+    /// 球面线性插值
+    pub fn slerp(&self, other: &Self, t: f64) -> Self {
+        let t = clamp(t, 0.0, 1.0);
+        let mut end = *other;
+        let mut cos_theta = self.dot(other);
+        
+        // 走最短路径
+        if cos_theta < 0.0 {
+            end = Self::new(-end.w, -end.x, -end.y, -end.z);
+            cos_theta = -cos_theta;
+        }
+        
+        if cos_theta > 0.9995 {
+            // 角度很小时退化为线性插值
+            return Self::new(
+                lerp(self.w, end.w, t),
+                lerp(self.x, end.x, t),
+                lerp(self.y, end.y, t),
+                lerp(self.z, end.z, t),
+            ).normalize();
+        }
+        
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / sin_theta;
+        let b = (t * theta).sin() / sin_theta;
+        
+        Self::new(
+            a * self.w + b * end.w,
+            a * self.x + b * end.x,
+            a * self.y + b * end.y,
+            a * self.z + b * end.z,
+        )
+    }
+}
+
+impl std::ops::Mul for Quaternion {
+    type Output = Self;
+    
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        )
+    }
 }
 
 /// 位姿结构（位置 + 方向）
@@ -135,15 +224,338 @@ impl Pose {
     }
 }
 
+/// 角度（内部统一以弧度保存）
+///
+/// 默认按弧度数值序列化；以度为单位的配置字段使用 `#[serde(with = "serde_degrees")]`。
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Angle(f64);
+
+impl Angle {
+    pub const ZERO: Angle = Angle(0.0);
+    
+    pub const fn from_radians(radians: f64) -> Self {
+        Self(radians)
+    }
+    
+    pub fn from_degrees(degrees: f64) -> Self {
+        Self(degrees.to_radians())
+    }
+    
+    pub fn radians(self) -> f64 {
+        self.0
+    }
+    
+    pub fn degrees(self) -> f64 {
+        self.0.to_degrees()
+    }
+    
+    pub fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+    
+    /// 归一化到[-π, π)区间
+    pub fn normalized(self) -> Self {
+        Self(normalize_angle(self.0))
+    }
+}
+
+impl std::ops::Add for Angle {
+    type Output = Angle;
+    
+    fn add(self, other: Angle) -> Angle {
+        Angle(self.0 + other.0)
+    }
+}
+
+impl std::ops::Sub for Angle {
+    type Output = Angle;
+    
+    fn sub(self, other: Angle) -> Angle {
+        Angle(self.0 - other.0)
+    }
+}
+
+impl std::ops::Neg for Angle {
+    type Output = Angle;
+    
+    fn neg(self) -> Angle {
+        Angle(-self.0)
+    }
+}
+
+impl std::ops::Mul<f64> for Angle {
+    type Output = Angle;
+    
+    fn mul(self, scalar: f64) -> Angle {
+        Angle(self.0 * scalar)
+    }
+}
+
+impl std::ops::Div<f64> for Angle {
+    type Output = Angle;
+    
+    fn div(self, scalar: f64) -> Angle {
+        Angle(self.0 / scalar)
+    }
+}
+
+/// 角速度（内部统一以rad/s保存）
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AngularVelocity(f64);
+
+impl AngularVelocity {
+    pub const ZERO: AngularVelocity = AngularVelocity(0.0);
+    
+    pub const fn from_radians_per_second(value: f64) -> Self {
+        Self(value)
+    }
+    
+    pub fn from_degrees_per_second(value: f64) -> Self {
+        Self(value.to_radians())
+    }
+    
+    pub fn radians_per_second(self) -> f64 {
+        self.0
+    }
+    
+    pub fn degrees_per_second(self) -> f64 {
+        self.0.to_degrees()
+    }
+    
+    /// 以该角速度转动seconds秒经过的角度
+    pub fn over(self, seconds: f64) -> Angle {
+        Angle(self.0 * seconds)
+    }
+}
+
+impl std::ops::Mul<f64> for AngularVelocity {
+    type Output = AngularVelocity;
+    
+    fn mul(self, scalar: f64) -> AngularVelocity {
+        AngularVelocity(self.0 * scalar)
+    }
+}
+
+/// 可以用度表示的角度量
+pub trait DegreeUnit: Sized {
+    fn from_degree_value(value: f64) -> Self;
+    fn degree_value(&self) -> f64;
+}
+
+impl DegreeUnit for Angle {
+    fn from_degree_value(value: f64) -> Self {
+        Angle::from_degrees(value)
+    }
+    
+    fn degree_value(&self) -> f64 {
+        self.degrees()
+    }
+}
+
+impl DegreeUnit for AngularVelocity {
+    fn from_degree_value(value: f64) -> Self {
+        AngularVelocity::from_degrees_per_second(value)
+    }
+    
+    fn degree_value(&self) -> f64 {
+        self.degrees_per_second()
+    }
+}
+
+/// 以度（或度/秒）为单位序列化角度量，用于沿用角度制的配置文件
+pub mod serde_degrees {
+    use super::DegreeUnit;
+    use serde::{Deserialize, Deserializer, Serializer};
+    
+    pub fn serialize<T: DegreeUnit, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(value.degree_value())
+    }
+    
+    pub fn deserialize<'de, T: DegreeUnit, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        f64::deserialize(deserializer).map(T::from_degree_value)
+    }
+}
+
+/// 频率（内部以Hz保存）
+///
+/// 配置中可以写数值（Hz）或带单位的字符串，如 `"100Hz"`、`"1kHz"`；按Hz数值序列化，
+/// 旧的读取方不受影响。
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Frequency(f64);
+
+impl Frequency {
+    pub const fn from_hz(hz: f64) -> Self {
+        Self(hz)
+    }
+    
+    pub fn hz(self) -> f64 {
+        self.0
+    }
+    
+    /// 一个周期的时长，频率必须为正数
+    pub fn period(self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.0)
+    }
+}
+
+impl std::ops::Mul<f64> for Frequency {
+    type Output = Frequency;
+    
+    fn mul(self, scalar: f64) -> Frequency {
+        Frequency(self.0 * scalar)
+    }
+}
+
+impl std::fmt::Display for Frequency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}Hz", self.0)
+    }
+}
+
+impl std::str::FromStr for Frequency {
+    type Err = anyhow::Error;
+    
+    /// 解析 "100Hz"、"1.5 kHz"、"100" 等形式，单位不区分大小写
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let split = text.find(|c: char| c.is_alphabetic()).unwrap_or(text.len());
+        let (number, unit) = (text[..split].trim(), &text[split..]);
+        let scale = match unit.to_ascii_lowercase().as_str() {
+            "" | "hz" => 1.0,
+            "khz" => 1e3,
+            "mhz" => 1e6,
+            _ => return Err(anyhow::anyhow!("无法识别的频率单位: {}", text)),
+        };
+        let value: f64 = number.parse().map_err(|_| anyhow::anyhow!("频率格式错误: {}", text))?;
+        if !value.is_finite() {
+            return Err(anyhow::anyhow!("频率格式错误: {}", text));
+        }
+        Ok(Self(value * scale))
+    }
+}
+
+impl Serialize for Frequency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Frequency {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        match NumberOrText::deserialize(deserializer)? {
+            NumberOrText::Number(hz) => Ok(Self(hz)),
+            NumberOrText::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// 兼容旧的纯数值写法的配置值
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrText {
+    Number(f64),
+    Text(String),
+}
+
+/// 解析带单位的时长，如 "100ms"、"30s"、"1m30s"、"1.5h"；不带单位的数值按default_unit计
+pub fn parse_duration(text: &str, default_unit: Duration) -> Result<Duration> {
+    let error = || anyhow::anyhow!("时长格式错误: {}", text);
+    let text = text.trim();
+    if let Ok(value) = text.parse::<f64>() {
+        return scale_duration(value, default_unit).ok_or_else(error);
+    }
+    
+    if text.is_empty() {
+        return Err(error());
+    }
+    
+    let mut rest = text;
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        let value: f64 = rest[..number_end].parse().map_err(|_| error())?;
+        rest = rest[number_end..].trim_start();
+        let unit_end = rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len());
+        let unit = match &rest[..unit_end] {
+            "us" | "µs" => 1e-6,
+            "ms" => 1e-3,
+            "s" | "sec" | "secs" => 1.0,
+            "m" | "min" | "mins" => 60.0,
+            "h" | "hr" | "hrs" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            _ => return Err(error()),
+        };
+        seconds += value * unit;
+        rest = rest[unit_end..].trim_start();
+    }
+    scale_duration(seconds, Duration::from_secs(1)).ok_or_else(error)
+}
+
+fn scale_duration(value: f64, unit: Duration) -> Option<Duration> {
+    Duration::try_from_secs_f64(value * unit.as_secs_f64()).ok()
+}
+
+/// 以毫秒数值序列化时长，读取时也接受带单位的字符串，用于原来以毫秒整数表示的配置字段
+pub mod serde_duration_ms {
+    use super::{parse_duration, scale_duration, NumberOrText};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+    
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(value.as_millis() as u64)
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let unit = Duration::from_millis(1);
+        match NumberOrText::deserialize(deserializer)? {
+            NumberOrText::Number(ms) => scale_duration(ms, unit)
+                .ok_or_else(|| serde::de::Error::custom(format!("时长必须为非负数: {}", ms))),
+            NumberOrText::Text(text) => parse_duration(&text, unit).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// 以秒数值序列化时长，读取时也接受带单位的字符串，用于原来以秒数表示的配置字段
+pub mod serde_duration_secs {
+    use super::{parse_duration, scale_duration, NumberOrText};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+    
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(value.as_secs())
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let unit = Duration::from_secs(1);
+        match NumberOrText::deserialize(deserializer)? {
+            NumberOrText::Number(secs) => scale_duration(secs, unit)
+                .ok_or_else(|| serde::de::Error::custom(format!("时长必须为非负数: {}", secs))),
+            NumberOrText::Text(text) => parse_duration(&text, unit).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
 /// 关节状态结构
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct JointState {
     pub name: String,
+    /// 关节位置（rad），连续旋转关节为归一化到[-π, π)的值
     pub position: f64,
     pub velocity: f64,
+    /// 关节加速度（rad/s²），由状态估计得到
+    #[serde(default)]
+    pub acceleration: f64,
     pub effort: f64,
     pub temperature: Option<f64>,
     pub is_moving: bool,
+    /// 展开后的位置（rad），连续旋转关节包含累计的整圈，其它关节与position相同
+    #[serde(default)]
+    pub unwrapped_position: f64,
+    /// 连续旋转关节的整圈计数
+    #[serde(default)]
+    pub turns: i64,
 }
 
 impl JointState {
@@ -152,9 +564,36 @@ impl JointState {
             name,
             position: 0.0,
             velocity: 0.0,
+            acceleration: 0.0,
             effort: 0.0,
             temperature: None,
             is_moving: false,
+            unwrapped_position: 0.0,
+            turns: 0,
+        }
+    }
+    
+    /// 用新的位置读数更新状态
+    ///
+    /// 连续旋转关节的编码器读数在±π处回绕，相邻两次读数跳变超过π时视为跨过一圈，
+    /// 在软件中累计圈数。要求读数间隔内关节转动不超过半圈。
+    pub fn update_position(&mut self, position: f64, continuous: bool) {
+        if continuous {
+            let wrapped = normalize_angle(position);
+            let delta = wrapped - self.position;
+            
+            if delta > std::f64::consts::PI {
+                self.turns -= 1;
+            } else if delta < -std::f64::consts::PI {
+                self.turns += 1;
+            }
+            
+            self.position = wrapped;
+            self.unwrapped_position = wrapped + self.turns as f64 * std::f64::consts::TAU;
+        } else {
+            self.position = position;
+            self.unwrapped_position = position;
+            self.turns = 0;
         }
     }
 }
@@ -245,6 +684,28 @@ impl ImageData {
         let expected_size = (self.width * self.height * self.channels) as usize;
         self.data.len() == expected_size
     }
+    
+    /// 按整数倍缩小（最近邻取样），不做插值，开销很小
+    pub fn subsample(&self, factor: u32) -> ImageData {
+        if factor <= 1 || !self.is_valid() {
+            return self.clone();
+        }
+        
+        let (width, height) = ((self.width / factor).max(1), (self.height / factor).max(1));
+        let pixel = self.channels as usize;
+        let row = self.width as usize * pixel;
+        let step = factor as usize;
+        let mut data = Vec::with_capacity(width as usize * height as usize * pixel);
+        for y in 0..height as usize {
+            let start = y * step * row;
+            for x in 0..width as usize {
+                let offset = start + x * step * pixel;
+                data.extend_from_slice(&self.data[offset..offset + pixel]);
+            }
+        }
+        
+        ImageData { width, height, channels: self.channels, data, format: self.format, timestamp: self.timestamp }
+    }
 }
 
 /// 性能统计结构
@@ -313,11 +774,183 @@ impl Default for PerformanceStats {
     }
 }
 
+/// 可原子读写的f64，用于在循环线程和状态查询之间共享统计值而不加锁
+#[derive(Debug, Default)]
+pub struct AtomicF64(AtomicU64);
+
+impl AtomicF64 {
+    pub fn new(value: f64) -> Self {
+        Self(AtomicU64::new(value.to_bits()))
+    }
+    
+    pub fn load(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+    
+    pub fn store(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// ULID：前48位为毫秒时间戳、后80位为随机数，文本形式为26个字符的Crockford Base32，
+/// 按字典序排序即按生成时间排序。同一毫秒内生成的ID在上一个ID的基础上加一，保证单调递增。
+///
+/// 用作推理请求、运动命令和硬件事务的ID，同一个ID从API请求一直传到硬件并出现在各层日志中。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ulid(u128);
+
+impl Ulid {
+    const ALPHABET: &'static [u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+    const RANDOM_BITS: u32 = 80;
+    
+    /// 生成新的ULID
+    pub fn new() -> Self {
+        static LAST: std::sync::Mutex<u128> = std::sync::Mutex::new(0);
+        
+        let timestamp = current_timestamp() as u128 & ((1 << 48) - 1);
+        let mut last = LAST.lock().unwrap_or_else(|e| e.into_inner());
+        let id = if timestamp <= *last >> Self::RANDOM_BITS {
+            // 同一毫秒内（或时钟回拨）在上一个ID的基础上递增
+            *last + 1
+        } else {
+            timestamp << Self::RANDOM_BITS | rand::random::<u128>() & ((1 << Self::RANDOM_BITS) - 1)
+        };
+        *last = id;
+        Self(id)
+    }
+    
+    /// 生成时间（Unix时间，毫秒）
+    pub fn timestamp_ms(self) -> u64 {
+        (self.0 >> Self::RANDOM_BITS) as u64
+    }
+}
+
+impl Default for Ulid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for Ulid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut text = [0u8; 26];
+        for (i, c) in text.iter_mut().enumerate() {
+            let shift = 5 * (25 - i);
+            *c = Self::ALPHABET[(self.0 >> shift) as usize & 0x1f];
+        }
+        f.write_str(std::str::from_utf8(&text).expect("ULID字符均为ASCII"))
+    }
+}
+
+impl std::str::FromStr for Ulid {
+    type Err = anyhow::Error;
+    
+    /// 解析ULID文本，不区分大小写，按Crockford规则把I、L当作1，O当作0
+    fn from_str(text: &str) -> Result<Self> {
+        if text.len() != 26 || text.as_bytes()[0] > b'7' {
+            return Err(anyhow::anyhow!("ULID格式错误: {}", text));
+        }
+        
+        let mut value = 0u128;
+        for c in text.bytes() {
+            let c = match c.to_ascii_uppercase() {
+                b'I' | b'L' => b'1',
+                b'O' => b'0',
+                c => c,
+            };
+            let digit = Self::ALPHABET.iter().position(|&a| a == c)
+                .ok_or_else(|| anyhow::anyhow!("ULID格式错误: {}", text))?;
+            value = value << 5 | digit as u128;
+        }
+        Ok(Self(value))
+    }
+}
+
+impl Serialize for Ulid {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ulid {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 生成新的ID（ULID文本）
+pub fn new_id() -> String {
+    Ulid::new().to_string()
+}
+
 /// 配置验证trait
 pub trait ConfigValidation {
     fn validate(&self) -> Result<()>;
 }
 
+/// 硬件后端模式
+///
+/// 在运行时而非编译时选择真实设备或模拟实现，同一个二进制
+/// 既能在开发机上仿真运行，也能不做修改直接部署到机器人上。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendMode {
+    Real,
+    Mock,
+    /// 设备节点存在时使用真实设备，否则回退到模拟
+    #[default]
+    Auto,
+}
+
+impl BackendMode {
+    /// 判断是否使用真实设备
+    pub fn use_real(self, device: impl AsRef<std::path::Path>) -> bool {
+        match self {
+            BackendMode::Real => true,
+            BackendMode::Mock => false,
+            BackendMode::Auto => device.as_ref().exists(),
+        }
+    }
+}
+
+impl std::str::FromStr for BackendMode {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "real" => Ok(BackendMode::Real),
+            "mock" => Ok(BackendMode::Mock),
+            "auto" => Ok(BackendMode::Auto),
+            _ => Err(anyhow::anyhow!("未知的硬件后端模式: {}", s)),
+        }
+    }
+}
+
+/// 各硬件子系统的后端选择
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendSelection {
+    pub serial: BackendMode,
+    pub i2c: BackendMode,
+    pub gpio: BackendMode,
+    pub camera: BackendMode,
+    pub audio: BackendMode,
+}
+
+impl BackendSelection {
+    /// 所有子系统使用同一种模式
+    pub fn all(mode: BackendMode) -> Self {
+        Self {
+            serial: mode,
+            i2c: mode,
+            gpio: mode,
+            camera: mode,
+            audio: mode,
+        }
+    }
+}
+
 /// 状态管理trait
 pub trait StateManager {
     type State;
@@ -326,7 +959,8 @@ pub trait StateManager {
     fn set_state(&mut self, state: Self::State);
 }
 
-/// 生命周期管理trait
+/// 生命周期管理trait（仅在本crate内使用，不要求返回的Future满足Send）
+#[allow(async_fn_in_trait)]
 pub trait LifecycleManager {
     async fn start(&mut self) -> Result<()>;
     async fn stop(&mut self) -> Result<()>;
@@ -337,7 +971,7 @@ pub trait LifecycleManager {
     fn is_running(&self) -> bool;
 }
 
-/// 工具函数
+// 工具函数
 
 /// 获取当前时间戳（毫秒）
 pub fn current_timestamp() -> u64 {
@@ -365,6 +999,12 @@ pub fn radians_to_degrees(radians: f64) -> f64 {
     radians * 180.0 / std::f64::consts::PI
 }
 
+/// 把角度归一化到[-π, π)区间（弧度）
+pub fn normalize_angle(radians: f64) -> f64 {
+    let two_pi = 2.0 * std::f64::consts::PI;
+    (radians + std::f64::consts::PI).rem_euclid(two_pi) - std::f64::consts::PI
+}
+
 /// 限制值在指定范围内
 pub fn clamp<T: PartialOrd>(value: T, min: T, max: T) -> T {
     if value < min {
@@ -393,6 +1033,34 @@ pub fn angle_between_vectors(v1: &Vector3, v2: &Vector3) -> f64 {
     dot.clamp(-1.0, 1.0).acos()
 }
 
+/// 高斯消元（列主元）解线性方程组 a·x = b，矩阵奇异时返回None
+pub fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(pivot, col);
+        b.swap(pivot, col);
+        let pivot_row = a[col].clone();
+        for row in col + 1..n {
+            let factor = a[row][col] / pivot_row[col];
+            for (value, pivot) in a[row].iter_mut().zip(&pivot_row).skip(col) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
 /// 系统常量
 pub mod constants {
     use std::time::Duration;
@@ -411,7 +1079,7 @@ pub mod constants {
     pub const MAX_IMAGE_HEIGHT: u32 = 1080;
     
     /// 关节限制
-    pub const MAX_JOINT_VELOCITY: f64 = 3.14; // rad/s
+    pub const MAX_JOINT_VELOCITY: f64 = std::f64::consts::PI; // rad/s
     pub const MAX_JOINT_ACCELERATION: f64 = 10.0; // rad/s²
     
     /// 网络配置
@@ -475,6 +1143,57 @@ mod tests {
         assert!(q_euler.z.abs() < 1e-10);
     }
     
+    #[test]
+    fn test_quaternion_rotation() {
+        let q = Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_2);
+        let v = q.rotate_vector(&Vector3::new(1.0, 0.0, 0.0));
+        assert!((v.x - 0.0).abs() < 1e-10);
+        assert!((v.y - 1.0).abs() < 1e-10);
+        
+        let half = Quaternion::identity().slerp(&q, 0.5);
+        let v = half.rotate_vector(&Vector3::new(1.0, 0.0, 0.0));
+        let expected = std::f64::consts::FRAC_1_SQRT_2;
+        assert!((v.x - expected).abs() < 1e-10);
+        assert!((v.y - expected).abs() < 1e-10);
+    }
+    
+    #[test]
+    fn test_quaternion_euler_roundtrip() {
+        for (roll, pitch, yaw) in [(0.3, -0.2, 1.1), (-1.0, 0.5, -2.5), (0.0, std::f64::consts::FRAC_PI_2, 0.7)] {
+            let (r, p, y) = Quaternion::from_euler(roll, pitch, yaw).to_euler();
+            assert!((r - roll).abs() < 1e-6 && (p - pitch).abs() < 1e-6 && (y - yaw).abs() < 1e-6);
+        }
+    }
+    
+    #[test]
+    fn test_joint_state_multi_turn() {
+        let mut state = JointState::new("base_yaw".to_string());
+        
+        // 正向连续转过两圈多
+        let mut angle = 0.0;
+        for _ in 0..100 {
+            angle += 0.15;
+            state.update_position(angle, true);
+        }
+        assert_eq!(state.turns, 2);
+        assert!(state.position.abs() <= std::f64::consts::PI);
+        assert!((state.unwrapped_position - angle).abs() < 1e-9);
+        
+        // 反向转回
+        for _ in 0..100 {
+            angle -= 0.15;
+            state.update_position(angle, true);
+        }
+        assert_eq!(state.turns, 0);
+        assert!(state.unwrapped_position.abs() < 1e-9);
+        
+        // 非连续关节不回绕
+        let mut head = JointState::new("head_pan".to_string());
+        head.update_position(4.0, false);
+        assert_eq!(head.position, 4.0);
+        assert_eq!(head.unwrapped_position, 4.0);
+    }
+    
     #[test]
     fn test_image_data() {
         let img = ImageData::new(640, 480, 3, ImageFormat::RGB8);
@@ -495,5 +1214,87 @@ mod tests {
         assert_eq!(clamp(15, 0, 10), 10);
         
         assert!((lerp(0.0, 10.0, 0.5) - 5.0).abs() < 1e-10);
+        
+        assert!((normalize_angle(3.0 * std::f64::consts::PI) + std::f64::consts::PI).abs() < 1e-10);
+        assert!((normalize_angle(-0.5) + 0.5).abs() < 1e-10);
+    }
+    
+    #[test]
+    fn test_angle_units() {
+        let angle = Angle::from_degrees(90.0);
+        assert!((angle.radians() - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+        assert!(((angle + angle).degrees() - 180.0).abs() < 1e-10);
+        assert!(Angle::from_degrees(-10.0) < Angle::ZERO);
+        
+        let velocity = AngularVelocity::from_degrees_per_second(180.0);
+        assert!((velocity.over(0.5).degrees() - 90.0).abs() < 1e-10);
+        
+        #[derive(Serialize, Deserialize)]
+        struct Limits {
+            #[serde(with = "serde_degrees")]
+            max_angle: Angle,
+            max_velocity: AngularVelocity,
+        }
+        
+        // 标注了serde_degrees的字段按度读写，其余按弧度
+        let limits: Limits = serde_json::from_str(r#"{"max_angle": 180.0, "max_velocity": 1.0}"#).unwrap();
+        assert!((limits.max_angle.radians() - std::f64::consts::PI).abs() < 1e-10);
+        assert_eq!(limits.max_velocity.radians_per_second(), 1.0);
+        assert_eq!(serde_json::to_value(&limits).unwrap()["max_angle"], serde_json::json!(180.0));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_parse_duration_and_frequency() {
+        let ms = Duration::from_millis(1);
+        assert_eq!(parse_duration("100ms", ms).unwrap(), Duration::from_millis(100));
+        assert_eq!(parse_duration("1m30s", ms).unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1.5 h", ms).unwrap(), Duration::from_secs(5400));
+        // 不带单位时按字段原来的单位
+        assert_eq!(parse_duration("250", ms).unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("2", Duration::from_secs(1)).unwrap(), Duration::from_secs(2));
+        for invalid in ["", "-5ms", "10 parsecs", "ms", "1.2.3s"] {
+            assert!(parse_duration(invalid, ms).is_err(), "{}", invalid);
+        }
+        
+        assert_eq!("100Hz".parse::<Frequency>().unwrap(), Frequency::from_hz(100.0));
+        assert_eq!("1.5 kHz".parse::<Frequency>().unwrap().hz(), 1500.0);
+        assert_eq!("30".parse::<Frequency>().unwrap().hz(), 30.0);
+        assert!("fast".parse::<Frequency>().is_err());
+        assert_eq!(Frequency::from_hz(50.0).period(), Duration::from_millis(20));
+        assert_eq!(serde_json::to_value(Frequency::from_hz(100.0)).unwrap(), serde_json::json!(100.0));
+    }
+    
+    #[test]
+    fn test_backend_mode() {
+        let missing = std::env::temp_dir().join("reachy_no_such_device");
+        assert!(!BackendMode::Auto.use_real(&missing));
+        assert!(BackendMode::Auto.use_real(std::env::temp_dir()));
+        assert!(BackendMode::Real.use_real(&missing));
+        assert!(!BackendMode::Mock.use_real(std::env::temp_dir()));
+        
+        // 未配置的子系统默认为auto
+        let selection: BackendSelection = serde_json::from_str(r#"{"camera": "mock"}"#).unwrap();
+        assert_eq!(selection.camera, BackendMode::Mock);
+        assert_eq!(selection.serial, BackendMode::Auto);
+        assert_eq!("MOCK".parse::<BackendMode>().unwrap(), BackendMode::Mock);
+        assert!("fake".parse::<BackendMode>().is_err());
+    }
+    
+    #[test]
+    fn test_ulid_is_monotonic_and_round_trips() {
+        let ids: Vec<Ulid> = (0..1000).map(|_| Ulid::new()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        
+        let text = ids[0].to_string();
+        assert_eq!(text.len(), 26);
+        assert_eq!(text.parse::<Ulid>().unwrap(), ids[0]);
+        assert_eq!(text.to_lowercase().parse::<Ulid>().unwrap(), ids[0]);
+        // 文本的字典序与数值顺序一致
+        assert!(ids[0].to_string() < ids[999].to_string());
+        assert!(ids[0].timestamp_ms().abs_diff(current_timestamp()) < 60_000);
+        
+        assert!("01ARZ3NDEKTSV4RRFFQ69G5FAU".parse::<Ulid>().is_err());
+        assert!("81ARZ3NDEKTSV4RRFFQ69G5FAV".parse::<Ulid>().is_err());
+        assert_eq!(serde_json::to_value(ids[1]).unwrap(), serde_json::json!(ids[1].to_string()));
+    }
+}
@@ -1,20 +1,38 @@
 //! 配置管理模块
 //! 
 //! 提供统一的配置管理功能，支持从文件、环境变量等多种来源加载配置。
+//! 
+//! 时长和频率字段可以写带单位的值（如 `"100ms"`、`"30s"`、`"100Hz"`），也接受原来的纯数值写法
+//! （时长按字段名中的单位，频率按Hz），保存时仍写成纯数值。
 
 use crate::common::*;
+use crate::joints::JointSetConfig;
+use crate::transforms::TransformConfig;
+use crate::boot::BootSequenceConfig;
+use crate::behavior_pack::BehaviorPackConfig;
+use crate::ble::BleConfig;
+use crate::resource_guard::ResourceGuardConfig;
+use crate::event_filter::EventFilterConfig;
+use crate::config_crypto::{decrypt_sections, encrypt_sections};
+use crate::config_diff::{dry_run, ConfigDryRun};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use log::{info, warn, error, debug};
 
 /// 全局配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub system: SystemConfig,
+    #[serde(default)]
+    pub joints: JointSetConfig,
+    /// 头部和相机的几何参数
+    #[serde(default)]
+    pub transforms: TransformConfig,
     pub vision: VisionConfig,
     pub realtime: RealtimeConfig,
     pub hardware: HardwareConfig,
@@ -23,20 +41,43 @@ pub struct Config {
     pub network: NetworkConfig,
     pub security: SecurityConfig,
     pub performance: PerformanceConfig,
+    #[serde(default)]
+    pub boot: BootSequenceConfig,
+    #[serde(default)]
+    pub behavior_packs: BehaviorPackConfig,
+    #[serde(default)]
+    pub ble: BleConfig,
+    #[serde(default)]
+    pub resources: ResourceGuardConfig,
+    /// 事件去抖、迟滞和聚合规则
+    #[serde(default)]
+    pub events: EventFilterConfig,
 }
 
 impl Default for Config {
     fn default() -> Self {
+        let joints = JointSetConfig::default();
+        let realtime = RealtimeConfig::from_joint_set(&joints);
+        let hardware = HardwareConfig::from_joint_set(&joints);
+        let transforms = TransformConfig::from_joint_set(&joints);
+        
         Self {
             system: SystemConfig::default(),
+            joints,
+            transforms,
             vision: VisionConfig::default(),
-            realtime: RealtimeConfig::default(),
-            hardware: HardwareConfig::default(),
+            realtime,
+            hardware,
             ai: AIConfig::default(),
             logging: LoggingConfig::default(),
             network: NetworkConfig::default(),
             security: SecurityConfig::default(),
             performance: PerformanceConfig::default(),
+            boot: BootSequenceConfig::default(),
+            behavior_packs: BehaviorPackConfig::default(),
+            ble: BleConfig::default(),
+            resources: ResourceGuardConfig::default(),
+            events: EventFilterConfig::default(),
         }
     }
 }
@@ -44,6 +85,8 @@ impl Default for Config {
 impl ConfigValidation for Config {
     fn validate(&self) -> Result<()> {
         self.system.validate()?;
+        self.joints.validate()?;
+        self.transforms.validate()?;
         self.vision.validate()?;
         self.realtime.validate()?;
         self.hardware.validate()?;
@@ -52,6 +95,37 @@ impl ConfigValidation for Config {
         self.network.validate()?;
         self.security.validate()?;
         self.performance.validate()?;
+        self.boot.validate()?;
+        self.behavior_packs.validate()?;
+        self.ble.validate()?;
+        self.resources.validate()?;
+        self.events.validate()?;
+        self.validate_joint_references()?;
+        
+        // 启用认证后，允许任意源会让任何网页借用户的凭据操作机器人
+        if self.security.enabled && self.security.authentication.enabled && self.network.cors.allows_any_origin() {
+            return Err(anyhow::anyhow!("启用认证时CORS不能允许任意源（*），请配置具体的源"));
+        }
+        
+        Ok(())
+    }
+}
+
+impl Config {
+    /// 检查各模块引用的关节都在关节集合中定义
+    fn validate_joint_references(&self) -> Result<()> {
+        let referenced = self.realtime.pid_gains.keys()
+            .chain(self.realtime.joint_limits.keys())
+            .chain(self.hardware.servos.keys())
+            .chain([&self.transforms.head_pan_joint, &self.transforms.head_tilt_joint])
+            .chain(self.transforms.base_yaw_joint.as_ref());
+        
+        for name in referenced {
+            if !self.joints.contains(name) {
+                return Err(anyhow::anyhow!("关节 '{}' 未在关节集合中定义", name));
+            }
+        }
+        
         Ok(())
     }
 }
@@ -68,7 +142,8 @@ pub struct SystemConfig {
     pub data_directory: PathBuf,
     pub log_directory: PathBuf,
     pub temp_directory: PathBuf,
-    pub shutdown_timeout_ms: u64,
+    #[serde(rename = "shutdown_timeout_ms", alias = "shutdown_timeout", with = "serde_duration_ms")]
+    pub shutdown_timeout: Duration,
 }
 
 impl Default for SystemConfig {
@@ -83,7 +158,7 @@ impl Default for SystemConfig {
             data_directory: PathBuf::from("./data"),
             log_directory: PathBuf::from("./logs"),
             temp_directory: PathBuf::from("./temp"),
-            shutdown_timeout_ms: 5000,
+            shutdown_timeout: Duration::from_secs(5),
         }
     }
 }
@@ -102,7 +177,7 @@ impl ConfigValidation for SystemConfig {
             return Err(anyhow::anyhow!("最大线程数必须大于0"));
         }
         
-        if self.shutdown_timeout_ms == 0 {
+        if self.shutdown_timeout.is_zero() {
             return Err(anyhow::anyhow!("关闭超时时间必须大于0"));
         }
         
@@ -309,16 +384,20 @@ impl ConfigValidation for CameraCalibrationConfig {
     }
 }
 
-/// 实时控制配置
+/// 实时控制配置（配置文件中的角度量以度为单位）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RealtimeConfig {
     pub enabled: bool,
-    pub control_frequency: f64,
-    pub sensor_frequency: f64,
+    pub control_frequency: Frequency,
+    pub sensor_frequency: Frequency,
+    /// 最大角加速度（deg/s²）
     pub max_acceleration: f64,
-    pub max_velocity: f64,
-    pub position_tolerance: f64,
-    pub velocity_tolerance: f64,
+    #[serde(with = "serde_degrees")]
+    pub max_velocity: AngularVelocity,
+    #[serde(with = "serde_degrees")]
+    pub position_tolerance: Angle,
+    #[serde(with = "serde_degrees")]
+    pub velocity_tolerance: AngularVelocity,
     pub pid_gains: HashMap<String, PIDGains>,
     pub joint_limits: HashMap<String, JointLimits>,
     pub safety: SafetyConfig,
@@ -326,6 +405,13 @@ pub struct RealtimeConfig {
 
 impl Default for RealtimeConfig {
     fn default() -> Self {
+        Self::from_joint_set(&JointSetConfig::default())
+    }
+}
+
+impl RealtimeConfig {
+    /// 根据关节集合生成各关节的PID参数和限位
+    pub fn from_joint_set(joints: &JointSetConfig) -> Self {
         let mut pid_gains = HashMap::new();
         let mut joint_limits = HashMap::new();
         
@@ -338,35 +424,26 @@ impl Default for RealtimeConfig {
             max_output: 100.0,
         };
         
-        // 默认关节限制
-        let default_limits = JointLimits {
-            min_position: -180.0,
-            max_position: 180.0,
-            max_velocity: 90.0,
-            max_acceleration: 180.0,
-            max_torque: 10.0,
-        };
-        
-        // 为所有关节设置默认值
-        let joint_names = vec![
-            "head_pan", "head_tilt",
-            "left_shoulder_pitch", "left_shoulder_roll", "left_elbow_pitch",
-            "right_shoulder_pitch", "right_shoulder_roll", "right_elbow_pitch",
-        ];
-        
-        for joint_name in joint_names {
-            pid_gains.insert(joint_name.to_string(), default_pid.clone());
-            joint_limits.insert(joint_name.to_string(), default_limits.clone());
+        // 为关节集合中的所有关节设置默认值
+        for joint in &joints.joints {
+            pid_gains.insert(joint.name.clone(), default_pid.clone());
+            joint_limits.insert(joint.name.clone(), JointLimits {
+                min_position: joint.min_position,
+                max_position: joint.max_position,
+                max_velocity: joint.max_velocity,
+                max_acceleration: joint.max_acceleration.to_degrees(),
+                max_torque: joint.max_torque,
+            });
         }
         
         Self {
             enabled: true,
-            control_frequency: 100.0, // 100Hz
-            sensor_frequency: 1000.0, // 1kHz
+            control_frequency: Frequency::from_hz(100.0),
+            sensor_frequency: Frequency::from_hz(1000.0),
             max_acceleration: 180.0,   // deg/s²
-            max_velocity: 90.0,        // deg/s
-            position_tolerance: 1.0,   // degrees
-            velocity_tolerance: 5.0,   // deg/s
+            max_velocity: AngularVelocity::from_degrees_per_second(90.0),
+            position_tolerance: Angle::from_degrees(1.0),
+            velocity_tolerance: AngularVelocity::from_degrees_per_second(5.0),
             pid_gains,
             joint_limits,
             safety: SafetyConfig::default(),
@@ -376,11 +453,11 @@ impl Default for RealtimeConfig {
 
 impl ConfigValidation for RealtimeConfig {
     fn validate(&self) -> Result<()> {
-        if self.control_frequency <= 0.0 {
+        if self.control_frequency.hz() <= 0.0 {
             return Err(anyhow::anyhow!("控制频率必须大于0"));
         }
         
-        if self.sensor_frequency <= 0.0 {
+        if self.sensor_frequency.hz() <= 0.0 {
             return Err(anyhow::anyhow!("传感器频率必须大于0"));
         }
         
@@ -388,15 +465,15 @@ impl ConfigValidation for RealtimeConfig {
             return Err(anyhow::anyhow!("最大加速度必须大于0"));
         }
         
-        if self.max_velocity <= 0.0 {
+        if self.max_velocity <= AngularVelocity::ZERO {
             return Err(anyhow::anyhow!("最大速度必须大于0"));
         }
         
-        if self.position_tolerance <= 0.0 {
+        if self.position_tolerance <= Angle::ZERO {
             return Err(anyhow::anyhow!("位置容差必须大于0"));
         }
         
-        if self.velocity_tolerance <= 0.0 {
+        if self.velocity_tolerance <= AngularVelocity::ZERO {
             return Err(anyhow::anyhow!("速度容差必须大于0"));
         }
         
@@ -454,12 +531,16 @@ impl ConfigValidation for PIDGains {
     }
 }
 
-/// 关节限制
+/// 关节限制（配置文件中的角度量以度为单位）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JointLimits {
-    pub min_position: f64,
-    pub max_position: f64,
-    pub max_velocity: f64,
+    #[serde(with = "serde_degrees")]
+    pub min_position: Angle,
+    #[serde(with = "serde_degrees")]
+    pub max_position: Angle,
+    #[serde(with = "serde_degrees")]
+    pub max_velocity: AngularVelocity,
+    /// 最大角加速度（deg/s²）
     pub max_acceleration: f64,
     pub max_torque: f64,
 }
@@ -470,7 +551,7 @@ impl ConfigValidation for JointLimits {
             return Err(anyhow::anyhow!("最小位置必须小于最大位置"));
         }
         
-        if self.max_velocity <= 0.0 {
+        if self.max_velocity <= AngularVelocity::ZERO {
             return Err(anyhow::anyhow!("最大速度必须大于0"));
         }
         
@@ -486,26 +567,44 @@ impl ConfigValidation for JointLimits {
     }
 }
 
+/// 安全档位，决定自主行为（如跳舞）允许的动作强度
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyProfile {
+    /// 正常使用
+    #[default]
+    Standard,
+    /// 周围有人或靠近桌面边缘时降低动作幅度
+    Reduced,
+    /// 仅允许最小幅度的动作
+    Minimal,
+}
+
 /// 安全配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SafetyConfig {
+    /// 当前安全档位
+    #[serde(default)]
+    pub profile: SafetyProfile,
     pub emergency_stop_enabled: bool,
     pub collision_detection: bool,
     pub force_limit: f64,
     pub temperature_limit: f64,
     pub voltage_range: (f64, f64),
-    pub watchdog_timeout_ms: u64,
+    #[serde(rename = "watchdog_timeout_ms", alias = "watchdog_timeout", with = "serde_duration_ms")]
+    pub watchdog_timeout: Duration,
 }
 
 impl Default for SafetyConfig {
     fn default() -> Self {
         Self {
+            profile: SafetyProfile::default(),
             emergency_stop_enabled: true,
             collision_detection: true,
             force_limit: 50.0,      // N
             temperature_limit: 80.0, // °C
             voltage_range: (11.0, 13.0), // V
-            watchdog_timeout_ms: 1000,
+            watchdog_timeout: Duration::from_secs(1),
         }
     }
 }
@@ -524,7 +623,7 @@ impl ConfigValidation for SafetyConfig {
             return Err(anyhow::anyhow!("电压范围无效"));
         }
         
-        if self.watchdog_timeout_ms == 0 {
+        if self.watchdog_timeout.is_zero() {
             return Err(anyhow::anyhow!("看门狗超时时间必须大于0"));
         }
         
@@ -538,35 +637,42 @@ pub struct HardwareConfig {
     pub enabled: bool,
     pub serial_port: String,
     pub baud_rate: u32,
-    pub timeout_ms: u64,
+    #[serde(rename = "timeout_ms", alias = "timeout", with = "serde_duration_ms")]
+    pub timeout: Duration,
     pub retry_count: u32,
-    pub heartbeat_interval_ms: u64,
+    #[serde(rename = "heartbeat_interval_ms", alias = "heartbeat_interval", with = "serde_duration_ms")]
+    pub heartbeat_interval: Duration,
     pub servos: HashMap<String, ServoConfig>,
     pub sensors: HashMap<String, SensorConfig>,
     pub gpio: GPIOConfig,
+    /// 各子系统使用真实设备还是模拟，运行时决定
+    #[serde(default)]
+    pub backends: BackendSelection,
 }
 
 impl Default for HardwareConfig {
     fn default() -> Self {
+        Self::from_joint_set(&JointSetConfig::default())
+    }
+}
+
+impl HardwareConfig {
+    /// 根据关节集合生成舵机配置
+    pub fn from_joint_set(joints: &JointSetConfig) -> Self {
         let mut servos = HashMap::new();
         let mut sensors = HashMap::new();
         
-        // 默认舵机配置
-        let servo_names = vec![
-            "head_pan", "head_tilt",
-            "left_shoulder_pitch", "left_shoulder_roll", "left_elbow_pitch",
-            "right_shoulder_pitch", "right_shoulder_roll", "right_elbow_pitch",
-        ];
-        
-        for (i, name) in servo_names.iter().enumerate() {
-            servos.insert(name.to_string(), ServoConfig {
-                id: i as u8 + 1,
-                min_angle: -180.0,
-                max_angle: 180.0,
-                center_offset: 0.0,
+        // 每个关节对应一个舵机
+        for joint in &joints.joints {
+            servos.insert(joint.name.clone(), ServoConfig {
+                id: joint.servo_id,
+                min_angle: joint.min_position,
+                max_angle: joint.max_position,
+                center_offset: Angle::ZERO,
                 direction: 1,
                 max_speed: 100,
                 max_torque: 1023,
+                continuous: joint.continuous,
                 enabled: true,
             });
         }
@@ -575,7 +681,7 @@ impl Default for HardwareConfig {
         sensors.insert("imu".to_string(), SensorConfig {
             sensor_type: SensorType::IMU,
             address: 0x68,
-            frequency: 100.0,
+            frequency: Frequency::from_hz(100.0),
             enabled: true,
             calibration_file: Some("imu_calibration.yaml".to_string()),
         });
@@ -583,7 +689,7 @@ impl Default for HardwareConfig {
         sensors.insert("force_torque".to_string(), SensorConfig {
             sensor_type: SensorType::ForceTorque,
             address: 0x40,
-            frequency: 50.0,
+            frequency: Frequency::from_hz(50.0),
             enabled: true,
             calibration_file: Some("ft_calibration.yaml".to_string()),
         });
@@ -592,12 +698,13 @@ impl Default for HardwareConfig {
             enabled: true,
             serial_port: "/dev/ttyUSB0".to_string(),
             baud_rate: 115200,
-            timeout_ms: 1000,
+            timeout: Duration::from_secs(1),
             retry_count: 3,
-            heartbeat_interval_ms: 100,
+            heartbeat_interval: Duration::from_millis(100),
             servos,
             sensors,
             gpio: GPIOConfig::default(),
+            backends: BackendSelection::default(),
         }
     }
 }
@@ -612,11 +719,11 @@ impl ConfigValidation for HardwareConfig {
             return Err(anyhow::anyhow!("波特率必须大于0"));
         }
         
-        if self.timeout_ms == 0 {
+        if self.timeout.is_zero() {
             return Err(anyhow::anyhow!("超时时间必须大于0"));
         }
         
-        if self.heartbeat_interval_ms == 0 {
+        if self.heartbeat_interval.is_zero() {
             return Err(anyhow::anyhow!("心跳间隔必须大于0"));
         }
         
@@ -638,16 +745,22 @@ impl ConfigValidation for HardwareConfig {
     }
 }
 
-/// 舵机配置
+/// 舵机配置（配置文件中的角度量以度为单位）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServoConfig {
     pub id: u8,
-    pub min_angle: f64,
-    pub max_angle: f64,
-    pub center_offset: f64,
+    #[serde(with = "serde_degrees")]
+    pub min_angle: Angle,
+    #[serde(with = "serde_degrees")]
+    pub max_angle: Angle,
+    #[serde(with = "serde_degrees")]
+    pub center_offset: Angle,
     pub direction: i8,
     pub max_speed: u16,
     pub max_torque: u16,
+    /// 连续旋转（轮式）模式
+    #[serde(default)]
+    pub continuous: bool,
     pub enabled: bool,
 }
 
@@ -678,14 +791,14 @@ impl ConfigValidation for ServoConfig {
 pub struct SensorConfig {
     pub sensor_type: SensorType,
     pub address: u8,
-    pub frequency: f64,
+    pub frequency: Frequency,
     pub enabled: bool,
     pub calibration_file: Option<String>,
 }
 
 impl ConfigValidation for SensorConfig {
     fn validate(&self) -> Result<()> {
-        if self.frequency <= 0.0 {
+        if self.frequency.hz() <= 0.0 {
             return Err(anyhow::anyhow!("传感器频率必须大于0"));
         }
         
@@ -876,7 +989,8 @@ pub struct NetworkConfig {
     pub bind_address: String,
     pub port: u16,
     pub max_connections: usize,
-    pub timeout_ms: u64,
+    #[serde(rename = "timeout_ms", alias = "timeout", with = "serde_duration_ms")]
+    pub timeout: Duration,
     pub websocket: WebSocketConfig,
     pub http: HttpConfig,
     pub cors: CorsConfig,
@@ -889,7 +1003,7 @@ impl Default for NetworkConfig {
             bind_address: "0.0.0.0".to_string(),
             port: 8080,
             max_connections: 100,
-            timeout_ms: 30000,
+            timeout: Duration::from_secs(30),
             websocket: WebSocketConfig::default(),
             http: HttpConfig::default(),
             cors: CorsConfig::default(),
@@ -911,7 +1025,7 @@ impl ConfigValidation for NetworkConfig {
             return Err(anyhow::anyhow!("最大连接数必须大于0"));
         }
         
-        if self.timeout_ms == 0 {
+        if self.timeout.is_zero() {
             return Err(anyhow::anyhow!("超时时间必须大于0"));
         }
         
@@ -930,8 +1044,10 @@ pub struct WebSocketConfig {
     pub path: String,
     pub max_frame_size: usize,
     pub max_message_size: usize,
-    pub ping_interval_ms: u64,
-    pub pong_timeout_ms: u64,
+    #[serde(rename = "ping_interval_ms", alias = "ping_interval", with = "serde_duration_ms")]
+    pub ping_interval: Duration,
+    #[serde(rename = "pong_timeout_ms", alias = "pong_timeout", with = "serde_duration_ms")]
+    pub pong_timeout: Duration,
 }
 
 impl Default for WebSocketConfig {
@@ -941,8 +1057,8 @@ impl Default for WebSocketConfig {
             path: "/ws".to_string(),
             max_frame_size: 1024 * 1024,     // 1MB
             max_message_size: 10 * 1024 * 1024, // 10MB
-            ping_interval_ms: 30000,          // 30s
-            pong_timeout_ms: 10000,           // 10s
+            ping_interval: Duration::from_secs(30),
+            pong_timeout: Duration::from_secs(10),
         }
     }
 }
@@ -961,11 +1077,11 @@ impl ConfigValidation for WebSocketConfig {
             return Err(anyhow::anyhow!("最大消息大小必须大于0"));
         }
         
-        if self.ping_interval_ms == 0 {
+        if self.ping_interval.is_zero() {
             return Err(anyhow::anyhow!("Ping间隔必须大于0"));
         }
         
-        if self.pong_timeout_ms == 0 {
+        if self.pong_timeout.is_zero() {
             return Err(anyhow::anyhow!("Pong超时时间必须大于0"));
         }
         
@@ -978,7 +1094,8 @@ impl ConfigValidation for WebSocketConfig {
 pub struct HttpConfig {
     pub enabled: bool,
     pub max_request_size: usize,
-    pub request_timeout_ms: u64,
+    #[serde(rename = "request_timeout_ms", alias = "request_timeout", with = "serde_duration_ms")]
+    pub request_timeout: Duration,
     pub keep_alive: bool,
     pub compression: bool,
     pub static_files: Option<StaticFilesConfig>,
@@ -989,7 +1106,7 @@ impl Default for HttpConfig {
         Self {
             enabled: true,
             max_request_size: 10 * 1024 * 1024, // 10MB
-            request_timeout_ms: 30000,           // 30s
+            request_timeout: Duration::from_secs(30),
             keep_alive: true,
             compression: true,
             static_files: Some(StaticFilesConfig::default()),
@@ -1003,7 +1120,7 @@ impl ConfigValidation for HttpConfig {
             return Err(anyhow::anyhow!("最大请求大小必须大于0"));
         }
         
-        if self.request_timeout_ms == 0 {
+        if self.request_timeout.is_zero() {
             return Err(anyhow::anyhow!("请求超时时间必须大于0"));
         }
         
@@ -1097,26 +1214,55 @@ impl ConfigValidation for CorsConfig {
     }
 }
 
+impl CorsConfig {
+    /// 是否允许任意源
+    pub fn allows_any_origin(&self) -> bool {
+        self.enabled && self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+    
+    /// 请求的源是否允许访问（HTTP跨域请求和WebSocket握手共用）
+    ///
+    /// 没有Origin头的请求来自非浏览器客户端，与Host一致的源为同源访问，都始终允许；
+    /// 配置项可以是完整的源、`*`，或`https://*.example.com`形式的通配子域名。
+    pub fn is_origin_allowed(&self, origin: Option<&str>, host: Option<&str>) -> bool {
+        let Some(origin) = origin else {
+            return true;
+        };
+        let origin = origin.trim().trim_end_matches('/').to_ascii_lowercase();
+        
+        let authority = origin.split_once("://").map(|(_, rest)| rest).unwrap_or(&origin);
+        if host.is_some_and(|host| host.trim().eq_ignore_ascii_case(authority)) {
+            return true;
+        }
+        
+        self.enabled && self.allowed_origins.iter().any(|pattern| {
+            let pattern = pattern.trim().trim_end_matches('/').to_ascii_lowercase();
+            if pattern == "*" {
+                return true;
+            }
+            
+            match pattern.split_once("*.") {
+                // 通配符只匹配至少一级子域名，不匹配裸域名
+                Some((scheme, domain)) => origin.strip_prefix(scheme)
+                    .and_then(|rest| rest.strip_suffix(domain))
+                    .and_then(|subdomain| subdomain.strip_suffix('.'))
+                    .is_some_and(|subdomain| !subdomain.is_empty() && !subdomain.contains(['/', ':', '@'])),
+                None => origin == pattern,
+            }
+        })
+    }
+}
+
 /// 安全配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SecurityConfig {
+    /// 开发环境默认关闭
     pub enabled: bool,
     pub authentication: AuthConfig,
     pub rate_limiting: RateLimitConfig,
     pub encryption: EncryptionConfig,
 }
 
-impl Default for SecurityConfig {
-    fn default() -> Self {
-        Self {
-            enabled: false, // 开发环境默认关闭
-            authentication: AuthConfig::default(),
-            rate_limiting: RateLimitConfig::default(),
-            encryption: EncryptionConfig::default(),
-        }
-    }
-}
-
 impl ConfigValidation for SecurityConfig {
     fn validate(&self) -> Result<()> {
         if self.enabled {
@@ -1201,12 +1347,29 @@ impl ConfigValidation for RateLimitConfig {
     }
 }
 
-/// 加密配置
+/// 加密配置，启用后配置文件中的敏感配置节以密文保存（见`config_crypto`模块）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptionConfig {
     pub enabled: bool,
     pub algorithm: String,
     pub key_size: u32,
+    /// 保存base64密钥的环境变量
+    #[serde(default = "default_key_env")]
+    pub key_env: String,
+    /// 环境变量未设置时读取的密钥文件
+    #[serde(default)]
+    pub key_file: Option<PathBuf>,
+    /// 需要加密的配置节（点分路径）
+    #[serde(default = "default_encrypted_sections")]
+    pub sections: Vec<String>,
+}
+
+fn default_key_env() -> String {
+    "REACHY_CONFIG_KEY".to_string()
+}
+
+fn default_encrypted_sections() -> Vec<String> {
+    vec!["security.authentication".to_string()]
 }
 
 impl Default for EncryptionConfig {
@@ -1215,6 +1378,9 @@ impl Default for EncryptionConfig {
             enabled: false,
             algorithm: "AES-256-GCM".to_string(),
             key_size: 256,
+            key_env: default_key_env(),
+            key_file: None,
+            sections: default_encrypted_sections(),
         }
     }
 }
@@ -1225,6 +1391,10 @@ impl ConfigValidation for EncryptionConfig {
             return Err(anyhow::anyhow!("加密算法不能为空"));
         }
         
+        if self.enabled && (self.algorithm != "AES-256-GCM" || self.key_size != 256) {
+            return Err(anyhow::anyhow!("配置加密只支持AES-256-GCM（256位密钥）"));
+        }
+        
         if self.key_size == 0 {
             return Err(anyhow::anyhow!("密钥大小必须大于0"));
         }
@@ -1239,20 +1409,38 @@ pub struct PerformanceConfig {
     pub thread_pool_size: usize,
     pub async_runtime_threads: usize,
     pub memory_pool_size_mb: usize,
-    pub gc_interval_ms: u64,
+    #[serde(rename = "gc_interval_ms", alias = "gc_interval", with = "serde_duration_ms")]
+    pub gc_interval: Duration,
+    /// 打开后可以通过 profiling::capture_cpu_profile 采集CPU调用栈
     pub profiling_enabled: bool,
+    /// CPU采样频率，避开100Hz以免与周期性任务同步
+    #[serde(default = "default_profiling_frequency_hz")]
+    pub profiling_frequency_hz: u32,
+    /// 单次采样的最长时间（秒）
+    #[serde(default = "default_profiling_max_seconds")]
+    pub profiling_max_seconds: u64,
     pub metrics_enabled: bool,
     pub cache: CacheConfig,
 }
 
+fn default_profiling_frequency_hz() -> u32 {
+    99
+}
+
+fn default_profiling_max_seconds() -> u64 {
+    60
+}
+
 impl Default for PerformanceConfig {
     fn default() -> Self {
         Self {
             thread_pool_size: num_cpus::get(),
             async_runtime_threads: num_cpus::get(),
             memory_pool_size_mb: 512,
-            gc_interval_ms: 60000, // 1 minute
+            gc_interval: Duration::from_secs(60),
             profiling_enabled: false,
+            profiling_frequency_hz: default_profiling_frequency_hz(),
+            profiling_max_seconds: default_profiling_max_seconds(),
             metrics_enabled: true,
             cache: CacheConfig::default(),
         }
@@ -1273,10 +1461,18 @@ impl ConfigValidation for PerformanceConfig {
             return Err(anyhow::anyhow!("内存池大小必须大于0"));
         }
         
-        if self.gc_interval_ms == 0 {
+        if self.gc_interval.is_zero() {
             return Err(anyhow::anyhow!("垃圾回收间隔必须大于0"));
         }
         
+        if self.profiling_frequency_hz == 0 || self.profiling_frequency_hz > 10_000 {
+            return Err(anyhow::anyhow!("性能剖析采样频率必须在1到10000Hz之间"));
+        }
+        
+        if self.profiling_max_seconds == 0 {
+            return Err(anyhow::anyhow!("性能剖析最长采样时间必须大于0"));
+        }
+        
         self.cache.validate()?;
         
         Ok(())
@@ -1288,8 +1484,10 @@ impl ConfigValidation for PerformanceConfig {
 pub struct CacheConfig {
     pub enabled: bool,
     pub max_size_mb: usize,
-    pub ttl_seconds: u64,
-    pub cleanup_interval_ms: u64,
+    #[serde(rename = "ttl_seconds", alias = "ttl", with = "serde_duration_secs")]
+    pub ttl: Duration,
+    #[serde(rename = "cleanup_interval_ms", alias = "cleanup_interval", with = "serde_duration_ms")]
+    pub cleanup_interval: Duration,
 }
 
 impl Default for CacheConfig {
@@ -1297,8 +1495,8 @@ impl Default for CacheConfig {
         Self {
             enabled: true,
             max_size_mb: 256,
-            ttl_seconds: 3600, // 1 hour
-            cleanup_interval_ms: 300000, // 5 minutes
+            ttl: Duration::from_secs(3600),
+            cleanup_interval: Duration::from_secs(300),
         }
     }
 }
@@ -1309,11 +1507,11 @@ impl ConfigValidation for CacheConfig {
             return Err(anyhow::anyhow!("缓存最大大小必须大于0"));
         }
         
-        if self.ttl_seconds == 0 {
+        if self.ttl.is_zero() {
             return Err(anyhow::anyhow!("TTL必须大于0"));
         }
         
-        if self.cleanup_interval_ms == 0 {
+        if self.cleanup_interval.is_zero() {
             return Err(anyhow::anyhow!("清理间隔必须大于0"));
         }
         
@@ -1333,6 +1531,12 @@ pub trait ConfigWatcher: Send + Sync {
     fn on_config_changed(&self, config: &Config) -> Result<()>;
 }
 
+impl Default for ConfigManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ConfigManager {
     /// 创建新的配置管理器
     pub fn new() -> Self {
@@ -1359,7 +1563,16 @@ impl ConfigManager {
         let content = fs::read_to_string(path)
             .map_err(|e| anyhow::anyhow!("读取配置文件失败: {}", e))?;
         
-        self.config = serde_yaml::from_str(&content)
+        let mut document: serde_yaml::Value = serde_yaml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("解析配置文件失败: {}", e))?;
+        
+        // 透明解密敏感配置节
+        let decrypted = decrypt_sections(&mut document)?;
+        if decrypted > 0 {
+            debug!("已解密 {} 个配置节", decrypted);
+        }
+        
+        self.config = serde_yaml::from_value(document)
             .map_err(|e| anyhow::anyhow!("解析配置文件失败: {}", e))?;
         
         // 验证配置
@@ -1384,7 +1597,11 @@ impl ConfigManager {
                 .map_err(|e| anyhow::anyhow!("创建配置目录失败: {}", e))?;
         }
         
-        let content = serde_yaml::to_string(&self.config)
+        let mut document = serde_yaml::to_value(&self.config)
+            .map_err(|e| anyhow::anyhow!("序列化配置失败: {}", e))?;
+        encrypt_sections(&mut document, &self.config.security.encryption)?;
+        
+        let content = serde_yaml::to_string(&document)
             .map_err(|e| anyhow::anyhow!("序列化配置失败: {}", e))?;
         
         fs::write(path, content)
@@ -1443,6 +1660,14 @@ impl ConfigManager {
             }
         }
         
+        // 所有硬件子系统统一使用同一种后端，例如开发机上设为mock
+        if let Ok(backend) = env::var("REACHY_HARDWARE_BACKEND") {
+            match backend.parse::<BackendMode>() {
+                Ok(mode) => self.config.hardware.backends = BackendSelection::all(mode),
+                Err(e) => warn!("{}", e),
+            }
+        }
+        
         debug!("环境变量覆盖完成");
         Ok(())
     }
@@ -1490,7 +1715,7 @@ impl ConfigManager {
     /// 重新加载配置
     pub fn reload(&mut self) -> Result<()> {
         info!("重新加载配置...");
-        self.load_from_file(&self.config_path.clone())?;
+        self.load_from_file(self.config_path.clone())?;
         
         // 通知监听器
         for watcher in &self.watchers {
@@ -1508,6 +1733,11 @@ impl ConfigManager {
         self.config.validate()
     }
     
+    /// 试运行新配置：校验并列出与当前配置的差异，不修改当前配置
+    pub fn dry_run(&self, proposed: &Config) -> ConfigDryRun {
+        dry_run(&self.config, proposed)
+    }
+    
     /// 获取配置摘要
     pub fn get_summary(&self) -> ConfigSummary {
         ConfigSummary {
@@ -1579,7 +1809,7 @@ pub fn init_global_config() -> Result<()> {
 /// 获取全局配置
 pub fn get_global_config() -> Result<&'static Config> {
     unsafe {
-        GLOBAL_CONFIG
+        (*std::ptr::addr_of!(GLOBAL_CONFIG))
             .as_ref()
             .map(|cm| cm.get_config())
             .ok_or_else(|| anyhow::anyhow!("全局配置未初始化"))
@@ -1589,7 +1819,7 @@ pub fn get_global_config() -> Result<&'static Config> {
 /// 获取全局配置管理器
 pub fn get_global_config_manager() -> Result<&'static mut ConfigManager> {
     unsafe {
-        GLOBAL_CONFIG
+        (*std::ptr::addr_of_mut!(GLOBAL_CONFIG))
             .as_mut()
             .ok_or_else(|| anyhow::anyhow!("全局配置管理器未初始化"))
     }
@@ -1626,6 +1856,21 @@ impl ConfigBuilder {
         self
     }
     
+    /// 设置关节集合，并据此重新生成实时控制、硬件和坐标变换的关节配置
+    pub fn joints(mut self, joints: JointSetConfig) -> Self {
+        let realtime = RealtimeConfig::from_joint_set(&joints);
+        let hardware = HardwareConfig::from_joint_set(&joints);
+        let transforms = TransformConfig::from_joint_set(&joints);
+        self.config.realtime.pid_gains = realtime.pid_gains;
+        self.config.realtime.joint_limits = realtime.joint_limits;
+        self.config.hardware.servos = hardware.servos;
+        self.config.transforms.head_pan_joint = transforms.head_pan_joint;
+        self.config.transforms.head_tilt_joint = transforms.head_tilt_joint;
+        self.config.transforms.base_yaw_joint = transforms.base_yaw_joint;
+        self.config.joints = joints;
+        self
+    }
+    
     /// 设置视觉配置
     pub fn vision(mut self, vision_config: VisionConfig) -> Self {
         self.config.vision = vision_config;
@@ -1697,6 +1942,32 @@ mod tests {
         assert!(config.validate().is_ok());
     }
     
+    #[test]
+    fn test_cors_origin_validation() {
+        let cors = CorsConfig {
+            allowed_origins: vec!["http://localhost:3000".to_string(), "https://*.example.com".to_string()],
+            ..CorsConfig::default()
+        };
+        
+        assert!(cors.is_origin_allowed(None, None));
+        assert!(cors.is_origin_allowed(Some("http://localhost:3000/"), None));
+        assert!(cors.is_origin_allowed(Some("https://app.example.com"), None));
+        assert!(!cors.is_origin_allowed(Some("https://example.com"), None));
+        assert!(!cors.is_origin_allowed(Some("https://evil.com/.example.com"), None));
+        assert!(!cors.is_origin_allowed(Some("http://evil.com"), Some("robot.local:8080")));
+        // 同源访问不受配置影响
+        assert!(cors.is_origin_allowed(Some("http://robot.local:8080"), Some("robot.local:8080")));
+        
+        // 启用认证后不能允许任意源
+        let mut config = Config::default();
+        config.security.enabled = true;
+        config.security.authentication.enabled = true;
+        config.security.authentication.jwt_secret = "x".repeat(32);
+        assert!(config.validate().is_err());
+        config.network.cors = cors;
+        assert!(config.validate().is_ok());
+    }
+    
     #[test]
     fn test_system_config_validation() {
         let mut config = SystemConfig::default();
@@ -1727,6 +1998,19 @@ mod tests {
         assert!(config.validate().is_ok());
     }
     
+    #[test]
+    fn test_joint_references_validation() {
+        let mut config = Config::default();
+        assert!(config.hardware.servos.contains_key("left_antenna"));
+        
+        config.joints.joints.retain(|j| j.name != "left_antenna");
+        assert!(config.validate().is_err());
+        
+        let joints = config.joints.clone();
+        let config = ConfigBuilder::new().joints(joints).build().unwrap();
+        assert!(!config.realtime.joint_limits.contains_key("left_antenna"));
+    }
+    
     #[test]
     fn test_logging_config_validation() {
         let mut config = LoggingConfig::default();
@@ -1745,6 +2029,34 @@ mod tests {
         assert!(config.validate().is_err());
     }
     
+    #[test]
+    fn test_duration_and_frequency_accept_units_and_numbers() {
+        let mut value = serde_json::to_value(NetworkConfig::default()).unwrap();
+        value["timeout_ms"] = serde_json::json!("1m30s");
+        // 也可以使用不带单位后缀的字段名
+        value["websocket"].as_object_mut().unwrap().remove("ping_interval_ms");
+        value["websocket"]["ping_interval"] = serde_json::json!("15s");
+        value["websocket"]["pong_timeout_ms"] = serde_json::json!(2500);
+        let config: NetworkConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.timeout, Duration::from_secs(90));
+        assert_eq!(config.websocket.ping_interval, Duration::from_secs(15));
+        assert_eq!(config.websocket.pong_timeout, Duration::from_millis(2500));
+        
+        // 保存时仍写成原来的毫秒数值
+        let saved = serde_json::to_value(&config).unwrap();
+        assert_eq!(saved["timeout_ms"], serde_json::json!(90_000));
+        
+        let mut value = serde_json::to_value(RealtimeConfig::default()).unwrap();
+        value["control_frequency"] = serde_json::json!("200Hz");
+        value["sensor_frequency"] = serde_json::json!(500);
+        let config: RealtimeConfig = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(config.control_frequency.hz(), 200.0);
+        assert_eq!(config.sensor_frequency.period(), Duration::from_millis(2));
+        
+        value["control_frequency"] = serde_json::json!("200rpm");
+        assert!(serde_json::from_value::<RealtimeConfig>(value).is_err());
+    }
+    
     #[test]
     fn test_config_builder() {
         let config = ConfigBuilder::new()
@@ -1761,7 +2073,7 @@ mod tests {
     
     #[test]
     fn test_config_manager() {
-        let mut manager = ConfigManager::new();
+        let manager = ConfigManager::new();
         let config = manager.get_config();
         assert_eq!(config.system.name, "ReachyMini");
     }
@@ -1785,16 +2097,16 @@ mod tests {
     #[test]
     fn test_joint_limits_validation() {
         let limits = JointLimits {
-            min_position: -180.0,
-            max_position: 180.0,
-            max_velocity: 90.0,
+            min_position: Angle::from_degrees(-180.0),
+            max_position: Angle::from_degrees(180.0),
+            max_velocity: AngularVelocity::from_degrees_per_second(90.0),
             max_acceleration: 180.0,
             max_torque: 10.0,
         };
         assert!(limits.validate().is_ok());
         
         let mut invalid_limits = limits.clone();
-        invalid_limits.min_position = 200.0;
+        invalid_limits.min_position = Angle::from_degrees(200.0);
         assert!(invalid_limits.validate().is_err());
     }
     
@@ -1802,12 +2114,13 @@ mod tests {
     fn test_servo_config_validation() {
         let config = ServoConfig {
             id: 1,
-            min_angle: -180.0,
-            max_angle: 180.0,
-            center_offset: 0.0,
+            min_angle: Angle::from_degrees(-180.0),
+            max_angle: Angle::from_degrees(180.0),
+            center_offset: Angle::ZERO,
             direction: 1,
             max_speed: 100,
             max_torque: 1023,
+            continuous: false,
             enabled: true,
         };
         assert!(config.validate().is_ok());
@@ -1829,36 +2142,24 @@ mod tests {
     #[test]
     fn test_feature_detector_type() {
         let detector = FeatureDetectorType::SIFT;
-        match detector {
-            FeatureDetectorType::SIFT => assert!(true),
-            _ => assert!(false),
-        }
+        assert!(matches!(detector, FeatureDetectorType::SIFT));
     }
     
     #[test]
     fn test_sensor_type() {
         let sensor = SensorType::IMU;
-        match sensor {
-            SensorType::IMU => assert!(true),
-            _ => assert!(false),
-        }
+        assert!(matches!(sensor, SensorType::IMU));
     }
     
     #[test]
     fn test_gpio_mode() {
         let mode = GPIOMode::Output;
-        match mode {
-            GPIOMode::Output => assert!(true),
-            _ => assert!(false),
-        }
+        assert!(matches!(mode, GPIOMode::Output));
     }
     
     #[test]
     fn test_log_level() {
         let level = LogLevel::Info;
-        match level {
-            LogLevel::Info => assert!(true),
-            _ => assert!(false),
-        }
+        assert!(matches!(level, LogLevel::Info));
     }
 }
\ No newline at end of file
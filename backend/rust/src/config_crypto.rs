@@ -0,0 +1,221 @@
+//! 配置加密
+//!
+//! 按`EncryptionConfig`用AES-256-GCM加密配置文件中的敏感配置节（如认证密钥），
+//! 加载时透明解密，保存时重新加密，启用加密后绝不以明文写回磁盘。
+//!
+//! 加密后的配置节在文件中替换为一个字符串：
+//!
+//! ```yaml
+//! security:
+//!   authentication: "ENC[AES-256-GCM,<base64(12字节随机数 + 密文 + 认证标签)>]"
+//! ```
+//!
+//! 明文为配置节的JSON，配置节路径作为附加认证数据，密文不能被挪到其他配置节。
+//! 密钥为32字节，以base64形式从环境变量（默认`REACHY_CONFIG_KEY`）或密钥文件读取。
+
+use crate::config::EncryptionConfig;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_yaml::Value;
+use std::fs;
+
+/// 加密值的前缀和后缀
+pub const ENCRYPTED_PREFIX: &str = "ENC[AES-256-GCM,";
+pub const ENCRYPTED_SUFFIX: &str = "]";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// 值是否为加密后的字符串
+pub fn is_encrypted(value: &Value) -> bool {
+    value.as_str().is_some_and(|s| s.starts_with(ENCRYPTED_PREFIX) && s.ends_with(ENCRYPTED_SUFFIX))
+}
+
+/// 配置节加解密器
+pub struct ConfigCipher {
+    cipher: Aes256Gcm,
+}
+
+impl ConfigCipher {
+    pub fn from_key(key: &[u8]) -> Result<Self> {
+        if key.len() != KEY_LEN {
+            return Err(anyhow::anyhow!("配置加密密钥必须为 {} 字节，实际为 {} 字节", KEY_LEN, key.len()));
+        }
+
+        let cipher = Aes256Gcm::new_from_slice(key)
+            .map_err(|_| anyhow::anyhow!("配置加密密钥无效"))?;
+        Ok(Self { cipher })
+    }
+
+    /// 按配置读取密钥：环境变量优先，其次是密钥文件；都没有时返回None
+    pub fn from_config(config: &EncryptionConfig) -> Result<Option<Self>> {
+        let encoded = match std::env::var(&config.key_env) {
+            Ok(value) if !value.trim().is_empty() => value,
+            _ => match &config.key_file {
+                Some(path) => fs::read_to_string(path)
+                    .map_err(|e| anyhow::anyhow!("读取配置加密密钥文件 {} 失败: {}", path.display(), e))?,
+                None => return Ok(None),
+            },
+        };
+
+        let key = BASE64.decode(encoded.trim())
+            .map_err(|e| anyhow::anyhow!("配置加密密钥不是有效的base64: {}", e))?;
+        Self::from_key(&key).map(Some)
+    }
+
+    /// 加密一个配置节，返回写入文件的字符串
+    pub fn encrypt(&self, path: &str, value: &Value) -> Result<String> {
+        let plaintext = serde_json::to_vec(value)?;
+        let nonce: [u8; NONCE_LEN] = rand::random();
+
+        let ciphertext = self.cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: &plaintext, aad: path.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("加密配置节 {} 失败", path))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}{}", ENCRYPTED_PREFIX, BASE64.encode(sealed), ENCRYPTED_SUFFIX))
+    }
+
+    /// 解密一个配置节
+    pub fn decrypt(&self, path: &str, encrypted: &str) -> Result<Value> {
+        let encoded = encrypted.strip_prefix(ENCRYPTED_PREFIX)
+            .and_then(|s| s.strip_suffix(ENCRYPTED_SUFFIX))
+            .ok_or_else(|| anyhow::anyhow!("配置节 {} 不是加密格式", path))?;
+
+        let sealed = BASE64.decode(encoded)
+            .map_err(|e| anyhow::anyhow!("配置节 {} 的密文格式错误: {}", path, e))?;
+        if sealed.len() <= NONCE_LEN {
+            return Err(anyhow::anyhow!("配置节 {} 的密文过短", path));
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self.cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: path.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("解密配置节 {} 失败：密钥错误或内容被篡改", path))?;
+
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+/// 按点分路径查找配置节
+fn section_mut<'a>(document: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    path.split('.').try_fold(document, |value, key| value.get_mut(key))
+}
+
+/// 读取文件中的加密设置，缺省时使用默认值
+pub fn encryption_settings(document: &Value) -> Result<EncryptionConfig> {
+    match document.get("security").and_then(|security| security.get("encryption")) {
+        Some(value) => Ok(serde_yaml::from_value(value.clone())?),
+        None => Ok(EncryptionConfig::default()),
+    }
+}
+
+/// 加密文档中配置的各敏感配置节；启用加密但没有密钥时返回错误，不会写出明文
+pub fn encrypt_sections(document: &mut Value, config: &EncryptionConfig) -> Result<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let cipher = ConfigCipher::from_config(config)?.ok_or_else(|| anyhow::anyhow!(
+        "配置加密已启用但没有密钥（环境变量 {} 或密钥文件），拒绝以明文保存敏感配置", config.key_env
+    ))?;
+
+    for path in &config.sections {
+        if let Some(section) = section_mut(document, path) {
+            if !is_encrypted(section) {
+                *section = Value::String(cipher.encrypt(path, section)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 解密文档中所有加密的配置节，返回解密的配置节数
+pub fn decrypt_sections(document: &mut Value) -> Result<usize> {
+    let config = encryption_settings(document)?;
+    let mut cipher = None;
+    let mut decrypted = 0;
+
+    for path in &config.sections {
+        let Some(section) = section_mut(document, path) else {
+            continue;
+        };
+        if !is_encrypted(section) {
+            continue;
+        }
+
+        if cipher.is_none() {
+            cipher = ConfigCipher::from_config(&config)?;
+        }
+        let cipher = cipher.as_ref().ok_or_else(|| anyhow::anyhow!(
+            "配置节 {} 已加密，但没有密钥（环境变量 {} 或密钥文件）", path, config.key_env
+        ))?;
+
+        let encrypted = section.as_str().unwrap_or_default().to_string();
+        *section = cipher.decrypt(path, &encrypted)?;
+        decrypted += 1;
+    }
+
+    Ok(decrypted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn key_file() -> (std::path::PathBuf, Vec<u8>) {
+        let key: [u8; KEY_LEN] = rand::random();
+        let path = std::env::temp_dir().join(format!("reachy_config_key_{}", std::process::id()));
+        fs::write(&path, BASE64.encode(key)).unwrap();
+        (path, key.to_vec())
+    }
+
+    #[test]
+    fn test_section_roundtrip_and_tamper_detection() {
+        let cipher = ConfigCipher::from_key(&[7u8; KEY_LEN]).unwrap();
+        let section: Value = serde_yaml::from_str("jwt_secret: s3cret\nenabled: true").unwrap();
+
+        let encrypted = cipher.encrypt("security.authentication", &section).unwrap();
+        assert!(is_encrypted(&Value::String(encrypted.clone())));
+        assert!(!encrypted.contains("s3cret"));
+        assert_eq!(cipher.decrypt("security.authentication", &encrypted).unwrap(), section);
+
+        // 挪到其他配置节或换用其他密钥都无法解密
+        assert!(cipher.decrypt("network.cors", &encrypted).is_err());
+        assert!(ConfigCipher::from_key(&[8u8; KEY_LEN]).unwrap().decrypt("security.authentication", &encrypted).is_err());
+        assert!(ConfigCipher::from_key(&[7u8; 16]).is_err());
+    }
+
+    #[test]
+    fn test_document_encrypted_at_rest() {
+        let (path, _) = key_file();
+        let mut config = Config::default();
+        config.security.authentication.jwt_secret = "a-very-long-secret-that-must-stay-private".to_string();
+        config.security.encryption.enabled = true;
+        config.security.encryption.key_env = "REACHY_TEST_UNSET_CONFIG_KEY".to_string();
+        config.security.encryption.key_file = Some(path.clone());
+
+        let mut document = serde_yaml::to_value(&config).unwrap();
+        encrypt_sections(&mut document, &config.security.encryption).unwrap();
+        let written = serde_yaml::to_string(&document).unwrap();
+        assert!(!written.contains("a-very-long-secret"));
+
+        let mut loaded: Value = serde_yaml::from_str(&written).unwrap();
+        assert_eq!(decrypt_sections(&mut loaded).unwrap(), 1);
+        let loaded: Config = serde_yaml::from_value(loaded).unwrap();
+        assert_eq!(loaded.security.authentication.jwt_secret, config.security.authentication.jwt_secret);
+
+        // 没有密钥时拒绝保存明文
+        config.security.encryption.key_file = None;
+        let mut document = serde_yaml::to_value(&config).unwrap();
+        assert!(encrypt_sections(&mut document, &config.security.encryption).is_err());
+
+        let _ = fs::remove_file(path);
+    }
+}
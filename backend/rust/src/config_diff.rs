@@ -0,0 +1,204 @@
+//! 配置差异与试运行
+//! 
+//! 设置界面提交新配置之前先试运行：校验配置，逐项列出与当前配置的差异，
+//! 并提示哪些子系统需要重启才能生效，确认后再调用`update_config`提交。
+//! 差异以点分路径表示（如`realtime.control_frequency`），数组整体比较；
+//! 认证密钥等敏感字段在差异中只显示为`***`。
+
+use crate::common::ConfigValidation;
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 修改后需要重启的配置路径前缀及对应的子系统，先匹配的规则优先
+const RESTART_RULES: &[(&str, &str)] = &[
+    ("system", "整个系统"),
+    ("performance", "整个系统"),
+    ("joints", "实时控制器和硬件接口"),
+    ("hardware", "硬件接口"),
+    ("realtime.control_frequency", "实时控制器"),
+    ("realtime.sensor_frequency", "实时控制器"),
+    ("vision", "视觉模块"),
+    ("ai", "AI引擎"),
+    ("network", "网络服务"),
+];
+
+/// 关闭时需要提醒的安全开关
+const SAFETY_SWITCHES: &[&str] = &["emergency_stop_enabled", "collision_detection"];
+
+/// 差异中需要隐藏取值的路径前缀和字段名
+const SECRET_PREFIXES: &[&str] = &["security.authentication"];
+const SECRET_FIELDS: &[&str] = &["secret", "password", "token", "key"];
+
+/// 一项配置差异，新增或删除的字段对应一侧为None
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl ConfigChange {
+    /// 可读的差异描述
+    pub fn describe(&self) -> String {
+        let show = |value: &Value| match value {
+            Value::String(s) => format!("\"{}\"", s),
+            other => other.to_string(),
+        };
+        
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => format!("{}: {} → {}", self.path, show(old), show(new)),
+            (None, Some(new)) => format!("{}: 新增 {}", self.path, show(new)),
+            (Some(old), None) => format!("{}: 删除 {}", self.path, show(old)),
+            (None, None) => self.path.clone(),
+        }
+    }
+    
+    /// 修改后需要重启的子系统
+    pub fn restart_required(&self) -> Option<&'static str> {
+        RESTART_RULES.iter()
+            .find(|(prefix, _)| path_starts_with(&self.path, prefix))
+            .map(|(_, subsystem)| *subsystem)
+    }
+}
+
+/// 试运行结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigDryRun {
+    /// 新配置是否通过校验
+    pub valid: bool,
+    pub errors: Vec<String>,
+    pub changes: Vec<ConfigChange>,
+    /// 每项差异的可读描述，与changes一一对应
+    pub summary: Vec<String>,
+    /// 需要重启的子系统（去重）
+    pub restart_required: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+fn path_starts_with(path: &str, prefix: &str) -> bool {
+    path == prefix || path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('.'))
+}
+
+fn is_secret(path: &str) -> bool {
+    let field = path.rsplit('.').next().unwrap_or(path).to_ascii_lowercase();
+    SECRET_PREFIXES.iter().any(|prefix| path_starts_with(path, prefix))
+        || SECRET_FIELDS.iter().any(|name| field.contains(name))
+}
+
+/// 逐项比较两份JSON，对象按键递归，其余类型整体比较；结果按路径排序
+pub fn diff_values(old: &Value, new: &Value) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    collect_changes("", Some(old), Some(new), &mut changes);
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+fn collect_changes(path: &str, old: Option<&Value>, new: Option<&Value>, changes: &mut Vec<ConfigChange>) {
+    if let (Some(Value::Object(old)), Some(Value::Object(new))) = (old, new) {
+        for key in old.keys().chain(new.keys().filter(|key| !old.contains_key(*key))) {
+            let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+            collect_changes(&child, old.get(key), new.get(key), changes);
+        }
+        return;
+    }
+    
+    if old == new {
+        return;
+    }
+    
+    let redact = |value: Option<&Value>| value.map(|v| if is_secret(path) { Value::String("***".to_string()) } else { v.clone() });
+    changes.push(ConfigChange {
+        path: path.to_string(),
+        old: redact(old),
+        new: redact(new),
+    });
+}
+
+/// 试运行：校验新配置并列出与当前配置的差异，不做任何修改
+pub fn dry_run(active: &Config, proposed: &Config) -> ConfigDryRun {
+    let errors = match proposed.validate() {
+        Ok(()) => Vec::new(),
+        Err(e) => vec![e.to_string()],
+    };
+    
+    let to_value = |config: &Config| serde_json::to_value(config).unwrap_or(Value::Null);
+    let changes = diff_values(&to_value(active), &to_value(proposed));
+    
+    let mut restart_required: Vec<String> = Vec::new();
+    let mut warnings = Vec::new();
+    
+    for change in &changes {
+        if let Some(subsystem) = change.restart_required() {
+            if !restart_required.iter().any(|s| s == subsystem) {
+                restart_required.push(subsystem.to_string());
+            }
+        }
+        
+        // 关闭安全功能时单独提醒
+        let disabled = change.old == Some(Value::Bool(true)) && change.new == Some(Value::Bool(false));
+        if disabled && SAFETY_SWITCHES.iter().any(|field| change.path.ends_with(field)) {
+            warnings.push(format!("{} 将被关闭，请确认机器人周围安全", change.path));
+        }
+    }
+    
+    for subsystem in &restart_required {
+        warnings.push(format!("需要重启{}才能生效", subsystem));
+    }
+    
+    ConfigDryRun {
+        valid: errors.is_empty(),
+        errors,
+        summary: changes.iter().map(ConfigChange::describe).collect(),
+        changes,
+        restart_required,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LogLevel;
+    
+    #[test]
+    fn test_dry_run_reports_changes_and_restarts() {
+        let active = Config::default();
+        let mut proposed = active.clone();
+        proposed.realtime.control_frequency = proposed.realtime.control_frequency * 2.0;
+        proposed.logging.level = LogLevel::Debug;
+        proposed.security.authentication.jwt_secret = "new-secret".to_string();
+        proposed.realtime.safety.emergency_stop_enabled = false;
+        
+        let result = dry_run(&active, &proposed);
+        assert!(result.valid);
+        
+        let paths: Vec<&str> = result.changes.iter().map(|c| c.path.as_str()).collect();
+        assert!(paths.contains(&"realtime.control_frequency"));
+        assert!(paths.contains(&"logging.level"));
+        assert_eq!(result.summary.len(), result.changes.len());
+        
+        // 只有控制频率需要重启实时控制器，日志级别和安全开关可以直接生效
+        assert_eq!(result.restart_required, vec!["实时控制器".to_string()]);
+        assert!(result.warnings.iter().any(|w| w.contains("emergency_stop_enabled")));
+        
+        // 敏感字段不出现在差异中
+        let secret = result.changes.iter().find(|c| c.path == "security.authentication.jwt_secret").unwrap();
+        assert_eq!(secret.new, Some(Value::String("***".to_string())));
+        assert!(!result.summary.iter().any(|line| line.contains("new-secret")));
+    }
+    
+    #[test]
+    fn test_dry_run_invalid_config() {
+        let active = Config::default();
+        let mut proposed = active.clone();
+        proposed.network.port = 0;
+        
+        let result = dry_run(&active, &proposed);
+        assert!(!result.valid);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.summary, vec!["network.port: 8080 → 0".to_string()]);
+        
+        assert!(dry_run(&active, &active).changes.is_empty());
+    }
+}
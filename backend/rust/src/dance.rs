@@ -0,0 +1,594 @@
+//! 音频驱动的跳舞模块
+//! 
+//! 对输入音频逐帧做FFT，提取低频段能量检测节拍并估计节奏（BPM），
+//! 再按节拍相位驱动预设的舞蹈动画层（点头、摇摆、天线交替等）。
+//! 输出幅度随音乐能量变化，并受当前安全档位的强度上限约束，
+//! 最终目标位置被限制在各关节自身的限位内。
+
+use crate::common::*;
+use crate::config::SafetyProfile;
+use crate::joints::JointSetConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::f64::consts::PI;
+
+/// 节拍能量历史长度（帧），约为1秒的音频
+const ENERGY_HISTORY_LEN: usize = 43;
+
+/// 参与节奏估计的节拍间隔数量
+const TEMPO_HISTORY_LEN: usize = 16;
+
+/// 动画层波形（相位0对应节拍时刻）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DanceShape {
+    /// 正弦往复摆动，-1到1
+    Sway,
+    /// 节拍时刻下压，拍间回到中位，-1到0
+    Bounce,
+    /// 节拍时刻冲出后指数衰减，0到1
+    Pulse,
+}
+
+impl DanceShape {
+    fn value(&self, phase: f64) -> f64 {
+        match self {
+            DanceShape::Sway => (phase * 2.0 * PI).sin(),
+            DanceShape::Bounce => -(0.5 + 0.5 * (phase * 2.0 * PI).cos()),
+            DanceShape::Pulse => (-6.0 * phase).exp(),
+        }
+    }
+}
+
+/// 单个舞蹈动画层
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DanceLayer {
+    pub joint: String,
+    /// 满强度时的幅度（rad）
+    pub amplitude: f64,
+    /// 一个动作周期占用的拍数
+    pub beats_per_cycle: f64,
+    /// 相位偏移（周期的比例，0-1），用于让左右两侧交替
+    pub phase_offset: f64,
+    /// 音乐能量低于该值时本层不参与（0-1）
+    pub min_energy: f64,
+    pub shape: DanceShape,
+}
+
+impl DanceLayer {
+    fn new(joint: &str, amplitude: f64, beats_per_cycle: f64, shape: DanceShape) -> Self {
+        Self {
+            joint: joint.to_string(),
+            amplitude,
+            beats_per_cycle,
+            phase_offset: 0.0,
+            min_energy: 0.0,
+            shape,
+        }
+    }
+    
+    fn with_phase(mut self, phase_offset: f64) -> Self {
+        self.phase_offset = phase_offset;
+        self
+    }
+    
+    fn with_min_energy(mut self, min_energy: f64) -> Self {
+        self.min_energy = min_energy;
+        self
+    }
+}
+
+/// 跳舞模式配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DanceConfig {
+    pub sample_rate: u32,
+    /// FFT帧长（采样点数，必须为2的幂）
+    pub frame_size: usize,
+    /// 用于节拍检测的低频段（Hz）
+    pub bass_band: (f64, f64),
+    /// 低频能量超过近期平均值的倍数时判定为节拍
+    pub beat_sensitivity: f64,
+    /// 相邻节拍的最小间隔（毫秒）
+    pub min_beat_interval_ms: u64,
+    /// 节奏估计范围（BPM），超出范围的间隔按倍频折叠
+    pub tempo_range: (f64, f64),
+    /// 低于该RMS的音频视为静音
+    pub silence_rms: f32,
+    /// 超过该时长没有检测到节拍时停止跳舞（毫秒）
+    pub beat_timeout_ms: u64,
+    pub layers: Vec<DanceLayer>,
+    /// 各安全档位允许的最大强度（0-1）
+    pub intensity_limits: HashMap<SafetyProfile, f64>,
+}
+
+impl Default for DanceConfig {
+    fn default() -> Self {
+        let layers = vec![
+            DanceLayer::new("head_tilt", 0.15, 1.0, DanceShape::Bounce),
+            DanceLayer::new("head_pan", 0.25, 2.0, DanceShape::Sway).with_min_energy(0.3),
+            DanceLayer::new("left_antenna", 0.6, 2.0, DanceShape::Sway),
+            DanceLayer::new("right_antenna", 0.6, 2.0, DanceShape::Sway).with_phase(0.5),
+            DanceLayer::new("base_yaw", 0.3, 4.0, DanceShape::Sway).with_min_energy(0.6),
+        ];
+        
+        let intensity_limits = HashMap::from([
+            (SafetyProfile::Standard, 1.0),
+            (SafetyProfile::Reduced, 0.5),
+            (SafetyProfile::Minimal, 0.2),
+        ]);
+        
+        Self {
+            sample_rate: 16000,
+            frame_size: 512,
+            bass_band: (40.0, 200.0),
+            beat_sensitivity: 1.4,
+            min_beat_interval_ms: 250,
+            tempo_range: (60.0, 180.0),
+            silence_rms: 0.01,
+            beat_timeout_ms: 2000,
+            layers,
+            intensity_limits,
+        }
+    }
+}
+
+impl DanceConfig {
+    /// 指定安全档位下的强度上限，未配置的档位不允许跳舞
+    pub fn intensity_limit(&self, profile: SafetyProfile) -> f64 {
+        self.intensity_limits.get(&profile).copied().unwrap_or(0.0)
+    }
+    
+    /// 检查动画层引用的关节都存在
+    pub fn validate_joints(&self, joints: &JointSetConfig) -> Result<()> {
+        for layer in &self.layers {
+            if !joints.contains(&layer.joint) {
+                return Err(anyhow::anyhow!("舞蹈动画层引用了未知关节: {}", layer.joint));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+impl ConfigValidation for DanceConfig {
+    fn validate(&self) -> Result<()> {
+        if self.sample_rate == 0 {
+            return Err(anyhow::anyhow!("采样率必须大于0"));
+        }
+        
+        if self.frame_size < 64 || !self.frame_size.is_power_of_two() {
+            return Err(anyhow::anyhow!("FFT帧长必须是不小于64的2的幂"));
+        }
+        
+        let nyquist = self.sample_rate as f64 * 0.5;
+        if !(self.bass_band.0 >= 0.0 && self.bass_band.0 < self.bass_band.1 && self.bass_band.1 <= nyquist) {
+            return Err(anyhow::anyhow!("低频段范围无效"));
+        }
+        
+        if self.beat_sensitivity <= 1.0 {
+            return Err(anyhow::anyhow!("节拍灵敏度必须大于1"));
+        }
+        
+        if !(self.tempo_range.0 > 0.0 && self.tempo_range.0 * 2.0 <= self.tempo_range.1) {
+            return Err(anyhow::anyhow!("节奏范围无效，上限至少为下限的两倍"));
+        }
+        
+        for layer in &self.layers {
+            if !(layer.amplitude >= 0.0 && layer.amplitude.is_finite()) {
+                return Err(anyhow::anyhow!("舞蹈动画层 '{}' 的幅度无效", layer.joint));
+            }
+            if layer.beats_per_cycle <= 0.0 {
+                return Err(anyhow::anyhow!("舞蹈动画层 '{}' 的周期拍数必须大于0", layer.joint));
+            }
+            if !(0.0..=1.0).contains(&layer.min_energy) {
+                return Err(anyhow::anyhow!("舞蹈动画层 '{}' 的能量阈值必须在0-1之间", layer.joint));
+            }
+        }
+        
+        for (profile, limit) in &self.intensity_limits {
+            if !(0.0..=1.0).contains(limit) {
+                return Err(anyhow::anyhow!("安全档位 {:?} 的强度上限必须在0-1之间", profile));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 原地基2 FFT，输入长度必须为2的幂
+pub(crate) fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    
+    // 位反转重排
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// 一帧音频的分析结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioAnalysis {
+    /// 本帧是否检测到节拍
+    pub beat: bool,
+    /// 归一化的音乐能量（0-1）
+    pub energy: f64,
+    /// 估计的节奏（BPM），节拍不足时为None
+    pub tempo_bpm: Option<f64>,
+}
+
+/// 基于低频能量突变的节拍检测器
+#[derive(Debug, Clone)]
+pub struct BeatDetector {
+    config: DanceConfig,
+    window: Vec<f64>,
+    bass_history: VecDeque<f64>,
+    intervals: VecDeque<f64>,
+    last_beat: Option<u64>,
+    /// 平滑后的响度（约1秒时间常数），鼓点之间不会骤降
+    loudness: f64,
+    /// 近期响度峰值，用于能量归一化
+    peak_loudness: f64,
+}
+
+impl BeatDetector {
+    pub fn new(config: DanceConfig) -> Result<Self> {
+        config.validate()?;
+        
+        // Hann窗
+        let n = config.frame_size;
+        let window = (0..n)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / (n - 1) as f64).cos())
+            .collect();
+        
+        Ok(Self {
+            config,
+            window,
+            bass_history: VecDeque::with_capacity(ENERGY_HISTORY_LEN),
+            intervals: VecDeque::with_capacity(TEMPO_HISTORY_LEN),
+            last_beat: None,
+            loudness: 0.0,
+            peak_loudness: 0.0,
+        })
+    }
+    
+    /// 分析一帧音频（多于帧长时只取最新的部分），now为帧的时间戳（毫秒）
+    pub fn process(&mut self, samples: &[f32], now: u64) -> AudioAnalysis {
+        let n = self.config.frame_size;
+        let frame = &samples[samples.len().saturating_sub(n)..];
+        
+        let rms = if frame.is_empty() {
+            0.0
+        } else {
+            (frame.iter().map(|&s| (s as f64) * (s as f64)).sum::<f64>() / frame.len() as f64).sqrt()
+        };
+        
+        if rms < self.config.silence_rms as f64 {
+            self.loudness *= 0.9;
+            return AudioAnalysis { beat: false, energy: self.energy(), tempo_bpm: self.tempo_bpm() };
+        }
+        
+        // 能量按缓慢衰减的响度峰值归一化，适应不同的音量
+        self.loudness = 0.95 * self.loudness + 0.05 * rms;
+        self.peak_loudness = self.loudness.max(self.peak_loudness * 0.999);
+        
+        let bass = self.bass_energy(frame);
+        let average = if self.bass_history.is_empty() {
+            0.0
+        } else {
+            self.bass_history.iter().sum::<f64>() / self.bass_history.len() as f64
+        };
+        
+        let ready = self.bass_history.len() >= ENERGY_HISTORY_LEN / 4;
+        let spaced = self.last_beat.is_none_or(|last| now.saturating_sub(last) >= self.config.min_beat_interval_ms);
+        let beat = ready && spaced && bass > average * self.config.beat_sensitivity;
+        
+        if beat {
+            if let Some(last) = self.last_beat {
+                if self.intervals.len() == TEMPO_HISTORY_LEN {
+                    self.intervals.pop_front();
+                }
+                self.intervals.push_back(now.saturating_sub(last) as f64 / 1000.0);
+            }
+            self.last_beat = Some(now);
+        }
+        
+        if self.bass_history.len() == ENERGY_HISTORY_LEN {
+            self.bass_history.pop_front();
+        }
+        self.bass_history.push_back(bass);
+        
+        AudioAnalysis { beat, energy: self.energy(), tempo_bpm: self.tempo_bpm() }
+    }
+    
+    /// 当前响度相对近期峰值的比例（0-1）
+    pub fn energy(&self) -> f64 {
+        if self.peak_loudness > 0.0 {
+            clamp(self.loudness / self.peak_loudness, 0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+    
+    fn bass_energy(&self, frame: &[f32]) -> f64 {
+        let n = self.config.frame_size;
+        let mut re = vec![0.0; n];
+        let mut im = vec![0.0; n];
+        for (i, &sample) in frame.iter().enumerate() {
+            re[i] = sample as f64 * self.window[i];
+        }
+        
+        fft(&mut re, &mut im);
+        
+        let bin_hz = self.config.sample_rate as f64 / n as f64;
+        let low = (self.config.bass_band.0 / bin_hz).floor() as usize;
+        let high = ((self.config.bass_band.1 / bin_hz).ceil() as usize).min(n / 2);
+        
+        (low..=high).map(|k| re[k] * re[k] + im[k] * im[k]).sum::<f64>()
+    }
+    
+    /// 节拍间隔的中位数换算成BPM，超出范围的间隔按倍频折叠
+    pub fn tempo_bpm(&self) -> Option<f64> {
+        if self.intervals.len() < 2 {
+            return None;
+        }
+        
+        let (min_bpm, max_bpm) = self.config.tempo_range;
+        let mut tempos: Vec<f64> = self.intervals.iter()
+            .filter(|interval| **interval > 0.0)
+            .map(|interval| {
+                let mut bpm = 60.0 / interval;
+                while bpm > max_bpm {
+                    bpm *= 0.5;
+                }
+                while bpm < min_bpm {
+                    bpm *= 2.0;
+                }
+                bpm
+            })
+            .collect();
+        if tempos.is_empty() {
+            return None;
+        }
+        
+        tempos.sort_by(|a, b| a.total_cmp(b));
+        Some(tempos[tempos.len() / 2])
+    }
+    
+    pub fn last_beat(&self) -> Option<u64> {
+        self.last_beat
+    }
+    
+    pub fn reset(&mut self) {
+        self.bass_history.clear();
+        self.intervals.clear();
+        self.last_beat = None;
+        self.loudness = 0.0;
+        self.peak_loudness = 0.0;
+    }
+}
+
+/// 跳舞控制器：把音频分析结果转换成各关节的目标位置
+#[derive(Debug, Clone)]
+pub struct DanceController {
+    config: DanceConfig,
+    detector: BeatDetector,
+    /// (关节名称, 最小位置, 最大位置)
+    limits: HashMap<String, (f64, f64)>,
+    /// 节拍相位的锚点：最近一次节拍时刻及当时累计的拍数
+    beat_anchor: Option<(u64, f64)>,
+    beat_count: f64,
+    energy: f64,
+}
+
+impl DanceController {
+    pub fn new(config: DanceConfig, joints: &JointSetConfig) -> Result<Self> {
+        config.validate_joints(joints)?;
+        let detector = BeatDetector::new(config.clone())?;
+        
+        let limits = config.layers.iter()
+            .filter_map(|layer| joints.get(&layer.joint))
+            .map(|j| (j.name.clone(), (j.min_position.radians(), j.max_position.radians())))
+            .collect();
+        
+        Ok(Self {
+            config,
+            detector,
+            limits,
+            beat_anchor: None,
+            beat_count: 0.0,
+            energy: 0.0,
+        })
+    }
+    
+    pub fn process_audio(&mut self, samples: &[f32]) -> AudioAnalysis {
+        self.process_audio_at(samples, current_timestamp())
+    }
+    
+    fn process_audio_at(&mut self, samples: &[f32], now: u64) -> AudioAnalysis {
+        let analysis = self.detector.process(samples, now);
+        self.energy = analysis.energy;
+        
+        if analysis.beat {
+            // 每个检测到的节拍把相位对齐到整数拍，避免节奏估计的误差累积
+            self.beat_count = match self.beat_anchor {
+                Some(_) => self.beat_count.floor() + 1.0,
+                None => 0.0,
+            };
+            self.beat_anchor = Some((now, self.beat_count));
+        }
+        
+        analysis
+    }
+    
+    /// 当前是否在跟随音乐跳舞
+    pub fn is_dancing(&self) -> bool {
+        self.is_dancing_at(current_timestamp())
+    }
+    
+    fn is_dancing_at(&self, now: u64) -> bool {
+        self.detector.tempo_bpm().is_some()
+            && self.detector.last_beat()
+                .is_some_and(|last| now.saturating_sub(last) <= self.config.beat_timeout_ms)
+    }
+    
+    /// 计算各关节的目标位置，没有在跳舞时返回None
+    pub fn targets(&mut self, profile: SafetyProfile) -> Option<HashMap<String, f64>> {
+        self.targets_at(profile, current_timestamp())
+    }
+    
+    fn targets_at(&mut self, profile: SafetyProfile, now: u64) -> Option<HashMap<String, f64>> {
+        if !self.is_dancing_at(now) {
+            return None;
+        }
+        
+        let tempo = self.detector.tempo_bpm()?;
+        let (anchor_time, anchor_beats) = self.beat_anchor?;
+        let beats = anchor_beats + now.saturating_sub(anchor_time) as f64 / 1000.0 * tempo / 60.0;
+        self.beat_count = beats;
+        
+        let intensity = self.energy.min(self.config.intensity_limit(profile));
+        
+        let mut targets = HashMap::new();
+        for layer in &self.config.layers {
+            let Some(&(min, max)) = self.limits.get(&layer.joint) else {
+                continue;
+            };
+            
+            let weight = if self.energy >= layer.min_energy { intensity } else { 0.0 };
+            let phase = (beats / layer.beats_per_cycle + layer.phase_offset).rem_euclid(1.0);
+            let offset = layer.amplitude * weight * layer.shape.value(phase);
+            
+            // 多个动画层作用于同一关节时叠加
+            let center = (min + max) * 0.5;
+            let target = targets.entry(layer.joint.clone()).or_insert(center);
+            *target = clamp(*target + offset, min, max);
+        }
+        
+        Some(targets)
+    }
+    
+    pub fn reset(&mut self) {
+        self.detector.reset();
+        self.beat_anchor = None;
+        self.beat_count = 0.0;
+        self.energy = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 120 BPM的合成鼓点：每拍开头一段60Hz的低频冲击，其余为微弱的高频底噪
+    fn drum_frame(config: &DanceConfig, frame_index: usize, frame_ms: u64) -> Vec<f32> {
+        let start_ms = frame_index as u64 * frame_ms;
+        (0..config.frame_size).map(|i| {
+            let t = (start_ms as f64 / 1000.0) + i as f64 / config.sample_rate as f64;
+            let since_beat = t % 0.5;
+            let kick = if since_beat < 0.05 { 0.8 * (2.0 * PI * 60.0 * t).sin() } else { 0.0 };
+            (kick + 0.05 * (2.0 * PI * 3000.0 * t).sin()) as f32
+        }).collect()
+    }
+    
+    #[test]
+    fn test_beat_detection_and_tempo() {
+        let config = DanceConfig::default();
+        assert!(config.validate().is_ok());
+        
+        let mut detector = BeatDetector::new(config.clone()).unwrap();
+        let frame_ms = 1000 * config.frame_size as u64 / config.sample_rate as u64;
+        
+        let mut beats = 0;
+        let mut analysis = None;
+        for frame in 0..(8000 / frame_ms as usize) {
+            let result = detector.process(&drum_frame(&config, frame, frame_ms), frame as u64 * frame_ms);
+            if result.beat {
+                beats += 1;
+            }
+            analysis = Some(result);
+        }
+        
+        let analysis = analysis.unwrap();
+        assert!(beats >= 12, "beats {}", beats);
+        let tempo = analysis.tempo_bpm.unwrap();
+        assert!((tempo - 120.0).abs() < 10.0, "tempo {}", tempo);
+        assert!(analysis.energy > 0.0 && analysis.energy <= 1.0);
+        
+        // 静音时不再检测到节拍
+        let silence = vec![0.0; config.frame_size];
+        assert!(!detector.process(&silence, 9000).beat);
+    }
+    
+    #[test]
+    fn test_dance_intensity_limited_by_safety_profile() {
+        let joints = JointSetConfig::default();
+        let config = DanceConfig::default();
+        let mut controller = DanceController::new(config.clone(), &joints).unwrap();
+        let frame_ms = 1000 * config.frame_size as u64 / config.sample_rate as u64;
+        
+        assert!(controller.targets_at(SafetyProfile::Standard, 0).is_none());
+        
+        let frames = 6000 / frame_ms as usize;
+        for frame in 0..frames {
+            controller.process_audio_at(&drum_frame(&config, frame, frame_ms), frame as u64 * frame_ms);
+        }
+        let now = frames as u64 * frame_ms;
+        
+        let peak = |controller: &mut DanceController, profile| {
+            let mut peak: f64 = 0.0;
+            for step in 0..40 {
+                let targets = controller.targets_at(profile, now + step * 25).unwrap();
+                for (name, position) in &targets {
+                    let joint = joints.get(name).unwrap();
+                    let (min, max) = (joint.min_position.radians(), joint.max_position.radians());
+                    assert!(*position >= min && *position <= max);
+                    peak = peak.max((position - (min + max) * 0.5).abs());
+                }
+            }
+            peak
+        };
+        
+        let standard = peak(&mut controller, SafetyProfile::Standard);
+        let minimal = peak(&mut controller, SafetyProfile::Minimal);
+        assert!(standard > 0.05, "standard {}", standard);
+        let max_amplitude = config.layers.iter().map(|l| l.amplitude).fold(0.0, f64::max);
+        assert!(minimal < standard);
+        assert!(minimal <= max_amplitude * config.intensity_limit(SafetyProfile::Minimal) + 1e-9);
+        
+        // 节拍消失一段时间后停止跳舞
+        assert!(controller.targets_at(SafetyProfile::Standard, now + config.beat_timeout_ms + 1000).is_none());
+        
+        let mut invalid = config.clone();
+        invalid.layers.push(DanceLayer::new("tail", 0.1, 1.0, DanceShape::Pulse));
+        assert!(DanceController::new(invalid, &joints).is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! 关节动力学辨识
+//! 
+//! 用安全的激励轨迹驱动单个关节，记录速度、加速度和力矩，按
+//! `力矩 = 惯量·加速度 + 粘滞摩擦·速度 + 库仑摩擦·sign(速度)`
+//! 做最小二乘拟合。辨识出的参数用于控制输出的前馈补偿，也可以写入导出的仿真模型。
+//!
+//! 激励轨迹是以当前位置为中心的多谐波正弦，两端加窗从静止开始、回到静止结束，
+//! 幅度按关节限位以及最大速度、加速度的安全裕度缩放。
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// 低于该速度（rad/s）的样本方向不明确，不参与拟合
+const VELOCITY_EPSILON: f64 = 1e-3;
+
+/// 拟合至少需要的样本数
+const MIN_SAMPLES: usize = 20;
+
+/// 单个关节的动力学参数
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DynamicsParams {
+    /// 关节轴上的等效转动惯量（kg·m²）
+    pub inertia: f64,
+    /// 粘滞摩擦系数（N·m·s/rad）
+    pub viscous_friction: f64,
+    /// 库仑摩擦力矩（N·m）
+    pub coulomb_friction: f64,
+}
+
+impl ConfigValidation for DynamicsParams {
+    fn validate(&self) -> Result<()> {
+        for value in [self.inertia, self.viscous_friction, self.coulomb_friction] {
+            if !(value >= 0.0 && value.is_finite()) {
+                return Err(anyhow::anyhow!("惯量和摩擦参数必须为非负数"));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+impl DynamicsParams {
+    /// 按期望速度和加速度计算前馈力矩
+    pub fn feedforward(&self, velocity: f64, acceleration: f64) -> f64 {
+        let direction = if velocity.abs() > VELOCITY_EPSILON { velocity.signum() } else { 0.0 };
+        self.inertia * acceleration + self.viscous_friction * velocity + self.coulomb_friction * direction
+    }
+}
+
+/// 激励轨迹参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcitationConfig {
+    /// 偏离中心的最大幅度（rad）
+    pub amplitude: f64,
+    /// 激励时长（秒）
+    pub duration: f64,
+    /// 基频（Hz），各谐波为基频的整数倍
+    pub base_frequency: f64,
+    pub harmonics: usize,
+    /// 峰值速度、加速度占关节上限的比例
+    pub safety_margin: f64,
+}
+
+impl Default for ExcitationConfig {
+    fn default() -> Self {
+        Self {
+            amplitude: 0.3,
+            duration: 10.0,
+            base_frequency: 0.2,
+            harmonics: 3,
+            safety_margin: 0.5,
+        }
+    }
+}
+
+impl ConfigValidation for ExcitationConfig {
+    fn validate(&self) -> Result<()> {
+        if self.amplitude <= 0.0 {
+            return Err(anyhow::anyhow!("激励幅度必须为正数"));
+        }
+        
+        if self.duration <= 0.0 || self.base_frequency <= 0.0 {
+            return Err(anyhow::anyhow!("激励时长和基频必须为正数"));
+        }
+        
+        if self.duration * self.base_frequency < 1.0 {
+            return Err(anyhow::anyhow!("激励时长至少要覆盖一个基频周期"));
+        }
+        
+        if self.harmonics == 0 {
+            return Err(anyhow::anyhow!("谐波数不能为0"));
+        }
+        
+        if !(self.safety_margin > 0.0 && self.safety_margin <= 1.0) {
+            return Err(anyhow::anyhow!("安全裕度必须在0-1之间"));
+        }
+        
+        Ok(())
+    }
+}
+
+fn peak_abs(values: impl Iterator<Item = f64>) -> f64 {
+    values.fold(0.0, |peak, v| peak.max(v.abs()))
+}
+
+fn rms(values: impl ExactSizeIterator<Item = f64>) -> f64 {
+    let n = values.len().max(1) as f64;
+    (values.map(|v| v * v).sum::<f64>() / n).sqrt()
+}
+
+/// 生成激励轨迹，返回 (时间, 位置) 序列
+///
+/// range为关节的位置范围，连续旋转关节传入无穷大的范围；
+/// 轨迹不会超出范围，峰值速度和加速度不超过上限乘以安全裕度。
+pub fn excitation_trajectory(
+    center: f64,
+    range: (f64, f64),
+    max_velocity: f64,
+    max_acceleration: f64,
+    config: &ExcitationConfig,
+    sample_rate: f64,
+) -> Result<Vec<(f64, f64)>> {
+    config.validate()?;
+    if sample_rate <= 0.0 || max_velocity <= 0.0 || max_acceleration <= 0.0 {
+        return Err(anyhow::anyhow!("采样率、最大速度和最大加速度必须为正数"));
+    }
+    
+    // 先按单位幅度生成波形，再整体缩放，速度和加速度随幅度线性变化
+    let dt = 1.0 / sample_rate;
+    let steps = (config.duration * sample_rate).ceil() as usize;
+    let shape: Vec<f64> = (0..=steps)
+        .map(|i| {
+            let t = (i as f64 * dt).min(config.duration);
+            let window = (std::f64::consts::PI * t / config.duration).sin().powi(2);
+            let wave: f64 = (1..=config.harmonics)
+                .map(|k| (2.0 * std::f64::consts::PI * k as f64 * config.base_frequency * t).sin() / k as f64)
+                .sum();
+            window * wave
+        })
+        .collect();
+    
+    let peak_offset = peak_abs(shape.iter().copied());
+    let peak_velocity = peak_abs(shape.windows(2).map(|w| (w[1] - w[0]) / dt));
+    let peak_acceleration = peak_abs(shape.windows(3).map(|w| (w[2] - 2.0 * w[1] + w[0]) / (dt * dt)));
+    
+    let room = (center - range.0).min(range.1 - center);
+    if room <= 0.0 {
+        return Err(anyhow::anyhow!("关节位于限位上，无法激励"));
+    }
+    
+    let scale = [
+        config.amplitude.min(room) / peak_offset,
+        config.safety_margin * max_velocity / peak_velocity,
+        config.safety_margin * max_acceleration / peak_acceleration,
+    ]
+    .into_iter()
+    .fold(f64::INFINITY, f64::min);
+    
+    Ok(shape.iter()
+        .enumerate()
+        .map(|(i, offset)| ((i as f64 * dt).min(config.duration), center + scale * offset))
+        .collect())
+}
+
+/// 辨识用的单个样本
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DynamicsSample {
+    pub velocity: f64,
+    pub acceleration: f64,
+    pub effort: f64,
+}
+
+/// 拟合结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DynamicsFit {
+    pub params: DynamicsParams,
+    /// 参与拟合的样本数
+    pub samples: usize,
+    /// 实测力矩的均方根
+    pub rms_effort: f64,
+    /// 扣除模型预测后的残差均方根
+    pub rms_residual: f64,
+    /// 模型解释的力矩比例（1 - 残差/实测），越接近1前馈越准确
+    pub improvement: f64,
+}
+
+/// 解3x3线性方程组（列主元高斯消元），矩阵奇异时返回None
+fn solve3(mut a: [[f64; 3]; 3], mut b: [f64; 3]) -> Option<[f64; 3]> {
+    for col in 0..3 {
+        let pivot = (col..3).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        
+        for row in col + 1..3 {
+            let pivot_row = a[col];
+            let factor = a[row][col] / pivot_row[col];
+            for (value, pivot) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    
+    let mut x = [0.0; 3];
+    for row in (0..3).rev() {
+        let sum: f64 = (row + 1..3).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// 最小二乘拟合动力学参数；样本不足、只朝一个方向运动或没有加减速时返回None
+///
+/// 拟合出的负值没有物理意义，按0处理。
+pub fn fit_dynamics(samples: &[DynamicsSample]) -> Option<DynamicsFit> {
+    let moving: Vec<&DynamicsSample> = samples.iter()
+        .filter(|s| s.velocity.abs() > VELOCITY_EPSILON && s.velocity.is_finite() && s.acceleration.is_finite() && s.effort.is_finite())
+        .collect();
+    if moving.len() < MIN_SAMPLES {
+        return None;
+    }
+    
+    let mut ata = [[0.0; 3]; 3];
+    let mut atb = [0.0; 3];
+    for sample in &moving {
+        let phi = [sample.acceleration, sample.velocity, sample.velocity.signum()];
+        for i in 0..3 {
+            for j in 0..3 {
+                ata[i][j] += phi[i] * phi[j];
+            }
+            atb[i] += phi[i] * sample.effort;
+        }
+    }
+    
+    let [inertia, viscous_friction, coulomb_friction] = solve3(ata, atb)?;
+    let params = DynamicsParams {
+        inertia: inertia.max(0.0),
+        viscous_friction: viscous_friction.max(0.0),
+        coulomb_friction: coulomb_friction.max(0.0),
+    };
+    
+    let rms_effort = rms(moving.iter().map(|s| s.effort));
+    let rms_residual = rms(moving.iter().map(|s| s.effort - params.feedforward(s.velocity, s.acceleration)));
+    
+    Some(DynamicsFit {
+        params,
+        samples: moving.len(),
+        rms_effort,
+        rms_residual,
+        improvement: if rms_effort > 0.0 { 1.0 - rms_residual / rms_effort } else { 0.0 },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_excitation_respects_limits() {
+        let config = ExcitationConfig::default();
+        let trajectory = excitation_trajectory(0.9, (-1.0, 1.0), 2.0, 5.0, &config, 100.0).unwrap();
+        
+        // 两端静止在中心
+        assert_eq!(trajectory.first().unwrap().1, 0.9);
+        assert!((trajectory.last().unwrap().1 - 0.9).abs() < 1e-9);
+        assert!((trajectory.last().unwrap().0 - config.duration).abs() < 1e-9);
+        
+        // 靠近上限时幅度收缩到剩余行程以内
+        assert!(trajectory.iter().all(|(_, p)| *p >= 0.8 - 1e-9 && *p <= 1.0 + 1e-9));
+        let peak_velocity = trajectory.windows(2)
+            .map(|w| ((w[1].1 - w[0].1) / (w[1].0 - w[0].0)).abs())
+            .fold(0.0, f64::max);
+        assert!(peak_velocity <= 1.0 + 1e-9);
+        
+        assert!(excitation_trajectory(1.0, (-1.0, 1.0), 2.0, 5.0, &config, 100.0).is_err());
+    }
+    
+    #[test]
+    fn test_fit_recovers_parameters() {
+        let truth = DynamicsParams { inertia: 0.02, viscous_friction: 0.15, coulomb_friction: 0.05 };
+        let config = ExcitationConfig::default();
+        let trajectory = excitation_trajectory(0.0, (-1.0, 1.0), 2.0, 5.0, &config, 100.0).unwrap();
+        
+        let samples: Vec<DynamicsSample> = trajectory.windows(3)
+            .map(|w| {
+                let dt = w[1].0 - w[0].0;
+                let velocity = (w[2].1 - w[0].1) / (2.0 * dt);
+                let acceleration = (w[2].1 - 2.0 * w[1].1 + w[0].1) / (dt * dt);
+                let noise = (rand::random::<f64>() - 0.5) * 0.002;
+                DynamicsSample { velocity, acceleration, effort: truth.feedforward(velocity, acceleration) + noise }
+            })
+            .collect();
+        
+        let fit = fit_dynamics(&samples).unwrap();
+        assert!((fit.params.inertia - truth.inertia).abs() < 0.002, "{:?}", fit.params);
+        assert!((fit.params.viscous_friction - truth.viscous_friction).abs() < 0.01, "{:?}", fit.params);
+        assert!((fit.params.coulomb_friction - truth.coulomb_friction).abs() < 0.005, "{:?}", fit.params);
+        assert!(fit.improvement > 0.9);
+        
+        // 静止不动时无法辨识
+        let still = vec![DynamicsSample { velocity: 0.0, acceleration: 0.0, effort: 0.1 }; 100];
+        assert!(fit_dynamics(&still).is_none());
+    }
+}
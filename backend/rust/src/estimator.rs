@@ -0,0 +1,207 @@
+//! 关节状态估计模块
+//! 
+//! 舵机的速度读数噪声很大，很多型号甚至不提供速度。
+//! 本模块对每个关节的原始位置读数运行α-β-γ滤波器，得到平滑的位置、速度和加速度，
+//! 同时供控制回路使用并写入对外报告的JointState。
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 单个关节的滤波参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EstimatorParams {
+    /// 关闭时直接使用原始位置，速度和加速度由差分得到
+    pub enabled: bool,
+    /// 位置修正系数，越小位置越平滑、延迟越大
+    pub alpha: f64,
+    /// 速度修正系数
+    pub beta: f64,
+    /// 加速度修正系数
+    pub gamma: f64,
+}
+
+impl Default for EstimatorParams {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            alpha: 0.5,
+            beta: 0.1,
+            gamma: 0.005,
+        }
+    }
+}
+
+impl ConfigValidation for EstimatorParams {
+    fn validate(&self) -> Result<()> {
+        if !(self.alpha > 0.0 && self.alpha <= 1.0) {
+            return Err(anyhow::anyhow!("alpha必须在(0, 1]之间"));
+        }
+        
+        // α-β滤波器的稳定条件
+        if !(self.beta >= 0.0 && self.beta < 4.0 - 2.0 * self.alpha) {
+            return Err(anyhow::anyhow!("beta必须在[0, 4 - 2·alpha)之间"));
+        }
+        
+        if !(self.gamma >= 0.0 && self.gamma <= self.beta) {
+            return Err(anyhow::anyhow!("gamma必须在[0, beta]之间"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 状态估计配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateEstimationConfig {
+    /// 未单独配置的关节使用的参数
+    pub default: EstimatorParams,
+    /// 按关节覆盖的参数
+    #[serde(default)]
+    pub joints: HashMap<String, EstimatorParams>,
+}
+
+impl StateEstimationConfig {
+    pub fn params_for(&self, joint_name: &str) -> &EstimatorParams {
+        self.joints.get(joint_name).unwrap_or(&self.default)
+    }
+}
+
+impl ConfigValidation for StateEstimationConfig {
+    fn validate(&self) -> Result<()> {
+        self.default.validate()?;
+        
+        for (joint_name, params) in &self.joints {
+            params.validate().map_err(|e| {
+                anyhow::anyhow!("关节 '{}' 的状态估计参数无效: {}", joint_name, e)
+            })?;
+        }
+        
+        Ok(())
+    }
+}
+
+/// 估计结果（位置为展开后的位置）
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EstimatedState {
+    pub position: f64,
+    pub velocity: f64,
+    pub acceleration: f64,
+}
+
+/// 单个关节的α-β-γ滤波器
+#[derive(Debug, Clone)]
+pub struct JointStateEstimator {
+    params: EstimatorParams,
+    state: Option<EstimatedState>,
+}
+
+impl JointStateEstimator {
+    pub fn new(params: EstimatorParams) -> Self {
+        Self { params, state: None }
+    }
+    
+    /// 输入一次位置读数（rad，连续旋转关节使用展开后的位置）和距上次读数的时间（秒）
+    pub fn update(&mut self, measured: f64, dt: f64) -> EstimatedState {
+        let next = match self.state {
+            Some(previous) if dt > 0.0 => {
+                if self.params.enabled {
+                    Self::filter(&self.params, previous, measured, dt)
+                } else {
+                    let velocity = (measured - previous.position) / dt;
+                    EstimatedState {
+                        position: measured,
+                        velocity,
+                        acceleration: (velocity - previous.velocity) / dt,
+                    }
+                }
+            }
+            Some(previous) => previous,
+            None => EstimatedState { position: measured, ..Default::default() },
+        };
+        
+        self.state = Some(next);
+        next
+    }
+    
+    fn filter(params: &EstimatorParams, previous: EstimatedState, measured: f64, dt: f64) -> EstimatedState {
+        // 按上一时刻的状态预测，再用残差修正
+        let predicted_position = previous.position + previous.velocity * dt + 0.5 * previous.acceleration * dt * dt;
+        let predicted_velocity = previous.velocity + previous.acceleration * dt;
+        let residual = measured - predicted_position;
+        
+        EstimatedState {
+            position: predicted_position + params.alpha * residual,
+            velocity: predicted_velocity + params.beta * residual / dt,
+            acceleration: previous.acceleration + 2.0 * params.gamma * residual / (dt * dt),
+        }
+    }
+    
+    /// 清除历史，下一次读数重新初始化（例如传感器重连之后）
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+/// 用估计结果更新关节状态，连续旋转关节同时更新归一化位置和圈数
+pub fn apply_estimate(joint_state: &mut JointState, estimate: &EstimatedState, continuous: bool) {
+    joint_state.unwrapped_position = estimate.position;
+    joint_state.velocity = estimate.velocity;
+    joint_state.acceleration = estimate.acceleration;
+    
+    if continuous {
+        joint_state.position = normalize_angle(estimate.position);
+        joint_state.turns = ((estimate.position - joint_state.position) / std::f64::consts::TAU).round() as i64;
+    } else {
+        joint_state.position = estimate.position;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_velocity_from_noisy_positions() {
+        let params = EstimatorParams::default();
+        assert!(params.validate().is_ok());
+        
+        let mut estimator = JointStateEstimator::new(params);
+        let dt = 0.005;
+        let true_velocity = 0.8;
+        let mut estimate = EstimatedState::default();
+        
+        // 匀速运动叠加±1mrad的交替噪声
+        for step in 0..2000 {
+            let noise = if step % 2 == 0 { 0.001 } else { -0.001 };
+            estimate = estimator.update(true_velocity * step as f64 * dt + noise, dt);
+        }
+        
+        assert!((estimate.velocity - true_velocity).abs() < 0.1, "velocity {}", estimate.velocity);
+        assert!(estimate.acceleration.abs() < 1.0, "acceleration {}", estimate.acceleration);
+        
+        // 原始差分的速度噪声远大于滤波结果
+        let mut raw = JointStateEstimator::new(EstimatorParams { enabled: false, ..EstimatorParams::default() });
+        raw.update(0.001, dt);
+        let differenced = raw.update(true_velocity * dt - 0.001, dt);
+        assert!((differenced.velocity - true_velocity).abs() > 0.3);
+    }
+    
+    #[test]
+    fn test_apply_estimate_to_continuous_joint() {
+        let mut joint_state = JointState::new("base_yaw".to_string());
+        let estimate = EstimatedState { position: 7.0, velocity: 1.0, acceleration: 0.0 };
+        
+        apply_estimate(&mut joint_state, &estimate, true);
+        assert_eq!(joint_state.turns, 1);
+        assert!((joint_state.position - (7.0 - std::f64::consts::TAU)).abs() < 1e-9);
+        assert_eq!(joint_state.unwrapped_position, 7.0);
+        
+        let invalid = StateEstimationConfig {
+            joints: HashMap::from([("base_yaw".to_string(), EstimatorParams { alpha: 0.0, ..EstimatorParams::default() })]),
+            ..StateEstimationConfig::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
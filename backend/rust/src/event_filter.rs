@@ -0,0 +1,433 @@
+//! 事件去抖与聚合
+//! 
+//! 视觉等子系统逐帧发布原始事件：人脸在画面中时每帧一个FaceDetected，漏检一帧就是FaceLost，
+//! 直接交给行为规则太嘈杂。事件过滤器订阅原始事件总线，按配置文件`events`段的规则处理后，
+//! 把稳定的高层事件发布到另一条总线，行为规则订阅后者：
+//! 
+//! - 去抖：同类事件在间隔内只转发第一个
+//! - 出现/消失迟滞：出现事件持续appear_ms才转发一次，之后不再转发；出现事件中断超过
+//!   disappear_ms才发布一次消失事件，原始的消失事件不转发
+//! - 聚合：窗口内同类事件的次数和最高置信度汇总为一个自定义事件，原始事件照常处理
+//! 
+//! 没有去抖或迟滞规则的事件原样转发
+
+use crate::common::*;
+use crate::events::{EventBus, EventEnvelope, RobotEvent};
+use crate::shutdown::CancellationToken;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use log::{info, warn, debug};
+
+/// 过滤器自己产生的事件的来源
+const SOURCE: &str = "event_filter";
+
+/// 同类事件在间隔内只转发第一个
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DebounceRule {
+    /// 事件类型（见RobotEvent::kind）
+    pub event: String,
+    pub interval_ms: u64,
+}
+
+/// 出现/消失迟滞
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresenceRule {
+    /// 出现事件类型，如FaceDetected
+    pub appear: String,
+    /// 消失事件类型，如FaceLost；稳定消失时发布该类型的事件
+    pub disappear: String,
+    /// 置信度低于该值的出现事件视为没有出现
+    #[serde(default)]
+    pub min_confidence: f64,
+    /// 出现事件持续这么久才算出现，期间中断超过disappear_ms重新计时
+    pub appear_ms: u64,
+    /// 这么久没有出现事件才算消失
+    pub disappear_ms: u64,
+}
+
+/// 窗口聚合
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AggregateRule {
+    /// 被统计的事件类型
+    pub event: String,
+    /// 窗口从第一个事件开始计时
+    pub window_ms: u64,
+    /// 窗口结束时发布的自定义事件名称
+    pub emit: String,
+    /// 窗口内至少这么多个事件才发布
+    #[serde(default = "default_min_count")]
+    pub min_count: usize,
+}
+
+fn default_min_count() -> usize {
+    1
+}
+
+/// 事件过滤配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventFilterConfig {
+    /// 关闭时所有事件原样转发
+    pub enabled: bool,
+    pub debounce: Vec<DebounceRule>,
+    pub presence: Vec<PresenceRule>,
+    pub aggregate: Vec<AggregateRule>,
+    /// 检查消失和聚合窗口的周期（毫秒）
+    pub tick_ms: u64,
+}
+
+impl Default for EventFilterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            debounce: vec![DebounceRule { event: "SoundDetected".to_string(), interval_ms: 500 }],
+            presence: vec![PresenceRule {
+                appear: "FaceDetected".to_string(),
+                disappear: "FaceLost".to_string(),
+                min_confidence: 0.5,
+                appear_ms: 300,
+                disappear_ms: 1500,
+            }],
+            aggregate: Vec::new(),
+            tick_ms: 100,
+        }
+    }
+}
+
+impl ConfigValidation for EventFilterConfig {
+    fn validate(&self) -> Result<()> {
+        if self.tick_ms == 0 {
+            return Err(anyhow::anyhow!("事件过滤的检查周期必须大于0"));
+        }
+        
+        // 去抖和迟滞会吞掉原始事件，同一类事件只能由一条规则处理
+        let mut consumed = HashSet::new();
+        let mut consume = |event: &str| {
+            if event.is_empty() {
+                return Err(anyhow::anyhow!("事件过滤规则的事件类型不能为空"));
+            }
+            if !consumed.insert(event.to_string()) {
+                return Err(anyhow::anyhow!("事件 '{}' 出现在多条去抖或迟滞规则中", event));
+            }
+            Ok(())
+        };
+        
+        for rule in &self.debounce {
+            consume(&rule.event)?;
+            if rule.interval_ms == 0 {
+                return Err(anyhow::anyhow!("事件 '{}' 的去抖间隔必须大于0", rule.event));
+            }
+        }
+        
+        for rule in &self.presence {
+            consume(&rule.appear)?;
+            consume(&rule.disappear)?;
+            if rule.disappear_ms == 0 {
+                return Err(anyhow::anyhow!("事件 '{}' 的消失时间必须大于0", rule.appear));
+            }
+            if !(0.0..=1.0).contains(&rule.min_confidence) {
+                return Err(anyhow::anyhow!("事件 '{}' 的最小置信度必须在0-1之间", rule.appear));
+            }
+        }
+        
+        for rule in &self.aggregate {
+            if rule.event.is_empty() || rule.emit.is_empty() {
+                return Err(anyhow::anyhow!("聚合规则的事件类型和发布名称不能为空"));
+            }
+            if rule.window_ms == 0 || rule.min_count == 0 {
+                return Err(anyhow::anyhow!("事件 '{}' 的聚合窗口和最少次数必须大于0", rule.event));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 一条迟滞规则的状态
+#[derive(Debug, Clone, Default)]
+struct PresenceState {
+    present: bool,
+    /// 尚未确认出现时，连续出现的起始时间
+    candidate_since: Option<u64>,
+    last_seen: u64,
+}
+
+/// 一个聚合窗口
+#[derive(Debug, Clone)]
+struct AggregateWindow {
+    start: u64,
+    count: usize,
+    max_confidence: Option<f64>,
+}
+
+/// 事件过滤器
+#[derive(Debug)]
+pub struct EventFilter {
+    config: EventFilterConfig,
+    last_forwarded: HashMap<String, u64>,
+    presence: Vec<PresenceState>,
+    windows: Vec<Option<AggregateWindow>>,
+}
+
+impl EventFilter {
+    pub fn new(config: EventFilterConfig) -> Result<Self> {
+        config.validate()?;
+        
+        Ok(Self {
+            last_forwarded: HashMap::new(),
+            presence: vec![PresenceState::default(); config.presence.len()],
+            windows: vec![None; config.aggregate.len()],
+            config,
+        })
+    }
+    
+    /// 处理一个原始事件，返回要转发的事件
+    pub fn process(&mut self, envelope: &EventEnvelope) -> Vec<EventEnvelope> {
+        if !self.config.enabled {
+            return vec![envelope.clone()];
+        }
+        
+        let kind = envelope.event.kind();
+        let timestamp = envelope.timestamp;
+        
+        for (rule, window) in self.config.aggregate.iter().zip(&mut self.windows) {
+            if rule.event == kind {
+                let window = window.get_or_insert(AggregateWindow { start: timestamp, count: 0, max_confidence: None });
+                window.count += 1;
+                if let Some(confidence) = envelope.event.confidence() {
+                    window.max_confidence = Some(window.max_confidence.map_or(confidence, |max| max.max(confidence)));
+                }
+            }
+        }
+        
+        if let Some(rule) = self.config.debounce.iter().find(|rule| rule.event == kind) {
+            let due = self.last_forwarded.get(kind)
+                .is_none_or(|&last| timestamp.saturating_sub(last) >= rule.interval_ms);
+            if !due {
+                return Vec::new();
+            }
+            self.last_forwarded.insert(kind.to_string(), timestamp);
+            return vec![envelope.clone()];
+        }
+        
+        for (rule, state) in self.config.presence.iter().zip(&mut self.presence) {
+            if rule.disappear == kind {
+                return Vec::new();
+            }
+            if rule.appear != kind {
+                continue;
+            }
+            if envelope.event.confidence().is_some_and(|confidence| confidence < rule.min_confidence) {
+                return Vec::new();
+            }
+            
+            let continuous = timestamp.saturating_sub(state.last_seen) < rule.disappear_ms;
+            state.last_seen = timestamp;
+            if state.present {
+                return Vec::new();
+            }
+            
+            let since = match state.candidate_since {
+                Some(since) if continuous => since,
+                _ => *state.candidate_since.insert(timestamp),
+            };
+            if timestamp.saturating_sub(since) < rule.appear_ms {
+                return Vec::new();
+            }
+            
+            state.present = true;
+            state.candidate_since = None;
+            debug!("事件 {} 稳定出现", kind);
+            return vec![envelope.clone()];
+        }
+        
+        vec![envelope.clone()]
+    }
+    
+    /// 检查消失和聚合窗口，now为当前时间戳（毫秒），返回到期产生的事件
+    pub fn poll(&mut self, now: u64) -> Vec<EventEnvelope> {
+        let mut events = Vec::new();
+        if !self.config.enabled {
+            return events;
+        }
+        
+        for (rule, state) in self.config.presence.iter().zip(&mut self.presence) {
+            if now.saturating_sub(state.last_seen) < rule.disappear_ms {
+                continue;
+            }
+            state.candidate_since = None;
+            if state.present {
+                state.present = false;
+                debug!("事件 {} 稳定消失", rule.appear);
+                events.push(EventEnvelope { source: SOURCE.to_string(), timestamp: now, event: event_of_kind(&rule.disappear) });
+            }
+        }
+        
+        for (rule, window) in self.config.aggregate.iter().zip(&mut self.windows) {
+            let Some(current) = window.take_if(|window| now.saturating_sub(window.start) >= rule.window_ms) else {
+                continue;
+            };
+            if current.count < rule.min_count {
+                continue;
+            }
+            events.push(EventEnvelope {
+                source: SOURCE.to_string(),
+                timestamp: now,
+                event: RobotEvent::Custom {
+                    name: rule.emit.clone(),
+                    data: serde_json::json!({
+                        "event": rule.event,
+                        "count": current.count,
+                        "max_confidence": current.max_confidence,
+                        "window_ms": rule.window_ms,
+                    }),
+                },
+            });
+        }
+        
+        events
+    }
+    
+    /// 订阅原始事件总线，把过滤后的事件发布到output，直到收到关闭信号
+    pub async fn run(mut self, input: EventBus, output: EventBus, shutdown: CancellationToken) {
+        let mut receiver = input.subscribe();
+        let mut tick = tokio::time::interval(Duration::from_millis(self.config.tick_ms));
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        info!(
+            "事件过滤启动：{} 条去抖、{} 条迟滞、{} 条聚合规则",
+            self.config.debounce.len(), self.config.presence.len(), self.config.aggregate.len()
+        );
+        
+        loop {
+            let events = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = tick.tick() => self.poll(current_timestamp()),
+                received = receiver.recv() => match received {
+                    Ok(envelope) => self.process(&envelope),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("事件过滤处理过慢，丢弃了 {} 个事件", skipped);
+                        continue;
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+            
+            for envelope in events {
+                output.publish_envelope(envelope);
+            }
+        }
+        
+        info!("事件过滤已停止");
+    }
+}
+
+/// 按类型名构造不带数据的事件（如FaceLost），不是内置事件类型时构造同名的自定义事件
+fn event_of_kind(kind: &str) -> RobotEvent {
+    serde_json::from_value(serde_json::json!({ "type": kind })).unwrap_or_else(|_| RobotEvent::Custom {
+        name: kind.to_string(),
+        data: serde_json::Value::Null,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn envelope(timestamp: u64, event: RobotEvent) -> EventEnvelope {
+        EventEnvelope { source: "vision".to_string(), timestamp, event }
+    }
+    
+    fn face(timestamp: u64) -> EventEnvelope {
+        envelope(timestamp, RobotEvent::FaceDetected { confidence: 0.9, position: None })
+    }
+    
+    #[test]
+    fn test_presence_hysteresis_debounce_and_aggregation() {
+        let mut filter = EventFilter::new(EventFilterConfig::default()).unwrap();
+        
+        // 每100ms一帧：持续300ms后才出现一次，之后的帧和漏检的FaceLost都不转发
+        let forwarded: Vec<u64> = (0..10).flat_map(|i| filter.process(&face(i * 100))).map(|e| e.timestamp).collect();
+        assert_eq!(forwarded, vec![300]);
+        assert!(filter.process(&envelope(950, RobotEvent::FaceLost)).is_empty());
+        
+        // 1500ms内没有人脸才消失，发布的是FaceLost
+        assert!(filter.poll(2000).is_empty());
+        let events = filter.poll(2400);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].event, RobotEvent::FaceLost));
+        assert_eq!(events[0].source, SOURCE);
+        
+        // 聚合窗口在第一个事件后1000ms结束，窗口内10个事件
+        let mut filter = EventFilter::new(EventFilterConfig {
+            aggregate: vec![AggregateRule { event: "FaceDetected".to_string(), window_ms: 1000, emit: "FaceActivity".to_string(), min_count: 5 }],
+            ..EventFilterConfig::default()
+        }).unwrap();
+        for i in 0..10 {
+            filter.process(&face(i * 100));
+        }
+        let events = filter.poll(1000);
+        match &events[..] {
+            [EventEnvelope { event: RobotEvent::Custom { name, data }, .. }] => {
+                assert_eq!(name, "FaceActivity");
+                assert_eq!(data["count"], 10);
+            },
+            other => panic!("聚合事件不符: {:?}", other),
+        }
+        
+        // 短暂出现又消失的人脸（闪烁）不转发
+        let mut filter = EventFilter::new(EventFilterConfig::default()).unwrap();
+        assert!(filter.process(&face(0)).is_empty());
+        assert!(filter.process(&face(100)).is_empty());
+        assert!(filter.poll(2000).is_empty());
+        assert!(filter.process(&face(2100)).is_empty());
+        
+        // 去抖：间隔内只转发第一个；没有规则的事件原样转发
+        let mode = |timestamp| envelope(timestamp, RobotEvent::ModeChanged { from: "Idle".to_string(), to: "Dance".to_string() });
+        let mut filter = EventFilter::new(EventFilterConfig {
+            debounce: vec![DebounceRule { event: "ModeChanged".to_string(), interval_ms: 1000 }],
+            ..EventFilterConfig::default()
+        }).unwrap();
+        assert_eq!(filter.process(&mode(0)).len(), 1);
+        assert!(filter.process(&mode(500)).is_empty());
+        assert_eq!(filter.process(&mode(1000)).len(), 1);
+        assert_eq!(filter.process(&envelope(0, RobotEvent::EmergencyStop)).len(), 1);
+        
+        let disabled = EventFilterConfig { enabled: false, ..EventFilterConfig::default() };
+        assert_eq!(EventFilter::new(disabled).unwrap().process(&face(0)).len(), 1);
+    }
+    
+    #[test]
+    fn test_config_from_yaml() {
+        let yaml = "
+presence:
+  - appear: PersonSeen
+    disappear: PersonGone
+    appear_ms: 0
+    disappear_ms: 500
+aggregate:
+  - event: SoundEventDetected
+    window_ms: 2000
+    emit: ClapBurst
+";
+        let config: EventFilterConfig = serde_yaml::from_str(yaml).unwrap();
+        // 没有写的部分使用默认规则
+        assert!(config.enabled);
+        assert_eq!(config.debounce, EventFilterConfig::default().debounce);
+        assert_eq!(config.aggregate[0].min_count, 1);
+        
+        // 不是内置类型的消失事件发布为同名的自定义事件
+        let mut filter = EventFilter::new(config).unwrap();
+        let seen = envelope(0, RobotEvent::Custom { name: "PersonSeen".to_string(), data: serde_json::Value::Null });
+        assert_eq!(filter.process(&seen).len(), 1);
+        assert_eq!(filter.poll(500)[0].event.kind(), "PersonGone");
+        
+        let duplicate = EventFilterConfig {
+            debounce: vec![DebounceRule { event: "FaceLost".to_string(), interval_ms: 100 }],
+            ..EventFilterConfig::default()
+        };
+        assert!(duplicate.validate().is_err());
+    }
+}
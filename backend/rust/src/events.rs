@@ -0,0 +1,163 @@
+//! 事件总线模块
+//! 
+//! 各子系统（视觉、音频、实时控制等）把感知和状态变化发布为事件，
+//! 行为规则、日志等消费者通过订阅总线获取事件。
+
+use crate::adaptive_resolution::ResolutionChange;
+use crate::common::*;
+use crate::loop_rate::RateChange;
+use crate::oscillation::OscillationReport;
+use crate::segmentation::SegmentationCoverage;
+use crate::sound_events::SoundEvent;
+#[cfg(feature = "audio")]
+use crate::sound_localization::SoundBearing;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use log::debug;
+
+/// 机器人事件，序列化时以`type`字段标记事件类型（与`kind()`一致）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RobotEvent {
+    FaceDetected {
+        confidence: f32,
+        /// 人脸在底座坐标系中的位置（米），未知时为None
+        position: Option<Vector3>,
+    },
+    FaceLost,
+    #[cfg(feature = "audio")]
+    SoundDetected(SoundBearing),
+    CommandExecuted {
+        joint_name: String,
+    },
+    ModeChanged {
+        from: String,
+        to: String,
+    },
+    EmergencyStop,
+    /// 控制循环因负载降频或恢复
+    ControlRateChanged(RateChange),
+    /// 视觉因负载降低或恢复分辨率
+    ResolutionChanged(ResolutionChange),
+    /// 关节持续振荡，已降低增益或停止关节
+    OscillationDetected(OscillationReport),
+    /// 语义分割得到的各类别占画面的比例
+    SegmentationCoverage(SegmentationCoverage),
+    /// 音频分类模型在一个音频窗口中识别出的声音事件（拍手、门铃等）
+    SoundEventDetected(SoundEvent),
+    Custom {
+        name: String,
+        data: serde_json::Value,
+    },
+}
+
+impl RobotEvent {
+    /// 事件类型名，自定义事件返回其名称
+    pub fn kind(&self) -> &str {
+        match self {
+            RobotEvent::FaceDetected { .. } => "FaceDetected",
+            RobotEvent::FaceLost => "FaceLost",
+            #[cfg(feature = "audio")]
+            RobotEvent::SoundDetected(_) => "SoundDetected",
+            RobotEvent::CommandExecuted { .. } => "CommandExecuted",
+            RobotEvent::ModeChanged { .. } => "ModeChanged",
+            RobotEvent::EmergencyStop => "EmergencyStop",
+            RobotEvent::ControlRateChanged(_) => "ControlRateChanged",
+            RobotEvent::ResolutionChanged(_) => "ResolutionChanged",
+            RobotEvent::OscillationDetected(_) => "OscillationDetected",
+            RobotEvent::SegmentationCoverage(_) => "SegmentationCoverage",
+            RobotEvent::SoundEventDetected(_) => "SoundEventDetected",
+            RobotEvent::Custom { name, .. } => name,
+        }
+    }
+    
+    /// 事件携带的置信度（如果有）
+    pub fn confidence(&self) -> Option<f64> {
+        match self {
+            RobotEvent::FaceDetected { confidence, .. } => Some(*confidence as f64),
+            #[cfg(feature = "audio")]
+            RobotEvent::SoundDetected(bearing) => Some(bearing.confidence),
+            RobotEvent::SoundEventDetected(event) => Some(event.confidence),
+            _ => None,
+        }
+    }
+}
+
+/// 带来源和时间戳的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventEnvelope {
+    pub source: String,
+    pub timestamp: u64,
+    pub event: RobotEvent,
+}
+
+impl EventEnvelope {
+    pub fn new(source: &str, event: RobotEvent) -> Self {
+        Self {
+            source: source.to_string(),
+            timestamp: current_timestamp(),
+            event,
+        }
+    }
+}
+
+/// 事件总线（多生产者、多订阅者）
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<EventEnvelope>,
+}
+
+impl EventBus {
+    /// 创建事件总线，capacity为每个订阅者可缓存的事件数量
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+    
+    /// 发布事件，返回接收到事件的订阅者数量
+    pub fn publish(&self, source: &str, event: RobotEvent) -> usize {
+        self.publish_envelope(EventEnvelope::new(source, event))
+    }
+    
+    /// 发布已封装的事件
+    pub fn publish_envelope(&self, envelope: EventEnvelope) -> usize {
+        debug!("事件 {} 来自 {}", envelope.event.kind(), envelope.source);
+        // 没有订阅者时发送失败，事件直接丢弃
+        self.sender.send(envelope).unwrap_or(0)
+    }
+    
+    /// 订阅事件
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.sender.subscribe()
+    }
+    
+    /// 当前订阅者数量
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_event_bus_publish_subscribe() {
+        let bus = EventBus::default();
+        assert_eq!(bus.publish("test", RobotEvent::FaceLost), 0);
+        
+        let mut receiver = bus.subscribe();
+        assert_eq!(bus.publish("vision", RobotEvent::FaceDetected { confidence: 0.9, position: None }), 1);
+        
+        let envelope = receiver.recv().await.unwrap();
+        assert_eq!(envelope.source, "vision");
+        assert_eq!(envelope.event.kind(), "FaceDetected");
+        assert_eq!(envelope.event.confidence(), Some(0.9_f32 as f64));
+    }
+}
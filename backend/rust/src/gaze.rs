@@ -0,0 +1,505 @@
+//! 注视控制模块
+//! 
+//! 把"看向某个方向/某个点"转换为头部和底座转盘的关节目标。
+//! 水平方向优先由头部转动完成，超出舒适范围的部分交给底座转盘，
+//! 这样机器人可以转身面向声源或人。
+//! 
+//! `look_at_*`把超出限位的目标截断到限位上；`try_look_at_*`在目标不可达时返回
+//! `GazeUnreachable`，其中给出最接近的可达方向和挡住目标的关节限位。
+//! `workspace`给出可达的注视范围，供前端绘制包络。
+//! 
+//! 头部运动链的关节按作用从关节集合中查找，头部旋转中心取自坐标变换配置。
+
+use crate::common::*;
+use crate::joints::{HeadJoints, JointSetConfig};
+use crate::realtime::{CommandType, MotionCommand};
+use crate::transforms::TransformConfig;
+#[cfg(feature = "audio")]
+use crate::sound_localization::SoundBearing;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 注视控制配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GazeConfig {
+    /// 是否使用底座转盘，为false或关节集合中没有底座转盘时只转头
+    pub use_base: bool,
+    /// 头部水平转动的舒适范围（rad），超出部分由底座完成
+    pub pan_comfort_range: f64,
+    /// 注视运动时长（秒）
+    pub motion_duration: f64,
+}
+
+impl Default for GazeConfig {
+    fn default() -> Self {
+        Self {
+            use_base: true,
+            pan_comfort_range: 0.6,
+            motion_duration: 0.5,
+        }
+    }
+}
+
+impl ConfigValidation for GazeConfig {
+    fn validate(&self) -> Result<()> {
+        if self.pan_comfort_range <= 0.0 {
+            return Err(anyhow::anyhow!("头部舒适范围必须为正数"));
+        }
+        
+        if self.motion_duration <= 0.0 {
+            return Err(anyhow::anyhow!("注视运动时长必须为正数"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 判断方向是否到达时的角度容差（rad）
+const DIRECTION_TOLERANCE: f64 = 1e-6;
+
+/// 关节行程
+#[derive(Debug, Clone, Copy)]
+struct JointRange {
+    min: Angle,
+    max: Angle,
+    continuous: bool,
+}
+
+impl JointRange {
+    fn limit(&self, position: f64) -> f64 {
+        if self.continuous {
+            normalize_angle(position)
+        } else {
+            clamp(position, self.min.radians(), self.max.radians())
+        }
+    }
+    
+    /// 位置超出行程时返回挡住它的限位
+    fn hit(&self, joint: &str, required: f64) -> Option<JointLimitHit> {
+        let limit = self.limit(required);
+        (!self.continuous && (limit - required).abs() > DIRECTION_TOLERANCE)
+            .then(|| JointLimitHit { joint: joint.to_string(), required, limit })
+    }
+}
+
+/// 挡住注视目标的关节限位
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JointLimitHit {
+    pub joint: String,
+    /// 到达目标需要的位置（rad）
+    pub required: f64,
+    /// 挡住目标的限位（rad）
+    pub limit: f64,
+}
+
+impl std::fmt::Display for JointLimitHit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "关节 {} 需要 {:.3} rad，超出限位 {:.3} rad", self.joint, self.required, self.limit)
+    }
+}
+
+fn describe_hits(hits: &[JointLimitHit]) -> String {
+    hits.iter().map(ToString::to_string).collect::<Vec<_>>().join("；")
+}
+
+/// 注视目标不可达（角度单位rad）
+#[derive(Debug, Clone, Serialize, Deserialize, thiserror::Error)]
+#[error(
+    "注视目标不可达（方位 {azimuth:.3}，俯仰 {elevation:.3}）：{}；最接近的可达方向为方位 {nearest_azimuth:.3}，俯仰 {nearest_elevation:.3}",
+    describe_hits(.blocking)
+)]
+pub struct GazeUnreachable {
+    pub azimuth: f64,
+    pub elevation: f64,
+    pub nearest_azimuth: f64,
+    pub nearest_elevation: f64,
+    /// 看向最接近的可达方向的关节目标
+    pub nearest_targets: HashMap<String, f64>,
+    pub blocking: Vec<JointLimitHit>,
+}
+
+/// 可达的注视范围（底座坐标系，角度单位rad）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GazeWorkspace {
+    /// 为true时任意方位都可达，此时方位范围为[-π, π]
+    pub full_turn: bool,
+    pub azimuth_min: f64,
+    pub azimuth_max: f64,
+    pub elevation_min: f64,
+    pub elevation_max: f64,
+    /// 包络边界上的采样点（米），位于以头部旋转中心为球心的球面上
+    pub boundary: Vec<Vector3>,
+}
+
+/// 注视控制器
+#[derive(Debug, Clone)]
+pub struct GazeController {
+    config: GazeConfig,
+    joints: HeadJoints,
+    /// 头部旋转中心相对底座的偏移（米）
+    head_offset: Vector3,
+    pan: JointRange,
+    tilt: JointRange,
+    base: Option<JointRange>,
+}
+
+impl GazeController {
+    /// 根据关节集合和坐标变换配置创建注视控制器，关节限位取自关节定义
+    pub fn new(config: GazeConfig, joints: &JointSetConfig, transforms: &TransformConfig) -> Result<Self> {
+        config.validate()?;
+        
+        let mut head = joints.head_joints()?;
+        if !config.use_base {
+            head.base_yaw = None;
+        }
+        
+        let range = |name: &str| {
+            joints.get(name)
+                .map(|j| JointRange { min: j.min_position, max: j.max_position, continuous: j.continuous })
+                .ok_or_else(|| anyhow::anyhow!("关节 '{}' 未在关节集合中定义", name))
+        };
+        
+        let pan = range(&head.pan)?;
+        let tilt = range(&head.tilt)?;
+        let base = match &head.base_yaw {
+            Some(name) => Some(range(name)?),
+            None => None,
+        };
+        
+        Ok(Self { config, joints: head, head_offset: transforms.head_offset, pan, tilt, base })
+    }
+    
+    /// 看向底座坐标系中的方位（azimuth向左为正，elevation向上为正，单位rad）
+    pub fn look_at_bearing(
+        &self,
+        azimuth: f64,
+        elevation: f64,
+        joint_states: &HashMap<String, JointState>,
+    ) -> HashMap<String, f64> {
+        self.solve_bearing(azimuth, elevation, joint_states).0
+    }
+    
+    /// 与`look_at_bearing`相同，但目标超出关节限位时返回错误而不是截断
+    pub fn try_look_at_bearing(
+        &self,
+        azimuth: f64,
+        elevation: f64,
+        joint_states: &HashMap<String, JointState>,
+    ) -> std::result::Result<HashMap<String, f64>, GazeUnreachable> {
+        let (targets, blocking) = self.solve_bearing(azimuth, elevation, joint_states);
+        
+        let base = self.joints.base_yaw.as_ref().and_then(|name| targets.get(name)).copied().unwrap_or(0.0);
+        let nearest_azimuth = normalize_angle(base + targets[&self.joints.pan]);
+        let nearest_elevation = -targets[&self.joints.tilt];
+        
+        let azimuth = normalize_angle(azimuth);
+        let reached = normalize_angle(nearest_azimuth - azimuth).abs() <= DIRECTION_TOLERANCE
+            && (nearest_elevation - elevation).abs() <= DIRECTION_TOLERANCE;
+        if reached {
+            return Ok(targets);
+        }
+        
+        Err(GazeUnreachable {
+            azimuth,
+            elevation,
+            nearest_azimuth,
+            nearest_elevation,
+            nearest_targets: targets,
+            blocking,
+        })
+    }
+    
+    /// 求解关节目标（已截断到限位），同时返回被限位挡住的关节
+    fn solve_bearing(
+        &self,
+        azimuth: f64,
+        elevation: f64,
+        joint_states: &HashMap<String, JointState>,
+    ) -> (HashMap<String, f64>, Vec<JointLimitHit>) {
+        let mut targets = HashMap::new();
+        let mut blocking = Vec::new();
+        let azimuth = normalize_angle(azimuth);
+        
+        let current_base = self.joints.base_yaw.as_ref()
+            .and_then(|name| joint_states.get(name))
+            .map(|j| j.position)
+            .unwrap_or(0.0);
+        
+        // 头部先在舒适范围内转动，剩余部分交给底座
+        let mut base_target = current_base;
+        if let (Some(base), Some(name)) = (&self.base, &self.joints.base_yaw) {
+            let pan_needed = normalize_angle(azimuth - current_base);
+            let comfort = self.config.pan_comfort_range;
+            let excess = pan_needed - clamp(pan_needed, -comfort, comfort);
+            if excess != 0.0 {
+                blocking.extend(base.hit(name, current_base + excess));
+                base_target = base.limit(current_base + excess);
+                targets.insert(name.clone(), base_target);
+            }
+        }
+        
+        let pan_required = normalize_angle(azimuth - base_target);
+        let pan_target = self.pan.limit(pan_required);
+        targets.insert(self.joints.pan.clone(), pan_target);
+        
+        // 底座被限位挡住时头部可以补足；头部也补不足时两者都在挡住目标的关节之列
+        match self.pan.hit(&self.joints.pan, pan_required) {
+            Some(hit) => blocking.push(hit),
+            None => blocking.clear(),
+        }
+        
+        // 俯仰关节绕y轴正向旋转时视线朝下，因此向上看取负值
+        let tilt_target = self.tilt.limit(-elevation);
+        blocking.extend(self.tilt.hit(&self.joints.tilt, -elevation));
+        targets.insert(self.joints.tilt.clone(), tilt_target);
+        
+        (targets, blocking)
+    }
+    
+    /// 点相对头部旋转中心的方位和俯仰
+    fn bearing_of(&self, point: &Vector3) -> (f64, f64) {
+        let relative = *point - self.head_offset;
+        let azimuth = relative.y.atan2(relative.x);
+        let elevation = relative.z.atan2((relative.x * relative.x + relative.y * relative.y).sqrt());
+        (azimuth, elevation)
+    }
+    
+    /// 看向底座坐标系中的一个点（米）
+    pub fn look_at_point(
+        &self,
+        point: &Vector3,
+        joint_states: &HashMap<String, JointState>,
+    ) -> HashMap<String, f64> {
+        let (azimuth, elevation) = self.bearing_of(point);
+        self.look_at_bearing(azimuth, elevation, joint_states)
+    }
+    
+    /// 与`look_at_point`相同，但目标超出关节限位时返回错误而不是截断
+    pub fn try_look_at_point(
+        &self,
+        point: &Vector3,
+        joint_states: &HashMap<String, JointState>,
+    ) -> std::result::Result<HashMap<String, f64>, GazeUnreachable> {
+        let (azimuth, elevation) = self.bearing_of(point);
+        self.try_look_at_bearing(azimuth, elevation, joint_states)
+    }
+    
+    /// 可达的注视范围，boundary在半径为radius的球面上沿包络边界每条边采样samples个点
+    pub fn workspace(&self, radius: f64, samples: usize) -> GazeWorkspace {
+        use std::f64::consts::{FRAC_PI_2, PI};
+        
+        let ranges = std::iter::once(&self.pan).chain(self.base.as_ref());
+        let full_turn = ranges.clone().any(|r| r.continuous)
+            || ranges.map(|r| r.max.radians() - r.min.radians()).sum::<f64>() >= 2.0 * PI;
+        
+        let (azimuth_min, azimuth_max) = if full_turn {
+            (-PI, PI)
+        } else {
+            let base = self.base.map(|b| (b.min.radians(), b.max.radians())).unwrap_or((0.0, 0.0));
+            (base.0 + self.pan.min.radians(), base.1 + self.pan.max.radians())
+        };
+        
+        // 俯仰关节取负值为仰角
+        let elevation_min = clamp(-self.tilt.max.radians(), -FRAC_PI_2, FRAC_PI_2);
+        let elevation_max = clamp(-self.tilt.min.radians(), -FRAC_PI_2, FRAC_PI_2);
+        
+        let point = |azimuth: f64, elevation: f64| self.head_offset + Vector3::new(
+            radius * elevation.cos() * azimuth.cos(),
+            radius * elevation.cos() * azimuth.sin(),
+            radius * elevation.sin(),
+        );
+        let samples = samples.max(2);
+        let lerp = |from: f64, to: f64, i: usize| from + (to - from) * i as f64 / (samples - 1) as f64;
+        
+        // 上边缘、右边缘、下边缘、左边缘依次采样；整周可达时没有左右边缘
+        let mut boundary = Vec::new();
+        boundary.extend((0..samples).map(|i| point(lerp(azimuth_min, azimuth_max, i), elevation_max)));
+        if !full_turn {
+            boundary.extend((0..samples).map(|i| point(azimuth_max, lerp(elevation_max, elevation_min, i))));
+        }
+        boundary.extend((0..samples).map(|i| point(lerp(azimuth_max, azimuth_min, i), elevation_min)));
+        if !full_turn {
+            boundary.extend((0..samples).map(|i| point(azimuth_min, lerp(elevation_min, elevation_max, i))));
+        }
+        
+        GazeWorkspace {
+            full_turn,
+            azimuth_min,
+            azimuth_max,
+            elevation_min,
+            elevation_max,
+            boundary,
+        }
+    }
+    
+    /// 转向声源（麦克风阵列安装在头部），保持当前俯仰角
+    #[cfg(feature = "audio")]
+    pub fn look_at_sound(
+        &self,
+        bearing: &SoundBearing,
+        joint_states: &HashMap<String, JointState>,
+    ) -> HashMap<String, f64> {
+        let position = |name: &str| joint_states.get(name).map(|j| j.position).unwrap_or(0.0);
+        
+        let base = self.joints.base_yaw.as_deref().map(position).unwrap_or(0.0);
+        let pan = position(&self.joints.pan);
+        let tilt = position(&self.joints.tilt);
+        
+        self.look_at_bearing(base + pan + bearing.azimuth, -tilt, joint_states)
+    }
+    
+    /// 把关节目标转换为位置命令
+    pub fn to_commands(&self, targets: &HashMap<String, f64>) -> Vec<MotionCommand> {
+        targets.iter()
+            .map(|(joint_name, position)| MotionCommand {
+                joint_name: joint_name.clone(),
+                command_type: CommandType::Position,
+                target_position: Some(*position),
+                target_velocity: None,
+                target_torque: None,
+                duration: Some(self.config.motion_duration),
+                timestamp: current_timestamp(),
+                correlation_id: None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+    
+    #[test]
+    fn test_small_bearing_uses_head_only() {
+        let gaze = GazeController::new(GazeConfig::default(), &JointSetConfig::default(), &TransformConfig::default()).unwrap();
+        let targets = gaze.look_at_bearing(0.3, 0.1, &HashMap::new());
+        
+        assert!(!targets.contains_key("base_yaw"));
+        assert!((targets["head_pan"] - 0.3).abs() < 1e-9);
+        assert!((targets["head_tilt"] + 0.1).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_large_bearing_turns_base() {
+        let gaze = GazeController::new(GazeConfig::default(), &JointSetConfig::default(), &TransformConfig::default()).unwrap();
+        let targets = gaze.look_at_point(&Vector3::new(0.0, 2.0, 0.17), &HashMap::new());
+        
+        let base = targets["base_yaw"];
+        let pan = targets["head_pan"];
+        assert!((base + pan - FRAC_PI_2).abs() < 1e-9);
+        assert!(pan.abs() <= GazeConfig::default().pan_comfort_range + 1e-9);
+    }
+    
+    #[test]
+    #[cfg(feature = "audio")]
+    fn test_look_at_sound_accounts_for_head_pose() {
+        let gaze = GazeController::new(GazeConfig::default(), &JointSetConfig::default(), &TransformConfig::default()).unwrap();
+        let mut joints = HashMap::new();
+        let mut pan = JointState::new("head_pan".to_string());
+        pan.position = 0.2;
+        joints.insert("head_pan".to_string(), pan);
+        
+        let bearing = SoundBearing { azimuth: 0.1, confidence: 1.0, energy: 0.1, timestamp: 0 };
+        let targets = gaze.look_at_sound(&bearing, &joints);
+        assert!((targets["head_pan"] - 0.3).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_unreachable_target_reports_nearest_and_limit() {
+        let mut joints = JointSetConfig::default();
+        for joint in joints.joints.iter_mut() {
+            match joint.name.as_str() {
+                "head_tilt" => {
+                    joint.min_position = Angle::from_radians(-0.5);
+                    joint.max_position = Angle::from_radians(0.8);
+                },
+                "head_pan" => {
+                    joint.min_position = Angle::from_radians(-1.0);
+                    joint.max_position = Angle::from_radians(1.0);
+                },
+                _ => {},
+            }
+        }
+        let config = GazeConfig { use_base: false, ..GazeConfig::default() };
+        let gaze = GazeController::new(config, &joints, &TransformConfig::default()).unwrap();
+        
+        assert!(gaze.try_look_at_bearing(0.5, 0.3, &HashMap::new()).is_ok());
+        
+        // 向上看0.7超出俯仰下限-0.5，最接近的方向仰角为0.5
+        let err = gaze.try_look_at_bearing(0.5, 0.7, &HashMap::new()).unwrap_err();
+        assert_eq!(err.blocking.len(), 1);
+        assert_eq!(err.blocking[0].joint, "head_tilt");
+        assert!((err.blocking[0].limit + 0.5).abs() < 1e-9);
+        assert!((err.nearest_elevation - 0.5).abs() < 1e-9);
+        assert!((err.nearest_azimuth - 0.5).abs() < 1e-9);
+        assert!(err.to_string().contains("head_tilt"));
+        
+        // 方位和俯仰都超限时两个关节都列出
+        let err = gaze.try_look_at_bearing(2.0, -1.0, &HashMap::new()).unwrap_err();
+        let blocked: Vec<&str> = err.blocking.iter().map(|h| h.joint.as_str()).collect();
+        assert_eq!(blocked, vec!["head_pan", "head_tilt"]);
+        assert!((err.nearest_azimuth - 1.0).abs() < 1e-9);
+        
+        let workspace = gaze.workspace(1.0, 5);
+        assert!(!workspace.full_turn);
+        assert!((workspace.azimuth_max - 1.0).abs() < 1e-9);
+        assert!((workspace.elevation_min + 0.8).abs() < 1e-9);
+        assert!((workspace.elevation_max - 0.5).abs() < 1e-9);
+        assert_eq!(workspace.boundary.len(), 20);
+    }
+    
+    #[test]
+    fn test_base_limit_compensated_by_head() {
+        let mut joints = JointSetConfig::default();
+        for joint in joints.joints.iter_mut().filter(|j| j.name == "base_yaw") {
+            joint.min_position = Angle::from_radians(-1.0);
+            joint.max_position = Angle::from_radians(1.0);
+        }
+        let gaze = GazeController::new(GazeConfig::default(), &joints, &TransformConfig::default()).unwrap();
+        
+        // 底座转到限位后剩余部分由头部补足，目标仍然可达
+        let targets = gaze.try_look_at_bearing(2.5, 0.0, &HashMap::new()).unwrap();
+        assert!((targets["base_yaw"] - 1.0).abs() < 1e-9);
+        assert!((targets["head_pan"] - 1.5).abs() < 1e-9);
+        
+        let workspace = gaze.workspace(1.0, 8);
+        assert!(workspace.full_turn);
+        assert_eq!(workspace.boundary.len(), 16);
+    }
+    
+    #[test]
+    fn test_gaze_without_base() {
+        let config = GazeConfig {
+            use_base: false,
+            ..GazeConfig::default()
+        };
+        let gaze = GazeController::new(config, &JointSetConfig::default(), &TransformConfig::default()).unwrap();
+        let targets = gaze.look_at_bearing(FRAC_PI_2, 0.0, &HashMap::new());
+        
+        assert!(!targets.contains_key("base_yaw"));
+        assert!((targets["head_pan"] - FRAC_PI_2).abs() < 1e-9);
+        
+        // 关节集合中没有底座转盘时同样只转头
+        let mut joints = JointSetConfig::default();
+        joints.joints.retain(|j| j.name != "base_yaw");
+        let gaze = GazeController::new(GazeConfig::default(), &joints, &TransformConfig::default()).unwrap();
+        assert!(!gaze.look_at_bearing(FRAC_PI_2, 0.0, &HashMap::new()).contains_key("base_yaw"));
+    }
+    
+    #[test]
+    fn test_joints_and_geometry_from_shared_config() {
+        let mut joints = JointSetConfig::default();
+        for joint in joints.joints.iter_mut() {
+            joint.name = format!("neck_{}", joint.name);
+        }
+        let transforms = TransformConfig { head_offset: Vector3::new(0.0, 0.0, 0.5), ..TransformConfig::default() };
+        let gaze = GazeController::new(GazeConfig::default(), &joints, &transforms).unwrap();
+        
+        // 与头部旋转中心等高的点仰角为0
+        let targets = gaze.look_at_point(&Vector3::new(1.0, 0.0, 0.5), &HashMap::new());
+        assert!(targets["neck_head_tilt"].abs() < 1e-9);
+        assert!(targets["neck_head_pan"].abs() < 1e-9);
+    }
+}
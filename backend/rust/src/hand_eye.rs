@@ -0,0 +1,386 @@
+//! 相机外参（手眼）标定
+//! 
+//! 相机装在头部上（eye-in-hand）。头部按脚本依次转到一组姿态，每个姿态记录头部在底座
+//! 坐标系中的位姿 Hᵢ（由关节位置经运动学得到）和固定标记在相机坐标系中的位姿 Cᵢ。
+//! 标记相对底座不动，所以 Hᵢ·X·Cᵢ 对所有姿态相同，X 即相机在头部坐标系中的位姿。
+//! 任意两个姿态 i、j 给出 A·X = X·B，其中 A = Hⱼ⁻¹·Hᵢ，B = Cⱼ·Cᵢ⁻¹。
+//!
+//! 旋转由四元数形式 (L(q_A) − R(q_B))·q_X = 0 对所有姿态对做最小二乘，取4×4对称矩阵最小
+//! 特征值对应的特征向量；平移再由 (R_A − I)·t_X = R_X·t_B − t_A 求最小二乘解。头部只有
+//! 水平和俯仰两个转动轴，姿态需要同时包含两个方向的转动，否则解不唯一。
+//!
+//! 本模块不做标记检测：调用方提供观测函数，返回标记在相机坐标系中的位姿（例如
+//! AprilTag/ArUco检测后用PnP求出），看不到标记时返回None，该姿态被跳过。
+//! 标定结果写入控制器的坐标变换树，末端速度控制随即使用新的相机位置；调用方可以把它
+//! 写回配置的`camera_mount`保存。
+
+use crate::common::*;
+use crate::realtime::{CommandType, MotionCommand, RealtimeController};
+use crate::transforms::{Transform, BASE_FRAME, HEAD_FRAME};
+use anyhow::Result;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// 标定脚本中的一个头部姿态，相对开始标定时的位置（rad）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HeadPose {
+    pub pan: f64,
+    pub tilt: f64,
+}
+
+/// 手眼标定配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandEyeConfig {
+    /// 头部依次转到的姿态；开始标定前头部应大致对准标记
+    pub poses: Vec<HeadPose>,
+    /// 每个姿态转到后等待稳定的时间（毫秒）
+    pub settle_ms: u64,
+    /// 至少需要看到标记的姿态数
+    pub min_samples: usize,
+    /// 各姿态求出的标记位置相对平均值的最大均方根残差（米）
+    pub max_translation_residual: f64,
+    /// 各姿态求出的标记朝向相对平均值的最大均方根残差（rad）
+    pub max_rotation_residual: f64,
+}
+
+impl Default for HandEyeConfig {
+    fn default() -> Self {
+        let mut poses = vec![HeadPose { pan: 0.0, tilt: 0.0 }];
+        for (pan, tilt) in [(0.25, 0.0), (-0.25, 0.0), (0.0, 0.15), (0.0, -0.15), (0.2, 0.12), (-0.2, 0.12), (0.2, -0.12), (-0.2, -0.12)] {
+            poses.push(HeadPose { pan, tilt });
+        }
+        
+        Self {
+            poses,
+            settle_ms: 800,
+            min_samples: 5,
+            max_translation_residual: 0.01,
+            max_rotation_residual: 0.03,
+        }
+    }
+}
+
+impl ConfigValidation for HandEyeConfig {
+    fn validate(&self) -> Result<()> {
+        if self.min_samples < 3 {
+            return Err(anyhow::anyhow!("手眼标定至少需要3个姿态"));
+        }
+        
+        if self.poses.len() < self.min_samples {
+            return Err(anyhow::anyhow!("标定姿态数 {} 少于最少有效姿态数 {}", self.poses.len(), self.min_samples));
+        }
+        
+        if self.poses.iter().any(|pose| !(pose.pan.is_finite() && pose.tilt.is_finite())) {
+            return Err(anyhow::anyhow!("标定姿态包含非有限值"));
+        }
+        
+        if !(self.max_translation_residual > 0.0 && self.max_rotation_residual > 0.0) {
+            return Err(anyhow::anyhow!("标定残差上限必须为正数"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 一个姿态的观测
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HandEyeSample {
+    /// 头部在底座坐标系中的位姿
+    pub head_in_base: Transform,
+    /// 标记在相机坐标系中的位姿
+    pub marker_in_camera: Transform,
+}
+
+/// 标定结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandEyeCalibration {
+    /// 相机在头部坐标系中的位姿
+    pub camera_in_head: Transform,
+    /// 标记在底座坐标系中的位姿（各姿态的平均）
+    pub marker_in_base: Transform,
+    /// 标记位置的均方根残差（米）
+    pub translation_residual: f64,
+    /// 标记朝向的均方根残差（rad）
+    pub rotation_residual: f64,
+    /// 使用的姿态数
+    pub samples: usize,
+}
+
+/// 四元数左乘矩阵 L(q)·p = q·p
+fn left_matrix(q: &Quaternion) -> [[f64; 4]; 4] {
+    [
+        [q.w, -q.x, -q.y, -q.z],
+        [q.x, q.w, -q.z, q.y],
+        [q.y, q.z, q.w, -q.x],
+        [q.z, -q.y, q.x, q.w],
+    ]
+}
+
+/// 四元数右乘矩阵 R(q)·p = p·q
+fn right_matrix(q: &Quaternion) -> [[f64; 4]; 4] {
+    [
+        [q.w, -q.x, -q.y, -q.z],
+        [q.x, q.w, q.z, -q.y],
+        [q.y, -q.z, q.w, q.x],
+        [q.z, q.y, -q.x, q.w],
+    ]
+}
+
+/// 取w非负的那个四元数，q和-q表示同一个旋转
+fn canonical(q: Quaternion) -> Quaternion {
+    let q = q.normalize();
+    if q.w < 0.0 { Quaternion::new(-q.w, -q.x, -q.y, -q.z) } else { q }
+}
+
+/// 两个旋转之间的夹角（rad）
+fn rotation_angle(a: &Quaternion, b: &Quaternion) -> f64 {
+    2.0 * a.normalize().dot(&b.normalize()).abs().min(1.0).acos()
+}
+
+/// 对称半正定矩阵最小特征值对应的单位特征向量（带小位移的反幂迭代）
+fn smallest_eigenvector(m: &[Vec<f64>]) -> Option<Vec<f64>> {
+    let n = m.len();
+    let trace: f64 = (0..n).map(|i| m[i][i]).sum();
+    let shift = 1e-9 * trace.max(1.0);
+    let shifted: Vec<Vec<f64>> = m.iter().enumerate()
+        .map(|(i, row)| row.iter().enumerate().map(|(j, v)| if i == j { v + shift } else { *v }).collect())
+        .collect();
+    
+    let mut v = vec![1.0; n];
+    for _ in 0..50 {
+        let next = solve_linear(shifted.clone(), v)?;
+        let norm = next.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if !(norm > 0.0 && norm.is_finite()) {
+            return None;
+        }
+        v = next.into_iter().map(|x| x / norm).collect();
+    }
+    Some(v)
+}
+
+/// 由各姿态的观测求相机在头部坐标系中的位姿，并检查残差
+pub fn solve_hand_eye(samples: &[HandEyeSample], config: &HandEyeConfig) -> Result<HandEyeCalibration> {
+    if samples.len() < config.min_samples {
+        return Err(anyhow::anyhow!("只有 {} 个姿态看到了标记，至少需要 {} 个", samples.len(), config.min_samples));
+    }
+    
+    // 所有姿态对的相对运动 A = Hⱼ⁻¹·Hᵢ，B = Cⱼ·Cᵢ⁻¹
+    let mut motions = Vec::new();
+    for (i, first) in samples.iter().enumerate() {
+        for second in &samples[i + 1..] {
+            let a = second.head_in_base.inverse().compose(&first.head_in_base);
+            let b = second.marker_in_camera.compose(&first.marker_in_camera.inverse());
+            motions.push((a, b));
+        }
+    }
+    
+    // 转动轴都平行时绕该轴的相机旋转无法确定
+    let axes: Vec<Vector3> = motions.iter()
+        .map(|(a, _)| canonical(a.rotation))
+        .filter(|q| q.w < (0.01f64).cos())
+        .map(|q| Vector3::new(q.x, q.y, q.z).normalize())
+        .collect();
+    let diverse = axes.iter().any(|a| axes.iter().any(|b| a.cross(b).magnitude() > 0.1));
+    if !diverse {
+        return Err(anyhow::anyhow!("标定姿态的转动轴都平行，需要同时包含水平和俯仰转动"));
+    }
+    
+    // 旋转：最小化 Σ‖(L(q_A) − R(q_B))·q_X‖²
+    let mut normal = vec![vec![0.0; 4]; 4];
+    for (a, b) in &motions {
+        let (l, r) = (left_matrix(&canonical(a.rotation)), right_matrix(&canonical(b.rotation)));
+        for i in 0..4 {
+            for j in 0..4 {
+                normal[i][j] += (0..4).map(|k| (l[k][i] - r[k][i]) * (l[k][j] - r[k][j])).sum::<f64>();
+            }
+        }
+    }
+    let q = smallest_eigenvector(&normal)
+        .ok_or_else(|| anyhow::anyhow!("手眼标定求解旋转失败"))?;
+    let rotation = canonical(Quaternion::new(q[0], q[1], q[2], q[3]));
+    
+    // 平移：最小化 Σ‖(R_A − I)·t_X − (R_X·t_B − t_A)‖²
+    let basis = [Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0)];
+    let mut normal = vec![vec![0.0; 3]; 3];
+    let mut rhs = vec![0.0; 3];
+    for (a, b) in &motions {
+        let columns = basis.map(|e| a.rotation.rotate_vector(&e) - e);
+        let target = rotation.rotate_vector(&b.translation) - a.translation;
+        for i in 0..3 {
+            for j in 0..3 {
+                normal[i][j] += columns[i].dot(&columns[j]);
+            }
+            rhs[i] += columns[i].dot(&target);
+        }
+    }
+    let t = solve_linear(normal, rhs)
+        .ok_or_else(|| anyhow::anyhow!("手眼标定求解平移失败"))?;
+    let camera_in_head = Transform::new(Vector3::new(t[0], t[1], t[2]), rotation);
+    
+    // 残差：各姿态算出的标记位姿应当重合
+    let markers: Vec<Transform> = samples.iter()
+        .map(|s| s.head_in_base.compose(&camera_in_head).compose(&s.marker_in_camera))
+        .collect();
+    let count = markers.len() as f64;
+    let mean_translation = markers.iter().fold(Vector3::zero(), |sum, m| sum + m.translation) * (1.0 / count);
+    let reference = markers[0].rotation;
+    let sum = markers.iter().fold(Quaternion::new(0.0, 0.0, 0.0, 0.0), |sum, m| {
+        let sign = if m.rotation.dot(&reference) < 0.0 { -1.0 } else { 1.0 };
+        Quaternion::new(sum.w + sign * m.rotation.w, sum.x + sign * m.rotation.x, sum.y + sign * m.rotation.y, sum.z + sign * m.rotation.z)
+    });
+    let mean_rotation = sum.normalize();
+    
+    let rms = |errors: Vec<f64>| (errors.iter().map(|e| e * e).sum::<f64>() / count).sqrt();
+    let translation_residual = rms(markers.iter().map(|m| (m.translation - mean_translation).magnitude()).collect());
+    let rotation_residual = rms(markers.iter().map(|m| rotation_angle(&m.rotation, &mean_rotation)).collect());
+    
+    if translation_residual > config.max_translation_residual || rotation_residual > config.max_rotation_residual {
+        return Err(anyhow::anyhow!(
+            "手眼标定残差过大：位置 {:.4} m，朝向 {:.4} rad，请检查标记是否固定、检测是否准确",
+            translation_residual, rotation_residual
+        ));
+    }
+    
+    Ok(HandEyeCalibration {
+        camera_in_head,
+        marker_in_base: Transform::new(mean_translation, mean_rotation),
+        translation_residual,
+        rotation_residual,
+        samples: samples.len(),
+    })
+}
+
+/// 运行手眼标定：头部按脚本转动，每个姿态稳定后调用observe检测标记，求解后写入控制器的坐标变换树
+///
+/// 需要控制器运行且头部能自由移动，结束后头部回到开始时的位置。
+pub async fn calibrate_hand_eye<F, Fut>(
+    controller: &RealtimeController,
+    config: &HandEyeConfig,
+    mut observe: F,
+) -> Result<HandEyeCalibration>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<Transform>>,
+{
+    config.validate()?;
+    
+    let (pan_joint, tilt_joint) = {
+        let tree = controller.transforms().read().await;
+        (tree.config().head_pan_joint.clone(), tree.config().head_tilt_joint.clone())
+    };
+    let start = controller.sensor_snapshot();
+    let position = |name: &str| start.joint_states.get(name)
+        .map(|state| state.position)
+        .ok_or_else(|| anyhow::anyhow!("关节 '{}' 不存在", name));
+    let center = HeadPose { pan: position(&pan_joint)?, tilt: position(&tilt_joint)? };
+    
+    info!("开始手眼标定，共 {} 个姿态", config.poses.len());
+    
+    let move_head = |pose: HeadPose| {
+        let commands = [(&pan_joint, pose.pan), (&tilt_joint, pose.tilt)]
+            .map(|(joint_name, target)| MotionCommand::builder(joint_name.clone(), CommandType::Position).position(target).build());
+        async move {
+            for command in commands {
+                controller.add_command(command?).await?;
+            }
+            Ok::<_, anyhow::Error>(())
+        }
+    };
+    
+    let mut samples = Vec::new();
+    for (index, pose) in config.poses.iter().enumerate() {
+        move_head(HeadPose { pan: center.pan + pose.pan, tilt: center.tilt + pose.tilt }).await?;
+        sleep(Duration::from_millis(config.settle_ms)).await;
+        
+        let Some(marker_in_camera) = observe().await else {
+            warn!("手眼标定第 {} 个姿态看不到标记，已跳过", index + 1);
+            continue;
+        };
+        
+        let data = controller.sensor_snapshot();
+        let head_in_base = {
+            let mut tree = controller.transforms().write().await;
+            tree.update_from_joint_states(&data.joint_states, data.timestamp)?;
+            tree.lookup_transform(BASE_FRAME, HEAD_FRAME, None)?
+        };
+        samples.push(HandEyeSample { head_in_base, marker_in_camera });
+    }
+    
+    move_head(center).await?;
+    
+    let calibration = solve_hand_eye(&samples, config)?;
+    controller.transforms().write().await.set_camera_calibration(calibration.camera_in_head)?;
+    
+    info!(
+        "手眼标定完成：{} 个姿态，残差 {:.4} m / {:.4} rad",
+        calibration.samples, calibration.translation_residual, calibration.rotation_residual
+    );
+    Ok(calibration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transforms::{TransformConfig, TransformTree, CAMERA_FRAME};
+    use std::collections::HashMap;
+    
+    /// 用运动学生成各姿态的头部位姿，按真实外参算出标记在相机中的位姿
+    fn synthetic_samples(camera_in_head: Transform, marker_in_base: Transform, poses: &[HeadPose]) -> Vec<HandEyeSample> {
+        let mut tree = TransformTree::new(TransformConfig::default()).unwrap();
+        poses.iter().enumerate().map(|(i, pose)| {
+            let mut joints = HashMap::new();
+            for (name, position) in [("head_pan", pose.pan), ("head_tilt", pose.tilt)] {
+                let mut state = JointState::new(name.to_string());
+                state.position = position;
+                joints.insert(name.to_string(), state);
+            }
+            tree.update_from_joint_states(&joints, current_timestamp() + 1 + i as u64).unwrap();
+            let head_in_base = tree.lookup_transform(BASE_FRAME, HEAD_FRAME, None).unwrap();
+            let marker_in_camera = head_in_base.compose(&camera_in_head).inverse().compose(&marker_in_base);
+            HandEyeSample { head_in_base, marker_in_camera }
+        }).collect()
+    }
+    
+    #[test]
+    fn test_solve_hand_eye_recovers_extrinsics() {
+        let truth = Transform::new(
+            Vector3::new(0.06, -0.01, 0.035),
+            Quaternion::from_euler(0.02, -0.05, 0.03),
+        );
+        let marker = Transform::new(Vector3::new(0.8, 0.1, 0.3), Quaternion::from_euler(0.0, 1.5, 3.1));
+        let config = HandEyeConfig::default();
+        let samples = synthetic_samples(truth, marker, &config.poses);
+        
+        let calibration = solve_hand_eye(&samples, &config).unwrap();
+        assert!((calibration.camera_in_head.translation - truth.translation).magnitude() < 1e-6);
+        assert!(rotation_angle(&calibration.camera_in_head.rotation, &truth.rotation) < 1e-6);
+        assert!((calibration.marker_in_base.translation - marker.translation).magnitude() < 1e-6);
+        assert!(calibration.translation_residual < 1e-6);
+        
+        // 结果写入坐标变换树
+        let mut tree = TransformTree::new(TransformConfig::default()).unwrap();
+        tree.set_camera_calibration(calibration.camera_in_head).unwrap();
+        let camera = tree.lookup_transform(HEAD_FRAME, CAMERA_FRAME, None).unwrap();
+        assert!((camera.translation - truth.translation).magnitude() < 1e-6);
+    }
+    
+    #[test]
+    fn test_solve_hand_eye_rejects_degenerate_poses() {
+        let truth = Transform::new(Vector3::new(0.05, 0.0, 0.03), Quaternion::identity());
+        let marker = Transform::new(Vector3::new(0.8, 0.0, 0.2), Quaternion::identity());
+        let config = HandEyeConfig::default();
+        
+        // 只有水平转动，绕z轴的相机旋转和z方向平移无法确定
+        let pan_only: Vec<HeadPose> = (0..6).map(|i| HeadPose { pan: 0.1 * i as f64 - 0.25, tilt: 0.0 }).collect();
+        assert!(solve_hand_eye(&synthetic_samples(truth, marker, &pan_only), &config).is_err());
+        
+        // 标记被碰动后残差过大
+        let mut samples = synthetic_samples(truth, marker, &config.poses);
+        samples[3].marker_in_camera.translation = samples[3].marker_in_camera.translation + Vector3::new(0.05, 0.0, 0.0);
+        assert!(solve_hand_eye(&samples, &config).is_err());
+    }
+}
@@ -1,17 +1,32 @@
 //! 硬件接口模块
 //! 
 //! 提供与Reachy Mini机器人硬件的底层通信接口，包括串口通信、I2C、GPIO等。
+//! 
+//! 每个子系统按`backends`配置在运行时选择真实设备或模拟实现，
+//! 同一个二进制可以在没有硬件的开发机上仿真运行。
+
+mod backend;
+mod dynamixel;
+pub mod io_thread;
 
 use crate::common::*;
+use crate::joints::JointSetConfig;
+use crate::shutdown::{CancellationToken, TaskGroup};
 use anyhow::Result;
+use backend::{Devices, Gpio};
+use io_thread::{io_channel, IoEndpoints, IoThreadConfig, ServoBus, ThreadTiming, ThreadTimingStats};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{RwLock, mpsc, Mutex};
-use tokio::time::{interval, timeout};
+use tokio::time::interval;
 use log::{info, warn, error, debug};
 
+/// 总线扫描时每个ID等待PING应答的最长时间（毫秒），舵机默认的应答延迟远小于此值
+const SCAN_PING_TIMEOUT_MS: u64 = 20;
+
 /// 硬件配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HardwareConfig {
@@ -24,6 +39,20 @@ pub struct HardwareConfig {
     pub communication_timeout_ms: u64,
     pub retry_attempts: u32,
     pub heartbeat_interval_ms: u64,
+    /// 机器人序列号，未配置时从主板EEPROM读取
+    #[serde(default)]
+    pub robot_serial: Option<String>,
+    /// 存放主板序列号的EEPROM的I2C地址（at24驱动）
+    #[serde(default)]
+    pub eeprom_address: Option<u8>,
+    #[serde(default)]
+    pub park: ParkConfig,
+    /// 串口、I2C和GPIO的后端选择
+    #[serde(default)]
+    pub backends: BackendSelection,
+    /// 独立的总线I/O线程
+    #[serde(default)]
+    pub io_thread: IoThreadConfig,
 }
 
 impl Default for HardwareConfig {
@@ -43,6 +72,11 @@ impl Default for HardwareConfig {
             communication_timeout_ms: 1000,
             retry_attempts: 3,
             heartbeat_interval_ms: 1000,
+            robot_serial: None,
+            eeprom_address: Some(0x50),
+            park: ParkConfig::default(),
+            backends: BackendSelection::default(),
+            io_thread: IoThreadConfig::default(),
         }
     }
 }
@@ -63,11 +97,91 @@ impl ConfigValidation for HardwareConfig {
         
         self.servo_config.validate()?;
         self.sensor_config.validate()?;
+        self.park.validate()?;
+        self.io_thread.validate()?;
+        
+        if let Some(relay) = &self.park.power_relay {
+            if !self.gpio_pins.contains_key(relay) {
+                return Err(anyhow::anyhow!("断电继电器GPIO '{}' 未在gpio_pins中定义", relay));
+            }
+        }
         
         Ok(())
     }
 }
 
+/// 停靠（收纳）配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParkConfig {
+    /// 各舵机的收纳位置（舵机位置单位），未指定的舵机回到零位
+    pub positions: HashMap<u8, i16>,
+    pub speed: u16,
+    /// 移动到收纳位置后的等待时间
+    pub settle_ms: u64,
+    /// 扭矩从当前值降到0的时长
+    pub torque_ramp_ms: u64,
+    pub torque_ramp_steps: u32,
+    /// 保存最终位置的文件，下次启动时可据此恢复
+    pub state_file: PathBuf,
+    /// 断电继电器对应的GPIO名称（gpio_pins中的键）
+    pub power_relay: Option<String>,
+    /// 停止硬件接口时自动停靠
+    pub park_on_shutdown: bool,
+}
+
+impl Default for ParkConfig {
+    fn default() -> Self {
+        Self {
+            positions: HashMap::new(),
+            speed: 200,
+            settle_ms: 1500,
+            torque_ramp_ms: 1000,
+            torque_ramp_steps: 10,
+            state_file: PathBuf::from("data/park_state.json"),
+            power_relay: None,
+            park_on_shutdown: true,
+        }
+    }
+}
+
+impl ConfigValidation for ParkConfig {
+    fn validate(&self) -> Result<()> {
+        if self.speed == 0 {
+            return Err(anyhow::anyhow!("停靠速度必须大于0"));
+        }
+        
+        if self.torque_ramp_steps == 0 {
+            return Err(anyhow::anyhow!("扭矩下降步数必须大于0"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 停靠后保存的关节位置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParkedState {
+    pub timestamp: u64,
+    pub positions: HashMap<u8, i16>,
+    pub powered_off: bool,
+}
+
+impl ParkedState {
+    /// 读取上次停靠保存的状态
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+    
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
 /// 舵机配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServoConfig {
@@ -77,17 +191,34 @@ pub struct ServoConfig {
     pub torque_limits: HashMap<u8, u16>,
     pub temperature_limit: u8,
     pub voltage_limits: (f32, f32), // (min, max)
+    /// 各舵机的单位换算参数，未配置的舵机使用默认参数
+    #[serde(default)]
+    pub conversions: HashMap<u8, ServoConversion>,
+}
+
+impl ServoConfig {
+    /// 指定舵机的单位换算参数
+    pub fn conversion(&self, id: u8) -> ServoConversion {
+        self.conversions.get(&id).cloned().unwrap_or_default()
+    }
 }
 
 impl Default for ServoConfig {
     fn default() -> Self {
-        let servo_ids = vec![1, 2, 3, 4, 5, 6, 7, 8]; // 8个舵机
+        // 每个关节对应一个舵机
+        let joints = JointSetConfig::default();
+        let servo_ids: Vec<u8> = joints.joints.iter().map(|j| j.servo_id).collect();
         let mut position_limits = HashMap::new();
         let mut speed_limits = HashMap::new();
         let mut torque_limits = HashMap::new();
         
-        for &id in &servo_ids {
-            position_limits.insert(id, (-1800, 1800)); // ±180度
+        let conversion = ServoConversion::default();
+        for joint in &joints.joints {
+            let id = joint.servo_id;
+            // 连续旋转的舵机不设位置限制
+            if !joint.continuous {
+                position_limits.insert(id, conversion.angle_range_to_ticks(joint.min_position, joint.max_position));
+            }
             speed_limits.insert(id, 1000); // 最大速度
             torque_limits.insert(id, 1000); // 最大扭矩
         }
@@ -99,6 +230,7 @@ impl Default for ServoConfig {
             torque_limits,
             temperature_limit: 70, // 70°C
             voltage_limits: (6.0, 12.0), // 6V-12V
+            conversions: HashMap::new(),
         }
     }
 }
@@ -117,10 +249,98 @@ impl ConfigValidation for ServoConfig {
             return Err(anyhow::anyhow!("电压限制范围无效"));
         }
         
+        for (id, conversion) in &self.conversions {
+            conversion.validate().map_err(|e| {
+                anyhow::anyhow!("舵机 {} 的单位换算参数无效: {}", id, e)
+            })?;
+        }
+        
+        Ok(())
+    }
+}
+
+/// 舵机原始单位与关节角度之间的换算参数
+///
+/// 关节角度 = (舵机位置 - center_offset) × direction / resolution × 2π / gear_ratio，
+/// 速度读数使用与位置相同的单位（每秒的舵机位置变化量）。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServoConversion {
+    /// 舵机输出轴每转一圈的位置单位数
+    pub resolution: u32,
+    /// 减速比：关节转一圈时舵机输出轴转过的圈数
+    pub gear_ratio: f64,
+    /// 关节零位对应的舵机位置
+    pub center_offset: i16,
+    /// 舵机转向与关节正方向一致时为1，相反时为-1
+    pub direction: i8,
+}
+
+impl Default for ServoConversion {
+    fn default() -> Self {
+        // 默认单位为0.1度，舵机直接驱动关节
+        Self {
+            resolution: 3600,
+            gear_ratio: 1.0,
+            center_offset: 0,
+            direction: 1,
+        }
+    }
+}
+
+impl ConfigValidation for ServoConversion {
+    fn validate(&self) -> Result<()> {
+        if self.resolution == 0 {
+            return Err(anyhow::anyhow!("舵机分辨率必须大于0"));
+        }
+        
+        if !self.gear_ratio.is_finite() || self.gear_ratio <= 0.0 {
+            return Err(anyhow::anyhow!("减速比必须为正数"));
+        }
+        
+        if self.direction != 1 && self.direction != -1 {
+            return Err(anyhow::anyhow!("方向必须是1或-1"));
+        }
+        
         Ok(())
     }
 }
 
+impl ServoConversion {
+    /// 每个舵机位置单位对应的关节角度（rad）
+    fn radians_per_tick(&self) -> f64 {
+        std::f64::consts::TAU / (self.resolution as f64 * self.gear_ratio)
+    }
+    
+    /// 舵机位置转换为关节角度
+    pub fn ticks_to_angle(&self, ticks: i16) -> Angle {
+        let relative = (ticks as f64 - self.center_offset as f64) * self.direction as f64;
+        Angle::from_radians(relative * self.radians_per_tick())
+    }
+    
+    /// 关节角度转换为舵机位置（超出舵机范围时截断）
+    pub fn angle_to_ticks(&self, angle: Angle) -> i16 {
+        let relative = angle.radians() / self.radians_per_tick() * self.direction as f64;
+        (relative.round() + self.center_offset as f64).clamp(i16::MIN as f64, i16::MAX as f64) as i16
+    }
+    
+    /// 关节角度范围转换为舵机位置范围，方向相反时交换上下限
+    pub fn angle_range_to_ticks(&self, min: Angle, max: Angle) -> (i16, i16) {
+        let (a, b) = (self.angle_to_ticks(min), self.angle_to_ticks(max));
+        (a.min(b), a.max(b))
+    }
+    
+    /// 舵机速度读数转换为关节角速度
+    pub fn speed_to_velocity(&self, speed: i16) -> AngularVelocity {
+        AngularVelocity::from_radians_per_second(speed as f64 * self.direction as f64 * self.radians_per_tick())
+    }
+    
+    /// 关节角速度转换为舵机速度设定值（只取大小）
+    pub fn velocity_to_speed(&self, velocity: AngularVelocity) -> u16 {
+        let speed = velocity.radians_per_second().abs() / self.radians_per_tick();
+        speed.round().min(u16::MAX as f64) as u16
+    }
+}
+
 /// 传感器配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SensorConfig {
@@ -158,7 +378,7 @@ impl ConfigValidation for SensorConfig {
 }
 
 /// 硬件状态
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct HardwareStatus {
     pub is_connected: bool,
     pub serial_connected: bool,
@@ -168,20 +388,100 @@ pub struct HardwareStatus {
     pub last_heartbeat: u64,
     pub communication_errors: u64,
     pub performance_stats: PerformanceStats,
+    #[serde(default)]
+    pub identity: HardwareIdentity,
+    /// 当前使用模拟后端的子系统
+    #[serde(default)]
+    pub mock_backends: Vec<String>,
+    /// 总线I/O线程的周期耗时，未启动I/O线程时为None
+    #[serde(default)]
+    pub io_timing: Option<ThreadTiming>,
 }
 
-impl Default for HardwareStatus {
-    fn default() -> Self {
-        Self {
-            is_connected: false,
-            serial_connected: false,
-            i2c_connected: false,
-            servo_status: HashMap::new(),
-            sensor_status: SensorStatus::default(),
-            last_heartbeat: 0,
-            communication_errors: 0,
-            performance_stats: PerformanceStats::new(),
+/// 硬件身份信息，连接时采集，用于设备盘点
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HardwareIdentity {
+    /// 机器人/主板序列号
+    pub robot_serial: Option<String>,
+    /// 序列号来源："config" 或 "eeprom"
+    pub serial_source: Option<String>,
+    pub serial_adapter: Option<SerialAdapterIdentity>,
+    pub servos: HashMap<u8, ServoIdentity>,
+    /// 配置了但没有应答PING的舵机，型号和固件版本未知
+    #[serde(default)]
+    pub unreachable_servos: Vec<u8>,
+    /// 舵机信息由模拟串口后端生成，不是真实读数
+    #[serde(default)]
+    pub simulated: bool,
+    pub collected_at: u64,
+}
+
+/// 舵机身份信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServoIdentity {
+    pub id: u8,
+    pub model_number: u16,
+    pub firmware_version: u8,
+}
+
+/// USB串口适配器身份信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SerialAdapterIdentity {
+    pub port: String,
+    /// USB厂商ID（十六进制字符串，如 "0403"）
+    pub vendor_id: Option<String>,
+    pub product_id: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial_number: Option<String>,
+}
+
+impl SerialAdapterIdentity {
+    /// 通过sysfs查询串口对应的USB设备信息
+    pub fn read(port: &str) -> Self {
+        let mut identity = Self {
+            port: port.to_string(),
+            ..Default::default()
+        };
+        
+        // /dev/serial/by-id/... 等符号链接先解析为实际设备
+        let device = std::fs::canonicalize(port).unwrap_or_else(|_| port.into());
+        let Some(name) = device.file_name() else {
+            return identity;
+        };
+        let sysfs = std::path::Path::new("/sys/class/tty").join(name).join("device");
+        let Ok(device_dir) = std::fs::canonicalize(sysfs) else {
+            return identity;
+        };
+        
+        // 向上查找带有idVendor的USB设备目录
+        if let Some(usb_dir) = device_dir.ancestors().find(|dir| dir.join("idVendor").exists()) {
+            let read = |attr: &str| {
+                std::fs::read_to_string(usb_dir.join(attr))
+                    .ok()
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            };
+            identity.vendor_id = read("idVendor");
+            identity.product_id = read("idProduct");
+            identity.manufacturer = read("manufacturer");
+            identity.product = read("product");
+            identity.serial_number = read("serial");
         }
+        
+        identity
+    }
+}
+
+/// 从EEPROM内容中解析序列号（ASCII，遇到0x00或0xFF结束）
+fn parse_eeprom_serial(data: &[u8]) -> Option<String> {
+    let end = data.iter().position(|&b| b == 0x00 || b == 0xFF).unwrap_or(data.len());
+    let serial = std::str::from_utf8(&data[..end]).ok()?.trim();
+    
+    if serial.is_empty() || !serial.chars().all(|c| c.is_ascii_graphic()) {
+        None
+    } else {
+        Some(serial.to_string())
     }
 }
 
@@ -197,6 +497,10 @@ pub struct ServoStatus {
     pub is_moving: bool,
     pub error_flags: u8,
     pub last_update: u64,
+    #[serde(default)]
+    pub torque_enabled: bool,
+    #[serde(default)]
+    pub torque_limit: u16,
 }
 
 impl Default for ServoStatus {
@@ -211,6 +515,8 @@ impl Default for ServoStatus {
             is_moving: false,
             error_flags: 0,
             last_update: 0,
+            torque_enabled: false,
+            torque_limit: 0,
         }
     }
 }
@@ -252,6 +558,7 @@ pub enum CalibrationStatus {
 
 /// 硬件命令
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum HardwareCommand {
     ServoMove {
         id: u8,
@@ -281,6 +588,13 @@ pub enum HardwareCommand {
     Calibrate,
 }
 
+/// 一次硬件事务：命令及其关联ID，ID出现在执行命令的日志中
+#[derive(Debug, Clone)]
+pub struct HardwareTransaction {
+    pub correlation_id: String,
+    pub command: HardwareCommand,
+}
+
 /// 硬件响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum HardwareResponse {
@@ -330,12 +644,16 @@ pub enum HardwareError {
 pub struct HardwareInterface {
     config: HardwareConfig,
     status: Arc<RwLock<HardwareStatus>>,
-    command_queue: Arc<Mutex<mpsc::UnboundedReceiver<HardwareCommand>>>,
-    command_sender: mpsc::UnboundedSender<HardwareCommand>,
-    response_sender: Arc<Mutex<Option<mpsc::UnboundedSender<HardwareResponse>>>>,
-    communication_handle: Option<tokio::task::JoinHandle<()>>,
-    heartbeat_handle: Option<tokio::task::JoinHandle<()>>,
+    command_queue: Arc<Mutex<mpsc::UnboundedReceiver<HardwareTransaction>>>,
+    command_sender: mpsc::UnboundedSender<HardwareTransaction>,
+    tasks: TaskGroup,
     is_running: Arc<RwLock<bool>>,
+    /// 换算为SI单位的关节状态，保留连续旋转关节的整圈计数
+    joint_states: Arc<RwLock<HashMap<String, JointState>>>,
+    devices: Devices,
+    gpio: Gpio,
+    /// 总线I/O线程的耗时统计，未启动时为None
+    io_timing: Option<Arc<ThreadTimingStats>>,
 }
 
 impl HardwareInterface {
@@ -356,10 +674,12 @@ impl HardwareInterface {
             status,
             command_queue,
             command_sender,
-            response_sender: Arc::new(Mutex::new(None)),
-            communication_handle: None,
-            heartbeat_handle: None,
+            tasks: TaskGroup::new("硬件接口"),
             is_running,
+            joint_states: Arc::new(RwLock::new(HashMap::new())),
+            devices: Devices::default(),
+            gpio: Gpio::default(),
+            io_timing: None,
         };
         
         info!("硬件接口初始化完成");
@@ -368,7 +688,8 @@ impl HardwareInterface {
     
     /// 启动硬件接口
     pub async fn start(&mut self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
+        let is_running_lock = Arc::clone(&self.is_running);
+        let mut is_running = is_running_lock.write().await;
         if *is_running {
             return Ok(());
         }
@@ -398,24 +719,37 @@ impl HardwareInterface {
     
     /// 停止硬件接口
     pub async fn stop(&mut self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
+        if let Some(Err(e)) = self.shutdown(self.config.park.park_on_shutdown).await? {
+            error!("停止前停靠失败: {}", e);
+        }
+        Ok(())
+    }
+    
+    /// 先停靠再停止硬件接口，不受park_on_shutdown影响；接口未运行时返回None
+    ///
+    /// 停靠失败时接口仍会停止，并返回停靠错误
+    pub async fn park_and_stop(&mut self) -> Result<Option<ParkedState>> {
+        self.shutdown(true).await?.transpose()
+    }
+    
+    /// 停止通信和心跳循环并关闭连接，park为true时先停靠；返回停靠结果，未运行或未停靠时为None
+    async fn shutdown(&mut self, park: bool) -> Result<Option<Result<ParkedState>>> {
+        let is_running_lock = Arc::clone(&self.is_running);
+        let mut is_running = is_running_lock.write().await;
         if !*is_running {
-            return Ok(());
+            return Ok(None);
         }
         
         info!("停止硬件接口...");
         
-        *is_running = false;
+        // 先停靠，避免进程退出后关节失去支撑而跌落
+        let parked = if park { Some(self.park(false).await) } else { None };
         
-        // 停止通信循环
-        if let Some(handle) = self.communication_handle.take() {
-            handle.abort();
-        }
+        *is_running = false;
         
-        // 停止心跳循环
-        if let Some(handle) = self.heartbeat_handle.take() {
-            handle.abort();
-        }
+        // 通知通信和心跳循环退出，等待已入队的命令执行完
+        self.tasks.shutdown().await;
+        self.io_timing = None;
         
         // 关闭硬件连接
         self.cleanup_hardware().await?;
@@ -429,14 +763,31 @@ impl HardwareInterface {
         }
         
         info!("硬件接口停止完成");
-        Ok(())
+        Ok(parked)
+    }
+    
+    /// 在独立线程中启动总线I/O，返回交给实时控制器的端点；停止硬件接口时线程一并退出
+    pub fn start_io_thread(&mut self, bus: Box<dyn ServoBus>) -> Result<IoEndpoints> {
+        if !self.config.io_thread.enabled {
+            return Err(anyhow::anyhow!("总线I/O线程未启用"));
+        }
+        if self.io_timing.is_some() {
+            return Err(anyhow::anyhow!("总线I/O线程已在运行"));
+        }
+        
+        let (thread, endpoints) = io_channel(bus, self.config.io_thread.clone())?;
+        let shutdown = self.tasks.token();
+        self.tasks.spawn_blocking("总线I/O线程", move || thread.run(shutdown));
+        self.io_timing = Some(Arc::clone(&endpoints.timing));
+        
+        Ok(endpoints)
     }
     
     /// 初始化硬件
     async fn initialize_hardware(&mut self) -> Result<()> {
         info!("初始化硬件连接...");
         
-        // 初始化串口（模拟）
+        // 初始化串口
         match self.initialize_serial().await {
             Ok(_) => {
                 let mut status = self.status.write().await;
@@ -448,7 +799,7 @@ impl HardwareInterface {
             }
         }
         
-        // 初始化I2C（模拟）
+        // 初始化I2C
         match self.initialize_i2c().await {
             Ok(_) => {
                 let mut status = self.status.write().await;
@@ -460,7 +811,7 @@ impl HardwareInterface {
             }
         }
         
-        // 初始化GPIO（模拟）
+        // 初始化GPIO
         self.initialize_gpio().await?;
         
         // 初始化舵机状态
@@ -469,29 +820,151 @@ impl HardwareInterface {
         // 初始化传感器
         self.initialize_sensors().await?;
         
+        // 采集硬件身份信息
+        self.collect_identity().await;
+        
         Ok(())
     }
     
-    /// 初始化串口（模拟）
-    async fn initialize_serial(&self) -> Result<()> {
-        // 在实际实现中，这里会打开串口设备
-        // 现在只是模拟成功
-        debug!("模拟串口初始化: {} @ {}", self.config.serial_port, self.config.baud_rate);
+    /// 采集序列号、串口适配器和舵机的身份信息
+    ///
+    /// 舵机的型号和固件版本取自PING应答；串口使用模拟后端时填入模拟值并标记simulated
+    async fn collect_identity(&self) {
+        let (servos, unreachable_servos, simulated) = self.identify_servos().await;
+        if !unreachable_servos.is_empty() {
+            warn!("舵机没有应答PING，型号和固件版本未知: {:?}", unreachable_servos);
+        }
+        
+        let mut status = self.status.write().await;
+        
+        let (robot_serial, serial_source) = match &self.config.robot_serial {
+            Some(serial) => (Some(serial.clone()), Some("config".to_string())),
+            None => match self.read_eeprom_serial() {
+                Some(serial) => (Some(serial), Some("eeprom".to_string())),
+                None => (None, None),
+            },
+        };
+        
+        if robot_serial.is_none() {
+            warn!("未能获取机器人序列号，请在配置中设置robot_serial");
+        }
+        
+        let serial_adapter = status.serial_connected
+            .then(|| SerialAdapterIdentity::read(&self.config.serial_port));
+        
+        status.identity = HardwareIdentity {
+            robot_serial,
+            serial_source,
+            serial_adapter,
+            servos,
+            unreachable_servos,
+            simulated,
+            collected_at: current_timestamp(),
+        };
+        
+        if let Ok(value) = serde_json::to_value(&status.identity) {
+            crate::set_hardware_identity(value);
+        }
+        info!("硬件身份信息采集完成: 序列号 {:?}", status.identity.robot_serial);
+    }
+    
+    /// 通过at24驱动的sysfs节点读取主板EEPROM中的序列号
+    fn read_eeprom_serial(&self) -> Option<String> {
+        let address = self.config.eeprom_address?;
+        let path = format!("/sys/bus/i2c/devices/{}-{:04x}/eeprom", self.config.i2c_bus, address);
+        
+        let data = std::fs::read(&path).ok()?;
+        let serial = parse_eeprom_serial(&data[..data.len().min(32)]);
+        debug!("读取EEPROM序列号 {}: {:?}", path, serial);
+        serial
+    }
+    
+    /// 向已配置的舵机逐个PING，返回(应答的舵机, 没有应答的ID, 是否为模拟值)
+    ///
+    /// 串口未打开时，模拟后端返回模拟值，否则所有舵机都记为没有应答
+    async fn identify_servos(&self) -> (HashMap<u8, ServoIdentity>, Vec<u8>, bool) {
+        let (mut ids, mock) = {
+            let status = self.status.read().await;
+            let ids: Vec<u8> = status.servo_status.keys().copied().collect();
+            (ids, status.mock_backends.iter().any(|name| name == "serial"))
+        };
+        ids.sort_unstable();
+        
+        let Some(serial) = &self.devices.serial else {
+            if mock {
+                return (ids.iter().map(|&id| (id, Self::simulated_servo_identity(id))).collect(), Vec::new(), true);
+            }
+            return (HashMap::new(), ids, false);
+        };
+        
+        let mut port = match serial.try_clone() {
+            Ok(port) => port,
+            Err(e) => {
+                warn!("无法复制串口句柄，舵机身份未知: {}", e);
+                return (HashMap::new(), ids, false);
+            },
+        };
+        let timeout = Duration::from_millis(self.config.communication_timeout_ms.min(SCAN_PING_TIMEOUT_MS));
+        
+        // PING是阻塞的串口读写，放到阻塞线程池中执行
+        let probe = ids.clone();
+        match tokio::task::spawn_blocking(move || dynamixel::identify(&mut port, &probe, timeout)).await {
+            Ok((servos, unreachable)) => (servos, unreachable, false),
+            Err(e) => {
+                warn!("舵机身份采集任务失败: {}", e);
+                (HashMap::new(), ids, false)
+            },
+        }
+    }
+    
+    /// 模拟后端的舵机型号和固件版本（XL330-M288）
+    fn simulated_servo_identity(id: u8) -> ServoIdentity {
+        ServoIdentity {
+            id,
+            model_number: 1200,
+            firmware_version: 52,
+        }
+    }
+    
+    /// 记录使用模拟后端的子系统
+    async fn mark_mock(&self, subsystem: &str) {
+        let mut status = self.status.write().await;
+        if !status.mock_backends.iter().any(|name| name == subsystem) {
+            status.mock_backends.push(subsystem.to_string());
+        }
+    }
+    
+    /// 初始化串口
+    async fn initialize_serial(&mut self) -> Result<()> {
+        if self.config.backends.serial.use_real(&self.config.serial_port) {
+            self.devices.serial = Some(backend::open_serial(&self.config.serial_port, self.config.baud_rate)?);
+        } else {
+            self.mark_mock("serial").await;
+            debug!("模拟串口初始化: {} @ {}", self.config.serial_port, self.config.baud_rate);
+        }
         Ok(())
     }
     
-    /// 初始化I2C（模拟）
-    async fn initialize_i2c(&self) -> Result<()> {
-        // 在实际实现中，这里会初始化I2C总线
-        // 现在只是模拟成功
-        debug!("模拟I2C初始化: bus {}", self.config.i2c_bus);
+    /// 初始化I2C
+    async fn initialize_i2c(&mut self) -> Result<()> {
+        if self.config.backends.i2c.use_real(backend::i2c_device(self.config.i2c_bus)) {
+            self.devices.i2c = Some(backend::open_i2c(self.config.i2c_bus)?);
+        } else {
+            self.mark_mock("i2c").await;
+            debug!("模拟I2C初始化: bus {}", self.config.i2c_bus);
+        }
         Ok(())
     }
     
-    /// 初始化GPIO（模拟）
-    async fn initialize_gpio(&self) -> Result<()> {
+    /// 初始化GPIO
+    async fn initialize_gpio(&mut self) -> Result<()> {
+        self.gpio = Gpio::new(self.config.backends.gpio.use_real(backend::GPIO_SYSFS_ROOT));
+        if !self.gpio.is_real() {
+            self.mark_mock("gpio").await;
+        }
+        
         for (name, pin) in &self.config.gpio_pins {
-            debug!("模拟GPIO初始化: {} -> pin {}", name, pin);
+            debug!("{}GPIO初始化: {} -> pin {}", if self.gpio.is_real() { "" } else { "模拟" }, name, pin);
         }
         Ok(())
     }
@@ -511,6 +984,8 @@ impl HardwareInterface {
                 is_moving: false,
                 error_flags: 0,
                 last_update: current_timestamp(),
+                torque_enabled: true,
+                torque_limit: self.config.servo_config.torque_limits.get(&servo_id).copied().unwrap_or(1000),
             };
             
             status.servo_status.insert(servo_id, servo_status);
@@ -543,80 +1018,94 @@ impl HardwareInterface {
     async fn start_communication_loop(&mut self) -> Result<()> {
         let command_queue = Arc::clone(&self.command_queue);
         let status = Arc::clone(&self.status);
-        let is_running = Arc::clone(&self.is_running);
+        let shutdown = self.tasks.token();
         let config = self.config.clone();
+        let gpio = self.gpio;
         
-        let handle = tokio::spawn(async move {
+        self.tasks.spawn("通信循环", async move {
             Self::communication_loop(
                 command_queue,
                 status,
-                is_running,
+                shutdown,
                 config,
+                gpio,
             ).await
         });
         
-        self.communication_handle = Some(handle);
         Ok(())
     }
     
     /// 通信循环
     async fn communication_loop(
-        command_queue: Arc<Mutex<mpsc::UnboundedReceiver<HardwareCommand>>>,
+        command_queue: Arc<Mutex<mpsc::UnboundedReceiver<HardwareTransaction>>>,
         status: Arc<RwLock<HardwareStatus>>,
-        is_running: Arc<RwLock<bool>>,
+        shutdown: CancellationToken,
         config: HardwareConfig,
+        gpio: Gpio,
     ) {
         let mut queue = command_queue.lock().await;
-        let timeout_duration = Duration::from_millis(config.communication_timeout_ms);
         
+        // 只在两条命令之间响应关闭，不会打断进行中的硬件事务
         loop {
-            // 检查是否应该停止
-            if !*is_running.read().await {
-                break;
-            }
-            
-            // 处理命令
-            match timeout(Duration::from_millis(100), queue.recv()).await {
-                Ok(Some(command)) => {
-                    let start_time = Instant::now();
-                    
-                    match Self::process_command(command, &status, &config).await {
-                        Ok(_) => {
-                            debug!("命令处理成功");
-                        },
-                        Err(e) => {
-                            error!("命令处理失败: {}", e);
-                            
-                            // 更新错误统计
-                            let mut status = status.write().await;
-                            status.communication_errors += 1;
-                        }
-                    }
-                    
-                    // 更新性能统计
-                    let processing_time = start_time.elapsed();
-                    let mut status = status.write().await;
-                    status.performance_stats.update_frame_stats(processing_time);
-                },
-                Ok(None) => {
+            tokio::select! {
+                biased;
+                _ = shutdown.cancelled() => break,
+                command = queue.recv() => match command {
+                    Some(command) => Self::execute_command(command, &status, &config, gpio).await,
                     // 通道关闭
-                    break;
+                    None => break,
                 },
-                Err(_) => {
-                    // 超时，继续循环
-                    continue;
-                }
             }
         }
         
+        // 排空队列：关闭前已提交的命令（例如紧急停止）仍然执行
+        let mut drained = 0;
+        while let Ok(command) = queue.try_recv() {
+            Self::execute_command(command, &status, &config, gpio).await;
+            drained += 1;
+        }
+        if drained > 0 {
+            info!("关闭前执行了 {} 条排队的硬件命令", drained);
+        }
+        
         info!("通信循环结束");
     }
     
+    /// 执行一条命令并更新统计
+    async fn execute_command(
+        transaction: HardwareTransaction,
+        status: &Arc<RwLock<HardwareStatus>>,
+        config: &HardwareConfig,
+        gpio: Gpio,
+    ) {
+        let start_time = Instant::now();
+        let HardwareTransaction { correlation_id, command } = transaction;
+        
+        match Self::process_command(command, status, config, gpio).await {
+            Ok(_) => {
+                debug!("[{}] 命令处理成功", correlation_id);
+            },
+            Err(e) => {
+                error!("[{}] 命令处理失败: {}", correlation_id, e);
+                
+                // 更新错误统计
+                let mut status = status.write().await;
+                status.communication_errors += 1;
+            }
+        }
+        
+        // 更新性能统计
+        let processing_time = start_time.elapsed();
+        let mut status = status.write().await;
+        status.performance_stats.update_frame_stats(processing_time);
+    }
+    
     /// 处理硬件命令
     async fn process_command(
         command: HardwareCommand,
         status: &Arc<RwLock<HardwareStatus>>,
         config: &HardwareConfig,
+        gpio: Gpio,
     ) -> Result<()> {
         match command {
             HardwareCommand::ServoMove { id, position, speed } => {
@@ -625,6 +1114,9 @@ impl HardwareInterface {
             HardwareCommand::ServoStop { id } => {
                 Self::process_servo_stop(id, status).await
             },
+            HardwareCommand::ServoSetTorque { id, enabled } => {
+                Self::process_set_torque(id, enabled, status).await
+            },
             HardwareCommand::ReadServoStatus { id } => {
                 Self::process_read_servo_status(id, status).await
             },
@@ -635,7 +1127,7 @@ impl HardwareInterface {
                 Self::process_read_imu(status).await
             },
             HardwareCommand::SetLED { pin, state } => {
-                Self::process_set_led(pin, state).await
+                Self::process_set_led(pin, state, gpio).await
             },
             HardwareCommand::EmergencyStop => {
                 Self::process_emergency_stop(status).await
@@ -705,6 +1197,28 @@ impl HardwareInterface {
         Ok(())
     }
     
+    /// 处理舵机扭矩开关命令
+    async fn process_set_torque(
+        id: u8,
+        enabled: bool,
+        status: &Arc<RwLock<HardwareStatus>>,
+    ) -> Result<()> {
+        let mut status = status.write().await;
+        
+        if let Some(servo_status) = status.servo_status.get_mut(&id) {
+            servo_status.torque_enabled = enabled;
+            if !enabled {
+                servo_status.is_moving = false;
+                servo_status.speed = 0;
+            }
+            servo_status.last_update = current_timestamp();
+            
+            debug!("舵机 {} 扭矩{}", id, if enabled { "开启" } else { "关闭" });
+        }
+        
+        Ok(())
+    }
+    
     /// 处理读取舵机状态命令
     async fn process_read_servo_status(
         id: u8,
@@ -758,9 +1272,10 @@ impl HardwareInterface {
     async fn process_set_led(
         pin: u8,
         state: bool,
+        gpio: Gpio,
     ) -> Result<()> {
         debug!("设置LED pin {} 状态: {}", pin, state);
-        Ok(())
+        gpio.write(pin, state)
     }
     
     /// 处理紧急停止命令
@@ -784,17 +1299,16 @@ impl HardwareInterface {
     async fn start_heartbeat_loop(&mut self) -> Result<()> {
         let heartbeat_interval = Duration::from_millis(self.config.heartbeat_interval_ms);
         let status = Arc::clone(&self.status);
-        let is_running = Arc::clone(&self.is_running);
+        let shutdown = self.tasks.token();
         
-        let handle = tokio::spawn(async move {
+        self.tasks.spawn("心跳循环", async move {
             Self::heartbeat_loop(
                 heartbeat_interval,
                 status,
-                is_running,
+                shutdown,
             ).await
         });
         
-        self.heartbeat_handle = Some(handle);
         Ok(())
     }
     
@@ -802,16 +1316,14 @@ impl HardwareInterface {
     async fn heartbeat_loop(
         heartbeat_interval: Duration,
         status: Arc<RwLock<HardwareStatus>>,
-        is_running: Arc<RwLock<bool>>,
+        shutdown: CancellationToken,
     ) {
         let mut interval = interval(heartbeat_interval);
         
         loop {
-            interval.tick().await;
-            
-            // 检查是否应该停止
-            if !*is_running.read().await {
-                break;
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
             }
             
             // 更新心跳时间戳
@@ -826,12 +1338,93 @@ impl HardwareInterface {
         info!("心跳循环结束");
     }
     
+    /// 停靠：移动到收纳姿态，逐步降低扭矩后关闭，保存最终位置，可选切断电源
+    pub async fn park(&self, power_off: bool) -> Result<ParkedState> {
+        let park = &self.config.park;
+        
+        if !self.status.read().await.is_connected {
+            return Err(HardwareError::NotConnected.into());
+        }
+        
+        info!("停靠机器人...");
+        
+        // 移动到收纳位置
+        let servo_ids: Vec<u8> = self.status.read().await.servo_status.keys().copied().collect();
+        for &id in &servo_ids {
+            let position = park.positions.get(&id).copied().unwrap_or(0);
+            Self::process_servo_move(id, position, Some(park.speed), &self.status, &self.config).await?;
+        }
+        tokio::time::sleep(Duration::from_millis(park.settle_ms)).await;
+        
+        // 逐步降低扭矩限制，让关节缓慢卸力
+        let initial_limits: HashMap<u8, u16> = self.status.read().await.servo_status.iter()
+            .map(|(&id, servo)| (id, servo.torque_limit))
+            .collect();
+        let step_delay = Duration::from_millis(park.torque_ramp_ms / park.torque_ramp_steps as u64);
+        
+        for step in 1..=park.torque_ramp_steps {
+            {
+                let mut status = self.status.write().await;
+                let remaining = 1.0 - step as f64 / park.torque_ramp_steps as f64;
+                for (id, servo) in status.servo_status.iter_mut() {
+                    let initial = initial_limits.get(id).copied().unwrap_or(0) as f64;
+                    servo.torque_limit = (initial * remaining).round() as u16;
+                }
+            }
+            tokio::time::sleep(step_delay).await;
+        }
+        
+        for &id in &servo_ids {
+            Self::process_set_torque(id, false, &self.status).await?;
+        }
+        
+        // 保存最终位置
+        let positions = self.status.read().await.servo_status.iter()
+            .map(|(&id, servo)| (id, servo.position))
+            .collect();
+        let mut state = ParkedState {
+            timestamp: current_timestamp(),
+            positions,
+            powered_off: false,
+        };
+        
+        // 断电继电器
+        if power_off {
+            match park.power_relay.as_ref().and_then(|name| self.config.gpio_pins.get(name)) {
+                Some(&pin) => {
+                    self.gpio.write(pin, false)?;
+                    info!("已断开电源继电器 (pin {})", pin);
+                    state.powered_off = true;
+                }
+                None => warn!("未配置断电继电器GPIO，跳过断电"),
+            }
+        }
+        
+        if let Err(e) = state.save(&park.state_file) {
+            warn!("保存停靠状态失败: {}", e);
+        }
+        
+        info!("停靠完成，共 {} 个舵机", servo_ids.len());
+        Ok(state)
+    }
+    
+    /// 上次停靠保存的状态
+    pub fn last_parked_state(&self) -> Option<ParkedState> {
+        ParkedState::load(&self.config.park.state_file)
+    }
+    
     /// 清理硬件
-    async fn cleanup_hardware(&self) -> Result<()> {
+    async fn cleanup_hardware(&mut self) -> Result<()> {
         info!("清理硬件连接...");
         
-        // 在实际实现中，这里会关闭串口、I2C等连接
-        // 现在只是模拟
+        // 关闭句柄即释放串口和I2C设备
+        if self.devices.serial.take().is_some() {
+            debug!("关闭串口 {}", self.config.serial_port);
+        }
+        if self.devices.i2c.take().is_some() {
+            debug!("关闭I2C总线 {}", self.config.i2c_bus);
+        }
+        self.status.write().await.mock_backends.clear();
         
         debug!("硬件连接清理完成");
         Ok(())
@@ -839,15 +1432,29 @@ impl HardwareInterface {
     
     /// 发送命令
     pub async fn send_command(&self, command: HardwareCommand) -> Result<()> {
-        self.command_sender.send(command)
+        self.send_correlated(command, None).await.map(|_| ())
+    }
+    
+    /// 发送命令并沿用上层的关联ID（未指定时生成新的ID），返回该ID
+    pub async fn send_correlated(&self, command: HardwareCommand, correlation_id: Option<String>) -> Result<String> {
+        let correlation_id = correlation_id.unwrap_or_else(new_id);
+        debug!("[{}] 提交硬件命令: {:?}", correlation_id, command);
+        self.command_sender.send(HardwareTransaction { correlation_id: correlation_id.clone(), command })
             .map_err(|e| HardwareError::Protocol(format!("发送命令失败: {}", e)))?;
-        Ok(())
+        Ok(correlation_id)
     }
     
     /// 获取状态
     pub async fn get_status(&self) -> Result<HardwareStatus> {
+        let mut status = self.status.read().await.clone();
+        status.io_timing = self.io_timing.as_ref().map(|timing| timing.snapshot());
+        Ok(status)
+    }
+    
+    /// 获取硬件身份信息
+    pub async fn get_identity(&self) -> HardwareIdentity {
         let status = self.status.read().await;
-        Ok(status.clone())
+        status.identity.clone()
     }
     
     /// 获取舵机状态
@@ -862,6 +1469,83 @@ impl HardwareInterface {
         Ok(status.servo_status.values().cloned().collect())
     }
     
+    /// 设置已配置GPIO引脚的输出电平，未在gpio_pins中配置的引脚被拒绝
+    pub async fn set_led(&self, pin: u8, state: bool) -> Result<()> {
+        if !self.config.gpio_pins.values().any(|&configured| configured == pin) {
+            return Err(anyhow::anyhow!("GPIO引脚 {} 未在配置中声明", pin));
+        }
+        
+        self.send_command(HardwareCommand::SetLED { pin, state }).await
+    }
+    
+    /// 扫描总线：向给定范围内的每个ID发送PING，返回应答的舵机（型号和固件版本取自应答），
+    /// 用于装配调试和排查接线，未配置或ID设错的舵机也能找到
+    ///
+    /// 串口使用模拟后端时没有真实总线可扫，返回配置中的舵机
+    pub async fn scan_bus(&self, ids: impl IntoIterator<Item = u8>) -> Result<Vec<ServoIdentity>> {
+        let ids: Vec<u8> = ids.into_iter().collect();
+        
+        let Some(serial) = &self.devices.serial else {
+            let status = self.status.read().await;
+            return Ok(ids.into_iter()
+                .filter(|id| status.servo_status.contains_key(id))
+                .map(Self::simulated_servo_identity)
+                .collect());
+        };
+        
+        let mut port = serial.try_clone()
+            .map_err(|e| anyhow::anyhow!("无法复制串口句柄: {}", e))?;
+        let timeout = Duration::from_millis(self.config.communication_timeout_ms.min(SCAN_PING_TIMEOUT_MS));
+        
+        // 逐个PING是阻塞的串口读写，放到阻塞线程池中执行
+        let servos = tokio::task::spawn_blocking(move || -> Result<Vec<ServoIdentity>> {
+            let mut servos = Vec::new();
+            for id in ids {
+                if let Some(identity) = dynamixel::ping(&mut port, id, timeout)? {
+                    debug!("舵机 {} 应答: 型号 {}, 固件 {}", id, identity.model_number, identity.firmware_version);
+                    servos.push(identity);
+                }
+            }
+            Ok(servos)
+        }).await.map_err(|e| anyhow::anyhow!("总线扫描任务失败: {}", e))??;
+        
+        info!("总线扫描完成，{}个舵机应答", servos.len());
+        Ok(servos)
+    }
+    
+    /// 以关节角度（SI单位）移动舵机，按该舵机的换算参数转换为舵机位置
+    pub async fn move_joint(&self, id: u8, position: Angle, velocity: Option<AngularVelocity>) -> Result<()> {
+        let conversion = self.config.servo_config.conversion(id);
+        
+        self.send_command(HardwareCommand::ServoMove {
+            id,
+            position: conversion.angle_to_ticks(position),
+            speed: velocity.map(|v| conversion.velocity_to_speed(v)),
+        }).await
+    }
+    
+    /// 读取以SI单位表示的关节状态，关节与舵机的对应关系取自关节集合
+    pub async fn read_joint_states(&self, joints: &JointSetConfig) -> HashMap<String, JointState> {
+        let status = self.status.read().await;
+        let mut joint_states = self.joint_states.write().await;
+        
+        for joint in &joints.joints {
+            let Some(servo) = status.servo_status.get(&joint.servo_id) else {
+                continue;
+            };
+            let conversion = self.config.servo_config.conversion(joint.servo_id);
+            
+            let state = joint_states.entry(joint.name.clone())
+                .or_insert_with(|| JointState::new(joint.name.clone()));
+            state.update_position(conversion.ticks_to_angle(servo.position).radians(), joint.continuous);
+            state.velocity = conversion.speed_to_velocity(servo.speed).radians_per_second();
+            state.temperature = Some(servo.temperature as f64);
+            state.is_moving = servo.is_moving;
+        }
+        
+        joint_states.clone()
+    }
+    
     /// 是否正在运行
     pub async fn is_running(&self) -> bool {
         *self.is_running.read().await
@@ -923,7 +1607,7 @@ mod tests {
     #[tokio::test]
     async fn test_servo_move_command() {
         let config = HardwareConfig::default();
-        let mut interface = HardwareInterface::new(config).await.unwrap();
+        let interface = HardwareInterface::new(config).await.unwrap();
         
         let command = HardwareCommand::ServoMove {
             id: 1,
@@ -934,4 +1618,202 @@ mod tests {
         let result = interface.send_command(command).await;
         assert!(result.is_ok());
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_servo_conversion() {
+        // 4096单位/圈、2:1减速、零位在2048、反向安装
+        let conversion = ServoConversion {
+            resolution: 4096,
+            gear_ratio: 2.0,
+            center_offset: 2048,
+            direction: -1,
+        };
+        assert!(conversion.validate().is_ok());
+        
+        assert_eq!(conversion.ticks_to_angle(2048), Angle::ZERO);
+        // 舵机正转半圈，关节反向转四分之一圈
+        let angle = conversion.ticks_to_angle(2048 + 2048);
+        assert!((angle.radians() + std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+        assert_eq!(conversion.angle_to_ticks(angle), 4096);
+        
+        let (min, max) = conversion.angle_range_to_ticks(Angle::from_degrees(-45.0), Angle::from_degrees(45.0));
+        assert!(min < max);
+        
+        let velocity = conversion.speed_to_velocity(-1024);
+        assert!((velocity.radians_per_second() - std::f64::consts::FRAC_PI_4).abs() < 1e-9);
+        assert_eq!(conversion.velocity_to_speed(velocity), 1024);
+        
+        // 默认参数与原有的0.1度单位一致
+        assert_eq!(ServoConversion::default().angle_to_ticks(Angle::from_degrees(90.0)), 900);
+    }
+    
+    #[tokio::test]
+    async fn test_joint_states_in_si_units() {
+        let joints = JointSetConfig::default();
+        let interface = HardwareInterface::new(HardwareConfig::default()).await.unwrap();
+        interface.initialize_servos().await.unwrap();
+        
+        let head_pan = joints.get("head_pan").unwrap().servo_id;
+        HardwareInterface::process_servo_move(head_pan, 450, Some(100), &interface.status, &interface.config).await.unwrap();
+        
+        let states = interface.read_joint_states(&joints).await;
+        let state = &states["head_pan"];
+        assert!((state.position - 45f64.to_radians()).abs() < 1e-9);
+        assert!((state.velocity - 10f64.to_radians()).abs() < 1e-9);
+        assert!(state.is_moving);
+    }
+    
+    #[test]
+    fn test_parse_eeprom_serial() {
+        let mut data = b"RM-2024-000123".to_vec();
+        data.extend_from_slice(&[0xFF; 18]);
+        assert_eq!(parse_eeprom_serial(&data), Some("RM-2024-000123".to_string()));
+        
+        // 未写入的EEPROM全为0xFF
+        assert_eq!(parse_eeprom_serial(&[0xFF; 32]), None);
+        assert_eq!(parse_eeprom_serial(&[0x01, 0x02, 0x00]), None);
+    }
+    
+    #[tokio::test]
+    async fn test_identity_from_config() {
+        let config = HardwareConfig {
+            robot_serial: Some("RM-TEST-1".to_string()),
+            backends: BackendSelection::all(BackendMode::Mock),
+            ..HardwareConfig::default()
+        };
+        let servo_count = config.servo_config.servo_ids.len();
+        let mut interface = HardwareInterface::new(config.clone()).await.unwrap();
+        
+        interface.initialize_serial().await.unwrap();
+        interface.initialize_servos().await.unwrap();
+        interface.collect_identity().await;
+        
+        let identity = interface.get_identity().await;
+        assert_eq!(identity.robot_serial.as_deref(), Some("RM-TEST-1"));
+        assert_eq!(identity.serial_source.as_deref(), Some("config"));
+        assert_eq!(identity.servos.len(), servo_count);
+        assert!(identity.servos.values().all(|servo| servo.model_number != 0));
+        // 模拟后端的舵机信息必须带标记
+        assert!(identity.simulated);
+        assert!(identity.unreachable_servos.is_empty());
+        
+        // 真实串口没有打开时不编造舵机信息
+        let interface = HardwareInterface::new(HardwareConfig { backends: BackendSelection::all(BackendMode::Real), ..config }).await.unwrap();
+        interface.initialize_servos().await.unwrap();
+        interface.collect_identity().await;
+        
+        let identity = interface.get_identity().await;
+        assert!(identity.servos.is_empty() && !identity.simulated);
+        assert_eq!(identity.unreachable_servos.len(), servo_count);
+    }
+    
+    #[tokio::test]
+    async fn test_park() {
+        let state_file = std::env::temp_dir().join(format!("reachy_park_{}.json", std::process::id()));
+        let mut config = HardwareConfig::default();
+        let servo_id = config.servo_config.servo_ids[0];
+        config.park = ParkConfig {
+            positions: HashMap::from([(servo_id, -300)]),
+            settle_ms: 0,
+            torque_ramp_ms: 10,
+            torque_ramp_steps: 5,
+            state_file: state_file.clone(),
+            ..ParkConfig::default()
+        };
+        let interface = HardwareInterface::new(config).await.unwrap();
+        
+        // 未连接时不能停靠
+        assert!(interface.park(false).await.is_err());
+        
+        interface.initialize_servos().await.unwrap();
+        interface.status.write().await.is_connected = true;
+        
+        let state = interface.park(true).await.unwrap();
+        assert_eq!(state.positions[&servo_id], -300);
+        assert!(!state.powered_off);
+        
+        let servos = interface.get_all_servo_status().await.unwrap();
+        assert!(servos.iter().all(|s| !s.torque_enabled && s.torque_limit == 0));
+        
+        let saved = interface.last_parked_state().unwrap();
+        assert_eq!(saved.positions, state.positions);
+        let _ = std::fs::remove_file(state_file);
+    }
+    
+    #[tokio::test]
+    async fn test_backend_selection() {
+        let mut config = HardwareConfig {
+            robot_serial: Some("RM-TEST-2".to_string()),
+            backends: BackendSelection::all(BackendMode::Mock),
+            ..HardwareConfig::default()
+        };
+        let mut interface = HardwareInterface::new(config.clone()).await.unwrap();
+        interface.initialize_hardware().await.unwrap();
+        
+        let status = interface.get_status().await.unwrap();
+        assert!(status.serial_connected && status.i2c_connected);
+        assert_eq!(status.mock_backends, vec!["serial", "i2c", "gpio"]);
+        
+        // 强制使用真实串口但设备不存在时，串口保持未连接
+        config.serial_port = "/dev/reachy-no-such-port".to_string();
+        config.backends.serial = BackendMode::Real;
+        let mut interface = HardwareInterface::new(config).await.unwrap();
+        interface.initialize_hardware().await.unwrap();
+        
+        let status = interface.get_status().await.unwrap();
+        assert!(!status.serial_connected);
+        assert!(!status.mock_backends.contains(&"serial".to_string()));
+        interface.cleanup_hardware().await.unwrap();
+        assert!(interface.get_status().await.unwrap().mock_backends.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_stop_drains_queue_and_restarts() {
+        let mut config = HardwareConfig {
+            backends: BackendSelection::all(BackendMode::Mock),
+            ..HardwareConfig::default()
+        };
+        config.park.park_on_shutdown = false;
+        let servo_id = config.servo_config.servo_ids[0];
+        let mut interface = HardwareInterface::new(config).await.unwrap();
+        
+        for _ in 0..2 {
+            interface.start().await.unwrap();
+            interface.send_command(HardwareCommand::ServoMove { id: servo_id, position: 123, speed: None }).await.unwrap();
+            
+            // 停止前已入队的命令仍会执行
+            interface.stop().await.unwrap();
+            let status = interface.get_status().await.unwrap();
+            assert_eq!(status.servo_status[&servo_id].position, 123);
+            assert!(!interface.is_running().await);
+            
+            interface.send_command(HardwareCommand::ServoStop { id: servo_id }).await.unwrap();
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_low_level_access() {
+        let mut config = HardwareConfig {
+            backends: BackendSelection::all(BackendMode::Mock),
+            ..HardwareConfig::default()
+        };
+        config.park.park_on_shutdown = false;
+        let servo_count = config.servo_config.servo_ids.len();
+        let (&servo_id, &(_, max_position)) = config.servo_config.position_limits.iter().next().unwrap();
+        let mut interface = HardwareInterface::new(config).await.unwrap();
+        interface.start().await.unwrap();
+        
+        assert_eq!(interface.scan_bus(0..=252).await.unwrap().len(), servo_count);
+        assert!(interface.set_led(20, true).await.is_ok());
+        assert!(interface.set_led(42, true).await.is_err());
+        
+        // JSON命令与内部命令走同一条路径，位置限制同样生效
+        let json = format!(r#"{{"type": "servo_move", "id": {}, "position": 32000, "speed": null}}"#, servo_id);
+        let command: HardwareCommand = serde_json::from_str(&json).unwrap();
+        interface.send_command(command).await.unwrap();
+        interface.stop().await.unwrap();
+        
+        let servo = interface.get_servo_status(servo_id).await.unwrap().unwrap();
+        assert_eq!(servo.position, max_position);
+    }
+}
@@ -0,0 +1,130 @@
+//! 硬件后端
+//! 
+//! 串口、I2C和GPIO的真实设备实现。使用真实设备还是模拟由配置中的
+//! `BackendMode`在运行时决定，这里只负责打开和操作Linux设备节点。
+
+use anyhow::Result;
+use log::debug;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// sysfs GPIO接口根目录
+pub const GPIO_SYSFS_ROOT: &str = "/sys/class/gpio";
+
+/// I2C总线对应的设备节点
+pub fn i2c_device(bus: u8) -> PathBuf {
+    PathBuf::from(format!("/dev/i2c-{}", bus))
+}
+
+/// 已打开的真实设备，模拟后端不持有句柄
+#[derive(Debug, Default)]
+pub struct Devices {
+    pub serial: Option<File>,
+    pub i2c: Option<File>,
+}
+
+/// 波特率对应的termios常量
+fn baud_constant(baud_rate: u32) -> Option<libc::speed_t> {
+    let speed = match baud_rate {
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        460800 => libc::B460800,
+        500000 => libc::B500000,
+        921600 => libc::B921600,
+        1000000 => libc::B1000000,
+        2000000 => libc::B2000000,
+        3000000 => libc::B3000000,
+        4000000 => libc::B4000000,
+        _ => return None,
+    };
+    Some(speed)
+}
+
+/// 打开串口并配置为原始模式（8N1，无流控）
+pub fn open_serial(port: &str, baud_rate: u32) -> Result<File> {
+    let speed = baud_constant(baud_rate)
+        .ok_or_else(|| anyhow::anyhow!("不支持的波特率: {}", baud_rate))?;
+    
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+        .open(port)
+        .map_err(|e| anyhow::anyhow!("无法打开串口 {}: {}", port, e))?;
+    let fd = file.as_raw_fd();
+    
+    let mut tty: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut tty) } != 0 {
+        return Err(anyhow::anyhow!("读取串口属性失败 {}: {}", port, std::io::Error::last_os_error()));
+    }
+    
+    unsafe {
+        libc::cfmakeraw(&mut tty);
+        libc::cfsetispeed(&mut tty, speed);
+        libc::cfsetospeed(&mut tty, speed);
+    }
+    tty.c_cflag |= libc::CLOCAL | libc::CREAD;
+    tty.c_cflag &= !(libc::CSTOPB | libc::CRTSCTS);
+    
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &tty) } != 0 {
+        return Err(anyhow::anyhow!("设置串口属性失败 {}: {}", port, std::io::Error::last_os_error()));
+    }
+    
+    debug!("串口 {} 已配置为 {} 8N1", port, baud_rate);
+    Ok(file)
+}
+
+/// 打开I2C总线
+pub fn open_i2c(bus: u8) -> Result<File> {
+    let path = i2c_device(bus);
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .map_err(|e| anyhow::anyhow!("无法打开I2C总线 {}: {}", path.display(), e))
+}
+
+/// GPIO输出：真实设备通过sysfs写电平，模拟时只记录日志
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gpio {
+    real: bool,
+}
+
+impl Gpio {
+    pub fn new(real: bool) -> Self {
+        Self { real }
+    }
+    
+    pub fn is_real(&self) -> bool {
+        self.real
+    }
+    
+    /// 设置输出电平，首次使用的引脚会先导出并设为输出
+    pub fn write(&self, pin: u8, high: bool) -> Result<()> {
+        if !self.real {
+            debug!("模拟GPIO输出: pin {} -> {}", pin, if high { "高电平" } else { "低电平" });
+            return Ok(());
+        }
+        
+        let root = Path::new(GPIO_SYSFS_ROOT);
+        let pin_dir = root.join(format!("gpio{}", pin));
+        if !pin_dir.exists() {
+            std::fs::write(root.join("export"), pin.to_string())
+                .map_err(|e| anyhow::anyhow!("导出GPIO {} 失败: {}", pin, e))?;
+        }
+        
+        std::fs::write(pin_dir.join("direction"), "out")
+            .map_err(|e| anyhow::anyhow!("设置GPIO {} 方向失败: {}", pin, e))?;
+        std::fs::write(pin_dir.join("value"), if high { "1" } else { "0" })
+            .map_err(|e| anyhow::anyhow!("写入GPIO {} 失败: {}", pin, e))?;
+        
+        debug!("GPIO输出: pin {} -> {}", pin, high);
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+//! Dynamixel 2.0协议
+//! 
+//! 总线扫描和身份采集用到的部分：组包、CRC-16校验、字节填充，以及PING指令的发送和应答解析。
+//! PING的应答参数为型号（2字节，小端）和固件版本（1字节）。
+
+use super::ServoIdentity;
+use anyhow::Result;
+use log::warn;
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::time::{Duration, Instant};
+
+/// 包头，后面依次是ID、长度（2字节，小端，包含指令、参数和CRC）、指令、参数、CRC
+pub const HEADER: [u8; 4] = [0xFF, 0xFF, 0xFD, 0x00];
+pub const INSTRUCTION_PING: u8 = 0x01;
+pub const INSTRUCTION_STATUS: u8 = 0x55;
+
+/// 协议规定的CRC-16（多项式0x8005，初值0，不反转）
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &byte| {
+        let mut crc = crc ^ ((byte as u16) << 8);
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+/// 参数中出现 FF FF FD 时追加一个 FD，避免被当作包头
+fn stuff(params: &[u8]) -> Vec<u8> {
+    let mut stuffed = Vec::with_capacity(params.len());
+    for &byte in params {
+        stuffed.push(byte);
+        if stuffed.ends_with(&HEADER[..3]) {
+            stuffed.push(0xFD);
+        }
+    }
+    stuffed
+}
+
+/// 去掉字节填充
+fn unstuff(params: &[u8]) -> Vec<u8> {
+    let mut plain = Vec::with_capacity(params.len());
+    for &byte in params {
+        if byte == 0xFD && plain.ends_with(&HEADER[..3]) {
+            continue;
+        }
+        plain.push(byte);
+    }
+    plain
+}
+
+/// 组成指令包
+pub fn instruction_packet(id: u8, instruction: u8, params: &[u8]) -> Vec<u8> {
+    let params = stuff(params);
+    let length = (params.len() + 3) as u16;
+    
+    let mut packet = HEADER.to_vec();
+    packet.push(id);
+    packet.extend_from_slice(&length.to_le_bytes());
+    packet.push(instruction);
+    packet.extend_from_slice(&params);
+    let crc = crc16(&packet);
+    packet.extend_from_slice(&crc.to_le_bytes());
+    packet
+}
+
+/// 舵机返回的状态包
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusPacket {
+    pub id: u8,
+    /// 错误字节，0表示正常
+    pub error: u8,
+    pub params: Vec<u8>,
+}
+
+/// 从缓冲区中解析第一个完整的状态包，返回状态包和已消耗的字节数；数据不完整时返回None
+///
+/// 包头之前的杂散字节和CRC错误的包会被跳过
+pub fn parse_status(buffer: &[u8]) -> Option<(StatusPacket, usize)> {
+    let mut start = 0;
+    while let Some(offset) = buffer[start..].windows(HEADER.len()).position(|window| window == HEADER) {
+        let packet = &buffer[start + offset..];
+        if packet.len() < 7 {
+            return None;
+        }
+        
+        let length = u16::from_le_bytes([packet[5], packet[6]]) as usize;
+        let total = 7 + length;
+        if length < 4 {
+            start += offset + 1;
+            continue;
+        }
+        if packet.len() < total {
+            return None;
+        }
+        
+        let crc = u16::from_le_bytes([packet[total - 2], packet[total - 1]]);
+        if crc16(&packet[..total - 2]) != crc || packet[7] != INSTRUCTION_STATUS {
+            start += offset + 1;
+            continue;
+        }
+        
+        let status = StatusPacket {
+            id: packet[4],
+            error: packet[8],
+            params: unstuff(&packet[9..total - 2]),
+        };
+        return Some((status, start + offset + total));
+    }
+    None
+}
+
+/// 向一个ID发送PING并等待应答，超时未应答时返回None
+///
+/// 串口以非阻塞模式打开，读不到数据时短暂休眠后重试
+pub fn ping<P: Read + Write>(port: &mut P, id: u8, timeout: Duration) -> Result<Option<ServoIdentity>> {
+    // 丢弃上一次事务残留的字节
+    let mut scratch = [0u8; 64];
+    loop {
+        match port.read(&mut scratch) {
+            Ok(0) => break,
+            Ok(_) => continue,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) => return Err(anyhow::anyhow!("读取串口失败: {}", e)),
+        }
+    }
+    
+    port.write_all(&instruction_packet(id, INSTRUCTION_PING, &[]))
+        .map_err(|e| anyhow::anyhow!("写入串口失败: {}", e))?;
+    
+    let deadline = Instant::now() + timeout;
+    let mut buffer = Vec::new();
+    while Instant::now() < deadline {
+        match port.read(&mut scratch) {
+            Ok(n) if n > 0 => buffer.extend_from_slice(&scratch[..n]),
+            Ok(_) => std::thread::sleep(Duration::from_millis(1)),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(Duration::from_millis(1)),
+            Err(e) => return Err(anyhow::anyhow!("读取串口失败: {}", e)),
+        }
+        
+        while let Some((status, consumed)) = parse_status(&buffer) {
+            buffer.drain(..consumed);
+            if status.id == id && status.params.len() >= 3 {
+                return Ok(Some(ServoIdentity {
+                    id,
+                    model_number: u16::from_le_bytes([status.params[0], status.params[1]]),
+                    firmware_version: status.params[2],
+                }));
+            }
+        }
+    }
+    
+    Ok(None)
+}
+
+/// 逐个PING给定的舵机，返回应答的舵机身份和没有应答的ID；读写出错的舵机同样记为没有应答
+pub fn identify<P: Read + Write>(port: &mut P, ids: &[u8], timeout: Duration) -> (HashMap<u8, ServoIdentity>, Vec<u8>) {
+    let mut servos = HashMap::new();
+    let mut unreachable = Vec::new();
+    for &id in ids {
+        match ping(port, id, timeout) {
+            Ok(Some(identity)) => {
+                servos.insert(id, identity);
+            },
+            Ok(None) => unreachable.push(id),
+            Err(e) => {
+                warn!("PING舵机 {} 失败: {}", id, e);
+                unreachable.push(id);
+            },
+        }
+    }
+    (servos, unreachable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    
+    /// 模拟总线：只有responders中的ID应答PING
+    struct FakeBus {
+        responders: Vec<(u8, Vec<u8>)>,
+        rx: VecDeque<u8>,
+    }
+    
+    impl Read for FakeBus {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.rx.is_empty() {
+                return Err(ErrorKind::WouldBlock.into());
+            }
+            let n = buf.len().min(self.rx.len());
+            for (slot, byte) in buf.iter_mut().zip(self.rx.drain(..n)) {
+                *slot = byte;
+            }
+            Ok(n)
+        }
+    }
+    
+    impl Write for FakeBus {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if let Some((_, reply)) = self.responders.iter().find(|(id, _)| buf[4] == *id) {
+                // 应答前带一个杂散字节
+                self.rx.push_back(0x00);
+                self.rx.extend(reply);
+            }
+            Ok(buf.len())
+        }
+        
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_ping_packets_and_scan() {
+        // 协议手册中的示例：PING ID 1，应答型号1030、固件版本38
+        assert_eq!(instruction_packet(1, INSTRUCTION_PING, &[]), vec![0xFF, 0xFF, 0xFD, 0x00, 0x01, 0x03, 0x00, 0x01, 0x19, 0x4E]);
+        let reply = vec![0xFF, 0xFF, 0xFD, 0x00, 0x01, 0x07, 0x00, 0x55, 0x00, 0x06, 0x04, 0x26, 0x65, 0x5D];
+        let (status, consumed) = parse_status(&reply).unwrap();
+        assert_eq!(status, StatusPacket { id: 1, error: 0, params: vec![0x06, 0x04, 0x26] });
+        assert_eq!(consumed, reply.len());
+        assert!(parse_status(&reply[..10]).is_none());
+        
+        // CRC错误的包被跳过
+        let mut corrupted = reply.clone();
+        corrupted[11] = 0x27;
+        assert!(parse_status(&corrupted).is_none());
+        
+        assert_eq!(unstuff(&stuff(&[0xFF, 0xFF, 0xFD, 0x01])), vec![0xFF, 0xFF, 0xFD, 0x01]);
+        assert_eq!(stuff(&[0xFF, 0xFF, 0xFD]), vec![0xFF, 0xFF, 0xFD, 0xFD]);
+        
+        let mut bus = FakeBus { responders: vec![(1, reply)], rx: VecDeque::new() };
+        let timeout = Duration::from_millis(5);
+        let identity = ping(&mut bus, 1, timeout).unwrap().unwrap();
+        assert_eq!((identity.model_number, identity.firmware_version), (1030, 38));
+        assert!(ping(&mut bus, 2, timeout).unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_identify_records_unresponsive_servos() {
+        let reply = vec![0xFF, 0xFF, 0xFD, 0x00, 0x01, 0x07, 0x00, 0x55, 0x00, 0x06, 0x04, 0x26, 0x65, 0x5D];
+        let mut bus = FakeBus { responders: vec![(1, reply)], rx: VecDeque::new() };
+        
+        let (servos, unreachable) = identify(&mut bus, &[1, 2], Duration::from_millis(5));
+        assert_eq!(servos.len(), 1);
+        assert_eq!((servos[&1].model_number, servos[&1].firmware_version), (1030, 38));
+        assert_eq!(unreachable, vec![2]);
+    }
+}
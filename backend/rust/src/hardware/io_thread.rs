@@ -0,0 +1,376 @@
+//! 硬件I/O线程
+//! 
+//! 总线读写在独立的高优先级线程中按固定频率运行，与控制循环之间只通过两个三缓冲交换
+//! 最新的目标位置和总线状态，慢的串口事务不会阻塞控制计算，控制循环的抖动也不会拖慢总线。
+//! 具体的总线协议实现`ServoBus`接入；没有真实舵机时使用`SimulatedBus`。
+
+use crate::common::*;
+use crate::joints::JointSetConfig;
+use crate::shutdown::CancellationToken;
+use crate::triple_buffer::{triple_buffer, TripleReader, TripleWriter};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use log::{info, warn};
+
+/// I/O线程配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IoThreadConfig {
+    pub enabled: bool,
+    /// 总线读写频率（Hz）
+    pub frequency: f64,
+    /// SCHED_FIFO实时优先级（1-99），None时使用普通调度；没有权限时退回普通调度
+    pub realtime_priority: Option<i32>,
+}
+
+impl Default for IoThreadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            frequency: 200.0,
+            realtime_priority: Some(50),
+        }
+    }
+}
+
+impl ConfigValidation for IoThreadConfig {
+    fn validate(&self) -> Result<()> {
+        if !(self.frequency > 0.0 && self.frequency <= 10_000.0) {
+            return Err(anyhow::anyhow!("I/O线程频率必须在0-10000Hz之间"));
+        }
+        
+        if let Some(priority) = self.realtime_priority {
+            if !(1..=99).contains(&priority) {
+                return Err(anyhow::anyhow!("实时优先级必须在1-99之间"));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 控制循环发给I/O线程的关节目标位置（rad）
+#[derive(Debug, Clone, Default)]
+pub struct JointTargets {
+    pub timestamp: u64,
+    pub positions: HashMap<String, f64>,
+    /// 控制循环写入的时刻，用于测量总线往返延迟
+    pub sent_at: Option<Instant>,
+}
+
+/// I/O线程发布的总线状态
+#[derive(Debug, Clone, Default)]
+pub struct BusState {
+    /// 成功完成的总线周期数
+    pub cycle: u64,
+    pub timestamp: u64,
+    pub positions: HashMap<String, f64>,
+    pub velocities: HashMap<String, f64>,
+    pub efforts: HashMap<String, f64>,
+    /// 本周期写入的目标从控制循环写入到总线应答的耗时，没有新目标时为None
+    pub round_trip: Option<Duration>,
+}
+
+/// 舵机总线
+pub trait ServoBus: Send {
+    /// 写入新的目标位置（没有新目标时为None），并把读回的关节状态写入state；
+    /// state中是较早的读数，需要完整覆盖
+    fn transfer(&mut self, targets: Option<&HashMap<String, f64>>, state: &mut BusState) -> Result<()>;
+}
+
+/// 模拟总线：关节按最大速度向目标移动，每次事务耗时latency
+pub struct SimulatedBus {
+    joints: Vec<(String, f64)>,
+    positions: HashMap<String, f64>,
+    targets: HashMap<String, f64>,
+    latency: Duration,
+    last_transfer: Option<Instant>,
+}
+
+impl SimulatedBus {
+    pub fn new(joints: &JointSetConfig, latency: Duration) -> Self {
+        Self {
+            joints: joints.joints.iter()
+                .map(|joint| (joint.name.clone(), joint.max_velocity.radians_per_second()))
+                .collect(),
+            positions: HashMap::new(),
+            targets: HashMap::new(),
+            latency,
+            last_transfer: None,
+        }
+    }
+}
+
+impl ServoBus for SimulatedBus {
+    fn transfer(&mut self, targets: Option<&HashMap<String, f64>>, state: &mut BusState) -> Result<()> {
+        if let Some(targets) = targets {
+            self.targets.extend(targets.iter().map(|(name, position)| (name.clone(), *position)));
+        }
+        std::thread::sleep(self.latency);
+        
+        let now = Instant::now();
+        let dt = self.last_transfer.map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+        self.last_transfer = Some(now);
+        
+        for (name, max_velocity) in &self.joints {
+            let position = self.positions.entry(name.clone()).or_insert(0.0);
+            let target = self.targets.get(name).copied().unwrap_or(*position);
+            let step = clamp(target - *position, -max_velocity * dt, max_velocity * dt);
+            *position += step;
+            
+            state.positions.insert(name.clone(), *position);
+            state.velocities.insert(name.clone(), if dt > 0.0 { step / dt } else { 0.0 });
+            state.efforts.insert(name.clone(), 0.0);
+        }
+        
+        Ok(())
+    }
+}
+
+/// 线程周期耗时统计快照
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThreadTiming {
+    /// 目标频率（Hz）
+    pub frequency: f64,
+    pub cycles: u64,
+    /// 耗时超过周期的次数
+    pub overruns: u64,
+    pub last_cycle_us: u64,
+    pub mean_cycle_us: u64,
+    pub max_cycle_us: u64,
+}
+
+/// 周期耗时统计，记录方和查询方之间只用原子量
+#[derive(Debug, Default)]
+pub struct ThreadTimingStats {
+    period_ns: AtomicU64,
+    cycles: AtomicU64,
+    overruns: AtomicU64,
+    total_ns: AtomicU64,
+    last_ns: AtomicU64,
+    max_ns: AtomicU64,
+}
+
+impl ThreadTimingStats {
+    pub fn new(frequency: f64) -> Self {
+        let stats = Self::default();
+        stats.set_frequency(frequency);
+        stats
+    }
+    
+    /// 目标频率变化时（例如控制循环降频）更新判断超时的周期
+    pub fn set_frequency(&self, frequency: f64) {
+        self.period_ns.store((1e9 / frequency) as u64, Ordering::Relaxed);
+    }
+    
+    pub fn record(&self, elapsed: Duration) {
+        let ns = elapsed.as_nanos() as u64;
+        self.cycles.fetch_add(1, Ordering::Relaxed);
+        self.total_ns.fetch_add(ns, Ordering::Relaxed);
+        self.last_ns.store(ns, Ordering::Relaxed);
+        self.max_ns.fetch_max(ns, Ordering::Relaxed);
+        if ns > self.period_ns.load(Ordering::Relaxed) {
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    
+    pub fn snapshot(&self) -> ThreadTiming {
+        let cycles = self.cycles.load(Ordering::Relaxed);
+        let period_ns = self.period_ns.load(Ordering::Relaxed);
+        
+        ThreadTiming {
+            frequency: if period_ns > 0 { 1e9 / period_ns as f64 } else { 0.0 },
+            cycles,
+            overruns: self.overruns.load(Ordering::Relaxed),
+            last_cycle_us: self.last_ns.load(Ordering::Relaxed) / 1000,
+            mean_cycle_us: self.total_ns.load(Ordering::Relaxed).checked_div(cycles).unwrap_or(0) / 1000,
+            max_cycle_us: self.max_ns.load(Ordering::Relaxed) / 1000,
+        }
+    }
+}
+
+/// 控制循环一侧的端点：写入目标位置、读取总线状态
+pub struct IoEndpoints {
+    pub targets: TripleWriter<JointTargets>,
+    pub state: TripleReader<BusState>,
+    pub timing: Arc<ThreadTimingStats>,
+}
+
+/// I/O线程一侧的端点
+pub struct IoThread {
+    bus: Box<dyn ServoBus>,
+    targets: TripleReader<JointTargets>,
+    state: TripleWriter<BusState>,
+    timing: Arc<ThreadTimingStats>,
+    config: IoThreadConfig,
+}
+
+/// 创建I/O线程及控制循环一侧的端点，线程由调用方在阻塞线程中运行`IoThread::run`
+pub fn io_channel(bus: Box<dyn ServoBus>, config: IoThreadConfig) -> Result<(IoThread, IoEndpoints)> {
+    config.validate()?;
+    
+    let (targets_writer, targets_reader) = triple_buffer(JointTargets::default());
+    let (state_writer, state_reader) = triple_buffer(BusState::default());
+    let timing = Arc::new(ThreadTimingStats::new(config.frequency));
+    
+    let thread = IoThread {
+        bus,
+        targets: targets_reader,
+        state: state_writer,
+        timing: Arc::clone(&timing),
+        config,
+    };
+    let endpoints = IoEndpoints {
+        targets: targets_writer,
+        state: state_reader,
+        timing,
+    };
+    Ok((thread, endpoints))
+}
+
+/// 当前线程的调度策略，用于退出时恢复
+#[cfg(unix)]
+struct SchedulingGuard {
+    policy: libc::c_int,
+    param: libc::sched_param,
+}
+
+#[cfg(unix)]
+impl SchedulingGuard {
+    /// 把当前线程切换到SCHED_FIFO，失败（通常是没有CAP_SYS_NICE）时返回错误
+    fn elevate(priority: i32) -> std::io::Result<Self> {
+        // SAFETY: 只读写当前线程的调度参数，参数结构体由本函数持有
+        unsafe {
+            let thread = libc::pthread_self();
+            let mut policy = 0;
+            let mut param: libc::sched_param = std::mem::zeroed();
+            if libc::pthread_getschedparam(thread, &mut policy, &mut param) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            
+            let realtime = libc::sched_param { sched_priority: priority };
+            let ret = libc::pthread_setschedparam(thread, libc::SCHED_FIFO, &realtime);
+            if ret != 0 {
+                return Err(std::io::Error::from_raw_os_error(ret));
+            }
+            Ok(Self { policy, param })
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SchedulingGuard {
+    fn drop(&mut self) {
+        // SAFETY: 恢复当前线程原来的调度参数；I/O线程运行在阻塞线程池中，线程会被复用
+        unsafe {
+            libc::pthread_setschedparam(libc::pthread_self(), self.policy, &self.param);
+        }
+    }
+}
+
+impl IoThread {
+    /// 按固定频率运行总线事务，直到收到关闭信号；在阻塞线程中调用
+    pub fn run(mut self, shutdown: CancellationToken) {
+        #[cfg(unix)]
+        let _priority = self.config.realtime_priority.and_then(|priority| {
+            SchedulingGuard::elevate(priority)
+                .map_err(|e| warn!("I/O线程无法使用实时优先级 {}，使用普通调度: {}", priority, e))
+                .ok()
+        });
+        
+        let period = Duration::from_secs_f64(1.0 / self.config.frequency);
+        let mut next = Instant::now();
+        let mut cycle = 0;
+        let mut consecutive_errors = 0u32;
+        
+        info!("硬件I/O线程启动，{} Hz", self.config.frequency);
+        
+        while !shutdown.is_cancelled() {
+            let start = Instant::now();
+            
+            let update = self.targets.take_update();
+            let sent_at = update.and_then(|targets| targets.sent_at);
+            let targets = update.map(|targets| &targets.positions);
+            let state = self.state.back_mut();
+            
+            // 失败的事务不发布，读取方继续使用上一次成功的读数
+            match self.bus.transfer(targets, state) {
+                Ok(()) => {
+                    cycle += 1;
+                    state.cycle = cycle;
+                    state.timestamp = current_timestamp();
+                    state.round_trip = sent_at.map(|sent_at| sent_at.elapsed());
+                    self.state.publish();
+                    consecutive_errors = 0;
+                },
+                Err(e) => {
+                    // 持续失败时只在次数为2的幂时记录，避免刷屏
+                    consecutive_errors += 1;
+                    if consecutive_errors.is_power_of_two() {
+                        warn!("总线事务失败（连续 {} 次）: {}", consecutive_errors, e);
+                    }
+                },
+            }
+            
+            self.timing.record(start.elapsed());
+            
+            next += period;
+            let now = Instant::now();
+            if next > now {
+                std::thread::sleep(next - now);
+            } else {
+                // 超时后从当前时刻重新计时，不补跑错过的周期
+                next = now;
+            }
+        }
+        
+        info!("硬件I/O线程结束");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shutdown::TaskGroup;
+    
+    #[tokio::test]
+    async fn test_slow_bus_does_not_block_control_side() {
+        let joints = JointSetConfig::default();
+        let config = IoThreadConfig { enabled: true, frequency: 100.0, realtime_priority: None };
+        // 每次总线事务耗时15ms，超过10ms的周期
+        let bus = SimulatedBus::new(&joints, Duration::from_millis(15));
+        let (thread, mut endpoints) = io_channel(Box::new(bus), config).unwrap();
+        
+        let mut tasks = TaskGroup::new("测试");
+        let shutdown = tasks.token();
+        tasks.spawn_blocking("I/O线程", move || thread.run(shutdown));
+        
+        // 控制一侧的写入和读取都不等待总线事务
+        let start = Instant::now();
+        for i in 0..1000 {
+            endpoints.targets.write(JointTargets {
+                timestamp: current_timestamp(),
+                positions: HashMap::from([("head_pan".to_string(), 0.001 * i as f64)]),
+                sent_at: None,
+            });
+            let _ = endpoints.state.latest();
+        }
+        assert!(start.elapsed() < Duration::from_millis(15));
+        
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let state = endpoints.state.latest().clone();
+        assert!(state.cycle > 3);
+        assert!(state.positions["head_pan"] > 0.0);
+        
+        let timing = endpoints.timing.snapshot();
+        assert_eq!(timing.frequency, 100.0);
+        assert!(timing.cycles >= state.cycle);
+        assert!(timing.overruns > 0);
+        assert!(timing.mean_cycle_us >= 15_000);
+        
+        tasks.shutdown().await;
+    }
+}
@@ -0,0 +1,311 @@
+//! 空闲微动模块
+//! 
+//! 在没有运动命令时产生小幅、随机但限速的头部微动（"呼吸"效果），
+//! 让机器人看起来更有生气。收到真实命令时立即让出控制权。
+
+use crate::common::*;
+use crate::joints::JointSetConfig;
+use crate::realtime::JointLimits;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use log::debug;
+
+/// 空闲微动配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdleMotionConfig {
+    pub enabled: bool,
+    /// 最后一条命令之后多久进入空闲微动（毫秒）
+    pub activation_delay_ms: u64,
+    /// 各关节的微动幅度（rad），只有列出的关节参与微动
+    pub amplitudes: HashMap<String, f64>,
+    /// 呼吸周期（秒）
+    pub breathing_period_s: f64,
+    /// 各关节单独的呼吸周期（秒），未列出的关节使用breathing_period_s
+    #[serde(default)]
+    pub breathing_periods: HashMap<String, f64>,
+    /// 随机张望间隔范围（毫秒）
+    pub glance_interval_ms: (u64, u64),
+    /// 微动最大速度（rad/s）
+    pub max_velocity: f64,
+    /// 与关节限位保持的安全余量（rad）
+    pub limit_margin: f64,
+}
+
+impl Default for IdleMotionConfig {
+    fn default() -> Self {
+        Self::from_joint_set(&JointSetConfig::default())
+    }
+}
+
+impl IdleMotionConfig {
+    /// 根据关节集合中各关节的空闲微动参数生成配置
+    pub fn from_joint_set(joints: &JointSetConfig) -> Self {
+        let mut amplitudes = HashMap::new();
+        let mut breathing_periods = HashMap::new();
+        
+        for joint in joints.joints.iter().filter(|j| j.idle_amplitude > 0.0) {
+            amplitudes.insert(joint.name.clone(), joint.idle_amplitude);
+            if let Some(period) = joint.idle_period_s {
+                breathing_periods.insert(joint.name.clone(), period);
+            }
+        }
+        
+        Self {
+            enabled: true,
+            activation_delay_ms: 3000,
+            amplitudes,
+            breathing_period_s: 4.0,
+            breathing_periods,
+            glance_interval_ms: (2000, 6000),
+            max_velocity: 0.3,
+            limit_margin: 0.05,
+        }
+    }
+}
+
+impl ConfigValidation for IdleMotionConfig {
+    fn validate(&self) -> Result<()> {
+        if self.breathing_period_s <= 0.0 {
+            return Err(anyhow::anyhow!("呼吸周期必须为正数"));
+        }
+        
+        for (name, period) in &self.breathing_periods {
+            if *period <= 0.0 {
+                return Err(anyhow::anyhow!("关节 '{}' 的呼吸周期必须为正数", name));
+            }
+        }
+        
+        if self.glance_interval_ms.0 > self.glance_interval_ms.1 {
+            return Err(anyhow::anyhow!("张望间隔范围无效"));
+        }
+        
+        if self.max_velocity <= 0.0 {
+            return Err(anyhow::anyhow!("微动最大速度必须为正数"));
+        }
+        
+        if self.limit_margin < 0.0 {
+            return Err(anyhow::anyhow!("限位安全余量不能为负数"));
+        }
+        
+        for (name, amplitude) in &self.amplitudes {
+            if *amplitude < 0.0 {
+                return Err(anyhow::anyhow!("关节 '{}' 的微动幅度不能为负数", name));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 空闲微动生成器
+#[derive(Debug, Clone)]
+pub struct IdleMotionGenerator {
+    config: IdleMotionConfig,
+    last_activity: Instant,
+    active: bool,
+    started_at: Instant,
+    last_update: Instant,
+    centers: HashMap<String, f64>,
+    outputs: HashMap<String, f64>,
+    glance_offsets: HashMap<String, f64>,
+    next_glance: Instant,
+}
+
+impl IdleMotionGenerator {
+    pub fn new(config: IdleMotionConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            config,
+            last_activity: now,
+            active: false,
+            started_at: now,
+            last_update: now,
+            centers: HashMap::new(),
+            outputs: HashMap::new(),
+            glance_offsets: HashMap::new(),
+            next_glance: now,
+        }
+    }
+    
+    /// 记录真实命令活动，立即退出空闲微动
+    pub fn notify_activity(&mut self) {
+        self.last_activity = Instant::now();
+        if self.active {
+            debug!("收到运动命令，退出空闲微动");
+        }
+        self.active = false;
+        self.outputs.clear();
+    }
+    
+    /// 是否正在输出空闲微动
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+    
+    /// 计算本周期的微动目标位置
+    ///
+    /// 未到激活时间或未启用时返回空表。
+    pub fn update(
+        &mut self,
+        now: Instant,
+        joint_states: &HashMap<String, JointState>,
+        joint_limits: &HashMap<String, JointLimits>,
+    ) -> HashMap<String, f64> {
+        if !self.config.enabled {
+            return HashMap::new();
+        }
+        
+        if !self.active {
+            let delay = Duration::from_millis(self.config.activation_delay_ms);
+            if now.saturating_duration_since(self.last_activity) < delay {
+                return HashMap::new();
+            }
+            self.activate(now, joint_states);
+        }
+        
+        let dt = now.saturating_duration_since(self.last_update).as_secs_f64();
+        self.last_update = now;
+        
+        if now >= self.next_glance {
+            self.pick_glance(now);
+        }
+        
+        let elapsed = now.saturating_duration_since(self.started_at).as_secs_f64();
+        let max_step = self.config.max_velocity * dt;
+        
+        let mut targets = HashMap::new();
+        for (joint_name, amplitude) in &self.config.amplitudes {
+            let Some(&center) = self.centers.get(joint_name) else {
+                continue;
+            };
+            
+            let period = self.config.breathing_periods.get(joint_name)
+                .copied()
+                .unwrap_or(self.config.breathing_period_s);
+            let phase = 2.0 * std::f64::consts::PI * elapsed / period;
+            let glance = self.glance_offsets.get(joint_name).copied().unwrap_or(0.0);
+            let breathing = 0.5 * amplitude * phase.sin();
+            let desired = center + clamp(glance + breathing, -amplitude, *amplitude);
+            
+            // 限速：相对上一次输出的变化量不超过max_velocity * dt
+            let previous = self.outputs.get(joint_name).copied().unwrap_or(center);
+            let mut target = previous + clamp(desired - previous, -max_step, max_step);
+            
+            // 安全限位
+            if let Some(limits) = joint_limits.get(joint_name) {
+                let margin = self.config.limit_margin;
+                let (min, max) = (limits.min_position.radians(), limits.max_position.radians());
+                let (mut low, mut high) = (min + margin, max - margin);
+                if low > high {
+                    // 余量大于可用行程时停在行程中点
+                    low = (min + max) * 0.5;
+                    high = low;
+                }
+                target = clamp(target, low, high);
+            }
+            
+            self.outputs.insert(joint_name.clone(), target);
+            targets.insert(joint_name.clone(), target);
+        }
+        
+        targets
+    }
+    
+    /// 进入空闲微动，以当前姿态为中心
+    fn activate(&mut self, now: Instant, joint_states: &HashMap<String, JointState>) {
+        self.active = true;
+        self.started_at = now;
+        self.last_update = now;
+        self.next_glance = now;
+        self.outputs.clear();
+        self.glance_offsets.clear();
+        self.centers = self.config.amplitudes.keys()
+            .filter_map(|name| joint_states.get(name).map(|s| (name.clone(), s.unwrapped_position)))
+            .collect();
+        
+        debug!("进入空闲微动，参与关节: {:?}", self.centers.keys().collect::<Vec<_>>());
+    }
+    
+    /// 随机选择下一个张望方向
+    fn pick_glance(&mut self, now: Instant) {
+        for (joint_name, amplitude) in &self.config.amplitudes {
+            let offset = (rand::random::<f64>() * 2.0 - 1.0) * amplitude * 0.5;
+            self.glance_offsets.insert(joint_name.clone(), offset);
+        }
+        
+        let (min_ms, max_ms) = self.config.glance_interval_ms;
+        let interval = min_ms + (rand::random::<f64>() * (max_ms - min_ms) as f64) as u64;
+        self.next_glance = now + Duration::from_millis(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn joint_states() -> HashMap<String, JointState> {
+        let mut states = HashMap::new();
+        for name in ["head_pan", "head_tilt"] {
+            states.insert(name.to_string(), JointState::new(name.to_string()));
+        }
+        states
+    }
+    
+    #[test]
+    fn test_idle_motion_waits_for_activation_delay() {
+        let mut generator = IdleMotionGenerator::new(IdleMotionConfig::default());
+        let targets = generator.update(Instant::now(), &joint_states(), &HashMap::new());
+        assert!(targets.is_empty());
+        assert!(!generator.is_active());
+    }
+    
+    #[test]
+    fn test_idle_motion_is_bounded_and_rate_limited() {
+        let config = IdleMotionConfig {
+            activation_delay_ms: 0,
+            ..IdleMotionConfig::default()
+        };
+        let mut limits = HashMap::new();
+        limits.insert("head_tilt".to_string(), JointLimits {
+            min_position: Angle::from_radians(-0.01),
+            max_position: Angle::from_radians(0.01),
+            ..JointLimits::default()
+        });
+        
+        let mut generator = IdleMotionGenerator::new(config.clone());
+        let start = Instant::now();
+        let mut previous: HashMap<String, f64> = HashMap::new();
+        
+        for step in 1..200 {
+            let now = start + Duration::from_millis(step * 10);
+            let targets = generator.update(now, &joint_states(), &limits);
+            assert!(generator.is_active());
+            
+            for (name, target) in &targets {
+                let amplitude = config.amplitudes[name];
+                assert!(target.abs() <= amplitude + 1e-9);
+                if let Some(prev) = previous.get(name) {
+                    assert!((target - prev).abs() <= config.max_velocity * 0.01 + 1e-9);
+                }
+            }
+            // 余量大于可用行程时停在行程中点
+            assert!(targets["head_tilt"].abs() <= 1e-9);
+            previous = targets;
+        }
+    }
+    
+    #[test]
+    fn test_idle_motion_yields_to_commands() {
+        let config = IdleMotionConfig {
+            activation_delay_ms: 0,
+            ..IdleMotionConfig::default()
+        };
+        let mut generator = IdleMotionGenerator::new(config);
+        assert!(!generator.update(Instant::now(), &joint_states(), &HashMap::new()).is_empty());
+        
+        generator.notify_activity();
+        assert!(!generator.is_active());
+    }
+}
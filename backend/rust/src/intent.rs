@@ -0,0 +1,457 @@
+//! 语音意图路由模块
+//! 
+//! 把语音识别得到的文字识别为类型化的意图（转头、播放动画、提问等），再分发给
+//! 行为或插件注册的处理器；新增语音命令只需要加一条语法模板并注册处理器，不需要改动语音识别。
+//! 语法模板匹配不到时可以交给意图分类器（例如调用大语言模型），仍然没有结果时按提问处理。
+//!
+//! 模板语法：`{名称}`为任意文字的槽位，`{名称:number}`为数字，`{名称:direction}`为方向词；
+//! `[...]`为可选部分，`(a|b)`为多选一；空白匹配任意数量的空白（中文可以不写空格），
+//! 不区分大小写，末尾的标点被忽略。
+
+use crate::common::*;
+use crate::events::{EventBus, RobotEvent};
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use log::{debug, warn};
+
+/// 方向词，每组中较长的词写在前面
+const DIRECTION_WORDS: [(HeadDirection, &[&str]); 5] = [
+    (HeadDirection::Left, &["left", "左边", "左"]),
+    (HeadDirection::Right, &["right", "右边", "右"]),
+    (HeadDirection::Up, &["up", "上面", "上"]),
+    (HeadDirection::Down, &["down", "下面", "下"]),
+    (HeadDirection::Center, &["center", "forward", "front", "中间", "前面", "前"]),
+];
+
+/// 转头方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HeadDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+    Center,
+}
+
+impl HeadDirection {
+    fn parse(word: &str) -> Option<Self> {
+        let word = word.to_lowercase();
+        DIRECTION_WORDS.iter()
+            .find(|(_, words)| words.contains(&word.as_str()))
+            .map(|(direction, _)| *direction)
+    }
+}
+
+/// 类型化的意图，序列化时以`type`字段标记类型（与`kind()`一致）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Intent {
+    MoveHead {
+        direction: HeadDirection,
+        /// 转动角度（度），未说明时由处理器决定
+        degrees: Option<f64>,
+    },
+    PlayAnimation {
+        name: String,
+    },
+    Query {
+        question: String,
+    },
+    /// 插件自定义的意图
+    Custom {
+        name: String,
+        slots: BTreeMap<String, String>,
+    },
+}
+
+impl Intent {
+    /// 意图类型名，自定义意图返回其名称
+    pub fn kind(&self) -> &str {
+        match self {
+            Intent::MoveHead { .. } => "MoveHead",
+            Intent::PlayAnimation { .. } => "PlayAnimation",
+            Intent::Query { .. } => "Query",
+            Intent::Custom { name, .. } => name,
+        }
+    }
+}
+
+/// 一条语法模板
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntentPattern {
+    /// 意图类型：MoveHead、PlayAnimation、Query或自定义名称
+    pub intent: String,
+    pub template: String,
+    /// 固定的槽位值，例如“跳舞”对应 name = dance
+    #[serde(default)]
+    pub slots: BTreeMap<String, String>,
+}
+
+impl IntentPattern {
+    pub fn new(intent: &str, template: &str) -> Self {
+        Self { intent: intent.to_string(), template: template.to_string(), slots: BTreeMap::new() }
+    }
+    
+    pub fn with_slot(mut self, name: &str, value: &str) -> Self {
+        self.slots.insert(name.to_string(), value.to_string());
+        self
+    }
+}
+
+/// 意图识别配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntentConfig {
+    /// 按顺序匹配，先匹配到的优先
+    pub patterns: Vec<IntentPattern>,
+    /// 都没有匹配时按提问处理
+    pub fallback_to_query: bool,
+    /// 分类器结果的最低置信度
+    pub min_classifier_confidence: f64,
+}
+
+impl Default for IntentConfig {
+    fn default() -> Self {
+        Self {
+            patterns: vec![
+                IntentPattern::new("MoveHead", "(look|turn) [your head] [to the] {direction:direction} [{degrees:number} degrees]"),
+                IntentPattern::new("MoveHead", "(向|往){direction:direction}(看|转)[{degrees:number}度]"),
+                IntentPattern::new("PlayAnimation", "(play|do) [the] {name} animation"),
+                IntentPattern::new("PlayAnimation", "(播放|做)[一个]{name}动画"),
+                IntentPattern::new("PlayAnimation", "(dance|跳舞|跳个舞)").with_slot("name", "dance"),
+            ],
+            fallback_to_query: true,
+            min_classifier_confidence: 0.6,
+        }
+    }
+}
+
+impl ConfigValidation for IntentConfig {
+    fn validate(&self) -> Result<()> {
+        for pattern in &self.patterns {
+            if pattern.intent.is_empty() {
+                return Err(anyhow::anyhow!("语法模板 '{}' 没有意图类型", pattern.template));
+            }
+            CompiledPattern::compile(pattern)?;
+        }
+        
+        if !(0.0..=1.0).contains(&self.min_classifier_confidence) {
+            return Err(anyhow::anyhow!("分类器最低置信度必须在0-1之间"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 意图的识别方式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum IntentSource {
+    /// 匹配的语法模板
+    Grammar(String),
+    Classifier,
+    /// 没有匹配，按提问处理
+    Fallback,
+}
+
+/// 识别结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecognizedIntent {
+    pub intent: Intent,
+    pub confidence: f64,
+    pub source: IntentSource,
+    pub transcript: String,
+}
+
+/// 意图分类器（语法匹配不到时使用，例如请大语言模型输出意图）
+#[allow(async_fn_in_trait)]
+pub trait IntentClassifier {
+    /// 返回意图和置信度，无法分类时返回None
+    async fn classify(&mut self, transcript: &str) -> Result<Option<(Intent, f64)>>;
+}
+
+#[derive(Debug)]
+struct CompiledPattern {
+    pattern: IntentPattern,
+    regex: Regex,
+}
+
+impl CompiledPattern {
+    fn compile(pattern: &IntentPattern) -> Result<Self> {
+        let mut regex = String::from(r"(?i)^\s*");
+        let mut chars = pattern.template.chars().peekable();
+        
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    let slot: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    let (name, kind) = slot.split_once(':').unwrap_or((slot.as_str(), "text"));
+                    let body = match kind {
+                        "text" => r".+?".to_string(),
+                        "number" => r"-?\d+(?:\.\d+)?".to_string(),
+                        "direction" => DIRECTION_WORDS.iter()
+                            .flat_map(|(_, words)| words.iter().map(|word| regex::escape(word)))
+                            .collect::<Vec<_>>()
+                            .join("|"),
+                        _ => return Err(anyhow::anyhow!("语法模板 '{}' 中的槽位类型未知: {}", pattern.template, kind)),
+                    };
+                    regex.push_str(&format!("(?P<{}>{})", name, body));
+                }
+                '[' | '(' => regex.push_str("(?:"),
+                ']' => regex.push_str(")?"),
+                ')' => regex.push(')'),
+                '|' => regex.push('|'),
+                c if c.is_whitespace() => {
+                    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                    regex.push_str(r"\s*");
+                }
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        regex.push_str(r"\s*[.!?。！？，,]*\s*$");
+        
+        let regex = Regex::new(&regex)
+            .map_err(|e| anyhow::anyhow!("语法模板 '{}' 无效: {}", pattern.template, e))?;
+        Ok(Self { pattern: pattern.clone(), regex })
+    }
+    
+    fn matches(&self, transcript: &str) -> Option<Intent> {
+        let captures = self.regex.captures(transcript)?;
+        let mut slots = self.pattern.slots.clone();
+        for name in self.regex.capture_names().flatten() {
+            if let Some(value) = captures.name(name) {
+                slots.insert(name.to_string(), value.as_str().trim().to_string());
+            }
+        }
+        
+        match self.pattern.intent.as_str() {
+            "MoveHead" => Some(Intent::MoveHead {
+                direction: HeadDirection::parse(slots.get("direction")?)?,
+                degrees: slots.get("degrees").and_then(|d| d.parse().ok()),
+            }),
+            "PlayAnimation" => Some(Intent::PlayAnimation { name: slots.remove("name")? }),
+            "Query" => Some(Intent::Query {
+                question: slots.remove("question").unwrap_or_else(|| transcript.trim().to_string()),
+            }),
+            name => Some(Intent::Custom { name: name.to_string(), slots }),
+        }
+    }
+}
+
+/// 意图识别器
+#[derive(Debug)]
+pub struct IntentRecognizer {
+    config: IntentConfig,
+    patterns: Vec<CompiledPattern>,
+}
+
+impl IntentRecognizer {
+    pub fn new(config: IntentConfig) -> Result<Self> {
+        config.validate()?;
+        let patterns = config.patterns.iter()
+            .map(CompiledPattern::compile)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { config, patterns })
+    }
+    
+    /// 只用语法模板识别
+    pub fn match_grammar(&self, transcript: &str) -> Option<RecognizedIntent> {
+        self.patterns.iter().find_map(|compiled| {
+            compiled.matches(transcript).map(|intent| RecognizedIntent {
+                intent,
+                confidence: 1.0,
+                source: IntentSource::Grammar(compiled.pattern.template.clone()),
+                transcript: transcript.to_string(),
+            })
+        })
+    }
+    
+    /// 语法模板、分类器、提问依次尝试；分类器出错时记录警告并继续
+    pub async fn recognize<C: IntentClassifier>(
+        &self,
+        transcript: &str,
+        classifier: Option<&mut C>,
+    ) -> Option<RecognizedIntent> {
+        let transcript = transcript.trim();
+        if transcript.is_empty() {
+            return None;
+        }
+        
+        if let Some(recognized) = self.match_grammar(transcript) {
+            return Some(recognized);
+        }
+        
+        if let Some(classifier) = classifier {
+            match classifier.classify(transcript).await {
+                Ok(Some((intent, confidence))) if confidence >= self.config.min_classifier_confidence => {
+                    return Some(RecognizedIntent {
+                        intent,
+                        confidence,
+                        source: IntentSource::Classifier,
+                        transcript: transcript.to_string(),
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => warn!("意图分类失败: {}", e),
+            }
+        }
+        
+        self.config.fallback_to_query.then(|| RecognizedIntent {
+            intent: Intent::Query { question: transcript.to_string() },
+            confidence: 0.0,
+            source: IntentSource::Fallback,
+            transcript: transcript.to_string(),
+        })
+    }
+}
+
+/// 意图处理器，由行为或插件注册
+pub trait IntentHandler: Send {
+    /// 返回是否已处理；未处理时交给下一个处理器
+    fn handle(&mut self, intent: &Intent) -> Result<bool>;
+}
+
+impl<F> IntentHandler for F
+where
+    F: FnMut(&Intent) -> Result<bool> + Send,
+{
+    fn handle(&mut self, intent: &Intent) -> Result<bool> {
+        self(intent)
+    }
+}
+
+struct Registration {
+    kind: String,
+    owner: String,
+    priority: i32,
+    handler: Box<dyn IntentHandler>,
+}
+
+/// 意图分发器
+pub struct IntentRouter {
+    handlers: Vec<Registration>,
+    bus: Option<EventBus>,
+}
+
+impl IntentRouter {
+    pub fn new(bus: Option<EventBus>) -> Self {
+        Self { handlers: Vec::new(), bus }
+    }
+    
+    /// 注册处理器，同一类型有多个处理器时按优先级从高到低尝试，优先级相同时先注册的优先
+    pub fn register<H: IntentHandler + 'static>(&mut self, kind: &str, owner: &str, priority: i32, handler: H) {
+        let index = self.handlers.iter()
+            .position(|r| r.priority < priority)
+            .unwrap_or(self.handlers.len());
+        self.handlers.insert(index, Registration {
+            kind: kind.to_string(),
+            owner: owner.to_string(),
+            priority,
+            handler: Box::new(handler),
+        });
+    }
+    
+    /// 注销某个行为或插件的全部处理器，返回注销的数量
+    pub fn unregister(&mut self, owner: &str) -> usize {
+        let before = self.handlers.len();
+        self.handlers.retain(|r| r.owner != owner);
+        before - self.handlers.len()
+    }
+    
+    /// 已注册处理器的意图类型
+    pub fn kinds(&self) -> Vec<&str> {
+        let mut kinds: Vec<&str> = self.handlers.iter().map(|r| r.kind.as_str()).collect();
+        kinds.sort_unstable();
+        kinds.dedup();
+        kinds
+    }
+    
+    /// 分发意图，返回处理它的注册者；处理器出错时记录警告并交给下一个
+    pub fn dispatch(&mut self, recognized: &RecognizedIntent) -> Option<String> {
+        let kind = recognized.intent.kind();
+        let mut handled_by = None;
+        
+        for registration in self.handlers.iter_mut().filter(|r| r.kind == kind) {
+            match registration.handler.handle(&recognized.intent) {
+                Ok(true) => {
+                    handled_by = Some(registration.owner.clone());
+                    break;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("{} 处理意图 {} 失败: {}", registration.owner, kind, e),
+            }
+        }
+        
+        debug!("意图 {} 由 {:?} 处理", kind, handled_by);
+        if let Some(bus) = &self.bus {
+            let name = if handled_by.is_some() { "intent.handled" } else { "intent.unhandled" };
+            bus.publish("intent", RobotEvent::Custom {
+                name: name.to_string(),
+                data: serde_json::json!({
+                    "intent": recognized.intent,
+                    "confidence": recognized.confidence,
+                    "source": recognized.source,
+                    "transcript": recognized.transcript,
+                    "handled_by": handled_by,
+                }),
+            });
+        }
+        handled_by
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    struct NoClassifier;
+    
+    impl IntentClassifier for NoClassifier {
+        async fn classify(&mut self, _transcript: &str) -> Result<Option<(Intent, f64)>> {
+            Ok(None)
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_grammar_recognizes_bilingual_commands() {
+        let recognizer = IntentRecognizer::new(IntentConfig::default()).unwrap();
+        let recognize = |text: &str| recognizer.match_grammar(text).map(|r| r.intent);
+        
+        assert_eq!(recognize("Look left 30 degrees."), Some(Intent::MoveHead { direction: HeadDirection::Left, degrees: Some(30.0) }));
+        assert_eq!(recognize("turn your head to the right"), Some(Intent::MoveHead { direction: HeadDirection::Right, degrees: None }));
+        assert_eq!(recognize("向左边转45度。"), Some(Intent::MoveHead { direction: HeadDirection::Left, degrees: Some(45.0) }));
+        assert_eq!(recognize("播放开心动画"), Some(Intent::PlayAnimation { name: "开心".to_string() }));
+        assert_eq!(recognize("跳个舞！"), Some(Intent::PlayAnimation { name: "dance".to_string() }));
+        assert_eq!(recognize("look at that"), None);
+        
+        let fallback = recognizer.recognize::<NoClassifier>("今天天气怎么样？", Some(&mut NoClassifier)).await.unwrap();
+        assert_eq!(fallback.source, IntentSource::Fallback);
+        assert_eq!(fallback.intent, Intent::Query { question: "今天天气怎么样？".to_string() });
+    }
+    
+    #[test]
+    fn test_router_dispatches_by_priority_and_owner() {
+        let bus = EventBus::new(16);
+        let mut events = bus.subscribe();
+        let mut router = IntentRouter::new(Some(bus));
+        
+        router.register("PlayAnimation", "fallback", 0, |_: &Intent| Ok(true));
+        router.register("PlayAnimation", "dance_pack", 10, |intent: &Intent| {
+            Ok(matches!(intent, Intent::PlayAnimation { name } if name == "dance"))
+        });
+        let recognizer = IntentRecognizer::new(IntentConfig::default()).unwrap();
+        
+        let dance = recognizer.match_grammar("dance").unwrap();
+        assert_eq!(router.dispatch(&dance).as_deref(), Some("dance_pack"));
+        let wave = recognizer.match_grammar("play the wave animation").unwrap();
+        assert_eq!(router.dispatch(&wave).as_deref(), Some("fallback"));
+        
+        assert_eq!(router.unregister("fallback"), 1);
+        assert_eq!(router.dispatch(&wave), None);
+        
+        let names: Vec<String> = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|envelope| envelope.event.kind().to_string())
+            .collect();
+        assert_eq!(names, vec!["intent.handled", "intent.handled", "intent.unhandled"]);
+    }
+}
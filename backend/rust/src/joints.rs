@@ -0,0 +1,303 @@
+//! 关节定义模块
+//! 
+//! 集中定义机器人的关节集合（头部、手臂、天线）。其他模块的默认关节配置
+//! 都从这里派生，避免在多个模块中重复硬编码关节名称列表。
+
+use crate::common::{Angle, AngularVelocity, ConfigValidation};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 关节分组
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum JointGroup {
+    Head,
+    LeftArm,
+    RightArm,
+    Antenna,
+    Base,
+}
+
+/// 关节在头部运动链中的作用，注视和末端速度控制按作用查找关节，而不是写死关节名
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum JointRole {
+    /// 头部水平转动
+    HeadPan,
+    /// 头部俯仰
+    HeadTilt,
+    /// 底座转盘
+    BaseYaw,
+}
+
+/// 单个关节定义（角度量按弧度序列化）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JointDefinition {
+    pub name: String,
+    pub group: JointGroup,
+    pub servo_id: u8,
+    pub min_position: Angle,
+    pub max_position: Angle,
+    pub max_velocity: AngularVelocity,
+    /// 最大角加速度（rad/s²）
+    pub max_acceleration: f64,
+    pub max_torque: f64,
+    /// 连续旋转关节（无机械限位，位置按±π回绕）
+    #[serde(default)]
+    pub continuous: bool,
+    /// 空闲微动幅度，0表示不参与空闲微动
+    pub idle_amplitude: f64,
+    /// 空闲微动周期（秒），None表示使用全局呼吸周期
+    pub idle_period_s: Option<f64>,
+    /// 在头部运动链中的作用，其他关节为None
+    #[serde(default)]
+    pub role: Option<JointRole>,
+}
+
+impl JointDefinition {
+    fn new(name: &str, group: JointGroup, servo_id: u8) -> Self {
+        Self {
+            name: name.to_string(),
+            group,
+            servo_id,
+            min_position: Angle::from_radians(-std::f64::consts::PI),
+            max_position: Angle::from_radians(std::f64::consts::PI),
+            max_velocity: AngularVelocity::from_radians_per_second(2.0),
+            max_acceleration: 5.0,
+            max_torque: 10.0,
+            continuous: false,
+            idle_amplitude: 0.0,
+            idle_period_s: None,
+            role: None,
+        }
+    }
+    
+    fn with_role(mut self, role: JointRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+    
+    fn with_range(mut self, min_position: f64, max_position: f64) -> Self {
+        self.min_position = Angle::from_radians(min_position);
+        self.max_position = Angle::from_radians(max_position);
+        self
+    }
+    
+    fn with_idle(mut self, amplitude: f64, period_s: Option<f64>) -> Self {
+        self.idle_amplitude = amplitude;
+        self.idle_period_s = period_s;
+        self
+    }
+}
+
+impl ConfigValidation for JointDefinition {
+    fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            return Err(anyhow::anyhow!("关节名称不能为空"));
+        }
+        
+        if self.min_position >= self.max_position {
+            return Err(anyhow::anyhow!("关节 '{}' 的位置范围无效", self.name));
+        }
+        
+        if self.max_velocity <= AngularVelocity::ZERO || self.max_acceleration <= 0.0 || self.max_torque <= 0.0 {
+            return Err(anyhow::anyhow!("关节 '{}' 的速度、加速度和扭矩限制必须为正数", self.name));
+        }
+        
+        if self.idle_amplitude < 0.0 {
+            return Err(anyhow::anyhow!("关节 '{}' 的微动幅度不能为负数", self.name));
+        }
+        
+        if matches!(self.idle_period_s, Some(period) if period <= 0.0) {
+            return Err(anyhow::anyhow!("关节 '{}' 的微动周期必须为正数", self.name));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 关节集合配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JointSetConfig {
+    pub joints: Vec<JointDefinition>,
+}
+
+impl Default for JointSetConfig {
+    fn default() -> Self {
+        use JointGroup::*;
+        
+        let joints = vec![
+            JointDefinition::new("head_pan", Head, 1)
+                .with_role(JointRole::HeadPan)
+                .with_idle(0.08, None),
+            JointDefinition::new("head_tilt", Head, 2)
+                .with_role(JointRole::HeadTilt)
+                .with_idle(0.04, None),
+            JointDefinition::new("left_shoulder_pitch", LeftArm, 3),
+            JointDefinition::new("left_shoulder_roll", LeftArm, 4),
+            JointDefinition::new("left_elbow_pitch", LeftArm, 5),
+            JointDefinition::new("right_shoulder_pitch", RightArm, 6),
+            JointDefinition::new("right_shoulder_roll", RightArm, 7),
+            JointDefinition::new("right_elbow_pitch", RightArm, 8),
+            // 天线行程较小，空闲时以更快的节奏轻摆
+            JointDefinition::new("left_antenna", Antenna, 9)
+                .with_range(-1.2, 1.2)
+                .with_idle(0.15, Some(2.5)),
+            JointDefinition::new("right_antenna", Antenna, 10)
+                .with_range(-1.2, 1.2)
+                .with_idle(0.15, Some(2.5)),
+            // 底座转盘，默认为带限位的关节；连续旋转的底座可在配置中设置continuous
+            JointDefinition::new("base_yaw", Base, 11)
+                .with_role(JointRole::BaseYaw)
+                .with_range(-2.79, 2.79),
+        ];
+        
+        Self { joints }
+    }
+}
+
+impl ConfigValidation for JointSetConfig {
+    fn validate(&self) -> Result<()> {
+        if self.joints.is_empty() {
+            return Err(anyhow::anyhow!("关节集合不能为空"));
+        }
+        
+        let mut names = HashSet::new();
+        let mut servo_ids = HashSet::new();
+        let mut roles = HashSet::new();
+        for joint in &self.joints {
+            joint.validate()?;
+            
+            if !names.insert(joint.name.as_str()) {
+                return Err(anyhow::anyhow!("关节名称重复: '{}'", joint.name));
+            }
+            
+            if !servo_ids.insert(joint.servo_id) {
+                return Err(anyhow::anyhow!("舵机ID重复: {}", joint.servo_id));
+            }
+            
+            if let Some(role) = joint.role {
+                if !roles.insert(role) {
+                    return Err(anyhow::anyhow!("关节作用 {:?} 重复", role));
+                }
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+impl JointSetConfig {
+    /// 所有关节名称（按定义顺序）
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.joints.iter().map(|j| j.name.as_str())
+    }
+    
+    /// 按名称查找关节
+    pub fn get(&self, name: &str) -> Option<&JointDefinition> {
+        self.joints.iter().find(|j| j.name == name)
+    }
+    
+    /// 是否包含指定关节
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+    
+    /// 指定分组的关节
+    pub fn group(&self, group: JointGroup) -> impl Iterator<Item = &JointDefinition> {
+        self.joints.iter().filter(move |j| j.group == group)
+    }
+    
+    /// 底座旋转关节（如果配置了）
+    pub fn base_yaw(&self) -> Option<&JointDefinition> {
+        self.group(JointGroup::Base).next()
+    }
+    
+    /// 按作用查找关节
+    pub fn by_role(&self, role: JointRole) -> Option<&JointDefinition> {
+        self.joints.iter().find(|j| j.role == Some(role))
+    }
+    
+    /// 头部运动链的关节
+    pub fn head_joints(&self) -> Result<HeadJoints> {
+        HeadJoints::resolve(self.joints.iter().map(|j| (j.name.as_str(), j.role)))
+    }
+}
+
+/// 头部运动链（底座转盘、头部水平转动、头部俯仰）的关节名，按关节作用解析
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadJoints {
+    pub pan: String,
+    pub tilt: String,
+    /// 没有底座转盘时为None
+    pub base_yaw: Option<String>,
+}
+
+impl HeadJoints {
+    /// 从(关节名, 作用)列表中找出头部运动链的关节，缺少头部关节时报错
+    pub fn resolve<'a>(joints: impl IntoIterator<Item = (&'a str, Option<JointRole>)>) -> Result<Self> {
+        let (mut pan, mut tilt, mut base_yaw) = (None, None, None);
+        for (name, role) in joints {
+            let slot = match role {
+                Some(JointRole::HeadPan) => &mut pan,
+                Some(JointRole::HeadTilt) => &mut tilt,
+                Some(JointRole::BaseYaw) => &mut base_yaw,
+                None => continue,
+            };
+            *slot = Some(name.to_string());
+        }
+        
+        Ok(Self {
+            pan: pan.ok_or_else(|| anyhow::anyhow!("关节集合中没有作用为head_pan的关节"))?,
+            tilt: tilt.ok_or_else(|| anyhow::anyhow!("关节集合中没有作用为head_tilt的关节"))?,
+            base_yaw,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_default_joint_set() {
+        let joints = JointSetConfig::default();
+        assert!(joints.validate().is_ok());
+        assert!(joints.contains("head_pan"));
+        assert_eq!(joints.group(JointGroup::Antenna).count(), 2);
+    }
+    
+    #[test]
+    fn test_joint_set_rejects_duplicates() {
+        let mut joints = JointSetConfig::default();
+        let mut duplicate = joints.joints[0].clone();
+        duplicate.servo_id = 42;
+        joints.joints.push(duplicate);
+        assert!(joints.validate().is_err());
+        
+        let mut joints = JointSetConfig::default();
+        joints.joints[1].servo_id = joints.joints[0].servo_id;
+        assert!(joints.validate().is_err());
+        
+        let mut joints = JointSetConfig::default();
+        joints.joints[1].role = Some(JointRole::HeadPan);
+        assert!(joints.validate().is_err());
+    }
+    
+    #[test]
+    fn test_head_joints_by_role() {
+        let mut joints = JointSetConfig::default();
+        let head = joints.head_joints().unwrap();
+        assert_eq!((head.pan.as_str(), head.tilt.as_str()), ("head_pan", "head_tilt"));
+        assert_eq!(head.base_yaw.as_deref(), Some("base_yaw"));
+        
+        // 关节改名后按作用仍然能找到；去掉底座后只剩头部
+        joints.joints[0].name = "neck_yaw".to_string();
+        joints.joints.retain(|j| j.role != Some(JointRole::BaseYaw));
+        let head = joints.head_joints().unwrap();
+        assert_eq!((head.pan.as_str(), head.base_yaw), ("neck_yaw", None));
+        
+        joints.joints[1].role = None;
+        assert!(joints.head_joints().is_err());
+    }
+}
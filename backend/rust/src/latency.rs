@@ -0,0 +1,206 @@
+//! 延迟自测
+//! 
+//! 按层分解一条命令从客户端到舵机应答的耗时：网络往返（客户端时间同步测得）、
+//! API→仲裁（校验、限流、仲裁后入队）、仲裁→控制循环（入队到被控制循环取出）、
+//! 控制→总线→应答（控制循环写入目标到读回包含该目标的总线状态），
+//! 输出延迟预算表，帮助判断卡顿来自网络、调度还是总线。
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 每个记录器保留的最近样本数
+const RECORDER_CAPACITY: usize = 256;
+
+/// 延迟所在的层
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyStage {
+    Network,
+    Arbitration,
+    Scheduling,
+    Bus,
+}
+
+impl LatencyStage {
+    pub const ALL: [LatencyStage; 4] = [
+        LatencyStage::Network,
+        LatencyStage::Arbitration,
+        LatencyStage::Scheduling,
+        LatencyStage::Bus,
+    ];
+    
+    pub fn label(self) -> &'static str {
+        match self {
+            LatencyStage::Network => "网络往返",
+            LatencyStage::Arbitration => "API→仲裁",
+            LatencyStage::Scheduling => "仲裁→控制循环",
+            LatencyStage::Bus => "控制→总线→应答",
+        }
+    }
+    
+    /// 该层占主导时的排查建议
+    fn hint(self) -> &'static str {
+        match self {
+            LatencyStage::Network => "检查Wi-Fi信号和客户端到机器人的网络",
+            LatencyStage::Arbitration => "命令校验或锁竞争耗时，检查是否有大量客户端同时发送命令",
+            LatencyStage::Scheduling => "控制循环调度延迟，检查CPU负载或提高控制频率",
+            LatencyStage::Bus => "总线事务耗时，检查波特率、舵机数量和I/O线程频率",
+        }
+    }
+}
+
+/// 一层延迟的统计（毫秒）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    /// 由样本计算统计，没有样本时为None
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        
+        let mut sorted: Vec<f64> = samples.iter().map(|sample| sample.as_secs_f64() * 1000.0).collect();
+        sorted.sort_by(f64::total_cmp);
+        let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+        
+        Some(Self {
+            samples: sorted.len(),
+            mean_ms: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+            max_ms: sorted[sorted.len() - 1],
+        })
+    }
+    
+    /// 只有一个测量值时（如时间同步得到的最小往返）
+    pub fn single(ms: f64) -> Self {
+        Self { samples: 1, mean_ms: ms, p50_ms: ms, p95_ms: ms, max_ms: ms }
+    }
+}
+
+/// 循环中持续记录的延迟样本，只保留最近的样本
+#[derive(Debug, Default)]
+pub struct LatencyRecorder {
+    samples: Mutex<VecDeque<Duration>>,
+}
+
+impl LatencyRecorder {
+    pub fn record(&self, latency: Duration) {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        if samples.len() == RECORDER_CAPACITY {
+            samples.pop_front();
+        }
+        samples.push_back(latency);
+    }
+    
+    /// 最近记录的样本
+    pub fn snapshot(&self) -> Vec<Duration> {
+        self.samples.lock().unwrap_or_else(|e| e.into_inner()).iter().copied().collect()
+    }
+}
+
+/// 一层的测量结果，未测量（如没有接入总线）时stats为None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageLatency {
+    pub stage: LatencyStage,
+    pub stats: Option<LatencyStats>,
+}
+
+/// 延迟预算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyBudget {
+    pub stages: Vec<StageLatency>,
+    /// 控制周期（毫秒），调度延迟在一个周期以内属于正常
+    pub control_period_ms: f64,
+}
+
+impl LatencyBudget {
+    pub fn new(control_period_ms: f64) -> Self {
+        Self {
+            stages: LatencyStage::ALL.iter().map(|&stage| StageLatency { stage, stats: None }).collect(),
+            control_period_ms,
+        }
+    }
+    
+    pub fn set(&mut self, stage: LatencyStage, stats: Option<LatencyStats>) {
+        if let Some(entry) = self.stages.iter_mut().find(|entry| entry.stage == stage) {
+            entry.stats = stats;
+        }
+    }
+    
+    pub fn get(&self, stage: LatencyStage) -> Option<&LatencyStats> {
+        self.stages.iter().find(|entry| entry.stage == stage).and_then(|entry| entry.stats.as_ref())
+    }
+    
+    /// 已测量各层中位数之和
+    pub fn total_p50_ms(&self) -> f64 {
+        self.stages.iter().filter_map(|entry| entry.stats.as_ref()).map(|stats| stats.p50_ms).sum()
+    }
+    
+    /// 中位数最大的层
+    pub fn dominant(&self) -> Option<LatencyStage> {
+        self.stages.iter()
+            .filter_map(|entry| entry.stats.as_ref().map(|stats| (entry.stage, stats.p50_ms)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(stage, _)| stage)
+    }
+}
+
+impl fmt::Display for LatencyBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total_p50_ms();
+        writeln!(f, "{:<16}{:>6}{:>10}{:>10}{:>10}{:>8}", "层", "样本", "中位数ms", "P95ms", "最大ms", "占比")?;
+        for entry in &self.stages {
+            match &entry.stats {
+                Some(stats) => writeln!(
+                    f, "{:<16}{:>6}{:>10.3}{:>10.3}{:>10.3}{:>7.0}%",
+                    entry.stage.label(), stats.samples, stats.p50_ms, stats.p95_ms, stats.max_ms,
+                    if total > 0.0 { stats.p50_ms / total * 100.0 } else { 0.0 },
+                )?,
+                None => writeln!(f, "{:<16}{:>6}", entry.stage.label(), "未测量")?,
+            }
+        }
+        writeln!(f, "合计（中位数）{:.3} ms，控制周期 {:.3} ms", total, self.control_period_ms)?;
+        if let Some(stage) = self.dominant() {
+            write!(f, "主要来源: {}，{}", stage.label(), stage.hint())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_stats_and_dominant_stage() {
+        let samples: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        let stats = LatencyStats::from_samples(&samples).unwrap();
+        assert_eq!(stats.samples, 100);
+        assert!((stats.mean_ms - 50.5).abs() < 1e-9);
+        assert!((stats.p50_ms - 51.0).abs() < 1e-9);
+        assert!((stats.p95_ms - 95.0).abs() < 1e-9);
+        assert_eq!(stats.max_ms, 100.0);
+        assert!(LatencyStats::from_samples(&[]).is_none());
+        
+        let mut budget = LatencyBudget::new(10.0);
+        budget.set(LatencyStage::Network, Some(LatencyStats::single(12.0)));
+        budget.set(LatencyStage::Scheduling, Some(LatencyStats::single(3.0)));
+        assert_eq!(budget.dominant(), Some(LatencyStage::Network));
+        assert_eq!(budget.total_p50_ms(), 15.0);
+        
+        let report = budget.to_string();
+        assert!(report.contains("未测量"));
+        assert!(report.contains("主要来源: 网络往返"));
+    }
+}
@@ -14,6 +14,21 @@
 //! 本库采用异步架构，使用Tokio运行时提供高性能的并发处理能力。
 //! 通过PyO3提供Python绑定，使得Python代码可以调用Rust的高性能功能。
 //! 
+//! # 特性
+//! 
+//! - `python-bindings`（默认）：PyO3绑定
+//! - `ai`（默认）：AI推理模块，关闭时`ai`模块只保留配置结构
+//! - `audio`（默认）：音频采集、声源定位和跳舞模式
+//! - `vision`：视觉模块，使用纯Rust后端（V4L2、image、ONNX人脸检测）
+//! - `opencv`：视觉模块改用OpenCV后端，需要系统安装OpenCV
+//! - `network`：WebSocket和HTTP客户端依赖
+//! 
+//! 控制相关模块始终编译。无头的小型设备可以只构建控制部分：
+//! 
+//! ```text
+//! cargo build --profile embedded --no-default-features
+//! ```
+//! 
 //! # 使用示例
 //! 
 //! ```rust
@@ -44,11 +59,85 @@ mod python_bindings;
 #[cfg(feature = "python-bindings")]
 pub use python_bindings::*;
 
+// 控制相关模块，所有构建都包含
+pub mod common;
+pub mod shutdown;
+pub mod logging;
+pub mod config;
+pub mod config_crypto;
+pub mod config_diff;
+pub mod joints;
+pub mod transforms;
+pub mod robot_model;
+pub mod triple_buffer;
+pub mod hardware;
+pub mod realtime;
+pub mod loop_rate;
+pub mod adaptive_resolution;
+pub mod latency;
+pub mod telemetry;
+pub mod oscillation;
+pub mod sim_bridge;
+pub mod arbiter;
+pub mod audit;
+pub mod command_filter;
+pub mod soft_start;
+pub mod backlash;
+pub mod dynamics;
+pub mod estimator;
+pub mod idle;
+pub mod antenna;
+pub mod gaze;
+pub mod twist;
+pub mod hand_eye;
+pub mod profiling;
+pub mod tensor_pool;
+pub mod postprocess;
+pub mod model_signature;
+pub mod segmentation;
+pub mod sound_events;
+pub mod events;
+pub mod event_filter;
+pub mod rules;
+pub mod intent;
+pub mod boot;
+pub mod behavior_pack;
+pub mod ble;
+pub mod resource_guard;
+pub mod time_sync;
+pub mod trajectory_file;
+pub mod trajectory_plot;
+pub mod motion_import;
+pub mod choreography;
+pub mod protocol;
+pub mod teleop;
+pub mod shell;
+
+// 可选模块，由cargo特性控制
+#[cfg(feature = "vision")]
+pub mod vision;
+#[cfg(feature = "ai")]
+pub mod ai;
+/// 未启用ai特性时的替身，只保留配置结构以兼容完整的配置文件
+#[cfg(not(feature = "ai"))]
+#[path = "ai_stub.rs"]
+pub mod ai;
+#[cfg(feature = "audio")]
+pub mod audio_ducking;
+#[cfg(feature = "audio")]
+pub mod audio_input;
+#[cfg(feature = "audio")]
+pub mod audio_processing;
+#[cfg(feature = "audio")]
+pub mod sound_localization;
+#[cfg(feature = "audio")]
+pub mod dance;
+
 // 标准库和第三方依赖导入
 use std::sync::Arc;           // 原子引用计数，用于多线程共享数据
 use tokio::sync::RwLock;      // 异步读写锁，保护共享状态
 use anyhow::Result;           // 错误处理类型
-use log::{info, error};       // 日志记录宏
+use log::info;                // 日志记录宏
 
 /// 全局配置结构
 /// 
@@ -86,11 +175,16 @@ pub struct Config {
 /// 3. `is_running()` - 查询运行状态
 /// 4. `get_status()` - 获取详细状态
 /// 5. `stop()` - 停止系统服务
+/// 
+/// 通过`attach_hardware()`挂接硬件接口后，系统启动时一并启动硬件接口，
+/// 停止时先让舵机回到停靠姿态并卸掉扭矩，再停止硬件接口。
 pub struct ReachyMiniSystem {
     /// 系统配置，使用Arc实现多线程共享
     config: Arc<Config>,
     /// 系统运行状态，使用RwLock保护并发访问
     is_running: Arc<RwLock<bool>>,
+    /// 挂接的硬件接口，未挂接时为None
+    hardware: RwLock<Option<hardware::HardwareInterface>>,
 }
 
 impl ReachyMiniSystem {
@@ -110,11 +204,15 @@ impl ReachyMiniSystem {
     /// # 示例
     /// 
     /// ```rust
+    /// # use reachy_mini_rust::{ReachyMiniSystem, Config};
+    /// # async fn example() -> anyhow::Result<()> {
     /// let config = Config {
     ///     name: "Reachy Mini".to_string(),
     ///     version: "1.0.0".to_string(),
     /// };
     /// let system = ReachyMiniSystem::new(config).await?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub async fn new(config: Config) -> Result<Self> {
         info!("初始化Reachy Mini系统: {} v{}", config.name, config.version);
@@ -127,9 +225,15 @@ impl ReachyMiniSystem {
         Ok(Self {
             config,
             is_running,
+            hardware: RwLock::new(None),
         })
     }
     
+    /// 挂接硬件接口，替换之前挂接的接口
+    pub async fn attach_hardware(&self, hardware: hardware::HardwareInterface) {
+        *self.hardware.write().await = Some(hardware);
+    }
+    
     /// 启动系统
     /// 
     /// 启动所有系统服务和组件。这个方法会：
@@ -158,6 +262,11 @@ impl ReachyMiniSystem {
             return Ok(());
         }
         
+        // 启动挂接的硬件接口
+        if let Some(hardware) = self.hardware.write().await.as_mut() {
+            hardware.start().await?;
+        }
+        
         // 设置运行状态为true
         *running = true;
         
@@ -166,12 +275,20 @@ impl ReachyMiniSystem {
     }
     
     /// 停止系统
+    /// 
+    /// 挂接了硬件接口时先停靠再停止硬件接口；停靠失败时系统仍会停止，并返回停靠错误。
     pub async fn stop(&self) -> Result<()> {
         info!("停止Reachy Mini系统...");
         
         let mut running = self.is_running.write().await;
         *running = false;
         
+        if let Some(hardware) = self.hardware.write().await.as_mut() {
+            if let Some(parked) = hardware.park_and_stop().await? {
+                info!("硬件已停靠，共 {} 个舵机", parked.positions.len());
+            }
+        }
+        
         info!("Reachy Mini系统已停止");
         Ok(())
     }
@@ -187,6 +304,7 @@ impl ReachyMiniSystem {
             is_running: self.is_running().await,
             name: self.config.name.clone(),
             version: self.config.version.clone(),
+            log_filter: logging::log_filter(),
             timestamp: chrono::Utc::now(),
         })
     }
@@ -198,12 +316,29 @@ pub struct SystemStatus {
     pub is_running: bool,
     pub name: String,
     pub version: String,
+    /// 当前生效的日志过滤规则
+    pub log_filter: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
-/// 初始化日志系统
+/// 最近一次连接硬件时采集的身份信息（序列号、串口适配器、舵机型号和固件版本）
+static HARDWARE_IDENTITY: std::sync::RwLock<Option<serde_json::Value>> = std::sync::RwLock::new(None);
+
+/// 记录硬件身份信息，由硬件接口在连接时调用
+pub fn set_hardware_identity(identity: serde_json::Value) {
+    if let Ok(mut current) = HARDWARE_IDENTITY.write() {
+        *current = Some(identity);
+    }
+}
+
+/// 获取硬件身份信息，尚未连接硬件时返回None
+pub fn hardware_identity() -> Option<serde_json::Value> {
+    HARDWARE_IDENTITY.read().ok().and_then(|identity| identity.clone())
+}
+
+/// 初始化日志系统，过滤规则取自RUST_LOG，运行中可通过`logging`模块调整
 pub fn init_logging() -> Result<()> {
-    env_logger::init();
+    logging::init()?;
     info!("日志系统初始化完成");
     Ok(())
 }
@@ -257,4 +392,48 @@ mod tests {
         system.stop().await.unwrap();
         assert!(!system.is_running().await);
     }
+    
+    #[tokio::test]
+    async fn test_stop_parks_attached_hardware_after_error() {
+        use common::{BackendMode, BackendSelection};
+        use hardware::{HardwareConfig, ParkConfig, ParkedState};
+        use std::collections::HashMap;
+        
+        let state_file = std::env::temp_dir().join(format!("reachy_system_park_{}.json", std::process::id()));
+        let mut hardware_config = HardwareConfig {
+            backends: BackendSelection::all(BackendMode::Mock),
+            ..HardwareConfig::default()
+        };
+        let servo_id = hardware_config.servo_config.servo_ids[0];
+        // 停止时的停靠不依赖park_on_shutdown
+        hardware_config.park = ParkConfig {
+            positions: HashMap::from([(servo_id, -200)]),
+            settle_ms: 0,
+            torque_ramp_ms: 10,
+            torque_ramp_steps: 2,
+            state_file: state_file.clone(),
+            park_on_shutdown: false,
+            ..ParkConfig::default()
+        };
+        
+        let config = Config {
+            name: "test".to_string(),
+            version: "0.1.0".to_string(),
+        };
+        let system = ReachyMiniSystem::new(config).await.unwrap();
+        system.attach_hardware(hardware::HardwareInterface::new(hardware_config).await.unwrap()).await;
+        
+        // 与Python端with块相同：块内抛出异常，退出时仍然停止系统
+        let body = async {
+            system.start().await?;
+            Err::<(), _>(anyhow::anyhow!("模拟with块中的异常"))
+        };
+        assert!(body.await.is_err());
+        system.stop().await.unwrap();
+        
+        assert!(!system.is_running().await);
+        let parked = ParkedState::load(&state_file).unwrap();
+        assert_eq!(parked.positions[&servo_id], -200);
+        let _ = std::fs::remove_file(state_file);
+    }
 }
\ No newline at end of file
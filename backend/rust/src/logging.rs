@@ -0,0 +1,244 @@
+//! 运行时日志级别
+//! 
+//! 过滤规则沿用RUST_LOG的写法（如`info,reachy_mini_rust::hardware=debug`），
+//! 启动时从RUST_LOG读取，运行中可以单独修改全局级别或某个模块的级别，无需重启。
+//! env_logger只负责格式化输出，是否输出由这里的规则决定：
+//! 模块级别按最长前缀匹配，没有匹配的模块使用全局级别。
+
+use anyhow::Result;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+/// 未设置RUST_LOG时的全局级别，与env_logger的默认行为一致
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Error;
+
+/// 日志过滤规则
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    modules: BTreeMap<String, LevelFilter>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self::new(DEFAULT_LEVEL)
+    }
+}
+
+impl LogFilter {
+    pub const fn new(default: LevelFilter) -> Self {
+        Self {
+            default,
+            modules: BTreeMap::new(),
+        }
+    }
+    
+    /// 全局级别
+    pub fn default_level(&self) -> LevelFilter {
+        self.default
+    }
+    
+    pub fn set_default_level(&mut self, level: LevelFilter) {
+        self.default = level;
+    }
+    
+    /// 设置模块级别，对其子模块同样生效
+    pub fn set_module_level(&mut self, module: &str, level: LevelFilter) {
+        self.modules.insert(module.to_string(), level);
+    }
+    
+    /// 移除模块级别，恢复使用上级模块或全局级别；模块原本没有单独设置时返回false
+    pub fn clear_module_level(&mut self, module: &str) -> bool {
+        self.modules.remove(module).is_some()
+    }
+    
+    /// 某个日志目标实际生效的级别
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules.iter()
+            .filter(|(module, _)| {
+                target == module.as_str()
+                    || target.strip_prefix(module.as_str()).is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+    
+    /// 所有规则中最详细的级别，用作log::max_level，尽早跳过不会输出的日志
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules.values().copied().fold(self.default, Ord::max)
+    }
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter> {
+    LevelFilter::from_str(level.trim())
+        .map_err(|_| anyhow::anyhow!("无效的日志级别: {}（可选 off、error、warn、info、debug、trace）", level))
+}
+
+impl FromStr for LogFilter {
+    type Err = anyhow::Error;
+    
+    fn from_str(spec: &str) -> Result<Self> {
+        let mut filter = Self::default();
+        
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    let module = module.trim();
+                    if module.is_empty() {
+                        return Err(anyhow::anyhow!("日志过滤规则缺少模块名: {}", directive));
+                    }
+                    filter.set_module_level(module, parse_level(level)?);
+                },
+                // 单独一个级别是全局级别，单独一个模块名表示该模块输出全部日志
+                None => match parse_level(directive) {
+                    Ok(level) => filter.set_default_level(level),
+                    Err(_) => filter.set_module_level(directive, LevelFilter::Trace),
+                },
+            }
+        }
+        
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.as_str().to_lowercase())?;
+        for (module, level) in &self.modules {
+            write!(f, ",{}={}", module, level.as_str().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// 当前生效的过滤规则
+static FILTER: RwLock<LogFilter> = RwLock::new(LogFilter::new(DEFAULT_LEVEL));
+
+fn update_filter(update: impl FnOnce(&mut LogFilter)) {
+    let mut filter = FILTER.write().unwrap_or_else(|e| e.into_inner());
+    update(&mut filter);
+    log::set_max_level(filter.max_level());
+}
+
+/// 按运行时规则过滤后交给env_logger输出
+struct RuntimeLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for RuntimeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let filter = FILTER.read().unwrap_or_else(|e| e.into_inner());
+        metadata.level() <= filter.level_for(metadata.target())
+    }
+    
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+    
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// 安装日志器，过滤规则取自RUST_LOG；进程内只能调用一次
+pub fn init() -> Result<()> {
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(spec) => spec.parse()?,
+        Err(_) => LogFilter::default(),
+    };
+    
+    // 内层只读取输出样式，不读取RUST_LOG，否则其中的模块规则会挡住运行时调高的级别
+    let inner = env_logger::Builder::from_env(env_logger::Env::new().write_style("RUST_LOG_STYLE"))
+        .filter_level(LevelFilter::Trace)
+        .build();
+    log::set_boxed_logger(Box::new(RuntimeLogger { inner }))
+        .map_err(|_| anyhow::anyhow!("日志系统已初始化"))?;
+    
+    update_filter(|current| *current = filter);
+    Ok(())
+}
+
+/// 当前生效的过滤规则，写法与RUST_LOG相同
+pub fn log_filter() -> String {
+    FILTER.read().unwrap_or_else(|e| e.into_inner()).to_string()
+}
+
+/// 整体替换过滤规则
+pub fn set_log_filter(spec: &str) -> Result<()> {
+    let filter: LogFilter = spec.parse()?;
+    update_filter(|current| *current = filter);
+    Ok(())
+}
+
+/// 修改全局级别（module为None）或某个模块的级别
+pub fn set_log_level(module: Option<&str>, level: &str) -> Result<()> {
+    let level = parse_level(level)?;
+    update_filter(|filter| match module {
+        Some(module) => filter.set_module_level(module, level),
+        None => filter.set_default_level(level),
+    });
+    Ok(())
+}
+
+/// 移除模块级别，恢复使用上级模块或全局级别
+pub fn clear_log_level(module: &str) -> bool {
+    let mut cleared = false;
+    update_filter(|filter| cleared = filter.clear_module_level(module));
+    cleared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_filter_parse_and_match() {
+        let mut filter: LogFilter = "warn,reachy_mini_rust::hardware=debug,reachy_mini_rust::hardware::bus=off"
+            .parse()
+            .unwrap();
+        
+        assert_eq!(filter.level_for("reachy_mini_rust::realtime"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("reachy_mini_rust::hardware"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("reachy_mini_rust::hardware::servo"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("reachy_mini_rust::hardware::bus"), LevelFilter::Off);
+        // 前缀必须在模块边界上
+        assert_eq!(filter.level_for("reachy_mini_rust::hardwarex"), LevelFilter::Warn);
+        assert_eq!(filter.max_level(), LevelFilter::Debug);
+        
+        filter.set_module_level("reachy_mini_rust::arbiter", LevelFilter::Trace);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+        assert!(filter.clear_module_level("reachy_mini_rust::arbiter"));
+        assert!(!filter.clear_module_level("reachy_mini_rust::arbiter"));
+        
+        // 输出的规则可以原样解析回来
+        let spec = filter.to_string();
+        assert_eq!(spec, "warn,reachy_mini_rust::hardware=debug,reachy_mini_rust::hardware::bus=off");
+        assert_eq!(spec.parse::<LogFilter>().unwrap(), filter);
+        
+        assert!("info,hardware=loud".parse::<LogFilter>().is_err());
+        assert!("=debug".parse::<LogFilter>().is_err());
+        assert_eq!("".parse::<LogFilter>().unwrap(), LogFilter::default());
+    }
+    
+    #[test]
+    fn test_runtime_adjustment() {
+        set_log_filter("info").unwrap();
+        set_log_level(Some("reachy_mini_rust::vision"), "trace").unwrap();
+        assert_eq!(log_filter(), "info,reachy_mini_rust::vision=trace");
+        assert_eq!(log::max_level(), LevelFilter::Trace);
+        
+        set_log_level(None, "debug").unwrap();
+        assert!(clear_log_level("reachy_mini_rust::vision"));
+        assert_eq!(log_filter(), "debug");
+        assert_eq!(log::max_level(), LevelFilter::Debug);
+        
+        assert!(set_log_level(None, "verbose").is_err());
+        assert_eq!(log_filter(), "debug");
+    }
+}
@@ -0,0 +1,237 @@
+//! 控制频率自适应
+//! 
+//! 降频运行的树莓派上100Hz控制循环可能持续超时。这里按窗口统计截止时间的错过比例：
+//! 超过阈值时把控制频率减半（最低到`min_frequency`），之后若连续多个窗口的周期耗时
+//! 都远小于更高一档的周期，再恢复到更高一档。
+//!
+//! 换档时按频率比例缩放PID增益，使控制带宽与采样率保持比例：kp×r、ki×r²，kd不变
+//! （r为当前频率与额定频率之比）。PID内部按实际dt积分和微分，积分状态在换档后仍然有效。
+
+use crate::common::*;
+use crate::realtime::PIDGains;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 控制频率自适应配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoopRateConfig {
+    pub enabled: bool,
+    /// 最低控制频率（Hz）
+    pub min_frequency: f64,
+    /// 统计窗口（控制周期数）
+    pub window_cycles: usize,
+    /// 窗口内错过截止时间的周期比例超过该值时降频
+    pub miss_ratio_threshold: f64,
+    /// 窗口内最长周期耗时低于更高一档周期的该比例时视为有余量
+    pub recover_utilization: f64,
+    /// 连续多少个有余量的窗口后升频
+    pub recover_windows: usize,
+}
+
+impl Default for LoopRateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_frequency: 50.0,
+            window_cycles: 100,
+            miss_ratio_threshold: 0.2,
+            recover_utilization: 0.5,
+            recover_windows: 5,
+        }
+    }
+}
+
+impl ConfigValidation for LoopRateConfig {
+    fn validate(&self) -> Result<()> {
+        if self.min_frequency <= 0.0 {
+            return Err(anyhow::anyhow!("最低控制频率必须为正数"));
+        }
+        
+        if self.window_cycles == 0 || self.recover_windows == 0 {
+            return Err(anyhow::anyhow!("统计窗口和恢复窗口数不能为0"));
+        }
+        
+        if !(self.miss_ratio_threshold > 0.0 && self.miss_ratio_threshold <= 1.0) {
+            return Err(anyhow::anyhow!("超时比例阈值必须在0-1之间"));
+        }
+        
+        if !(self.recover_utilization > 0.0 && self.recover_utilization < 1.0) {
+            return Err(anyhow::anyhow!("恢复耗时比例必须在0-1之间"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 一次换档
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateChange {
+    pub from_hz: f64,
+    pub to_hz: f64,
+    /// 触发换档的窗口中错过截止时间的比例
+    pub miss_ratio: f64,
+    /// 窗口内最长周期耗时占原周期的比例
+    pub utilization: f64,
+}
+
+impl RateChange {
+    pub fn is_degraded(&self) -> bool {
+        self.to_hz < self.from_hz
+    }
+}
+
+/// 按窗口统计周期耗时并决定换档
+#[derive(Debug, Clone)]
+pub struct LoopRateAdapter {
+    config: LoopRateConfig,
+    /// 可用的频率档位，从额定频率开始逐档减半
+    levels: Vec<f64>,
+    level: usize,
+    cycles: usize,
+    misses: usize,
+    max_busy: Duration,
+    good_windows: usize,
+}
+
+impl LoopRateAdapter {
+    pub fn new(nominal_frequency: f64, config: LoopRateConfig) -> Result<Self> {
+        config.validate()?;
+        if nominal_frequency <= 0.0 {
+            return Err(anyhow::anyhow!("控制频率必须为正数"));
+        }
+        
+        let mut levels = vec![nominal_frequency];
+        while config.enabled && levels[levels.len() - 1] / 2.0 >= config.min_frequency - 1e-9 {
+            levels.push(levels[levels.len() - 1] / 2.0);
+        }
+        
+        Ok(Self {
+            config,
+            levels,
+            level: 0,
+            cycles: 0,
+            misses: 0,
+            max_busy: Duration::ZERO,
+            good_windows: 0,
+        })
+    }
+    
+    pub fn frequency(&self) -> f64 {
+        self.levels[self.level]
+    }
+    
+    pub fn period(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.frequency())
+    }
+    
+    /// 当前频率与额定频率之比
+    pub fn ratio(&self) -> f64 {
+        self.frequency() / self.levels[0]
+    }
+    
+    /// 记录一个控制周期从计划时刻到完成的耗时，需要换档时返回换档信息
+    pub fn record(&mut self, busy: Duration) -> Option<RateChange> {
+        let period = self.period();
+        self.cycles += 1;
+        if busy > period {
+            self.misses += 1;
+        }
+        self.max_busy = self.max_busy.max(busy);
+        
+        if self.cycles < self.config.window_cycles {
+            return None;
+        }
+        
+        let miss_ratio = self.misses as f64 / self.cycles as f64;
+        let utilization = self.max_busy.as_secs_f64() / period.as_secs_f64();
+        let max_busy = self.max_busy;
+        self.cycles = 0;
+        self.misses = 0;
+        self.max_busy = Duration::ZERO;
+        
+        let from_hz = self.frequency();
+        if miss_ratio >= self.config.miss_ratio_threshold {
+            self.good_windows = 0;
+            if self.level + 1 < self.levels.len() {
+                self.level += 1;
+                return Some(RateChange { from_hz, to_hz: self.frequency(), miss_ratio, utilization });
+            }
+            return None;
+        }
+        
+        if self.level == 0 {
+            return None;
+        }
+        
+        // 以更高一档的周期衡量余量
+        let faster_period = 1.0 / self.levels[self.level - 1];
+        if max_busy.as_secs_f64() < self.config.recover_utilization * faster_period {
+            self.good_windows += 1;
+        } else {
+            self.good_windows = 0;
+        }
+        
+        if self.good_windows < self.config.recover_windows {
+            return None;
+        }
+        
+        self.good_windows = 0;
+        self.level -= 1;
+        Some(RateChange { from_hz, to_hz: self.frequency(), miss_ratio, utilization })
+    }
+}
+
+/// 按频率比例（新频率/额定频率）缩放PID增益
+pub fn rescale_gains(gains: &PIDGains, ratio: f64) -> PIDGains {
+    PIDGains {
+        kp: gains.kp * ratio,
+        ki: gains.ki * ratio * ratio,
+        ..gains.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn config() -> LoopRateConfig {
+        LoopRateConfig { window_cycles: 10, recover_windows: 2, ..LoopRateConfig::default() }
+    }
+    
+    #[test]
+    fn test_step_down_and_recover() {
+        let mut adapter = LoopRateAdapter::new(100.0, config()).unwrap();
+        assert_eq!(adapter.period(), Duration::from_millis(10));
+        
+        // 持续超时：降到50Hz，已是最低档时不再下降
+        let mut changes: Vec<RateChange> = (0..10).filter_map(|_| adapter.record(Duration::from_millis(12))).collect();
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].is_degraded());
+        assert_eq!((changes[0].from_hz, changes[0].to_hz), (100.0, 50.0));
+        assert_eq!(changes[0].miss_ratio, 1.0);
+        assert!((adapter.ratio() - 0.5).abs() < 1e-9);
+        assert!((0..20).all(|_| adapter.record(Duration::from_millis(25)).is_none()));
+        
+        // 12ms在50Hz下不超时，但对100Hz没有余量，不会升频
+        assert!((0..30).all(|_| adapter.record(Duration::from_millis(12)).is_none()));
+        
+        // 连续两个窗口都有余量后恢复
+        changes = (0..20).filter_map(|_| adapter.record(Duration::from_millis(3))).collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].from_hz, changes[0].to_hz), (50.0, 100.0));
+        assert_eq!(adapter.frequency(), 100.0);
+    }
+    
+    #[test]
+    fn test_disabled_and_gain_rescale() {
+        let disabled = LoopRateConfig { enabled: false, ..config() };
+        let mut adapter = LoopRateAdapter::new(100.0, disabled).unwrap();
+        assert!((0..50).all(|_| adapter.record(Duration::from_millis(20)).is_none()));
+        
+        let gains = PIDGains { kp: 2.0, ki: 0.4, kd: 0.1, max_integral: 10.0, max_output: 100.0 };
+        let scaled = rescale_gains(&gains, 0.5);
+        assert_eq!((scaled.kp, scaled.ki, scaled.kd), (1.0, 0.1, 0.1));
+        assert_eq!(scaled.max_output, gains.max_output);
+    }
+}
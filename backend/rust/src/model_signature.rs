@@ -0,0 +1,153 @@
+//! 模型输入输出签名
+//! 
+//! 模型加载后读取其实际的输入、输出名称、形状和数据类型，与配置中声明的输入形状和输出名称
+//! 比较，不一致时给出具体差异，排查"output0 not found"这类问题时不需要借助外部工具。
+//! 读取ONNX模型需要vision特性（ONNX Runtime），签名结构本身在所有构建中可用
+//! 
+//! 形状中的-1表示动态维度，与任何声明的值都视为一致
+
+use serde::{Deserialize, Serialize};
+
+/// 一个输入或输出张量的描述
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TensorInfo {
+    pub name: String,
+    /// 各维大小，动态维度为-1
+    pub shape: Vec<i64>,
+    /// 元素类型，如f32、i64
+    pub dtype: String,
+}
+
+/// 模型的输入输出签名
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelSignature {
+    pub inputs: Vec<TensorInfo>,
+    pub outputs: Vec<TensorInfo>,
+}
+
+impl ModelSignature {
+    pub fn output_names(&self) -> Vec<&str> {
+        self.outputs.iter().map(|output| output.name.as_str()).collect()
+    }
+    
+    /// 与配置声明的输入形状和输出名称比较，返回每一处差异的说明，一致时为空
+    pub fn mismatches(&self, input_shape: &[i64], output_names: &[String]) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        
+        match self.inputs.as_slice() {
+            [] => mismatches.push("模型没有输入".to_string()),
+            [input, rest @ ..] => {
+                if !shapes_compatible(&input.shape, input_shape) {
+                    mismatches.push(format!(
+                        "输入 '{}' 的形状为 {:?}，配置声明为 {:?}", input.name, input.shape, input_shape
+                    ));
+                }
+                if !rest.is_empty() {
+                    mismatches.push(format!("模型有 {} 个输入，只会向第一个输入 '{}' 提供数据", self.inputs.len(), input.name));
+                }
+            },
+        }
+        
+        for name in output_names {
+            if !self.outputs.iter().any(|output| &output.name == name) {
+                mismatches.push(format!("配置的输出 '{}' 不存在，模型的输出为 {:?}", name, self.output_names()));
+            }
+        }
+        
+        mismatches
+    }
+}
+
+/// 两个形状维数相同，且每一维相等或其中一方为动态维度
+pub fn shapes_compatible(actual: &[i64], declared: &[i64]) -> bool {
+    actual.len() == declared.len()
+        && actual.iter().zip(declared).all(|(&a, &d)| a < 0 || d < 0 || a == d)
+}
+
+#[cfg(feature = "vision")]
+mod onnx {
+    use super::{ModelSignature, TensorInfo};
+    use anyhow::Result;
+    use ort::session::Session;
+    use ort::value::ValueType;
+    use std::ffi::CString;
+    use std::path::Path;
+    
+    fn tensor_info(name: &str, value_type: &ValueType) -> TensorInfo {
+        TensorInfo {
+            name: name.to_string(),
+            shape: value_type.tensor_shape().map(|shape| shape.to_vec()).unwrap_or_default(),
+            dtype: value_type.tensor_type().map(|ty| ty.to_string()).unwrap_or_else(|| value_type.to_string()),
+        }
+    }
+    
+    impl ModelSignature {
+        /// 已加载会话的输入输出
+        pub fn from_session(session: &Session) -> Self {
+            Self {
+                inputs: session.inputs.iter().map(|input| tensor_info(&input.name, &input.input_type)).collect(),
+                outputs: session.outputs.iter().map(|output| tensor_info(&output.name, &output.output_type)).collect(),
+            }
+        }
+        
+        /// 加载ONNX模型并读取其输入输出
+        pub fn read_onnx(path: &Path) -> Result<Self> {
+            if !onnxruntime_available() {
+                return Err(anyhow::anyhow!("无法加载ONNX Runtime动态库，可通过ORT_DYLIB_PATH指定路径"));
+            }
+            let session = Session::builder()?.commit_from_file(path)?;
+            Ok(Self::from_session(&session))
+        }
+    }
+    
+    /// ort在找不到ONNX Runtime动态库时会直接panic，先用dlopen探测
+    pub fn onnxruntime_available() -> bool {
+        let path = std::env::var("ORT_DYLIB_PATH")
+            .ok()
+            .filter(|path| !path.is_empty())
+            .unwrap_or_else(|| "libonnxruntime.so".to_string());
+        let Ok(path) = CString::new(path) else {
+            return false;
+        };
+        
+        unsafe {
+            let handle = libc::dlopen(path.as_ptr(), libc::RTLD_LAZY);
+            if handle.is_null() {
+                return false;
+            }
+            libc::dlclose(handle);
+        }
+        
+        true
+    }
+}
+
+#[cfg(feature = "vision")]
+pub use onnx::onnxruntime_available;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn tensor(name: &str, shape: &[i64]) -> TensorInfo {
+        TensorInfo { name: name.to_string(), shape: shape.to_vec(), dtype: "f32".to_string() }
+    }
+    
+    #[test]
+    fn test_mismatches_report_shape_and_missing_outputs() {
+        let signature = ModelSignature {
+            inputs: vec![tensor("images", &[-1, 3, 640, 640])],
+            outputs: vec![tensor("output", &[1, 84, 8400])],
+        };
+        
+        assert!(signature.mismatches(&[1, 3, 640, 640], &["output".to_string()]).is_empty());
+        
+        let mismatches = signature.mismatches(&[1, 3, 320, 320], &["output0".to_string()]);
+        assert_eq!(mismatches.len(), 2);
+        assert!(mismatches[0].contains("images"));
+        assert!(mismatches[1].contains("output0") && mismatches[1].contains("\"output\""));
+        
+        assert!(!shapes_compatible(&[1, 3, 640], &[1, 3, 640, 640]));
+        assert_eq!(ModelSignature::default().mismatches(&[1], &[]), vec!["模型没有输入".to_string()]);
+    }
+}
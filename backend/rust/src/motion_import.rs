@@ -0,0 +1,326 @@
+//! 动作导入模块
+//! 
+//! 设计师习惯在Blender等动画工具里制作关键帧动作。本模块把这些工具导出的BVH文件
+//! 转换为内部的轨迹文件格式：按重定向配置把源骨骼的旋转通道映射到机器人关节，
+//! 换算为弧度，并按关节限位检查（超限时截断或报错）。
+//!
+//! BVH的每个旋转通道单独映射到一个关节，适用于头部、天线等单轴关节；
+//! 源骨骼的欧拉角顺序需与导出设置一致。
+
+use crate::common::*;
+use crate::joints::JointSetConfig;
+use crate::trajectory_file::{TrajectoryFile, TrajectoryFrame};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use log::warn;
+
+/// 源通道到机器人关节的映射
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelMapping {
+    /// 源骨骼名称
+    pub source_joint: String,
+    /// 源通道，例如Yrotation
+    pub channel: String,
+    /// 机器人关节名称
+    pub target_joint: String,
+    /// 缩放系数，-1可用于翻转方向
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    /// 缩放后叠加的偏移（度）
+    #[serde(default, with = "serde_degrees")]
+    pub offset: Angle,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+/// 重定向配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetargetConfig {
+    pub mappings: Vec<ChannelMapping>,
+    /// 超出关节限位时截断，关闭时直接报错
+    #[serde(default = "default_clamp")]
+    pub clamp_to_limits: bool,
+}
+
+fn default_clamp() -> bool {
+    true
+}
+
+impl RetargetConfig {
+    /// 检查映射是否有效，目标关节必须存在于关节集合中且不能重复
+    pub fn validate(&self, joints: &JointSetConfig) -> Result<()> {
+        if self.mappings.is_empty() {
+            return Err(anyhow::anyhow!("重定向配置没有任何映射"));
+        }
+        
+        let mut targets = HashSet::new();
+        for mapping in &self.mappings {
+            if joints.get(&mapping.target_joint).is_none() {
+                return Err(anyhow::anyhow!("目标关节 '{}' 不存在", mapping.target_joint));
+            }
+            
+            if !targets.insert(&mapping.target_joint) {
+                return Err(anyhow::anyhow!("目标关节 '{}' 被映射了多次", mapping.target_joint));
+            }
+            
+            if !mapping.scale.is_finite() || mapping.scale == 0.0 {
+                return Err(anyhow::anyhow!("关节 '{}' 的缩放系数无效", mapping.target_joint));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 导入报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub frames: usize,
+    pub frame_time: f64,
+    /// 各关节被截断的帧数
+    pub clamped: HashMap<String, usize>,
+    /// 文件中存在但没有映射的骨骼
+    pub unmapped_joints: Vec<String>,
+}
+
+/// 解析后的BVH动作数据
+#[derive(Debug, Clone)]
+pub struct BvhMotion {
+    /// 按出现顺序排列的 (骨骼, 通道)
+    pub channels: Vec<(String, String)>,
+    pub frame_time: f64,
+    /// 每帧的通道数值（位置为文件单位，旋转为度）
+    pub frames: Vec<Vec<f64>>,
+}
+
+impl BvhMotion {
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut tokens = content.split_whitespace();
+        let mut channels = Vec::new();
+        let mut current_joint: Option<String> = None;
+        
+        // 层级部分：只需要骨骼名称和通道顺序
+        loop {
+            let token = tokens.next().ok_or_else(|| anyhow::anyhow!("BVH缺少MOTION部分"))?;
+            match token {
+                "ROOT" | "JOINT" => {
+                    let name = tokens.next().ok_or_else(|| anyhow::anyhow!("BVH骨骼缺少名称"))?;
+                    current_joint = Some(name.to_string());
+                }
+                "CHANNELS" => {
+                    let joint = current_joint.clone().ok_or_else(|| anyhow::anyhow!("CHANNELS出现在骨骼定义之外"))?;
+                    let count: usize = tokens.next()
+                        .and_then(|t| t.parse().ok())
+                        .ok_or_else(|| anyhow::anyhow!("骨骼 {} 的通道数量无效", joint))?;
+                    for _ in 0..count {
+                        let channel = tokens.next().ok_or_else(|| anyhow::anyhow!("骨骼 {} 的通道不完整", joint))?;
+                        channels.push((joint.clone(), channel.to_string()));
+                    }
+                }
+                "MOTION" => break,
+                _ => {}
+            }
+        }
+        
+        expect_token(&mut tokens, "Frames:")?;
+        let frame_count: usize = tokens.next()
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("BVH帧数无效"))?;
+        
+        expect_token(&mut tokens, "Frame")?;
+        expect_token(&mut tokens, "Time:")?;
+        let frame_time: f64 = tokens.next()
+            .and_then(|t| t.parse().ok())
+            .filter(|t: &f64| t.is_finite() && *t > 0.0)
+            .ok_or_else(|| anyhow::anyhow!("BVH帧间隔无效"))?;
+        
+        let values: Vec<f64> = tokens
+            .map(|t| t.parse::<f64>().map_err(|e| anyhow::anyhow!("BVH动作数据错误 '{}': {}", t, e)))
+            .collect::<Result<_>>()?;
+        
+        if channels.is_empty() || values.len() != frame_count * channels.len() {
+            return Err(anyhow::anyhow!(
+                "BVH动作数据数量不符：{}帧 × {}通道，实际 {} 个数值", frame_count, channels.len(), values.len()
+            ));
+        }
+        
+        let frames = values.chunks(channels.len()).map(|chunk| chunk.to_vec()).collect();
+        Ok(Self { channels, frame_time, frames })
+    }
+}
+
+fn expect_token<'a>(tokens: &mut impl Iterator<Item = &'a str>, expected: &str) -> Result<()> {
+    match tokens.next() {
+        Some(token) if token == expected => Ok(()),
+        other => Err(anyhow::anyhow!("BVH格式错误：期望 {}，实际为 {:?}", expected, other)),
+    }
+}
+
+/// 把BVH动作重定向为机器人关节轨迹
+pub fn import_bvh(
+    content: &str,
+    name: &str,
+    retarget: &RetargetConfig,
+    joints: &JointSetConfig,
+) -> Result<(TrajectoryFile, ImportReport)> {
+    retarget.validate(joints)?;
+    let motion = BvhMotion::parse(content)?;
+    
+    // 找到每个映射对应的通道列
+    let mut columns = Vec::with_capacity(retarget.mappings.len());
+    for mapping in &retarget.mappings {
+        if !mapping.channel.ends_with("rotation") {
+            return Err(anyhow::anyhow!("只支持旋转通道，'{}' 不是旋转通道", mapping.channel));
+        }
+        
+        let column = motion.channels.iter()
+            .position(|(joint, channel)| *joint == mapping.source_joint && *channel == mapping.channel)
+            .ok_or_else(|| anyhow::anyhow!("BVH中没有通道 {}.{}", mapping.source_joint, mapping.channel))?;
+        columns.push(column);
+    }
+    
+    let mapped: HashSet<&String> = retarget.mappings.iter().map(|m| &m.source_joint).collect();
+    let mut unmapped_joints: Vec<String> = motion.channels.iter()
+        .map(|(joint, _)| joint)
+        .filter(|joint| !mapped.contains(joint))
+        .cloned()
+        .collect();
+    unmapped_joints.dedup();
+    
+    let mut report = ImportReport {
+        frames: motion.frames.len(),
+        frame_time: motion.frame_time,
+        unmapped_joints,
+        ..Default::default()
+    };
+    
+    let target_joints = retarget.mappings.iter().map(|m| m.target_joint.clone()).collect();
+    let mut trajectory = TrajectoryFile::new(name, target_joints);
+    
+    for (index, values) in motion.frames.iter().enumerate() {
+        let mut positions = Vec::with_capacity(columns.len());
+        
+        for (mapping, &column) in retarget.mappings.iter().zip(&columns) {
+            let joint = joints.get(&mapping.target_joint).unwrap();
+            let position = Angle::from_degrees(values[column]) * mapping.scale + mapping.offset;
+            
+            let within_limits = joint.continuous || (joint.min_position..=joint.max_position).contains(&position);
+            let position = if within_limits {
+                position
+            } else if retarget.clamp_to_limits {
+                *report.clamped.entry(joint.name.clone()).or_insert(0) += 1;
+                clamp(position, joint.min_position, joint.max_position)
+            } else {
+                return Err(anyhow::anyhow!(
+                    "第 {} 帧关节 {} 的位置 {:.1}° 超出限位", index, joint.name, position.degrees()
+                ));
+            };
+            
+            positions.push(position.radians());
+        }
+        
+        trajectory.frames.push(TrajectoryFrame {
+            time: index as f64 * motion.frame_time,
+            positions,
+            velocities: None,
+        });
+    }
+    
+    for (joint, count) in &report.clamped {
+        warn!("导入动作 {}：关节 {} 有 {} 帧超出限位，已截断", name, joint, count);
+    }
+    
+    trajectory.validate()?;
+    Ok((trajectory, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const NOD_BVH: &str = "
+HIERARCHY
+ROOT Hips
+{
+    OFFSET 0.0 0.0 0.0
+    CHANNELS 6 Xposition Yposition Zposition Zrotation Xrotation Yrotation
+    JOINT Head
+    {
+        OFFSET 0.0 10.0 0.0
+        CHANNELS 3 Zrotation Xrotation Yrotation
+        End Site
+        {
+            OFFSET 0.0 5.0 0.0
+        }
+    }
+}
+MOTION
+Frames: 3
+Frame Time: 0.04
+0 0 0 0 0 0  0 0 0
+0 0 0 0 0 0  0 20 30
+0 0 0 0 0 0  0 40 200
+";
+    
+    fn retarget() -> RetargetConfig {
+        RetargetConfig {
+            mappings: vec![
+                ChannelMapping {
+                    source_joint: "Head".to_string(),
+                    channel: "Yrotation".to_string(),
+                    target_joint: "head_pan".to_string(),
+                    scale: 1.0,
+                    offset: Angle::ZERO,
+                },
+                ChannelMapping {
+                    source_joint: "Head".to_string(),
+                    channel: "Xrotation".to_string(),
+                    target_joint: "head_tilt".to_string(),
+                    scale: -1.0,
+                    offset: Angle::ZERO,
+                },
+            ],
+            clamp_to_limits: true,
+        }
+    }
+    
+    #[test]
+    fn test_import_bvh() {
+        let joints = JointSetConfig::default();
+        let (trajectory, report) = import_bvh(NOD_BVH, "nod", &retarget(), &joints).unwrap();
+        
+        assert_eq!(trajectory.joints, vec!["head_pan".to_string(), "head_tilt".to_string()]);
+        assert_eq!(trajectory.frames.len(), 3);
+        assert!((trajectory.frames[1].time - 0.04).abs() < 1e-9);
+        assert!((trajectory.frames[1].positions[0] - 30f64.to_radians()).abs() < 1e-9);
+        assert!((trajectory.frames[1].positions[1] + 20f64.to_radians()).abs() < 1e-9);
+        
+        // 200°超出head_pan的限位，被截断
+        let head_pan = joints.get("head_pan").unwrap();
+        assert_eq!(trajectory.frames[2].positions[0], head_pan.max_position.radians());
+        assert_eq!(report.clamped.get("head_pan"), Some(&1));
+        assert_eq!(report.unmapped_joints, vec!["Hips".to_string()]);
+    }
+    
+    #[test]
+    fn test_import_errors() {
+        let joints = JointSetConfig::default();
+        
+        let strict = RetargetConfig { clamp_to_limits: false, ..retarget() };
+        assert!(import_bvh(NOD_BVH, "nod", &strict, &joints).unwrap_err().to_string().contains("head_pan"));
+        
+        let mut missing = retarget();
+        missing.mappings[0].source_joint = "Neck".to_string();
+        assert!(import_bvh(NOD_BVH, "nod", &missing, &joints).unwrap_err().to_string().contains("Neck"));
+        
+        let mut unknown = retarget();
+        unknown.mappings[0].target_joint = "tail".to_string();
+        assert!(import_bvh(NOD_BVH, "nod", &unknown, &joints).is_err());
+        
+        let truncated = NOD_BVH.replace("0 0 0 0 0 0  0 40 200", "");
+        assert!(BvhMotion::parse(&truncated).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! 振荡检测
+//! 
+//! 在线统计每个关节跟踪误差（目标位置减测量位置）的过零率和峰峰值：过零用
+//! `min_amplitude`的一半做滞回，小幅噪声不计入。一个窗口内由过零次数折算的频率
+//! 不低于`min_frequency_hz`且峰峰值不小于`min_amplitude`时记为振荡窗口，
+//! 连续`sustain_windows`个振荡窗口判定为持续振荡。
+//!
+//! 判定后按配置把该关节的增益乘以`gain_reduction`，增益比例低于`min_gain_scale`
+//! 或配置为直接停止时停止该关节，直到调用方复位。错误的PID参数不会一直驱动舵机来回抖动。
+
+use crate::common::*;
+use crate::realtime::PIDGains;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 检测到持续振荡后的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OscillationAction {
+    /// 降低增益，多次降低后仍振荡则停止关节
+    ReduceGains,
+    /// 停止关节
+    HaltJoint,
+}
+
+/// 振荡检测配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OscillationConfig {
+    pub enabled: bool,
+    /// 统计窗口（秒）
+    pub window_secs: f64,
+    /// 振荡频率下限（Hz），正常的跟踪误差变化比这慢
+    pub min_frequency_hz: f64,
+    /// 误差峰峰值下限（rad）
+    pub min_amplitude: f64,
+    /// 连续多少个振荡窗口判定为持续振荡
+    pub sustain_windows: usize,
+    pub action: OscillationAction,
+    /// 每次降低增益的比例
+    pub gain_reduction: f64,
+    /// 增益比例低于该值时改为停止关节
+    pub min_gain_scale: f64,
+}
+
+impl Default for OscillationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 0.5,
+            min_frequency_hz: 3.0,
+            min_amplitude: 0.01,
+            sustain_windows: 3,
+            action: OscillationAction::ReduceGains,
+            gain_reduction: 0.5,
+            min_gain_scale: 0.2,
+        }
+    }
+}
+
+impl ConfigValidation for OscillationConfig {
+    fn validate(&self) -> Result<()> {
+        if self.window_secs <= 0.0 || self.min_frequency_hz <= 0.0 || self.min_amplitude <= 0.0 {
+            return Err(anyhow::anyhow!("振荡检测的窗口、频率下限和幅值下限必须为正数"));
+        }
+        
+        if self.sustain_windows == 0 {
+            return Err(anyhow::anyhow!("持续窗口数不能为0"));
+        }
+        
+        if !(self.gain_reduction > 0.0 && self.gain_reduction < 1.0) {
+            return Err(anyhow::anyhow!("增益降低比例必须在0-1之间"));
+        }
+        
+        if !(self.min_gain_scale > 0.0 && self.min_gain_scale < 1.0) {
+            return Err(anyhow::anyhow!("最小增益比例必须在0-1之间"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 一次持续振荡的诊断数据和处理结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OscillationReport {
+    pub joint_name: String,
+    /// 最近一个窗口由过零率估计的振荡频率（Hz）
+    pub frequency_hz: f64,
+    /// 最近一个窗口误差的峰峰值（rad）
+    pub amplitude: f64,
+    /// 振荡持续的时长（秒）
+    pub duration_secs: f64,
+    pub action: OscillationAction,
+    /// 处理后的增益比例（相对配置的增益），停止关节时为0
+    pub gain_scale: f64,
+}
+
+/// 单个关节的窗口统计
+#[derive(Debug, Clone)]
+struct JointWindow {
+    elapsed: f64,
+    crossings: usize,
+    /// 上一次超出滞回区间时误差的符号
+    sign: f64,
+    min_error: f64,
+    max_error: f64,
+    sustained: usize,
+    gain_scale: f64,
+    halted: bool,
+}
+
+impl Default for JointWindow {
+    fn default() -> Self {
+        Self {
+            elapsed: 0.0,
+            crossings: 0,
+            sign: 0.0,
+            min_error: f64::INFINITY,
+            max_error: f64::NEG_INFINITY,
+            sustained: 0,
+            gain_scale: 1.0,
+            halted: false,
+        }
+    }
+}
+
+/// 各关节的在线振荡检测
+#[derive(Debug)]
+pub struct OscillationDetector {
+    config: OscillationConfig,
+    joints: HashMap<String, JointWindow>,
+}
+
+impl OscillationDetector {
+    pub fn new(config: OscillationConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self { config, joints: HashMap::new() })
+    }
+    
+    /// 输入关节本周期的跟踪误差和周期时长，判定为持续振荡时返回处理结果
+    pub fn update(&mut self, joint_name: &str, error: f64, dt: f64) -> Option<OscillationReport> {
+        if !self.config.enabled {
+            return None;
+        }
+        
+        let config = &self.config;
+        let window = self.joints.entry(joint_name.to_string()).or_default();
+        if window.halted {
+            return None;
+        }
+        
+        let hysteresis = config.min_amplitude / 2.0;
+        if error.abs() >= hysteresis {
+            let sign = error.signum();
+            if window.sign != 0.0 && sign != window.sign {
+                window.crossings += 1;
+            }
+            window.sign = sign;
+        }
+        window.min_error = window.min_error.min(error);
+        window.max_error = window.max_error.max(error);
+        window.elapsed += dt;
+        
+        if window.elapsed < config.window_secs {
+            return None;
+        }
+        
+        // 两次过零为一个周期
+        let frequency_hz = window.crossings as f64 / 2.0 / window.elapsed;
+        let amplitude = window.max_error - window.min_error;
+        if frequency_hz >= config.min_frequency_hz && amplitude >= config.min_amplitude {
+            window.sustained += 1;
+        } else {
+            window.sustained = 0;
+        }
+        let duration_secs = window.sustained as f64 * window.elapsed;
+        window.elapsed = 0.0;
+        window.crossings = 0;
+        window.min_error = f64::INFINITY;
+        window.max_error = f64::NEG_INFINITY;
+        
+        if window.sustained < config.sustain_windows {
+            return None;
+        }
+        window.sustained = 0;
+        
+        let reduced = window.gain_scale * config.gain_reduction;
+        let action = if config.action == OscillationAction::ReduceGains && reduced >= config.min_gain_scale {
+            window.gain_scale = reduced;
+            OscillationAction::ReduceGains
+        } else {
+            window.halted = true;
+            window.gain_scale = 0.0;
+            OscillationAction::HaltJoint
+        };
+        
+        Some(OscillationReport {
+            joint_name: joint_name.to_string(),
+            frequency_hz,
+            amplitude,
+            duration_secs,
+            action,
+            gain_scale: window.gain_scale,
+        })
+    }
+    
+    /// 关节当前的增益比例，没有降低过时为1
+    pub fn gain_scale(&self, joint_name: &str) -> f64 {
+        self.joints.get(joint_name).map_or(1.0, |window| window.gain_scale)
+    }
+    
+    pub fn is_halted(&self, joint_name: &str) -> bool {
+        self.joints.get(joint_name).is_some_and(|window| window.halted)
+    }
+    
+    /// 复位关节：恢复原增益并解除停止
+    pub fn reset(&mut self, joint_name: &str) {
+        self.joints.remove(joint_name);
+    }
+}
+
+/// 按比例缩放PID增益（积分和输出限幅不变）
+pub fn scale_gains(gains: &PIDGains, scale: f64) -> PIDGains {
+    PIDGains {
+        kp: gains.kp * scale,
+        ki: gains.ki * scale,
+        kd: gains.kd * scale,
+        ..gains.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 以100Hz采样的正弦误差
+    fn feed(detector: &mut OscillationDetector, frequency_hz: f64, amplitude: f64, seconds: f64) -> Vec<OscillationReport> {
+        let dt = 0.01;
+        (0..(seconds / dt) as usize)
+            .filter_map(|i| {
+                let error = amplitude * (2.0 * std::f64::consts::PI * frequency_hz * i as f64 * dt).sin();
+                detector.update("head_pan", error, dt)
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_sustained_oscillation_reduces_gains_then_halts() {
+        let mut detector = OscillationDetector::new(OscillationConfig::default()).unwrap();
+        
+        // 慢速的正常跟踪误差和小幅高频噪声都不触发
+        assert!(feed(&mut detector, 0.5, 0.2, 5.0).is_empty());
+        assert!(feed(&mut detector, 10.0, 0.002, 5.0).is_empty());
+        
+        // 8Hz、±0.05rad的振荡持续1.5秒后降低增益，再两次后停止关节
+        let reports = feed(&mut detector, 8.0, 0.05, 5.0);
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].action, OscillationAction::ReduceGains);
+        assert!((reports[0].frequency_hz - 8.0).abs() < 1.0);
+        assert!((reports[0].amplitude - 0.1).abs() < 0.01);
+        assert_eq!(reports[1].gain_scale, 0.25);
+        assert_eq!(reports[2].action, OscillationAction::HaltJoint);
+        assert!(detector.is_halted("head_pan"));
+        assert!(feed(&mut detector, 8.0, 0.05, 2.0).is_empty());
+        
+        detector.reset("head_pan");
+        assert!(!detector.is_halted("head_pan"));
+        assert_eq!(detector.gain_scale("head_pan"), 1.0);
+    }
+}
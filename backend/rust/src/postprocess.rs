@@ -0,0 +1,400 @@
+//! 检测模型后处理的向量化实现
+//! 
+//! 树莓派上推理之后最耗CPU的是框解码和非极大值抑制。这里的批量计算按结构数组组织，循环体
+//! 没有分支，编译器可以向量化：x86_64在运行时检测到AVX2时调用按AVX2编译的版本，aarch64的
+//! NEON是基础指令集，直接向量化。每个函数都有对应的标量版本（*_scalar）作为对照，
+//! 基准测试见benches/postprocess.rs（cargo bench --bench postprocess）
+//! 
+//! 向量化版本的IoU与标量版本逐位一致；指数函数使用多项式近似，相对误差在1e-6以内
+
+use std::sync::OnceLock;
+
+/// 每组并行计算的元素个数，8个f32正好是一个AVX寄存器
+const LANES: usize = 8;
+
+/// 运行时检测到的SIMD指令集
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdLevel {
+    Scalar,
+    Neon,
+    Avx2,
+}
+
+pub fn simd_level() -> SimdLevel {
+    static LEVEL: OnceLock<SimdLevel> = OnceLock::new();
+    *LEVEL.get_or_init(|| {
+        #[cfg(target_arch = "x86_64")]
+        if std::arch::is_x86_feature_detected!("avx2") {
+            return SimdLevel::Avx2;
+        }
+        #[cfg(target_arch = "aarch64")]
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return SimdLevel::Neon;
+        }
+        SimdLevel::Scalar
+    })
+}
+
+/// 生成按运行时检测结果分派的函数：支持AVX2时调用按AVX2编译的同一实现
+macro_rules! multiversion {
+    ($(#[$meta:meta])* $vis:vis fn $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)? => $generic:ident) => {
+        $(#[$meta])*
+        $vis fn $name($($arg: $ty),*) $(-> $ret)? {
+            #[cfg(target_arch = "x86_64")]
+            {
+                #[target_feature(enable = "avx2")]
+                fn avx2($($arg: $ty),*) $(-> $ret)? {
+                    $generic($($arg),*)
+                }
+                
+                if simd_level() == SimdLevel::Avx2 {
+                    // SAFETY: 已在运行时确认CPU支持AVX2
+                    return unsafe { avx2($($arg),*) };
+                }
+            }
+            $generic($($arg),*)
+        }
+    };
+}
+
+/// 按得分从高到低排列的下标，得分相同时保持原顺序
+fn order_by_score(scores: &[f32]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+    order
+}
+
+/// 两个(x1, y1, x2, y2)框的交并比
+pub fn iou(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    let overlap_w = (a[2].min(b[2]) - a[0].max(b[0])).max(0.0);
+    let overlap_h = (a[3].min(b[3]) - a[1].max(b[1])).max(0.0);
+    let overlap = overlap_w * overlap_h;
+    let union = (a[2] - a[0]) * (a[3] - a[1]) + (b[2] - b[0]) * (b[3] - b[1]) - overlap;
+    
+    if union > 0.0 { overlap / union } else { 0.0 }
+}
+
+/// 按得分排好序的框，坐标和面积分别连续存放
+struct SortedBoxes {
+    x1: Vec<f32>,
+    y1: Vec<f32>,
+    x2: Vec<f32>,
+    y2: Vec<f32>,
+    area: Vec<f32>,
+}
+
+impl SortedBoxes {
+    fn new(boxes: &[[f32; 4]], order: &[usize]) -> Self {
+        let column = |k: usize| order.iter().map(|&i| boxes[i][k]).collect::<Vec<f32>>();
+        let (x1, y1, x2, y2) = (column(0), column(1), column(2), column(3));
+        let area = order.iter().map(|&i| (boxes[i][2] - boxes[i][0]) * (boxes[i][3] - boxes[i][1])).collect();
+        Self { x1, y1, x2, y2, area }
+    }
+}
+
+/// 第i个框与其后所有框的IoU，写入out（长度为其后框的个数），运算顺序与iou相同
+#[inline(always)]
+fn iou_row_generic(boxes: &SortedBoxes, i: usize, out: &mut [f32]) {
+    let start = i + 1;
+    let n = out.len();
+    let (x1, y1) = (&boxes.x1[start..start + n], &boxes.y1[start..start + n]);
+    let (x2, y2) = (&boxes.x2[start..start + n], &boxes.y2[start..start + n]);
+    let area = &boxes.area[start..start + n];
+    let (bx1, by1, bx2, by2, barea) = (boxes.x1[i], boxes.y1[i], boxes.x2[i], boxes.y2[i], boxes.area[i]);
+    
+    for (j, value) in out.iter_mut().enumerate() {
+        let overlap_w = (bx2.min(x2[j]) - bx1.max(x1[j])).max(0.0);
+        let overlap_h = (by2.min(y2[j]) - by1.max(y1[j])).max(0.0);
+        let overlap = overlap_w * overlap_h;
+        let union = barea + area[j] - overlap;
+        *value = if union > 0.0 { overlap / union } else { 0.0 };
+    }
+}
+
+multiversion!(fn iou_row(boxes: &SortedBoxes, i: usize, out: &mut [f32]) => iou_row_generic);
+
+/// 贪心非极大值抑制：按得分从高到低，与已保留的框IoU都不超过阈值的框被保留
+///
+/// 框为(x1, y1, x2, y2)，返回保留框的下标，按得分从高到低，得分相同时保持原顺序
+pub fn nms(boxes: &[[f32; 4]], scores: &[f32], iou_threshold: f32) -> Vec<usize> {
+    let order = order_by_score(&scores[..boxes.len().min(scores.len())]);
+    let sorted = SortedBoxes::new(boxes, &order);
+    let mut suppressed = vec![false; order.len()];
+    let mut ious = vec![0.0f32; order.len()];
+    let mut kept = Vec::new();
+    
+    for (i, &index) in order.iter().enumerate() {
+        if suppressed[i] {
+            continue;
+        }
+        kept.push(index);
+        
+        // 一次算出与后面所有框的IoU，再标记被抑制的框
+        let rest = &mut ious[i + 1..];
+        iou_row(&sorted, i, rest);
+        for (suppressed, &iou) in suppressed[i + 1..].iter_mut().zip(rest.iter()) {
+            *suppressed |= iou > iou_threshold;
+        }
+    }
+    
+    kept
+}
+
+/// nms的标量版本：逐个候选框与已保留的框比较
+pub fn nms_scalar(boxes: &[[f32; 4]], scores: &[f32], iou_threshold: f32) -> Vec<usize> {
+    let mut kept: Vec<usize> = Vec::new();
+    for index in order_by_score(&scores[..boxes.len().min(scores.len())]) {
+        if kept.iter().all(|&k| iou(&boxes[k], &boxes[index]) <= iou_threshold) {
+            kept.push(index);
+        }
+    }
+    kept
+}
+
+/// 无分支的expf近似（Cephes的多项式），可以向量化
+#[inline(always)]
+fn fast_exp(x: f32) -> f32 {
+    const LN2_HI: f32 = 0.693_359_4;
+    const LN2_LO: f32 = -2.121_944_4e-4;
+    
+    // 截断到结果为正规数的范围
+    let x = x.clamp(-87.0, 88.0);
+    let n = (x * std::f32::consts::LOG2_E + 0.5).floor();
+    let r = x - n * LN2_HI - n * LN2_LO;
+    
+    let mut p = 1.987_569_1e-4f32;
+    p = p * r + 1.398_199_9e-3;
+    p = p * r + 8.333_452e-3;
+    p = p * r + 4.166_579_6e-2;
+    p = p * r + 1.666_666_5e-1;
+    p = p * r + 0.5;
+    let y = p * r * r + r + 1.0;
+    
+    y * f32::from_bits(((n as i32 + 127) << 23) as u32)
+}
+
+#[inline(always)]
+fn sigmoid_generic(values: &mut [f32]) {
+    for value in values.iter_mut() {
+        *value = 1.0 / (1.0 + fast_exp(-*value));
+    }
+}
+
+multiversion!(
+    /// 原地计算sigmoid
+    pub fn sigmoid(values: &mut [f32]) => sigmoid_generic
+);
+
+/// sigmoid的标量版本
+pub fn sigmoid_scalar(values: &mut [f32]) {
+    for value in values.iter_mut() {
+        *value = 1.0 / (1.0 + (-*value).exp());
+    }
+}
+
+/// 按LANES路并行归约，避免浮点归约的顺序依赖阻止向量化
+#[inline(always)]
+fn reduce_lanes(values: &[f32], init: f32, op: impl Fn(f32, f32) -> f32) -> f32 {
+    let mut lanes = [init; LANES];
+    let chunks = values.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    for chunk in chunks {
+        for (lane, &value) in lanes.iter_mut().zip(chunk) {
+            *lane = op(*lane, value);
+        }
+    }
+    lanes.iter().chain(remainder).fold(init, |acc, &value| op(acc, value))
+}
+
+#[inline(always)]
+fn softmax_generic(values: &mut [f32]) {
+    if values.is_empty() {
+        return;
+    }
+    
+    let max = reduce_lanes(values, f32::NEG_INFINITY, f32::max);
+    for value in values.iter_mut() {
+        *value = fast_exp(*value - max);
+    }
+    let inverse = 1.0 / reduce_lanes(values, 0.0, |a, b| a + b);
+    for value in values.iter_mut() {
+        *value *= inverse;
+    }
+}
+
+multiversion!(
+    /// 原地计算softmax
+    pub fn softmax(values: &mut [f32]) => softmax_generic
+);
+
+/// softmax的标量版本
+pub fn softmax_scalar(values: &mut [f32]) {
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    for value in values.iter_mut() {
+        *value = (*value - max).exp();
+    }
+    let sum: f32 = values.iter().sum();
+    for value in values.iter_mut() {
+        *value /= sum;
+    }
+}
+
+/// 检测模型解码出的候选框，bbox为(x1, y1, x2, y2)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+    pub bbox: [f32; 4],
+    pub score: f32,
+    pub class_id: usize,
+}
+
+/// 对每个先验框找得分最高的类别：逐类别整行比较，跨先验框向量化
+#[inline(always)]
+fn best_class_generic(scores: &[f32], anchors: usize, best: &mut [f32], best_class: &mut [u32]) {
+    best.copy_from_slice(&scores[..anchors]);
+    best_class.fill(0);
+    for (class_id, row) in scores.chunks_exact(anchors).enumerate().skip(1) {
+        let class_id = class_id as u32;
+        for ((best, best_class), &score) in best.iter_mut().zip(best_class.iter_mut()).zip(row) {
+            let better = score > *best;
+            *best = if better { score } else { *best };
+            *best_class = if better { class_id } else { *best_class };
+        }
+    }
+}
+
+multiversion!(fn best_class(scores: &[f32], anchors: usize, best: &mut [f32], best_class: &mut [u32]) => best_class_generic);
+
+fn sigmoid_one(value: f32) -> f32 {
+    1.0 / (1.0 + (-value).exp())
+}
+
+fn yolo_box(output: &[f32], anchors: usize, anchor: usize) -> [f32; 4] {
+    let (cx, cy) = (output[anchor], output[anchors + anchor]);
+    let (w, h) = (output[2 * anchors + anchor], output[3 * anchors + anchor]);
+    [cx - w / 2.0, cy - h / 2.0, cx + w / 2.0, cy + h / 2.0]
+}
+
+/// 解码YOLOv8格式的输出：形状为[4 + 类别数, 先验框数]，前4行为cx、cy、w、h，其后每行一个类别的得分
+///
+/// 每个先验框取得分最高的类别，低于score_threshold的丢弃。logits为true时类别得分是未经sigmoid的
+/// logit：sigmoid单调，先在logit空间比较阈值，只对保留下来的候选框计算sigmoid。
+/// 输出长度不是4 + 类别数的整数倍时多余的数据被忽略
+pub fn decode_yolo(output: &[f32], num_classes: usize, score_threshold: f32, logits: bool) -> Vec<Candidate> {
+    let anchors = output.len() / (4 + num_classes);
+    if anchors == 0 || num_classes == 0 {
+        return Vec::new();
+    }
+    
+    let mut best = vec![0.0f32; anchors];
+    let mut classes = vec![0u32; anchors];
+    best_class(&output[4 * anchors..(4 + num_classes) * anchors], anchors, &mut best, &mut classes);
+    
+    let threshold = if logits { (score_threshold / (1.0 - score_threshold)).ln() } else { score_threshold };
+    best.iter().zip(&classes).enumerate()
+        .filter(|(_, (&score, _))| score >= threshold)
+        .map(|(anchor, (&score, &class_id))| Candidate {
+            bbox: yolo_box(output, anchors, anchor),
+            score: if logits { sigmoid_one(score) } else { score },
+            class_id: class_id as usize,
+        })
+        .collect()
+}
+
+/// decode_yolo的标量版本：逐个先验框遍历所有类别，logits为true时对每个得分计算sigmoid
+pub fn decode_yolo_scalar(output: &[f32], num_classes: usize, score_threshold: f32, logits: bool) -> Vec<Candidate> {
+    let anchors = output.len() / (4 + num_classes);
+    let mut candidates = Vec::new();
+    if num_classes == 0 {
+        return candidates;
+    }
+    
+    for anchor in 0..anchors {
+        let mut best: Option<(f32, usize)> = None;
+        for class_id in 0..num_classes {
+            let mut score = output[(4 + class_id) * anchors + anchor];
+            if logits {
+                score = sigmoid_one(score);
+            }
+            if best.is_none_or(|(best, _)| score > best) {
+                best = Some((score, class_id));
+            }
+        }
+        if let Some((score, class_id)) = best.filter(|&(score, _)| score >= score_threshold) {
+            candidates.push(Candidate { bbox: yolo_box(output, anchors, anchor), score, class_id });
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 确定性的伪随机数，范围[0, 1)
+    fn random_values(seed: u64, count: usize) -> Vec<f32> {
+        let mut state = seed;
+        (0..count).map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 40) as f32 / (1u64 << 24) as f32
+        }).collect()
+    }
+    
+    #[test]
+    fn test_nms_matches_scalar() {
+        let values = random_values(7, 300 * 5);
+        let boxes: Vec<[f32; 4]> = values.chunks_exact(5).map(|v| {
+            let (x, y) = (v[0] * 600.0, v[1] * 400.0);
+            [x, y, x + 20.0 + v[2] * 60.0, y + 20.0 + v[3] * 60.0]
+        }).collect();
+        // 有相同的得分，检查顺序也一致
+        let scores: Vec<f32> = values.chunks_exact(5).map(|v| (v[4] * 20.0).round() / 20.0).collect();
+        
+        for threshold in [0.3, 0.5, 0.7] {
+            let kept = nms(&boxes, &scores, threshold);
+            assert_eq!(kept, nms_scalar(&boxes, &scores, threshold));
+            assert!(kept.len() < boxes.len());
+        }
+        assert!(nms(&[], &[], 0.5).is_empty());
+    }
+    
+    #[test]
+    fn test_sigmoid_and_softmax_match_scalar() {
+        let logits: Vec<f32> = random_values(11, 1000).iter().map(|v| (v - 0.5) * 40.0).collect();
+        
+        let (mut fast, mut reference) = (logits.clone(), logits.clone());
+        sigmoid(&mut fast);
+        sigmoid_scalar(&mut reference);
+        for (a, b) in fast.iter().zip(&reference) {
+            assert!((a - b).abs() <= 1e-6, "{} != {}", a, b);
+        }
+        
+        let (mut fast, mut reference) = (logits[..85].to_vec(), logits[..85].to_vec());
+        softmax(&mut fast);
+        softmax_scalar(&mut reference);
+        for (a, b) in fast.iter().zip(&reference) {
+            assert!((a - b).abs() <= 1e-6 * b.max(1e-3), "{} != {}", a, b);
+        }
+        assert!((fast.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+    }
+    
+    #[test]
+    fn test_decode_yolo_matches_scalar() {
+        let (num_classes, anchors) = (80, 500);
+        let mut output = random_values(3, (4 + num_classes) * anchors);
+        for value in &mut output[..4 * anchors] {
+            *value *= 640.0;
+        }
+        for value in &mut output[4 * anchors..] {
+            // 取到0.01，避免不同的logit经过sigmoid后相等
+            *value = ((*value - 0.9) * 3000.0).round() / 100.0;
+        }
+        
+        let candidates = decode_yolo(&output, num_classes, 0.6, true);
+        assert!(!candidates.is_empty());
+        assert_eq!(candidates, decode_yolo_scalar(&output, num_classes, 0.6, true));
+        
+        let mut probabilities = output.clone();
+        sigmoid_scalar(&mut probabilities[4 * anchors..]);
+        assert_eq!(decode_yolo(&probabilities, num_classes, 0.6, false), candidates);
+    }
+}
@@ -0,0 +1,192 @@
+//! CPU性能剖析
+//! 
+//! 打开 PerformanceConfig.profiling_enabled 后，可以在设备上采集本进程一段时间的CPU调用栈，
+//! 不需要重新编译。采样由pprof-rs在进程内完成（SIGPROF定时信号），Python服务和Rust扩展
+//! 运行在同一进程中，一次采样同时覆盖两边。结果折叠成每行 "帧;帧;...;帧 次数" 的文本，
+//! 同时生成SVG火焰图；折叠文本也可以交给speedscope查看
+//! 
+//! 采样需要启用profiling特性；启用tokio-console特性并以 RUSTFLAGS="--cfg tokio_unstable" 构建时，
+//! 打开profiling_enabled还会启动tokio-console的数据服务（默认监听127.0.0.1:6669）
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use log::info;
+
+use crate::config::PerformanceConfig;
+
+/// 同一时间只允许一次采样
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+/// 性能剖析错误
+#[derive(Debug, thiserror::Error)]
+pub enum ProfilingError {
+    #[error("性能剖析未启用（performance.profiling_enabled）")]
+    Disabled,
+    
+    #[error("采样时长必须在1到{max}秒之间: {seconds}")]
+    InvalidDuration { seconds: u64, max: u64 },
+    
+    #[error("已有一次性能剖析正在进行")]
+    Busy,
+    
+    #[error("构建时未启用profiling特性")]
+    Unavailable,
+    
+    #[error("采样失败: {0}")]
+    Sampling(String),
+}
+
+/// 一次CPU采样的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuProfile {
+    pub seconds: u64,
+    pub frequency_hz: u32,
+    pub samples: u64,
+    /// 折叠格式的调用栈，根在前，第一帧为线程名
+    pub folded: String,
+    /// SVG火焰图
+    pub flamegraph: String,
+}
+
+/// 采样期间占用CAPTURING，结束时释放
+struct CaptureGuard;
+
+impl CaptureGuard {
+    fn acquire() -> Result<Self, ProfilingError> {
+        CAPTURING
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| CaptureGuard)
+            .map_err(|_| ProfilingError::Busy)
+    }
+}
+
+impl Drop for CaptureGuard {
+    fn drop(&mut self) {
+        CAPTURING.store(false, Ordering::Release);
+    }
+}
+
+/// 采集本进程seconds秒的CPU调用栈，阻塞直到采样结束
+pub fn capture_cpu_profile(config: &PerformanceConfig, seconds: u64) -> Result<CpuProfile, ProfilingError> {
+    if !config.profiling_enabled {
+        return Err(ProfilingError::Disabled);
+    }
+    if seconds == 0 || seconds > config.profiling_max_seconds {
+        return Err(ProfilingError::InvalidDuration { seconds, max: config.profiling_max_seconds });
+    }
+    let _guard = CaptureGuard::acquire()?;
+    
+    info!("开始CPU性能剖析: {}秒, {}Hz", seconds, config.profiling_frequency_hz);
+    let (folded, samples, flamegraph) = sample(config.profiling_frequency_hz, seconds)?;
+    info!("CPU性能剖析完成: {}个样本", samples);
+    Ok(CpuProfile { seconds, frequency_hz: config.profiling_frequency_hz, samples, folded, flamegraph })
+}
+
+/// 用pprof采样，返回(折叠调用栈, 样本数, SVG火焰图)
+#[cfg(feature = "profiling")]
+fn sample(frequency_hz: u32, seconds: u64) -> Result<(String, u64, String), ProfilingError> {
+    let sampling_error = |e: pprof::Error| ProfilingError::Sampling(e.to_string());
+    
+    // 在libc和线程库内部中断时回溯可能死锁，跳过这些帧
+    let profiler = pprof::ProfilerGuardBuilder::default()
+        .frequency(frequency_hz as i32)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(sampling_error)?;
+    std::thread::sleep(std::time::Duration::from_secs(seconds));
+    let report = profiler.report().build().map_err(sampling_error)?;
+    drop(profiler);
+    
+    let (folded, samples) = fold_stacks(report.data.iter().map(|(frames, &count)| {
+        let names = frames.frames.iter().flat_map(|frame| frame.iter().map(|symbol| symbol.name()));
+        (frames.thread_name_or_id(), names.collect(), count.max(0) as u64)
+    }));
+    
+    let mut svg = Vec::new();
+    if samples > 0 {
+        report.flamegraph(&mut svg).map_err(sampling_error)?;
+    }
+    Ok((folded, samples, String::from_utf8_lossy(&svg).into_owned()))
+}
+
+#[cfg(not(feature = "profiling"))]
+fn sample(_frequency_hz: u32, _seconds: u64) -> Result<(String, u64, String), ProfilingError> {
+    Err(ProfilingError::Unavailable)
+}
+
+/// 把(线程名, 从叶到根的帧, 次数)折叠为每行 "线程;根帧;...;叶帧 次数"，相同调用栈合并并排序，
+/// 同时返回样本总数
+pub fn fold_stacks<I>(stacks: I) -> (String, u64)
+where
+    I: IntoIterator<Item = (String, Vec<String>, u64)>,
+{
+    let mut merged: BTreeMap<String, u64> = BTreeMap::new();
+    for (thread, frames, count) in stacks {
+        let stack: Vec<String> = std::iter::once(thread)
+            .chain(frames.into_iter().rev())
+            .map(|name| name.replace(';', ":"))
+            .collect();
+        *merged.entry(stack.join(";")).or_insert(0) += count;
+    }
+    
+    let samples = merged.values().sum();
+    let folded = merged.iter().map(|(stack, count)| format!("{} {}\n", stack, count)).collect();
+    (folded, samples)
+}
+
+/// profiling_enabled打开时启动tokio-console的数据服务，进程内只启动一次；返回是否已启动
+#[cfg(feature = "tokio-console")]
+pub fn init_tokio_console(config: &PerformanceConfig) -> bool {
+    static STARTED: std::sync::Once = std::sync::Once::new();
+    
+    if config.profiling_enabled {
+        STARTED.call_once(|| {
+            console_subscriber::init();
+            info!("tokio-console数据服务已启动");
+        });
+    }
+    STARTED.is_completed()
+}
+
+#[cfg(not(feature = "tokio-console"))]
+pub fn init_tokio_console(_config: &PerformanceConfig) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn stack(thread: &str, frames: &[&str], count: u64) -> (String, Vec<String>, u64) {
+        (thread.to_string(), frames.iter().map(|frame| frame.to_string()).collect(), count)
+    }
+    
+    #[test]
+    fn test_fold_stacks_merges_identical_stacks() {
+        let (folded, samples) = fold_stacks(vec![
+            stack("python3", &["read", "reachy_mini_rust::realtime::control_loop", "_PyEval_EvalFrameDefault"], 1),
+            stack("tokio-runtime-w", &["tokio::runtime::park"], 1),
+            stack("python3", &["read", "reachy_mini_rust::realtime::control_loop", "_PyEval_EvalFrameDefault"], 2),
+            stack("worker", &["<T as a;b>::f"], 1),
+        ]);
+        
+        assert_eq!(samples, 5);
+        assert_eq!(
+            folded,
+            "python3;_PyEval_EvalFrameDefault;reachy_mini_rust::realtime::control_loop;read 3\n\
+             tokio-runtime-w;tokio::runtime::park 1\n\
+             worker;<T as a:b>::f 1\n"
+        );
+    }
+    
+    #[test]
+    fn test_capture_requires_profiling_enabled() {
+        let config = PerformanceConfig::default();
+        assert!(matches!(capture_cpu_profile(&config, 1), Err(ProfilingError::Disabled)));
+        assert!(!init_tokio_console(&config));
+        
+        let config = PerformanceConfig { profiling_enabled: true, ..PerformanceConfig::default() };
+        assert!(matches!(capture_cpu_profile(&config, 0), Err(ProfilingError::InvalidDuration { .. })));
+    }
+}
@@ -0,0 +1,507 @@
+//! 通信协议版本
+//! 
+//! 前端、Python SDK和机器人各自独立升级，连接建立后先交换握手消息协商
+//! 协议版本：主版本号不同即不兼容，次版本号取双方较小者，新增的字段和
+//! 消息类型只在协商出的版本支持时才使用。消息统一用`type`字段标记类型，
+//! 未知的消息类型被识别为`Unknown`而不是解析失败，旧机器人可以忽略新消息。
+//!
+//! 协商出`topics`能力后，遥测按主题（关节、IMU、检测结果、日志、事件）推送：
+//! 客户端订阅需要显示的主题并可以为每个主题设置推送频率，未订阅的主题不发送。
+//!
+//! 同一网络中有多台机器人时，握手应答带机器人身份，主题消息带机器人UUID。
+//!
+//! 协商出`teleop`能力后客户端可以发送遥操作消息直接控制速度，见teleop模块。
+
+use crate::command_filter::ExternalCommand;
+use crate::common::current_timestamp;
+use crate::events::RobotEvent;
+use crate::teleop::{TeleopInput, TeleopStopReason};
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// 当前协议版本
+pub const PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::new(1, 0, 0);
+
+/// 仍然支持的最低协议版本
+pub const MIN_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::new(1, 0, 0);
+
+/// 本端支持的可选能力
+pub const CAPABILITIES: &[&str] = &["events", "time_sync", "trajectory", "topics", "teleop"];
+
+/// 主题推送频率上限（Hz）
+pub const MAX_TOPIC_RATE_HZ: f64 = 100.0;
+
+/// 语义化版本号，序列化为"主.次.修订"字符串
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProtocolVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl ProtocolVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self { major, minor, patch }
+    }
+    
+    /// 对方版本能否与本端通信
+    pub fn is_compatible(&self, other: &ProtocolVersion) -> bool {
+        self.major == other.major && *other >= MIN_PROTOCOL_VERSION
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.trim().split('.').collect();
+        if parts.len() != 3 {
+            return Err(anyhow::anyhow!("协议版本格式错误: '{}'，应为 主.次.修订", s));
+        }
+        
+        let parse = |part: &str| part.parse::<u16>()
+            .map_err(|_| anyhow::anyhow!("协议版本格式错误: '{}'", s));
+        Ok(Self::new(parse(parts[0])?, parse(parts[1])?, parse(parts[2])?))
+    }
+}
+
+impl Serialize for ProtocolVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtocolVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 客户端握手消息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientHello {
+    pub protocol_version: ProtocolVersion,
+    /// 客户端名称和版本，仅用于日志
+    #[serde(default)]
+    pub client: String,
+    /// 客户端支持的可选能力
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+/// 服务端握手应答
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerHello {
+    /// 协商出的协议版本，之后双方都按此版本收发消息
+    pub protocol_version: ProtocolVersion,
+    /// 服务端支持的最高协议版本
+    pub server_protocol_version: ProtocolVersion,
+    pub server_version: String,
+    /// 双方都支持的能力
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// 机器人身份，旧版本服务端不发送
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub robot: Option<RobotIdentity>,
+}
+
+/// 机器人身份：UUID区分机器人，名称和分组供人阅读和按组筛选
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RobotIdentity {
+    pub uuid: String,
+    pub name: String,
+    pub group: String,
+}
+
+/// 按客户端握手协商协议版本，主版本不同或低于最低支持版本时拒绝
+pub fn negotiate(hello: &ClientHello) -> Result<ServerHello> {
+    let client = hello.protocol_version;
+    if !PROTOCOL_VERSION.is_compatible(&client) {
+        return Err(anyhow::anyhow!(
+            "协议版本 {} 不兼容，支持 {} 到 {}.x",
+            client, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION.major
+        ));
+    }
+    
+    let capabilities = hello.capabilities.iter()
+        .filter(|c| CAPABILITIES.contains(&c.as_str()))
+        .cloned()
+        .collect();
+    
+    Ok(ServerHello {
+        protocol_version: client.min(PROTOCOL_VERSION),
+        server_protocol_version: PROTOCOL_VERSION,
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+        capabilities,
+        robot: None,
+    })
+}
+
+/// 推送主题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    Joints,
+    Imu,
+    Detections,
+    Logs,
+    Events,
+    /// 更高版本客户端订阅的、本端没有的主题
+    #[serde(other)]
+    Unknown,
+}
+
+impl Topic {
+    /// 未指定频率时的默认推送频率，None表示每条消息都推送
+    pub fn default_rate_hz(self) -> Option<f64> {
+        match self {
+            Topic::Joints | Topic::Imu => Some(30.0),
+            Topic::Detections => Some(10.0),
+            Topic::Logs | Topic::Events | Topic::Unknown => None,
+        }
+    }
+}
+
+/// 客户端发往机器人的消息
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Hello(ClientHello),
+    Command {
+        command: ExternalCommand,
+    },
+    Ping {
+        #[serde(default)]
+        timestamp: Option<u64>,
+    },
+    /// 订阅主题，rate_hz为空时使用主题的默认频率；重复订阅只修改频率
+    Subscribe {
+        topic: Topic,
+        #[serde(default)]
+        rate_hz: Option<f64>,
+    },
+    Unsubscribe {
+        topic: Topic,
+    },
+    /// 遥操作输入，同时是心跳
+    Teleop(TeleopInput),
+    /// 更高版本客户端发送的、本端不认识的消息类型
+    #[serde(other)]
+    Unknown,
+}
+
+/// 机器人发往客户端的消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Hello(ServerHello),
+    Event {
+        source: String,
+        event: RobotEvent,
+    },
+    Pong {
+        timestamp: u64,
+    },
+    /// 订阅或修改频率成功，rate_hz为实际使用的频率
+    Subscribed {
+        topic: Topic,
+        rate_hz: Option<f64>,
+    },
+    Unsubscribed {
+        topic: Topic,
+    },
+    /// 主题数据，robot为发送数据的机器人UUID
+    Topic {
+        topic: Topic,
+        timestamp: u64,
+        data: serde_json::Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        robot: Option<String>,
+    },
+    /// 遥操作速度归零：松开死人开关或心跳超时
+    TeleopStopped {
+        reason: TeleopStopReason,
+    },
+    Error {
+        message: String,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+/// 一个订阅：推送频率和上次推送时刻
+#[derive(Debug, Clone)]
+struct Subscription {
+    min_interval: Option<Duration>,
+    last_sent: Option<Instant>,
+}
+
+/// 一条连接订阅的主题，按各主题的频率丢弃过密的消息
+#[derive(Debug, Default)]
+pub struct TopicSubscriptions {
+    topics: HashMap<Topic, Subscription>,
+}
+
+impl TopicSubscriptions {
+    /// 订阅主题或修改频率，返回实际使用的频率
+    pub fn subscribe(&mut self, topic: Topic, rate_hz: Option<f64>) -> Result<Option<f64>> {
+        if topic == Topic::Unknown {
+            return Err(anyhow::anyhow!("未知的主题"));
+        }
+        
+        let rate_hz = rate_hz.or(topic.default_rate_hz());
+        if let Some(rate) = rate_hz {
+            if !(rate > 0.0 && rate <= MAX_TOPIC_RATE_HZ) {
+                return Err(anyhow::anyhow!("推送频率必须在0到{}Hz之间", MAX_TOPIC_RATE_HZ));
+            }
+        }
+        
+        let min_interval = rate_hz.map(|rate| Duration::from_secs_f64(1.0 / rate));
+        let last_sent = self.topics.get(&topic).and_then(|subscription| subscription.last_sent);
+        self.topics.insert(topic, Subscription { min_interval, last_sent });
+        Ok(rate_hz)
+    }
+    
+    /// 取消订阅，没有订阅时返回false
+    pub fn unsubscribe(&mut self, topic: Topic) -> bool {
+        self.topics.remove(&topic).is_some()
+    }
+    
+    pub fn is_subscribed(&self, topic: Topic) -> bool {
+        self.topics.contains_key(&topic)
+    }
+    
+    /// 判断now时刻是否推送该主题的一条消息，推送时记录时刻
+    pub fn should_send(&mut self, topic: Topic, now: Instant) -> bool {
+        let Some(subscription) = self.topics.get_mut(&topic) else {
+            return false;
+        };
+        
+        let due = match (subscription.min_interval, subscription.last_sent) {
+            (Some(interval), Some(last)) => now.saturating_duration_since(last) >= interval,
+            _ => true,
+        };
+        if due {
+            subscription.last_sent = Some(now);
+        }
+        due
+    }
+}
+
+/// 一条连接的协议状态：握手之前只接受握手消息
+#[derive(Debug, Default)]
+pub struct ProtocolSession {
+    negotiated: Option<ServerHello>,
+    subscriptions: TopicSubscriptions,
+    robot: Option<RobotIdentity>,
+}
+
+impl ProtocolSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 在握手应答和主题消息中带上机器人身份
+    pub fn with_robot(mut self, robot: RobotIdentity) -> Self {
+        self.robot = Some(robot);
+        self
+    }
+    
+    /// 协商出的协议版本，握手前为None
+    pub fn version(&self) -> Option<ProtocolVersion> {
+        self.negotiated.as_ref().map(|hello| hello.protocol_version)
+    }
+    
+    /// 双方都支持该能力
+    pub fn supports(&self, capability: &str) -> bool {
+        self.negotiated.as_ref().is_some_and(|hello| hello.capabilities.iter().any(|c| c == capability))
+    }
+    
+    /// 握手：协商成功后记录结果并返回应答
+    pub fn handshake(&mut self, hello: &ClientHello) -> Result<ServerHello> {
+        let mut reply = negotiate(hello)?;
+        reply.robot = self.robot.clone();
+        self.negotiated = Some(reply.clone());
+        Ok(reply)
+    }
+    
+    /// 处理订阅和取消订阅消息，返回应答；其他消息返回None
+    pub fn handle_subscription(&mut self, message: &ClientMessage) -> Option<ServerMessage> {
+        if !matches!(message, ClientMessage::Subscribe { .. } | ClientMessage::Unsubscribe { .. }) {
+            return None;
+        }
+        
+        if !self.supports("topics") {
+            return Some(ServerMessage::Error { message: "没有协商topics能力，不能订阅主题".to_string() });
+        }
+        
+        let reply = match message {
+            ClientMessage::Subscribe { topic, rate_hz } => match self.subscriptions.subscribe(*topic, *rate_hz) {
+                Ok(rate_hz) => ServerMessage::Subscribed { topic: *topic, rate_hz },
+                Err(e) => ServerMessage::Error { message: e.to_string() },
+            },
+            ClientMessage::Unsubscribe { topic } => {
+                self.subscriptions.unsubscribe(*topic);
+                ServerMessage::Unsubscribed { topic: *topic }
+            }
+            _ => return None,
+        };
+        Some(reply)
+    }
+    
+    /// 按订阅和频率决定是否推送一条主题数据，需要推送时返回消息
+    pub fn publish(&mut self, topic: Topic, data: &serde_json::Value, now: Instant) -> Option<ServerMessage> {
+        self.subscriptions.should_send(topic, now).then(|| ServerMessage::Topic {
+            topic,
+            timestamp: current_timestamp(),
+            data: data.clone(),
+            robot: self.robot.as_ref().map(|robot| robot.uuid.clone()),
+        })
+    }
+    
+    /// 解析一条客户端消息，握手前收到其他消息返回错误
+    pub fn parse(&self, json: &str) -> Result<ClientMessage> {
+        let message: ClientMessage = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("消息格式错误: {}", e))?;
+        
+        if self.negotiated.is_none() && !matches!(message, ClientMessage::Hello(_)) {
+            return Err(anyhow::anyhow!("请先发送hello消息协商协议版本"));
+        }
+        
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_filter::ExternalCommandType;
+    
+    fn hello(version: &str) -> ClientHello {
+        ClientHello {
+            protocol_version: version.parse().unwrap(),
+            client: "test".to_string(),
+            capabilities: vec!["events".to_string(), "holograms".to_string()],
+        }
+    }
+    
+    #[test]
+    fn test_version_parse_and_order() {
+        let version: ProtocolVersion = "1.2.3".parse().unwrap();
+        assert_eq!(version, ProtocolVersion::new(1, 2, 3));
+        assert_eq!(version.to_string(), "1.2.3");
+        assert!(ProtocolVersion::new(1, 10, 0) > ProtocolVersion::new(1, 9, 9));
+        
+        assert!("1.2".parse::<ProtocolVersion>().is_err());
+        assert!("1.x.0".parse::<ProtocolVersion>().is_err());
+        assert_eq!(serde_json::to_value(version).unwrap(), serde_json::json!("1.2.3"));
+    }
+    
+    #[test]
+    fn test_negotiation_compatibility() {
+        // 更新的客户端降级到本端版本，能力取交集
+        let newer = format!("{}.{}.0", PROTOCOL_VERSION.major, PROTOCOL_VERSION.minor + 1);
+        let reply = negotiate(&hello(&newer)).unwrap();
+        assert_eq!(reply.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(reply.capabilities, vec!["events".to_string()]);
+        
+        // 主版本不同时拒绝
+        let next_major = format!("{}.0.0", PROTOCOL_VERSION.major + 1);
+        assert!(negotiate(&hello(&next_major)).is_err());
+        assert!(negotiate(&hello("0.9.0")).is_err());
+    }
+    
+    #[test]
+    fn test_session_requires_handshake() {
+        let mut session = ProtocolSession::new();
+        let command = r#"{"type": "command", "command": {"joint": "head_pan", "type": "position", "position": 0.1}}"#;
+        assert!(session.parse(command).is_err());
+        
+        let ClientMessage::Hello(client_hello) = session.parse(r#"{"type": "hello", "protocol_version": "1.0.0"}"#).unwrap() else {
+            panic!("应解析为握手消息");
+        };
+        session.handshake(&client_hello).unwrap();
+        assert_eq!(session.version(), Some(ProtocolVersion::new(1, 0, 0)));
+        assert!(!session.supports("events"));
+        
+        let ClientMessage::Command { command } = session.parse(command).unwrap() else {
+            panic!("应解析为命令消息");
+        };
+        assert_eq!(command.command_type, ExternalCommandType::Position);
+        
+        // 新版本客户端的未知消息类型不会导致解析失败
+        assert!(matches!(session.parse(r#"{"type": "hologram", "color": "blue"}"#).unwrap(), ClientMessage::Unknown));
+    }
+    
+    #[test]
+    fn test_server_message_wire_format() {
+        let message = ServerMessage::Event {
+            source: "vision".to_string(),
+            event: RobotEvent::FaceDetected { confidence: 0.5, position: None },
+        };
+        let value = serde_json::to_value(&message).unwrap();
+        assert_eq!(value["type"], "event");
+        assert_eq!(value["event"]["type"], "FaceDetected");
+        
+        // 旧客户端收到新增的消息类型时同样能够跳过
+        let parsed: ServerMessage = serde_json::from_str(r#"{"type": "telemetry_v2", "data": []}"#).unwrap();
+        assert!(matches!(parsed, ServerMessage::Unknown));
+    }
+    
+    #[test]
+    fn test_topic_subscription_rates() {
+        let robot = RobotIdentity {
+            uuid: "5f0c2a4e-8b1d-4c3a-9e6f-2d7b8a9c0e1f".to_string(),
+            name: "Reachy Mini".to_string(),
+            group: "lab".to_string(),
+        };
+        let mut session = ProtocolSession::new().with_robot(robot.clone());
+        let mut client_hello = hello("1.0.0");
+        client_hello.capabilities.push("topics".to_string());
+        assert_eq!(session.handshake(&client_hello).unwrap().robot, Some(robot.clone()));
+        
+        let subscribe = session.parse(r#"{"type": "subscribe", "topic": "joints", "rate_hz": 10}"#).unwrap();
+        assert!(matches!(session.handle_subscription(&subscribe), Some(ServerMessage::Subscribed { topic: Topic::Joints, rate_hz: Some(_) })));
+        let events = session.parse(r#"{"type": "subscribe", "topic": "events"}"#).unwrap();
+        session.handle_subscription(&events).unwrap();
+        let too_fast = session.parse(r#"{"type": "subscribe", "topic": "imu", "rate_hz": 500}"#).unwrap();
+        assert!(matches!(session.handle_subscription(&too_fast), Some(ServerMessage::Error { .. })));
+        let unknown = session.parse(r#"{"type": "subscribe", "topic": "holograms"}"#).unwrap();
+        assert!(matches!(session.handle_subscription(&unknown), Some(ServerMessage::Error { .. })));
+        
+        // 10Hz的关节主题100ms内只推送一次，事件主题不限频，未订阅的主题不推送
+        let data = serde_json::json!({"head_pan": 0.1});
+        let start = Instant::now();
+        assert!(session.publish(Topic::Joints, &data, start).is_some());
+        assert!(session.publish(Topic::Joints, &data, start + Duration::from_millis(50)).is_none());
+        assert!(session.publish(Topic::Joints, &data, start + Duration::from_millis(100)).is_some());
+        assert!(session.publish(Topic::Events, &data, start).is_some());
+        assert!(session.publish(Topic::Events, &data, start).is_some());
+        assert!(session.publish(Topic::Imu, &data, start).is_none());
+        
+        let value = serde_json::to_value(session.publish(Topic::Events, &data, start).unwrap()).unwrap();
+        assert_eq!(value["type"], "topic");
+        assert_eq!(value["topic"], "events");
+        assert_eq!(value["robot"], robot.uuid.as_str());
+        
+        let unsubscribe = session.parse(r#"{"type": "unsubscribe", "topic": "joints"}"#).unwrap();
+        session.handle_subscription(&unsubscribe).unwrap();
+        assert!(session.publish(Topic::Joints, &data, start + Duration::from_secs(1)).is_none());
+        
+        // 没有协商topics能力时拒绝订阅
+        let mut legacy = ProtocolSession::new();
+        legacy.handshake(&hello("1.0.0")).unwrap();
+        assert!(matches!(legacy.handle_subscription(&subscribe), Some(ServerMessage::Error { .. })));
+    }
+}
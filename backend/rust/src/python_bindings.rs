@@ -10,61 +10,81 @@ use pyo3::types::PyModule;
 use pyo3::Bound;
 
 #[cfg(feature = "python-bindings")]
-use crate::{ReachyMiniSystem, Config, SystemStatus};
+use crate::{ReachyMiniSystem, Config};
+#[cfg(feature = "python-bindings")]
+use crate::hardware::{HardwareCommand, HardwareConfig, HardwareInterface};
 
 #[cfg(feature = "python-bindings")]
 #[pyclass]
-struct PyReachyMiniSystem {
+pub struct PyReachyMiniSystem {
+    // 挂接的硬件接口的通信和心跳任务在该运行时上持续运行
+    runtime: tokio::runtime::Runtime,
     inner: ReachyMiniSystem,
 }
 
 #[cfg(feature = "python-bindings")]
 #[pymethods]
 impl PyReachyMiniSystem {
+    /// 给出hardware_config_json时挂接硬件接口，停止系统时先停靠
     #[new]
-    fn new(name: String, version: String) -> PyResult<Self> {
+    #[pyo3(signature = (name, version, hardware_config_json=None))]
+    fn new(name: String, version: String, hardware_config_json: Option<String>) -> PyResult<Self> {
         let config = Config { name, version };
         
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        let inner = rt.block_on(async {
+        let runtime = tokio::runtime::Runtime::new()
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        let inner = runtime.block_on(async {
             ReachyMiniSystem::new(config).await
         }).map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
         
-        Ok(Self { inner })
+        if let Some(json) = hardware_config_json {
+            let hardware = runtime.block_on(HardwareInterface::new(hardware_config(Some(json))?))
+                .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+            runtime.block_on(inner.attach_hardware(hardware));
+        }
+        
+        Ok(Self { runtime, inner })
     }
     
-    fn start(&self) -> PyResult<()> {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        rt.block_on(async {
-            self.inner.start().await
-        }).map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
-        
-        Ok(())
+    fn start(&self, py: Python<'_>) -> PyResult<()> {
+        // 启动和停止可能等待硬件，期间释放GIL，asyncio可以在线程池中调用
+        let Self { runtime, inner } = self;
+        py.allow_threads(|| runtime.block_on(inner.start()))
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
     }
     
-    fn stop(&self) -> PyResult<()> {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        rt.block_on(async {
-            self.inner.stop().await
-        }).map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
-        
-        Ok(())
+    /// 停止系统，挂接了硬件接口时先停靠
+    fn stop(&self, py: Python<'_>) -> PyResult<()> {
+        let Self { runtime, inner } = self;
+        py.allow_threads(|| runtime.block_on(inner.stop()))
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+    
+    /// `with`语句进入时启动系统
+    fn __enter__(slf: PyRef<'_, Self>) -> PyResult<PyRef<'_, Self>> {
+        slf.start(slf.py())?;
+        Ok(slf)
+    }
+    
+    /// 退出时总是停止系统，不吞掉with块中的异常
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: Option<PyObject>,
+        _exc_value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        self.stop(py)?;
+        Ok(false)
     }
     
     fn is_running(&self) -> PyResult<bool> {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        let result = rt.block_on(async {
-            self.inner.is_running().await
-        });
-        
-        Ok(result)
+        Ok(self.runtime.block_on(self.inner.is_running()))
     }
     
     fn get_status(&self) -> PyResult<String> {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        let status = rt.block_on(async {
-            self.inner.get_status().await
-        }).map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        let status = self.runtime.block_on(self.inner.get_status())
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
         
         let json = serde_json::to_string(&status)
             .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
@@ -73,13 +93,216 @@ impl PyReachyMiniSystem {
     }
 }
 
+/// 把部分配置合并到默认配置上，Python端只需要给出要修改的字段
+#[cfg(feature = "python-bindings")]
+fn merge_json(base: &mut serde_json::Value, patch: serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        },
+        (base, patch) => *base = patch,
+    }
+}
+
+/// 把部分硬件配置合并到默认配置上
+#[cfg(feature = "python-bindings")]
+fn hardware_config(config_json: Option<String>) -> PyResult<HardwareConfig> {
+    let mut config = serde_json::to_value(HardwareConfig::default())
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    if let Some(json) = config_json {
+        let patch = serde_json::from_str(&json)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        merge_json(&mut config, patch);
+    }
+    serde_json::from_value(config)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+}
+
+/// 硬件接口的底层访问，用于装配调试；舵机命令与控制层经过同一条处理路径，
+/// 位置和速度限制同样生效
+#[cfg(feature = "python-bindings")]
+#[pyclass]
+pub struct PyHardwareInterface {
+    // 通信和心跳任务在该运行时上持续运行
+    runtime: tokio::runtime::Runtime,
+    inner: HardwareInterface,
+}
+
+#[cfg(feature = "python-bindings")]
+#[pymethods]
+impl PyHardwareInterface {
+    #[new]
+    #[pyo3(signature = (config_json=None))]
+    fn new(config_json: Option<String>) -> PyResult<Self> {
+        let config = hardware_config(config_json)?;
+        
+        let runtime = tokio::runtime::Runtime::new()
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        let inner = runtime.block_on(HardwareInterface::new(config))
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        
+        Ok(Self { runtime, inner })
+    }
+    
+    fn start(&mut self, py: Python<'_>) -> PyResult<()> {
+        let Self { runtime, inner } = self;
+        py.allow_threads(|| runtime.block_on(inner.start()))
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+    
+    fn stop(&mut self, py: Python<'_>) -> PyResult<()> {
+        let Self { runtime, inner } = self;
+        py.allow_threads(|| runtime.block_on(inner.stop()))
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+    
+    fn is_running(&self, py: Python<'_>) -> bool {
+        let Self { runtime, inner } = self;
+        py.allow_threads(|| runtime.block_on(inner.is_running()))
+    }
+    
+    /// 发送JSON格式的硬件命令，如 {"type": "servo_move", "id": 1, "position": 100}，返回关联ID
+    #[pyo3(signature = (command_json, correlation_id=None))]
+    fn send_command(&self, py: Python<'_>, command_json: String, correlation_id: Option<String>) -> PyResult<String> {
+        let command: HardwareCommand = serde_json::from_str(&command_json)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("命令格式错误: {}", e)))?;
+        let Self { runtime, inner } = self;
+        py.allow_threads(|| runtime.block_on(inner.send_correlated(command, correlation_id)))
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+    
+    fn get_servo_status(&self, id: u8) -> PyResult<Option<String>> {
+        let status = self.runtime.block_on(self.inner.get_servo_status(id))
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        
+        status.map(|status| serde_json::to_string(&status))
+            .transpose()
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+    
+    fn set_led(&self, py: Python<'_>, pin: u8, state: bool) -> PyResult<()> {
+        let Self { runtime, inner } = self;
+        py.allow_threads(|| runtime.block_on(inner.set_led(pin, state)))
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+    
+    #[pyo3(signature = (first=0, last=252))]
+    fn scan_bus(&self, py: Python<'_>, first: u8, last: u8) -> PyResult<String> {
+        let Self { runtime, inner } = self;
+        let servos = py.allow_threads(|| runtime.block_on(inner.scan_bus(first..=last)))
+            .map_err(|e| pyo3::exceptions::PyOSError::new_err(e.to_string()))?;
+        
+        serde_json::to_string(&servos)
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+    
+    fn get_status(&self, py: Python<'_>) -> PyResult<String> {
+        let Self { runtime, inner } = self;
+        let status = py.allow_threads(|| runtime.block_on(inner.get_status()))
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        
+        serde_json::to_string(&status)
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+}
+
+/// 不经过实时控制器执行的运动命令（如Python端的网页遥操作）的准入：角色检查、仲裁和审计，
+/// 规则和审计文件与实时控制器相同
+#[cfg(feature = "python-bindings")]
+#[pyclass]
+pub struct PyCommandGate {
+    inner: std::sync::Mutex<crate::audit::CommandGate>,
+}
+
+#[cfg(feature = "python-bindings")]
+#[pymethods]
+impl PyCommandGate {
+    #[new]
+    #[pyo3(signature = (config_json=None))]
+    fn new(config_json: Option<String>) -> PyResult<Self> {
+        use crate::arbiter::ArbiterConfig;
+        use crate::audit::{AuditConfig, CommandGate};
+        
+        let value_error = |e: String| pyo3::exceptions::PyValueError::new_err(e);
+        let mut config = serde_json::json!({ "arbitration": ArbiterConfig::default(), "audit": AuditConfig::default() });
+        if let Some(json) = config_json {
+            merge_json(&mut config, serde_json::from_str(&json).map_err(|e| value_error(e.to_string()))?);
+        }
+        let arbitration: ArbiterConfig = serde_json::from_value(config["arbitration"].take())
+            .map_err(|e| value_error(format!("仲裁配置格式错误: {}", e)))?;
+        let audit: AuditConfig = serde_json::from_value(config["audit"].take())
+            .map_err(|e| value_error(format!("审计配置格式错误: {}", e)))?;
+        
+        let gate = CommandGate::new(arbitration, audit).map_err(|e| value_error(e.to_string()))?;
+        Ok(Self { inner: std::sync::Mutex::new(gate) })
+    }
+    
+    /// 提交一条命令，origin如 {"source": "Teleop", "client_id": "...", "role": "operator"}
+    #[pyo3(signature = (origin_json, payload_json, emergency=false))]
+    fn submit(&self, py: Python<'_>, origin_json: &str, payload_json: &str, emergency: bool) -> PyResult<()> {
+        let origin: crate::arbiter::CommandOrigin = serde_json::from_str(origin_json)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("命令来源格式错误: {}", e)))?;
+        // 与外部命令一样，审计日志保存原始内容
+        let payload = serde_json::from_str(payload_json)
+            .unwrap_or_else(|_| serde_json::Value::String(payload_json.to_string()));
+        
+        py.allow_threads(|| {
+            let mut gate = self.inner.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            gate.submit(&origin, payload, emergency)
+        })
+        .map_err(|e| pyo3::exceptions::PyPermissionError::new_err(e.to_string()))
+    }
+}
+
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 fn init_logging() -> PyResult<()> {
-    crate::init_logging();
+    crate::init_logging()
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    
+    // 配置中打开了性能剖析时同时启动tokio-console数据服务（需要tokio-console特性）
+    crate::config::init_global_config()
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    if let Ok(config) = crate::config::get_global_config() {
+        crate::profiling::init_tokio_console(&config.performance);
+    }
     Ok(())
 }
 
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn get_log_filter() -> String {
+    crate::logging::log_filter()
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn set_log_filter(spec: String) -> PyResult<()> {
+    crate::logging::set_log_filter(&spec)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (level, module=None))]
+fn set_log_level(level: String, module: Option<String>) -> PyResult<()> {
+    crate::logging::set_log_level(module.as_deref(), &level)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn clear_log_level(module: String) -> bool {
+    crate::logging::clear_log_level(&module)
+}
+
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 fn get_system_info() -> PyResult<String> {
@@ -94,6 +317,9 @@ fn get_system_info() -> PyResult<String> {
             "async-runtime",
             "logging"
         ],
+        "hardware": crate::hardware_identity(),
+        "protocol_version": crate::protocol::PROTOCOL_VERSION,
+        "log_filter": crate::logging::log_filter(),
         "timestamp": chrono::Utc::now().to_rfc3339()
     });
     
@@ -126,13 +352,310 @@ fn validate_config(config_json: String) -> PyResult<bool> {
     }
 }
 
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn negotiate_protocol(hello_json: String) -> PyResult<String> {
+    use crate::protocol::{negotiate, ClientHello};
+    
+    let hello: ClientHello = serde_json::from_str(&hello_json)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let reply = negotiate(&hello)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    
+    serde_json::to_string(&reply)
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (proposed_json, active_json=None))]
+fn dry_run_config(proposed_json: String, active_json: Option<String>) -> PyResult<String> {
+    use crate::config::{get_global_config, Config};
+    
+    let parse = |json: &str| serde_json::from_str::<Config>(json)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("配置格式错误: {}", e)));
+    
+    let proposed = parse(&proposed_json)?;
+    // 未指定当前配置时与全局配置比较，全局配置未初始化时与默认配置比较
+    let active = match active_json {
+        Some(json) => parse(&json)?,
+        None => get_global_config().cloned().unwrap_or_default(),
+    };
+    
+    serde_json::to_string(&crate::config_diff::dry_run(&active, &proposed))
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+}
+
+/// 按全局配置（未初始化时为默认配置）中的关节集合和坐标变换配置创建注视控制器
+#[cfg(feature = "python-bindings")]
+fn gaze_controller() -> PyResult<crate::gaze::GazeController> {
+    use crate::gaze::{GazeConfig, GazeController};
+    
+    let config = crate::config::get_global_config().cloned().unwrap_or_default();
+    GazeController::new(GazeConfig::default(), &config.joints, &config.transforms)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+}
+
+/// 补上JSON对象中缺少的字段（只补顶层）
+#[cfg(feature = "python-bindings")]
+fn with_defaults(json: &str, defaults: serde_json::Value) -> PyResult<serde_json::Value> {
+    let mut value: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("JSON格式错误: {}", e)))?;
+    let object = value.as_object_mut()
+        .ok_or_else(|| pyo3::exceptions::PyValueError::new_err("需要JSON对象"))?;
+    if let serde_json::Value::Object(defaults) = defaults {
+        for (key, default) in defaults {
+            object.entry(key).or_insert(default);
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn build_motion_command(command_json: String) -> PyResult<String> {
+    use crate::realtime::MotionCommand;
+    
+    let value_error = |e: String| pyo3::exceptions::PyValueError::new_err(e);
+    let value = with_defaults(&command_json, serde_json::json!({ "timestamp": crate::common::current_timestamp() }))?;
+    let command: MotionCommand = serde_json::from_value(value).map_err(|e| value_error(format!("运动命令格式错误: {}", e)))?;
+    command.validate().map_err(|e| value_error(e.to_string()))?;
+    serde_json::to_string(&command).map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+}
+
+#[cfg(all(feature = "python-bindings", feature = "ai"))]
+#[pyfunction]
+fn build_inference_request(request_json: String) -> PyResult<String> {
+    use crate::ai::{InferenceOptions, InferenceRequest};
+    
+    let value_error = |e: String| pyo3::exceptions::PyValueError::new_err(e);
+    let defaults = serde_json::json!({
+        "request_id": crate::common::new_id(),
+        "timestamp": crate::common::current_timestamp(),
+        "options": InferenceOptions::default(),
+    });
+    let value = with_defaults(&request_json, defaults)?;
+    let request: InferenceRequest = serde_json::from_value(value).map_err(|e| value_error(format!("推理请求格式错误: {}", e)))?;
+    request.validate().map_err(|e| value_error(e.to_string()))?;
+    serde_json::to_string(&request).map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+}
+
+/// 加载ONNX模型读取其输入输出，并与给出的输入形状和输出名称比较
+#[cfg(all(feature = "python-bindings", feature = "vision"))]
+#[pyfunction]
+#[pyo3(signature = (path, input_shape=None, output_names=None))]
+fn inspect_model(py: Python<'_>, path: String, input_shape: Option<Vec<i64>>, output_names: Option<Vec<String>>) -> PyResult<String> {
+    use crate::model_signature::ModelSignature;
+    
+    let path = std::path::PathBuf::from(path);
+    if !path.exists() {
+        return Err(pyo3::exceptions::PyFileNotFoundError::new_err(format!("模型文件不存在: {}", path.display())));
+    }
+    
+    let signature = py.allow_threads(|| ModelSignature::read_onnx(&path))
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    let mismatches = match &input_shape {
+        Some(input_shape) => signature.mismatches(input_shape, output_names.as_deref().unwrap_or_default()),
+        None => Vec::new(),
+    };
+    
+    let result = serde_json::json!({
+        "inputs": signature.inputs,
+        "outputs": signature.outputs,
+        "mismatches": mismatches,
+    });
+    Ok(result.to_string())
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn solve_gaze(x: f64, y: f64, z: f64) -> PyResult<String> {
+    use serde_json::json;
+    
+    let gaze = gaze_controller()?;
+    let result = match gaze.try_look_at_point(&crate::common::Vector3::new(x, y, z), &std::collections::HashMap::new()) {
+        Ok(targets) => json!({ "reachable": true, "targets": targets }),
+        Err(unreachable) => json!({
+            "reachable": false,
+            "message": unreachable.to_string(),
+            "unreachable": unreachable,
+        }),
+    };
+    
+    Ok(result.to_string())
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (radius=1.0, samples=16))]
+fn gaze_workspace(radius: f64, samples: usize) -> PyResult<String> {
+    serde_json::to_string(&gaze_controller()?.workspace(radius, samples))
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (format="urdf", dynamics_json=None))]
+fn export_robot_model(format: &str, dynamics_json: Option<String>) -> PyResult<String> {
+    use crate::dynamics::DynamicsParams;
+    use crate::robot_model::{ModelFormat, RobotModel, RobotModelConfig};
+    
+    let value_error = |e: String| pyo3::exceptions::PyValueError::new_err(e);
+    let format: ModelFormat = format.parse().map_err(|e: anyhow::Error| value_error(e.to_string()))?;
+    let dynamics: std::collections::HashMap<String, DynamicsParams> = match dynamics_json {
+        Some(json) => serde_json::from_str(&json).map_err(|e| value_error(format!("动力学参数格式错误: {}", e)))?,
+        None => Default::default(),
+    };
+    let config = crate::config::get_global_config().cloned().unwrap_or_default();
+    
+    let mut model = RobotModel::build(&config.joints, &config.transforms, &RobotModelConfig::default())
+        .map_err(|e| value_error(e.to_string()))?;
+    model.apply_dynamics(&dynamics);
+    Ok(model.export(format))
+}
+
+#[cfg(feature = "python-bindings")]
+fn pack_store() -> PyResult<crate::behavior_pack::PackStore> {
+    let config = crate::config::get_global_config().map(|c| c.behavior_packs.clone()).unwrap_or_default();
+    crate::behavior_pack::PackStore::new(config)
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+}
+
+#[cfg(feature = "python-bindings")]
+fn pack_json<T: serde::Serialize>(result: anyhow::Result<T>) -> PyResult<String> {
+    let value = result.map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    serde_json::to_string(&value).map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn install_behavior_pack(py: Python<'_>, path: String) -> PyResult<String> {
+    let store = pack_store()?;
+    pack_json(py.allow_threads(|| store.install_file(std::path::Path::new(&path))))
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn inspect_behavior_pack(py: Python<'_>, path: String) -> PyResult<String> {
+    let store = pack_store()?;
+    pack_json(py.allow_threads(|| store.inspect_file(std::path::Path::new(&path))))
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn list_behavior_packs() -> PyResult<String> {
+    pack_json(pack_store()?.list())
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn set_behavior_pack_enabled(pack_id: &str, enabled: bool) -> PyResult<String> {
+    pack_json(pack_store()?.set_enabled(pack_id, enabled))
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+fn uninstall_behavior_pack(pack_id: &str) -> PyResult<()> {
+    pack_store()?.uninstall(pack_id)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+}
+
+/// 按Rust端配置文件中的performance段采集本进程的CPU调用栈，采样期间释放GIL
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (seconds=10))]
+fn capture_cpu_profile(py: Python<'_>, seconds: u64) -> PyResult<String> {
+    use crate::profiling::ProfilingError;
+    
+    crate::config::init_global_config()
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    let performance = crate::config::get_global_config().map(|c| c.performance.clone()).unwrap_or_default();
+    let profile = py.allow_threads(|| crate::profiling::capture_cpu_profile(&performance, seconds))
+        .map_err(|e| match e {
+            ProfilingError::Disabled => pyo3::exceptions::PyPermissionError::new_err(e.to_string()),
+            ProfilingError::InvalidDuration { .. } => pyo3::exceptions::PyValueError::new_err(e.to_string()),
+            ProfilingError::Busy | ProfilingError::Unavailable => pyo3::exceptions::PyRuntimeError::new_err(e.to_string()),
+            ProfilingError::Sampling(_) => pyo3::exceptions::PyOSError::new_err(e.to_string()),
+        })?;
+    
+    serde_json::to_string(&profile)
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+}
+
+/// 按采样率展开轨迹JSON（格式见trajectory_file模块）
+#[cfg(feature = "python-bindings")]
+fn sample_trajectory_json(trajectory_json: &str, sample_rate: f64) -> PyResult<crate::trajectory_plot::TrajectoryPlot> {
+    use crate::trajectory_file::TrajectoryFile;
+    use crate::trajectory_plot::{sample_trajectory, PlotOptions};
+    
+    let trajectory = TrajectoryFile::from_json(trajectory_json)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let options = PlotOptions { sample_rate, ..PlotOptions::default() };
+    
+    let transforms = crate::config::get_global_config().map(|c| c.transforms.clone()).unwrap_or_default();
+    sample_trajectory(&trajectory, &transforms, &options)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (trajectory_json, sample_rate=50.0))]
+fn sample_trajectory(trajectory_json: String, sample_rate: f64) -> PyResult<String> {
+    serde_json::to_string(&sample_trajectory_json(&trajectory_json, sample_rate)?)
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+}
+
+#[cfg(all(feature = "python-bindings", feature = "plot"))]
+#[pyfunction]
+#[pyo3(signature = (trajectory_json, sample_rate=50.0, width=1200, height=800, font_path=None))]
+fn plot_trajectory_png(
+    py: Python<'_>,
+    trajectory_json: String,
+    sample_rate: f64,
+    width: u32,
+    height: u32,
+    font_path: Option<String>,
+) -> PyResult<Py<pyo3::types::PyBytes>> {
+    let plot = sample_trajectory_json(&trajectory_json, sample_rate)?;
+    let png = py.allow_threads(|| {
+        crate::trajectory_plot::render_png(&plot, width, height, font_path.as_deref().map(std::path::Path::new))
+    }).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    
+    Ok(pyo3::types::PyBytes::new(py, &png).unbind())
+}
+
 #[cfg(feature = "python-bindings")]
 #[pymodule]
 fn reachy_mini_rust(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyReachyMiniSystem>()?;
+    m.add_class::<PyHardwareInterface>()?;
+    m.add_class::<PyCommandGate>()?;
     m.add_function(wrap_pyfunction!(init_logging, m)?)?;
+    m.add_function(wrap_pyfunction!(get_log_filter, m)?)?;
+    m.add_function(wrap_pyfunction!(set_log_filter, m)?)?;
+    m.add_function(wrap_pyfunction!(set_log_level, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_log_level, m)?)?;
     m.add_function(wrap_pyfunction!(get_system_info, m)?)?;
     m.add_function(wrap_pyfunction!(validate_config, m)?)?;
+    m.add_function(wrap_pyfunction!(negotiate_protocol, m)?)?;
+    m.add_function(wrap_pyfunction!(dry_run_config, m)?)?;
+    m.add_function(wrap_pyfunction!(build_motion_command, m)?)?;
+    #[cfg(feature = "ai")]
+    m.add_function(wrap_pyfunction!(build_inference_request, m)?)?;
+    #[cfg(feature = "vision")]
+    m.add_function(wrap_pyfunction!(inspect_model, m)?)?;
+    m.add_function(wrap_pyfunction!(solve_gaze, m)?)?;
+    m.add_function(wrap_pyfunction!(gaze_workspace, m)?)?;
+    m.add_function(wrap_pyfunction!(export_robot_model, m)?)?;
+    m.add_function(wrap_pyfunction!(sample_trajectory, m)?)?;
+    m.add_function(wrap_pyfunction!(install_behavior_pack, m)?)?;
+    m.add_function(wrap_pyfunction!(inspect_behavior_pack, m)?)?;
+    m.add_function(wrap_pyfunction!(list_behavior_packs, m)?)?;
+    m.add_function(wrap_pyfunction!(set_behavior_pack_enabled, m)?)?;
+    m.add_function(wrap_pyfunction!(uninstall_behavior_pack, m)?)?;
+    m.add_function(wrap_pyfunction!(capture_cpu_profile, m)?)?;
+    #[cfg(feature = "plot")]
+    m.add_function(wrap_pyfunction!(plot_trajectory_png, m)?)?;
     Ok(())
 }
 
@@ -146,4 +669,29 @@ pub fn reachy_mini_rust() {
 #[cfg(not(feature = "python-bindings"))]
 pub fn dummy() {
     // 空函数，防止编译器警告
-}
\ No newline at end of file
+}
+#[cfg(all(test, feature = "python-bindings"))]
+mod tests {
+    /// 导出的类和函数都必须在类型存根中声明
+    #[test]
+    fn test_type_stubs_cover_exports() {
+        let source = include_str!("python_bindings.rs");
+        let stubs = include_str!("../reachy_mini_rust.pyi");
+        
+        let exports = source.lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                line.strip_prefix("m.add_class::<").and_then(|rest| rest.split('>').next())
+                    .map(|name| format!("class {}:", name))
+                    .or_else(|| line.strip_prefix("m.add_function(wrap_pyfunction!(")
+                        .and_then(|rest| rest.split(',').next())
+                        .map(|name| format!("def {}(", name)))
+            })
+            .collect::<Vec<_>>();
+        
+        assert!(!exports.is_empty());
+        for export in exports {
+            assert!(stubs.contains(&export), "类型存根缺少 {}", export);
+        }
+    }
+}
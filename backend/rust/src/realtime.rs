@@ -3,60 +3,160 @@
 //! 提供高精度的实时控制功能，包括运动控制、传感器数据处理、PID控制等。
 
 use crate::common::*;
+use crate::choreography::{Choreography, ShowCue};
+use crate::backlash::{backlash_sweep, estimate_backlash, BacklashCompensator, BacklashParams};
+use crate::audit::{AuditConfig, AuditLog, AuditQuery, AuditRecord, AuditResult};
+use crate::arbiter::{ArbiterConfig, Arbitration, CommandArbiter, CommandOrigin, CommandSource, ControlLease, Permission};
+use crate::config::SafetyProfile;
+use crate::command_filter::{validate_finite, CommandFilter, CommandFilterConfig};
+use crate::dynamics::{excitation_trajectory, fit_dynamics, DynamicsFit, DynamicsParams, DynamicsSample, ExcitationConfig};
+use crate::events::{EventBus, RobotEvent};
+use crate::estimator::{apply_estimate, JointStateEstimator, StateEstimationConfig};
+use crate::hardware::io_thread::{BusState, IoEndpoints, JointTargets, ThreadTiming, ThreadTimingStats};
+use crate::idle::{IdleMotionConfig, IdleMotionGenerator};
+use crate::joints::{HeadJoints, JointDefinition, JointSetConfig};
+use crate::latency::{LatencyBudget, LatencyRecorder, LatencyStage, LatencyStats};
+use crate::loop_rate::{rescale_gains, LoopRateAdapter, LoopRateConfig};
+use crate::oscillation::{scale_gains, OscillationAction, OscillationConfig, OscillationDetector, OscillationReport};
+use crate::protocol::RobotIdentity;
+use crate::shutdown::{CancellationToken, TaskGroup};
+use crate::sim_bridge::{SimBridge, SimBridgeConfig};
+use crate::soft_start::{SoftStartConfig, SoftStartRamp};
+use crate::telemetry::{TelemetryConfig, TelemetryDump, TelemetryRecorder};
+use crate::time_sync::{TimeSync, TimeSyncConfig, TimeSyncStatus};
+use crate::trajectory_file::TrajectoryFile;
+use crate::transforms::{TransformConfig, TransformTree};
+use crate::triple_buffer::{TripleReader, TripleWriter};
+use crate::twist::{Twist, TwistConfig, TwistSolution, TwistSolver};
 use anyhow::Result;
+use arc_swap::ArcSwap;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{RwLock, mpsc, Mutex};
+use tokio::sync::{RwLock, Mutex};
 use tokio::time::{interval, sleep};
-use log::{info, warn, error, debug};
+use log::{info, warn, debug};
 
 /// 实时控制配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RealtimeConfig {
     pub control_frequency: f64,
-    pub max_joint_velocity: f64,
+    pub max_joint_velocity: AngularVelocity,
+    /// 最大关节角加速度（rad/s²）
     pub max_joint_acceleration: f64,
-    pub position_tolerance: f64,
-    pub velocity_tolerance: f64,
+    pub position_tolerance: Angle,
+    pub velocity_tolerance: AngularVelocity,
     pub enable_safety_limits: bool,
     pub emergency_stop_enabled: bool,
     pub pid_gains: HashMap<String, PIDGains>,
     pub joint_limits: HashMap<String, JointLimits>,
     pub sensor_update_rate: f64,
+    /// 命令在队列中的最长等待时间；速度命令超过这个时间没有刷新时平滑减速到零
     pub command_timeout_ms: u64,
+    #[serde(default)]
+    pub idle_motion: IdleMotionConfig,
+    #[serde(default)]
+    pub arbitration: ArbiterConfig,
+    #[serde(default)]
+    pub command_filter: CommandFilterConfig,
+    #[serde(default)]
+    pub time_sync: TimeSyncConfig,
+    #[serde(default)]
+    pub soft_start: SoftStartConfig,
+    /// 各关节的齿隙/死区补偿参数
+    #[serde(default)]
+    pub backlash: HashMap<String, BacklashParams>,
+    /// 各关节的惯量和摩擦参数，用于控制输出的前馈补偿
+    #[serde(default)]
+    pub dynamics: HashMap<String, DynamicsParams>,
+    /// 由位置读数估计速度和加速度的滤波参数
+    #[serde(default)]
+    pub state_estimation: StateEstimationConfig,
+    /// 外部命令审计日志
+    #[serde(default)]
+    pub audit: AuditConfig,
+    /// 启动时的安全档位，管理员可以在运行时修改
+    #[serde(default)]
+    pub safety_profile: SafetyProfile,
+    /// 各安全档位允许外部命令使用的关节行程和速度比例（行程以中点为中心缩放）
+    #[serde(default = "default_profile_scales")]
+    pub profile_scales: HashMap<SafetyProfile, f64>,
+    /// 仿真桥接，启用后由Gazebo/MuJoCo代替硬件
+    #[serde(default)]
+    pub sim_bridge: SimBridgeConfig,
+    /// 负载过高时自动降低控制频率
+    #[serde(default)]
+    pub loop_rate: LoopRateConfig,
+    /// 各关节设定点、测量位置和控制输出的环形缓冲，用于排查振荡和抖动
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    /// 持续振荡时自动降低增益或停止关节
+    #[serde(default)]
+    pub oscillation: OscillationConfig,
+    /// 末端速度（twist）命令的运动学和奇异位形阻尼
+    #[serde(default)]
+    pub twist: TwistConfig,
+    /// 头部和相机的几何参数，末端速度控制和手眼标定共用控制器中的坐标变换树
+    #[serde(default)]
+    pub transforms: TransformConfig,
+}
+
+fn default_profile_scales() -> HashMap<SafetyProfile, f64> {
+    HashMap::from([
+        (SafetyProfile::Standard, 1.0),
+        (SafetyProfile::Reduced, 0.5),
+        (SafetyProfile::Minimal, 0.2),
+    ])
 }
 
 impl Default for RealtimeConfig {
     fn default() -> Self {
+        Self::from_joint_set(&JointSetConfig::default())
+    }
+}
+
+impl RealtimeConfig {
+    /// 根据关节集合生成各关节的PID参数和限位
+    pub fn from_joint_set(joints: &JointSetConfig) -> Self {
         let mut pid_gains = HashMap::new();
         let mut joint_limits = HashMap::new();
         
-        // 默认关节配置
-        let joint_names = vec![
-            "head_pan", "head_tilt",
-            "left_shoulder_pitch", "left_shoulder_roll", "left_elbow_pitch",
-            "right_shoulder_pitch", "right_shoulder_roll", "right_elbow_pitch"
-        ];
-        
-        for joint_name in joint_names {
-            pid_gains.insert(joint_name.to_string(), PIDGains::default());
-            joint_limits.insert(joint_name.to_string(), JointLimits::default());
+        for joint in &joints.joints {
+            pid_gains.insert(joint.name.clone(), PIDGains::default());
+            joint_limits.insert(joint.name.clone(), JointLimits::from(joint));
         }
         
         Self {
             control_frequency: 100.0, // 100Hz
-            max_joint_velocity: 2.0,   // rad/s
+            max_joint_velocity: AngularVelocity::from_radians_per_second(2.0),
             max_joint_acceleration: 5.0, // rad/s²
-            position_tolerance: 0.01,  // rad
-            velocity_tolerance: 0.1,   // rad/s
+            position_tolerance: Angle::from_radians(0.01),
+            velocity_tolerance: AngularVelocity::from_radians_per_second(0.1),
             enable_safety_limits: true,
             emergency_stop_enabled: true,
             pid_gains,
             joint_limits,
             sensor_update_rate: 200.0, // 200Hz
             command_timeout_ms: 1000,
+            idle_motion: IdleMotionConfig::from_joint_set(joints),
+            arbitration: ArbiterConfig::default(),
+            command_filter: CommandFilterConfig::default(),
+            time_sync: TimeSyncConfig::default(),
+            soft_start: SoftStartConfig::default(),
+            backlash: HashMap::new(),
+            dynamics: HashMap::new(),
+            state_estimation: StateEstimationConfig::default(),
+            audit: AuditConfig::default(),
+            safety_profile: SafetyProfile::default(),
+            profile_scales: default_profile_scales(),
+            sim_bridge: SimBridgeConfig::default(),
+            loop_rate: LoopRateConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            oscillation: OscillationConfig::default(),
+            twist: TwistConfig::default(),
+            transforms: TransformConfig::from_joint_set(joints),
         }
     }
 }
@@ -67,7 +167,7 @@ impl ConfigValidation for RealtimeConfig {
             return Err(anyhow::anyhow!("控制频率必须为正数"));
         }
         
-        if self.max_joint_velocity <= 0.0 {
+        if self.max_joint_velocity <= AngularVelocity::ZERO {
             return Err(anyhow::anyhow!("最大关节速度必须为正数"));
         }
         
@@ -79,6 +179,53 @@ impl ConfigValidation for RealtimeConfig {
             return Err(anyhow::anyhow!("传感器更新率必须为正数"));
         }
         
+        self.idle_motion.validate()?;
+        self.arbitration.validate()?;
+        self.command_filter.validate()?;
+        self.time_sync.validate()?;
+        self.soft_start.validate()?;
+        
+        for (joint_name, params) in &self.backlash {
+            if !self.joint_limits.contains_key(joint_name) {
+                return Err(anyhow::anyhow!("齿隙补偿的关节 '{}' 不存在", joint_name));
+            }
+            params.validate()?;
+        }
+        
+        for (joint_name, params) in &self.dynamics {
+            if !self.joint_limits.contains_key(joint_name) {
+                return Err(anyhow::anyhow!("动力学参数的关节 '{}' 不存在", joint_name));
+            }
+            params.validate()?;
+        }
+        
+        if let Some(joint_name) = self.state_estimation.joints.keys().find(|name| !self.joint_limits.contains_key(*name)) {
+            return Err(anyhow::anyhow!("状态估计的关节 '{}' 不存在", joint_name));
+        }
+        self.state_estimation.validate()?;
+        self.audit.validate()?;
+        self.sim_bridge.validate()?;
+        self.loop_rate.validate()?;
+        self.telemetry.validate()?;
+        self.oscillation.validate()?;
+        self.twist.validate()?;
+        self.transforms.validate()?;
+        
+        let transforms = &self.transforms;
+        let head_joints = [&transforms.head_pan_joint, &transforms.head_tilt_joint].into_iter()
+            .chain(transforms.base_yaw_joint.as_ref());
+        for joint_name in head_joints {
+            if !self.joint_limits.contains_key(joint_name) {
+                return Err(anyhow::anyhow!("坐标变换的关节 '{}' 不存在", joint_name));
+            }
+        }
+        
+        for (profile, scale) in &self.profile_scales {
+            if !(*scale > 0.0 && *scale <= 1.0) {
+                return Err(anyhow::anyhow!("安全档位 {:?} 的行程比例必须在0-1之间", profile));
+            }
+        }
+        
         Ok(())
     }
 }
@@ -108,21 +255,39 @@ impl Default for PIDGains {
 /// 关节限制
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JointLimits {
-    pub min_position: f64,
-    pub max_position: f64,
-    pub max_velocity: f64,
+    pub min_position: Angle,
+    pub max_position: Angle,
+    pub max_velocity: AngularVelocity,
+    /// 最大角加速度（rad/s²）
     pub max_acceleration: f64,
     pub max_torque: f64,
+    /// 连续旋转关节（位置回绕，不受位置限制）
+    #[serde(default)]
+    pub continuous: bool,
 }
 
 impl Default for JointLimits {
     fn default() -> Self {
         Self {
-            min_position: -3.14159,
-            max_position: 3.14159,
-            max_velocity: 2.0,
+            min_position: Angle::from_radians(-std::f64::consts::PI),
+            max_position: Angle::from_radians(std::f64::consts::PI),
+            max_velocity: AngularVelocity::from_radians_per_second(2.0),
             max_acceleration: 5.0,
             max_torque: 10.0,
+            continuous: false,
+        }
+    }
+}
+
+impl From<&JointDefinition> for JointLimits {
+    fn from(joint: &JointDefinition) -> Self {
+        Self {
+            min_position: joint.min_position,
+            max_position: joint.max_position,
+            max_velocity: joint.max_velocity,
+            max_acceleration: joint.max_acceleration,
+            max_torque: joint.max_torque,
+            continuous: joint.continuous,
         }
     }
 }
@@ -137,6 +302,116 @@ pub struct MotionCommand {
     pub target_torque: Option<f64>,
     pub duration: Option<f64>,
     pub timestamp: u64,
+    /// 关联ID（ULID），同一个ID出现在审计日志和控制循环的日志中，未指定时入队时生成
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+impl MotionCommand {
+    /// 创建命令构建器，时间戳取当前时间
+    pub fn builder(joint_name: impl Into<String>, command_type: CommandType) -> MotionCommandBuilder {
+        MotionCommandBuilder::new(joint_name, command_type)
+    }
+    
+    /// 检查命令：位置、速度、力矩命令必须有对应的目标且不能带其它目标，停止命令不能带目标，
+    /// 数值必须有限，时长必须为正数
+    pub fn validate(&self) -> Result<()> {
+        if self.joint_name.is_empty() {
+            return Err(anyhow::anyhow!("运动命令的关节名不能为空"));
+        }
+        
+        validate_finite(self)?;
+        
+        let required = match self.command_type {
+            CommandType::Position => Some("target_position"),
+            CommandType::Velocity => Some("target_velocity"),
+            CommandType::Torque => Some("target_torque"),
+            CommandType::Stop | CommandType::EmergencyStop => None,
+        };
+        let targets = [
+            ("target_position", self.target_position.is_some()),
+            ("target_velocity", self.target_velocity.is_some()),
+            ("target_torque", self.target_torque.is_some()),
+        ];
+        for (field, present) in targets {
+            if Some(field) == required && !present {
+                return Err(anyhow::anyhow!("关节 {} 的 {:?} 命令缺少 {}", self.joint_name, self.command_type, field));
+            }
+            if Some(field) != required && present {
+                return Err(anyhow::anyhow!("关节 {} 的 {:?} 命令不能带 {}", self.joint_name, self.command_type, field));
+            }
+        }
+        
+        if self.duration.is_some_and(|duration| duration <= 0.0) {
+            return Err(anyhow::anyhow!("关节 {} 的命令时长必须为正数", self.joint_name));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 运动命令构建器，build时验证字段组合
+pub struct MotionCommandBuilder {
+    command: MotionCommand,
+}
+
+impl MotionCommandBuilder {
+    pub fn new(joint_name: impl Into<String>, command_type: CommandType) -> Self {
+        Self {
+            command: MotionCommand {
+                joint_name: joint_name.into(),
+                command_type,
+                target_position: None,
+                target_velocity: None,
+                target_torque: None,
+                duration: None,
+                timestamp: current_timestamp(),
+                correlation_id: None,
+            },
+        }
+    }
+    
+    /// 目标位置（rad）
+    pub fn position(mut self, position: f64) -> Self {
+        self.command.target_position = Some(position);
+        self
+    }
+    
+    /// 目标速度（rad/s）
+    pub fn velocity(mut self, velocity: f64) -> Self {
+        self.command.target_velocity = Some(velocity);
+        self
+    }
+    
+    /// 目标力矩
+    pub fn torque(mut self, torque: f64) -> Self {
+        self.command.target_torque = Some(torque);
+        self
+    }
+    
+    /// 运动时长（秒），None表示按速度限制尽快到达
+    pub fn duration(mut self, duration: impl Into<Option<f64>>) -> Self {
+        self.command.duration = duration.into();
+        self
+    }
+    
+    /// 关联ID，None时入队时生成
+    pub fn correlation_id(mut self, correlation_id: impl Into<Option<String>>) -> Self {
+        self.command.correlation_id = correlation_id.into();
+        self
+    }
+    
+    /// 覆盖时间戳（毫秒）
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.command.timestamp = timestamp;
+        self
+    }
+    
+    /// 构建并验证命令
+    pub fn build(self) -> Result<MotionCommand> {
+        self.command.validate()?;
+        Ok(self.command)
+    }
 }
 
 /// 命令类型
@@ -180,11 +455,26 @@ pub struct RealtimeStatus {
     pub is_running: bool,
     pub emergency_stop: bool,
     pub control_loop_frequency: f64,
+    /// 控制循环当前的目标频率，负载过高时低于配置的控制频率
+    #[serde(default)]
+    pub target_control_frequency: f64,
     pub sensor_update_frequency: f64,
     pub active_commands: usize,
     pub last_command_timestamp: u64,
     pub performance_stats: PerformanceStats,
     pub joint_states: HashMap<String, JointState>,
+    /// 当前独占控制权持有者
+    #[serde(default)]
+    pub control_owner: Option<ControlLease>,
+    /// 与参考时钟的同步状态
+    #[serde(default)]
+    pub time_sync: TimeSyncStatus,
+    /// 控制循环的周期耗时
+    #[serde(default)]
+    pub control_timing: ThreadTiming,
+    /// 总线I/O线程的周期耗时，未接入I/O线程时为None
+    #[serde(default)]
+    pub io_timing: Option<ThreadTiming>,
 }
 
 impl Default for RealtimeStatus {
@@ -193,15 +483,54 @@ impl Default for RealtimeStatus {
             is_running: false,
             emergency_stop: false,
             control_loop_frequency: 0.0,
+            target_control_frequency: 0.0,
             sensor_update_frequency: 0.0,
             active_commands: 0,
             last_command_timestamp: 0,
             performance_stats: PerformanceStats::new(),
             joint_states: HashMap::new(),
+            control_owner: None,
+            time_sync: TimeSyncStatus::default(),
+            control_timing: ThreadTiming::default(),
+            io_timing: None,
         }
     }
 }
 
+/// 实时控制统计
+///
+/// 控制循环、传感器循环和状态查询之间不共享锁：标志和计数用原子量，
+/// 性能统计每秒发布一次快照，查询时再组装成`RealtimeStatus`。
+#[derive(Debug, Default)]
+struct RealtimeStats {
+    is_running: AtomicBool,
+    emergency_stop: AtomicBool,
+    control_loop_frequency: AtomicF64,
+    target_control_frequency: AtomicF64,
+    sensor_update_frequency: AtomicF64,
+    active_commands: AtomicUsize,
+    last_command_timestamp: AtomicU64,
+    performance_stats: ArcSwap<PerformanceStats>,
+    control_timing: ThreadTimingStats,
+    /// 控制循环处理完命令队列时通知，用于测量调度延迟
+    commands_processed: tokio::sync::Notify,
+    /// 控制循环写入目标到总线应答的耗时
+    bus_latency: LatencyRecorder,
+}
+
+/// 总线或仿真器读回的关节位置和力矩
+type MeasuredJoints<'a> = (&'a HashMap<String, f64>, &'a HashMap<String, f64>);
+
+/// 与总线I/O线程交换数据的端点
+///
+/// 互斥锁只在控制循环（写目标）和传感器循环（读状态）各自内部使用，
+/// 与I/O线程之间只经过三缓冲，不会被总线事务阻塞。
+struct HardwareIoLink {
+    targets: std::sync::Mutex<TripleWriter<JointTargets>>,
+    state: std::sync::Mutex<TripleReader<BusState>>,
+    timing: Arc<ThreadTimingStats>,
+}
+
 /// PID控制器
 #[derive(Debug, Clone)]
 struct PIDController {
@@ -209,6 +538,11 @@ struct PIDController {
     integral: f64,
     last_error: f64,
     last_time: Instant,
+    /// 最近一次的控制输出（含前馈和软启动比例），供遥测记录
+    last_output: f64,
+    compensator: BacklashCompensator,
+    /// 前馈补偿用的动力学参数，全为0时不做前馈
+    dynamics: DynamicsParams,
 }
 
 impl PIDController {
@@ -218,6 +552,9 @@ impl PIDController {
             integral: 0.0,
             last_error: 0.0,
             last_time: Instant::now(),
+            last_output: 0.0,
+            compensator: BacklashCompensator::default(),
+            dynamics: DynamicsParams::default(),
         }
     }
     
@@ -229,6 +566,8 @@ impl PIDController {
             return 0.0;
         }
         
+        // 齿隙补偿
+        let setpoint = self.compensator.compensate_setpoint(setpoint);
         let error = setpoint - measurement;
         
         // 比例项
@@ -243,7 +582,7 @@ impl PIDController {
         let derivative = self.gains.kd * (error - self.last_error) / dt;
         
         // 总输出
-        let output = proportional + integral + derivative;
+        let output = self.compensator.compensate_output(proportional + integral + derivative);
         let clamped_output = clamp(output, -self.gains.max_output, self.gains.max_output);
         
         // 更新状态
@@ -257,6 +596,7 @@ impl PIDController {
         self.integral = 0.0;
         self.last_error = 0.0;
         self.last_time = Instant::now();
+        self.compensator.reset();
     }
 }
 
@@ -265,9 +605,6 @@ impl PIDController {
 struct TrajectoryGenerator {
     start_position: f64,
     target_position: f64,
-    start_velocity: f64,
-    max_velocity: f64,
-    max_acceleration: f64,
     start_time: Instant,
     duration: Duration,
 }
@@ -276,7 +613,8 @@ impl TrajectoryGenerator {
     fn new(
         start_position: f64,
         target_position: f64,
-        start_velocity: f64,
+        // 平滑插值轮廓从静止开始，暂不使用初始速度
+        _start_velocity: f64,
         max_velocity: f64,
         max_acceleration: f64,
     ) -> Self {
@@ -286,9 +624,6 @@ impl TrajectoryGenerator {
         Self {
             start_position,
             target_position,
-            start_velocity,
-            max_velocity,
-            max_acceleration,
             start_time: Instant::now(),
             duration,
         }
@@ -338,23 +673,96 @@ impl TrajectoryGenerator {
         (pos2 - pos1) / dt
     }
     
+    fn get_acceleration(&self, time: Instant) -> f64 {
+        let dt = 0.001;
+        let vel1 = self.get_velocity(time);
+        let vel2 = self.get_velocity(time + Duration::from_secs_f64(dt));
+        
+        (vel2 - vel1) / dt
+    }
+    
     fn is_finished(&self, time: Instant) -> bool {
         time.duration_since(self.start_time) >= self.duration
     }
 }
 
+/// 速度模式设定点：按目标速度积分出目标位置
+///
+/// 超过`command_timeout_ms`没有收到新的速度命令时目标速度视为零，按关节最大加速度
+/// 平滑减速，不会一直保持最后一次的速度（遥控端断线、摇杆消息丢失时关节不会一直转下去）。
+#[derive(Debug, Clone)]
+struct VelocitySetpoint {
+    /// 命令的目标速度（rad/s）
+    commanded: f64,
+    /// 当前输出的速度，按最大加速度趋近目标速度
+    velocity: f64,
+    /// 积分得到的目标位置（展开后的角度）
+    position: f64,
+    refreshed_at: Instant,
+}
+
+impl VelocitySetpoint {
+    fn new(position: f64, velocity: f64, now: Instant) -> Self {
+        Self { commanded: 0.0, velocity, position, refreshed_at: now }
+    }
+    
+    /// 收到新的速度命令
+    fn refresh(&mut self, commanded: f64, now: Instant) {
+        self.commanded = commanded;
+        self.refreshed_at = now;
+    }
+    
+    fn is_stale(&self, now: Instant, timeout: Duration) -> bool {
+        now.duration_since(self.refreshed_at) > timeout
+    }
+    
+    /// 前进dt秒，返回本周期的目标位置
+    fn step(&mut self, now: Instant, dt: f64, timeout: Duration, max_acceleration: f64) -> f64 {
+        let target = if self.is_stale(now, timeout) { 0.0 } else { self.commanded };
+        let max_change = max_acceleration * dt;
+        let previous = self.velocity;
+        let change = target - self.velocity;
+        self.velocity = if change.abs() <= max_change { target } else { self.velocity + max_change.copysign(change) };
+        self.position += (previous + self.velocity) * 0.5 * dt;
+        self.position
+    }
+    
+    /// 超时后已经减速到零，可以移除
+    fn is_expired(&self, now: Instant, timeout: Duration) -> bool {
+        self.velocity == 0.0 && self.is_stale(now, timeout)
+    }
+}
+
 /// 实时控制器
 pub struct RealtimeController {
     config: RealtimeConfig,
-    status: Arc<RwLock<RealtimeStatus>>,
+    stats: Arc<RealtimeStats>,
     pid_controllers: Arc<RwLock<HashMap<String, PIDController>>>,
     trajectories: Arc<RwLock<HashMap<String, TrajectoryGenerator>>>,
+    velocity_setpoints: Arc<Mutex<HashMap<String, VelocitySetpoint>>>,
     command_queue: Arc<Mutex<VecDeque<MotionCommand>>>,
-    sensor_data: Arc<RwLock<SensorData>>,
-    control_handle: Option<tokio::task::JoinHandle<()>>,
-    sensor_handle: Option<tokio::task::JoinHandle<()>>,
+    /// 传感器循环每个周期发布一份新快照，读取方不会阻塞写入
+    sensor_data: Arc<ArcSwap<SensorData>>,
+    idle_motion: Arc<Mutex<IdleMotionGenerator>>,
+    arbiter: Arc<Mutex<CommandArbiter>>,
+    command_filter: Arc<Mutex<CommandFilter>>,
+    audit_log: Arc<Mutex<AuditLog>>,
+    safety_profile: Arc<RwLock<SafetyProfile>>,
+    time_sync: Arc<RwLock<TimeSync>>,
+    soft_start: Arc<RwLock<Option<SoftStartRamp>>>,
+    /// 仿真桥接，未启用时为None
+    sim_bridge: Option<Arc<SimBridge>>,
+    /// 控制频率变化等状态事件发布到这里，未设置时不发布
+    event_bus: Option<EventBus>,
+    /// 总线I/O线程，未接入时为None
+    hardware_io: Option<Arc<HardwareIoLink>>,
+    telemetry: Arc<TelemetryRecorder>,
+    oscillation: Arc<Mutex<OscillationDetector>>,
+    twist_solver: TwistSolver,
+    /// 头部和相机的坐标变换，传感器循环每个周期按关节状态更新，相机外参标定结果写入这里
+    transforms: Arc<RwLock<TransformTree>>,
+    tasks: TaskGroup,
     is_running: Arc<RwLock<bool>>,
-    emergency_stop: Arc<RwLock<bool>>,
 }
 
 impl RealtimeController {
@@ -364,14 +772,20 @@ impl RealtimeController {
         
         info!("初始化实时控制器...");
         
-        let status = Arc::new(RwLock::new(RealtimeStatus::default()));
+        let stats = Arc::new(RealtimeStats::default());
         let is_running = Arc::new(RwLock::new(false));
-        let emergency_stop = Arc::new(RwLock::new(false));
         
         // 初始化PID控制器
         let mut pid_controllers = HashMap::new();
         for (joint_name, gains) in &config.pid_gains {
-            pid_controllers.insert(joint_name.clone(), PIDController::new(gains.clone()));
+            let mut controller = PIDController::new(gains.clone());
+            if let Some(params) = config.backlash.get(joint_name) {
+                controller.compensator.set_params(params.clone());
+            }
+            if let Some(params) = config.dynamics.get(joint_name) {
+                controller.dynamics = params.clone();
+            }
+            pid_controllers.insert(joint_name.clone(), controller);
         }
         let pid_controllers = Arc::new(RwLock::new(pid_controllers));
         
@@ -381,36 +795,89 @@ impl RealtimeController {
         // 初始化传感器数据
         let mut joint_states = HashMap::new();
         for joint_name in config.joint_limits.keys() {
-            joint_states.insert(joint_name.clone(), JointState::default());
+            joint_states.insert(joint_name.clone(), JointState::new(joint_name.clone()));
         }
         
-        let sensor_data = Arc::new(RwLock::new(SensorData {
+        let sensor_data = Arc::new(ArcSwap::from_pointee(SensorData {
             joint_states,
             imu_data: None,
             force_torque: None,
             timestamp: current_timestamp(),
         }));
         
+        let idle_motion = Arc::new(Mutex::new(IdleMotionGenerator::new(config.idle_motion.clone())));
+        let arbiter = Arc::new(Mutex::new(CommandArbiter::new(config.arbitration.clone())?));
+        let command_filter = Arc::new(Mutex::new(CommandFilter::new(config.command_filter.clone())?));
+        let time_sync = Arc::new(RwLock::new(TimeSync::new(config.time_sync.clone())?));
+        let audit_log = Arc::new(Mutex::new(AuditLog::new(config.audit.clone())?));
+        let safety_profile = Arc::new(RwLock::new(config.safety_profile));
+        let telemetry = Arc::new(TelemetryRecorder::new(config.telemetry.clone(), config.joint_limits.keys().cloned())?);
+        let oscillation = Arc::new(Mutex::new(OscillationDetector::new(config.oscillation.clone())?));
+        let head_joints = HeadJoints {
+            pan: config.transforms.head_pan_joint.clone(),
+            tilt: config.transforms.head_tilt_joint.clone(),
+            base_yaw: config.transforms.base_yaw_joint.clone(),
+        };
+        let twist_solver = TwistSolver::new(config.twist.clone(), head_joints)?;
+        let transforms = Arc::new(RwLock::new(TransformTree::new(config.transforms.clone())?));
+        
+        let sim_bridge = if config.sim_bridge.enabled {
+            let bridge = SimBridge::bind(&config.sim_bridge).await?;
+            info!("仿真桥接已启用，控制输出发送到 {}", config.sim_bridge.simulator_address);
+            Some(Arc::new(bridge))
+        } else {
+            None
+        };
+        
         let controller = Self {
             config,
-            status,
+            stats,
             pid_controllers,
             trajectories,
+            velocity_setpoints: Arc::new(Mutex::new(HashMap::new())),
             command_queue,
             sensor_data,
-            control_handle: None,
-            sensor_handle: None,
+            idle_motion,
+            arbiter,
+            command_filter,
+            audit_log,
+            safety_profile,
+            time_sync,
+            soft_start: Arc::new(RwLock::new(None)),
+            sim_bridge,
+            event_bus: None,
+            hardware_io: None,
+            telemetry,
+            oscillation,
+            twist_solver,
+            transforms,
+            tasks: TaskGroup::new("实时控制器"),
             is_running,
-            emergency_stop,
         };
         
         info!("实时控制器初始化完成");
         Ok(controller)
     }
     
+    /// 设置发布状态事件的事件总线，需要在启动前调用
+    pub fn set_event_bus(&mut self, bus: EventBus) {
+        self.event_bus = Some(bus);
+    }
+    
+    /// 接入总线I/O线程（见`HardwareInterface::start_io_thread`），需要在启动前调用：
+    /// 控制循环把每个周期的目标位置交给I/O线程，传感器循环使用I/O线程读回的关节状态
+    pub fn attach_hardware_io(&mut self, endpoints: IoEndpoints) {
+        self.hardware_io = Some(Arc::new(HardwareIoLink {
+            targets: std::sync::Mutex::new(endpoints.targets),
+            state: std::sync::Mutex::new(endpoints.state),
+            timing: endpoints.timing,
+        }));
+    }
+    
     /// 启动实时控制
     pub async fn start(&mut self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
+        let is_running_lock = Arc::clone(&self.is_running);
+        let mut is_running = is_running_lock.write().await;
         if *is_running {
             return Ok(());
         }
@@ -423,21 +890,30 @@ impl RealtimeController {
         // 启动传感器更新循环
         self.start_sensor_loop().await?;
         
-        *is_running = true;
+        // 上电时扭矩刚开启，从当前位置软启动
+        self.begin_soft_start().await;
         
-        // 更新状态
-        {
-            let mut status = self.status.write().await;
-            status.is_running = true;
+        // 配置了NTP服务器时启动时间同步
+        if let Some(server) = self.config.time_sync.ntp_server.clone() {
+            self.tasks.spawn("时间同步", crate::time_sync::run_ntp_sync(
+                Arc::clone(&self.time_sync),
+                server,
+                self.tasks.token(),
+            ));
         }
         
+        *is_running = true;
+        
+        self.stats.is_running.store(true, Ordering::Relaxed);
+        
         info!("实时控制器启动完成");
         Ok(())
     }
     
     /// 停止实时控制
     pub async fn stop(&mut self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
+        let is_running_lock = Arc::clone(&self.is_running);
+        let mut is_running = is_running_lock.write().await;
         if !*is_running {
             return Ok(());
         }
@@ -446,21 +922,18 @@ impl RealtimeController {
         
         *is_running = false;
         
-        // 停止控制循环
-        if let Some(handle) = self.control_handle.take() {
-            handle.abort();
-        }
-        
-        // 停止传感器循环
-        if let Some(handle) = self.sensor_handle.take() {
-            handle.abort();
-        }
+        // 通知控制、传感器和时间同步循环在当前周期结束后退出
+        self.tasks.shutdown().await;
         
-        // 清空命令队列
+        // 丢弃尚未执行的运动命令，停止后不应再有关节运动
         {
             let mut queue = self.command_queue.lock().await;
+            if !queue.is_empty() {
+                info!("丢弃 {} 条未执行的运动命令", queue.len());
+            }
             queue.clear();
         }
+        self.velocity_setpoints.lock().await.clear();
         
         // 重置PID控制器
         {
@@ -470,12 +943,8 @@ impl RealtimeController {
             }
         }
         
-        // 更新状态
-        {
-            let mut status = self.status.write().await;
-            status.is_running = false;
-            status.active_commands = 0;
-        }
+        self.stats.is_running.store(false, Ordering::Relaxed);
+        self.stats.active_commands.store(0, Ordering::Relaxed);
         
         info!("实时控制器停止完成");
         Ok(())
@@ -483,91 +952,229 @@ impl RealtimeController {
     
     /// 启动控制循环
     async fn start_control_loop(&mut self) -> Result<()> {
-        let control_period = Duration::from_secs_f64(1.0 / self.config.control_frequency);
+        let rate = LoopRateAdapter::new(self.config.control_frequency, self.config.loop_rate.clone())?;
+        self.stats.target_control_frequency.store(rate.frequency());
+        self.stats.control_timing.set_frequency(rate.frequency());
         
-        let is_running = Arc::clone(&self.is_running);
-        let emergency_stop = Arc::clone(&self.emergency_stop);
-        let status = Arc::clone(&self.status);
+        let shutdown = self.tasks.token();
+        let stats = Arc::clone(&self.stats);
         let pid_controllers = Arc::clone(&self.pid_controllers);
         let trajectories = Arc::clone(&self.trajectories);
+        let velocity_setpoints = Arc::clone(&self.velocity_setpoints);
         let command_queue = Arc::clone(&self.command_queue);
         let sensor_data = Arc::clone(&self.sensor_data);
+        let idle_motion = Arc::clone(&self.idle_motion);
+        let soft_start = Arc::clone(&self.soft_start);
+        let sim_bridge = self.sim_bridge.clone();
+        let hardware_io = self.hardware_io.clone();
+        let telemetry = Arc::clone(&self.telemetry);
+        let oscillation = Arc::clone(&self.oscillation);
+        let event_bus = self.event_bus.clone();
         let config = self.config.clone();
         
-        let handle = tokio::spawn(async move {
+        self.tasks.spawn("控制循环", async move {
             Self::control_loop(
-                control_period,
-                is_running,
-                emergency_stop,
-                status,
+                rate,
+                shutdown,
+                stats,
                 pid_controllers,
                 trajectories,
+                velocity_setpoints,
                 command_queue,
                 sensor_data,
+                idle_motion,
+                soft_start,
+                sim_bridge,
+                hardware_io,
+                telemetry,
+                oscillation,
+                event_bus,
                 config,
             ).await
         });
         
-        self.control_handle = Some(handle);
         Ok(())
     }
     
     /// 控制循环
+    #[allow(clippy::too_many_arguments)]
     async fn control_loop(
-        control_period: Duration,
-        is_running: Arc<RwLock<bool>>,
-        emergency_stop: Arc<RwLock<bool>>,
-        status: Arc<RwLock<RealtimeStatus>>,
+        mut rate: LoopRateAdapter,
+        shutdown: CancellationToken,
+        stats: Arc<RealtimeStats>,
         pid_controllers: Arc<RwLock<HashMap<String, PIDController>>>,
         trajectories: Arc<RwLock<HashMap<String, TrajectoryGenerator>>>,
+        velocity_setpoints: Arc<Mutex<HashMap<String, VelocitySetpoint>>>,
         command_queue: Arc<Mutex<VecDeque<MotionCommand>>>,
-        sensor_data: Arc<RwLock<SensorData>>,
+        sensor_data: Arc<ArcSwap<SensorData>>,
+        idle_motion: Arc<Mutex<IdleMotionGenerator>>,
+        soft_start: Arc<RwLock<Option<SoftStartRamp>>>,
+        sim_bridge: Option<Arc<SimBridge>>,
+        hardware_io: Option<Arc<HardwareIoLink>>,
+        telemetry: Arc<TelemetryRecorder>,
+        oscillation: Arc<Mutex<OscillationDetector>>,
+        event_bus: Option<EventBus>,
         config: RealtimeConfig,
     ) {
-        let mut interval = interval(control_period);
+        let mut interval = interval(rate.period());
         let mut loop_count = 0u64;
         let mut last_stats_update = Instant::now();
+        let mut performance_stats = PerformanceStats::new();
         
+        // 每个控制周期开始前检查关闭信号，周期内的计算和输出不会被打断
         loop {
-            interval.tick().await;
-            
-            // 检查是否应该停止
-            if !*is_running.read().await {
-                break;
-            }
+            let scheduled = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                scheduled = interval.tick() => scheduled,
+            };
             
             let loop_start = Instant::now();
             
             // 检查紧急停止
-            if *emergency_stop.read().await {
-                Self::handle_emergency_stop(&pid_controllers, &trajectories).await;
+            if stats.emergency_stop.load(Ordering::SeqCst) {
+                Self::handle_emergency_stop(&pid_controllers, &trajectories, &velocity_setpoints, sim_bridge.as_deref()).await;
+                idle_motion.lock().await.notify_activity();
                 continue;
             }
             
+            // 软启动期间按比例降低控制输出
+            let stiffness = Self::soft_start_stiffness(&soft_start).await;
+            
+            // 整个控制周期使用同一份传感器快照
+            let sensor_data = sensor_data.load_full();
+            
             // 处理命令队列
-            Self::process_command_queue(
+            let processed_commands = Self::process_command_queue(
                 &command_queue,
                 &trajectories,
+                &velocity_setpoints,
                 &sensor_data,
                 &config,
             ).await;
+            if processed_commands > 0 {
+                stats.commands_processed.notify_waiters();
+            }
+            let velocity_active = !velocity_setpoints.lock().await.is_empty();
             
-            // 更新轨迹和控制
-            Self::update_control(
+            // 空闲微动（有命令、轨迹或速度设定点时立即让出）
+            let mut targets = Self::update_idle_motion(
+                &idle_motion,
+                processed_commands > 0 || velocity_active,
                 &pid_controllers,
                 &trajectories,
                 &sensor_data,
+                stiffness,
                 &config,
             ).await;
             
+            // 更新轨迹和控制
+            targets.extend(Self::update_control(
+                &pid_controllers,
+                &trajectories,
+                &sensor_data,
+                stiffness,
+            ).await);
+            
+            // 速度模式：没有及时刷新的速度命令平滑减速到零
+            if velocity_active {
+                targets.extend(Self::update_velocity_control(
+                    &velocity_setpoints,
+                    &pid_controllers,
+                    &sensor_data,
+                    stiffness,
+                    rate.period().as_secs_f64(),
+                    &config,
+                ).await);
+            }
+            
+            if telemetry.is_enabled() {
+                let controllers = pid_controllers.read().await;
+                for (joint_name, joint_state) in &sensor_data.joint_states {
+                    let setpoint = targets.get(joint_name).copied();
+                    let output = setpoint.and(controllers.get(joint_name)).map(|controller| controller.last_output);
+                    telemetry.record(joint_name, loop_start, setpoint, joint_state.unwrapped_position, output);
+                }
+            }
+            
+            // 振荡检测：持续振荡的关节降低增益，仍然振荡或已停止的关节不再输出目标
+            if config.oscillation.enabled && !targets.is_empty() {
+                let mut detector = oscillation.lock().await;
+                let dt = rate.period().as_secs_f64();
+                for (joint_name, target_position) in &targets {
+                    let Some(joint_state) = sensor_data.joint_states.get(joint_name) else {
+                        continue;
+                    };
+                    if let Some(report) = detector.update(joint_name, target_position - joint_state.unwrapped_position, dt) {
+                        Self::handle_oscillation(&report, &pid_controllers, &config, rate.ratio()).await;
+                        if let Some(bus) = &event_bus {
+                            bus.publish("realtime", RobotEvent::OscillationDetected(report));
+                        }
+                    }
+                }
+                
+                let halted: Vec<String> = targets.keys().filter(|joint_name| detector.is_halted(joint_name)).cloned().collect();
+                for joint_name in halted {
+                    targets.remove(&joint_name);
+                    Self::stop_joint(&joint_name, &trajectories, &velocity_setpoints).await;
+                }
+            }
+            
+            // 仿真模式下由仿真器内的位置控制器跟踪本周期的目标位置
+            if let Some(bridge) = &sim_bridge {
+                if !targets.is_empty() {
+                    if let Err(e) = bridge.send_targets(&targets).await {
+                        warn!("发送仿真目标位置失败: {}", e);
+                    }
+                }
+            }
+            
+            // 交给I/O线程写入总线，不等待串口事务
+            if let Some(io) = &hardware_io {
+                if !targets.is_empty() {
+                    io.targets.lock().unwrap_or_else(|e| e.into_inner()).update(|slot| {
+                        slot.timestamp = current_timestamp();
+                        slot.positions.clone_from(&targets);
+                        slot.sent_at = Some(Instant::now());
+                    });
+                }
+            }
+            
             loop_count += 1;
+            stats.control_timing.record(loop_start.elapsed());
+            
+            // 从计划时刻算起的耗时，包含调度延迟，超过周期即错过截止时间
+            if let Some(change) = rate.record(scheduled.elapsed()) {
+                if change.is_degraded() {
+                    warn!("控制循环持续超时（{:.0}%的周期错过截止时间），频率从 {} Hz 降到 {} Hz",
+                          change.miss_ratio * 100.0, change.from_hz, change.to_hz);
+                } else {
+                    info!("控制循环负载恢复，频率从 {} Hz 回到 {} Hz", change.from_hz, change.to_hz);
+                }
+                
+                {
+                    let detector = oscillation.lock().await;
+                    let mut controllers = pid_controllers.write().await;
+                    for (joint_name, controller) in controllers.iter_mut() {
+                        if let Some(gains) = config.pid_gains.get(joint_name) {
+                            controller.gains = scale_gains(&rescale_gains(gains, rate.ratio()), detector.gain_scale(joint_name));
+                        }
+                    }
+                }
+                
+                interval = tokio::time::interval(rate.period());
+                stats.target_control_frequency.store(rate.frequency());
+                stats.control_timing.set_frequency(rate.frequency());
+                if let Some(bus) = &event_bus {
+                    bus.publish("realtime", RobotEvent::ControlRateChanged(change));
+                }
+            }
             
             // 更新性能统计
             let loop_time = loop_start.elapsed();
             if last_stats_update.elapsed() >= Duration::from_secs(1) {
-                let mut status = status.write().await;
-                status.control_loop_frequency = loop_count as f64 / last_stats_update.elapsed().as_secs_f64();
-                status.performance_stats.update_frame_stats(loop_time);
+                stats.control_loop_frequency.store(loop_count as f64 / last_stats_update.elapsed().as_secs_f64());
+                performance_stats.update_frame_stats(loop_time);
+                stats.performance_stats.store(Arc::new(performance_stats.clone()));
                 
                 loop_count = 0;
                 last_stats_update = Instant::now();
@@ -577,16 +1184,35 @@ impl RealtimeController {
         info!("控制循环结束");
     }
     
+    /// 当前软启动刚度，软启动结束后清除
+    async fn soft_start_stiffness(soft_start: &Arc<RwLock<Option<SoftStartRamp>>>) -> f64 {
+        let now = Instant::now();
+        let mut soft_start = soft_start.write().await;
+        
+        match soft_start.as_ref() {
+            Some(ramp) if ramp.is_finished(now) => {
+                *soft_start = None;
+                debug!("软启动完成");
+                1.0
+            }
+            Some(ramp) => ramp.stiffness(now),
+            None => 1.0,
+        }
+    }
+    
     /// 处理紧急停止
     async fn handle_emergency_stop(
         pid_controllers: &Arc<RwLock<HashMap<String, PIDController>>>,
         trajectories: &Arc<RwLock<HashMap<String, TrajectoryGenerator>>>,
+        velocity_setpoints: &Arc<Mutex<HashMap<String, VelocitySetpoint>>>,
+        sim_bridge: Option<&SimBridge>,
     ) {
-        // 清空所有轨迹
+        // 清空所有轨迹和速度设定点
         {
             let mut trajs = trajectories.write().await;
             trajs.clear();
         }
+        velocity_setpoints.lock().await.clear();
         
         // 重置所有PID控制器
         {
@@ -596,6 +1222,12 @@ impl RealtimeController {
             }
         }
         
+        if let Some(bridge) = sim_bridge {
+            if let Err(e) = bridge.send_stop().await {
+                warn!("发送仿真停止命令失败: {}", e);
+            }
+        }
+        
         // TODO: 发送停止命令到硬件
         warn!("紧急停止激活");
     }
@@ -604,22 +1236,29 @@ impl RealtimeController {
     async fn process_command_queue(
         command_queue: &Arc<Mutex<VecDeque<MotionCommand>>>,
         trajectories: &Arc<RwLock<HashMap<String, TrajectoryGenerator>>>,
-        sensor_data: &Arc<RwLock<SensorData>>,
+        velocity_setpoints: &Arc<Mutex<HashMap<String, VelocitySetpoint>>>,
+        sensor_data: &SensorData,
         config: &RealtimeConfig,
-    ) {
+    ) -> usize {
         let mut queue = command_queue.lock().await;
+        let mut processed = 0;
         
         while let Some(command) = queue.pop_front() {
+            processed += 1;
+            
             // 检查命令超时
+            let id = command.correlation_id.as_deref().unwrap_or("-");
             let command_age = current_timestamp() - command.timestamp;
             if command_age > config.command_timeout_ms {
-                warn!("命令超时，丢弃: {:?}", command);
+                warn!("[{}] 命令超时，丢弃: {:?}", id, command);
                 continue;
             }
+            debug!("[{}] 执行运动命令: {} {:?}", id, command.joint_name, command.command_type);
             
             match command.command_type {
                 CommandType::Position => {
                     if let Some(target_position) = command.target_position {
+                        velocity_setpoints.lock().await.remove(&command.joint_name);
                         Self::create_position_trajectory(
                             &command.joint_name,
                             target_position,
@@ -629,8 +1268,13 @@ impl RealtimeController {
                         ).await;
                     }
                 },
+                CommandType::Velocity => {
+                    if let Some(velocity) = command.target_velocity.filter(|v| v.is_finite()) {
+                        Self::set_joint_velocity(&command.joint_name, velocity, trajectories, velocity_setpoints, sensor_data, config).await;
+                    }
+                },
                 CommandType::Stop => {
-                    Self::stop_joint(&command.joint_name, trajectories).await;
+                    Self::stop_joint(&command.joint_name, trajectories, velocity_setpoints).await;
                 },
                 CommandType::EmergencyStop => {
                     // 紧急停止在主循环中处理
@@ -642,6 +1286,8 @@ impl RealtimeController {
                 }
             }
         }
+        
+        processed
     }
     
     /// 创建位置轨迹
@@ -649,20 +1295,24 @@ impl RealtimeController {
         joint_name: &str,
         target_position: f64,
         trajectories: &Arc<RwLock<HashMap<String, TrajectoryGenerator>>>,
-        sensor_data: &Arc<RwLock<SensorData>>,
+        sensor_data: &SensorData,
         config: &RealtimeConfig,
     ) {
-        let sensor_data = sensor_data.read().await;
-        
         if let Some(joint_state) = sensor_data.joint_states.get(joint_name) {
-            let start_position = joint_state.position;
+            // 轨迹在展开后的位置上规划，连续旋转关节跨越±π时不会绕远路
+            let start_position = joint_state.unwrapped_position;
             let start_velocity = joint_state.velocity;
             
             // 检查关节限制
             if let Some(limits) = config.joint_limits.get(joint_name) {
-                let clamped_target = clamp(target_position, limits.min_position, limits.max_position);
+                let clamped_target = if limits.continuous {
+                    // 目标视为回绕角度，沿最短方向转过去
+                    start_position + normalize_angle(target_position - joint_state.position)
+                } else {
+                    clamp(target_position, limits.min_position.radians(), limits.max_position.radians())
+                };
                 
-                if clamped_target != target_position {
+                if !limits.continuous && clamped_target != target_position {
                     warn!("关节 {} 目标位置 {} 超出限制，限制为 {}", 
                           joint_name, target_position, clamped_target);
                 }
@@ -671,7 +1321,7 @@ impl RealtimeController {
                     start_position,
                     clamped_target,
                     start_velocity,
-                    limits.max_velocity,
+                    limits.max_velocity.radians_per_second(),
                     limits.max_acceleration,
                 );
                 
@@ -683,25 +1333,81 @@ impl RealtimeController {
         }
     }
     
+    /// 处理持续振荡：按检测器给出的比例重设增益，停止关节时同时清空积分
+    async fn handle_oscillation(
+        report: &OscillationReport,
+        pid_controllers: &Arc<RwLock<HashMap<String, PIDController>>>,
+        config: &RealtimeConfig,
+        rate_ratio: f64,
+    ) {
+        match report.action {
+            OscillationAction::ReduceGains => warn!(
+                "关节 {} 持续振荡（{:.1} Hz，峰峰值 {:.4} rad，{:.1} 秒），增益降到 {:.0}%",
+                report.joint_name, report.frequency_hz, report.amplitude, report.duration_secs, report.gain_scale * 100.0
+            ),
+            OscillationAction::HaltJoint => warn!(
+                "关节 {} 持续振荡（{:.1} Hz，峰峰值 {:.4} rad，{:.1} 秒），已停止该关节，检查PID参数后复位",
+                report.joint_name, report.frequency_hz, report.amplitude, report.duration_secs
+            ),
+        }
+        
+        let mut controllers = pid_controllers.write().await;
+        if let (Some(controller), Some(gains)) = (controllers.get_mut(&report.joint_name), config.pid_gains.get(&report.joint_name)) {
+            controller.gains = scale_gains(&rescale_gains(gains, rate_ratio), report.gain_scale);
+            if report.action == OscillationAction::HaltJoint {
+                controller.integral = 0.0;
+            }
+        }
+    }
+    
+    /// 设置或刷新关节的速度设定点，速度模式接管该关节时结束正在执行的轨迹
+    async fn set_joint_velocity(
+        joint_name: &str,
+        velocity: f64,
+        trajectories: &Arc<RwLock<HashMap<String, TrajectoryGenerator>>>,
+        velocity_setpoints: &Arc<Mutex<HashMap<String, VelocitySetpoint>>>,
+        sensor_data: &SensorData,
+        config: &RealtimeConfig,
+    ) {
+        let (Some(limits), Some(joint_state)) = (config.joint_limits.get(joint_name), sensor_data.joint_states.get(joint_name)) else {
+            return;
+        };
+        
+        let max_velocity = limits.max_velocity.radians_per_second();
+        let clamped = clamp(velocity, -max_velocity, max_velocity);
+        if clamped != velocity {
+            warn!("关节 {} 目标速度 {} 超出限制，限制为 {}", joint_name, velocity, clamped);
+        }
+        
+        let now = Instant::now();
+        trajectories.write().await.remove(joint_name);
+        velocity_setpoints.lock().await
+            .entry(joint_name.to_string())
+            .or_insert_with(|| VelocitySetpoint::new(joint_state.unwrapped_position, joint_state.velocity, now))
+            .refresh(clamped, now);
+    }
+    
     /// 停止关节
     async fn stop_joint(
         joint_name: &str,
         trajectories: &Arc<RwLock<HashMap<String, TrajectoryGenerator>>>,
+        velocity_setpoints: &Arc<Mutex<HashMap<String, VelocitySetpoint>>>,
     ) {
         let mut trajs = trajectories.write().await;
         trajs.remove(joint_name);
+        velocity_setpoints.lock().await.remove(joint_name);
         debug!("停止关节 {} 的运动", joint_name);
     }
     
-    /// 更新控制
+    /// 更新控制，返回本周期各活动轨迹的目标位置
     async fn update_control(
         pid_controllers: &Arc<RwLock<HashMap<String, PIDController>>>,
         trajectories: &Arc<RwLock<HashMap<String, TrajectoryGenerator>>>,
-        sensor_data: &Arc<RwLock<SensorData>>,
-        config: &RealtimeConfig,
-    ) {
+        sensor_data: &SensorData,
+        stiffness: f64,
+    ) -> HashMap<String, f64> {
         let now = Instant::now();
-        let sensor_data = sensor_data.read().await;
+        let mut targets = HashMap::new();
         let mut controllers = pid_controllers.write().await;
         let mut trajs = trajectories.write().await;
         
@@ -715,69 +1421,209 @@ impl RealtimeController {
                 sensor_data.joint_states.get(joint_name)
             ) {
                 let target_position = trajectory.get_position(now);
-                let current_position = joint_state.position;
+                let current_position = joint_state.unwrapped_position;
                 
-                let control_output = controller.update(target_position, current_position);
+                // 按轨迹的期望速度和加速度补偿惯量和摩擦
+                let feedforward = controller.dynamics.feedforward(trajectory.get_velocity(now), trajectory.get_acceleration(now));
+                let control_output = (controller.update(target_position, current_position) + feedforward) * stiffness;
+                controller.last_output = control_output;
+                targets.insert(joint_name.clone(), target_position);
                 
                 // TODO: 发送控制输出到硬件
                 debug!("关节 {} 控制输出: {:.3} (目标: {:.3}, 当前: {:.3})", 
                        joint_name, control_output, target_position, current_position);
             }
         }
+        
+        targets
+    }
+    
+    /// 速度模式：由速度设定点积分目标位置并计算控制输出，返回本周期的目标位置
+    ///
+    /// 超时的设定点减速到零后移除，关节停在减速结束的位置
+    async fn update_velocity_control(
+        velocity_setpoints: &Arc<Mutex<HashMap<String, VelocitySetpoint>>>,
+        pid_controllers: &Arc<RwLock<HashMap<String, PIDController>>>,
+        sensor_data: &SensorData,
+        stiffness: f64,
+        dt: f64,
+        config: &RealtimeConfig,
+    ) -> HashMap<String, f64> {
+        let now = Instant::now();
+        let timeout = Duration::from_millis(config.command_timeout_ms);
+        let mut targets = HashMap::new();
+        let mut controllers = pid_controllers.write().await;
+        let mut setpoints = velocity_setpoints.lock().await;
+        
+        setpoints.retain(|joint_name, setpoint| {
+            let Some(limits) = config.joint_limits.get(joint_name) else {
+                return false;
+            };
+            
+            let previous_velocity = setpoint.velocity;
+            let mut target_position = setpoint.step(now, dt, timeout, limits.max_acceleration);
+            if !limits.continuous {
+                let clamped = clamp(target_position, limits.min_position.radians(), limits.max_position.radians());
+                if clamped != target_position {
+                    // 到达限位时停在限位处
+                    target_position = clamped;
+                    setpoint.position = clamped;
+                    setpoint.velocity = 0.0;
+                }
+            }
+            
+            if let (Some(controller), Some(joint_state)) = (
+                controllers.get_mut(joint_name),
+                sensor_data.joint_states.get(joint_name)
+            ) {
+                let acceleration = (setpoint.velocity - previous_velocity) / dt;
+                let feedforward = controller.dynamics.feedforward(setpoint.velocity, acceleration);
+                let control_output = (controller.update(target_position, joint_state.unwrapped_position) + feedforward) * stiffness;
+                controller.last_output = control_output;
+                targets.insert(joint_name.clone(), target_position);
+            }
+            
+            if setpoint.is_expired(now, timeout) {
+                warn!("关节 {} 的速度命令超过 {} ms 未刷新，已减速停止", joint_name, config.command_timeout_ms);
+                return false;
+            }
+            true
+        });
+        
+        targets
+    }
+    
+    /// 更新空闲微动，返回本周期的微动目标位置
+    async fn update_idle_motion(
+        idle_motion: &Arc<Mutex<IdleMotionGenerator>>,
+        had_commands: bool,
+        pid_controllers: &Arc<RwLock<HashMap<String, PIDController>>>,
+        trajectories: &Arc<RwLock<HashMap<String, TrajectoryGenerator>>>,
+        sensor_data: &SensorData,
+        stiffness: f64,
+        config: &RealtimeConfig,
+    ) -> HashMap<String, f64> {
+        let mut idle = idle_motion.lock().await;
+        
+        if had_commands || !trajectories.read().await.is_empty() {
+            idle.notify_activity();
+            return HashMap::new();
+        }
+        
+        let targets = idle.update(Instant::now(), &sensor_data.joint_states, &config.joint_limits);
+        if targets.is_empty() {
+            return targets;
+        }
+        
+        let mut controllers = pid_controllers.write().await;
+        for (joint_name, target_position) in &targets {
+            if let (Some(controller), Some(joint_state)) = (
+                controllers.get_mut(joint_name),
+                sensor_data.joint_states.get(joint_name)
+            ) {
+                let control_output = controller.update(*target_position, joint_state.unwrapped_position) * stiffness;
+                controller.last_output = control_output;
+                
+                // TODO: 发送控制输出到硬件
+                debug!("关节 {} 空闲微动输出: {:.3} (目标: {:.3}, 当前: {:.3})", 
+                       joint_name, control_output, target_position, joint_state.unwrapped_position);
+            }
+        }
+        
+        targets
     }
     
     /// 启动传感器循环
     async fn start_sensor_loop(&mut self) -> Result<()> {
         let sensor_period = Duration::from_secs_f64(1.0 / self.config.sensor_update_rate);
         
-        let is_running = Arc::clone(&self.is_running);
-        let status = Arc::clone(&self.status);
+        let shutdown = self.tasks.token();
+        let stats = Arc::clone(&self.stats);
         let sensor_data = Arc::clone(&self.sensor_data);
+        let sim_bridge = self.sim_bridge.clone();
+        let hardware_io = self.hardware_io.clone();
+        let transforms = Arc::clone(&self.transforms);
         let config = self.config.clone();
         
-        let handle = tokio::spawn(async move {
+        self.tasks.spawn("传感器循环", async move {
             Self::sensor_loop(
                 sensor_period,
-                is_running,
-                status,
+                shutdown,
+                stats,
                 sensor_data,
+                sim_bridge,
+                hardware_io,
+                transforms,
                 config,
             ).await
         });
         
-        self.sensor_handle = Some(handle);
         Ok(())
     }
     
     /// 传感器循环
+    #[allow(clippy::too_many_arguments)]
     async fn sensor_loop(
         sensor_period: Duration,
-        is_running: Arc<RwLock<bool>>,
-        status: Arc<RwLock<RealtimeStatus>>,
-        sensor_data: Arc<RwLock<SensorData>>,
+        shutdown: CancellationToken,
+        stats: Arc<RealtimeStats>,
+        sensor_data: Arc<ArcSwap<SensorData>>,
+        sim_bridge: Option<Arc<SimBridge>>,
+        hardware_io: Option<Arc<HardwareIoLink>>,
+        transforms: Arc<RwLock<TransformTree>>,
         config: RealtimeConfig,
     ) {
         let mut interval = interval(sensor_period);
         let mut loop_count = 0u64;
         let mut last_stats_update = Instant::now();
+        let mut estimators = HashMap::new();
+        let mut last_update = Instant::now();
+        let mut last_bus_cycle = 0;
         
         loop {
-            interval.tick().await;
-            
-            // 检查是否应该停止
-            if !*is_running.read().await {
-                break;
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
             }
             
             // 模拟传感器数据更新
-            Self::update_sensor_data(&sensor_data, &config).await;
+            let dt = last_update.elapsed().as_secs_f64();
+            last_update = Instant::now();
+            // 传感器循环是唯一的写入方，在上一份快照的副本上更新后整体替换
+            let mut data = SensorData::clone(&sensor_data.load());
+            let sim_state = sim_bridge.as_ref().and_then(|bridge| bridge.poll_state());
+            {
+                // I/O线程完成过总线事务后使用其最新读数，否则使用仿真状态或模拟数据
+                let mut bus_reader = hardware_io.as_ref().map(|io| io.state.lock().unwrap_or_else(|e| e.into_inner()));
+                let measured = bus_reader.as_mut()
+                    .map(|reader| reader.latest())
+                    .filter(|state| state.cycle > 0)
+                    .inspect(|state| {
+                        if state.cycle != last_bus_cycle {
+                            last_bus_cycle = state.cycle;
+                            if let Some(round_trip) = state.round_trip {
+                                stats.bus_latency.record(round_trip);
+                            }
+                        }
+                    })
+                    .map(|state| (&state.positions, &state.efforts))
+                    .or(sim_state.as_ref().map(|state| (&state.positions, &state.efforts)));
+                Self::update_sensor_data(&mut data, &config, &mut estimators, dt, measured);
+            }
+            
+            // 按关节状态更新坐标变换；树正被读取或标定时跳过本周期，传感器循环不等待
+            if let Ok(mut tree) = transforms.try_write() {
+                if let Err(e) = tree.update_from_joint_states(&data.joint_states, data.timestamp) {
+                    debug!("更新坐标变换失败: {}", e);
+                }
+            }
+            sensor_data.store(Arc::new(data));
             
             loop_count += 1;
             
             // 更新统计
             if last_stats_update.elapsed() >= Duration::from_secs(1) {
-                let mut status = status.write().await;
-                status.sensor_update_frequency = loop_count as f64 / last_stats_update.elapsed().as_secs_f64();
+                stats.sensor_update_frequency.store(loop_count as f64 / last_stats_update.elapsed().as_secs_f64());
                 
                 loop_count = 0;
                 last_stats_update = Instant::now();
@@ -787,20 +1633,35 @@ impl RealtimeController {
         info!("传感器循环结束");
     }
     
-    /// 更新传感器数据（模拟）
-    async fn update_sensor_data(
-        sensor_data: &Arc<RwLock<SensorData>>,
+    /// 更新传感器数据（模拟），measured为总线或仿真器读回的关节位置和力矩
+    fn update_sensor_data(
+        data: &mut SensorData,
         config: &RealtimeConfig,
+        estimators: &mut HashMap<String, JointStateEstimator>,
+        dt: f64,
+        measured: Option<MeasuredJoints>,
     ) {
-        let mut data = sensor_data.write().await;
-        
         // 模拟关节状态更新
-        for (joint_name, _) in &config.joint_limits {
+        for (joint_name, limits) in &config.joint_limits {
             if let Some(joint_state) = data.joint_states.get_mut(joint_name) {
-                // 简单的模拟：添加小的随机噪声
-                joint_state.position += (rand::random::<f64>() - 0.5) * 0.001;
-                joint_state.velocity += (rand::random::<f64>() - 0.5) * 0.01;
-                joint_state.effort += (rand::random::<f64>() - 0.5) * 0.1;
+                let position = match measured.and_then(|(positions, _)| positions.get(joint_name)) {
+                    Some(position) => *position,
+                    // 简单的模拟：原始位置读数带有小的随机噪声
+                    None => joint_state.position + (rand::random::<f64>() - 0.5) * 0.001,
+                };
+                joint_state.update_position(position, limits.continuous);
+                
+                // 由位置读数估计平滑的位置、速度和加速度
+                let estimator = estimators.entry(joint_name.clone()).or_insert_with(|| {
+                    JointStateEstimator::new(config.state_estimation.params_for(joint_name).clone())
+                });
+                let estimate = estimator.update(joint_state.unwrapped_position, dt);
+                apply_estimate(joint_state, &estimate, limits.continuous);
+                
+                match measured.and_then(|(_, efforts)| efforts.get(joint_name)) {
+                    Some(effort) => joint_state.effort = *effort,
+                    None => joint_state.effort += (rand::random::<f64>() - 0.5) * 0.1,
+                }
             }
         }
         
@@ -828,61 +1689,666 @@ impl RealtimeController {
         data.timestamp = current_timestamp();
     }
     
-    /// 添加运动命令
-    pub async fn add_command(&self, command: MotionCommand) -> Result<()> {
+    /// 提交带来源的运动命令，经过限流、数值检查和仲裁后加入队列，被拒绝时返回错误
+    ///
+    /// 命令和处理结果都会写入审计日志。
+    pub async fn submit_command(&self, origin: &CommandOrigin, mut command: MotionCommand) -> Result<()> {
+        command.correlation_id.get_or_insert_with(new_id);
+        let payload = serde_json::to_value(&command).unwrap_or_default();
+        let result = self.arbitrate_and_queue(origin, command).await;
+        self.audit_log.lock().await.record(origin, payload, AuditResult::from_result(&result));
+        result
+    }
+    
+    async fn arbitrate_and_queue(&self, origin: &CommandOrigin, command: MotionCommand) -> Result<()> {
+        let emergency = matches!(command.command_type, CommandType::EmergencyStop);
+        
+        command.validate()?;
+        
+        if !self.config.joint_limits.contains_key(&command.joint_name) {
+            return Err(anyhow::anyhow!("未知关节: {}", command.joint_name));
+        }
+        
+        if !emergency && self.oscillation.lock().await.is_halted(&command.joint_name) {
+            return Err(anyhow::anyhow!("关节 {} 因持续振荡已停止，需要先复位", command.joint_name));
+        }
+        
+        if let Err(e) = self.command_filter.lock().await.check_rate(origin, emergency) {
+            debug!("拒绝 {:?}:{} 的命令: {}", origin.source, origin.client_id, e);
+            return Err(e);
+        }
+        
+        if !emergency {
+            self.check_safety_profile(&command).await?;
+        }
+        
+        if let Arbitration::Rejected(reason) = self.arbiter.lock().await.arbitrate(origin, emergency) {
+            debug!("拒绝 {:?}:{} 的命令: {}", origin.source, origin.client_id, reason);
+            return Err(anyhow::anyhow!("命令被拒绝: {}", reason));
+        }
+        
+        self.add_command(command).await
+    }
+    
+    /// 提交外部接口的命令JSON，按来源声明的角度单位解析
+    pub async fn submit_external_command(&self, origin: &CommandOrigin, json: &str) -> Result<()> {
+        // 审计日志保存客户端发来的原始内容，而不是换算单位后的命令
+        let payload = serde_json::from_str(json).unwrap_or_else(|_| serde_json::Value::String(json.to_string()));
+        let parsed = self.command_filter.lock().await.parse(origin.source, json);
+        let result = match parsed {
+            Ok(mut command) => {
+                command.correlation_id.get_or_insert_with(new_id);
+                self.arbitrate_and_queue(origin, command).await
+            },
+            Err(e) => Err(e),
+        };
+        self.audit_log.lock().await.record(origin, payload, AuditResult::from_result(&result));
+        result
+    }
+    
+    /// 提交末端速度（twist）：按当前关节位置换算为关节速度后作为速度命令排队。
+    /// 一个twist只占用一次限流和仲裁；客户端停止发送后由命令超时看门狗让关节停下
+    pub async fn submit_twist(&self, origin: &CommandOrigin, twist: Twist) -> Result<TwistSolution> {
+        let result = self.queue_twist(origin, &twist).await;
+        let payload = serde_json::json!({ "type": "twist", "linear": twist.linear, "angular": twist.angular });
+        self.audit_log.lock().await.record(origin, payload, AuditResult::from_result(&result));
+        result
+    }
+    
+    async fn queue_twist(&self, origin: &CommandOrigin, twist: &Twist) -> Result<TwistSolution> {
+        let joints = self.twist_solver.joints();
+        for joint_name in &joints {
+            if !self.config.joint_limits.contains_key(*joint_name) {
+                return Err(anyhow::anyhow!("未知关节: {}", joint_name));
+            }
+            if self.oscillation.lock().await.is_halted(joint_name) {
+                return Err(anyhow::anyhow!("关节 {} 因持续振荡已停止，需要先复位", joint_name));
+            }
+        }
+        
+        if let Err(e) = self.command_filter.lock().await.check_rate(origin, false) {
+            debug!("拒绝 {:?}:{} 的末端速度命令: {}", origin.source, origin.client_id, e);
+            return Err(e);
+        }
+        
+        // 关节速度上限按安全档位缩小，求解时整体缩放而不是拒绝
+        let profile = *self.safety_profile.read().await;
+        let scale = self.config.profile_scales.get(&profile).copied().unwrap_or(1.0);
+        let max_velocities: HashMap<String, f64> = joints.iter()
+            .map(|name| (name.to_string(), self.config.joint_limits[*name].max_velocity.radians_per_second() * scale))
+            .collect();
+        
+        let sensor_data = self.sensor_data.load();
+        let solution = {
+            let transforms = self.transforms.read().await;
+            self.twist_solver.solve(twist, &sensor_data.joint_states, &transforms, &max_velocities)?
+        };
+        
+        if let Arbitration::Rejected(reason) = self.arbiter.lock().await.arbitrate(origin, false) {
+            debug!("拒绝 {:?}:{} 的末端速度命令: {}", origin.source, origin.client_id, reason);
+            return Err(anyhow::anyhow!("命令被拒绝: {}", reason));
+        }
+        
+        for command in solution.to_commands() {
+            self.add_command(command).await?;
+        }
+        Ok(solution)
+    }
+    
+    /// 检查命令是否在当前安全档位允许的行程和速度之内
+    async fn check_safety_profile(&self, command: &MotionCommand) -> Result<()> {
+        let profile = *self.safety_profile.read().await;
+        let scale = self.config.profile_scales.get(&profile).copied().unwrap_or(1.0);
+        // 完整行程下超限的位置由控制循环裁剪
+        if scale >= 1.0 {
+            return Ok(());
+        }
+        
+        let limits = &self.config.joint_limits[&command.joint_name];
+        
+        if let (Some(position), false) = (command.target_position, limits.continuous) {
+            let (min, max) = (limits.min_position.radians(), limits.max_position.radians());
+            let center = (min + max) / 2.0;
+            if (position - center).abs() > (max - min) / 2.0 * scale {
+                return Err(anyhow::anyhow!(
+                    "关节 {} 的目标位置 {:.3} rad 超出安全档位 {:?} 允许的范围", command.joint_name, position, profile
+                ));
+            }
+        }
+        
+        if let Some(velocity) = command.target_velocity {
+            if velocity.abs() > limits.max_velocity.radians_per_second() * scale {
+                return Err(anyhow::anyhow!(
+                    "关节 {} 的目标速度 {:.3} rad/s 超出安全档位 {:?} 允许的范围", command.joint_name, velocity, profile
+                ));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// 当前安全档位
+    pub async fn safety_profile(&self) -> SafetyProfile {
+        *self.safety_profile.read().await
+    }
+    
+    /// 修改安全档位，只有管理员可以修改，修改同样写入审计日志
+    pub async fn set_safety_profile(&self, origin: &CommandOrigin, profile: SafetyProfile) -> Result<()> {
+        let result = origin.authorize(Permission::Configure);
+        if result.is_ok() {
+            let previous = std::mem::replace(&mut *self.safety_profile.write().await, profile);
+            info!("{:?}:{} 把安全档位从 {:?} 改为 {:?}", origin.source, origin.client_id, previous, profile);
+        }
+        
+        let payload = serde_json::json!({ "type": "set_safety_profile", "profile": profile });
+        self.audit_log.lock().await.record(origin, payload, AuditResult::from_result(&result));
+        result
+    }
+    
+    /// 查询命令审计日志
+    pub async fn query_audit(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>> {
+        self.audit_log.lock().await.query(query)
+    }
+    
+    /// 各来源被限流拒绝的命令数
+    pub async fn rate_limited_counts(&self) -> HashMap<CommandSource, u64> {
+        self.command_filter.lock().await.rejected_counts()
+    }
+    
+    /// 申请独占控制权
+    pub async fn acquire_control(&self, origin: &CommandOrigin, duration_ms: Option<u64>) -> Result<ControlLease> {
+        self.arbiter.lock().await.acquire(origin, duration_ms)
+    }
+    
+    /// 释放独占控制权
+    pub async fn release_control(&self, origin: &CommandOrigin) -> Result<()> {
+        self.arbiter.lock().await.release(origin)
+    }
+    
+    /// 当前控制权持有者
+    pub async fn control_owner(&self) -> Option<ControlLease> {
+        self.arbiter.lock().await.owner()
+    }
+    
+    /// 添加运动命令（不经过仲裁，供内部模块使用）
+    pub async fn add_command(&self, mut command: MotionCommand) -> Result<()> {
+        // 真实命令到达时立即停止空闲微动
+        self.idle_motion.lock().await.notify_activity();
+        
+        let id = command.correlation_id.get_or_insert_with(new_id);
+        debug!("[{}] 运动命令入队: {} {:?}", id, command.joint_name, command.command_type);
+        
         let mut queue = self.command_queue.lock().await;
         queue.push_back(command);
         
-        // 更新状态
-        {
-            let mut status = self.status.write().await;
-            status.active_commands = queue.len();
-            status.last_command_timestamp = current_timestamp();
+        self.stats.active_commands.store(queue.len(), Ordering::Relaxed);
+        self.stats.last_command_timestamp.store(current_timestamp(), Ordering::Relaxed);
+        
+        Ok(())
+    }
+    
+    /// 按采样率录制一段时间内的关节位置和速度
+    pub async fn record_trajectory(&self, name: &str, duration: Duration, sample_rate: f64) -> Result<TrajectoryFile> {
+        if sample_rate <= 0.0 {
+            return Err(anyhow::anyhow!("采样率必须为正数"));
+        }
+        
+        let mut joints: Vec<String> = self.config.joint_limits.keys().cloned().collect();
+        joints.sort();
+        let mut trajectory = TrajectoryFile::new(name, joints);
+        
+        let start = Instant::now();
+        let mut interval = interval(Duration::from_secs_f64(1.0 / sample_rate));
+        while start.elapsed() <= duration {
+            interval.tick().await;
+            
+            let sensor_data = self.sensor_data.load();
+            let positions = sensor_data.joint_states.iter()
+                .map(|(name, state)| (name.clone(), state.unwrapped_position))
+                .collect();
+            let velocities = sensor_data.joint_states.iter()
+                .map(|(name, state)| (name.clone(), state.velocity))
+                .collect();
+            trajectory.push_frame(start.elapsed().as_secs_f64(), &positions, Some(&velocities));
+        }
+        
+        Ok(trajectory)
+    }
+    
+    /// 按采样率导出当前正在执行的规划轨迹（从现在到最后一条轨迹结束）
+    pub async fn planned_trajectory(&self, name: &str, sample_rate: f64) -> Result<TrajectoryFile> {
+        if sample_rate <= 0.0 {
+            return Err(anyhow::anyhow!("采样率必须为正数"));
+        }
+        
+        let trajectories = self.trajectories.read().await;
+        let mut joints: Vec<String> = trajectories.keys().cloned().collect();
+        joints.sort();
+        let mut planned = TrajectoryFile::new(name, joints);
+        
+        let now = Instant::now();
+        let end = trajectories.values().map(|t| t.start_time + t.duration).max().unwrap_or(now);
+        let steps = (end.saturating_duration_since(now).as_secs_f64() * sample_rate).ceil() as usize;
+        
+        for step in 0..=steps {
+            let t = step as f64 / sample_rate;
+            let time = now + Duration::from_secs_f64(t);
+            let positions = trajectories.iter().map(|(name, g)| (name.clone(), g.get_position(time))).collect();
+            let velocities = trajectories.iter().map(|(name, g)| (name.clone(), g.get_velocity(time))).collect();
+            planned.push_frame(t, &positions, Some(&velocities));
+        }
+        
+        Ok(planned)
+    }
+    
+    /// 回放轨迹文件：按帧时间依次下发位置命令，轨迹中的关节必须都存在
+    pub async fn play_trajectory(&self, trajectory: &TrajectoryFile) -> Result<()> {
+        trajectory.validate()?;
+        
+        if let Some(joint) = trajectory.joints.iter().find(|j| !self.config.joint_limits.contains_key(*j)) {
+            return Err(anyhow::anyhow!("轨迹中的关节 '{}' 不存在", joint));
+        }
+        
+        info!("回放轨迹 {}（{}帧，{:.2}秒）", trajectory.name, trajectory.frames.len(), trajectory.duration());
+        let start = Instant::now();
+        
+        for (index, frame) in trajectory.frames.iter().enumerate() {
+            if self.stats.emergency_stop.load(Ordering::SeqCst) {
+                return Err(anyhow::anyhow!("紧急停止，轨迹回放中断"));
+            }
+            
+            let frame_time = start + Duration::from_secs_f64(frame.time);
+            tokio::time::sleep_until(frame_time.into()).await;
+            
+            let duration = trajectory.frames.get(index + 1).map(|next| next.time - frame.time);
+            for (joint_name, &position) in trajectory.joints.iter().zip(&frame.positions) {
+                let command = MotionCommand::builder(joint_name.clone(), CommandType::Position)
+                    .position(position)
+                    .duration(duration)
+                    .build()?;
+                self.add_command(command).await?;
+            }
         }
         
         Ok(())
     }
     
-    /// 设置紧急停止
-    pub async fn set_emergency_stop(&self, stop: bool) -> Result<()> {
-        let mut emergency_stop = self.emergency_stop.write().await;
-        *emergency_stop = stop;
+    /// 按编舞文件中本机器人的轨道演出，提示中的开始时间按时间同步换算为本地时间
+    ///
+    /// 在开始前检查所有动画的关节；提示到达时开始时间已过则不演出，避免与其他机器人错位。
+    pub async fn play_choreography(&self, choreography: &Choreography, robot: &RobotIdentity, cue: ShowCue) -> Result<()> {
+        let schedule = choreography.schedule(robot)?;
+        for (_, animation) in &schedule {
+            if let Some(joint) = animation.joints.iter().find(|j| !self.config.joint_limits.contains_key(*j)) {
+                return Err(anyhow::anyhow!("动画 '{}' 中的关节 '{}' 不存在", animation.name, joint));
+            }
+        }
         
-        // 更新状态
+        let local_start = cue.local_start(&*self.time_sync.read().await);
+        let now = current_timestamp();
+        if local_start < now {
+            return Err(anyhow::anyhow!("演出开始时间已过 {}ms", now - local_start));
+        }
+        
+        info!("编舞 {} 将在 {}ms 后开始，本机 {} 段动画", choreography.name, local_start - now, schedule.len());
+        let start = Instant::now() + Duration::from_millis(local_start - now);
+        for (at, animation) in schedule {
+            tokio::time::sleep_until((start + Duration::from_secs_f64(at)).into()).await;
+            self.play_trajectory(animation).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// 所有关节移动到初始姿态（零位，限制在关节范围内），overrides可指定个别关节的位置
+    pub async fn move_to_home(&self, duration: f64, overrides: &HashMap<String, f64>) -> Result<()> {
+        for (joint_name, limits) in &self.config.joint_limits {
+            let target = overrides.get(joint_name).copied().unwrap_or(0.0);
+            
+            let command = MotionCommand::builder(joint_name.clone(), CommandType::Position)
+                .position(clamp(target, limits.min_position.radians(), limits.max_position.radians()))
+                .duration(duration)
+                .build()?;
+            self.add_command(command).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// 开始软启动：以当前位置作为设定点保持不动，并逐步提升控制刚度
+    ///
+    /// 开启扭矩或解除急停时调用，避免关节跳回旧的设定点。
+    pub async fn begin_soft_start(&self) {
+        let config = &self.config.soft_start;
+        if !config.enabled {
+            return;
+        }
+        
+        let now = Instant::now();
+        let ramp = SoftStartRamp::new(config, now);
+        
+        // 丢弃排队中的旧命令，用当前位置生成保持轨迹
+        self.command_queue.lock().await.clear();
         {
-            let mut status = self.status.write().await;
-            status.emergency_stop = stop;
+            let sensor_data = self.sensor_data.load_full();
+            let mut trajectories = self.trajectories.write().await;
+            trajectories.clear();
+            
+            for (joint_name, joint_state) in &sensor_data.joint_states {
+                trajectories.insert(joint_name.clone(), TrajectoryGenerator {
+                    start_position: joint_state.unwrapped_position,
+                    target_position: joint_state.unwrapped_position,
+                    start_time: now,
+                    duration: ramp.duration(),
+                });
+            }
+        }
+        
+        for controller in self.pid_controllers.write().await.values_mut() {
+            controller.reset();
+        }
+        self.idle_motion.lock().await.notify_activity();
+        
+        *self.soft_start.write().await = Some(ramp);
+        info!("开始软启动，时长 {}ms", config.duration_ms);
+    }
+    
+    /// 软启动是否进行中
+    pub async fn is_soft_starting(&self) -> bool {
+        self.soft_start.read().await.as_ref().is_some_and(|ramp| !ramp.is_finished(Instant::now()))
+    }
+    
+    /// 设置关节的齿隙/死区补偿参数
+    pub async fn set_backlash_params(&self, joint_name: &str, params: BacklashParams) -> Result<()> {
+        params.validate()?;
+        
+        let mut controllers = self.pid_controllers.write().await;
+        let controller = controllers.get_mut(joint_name)
+            .ok_or_else(|| anyhow::anyhow!("关节 '{}' 没有控制器", joint_name))?;
+        controller.compensator.set_params(params);
+        Ok(())
+    }
+    
+    /// 获取各关节的齿隙/死区补偿参数
+    pub async fn backlash_params(&self) -> HashMap<String, BacklashParams> {
+        self.pid_controllers.read().await.iter()
+            .map(|(name, controller)| (name.clone(), controller.compensator.params().clone()))
+            .collect()
+    }
+    
+    /// 辨识关节齿隙：以当前位置为中心做往返扫描，比较命令和稳定后的实测位置
+    ///
+    /// 估计结果会立即生效（死区参数保持不变）。需要控制器运行且关节能自由移动。
+    pub async fn identify_backlash(&self, joint_name: &str, amplitude: f64, steps: usize, settle_ms: u64) -> Result<BacklashParams> {
+        let center = self.sensor_data.load().joint_states.get(joint_name)
+            .map(|state| state.position)
+            .ok_or_else(|| anyhow::anyhow!("关节 '{}' 不存在", joint_name))?;
+        
+        info!("开始辨识关节 {} 的齿隙，幅度 {:.3} rad", joint_name, amplitude);
+        
+        // 扫描时不做齿隙补偿，否则测到的是补偿后的残差
+        let previous = self.backlash_params().await.remove(joint_name).unwrap_or_default();
+        self.set_backlash_params(joint_name, BacklashParams { backlash: 0.0, ..previous.clone() }).await?;
+        
+        let mut samples = Vec::new();
+        for target in backlash_sweep(center, amplitude, steps) {
+            self.add_command(MotionCommand::builder(joint_name, CommandType::Position).position(target).build()?).await?;
+            sleep(Duration::from_millis(settle_ms)).await;
+            
+            let measured = self.sensor_data.load().joint_states[joint_name].unwrapped_position;
+            samples.push((target, measured));
+        }
+        
+        let Some(backlash) = estimate_backlash(&samples) else {
+            self.set_backlash_params(joint_name, previous).await?;
+            return Err(anyhow::anyhow!("关节 '{}' 的扫描数据不足，无法估计齿隙", joint_name));
+        };
+        
+        let params = BacklashParams { backlash, ..previous };
+        self.set_backlash_params(joint_name, params.clone()).await?;
+        
+        info!("关节 {} 齿隙估计为 {:.4} rad", joint_name, backlash);
+        Ok(params)
+    }
+    
+    /// 设置单个关节的动力学参数，立即用于前馈补偿
+    pub async fn set_dynamics_params(&self, joint_name: &str, params: DynamicsParams) -> Result<()> {
+        params.validate()?;
+        
+        let mut controllers = self.pid_controllers.write().await;
+        let controller = controllers.get_mut(joint_name)
+            .ok_or_else(|| anyhow::anyhow!("关节 '{}' 没有控制器", joint_name))?;
+        controller.dynamics = params;
+        Ok(())
+    }
+    
+    /// 获取各关节的动力学参数
+    pub async fn dynamics_params(&self) -> HashMap<String, DynamicsParams> {
+        self.pid_controllers.read().await.iter()
+            .map(|(name, controller)| (name.clone(), controller.dynamics.clone()))
+            .collect()
+    }
+    
+    /// 辨识关节的惯量和摩擦：以当前位置为中心运行激励轨迹，拟合实测的速度、加速度和力矩
+    ///
+    /// 估计结果会立即用于前馈补偿，调用方可以把它写回配置的`dynamics`保存。
+    /// 需要控制器运行且关节能自由移动，紧急停止会中断辨识并恢复原参数。
+    pub async fn identify_dynamics(&self, joint_name: &str, excitation: &ExcitationConfig) -> Result<DynamicsFit> {
+        let limits = self.config.joint_limits.get(joint_name)
+            .ok_or_else(|| anyhow::anyhow!("关节 '{}' 不存在", joint_name))?;
+        let center = self.sensor_data.load().joint_states[joint_name].unwrapped_position;
+        let range = if limits.continuous {
+            (f64::NEG_INFINITY, f64::INFINITY)
+        } else {
+            (limits.min_position.radians(), limits.max_position.radians())
+        };
+        
+        let trajectory = excitation_trajectory(
+            center,
+            range,
+            limits.max_velocity.radians_per_second(),
+            limits.max_acceleration,
+            excitation,
+            self.config.control_frequency,
+        )?;
+        
+        info!("开始辨识关节 {} 的惯量和摩擦，时长 {:.1} 秒", joint_name, excitation.duration);
+        
+        // 激励时不做前馈，测到的力矩完全来自反馈
+        let previous = self.dynamics_params().await.remove(joint_name).unwrap_or_default();
+        self.set_dynamics_params(joint_name, DynamicsParams::default()).await?;
+        
+        let start = Instant::now();
+        let mut samples = Vec::with_capacity(trajectory.len());
+        for (time, target) in trajectory {
+            if self.stats.emergency_stop.load(Ordering::SeqCst) {
+                self.set_dynamics_params(joint_name, previous).await?;
+                return Err(anyhow::anyhow!("紧急停止，关节 '{}' 的动力学辨识中断", joint_name));
+            }
+            
+            tokio::time::sleep_until((start + Duration::from_secs_f64(time)).into()).await;
+            self.add_command(MotionCommand::builder(joint_name, CommandType::Position).position(target).build()?).await?;
+            
+            let snapshot = self.sensor_data.load();
+            let state = &snapshot.joint_states[joint_name];
+            samples.push(DynamicsSample {
+                velocity: state.velocity,
+                acceleration: state.acceleration,
+                effort: state.effort,
+            });
         }
         
+        let Some(fit) = fit_dynamics(&samples) else {
+            self.set_dynamics_params(joint_name, previous).await?;
+            return Err(anyhow::anyhow!("关节 '{}' 的激励数据不足，无法辨识惯量和摩擦", joint_name));
+        };
+        
+        self.set_dynamics_params(joint_name, fit.params.clone()).await?;
+        
+        info!("关节 {} 辨识完成：惯量 {:.4}，粘滞摩擦 {:.4}，库仑摩擦 {:.4}，模型解释 {:.0}% 的力矩",
+              joint_name, fit.params.inertia, fit.params.viscous_friction, fit.params.coulomb_friction,
+              fit.improvement * 100.0);
+        Ok(fit)
+    }
+    
+    /// 设置紧急停止
+    pub async fn set_emergency_stop(&self, stop: bool) -> Result<()> {
+        let was_stopped = self.stats.emergency_stop.swap(stop, Ordering::SeqCst);
+        
         if stop {
             warn!("紧急停止激活");
         } else {
             info!("紧急停止解除");
+            
+            // 解除急停后从当前位置软启动
+            if was_stopped {
+                self.begin_soft_start().await;
+            }
         }
         
         Ok(())
     }
     
-    /// 获取传感器数据
+    /// 获取传感器数据，时间戳已按参考时钟修正
     pub async fn get_sensor_data(&self) -> Result<SensorData> {
-        let data = self.sensor_data.read().await;
-        Ok(data.clone())
+        let mut data = SensorData::clone(&self.sensor_data.load());
+        data.timestamp = self.time_sync.read().await.to_reference(data.timestamp);
+        Ok(data)
     }
     
-    /// 获取状态
-    pub async fn get_status(&self) -> Result<RealtimeStatus> {
-        let mut status = self.status.read().await.clone();
+    /// 最新的传感器快照，不复制数据，时间戳为本机时钟
+    pub fn sensor_snapshot(&self) -> Arc<SensorData> {
+        self.sensor_data.load_full()
+    }
+    
+    /// 头部和相机的坐标变换树，末端速度控制从这里读取头部和相机的位置
+    pub fn transforms(&self) -> &RwLock<TransformTree> {
+        &self.transforms
+    }
+    
+    /// 记录与客户端的一次时间交换（客户端时间戳均为客户端时钟，毫秒）
+    pub async fn record_time_exchange(&self, client_id: &str, client_send: u64, robot_receive: u64,
+                                      robot_send: u64, client_receive: u64) -> bool {
+        self.time_sync.write().await.add_client_exchange(client_id, client_send, robot_receive, robot_send, client_receive)
+    }
+    
+    /// 复位因振荡降低增益或停止的关节，恢复配置的增益
+    pub async fn reset_oscillation(&self, joint_name: &str) -> Result<()> {
+        if !self.config.joint_limits.contains_key(joint_name) {
+            return Err(anyhow::anyhow!("未知关节: {}", joint_name));
+        }
+        
+        self.oscillation.lock().await.reset(joint_name);
+        let ratio = self.stats.target_control_frequency.load() / self.config.control_frequency;
+        if let (Some(controller), Some(gains)) = (
+            self.pid_controllers.write().await.get_mut(joint_name),
+            self.config.pid_gains.get(joint_name),
+        ) {
+            // 控制器未启动时目标频率为0，按额定频率恢复
+            controller.gains = rescale_gains(gains, if ratio > 0.0 { ratio } else { 1.0 });
+        }
         
-        // 更新关节状态
-        let sensor_data = self.sensor_data.read().await;
-        status.joint_states = sensor_data.joint_states.clone();
+        info!("关节 {} 的振荡保护已复位", joint_name);
+        Ok(())
+    }
+    
+    /// 是否有关节因持续振荡被停止
+    pub async fn is_joint_halted(&self, joint_name: &str) -> bool {
+        self.oscillation.lock().await.is_halted(joint_name)
+    }
+    
+    /// 导出关节最近seconds秒的设定点、测量位置和控制输出
+    pub fn dump_telemetry(&self, joint_name: &str, seconds: f64) -> Result<TelemetryDump> {
+        self.telemetry.dump(joint_name, seconds)
+    }
+    
+    /// 获取时间同步状态
+    pub async fn time_sync_status(&self) -> TimeSyncStatus {
+        self.time_sync.read().await.status()
+    }
+    
+    /// 延迟自测：发送samples条探测命令，测量各层耗时并输出延迟预算
+    ///
+    /// 探测命令是第一个关节上的停止命令，经过与外部命令相同的限流和仲裁，
+    /// 有轨迹在执行时拒绝测量，避免打断运动。网络往返取客户端时间同步的结果；
+    /// 停止命令不产生总线写入，总线往返取最近控制循环写入目标时记录的样本，
+    /// 没有接入硬件I/O线程或启动后还没有运动过时为未测量。
+    pub async fn measure_latency(&self, samples: usize) -> Result<LatencyBudget> {
+        if samples == 0 {
+            return Err(anyhow::anyhow!("探测次数必须为正数"));
+        }
+        if !self.stats.is_running.load(Ordering::Relaxed) {
+            return Err(anyhow::anyhow!("实时控制器未运行"));
+        }
+        if !self.trajectories.read().await.is_empty() {
+            return Err(anyhow::anyhow!("有轨迹正在执行，请在机器人静止时测量延迟"));
+        }
+        
+        let joint_name = self.config.joint_limits.keys().min()
+            .ok_or_else(|| anyhow::anyhow!("没有配置关节"))?
+            .clone();
+        let origin = CommandOrigin::new(CommandSource::Python, "latency_probe");
+        let period = Duration::from_secs_f64(1.0 / self.stats.target_control_frequency.load());
+        
+        let mut arbitration = Vec::with_capacity(samples);
+        let mut scheduling = Vec::with_capacity(samples);
+        
+        for _ in 0..samples {
+            let processed = self.stats.commands_processed.notified();
+            tokio::pin!(processed);
+            processed.as_mut().enable();
+            
+            let command = MotionCommand::builder(joint_name.clone(), CommandType::Stop).build()?;
+            
+            let submitted = Instant::now();
+            self.submit_command(&origin, command).await?;
+            let queued = Instant::now();
+            arbitration.push(queued - submitted);
+            
+            tokio::time::timeout(Duration::from_secs(1), processed).await
+                .map_err(|_| anyhow::anyhow!("控制循环没有在1秒内处理探测命令"))?;
+            scheduling.push(queued.elapsed());
+            
+            sleep(period * 2).await;
+        }
         
-        Ok(status)
+        let mut budget = LatencyBudget::new(period.as_secs_f64() * 1000.0);
+        let time_sync = self.time_sync_status().await;
+        if time_sync.synchronized {
+            budget.set(LatencyStage::Network, Some(LatencyStats::single(time_sync.round_trip_ms)));
+        }
+        budget.set(LatencyStage::Arbitration, LatencyStats::from_samples(&arbitration));
+        budget.set(LatencyStage::Scheduling, LatencyStats::from_samples(&scheduling));
+        budget.set(LatencyStage::Bus, LatencyStats::from_samples(&self.stats.bus_latency.snapshot()));
+        
+        info!("延迟自测（{} 次探测）:\n{}", samples, budget);
+        Ok(budget)
+    }
+    
+    /// 获取状态
+    pub async fn get_status(&self) -> Result<RealtimeStatus> {
+        let stats = &self.stats;
+        
+        Ok(RealtimeStatus {
+            is_running: stats.is_running.load(Ordering::Relaxed),
+            emergency_stop: stats.emergency_stop.load(Ordering::SeqCst),
+            control_loop_frequency: stats.control_loop_frequency.load(),
+            target_control_frequency: stats.target_control_frequency.load(),
+            sensor_update_frequency: stats.sensor_update_frequency.load(),
+            active_commands: stats.active_commands.load(Ordering::Relaxed),
+            last_command_timestamp: stats.last_command_timestamp.load(Ordering::Relaxed),
+            performance_stats: PerformanceStats::clone(&stats.performance_stats.load()),
+            joint_states: self.sensor_data.load().joint_states.clone(),
+            control_owner: self.control_owner().await,
+            time_sync: self.time_sync_status().await,
+            control_timing: stats.control_timing.snapshot(),
+            io_timing: self.hardware_io.as_ref().map(|io| io.timing.snapshot()),
+        })
     }
     
     /// 是否正在运行
     pub async fn is_running(&self) -> bool {
-        *self.is_running.read().await
+        self.stats.is_running.load(Ordering::Relaxed)
     }
 }
 
@@ -896,14 +2362,28 @@ impl LifecycleManager for RealtimeController {
     }
     
     fn is_running(&self) -> bool {
-        // 注意：这是同步版本，异步版本在上面
-        false // 占位符实现
+        self.stats.is_running.load(Ordering::Relaxed)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::arbiter::Role;
+    
+    /// 审计日志只保留在内存中，测试不写文件
+    fn test_config() -> RealtimeConfig {
+        let mut config = RealtimeConfig::default();
+        config.audit.directory = None;
+        config
+    }
+    
+    /// 替换传感器快照中某个关节的位置
+    fn set_position(controller: &RealtimeController, joint_name: &str, position: f64) {
+        let mut data = SensorData::clone(&controller.sensor_data.load());
+        data.joint_states.get_mut(joint_name).unwrap().update_position(position, false);
+        controller.sensor_data.store(Arc::new(data));
+    }
     
     #[tokio::test]
     async fn test_realtime_config_validation() {
@@ -928,7 +2408,7 @@ mod tests {
     async fn test_trajectory_generator() {
         let trajectory = TrajectoryGenerator::new(0.0, 1.0, 0.0, 1.0, 2.0);
         
-        let start_time = Instant::now();
+        let start_time = trajectory.start_time;
         let position = trajectory.get_position(start_time);
         assert_eq!(position, 0.0); // 起始位置
         
@@ -942,4 +2422,436 @@ mod tests {
         let controller = RealtimeController::new(config).await;
         assert!(controller.is_ok());
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_status_from_snapshots_and_atomics() {
+        let mut controller = RealtimeController::new(RealtimeConfig::default()).await.unwrap();
+        controller.start().await.unwrap();
+        assert!(LifecycleManager::is_running(&controller));
+        
+        // 传感器循环发布新快照，旧快照保持不变
+        let before = controller.sensor_snapshot();
+        sleep(Duration::from_millis(50)).await;
+        let after = controller.sensor_snapshot();
+        assert!(!Arc::ptr_eq(&before, &after));
+        assert!(after.timestamp >= before.timestamp);
+        
+        controller.add_command(MotionCommand {
+            joint_name: "head_pan".to_string(),
+            command_type: CommandType::Stop,
+            target_position: None,
+            target_velocity: None,
+            target_torque: None,
+            duration: None,
+            timestamp: current_timestamp(),
+            correlation_id: None,
+        }).await.unwrap();
+        controller.set_emergency_stop(true).await.unwrap();
+        
+        let status = controller.get_status().await.unwrap();
+        assert!(status.is_running);
+        assert!(status.emergency_stop);
+        assert!(status.last_command_timestamp > 0);
+        assert_eq!(status.joint_states.len(), after.joint_states.len());
+        
+        controller.stop().await.unwrap();
+        let status = controller.get_status().await.unwrap();
+        assert!(!status.is_running);
+        assert_eq!(status.active_commands, 0);
+    }
+    
+    #[tokio::test]
+    async fn test_submit_command_respects_lease() {
+        let controller = RealtimeController::new(test_config()).await.unwrap();
+        let owner = CommandOrigin::new(CommandSource::WebSocket, "dashboard");
+        let other = CommandOrigin::new(CommandSource::Python, "script");
+        let command = MotionCommand {
+            joint_name: "head_pan".to_string(),
+            command_type: CommandType::Position,
+            target_position: Some(0.2),
+            target_velocity: None,
+            target_torque: None,
+            duration: None,
+            timestamp: current_timestamp(),
+            correlation_id: None,
+        };
+        
+        controller.acquire_control(&owner, None).await.unwrap();
+        assert!(controller.submit_command(&other, command.clone()).await.is_err());
+        assert!(controller.submit_command(&owner, command.clone()).await.is_ok());
+        
+        let status = controller.get_status().await.unwrap();
+        assert_eq!(status.control_owner.map(|lease| lease.owner), Some(owner.clone()));
+        
+        controller.release_control(&owner).await.unwrap();
+        assert!(controller.control_owner().await.is_none());
+    }
+    
+    #[test]
+    fn test_motion_command_builder_validates_targets() {
+        let command = MotionCommand::builder("head_pan", CommandType::Position)
+            .position(0.3)
+            .duration(0.5)
+            .correlation_id("req-1".to_string())
+            .build()
+            .unwrap();
+        assert_eq!(command.target_position, Some(0.3));
+        assert_eq!(command.duration, Some(0.5));
+        assert_eq!(command.correlation_id.as_deref(), Some("req-1"));
+        assert!(command.timestamp > 0);
+        
+        assert!(MotionCommand::builder("head_pan", CommandType::Stop).build().is_ok());
+        
+        // 缺少目标、目标与类型不符、时长无效、关节名为空都被拒绝
+        let error = MotionCommand::builder("head_pan", CommandType::Position).build().unwrap_err();
+        assert!(error.to_string().contains("target_position"));
+        assert!(MotionCommand::builder("head_pan", CommandType::Velocity).position(0.1).velocity(0.2).build().is_err());
+        assert!(MotionCommand::builder("head_pan", CommandType::Stop).torque(1.0).build().is_err());
+        assert!(MotionCommand::builder("head_pan", CommandType::Position).position(0.1).duration(0.0).build().is_err());
+        assert!(MotionCommand::builder("head_pan", CommandType::Velocity).velocity(f64::INFINITY).build().is_err());
+        assert!(MotionCommand::builder("", CommandType::Stop).build().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_submit_external_command() {
+        let controller = RealtimeController::new(test_config()).await.unwrap();
+        let browser = CommandOrigin::new(CommandSource::WebSocket, "browser");
+        
+        // WebSocket接口使用角度制
+        // 调用方给出的关联ID原样保留
+        controller.submit_external_command(&browser, r#"{"joint": "head_pan", "type": "position", "position": 30, "correlation_id": "req-42"}"#).await.unwrap();
+        let queued = controller.command_queue.lock().await.back().cloned().unwrap();
+        assert!((queued.target_position.unwrap() - 30f64.to_radians()).abs() < 1e-9);
+        assert_eq!(queued.correlation_id.as_deref(), Some("req-42"));
+        
+        let error = controller.submit_external_command(&browser, r#"{"joint": "tail", "type": "stop"}"#).await.unwrap_err();
+        assert!(error.to_string().contains("tail"));
+        
+        // 突发额度用完后被限流，急停仍然可以发送
+        let flood = (0..20).map(|_| r#"{"joint": "head_pan", "type": "stop"}"#);
+        let mut rejected = 0;
+        for json in flood {
+            if controller.submit_external_command(&browser, json).await.is_err() {
+                rejected += 1;
+            }
+        }
+        assert!(rejected > 0);
+        assert!(controller.rate_limited_counts().await[&CommandSource::WebSocket] > 0);
+        controller.submit_external_command(&browser, r#"{"joint": "head_pan", "type": "emergency_stop"}"#).await.unwrap();
+        
+        // 每条命令连同原始内容和处理结果都进入审计日志
+        let records = controller.query_audit(&AuditQuery { limit: 1000, ..AuditQuery::default() }).await.unwrap();
+        assert_eq!(records.len(), 23);
+        assert_eq!(records[0].payload["position"], 30);
+        assert!(records[1].result == AuditResult::Rejected { reason: error.to_string() });
+        assert!(records.iter().all(|record| record.client_id == "browser"));
+        assert!(records.last().unwrap().result.is_accepted());
+    }
+    
+    #[tokio::test]
+    async fn test_roles_and_safety_profile() {
+        let controller = RealtimeController::new(test_config()).await.unwrap();
+        let viewer = CommandOrigin::new(CommandSource::WebSocket, "guest").with_role(Role::Viewer);
+        let operator = CommandOrigin::new(CommandSource::WebSocket, "dashboard");
+        let admin = CommandOrigin::new(CommandSource::Python, "setup").with_role(Role::Admin);
+        let wide = r#"{"joint": "head_pan", "type": "position", "position": 60}"#;
+        
+        // 查看者只能读取遥测数据和发送急停
+        assert!(controller.submit_external_command(&viewer, wide).await.is_err());
+        assert!(controller.get_status().await.is_ok());
+        controller.submit_external_command(&viewer, r#"{"joint": "head_pan", "type": "emergency_stop"}"#).await.unwrap();
+        
+        // 只有管理员可以修改安全档位
+        assert!(controller.set_safety_profile(&operator, SafetyProfile::Minimal).await.is_err());
+        controller.set_safety_profile(&admin, SafetyProfile::Minimal).await.unwrap();
+        assert_eq!(controller.safety_profile().await, SafetyProfile::Minimal);
+        
+        // 最小档位下大幅度动作被拒绝，小幅度动作和急停仍然可以执行
+        assert!(controller.submit_external_command(&operator, wide).await.is_err());
+        let limits = &controller.config.joint_limits["head_pan"];
+        let center = (limits.min_position.radians() + limits.max_position.radians()) / 2.0;
+        let small = format!(r#"{{"joint": "head_pan", "type": "position", "position": {}}}"#, center.to_degrees());
+        controller.submit_external_command(&operator, &small).await.unwrap();
+        controller.submit_external_command(&operator, r#"{"joint": "head_pan", "type": "emergency_stop"}"#).await.unwrap();
+        
+        // 审计日志记录角色
+        let records = controller.query_audit(&AuditQuery::default()).await.unwrap();
+        assert_eq!(records[0].role, Role::Viewer);
+        assert!(!records[0].result.is_accepted());
+        assert_eq!(records[1].role, Role::Viewer);
+        assert!(records[1].result.is_accepted());
+        assert_eq!(records[3].role, Role::Admin);
+        assert_eq!(records[3].payload["profile"], "minimal");
+    }
+    
+    #[tokio::test]
+    async fn test_record_and_play_trajectory() {
+        let controller = RealtimeController::new(RealtimeConfig::default()).await.unwrap();
+        set_position(&controller, "head_pan", 0.4);
+        
+        let recorded = controller.record_trajectory("hold", Duration::from_millis(30), 200.0).await.unwrap();
+        assert!(recorded.validate().is_ok());
+        assert!(recorded.frames.len() >= 2);
+        assert_eq!(recorded.sample(0.0).unwrap()["head_pan"], 0.4);
+        
+        let imported = TrajectoryFile::from_csv(&recorded.to_csv()).unwrap();
+        controller.play_trajectory(&imported).await.unwrap();
+        let queued = controller.command_queue.lock().await.len();
+        assert_eq!(queued, imported.frames.len() * imported.joints.len());
+        
+        let mut unknown = TrajectoryFile::new("bad", vec!["tail".to_string()]);
+        unknown.push_frame(0.0, &HashMap::from([("tail".to_string(), 0.0)]), None);
+        assert!(controller.play_trajectory(&unknown).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_soft_start_after_emergency_stop() {
+        let controller = RealtimeController::new(RealtimeConfig::default()).await.unwrap();
+        set_position(&controller, "head_pan", 0.3);
+        controller.add_command(MotionCommand {
+            joint_name: "head_pan".to_string(),
+            command_type: CommandType::Position,
+            target_position: Some(-0.5),
+            target_velocity: None,
+            target_torque: None,
+            duration: None,
+            timestamp: current_timestamp(),
+            correlation_id: None,
+        }).await.unwrap();
+        
+        controller.set_emergency_stop(true).await.unwrap();
+        assert!(!controller.is_soft_starting().await);
+        controller.set_emergency_stop(false).await.unwrap();
+        assert!(controller.is_soft_starting().await);
+        
+        // 旧命令被丢弃，设定点取自当前位置
+        assert!(controller.command_queue.lock().await.is_empty());
+        let trajectories = controller.trajectories.read().await;
+        let hold = &trajectories["head_pan"];
+        assert_eq!(hold.get_position(Instant::now()), 0.3);
+    }
+    
+    #[tokio::test]
+    async fn test_latency_self_measurement_with_simulated_bus() {
+        use crate::hardware::io_thread::{io_channel, IoThreadConfig, SimulatedBus};
+        
+        let bus = SimulatedBus::new(&JointSetConfig::default(), Duration::from_millis(2));
+        let io_config = IoThreadConfig { enabled: true, frequency: 200.0, realtime_priority: None };
+        let (thread, endpoints) = io_channel(Box::new(bus), io_config).unwrap();
+        let mut tasks = TaskGroup::new("测试");
+        let shutdown = tasks.token();
+        tasks.spawn_blocking("I/O线程", move || thread.run(shutdown));
+        
+        let mut controller = RealtimeController::new(test_config()).await.unwrap();
+        controller.attach_hardware_io(endpoints);
+        assert!(controller.measure_latency(3).await.is_err());
+        controller.start().await.unwrap();
+        
+        // 先运动一小段，产生总线往返样本
+        let origin = CommandOrigin::new(CommandSource::Python, "test");
+        controller.submit_command(&origin, MotionCommand {
+            joint_name: "head_pan".to_string(),
+            command_type: CommandType::Position,
+            target_position: Some(0.05),
+            target_velocity: None,
+            target_torque: None,
+            duration: None,
+            timestamp: current_timestamp(),
+            correlation_id: None,
+        }).await.unwrap();
+        let deadline = Instant::now() + Duration::from_secs(3);
+        while Instant::now() < deadline && !controller.trajectories.read().await.is_empty() {
+            sleep(Duration::from_millis(10)).await;
+        }
+        
+        let budget = controller.measure_latency(5).await.unwrap();
+        assert_eq!(budget.get(LatencyStage::Arbitration).unwrap().samples, 5);
+        // 命令入队后最迟在下一个控制周期被处理
+        let scheduling = budget.get(LatencyStage::Scheduling).unwrap();
+        assert!(scheduling.p50_ms < budget.control_period_ms * 3.0);
+        assert!(budget.get(LatencyStage::Bus).is_some());
+        assert!(budget.get(LatencyStage::Network).is_none());
+        assert!(budget.dominant().is_some());
+        
+        controller.stop().await.unwrap();
+        tasks.shutdown().await;
+    }
+    
+    #[tokio::test]
+    async fn test_control_loop_records_joint_telemetry() {
+        let mut controller = RealtimeController::new(test_config()).await.unwrap();
+        controller.start().await.unwrap();
+        controller.add_command(MotionCommand {
+            joint_name: "head_pan".to_string(),
+            command_type: CommandType::Position,
+            target_position: Some(0.2),
+            target_velocity: None,
+            target_torque: None,
+            duration: None,
+            timestamp: current_timestamp(),
+            correlation_id: None,
+        }).await.unwrap();
+        sleep(Duration::from_millis(150)).await;
+        controller.stop().await.unwrap();
+        
+        let dump = controller.dump_telemetry("head_pan", 1.0).unwrap();
+        assert!(dump.samples.len() >= 5);
+        assert!(dump.samples.iter().any(|sample| sample.setpoint.is_some() && sample.output.is_some()));
+        assert!(dump.samples.windows(2).all(|pair| pair[0].time < pair[1].time));
+        assert!(controller.dump_telemetry("tail", 1.0).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_halted_joint_rejects_commands_until_reset() {
+        let mut config = test_config();
+        config.oscillation.action = OscillationAction::HaltJoint;
+        let controller = RealtimeController::new(config).await.unwrap();
+        {
+            let mut detector = controller.oscillation.lock().await;
+            let halted = (0..200).filter_map(|i| {
+                let error = if (i / 5) % 2 == 0 { 0.05 } else { -0.05 };
+                detector.update("head_pan", error, 0.01)
+            }).next().unwrap();
+            assert_eq!(halted.action, OscillationAction::HaltJoint);
+        }
+        
+        let origin = CommandOrigin::new(CommandSource::Python, "test");
+        let command = r#"{"joint": "head_pan", "type": "position", "position": 5}"#;
+        assert!(controller.is_joint_halted("head_pan").await);
+        assert!(controller.submit_external_command(&origin, command).await.is_err());
+        
+        controller.reset_oscillation("head_pan").await.unwrap();
+        assert!(!controller.is_joint_halted("head_pan").await);
+        controller.submit_external_command(&origin, command).await.unwrap();
+    }
+    
+    #[test]
+    fn test_velocity_setpoint_decays_after_timeout() {
+        let start = Instant::now();
+        let timeout = Duration::from_millis(100);
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut setpoint = VelocitySetpoint::new(0.0, 0.0, start);
+        setpoint.refresh(1.0, start);
+        
+        // 按最大加速度5 rad/s²加速，100ms后达到0.5 rad/s
+        for ms in (10..=100).step_by(10) {
+            setpoint.step(at(ms), 0.01, timeout, 5.0);
+        }
+        assert!((setpoint.velocity - 0.5).abs() < 1e-9);
+        
+        // 超时后平滑减速：速度逐步下降，位置不回退，减到零后可以移除
+        let mut previous = (setpoint.velocity, setpoint.position);
+        for ms in (110..=210).step_by(10) {
+            assert!(!setpoint.is_expired(at(ms), timeout));
+            let position = setpoint.step(at(ms), 0.01, timeout, 5.0);
+            assert!(setpoint.velocity <= previous.0 && previous.0 - setpoint.velocity <= 0.05 + 1e-9);
+            assert!(position >= previous.1);
+            previous = (setpoint.velocity, position);
+        }
+        assert_eq!(setpoint.velocity, 0.0);
+        assert!(setpoint.is_expired(at(210), timeout));
+    }
+    
+    #[tokio::test]
+    async fn test_velocity_command_watchdog() {
+        let mut config = test_config();
+        config.command_timeout_ms = 100;
+        let mut controller = RealtimeController::new(config).await.unwrap();
+        controller.start().await.unwrap();
+        controller.add_command(MotionCommand {
+            joint_name: "head_pan".to_string(),
+            command_type: CommandType::Velocity,
+            target_position: None,
+            target_velocity: Some(0.5),
+            target_torque: None,
+            duration: None,
+            timestamp: current_timestamp(),
+            correlation_id: None,
+        }).await.unwrap();
+        
+        sleep(Duration::from_millis(50)).await;
+        assert!(controller.velocity_setpoints.lock().await["head_pan"].velocity > 0.0);
+        
+        // 不再刷新的速度命令超时后减速到零并被移除
+        sleep(Duration::from_millis(500)).await;
+        assert!(controller.velocity_setpoints.lock().await.is_empty());
+        controller.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_submit_twist_queues_velocity_commands() {
+        let controller = RealtimeController::new(test_config()).await.unwrap();
+        let origin = CommandOrigin::new(CommandSource::WebSocket, "dashboard");
+        
+        // 绕z轴的角速度换算为头部水平转动速度，超出限位时整体缩小
+        let twist = Twist::new(Vector3::zero(), Vector3::new(0.0, 0.0, 100.0));
+        let solution = controller.submit_twist(&origin, twist).await.unwrap();
+        assert!(solution.scale < 1.0);
+        
+        let queue = controller.command_queue.lock().await;
+        assert_eq!(queue.len(), 2);
+        let pan = queue.iter().find(|c| c.joint_name == "head_pan").unwrap();
+        assert!(matches!(pan.command_type, CommandType::Velocity));
+        let max_velocity = controller.config.joint_limits["head_pan"].max_velocity.radians_per_second();
+        assert!((pan.target_velocity.unwrap() - max_velocity).abs() < 1e-3);
+        drop(queue);
+        
+        let nan = Twist::new(Vector3::new(f64::NAN, 0.0, 0.0), Vector3::zero());
+        assert!(controller.submit_twist(&origin, nan).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_sensor_loop_updates_transform_tree() {
+        use crate::hardware::io_thread::{io_channel, IoThreadConfig, SimulatedBus};
+        use crate::transforms::{BASE_FRAME, CAMERA_FRAME};
+        
+        let bus = SimulatedBus::new(&JointSetConfig::default(), Duration::from_millis(1));
+        let io_config = IoThreadConfig { enabled: true, frequency: 200.0, realtime_priority: None };
+        let (thread, endpoints) = io_channel(Box::new(bus), io_config).unwrap();
+        let mut tasks = TaskGroup::new("测试");
+        let shutdown = tasks.token();
+        tasks.spawn_blocking("I/O线程", move || thread.run(shutdown));
+        
+        let mut controller = RealtimeController::new(test_config()).await.unwrap();
+        controller.attach_hardware_io(endpoints);
+        controller.start().await.unwrap();
+        let camera_at_start = controller.transforms().read().await
+            .lookup_transform(BASE_FRAME, CAMERA_FRAME, None).unwrap()
+            .transform_point(&Vector3::zero());
+        
+        // 仿真总线上转动头部
+        let origin = CommandOrigin::new(CommandSource::Python, "test");
+        controller.submit_command(&origin, MotionCommand {
+            joint_name: "head_pan".to_string(),
+            command_type: CommandType::Position,
+            target_position: Some(0.5),
+            target_velocity: None,
+            target_torque: None,
+            duration: None,
+            timestamp: current_timestamp(),
+            correlation_id: None,
+        }).await.unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline && controller.sensor_data.load().joint_states["head_pan"].position < 0.45 {
+            sleep(Duration::from_millis(10)).await;
+        }
+        sleep(Duration::from_millis(50)).await;
+        
+        // 控制器的变换树跟随关节状态，与按当前读数重新计算的结果一致
+        let joint_states = controller.sensor_data.load().joint_states.clone();
+        assert!(joint_states["head_pan"].position > 0.45);
+        let mut expected = TransformTree::new(controller.config.transforms.clone()).unwrap();
+        expected.update_from_joint_states(&joint_states, current_timestamp()).unwrap();
+        let expected = expected.lookup_transform(BASE_FRAME, CAMERA_FRAME, None).unwrap().transform_point(&Vector3::zero());
+        let camera = controller.transforms().read().await
+            .lookup_transform(BASE_FRAME, CAMERA_FRAME, None).unwrap()
+            .transform_point(&Vector3::zero());
+        
+        let distance = |a: &Vector3, b: &Vector3| ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt();
+        assert!(distance(&camera, &expected) < 1e-3, "{:?} != {:?}", camera, expected);
+        assert!(distance(&camera, &camera_at_start) > 1e-3);
+        
+        controller.stop().await.unwrap();
+        tasks.shutdown().await;
+    }
+}
@@ -0,0 +1,475 @@
+//! 资源限制与内存压力监控
+//! 
+//! 可选地通过cgroup v2给本进程所在的cgroup设置内存和CPU上限（需要systemd的Delegate=yes），
+//! 并调整oom_score_adj。内存压力监控根据cgroup内存用量（没有cgroup v2时用系统可用内存）和PSI
+//! 判断压力等级，在OOM killer结束整个进程（连同实时控制循环）之前主动降载：
+//! 警告时降低视觉采集分辨率，严重时再卸载空闲的AI模型；压力解除并持续一段时间后恢复。
+
+use crate::common::*;
+use crate::events::{EventBus, RobotEvent};
+use crate::shutdown::CancellationToken;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use log::{debug, info, warn};
+
+#[cfg(feature = "ai")]
+use crate::ai::AIEngine;
+#[cfg(feature = "vision")]
+use crate::vision::VisionProcessor;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const MB: u64 = 1024 * 1024;
+/// cpu.max使用的调度周期（微秒）
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// 资源限制与内存压力监控配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceGuardConfig {
+    pub enabled: bool,
+    /// cgroup的memory.max，超过时cgroup内触发OOM
+    pub memory_max_mb: Option<u64>,
+    /// cgroup的memory.high，超过后内核开始回收内存并节流
+    pub memory_high_mb: Option<u64>,
+    /// cgroup的cpu.max，100为一个核
+    pub cpu_max_percent: Option<u32>,
+    /// 本进程的oom_score_adj，调低需要CAP_SYS_RESOURCE
+    pub oom_score_adj: Option<i32>,
+    pub monitor_interval_ms: u64,
+    /// 内存用量占上限的比例
+    pub warning_ratio: f64,
+    pub critical_ratio: f64,
+    /// PSI中some avg10（百分比）
+    pub warning_psi: f64,
+    pub critical_psi: f64,
+    /// 警告时视觉采集分辨率的缩小倍数
+    pub vision_downscale: u32,
+    /// 严重时卸载超过这个时间未使用的模型（秒）
+    pub idle_model_secs: u64,
+    /// 压力降低后保持这么久才恢复（毫秒），避免来回切换
+    pub recovery_ms: u64,
+}
+
+impl Default for ResourceGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            memory_max_mb: None,
+            memory_high_mb: None,
+            cpu_max_percent: None,
+            oom_score_adj: None,
+            monitor_interval_ms: 1000,
+            warning_ratio: 0.8,
+            critical_ratio: 0.9,
+            warning_psi: 10.0,
+            critical_psi: 25.0,
+            vision_downscale: 2,
+            idle_model_secs: 60,
+            recovery_ms: 30_000,
+        }
+    }
+}
+
+impl ConfigValidation for ResourceGuardConfig {
+    fn validate(&self) -> Result<()> {
+        if self.monitor_interval_ms == 0 {
+            return Err(anyhow::anyhow!("内存压力检查间隔必须大于0"));
+        }
+        
+        if !(0.0 < self.warning_ratio && self.warning_ratio < self.critical_ratio && self.critical_ratio <= 1.0) {
+            return Err(anyhow::anyhow!("内存用量阈值必须满足 0 < 警告 < 严重 <= 1"));
+        }
+        
+        if !(0.0 < self.warning_psi && self.warning_psi < self.critical_psi && self.critical_psi <= 100.0) {
+            return Err(anyhow::anyhow!("内存PSI阈值必须满足 0 < 警告 < 严重 <= 100"));
+        }
+        
+        if let (Some(high), Some(max)) = (self.memory_high_mb, self.memory_max_mb) {
+            if high > max {
+                return Err(anyhow::anyhow!("memory_high_mb不能大于memory_max_mb"));
+            }
+        }
+        
+        if self.memory_max_mb == Some(0) || self.memory_high_mb == Some(0) || self.cpu_max_percent == Some(0) {
+            return Err(anyhow::anyhow!("资源上限必须大于0"));
+        }
+        
+        if let Some(adj) = self.oom_score_adj {
+            if !(-1000..=1000).contains(&adj) {
+                return Err(anyhow::anyhow!("oom_score_adj必须在-1000到1000之间"));
+            }
+        }
+        
+        if !(1..=8).contains(&self.vision_downscale) {
+            return Err(anyhow::anyhow!("视觉分辨率缩小倍数必须在1-8之间"));
+        }
+        
+        Ok(())
+    }
+}
+
+impl ResourceGuardConfig {
+    /// 按内存用量和PSI中较严重的一项确定压力等级
+    pub fn pressure_level(&self, snapshot: &MemorySnapshot) -> PressureLevel {
+        let ratio = snapshot.usage_ratio();
+        let psi = snapshot.psi_some_avg10.unwrap_or(0.0);
+        
+        if ratio >= self.critical_ratio || psi >= self.critical_psi {
+            PressureLevel::Critical
+        } else if ratio >= self.warning_ratio || psi >= self.warning_psi {
+            PressureLevel::Warning
+        } else {
+            PressureLevel::Normal
+        }
+    }
+}
+
+/// 内存压力等级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PressureLevel {
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// 一次内存用量采样
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemorySnapshot {
+    pub used_bytes: u64,
+    /// cgroup上限和物理内存中较小的一个
+    pub limit_bytes: u64,
+    /// 内核不支持PSI时为None
+    pub psi_some_avg10: Option<f64>,
+}
+
+impl MemorySnapshot {
+    pub fn usage_ratio(&self) -> f64 {
+        if self.limit_bytes == 0 {
+            return 0.0;
+        }
+        self.used_bytes as f64 / self.limit_bytes as f64
+    }
+}
+
+/// 从/proc/self/cgroup中取出cgroup v2路径（"0::"开头的一行）
+fn parse_cgroup_path(content: &str) -> Option<&str> {
+    content.lines().find_map(|line| line.strip_prefix("0::"))
+}
+
+/// PSI文件中some一行的avg10
+fn parse_psi(content: &str) -> Option<f64> {
+    content.lines()
+        .find(|line| line.starts_with("some "))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse().ok()
+}
+
+/// /proc/meminfo中的MemTotal和MemAvailable（字节）
+fn parse_meminfo(content: &str) -> Option<(u64, u64)> {
+    let field = |name: &str| -> Option<u64> {
+        let line = content.lines().find(|line| line.starts_with(name))?;
+        let kb: u64 = line[name.len()..].trim().trim_end_matches("kB").trim().parse().ok()?;
+        Some(kb * 1024)
+    };
+    Some((field("MemTotal:")?, field("MemAvailable:")?))
+}
+
+/// cgroup的memory.max/memory.high，"max"表示没有上限
+fn parse_limit(content: &str) -> Option<u64> {
+    content.trim().parse().ok()
+}
+
+/// 本进程所在的cgroup v2目录，系统不是cgroup v2时返回None
+pub fn current_cgroup() -> Option<PathBuf> {
+    let content = fs::read_to_string("/proc/self/cgroup").ok()?;
+    let path = parse_cgroup_path(&content)?;
+    let dir = Path::new(CGROUP_ROOT).join(path.trim_start_matches('/'));
+    dir.join("cgroup.controllers").exists().then_some(dir)
+}
+
+/// 设置oom_score_adj和cgroup资源上限
+pub fn apply_limits(config: &ResourceGuardConfig) -> Result<()> {
+    config.validate()?;
+    
+    if let Some(adj) = config.oom_score_adj {
+        fs::write("/proc/self/oom_score_adj", adj.to_string())
+            .map_err(|e| anyhow::anyhow!("设置oom_score_adj失败: {}", e))?;
+        info!("oom_score_adj设置为 {}", adj);
+    }
+    
+    let mut limits = Vec::new();
+    if let Some(mb) = config.memory_high_mb {
+        limits.push(("memory.high", (mb * MB).to_string()));
+    }
+    if let Some(mb) = config.memory_max_mb {
+        limits.push(("memory.max", (mb * MB).to_string()));
+    }
+    if let Some(percent) = config.cpu_max_percent {
+        limits.push(("cpu.max", format!("{} {}", percent as u64 * CPU_PERIOD_US / 100, CPU_PERIOD_US)));
+    }
+    if limits.is_empty() {
+        return Ok(());
+    }
+    
+    let cgroup = current_cgroup().ok_or_else(|| anyhow::anyhow!("未找到cgroup v2，无法设置资源上限"))?;
+    for (file, value) in limits {
+        fs::write(cgroup.join(file), &value)
+            .map_err(|e| anyhow::anyhow!("写入 {} 失败（服务需要cgroup委派）: {}", file, e))?;
+        info!("cgroup {} 设置为 {}", file, value);
+    }
+    
+    Ok(())
+}
+
+/// 读取内存用量；给出cgroup时使用cgroup的用量、上限和PSI
+pub fn read_memory(cgroup: Option<&Path>) -> Result<MemorySnapshot> {
+    let meminfo = fs::read_to_string("/proc/meminfo")?;
+    let (total, available) = parse_meminfo(&meminfo)
+        .ok_or_else(|| anyhow::anyhow!("无法解析/proc/meminfo"))?;
+    
+    let Some(cgroup) = cgroup else {
+        return Ok(MemorySnapshot {
+            used_bytes: total.saturating_sub(available),
+            limit_bytes: total,
+            psi_some_avg10: fs::read_to_string("/proc/pressure/memory").ok().as_deref().and_then(parse_psi),
+        });
+    };
+    
+    let used_bytes = fs::read_to_string(cgroup.join("memory.current"))?.trim().parse()?;
+    // memory.high先于memory.max生效，两者取较小的
+    let limit_bytes = ["memory.high", "memory.max"].iter()
+        .filter_map(|file| fs::read_to_string(cgroup.join(file)).ok())
+        .filter_map(|content| parse_limit(&content))
+        .fold(total, u64::min);
+    
+    Ok(MemorySnapshot {
+        used_bytes,
+        limit_bytes,
+        psi_some_avg10: fs::read_to_string(cgroup.join("memory.pressure")).ok().as_deref().and_then(parse_psi),
+    })
+}
+
+/// 压力等级的滞回：升级立即生效，降级要持续recovery之后才生效
+#[derive(Debug)]
+pub struct PressureTracker {
+    recovery: Duration,
+    level: PressureLevel,
+    lower_since: Option<Instant>,
+}
+
+impl PressureTracker {
+    pub fn new(recovery: Duration) -> Self {
+        Self { recovery, level: PressureLevel::Normal, lower_since: None }
+    }
+    
+    /// 当前生效的等级
+    pub fn level(&self) -> PressureLevel {
+        self.level
+    }
+    
+    /// 输入一次采样的等级，生效等级变化时返回新等级
+    pub fn update(&mut self, level: PressureLevel, now: Instant) -> Option<PressureLevel> {
+        if level >= self.level {
+            self.lower_since = None;
+            if level == self.level {
+                return None;
+            }
+            self.level = level;
+            return Some(level);
+        }
+        
+        let since = *self.lower_since.get_or_insert(now);
+        if now.duration_since(since) < self.recovery {
+            return None;
+        }
+        
+        self.level = level;
+        self.lower_since = None;
+        Some(level)
+    }
+}
+
+/// 降载执行器
+#[allow(async_fn_in_trait)]
+pub trait LoadShedder {
+    /// 按压力等级降载或恢复，返回执行的动作；同一等级可能被重复调用
+    async fn apply(&mut self, level: PressureLevel) -> Result<Vec<String>>;
+}
+
+/// 系统降载：警告时降低视觉分辨率，严重时卸载空闲模型，恢复正常后重新加载
+#[cfg(any(feature = "vision", feature = "ai"))]
+pub struct SystemLoadShedder<'a> {
+    #[cfg(feature = "vision")]
+    vision: Option<&'a VisionProcessor>,
+    #[cfg(feature = "vision")]
+    vision_downscale: u32,
+    #[cfg(feature = "ai")]
+    ai: Option<&'a AIEngine>,
+    #[cfg(feature = "ai")]
+    idle_model: Duration,
+}
+
+#[cfg(any(feature = "vision", feature = "ai"))]
+impl<'a> SystemLoadShedder<'a> {
+    pub fn new(config: &ResourceGuardConfig) -> Self {
+        Self {
+            #[cfg(feature = "vision")]
+            vision: None,
+            #[cfg(feature = "vision")]
+            vision_downscale: config.vision_downscale,
+            #[cfg(feature = "ai")]
+            ai: None,
+            #[cfg(feature = "ai")]
+            idle_model: Duration::from_secs(config.idle_model_secs),
+        }
+    }
+    
+    #[cfg(feature = "vision")]
+    pub fn with_vision(mut self, vision: &'a VisionProcessor) -> Self {
+        self.vision = Some(vision);
+        self
+    }
+    
+    #[cfg(feature = "ai")]
+    pub fn with_ai(mut self, ai: &'a AIEngine) -> Self {
+        self.ai = Some(ai);
+        self
+    }
+}
+
+#[cfg(any(feature = "vision", feature = "ai"))]
+impl LoadShedder for SystemLoadShedder<'_> {
+    async fn apply(&mut self, level: PressureLevel) -> Result<Vec<String>> {
+        let mut actions = Vec::new();
+        
+        #[cfg(feature = "vision")]
+        if let Some(vision) = self.vision {
+            let factor = if level >= PressureLevel::Warning { self.vision_downscale } else { 1 };
+            if vision.downscale() != factor {
+                vision.set_downscale(factor);
+                actions.push(format!("视觉分辨率缩小为1/{}", factor));
+            }
+        }
+        
+        #[cfg(feature = "ai")]
+        if let Some(ai) = self.ai {
+            match level {
+                PressureLevel::Critical => {
+                    for name in ai.unload_idle_models(self.idle_model).await {
+                        actions.push(format!("卸载模型 {}", name));
+                    }
+                    let pooled_mb = ai.release_tensor_buffers();
+                    if pooled_mb > 0.0 {
+                        actions.push(format!("释放张量缓冲区池 {:.1}MB", pooled_mb));
+                    }
+                }
+                PressureLevel::Normal => {
+                    for name in ai.reload_models().await {
+                        actions.push(format!("重新加载模型 {}", name));
+                    }
+                }
+                PressureLevel::Warning => {}
+            }
+        }
+        
+        Ok(actions)
+    }
+}
+
+/// 定期检查内存压力，等级变化时发布resource.pressure事件并降载或恢复，直到shutdown被取消
+pub async fn run_pressure_monitor<S: LoadShedder>(
+    config: &ResourceGuardConfig,
+    shedder: &mut S,
+    bus: &EventBus,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    config.validate()?;
+    
+    let cgroup = current_cgroup();
+    match &cgroup {
+        Some(path) => info!("内存压力监控已启动（cgroup: {}）", path.display()),
+        None => info!("内存压力监控已启动（系统内存）"),
+    }
+    
+    let mut tracker = PressureTracker::new(Duration::from_millis(config.recovery_ms));
+    let mut interval = tokio::time::interval(Duration::from_millis(config.monitor_interval_ms));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+        
+        let snapshot = match read_memory(cgroup.as_deref()) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                debug!("读取内存用量失败: {}", e);
+                continue;
+            }
+        };
+        
+        let changed = tracker.update(config.pressure_level(&snapshot), Instant::now());
+        if let Some(level) = changed {
+            if level == PressureLevel::Normal {
+                info!("内存压力已解除");
+            } else {
+                warn!("内存压力: {:?}（用量 {:.0}%，PSI {:?}）", level, snapshot.usage_ratio() * 100.0, snapshot.psi_some_avg10);
+            }
+            bus.publish("resources", RobotEvent::Custom {
+                name: "resource.pressure".to_string(),
+                data: serde_json::json!({ "level": level, "memory": snapshot }),
+            });
+        }
+        
+        // 严重时每次都执行，之后变为空闲的模型也会被卸载
+        if changed.is_some() || tracker.level() == PressureLevel::Critical {
+            match shedder.apply(tracker.level()).await {
+                Ok(actions) if !actions.is_empty() => info!("内存压力处理: {}", actions.join("，")),
+                Ok(_) => {}
+                Err(e) => warn!("内存压力处理失败: {}", e),
+            }
+        }
+    }
+    
+    info!("内存压力监控已停止");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_and_classify() {
+        assert_eq!(parse_cgroup_path("0::/system.slice/reachy.service\n"), Some("/system.slice/reachy.service"));
+        assert_eq!(parse_psi("some avg10=12.50 avg60=3.00 avg300=1.00 total=100\nfull avg10=1.00 avg60=0.00 avg300=0.00 total=10\n"), Some(12.5));
+        assert_eq!(parse_meminfo("MemTotal:        1000 kB\nMemFree:  10 kB\nMemAvailable:     400 kB\n"), Some((1000 * 1024, 400 * 1024)));
+        assert_eq!(parse_limit("max\n"), None);
+        
+        let config = ResourceGuardConfig::default();
+        let snapshot = |used, psi| MemorySnapshot { used_bytes: used, limit_bytes: 100, psi_some_avg10: psi };
+        assert_eq!(config.pressure_level(&snapshot(50, None)), PressureLevel::Normal);
+        assert_eq!(config.pressure_level(&snapshot(85, Some(1.0))), PressureLevel::Warning);
+        assert_eq!(config.pressure_level(&snapshot(50, Some(30.0))), PressureLevel::Critical);
+    }
+    
+    #[test]
+    fn test_tracker_hysteresis() {
+        let mut tracker = PressureTracker::new(Duration::from_secs(10));
+        let start = Instant::now();
+        
+        assert_eq!(tracker.update(PressureLevel::Critical, start), Some(PressureLevel::Critical));
+        assert_eq!(tracker.update(PressureLevel::Normal, start + Duration::from_secs(1)), None);
+        // 中途回到严重时重新计时
+        assert_eq!(tracker.update(PressureLevel::Critical, start + Duration::from_secs(5)), None);
+        assert_eq!(tracker.update(PressureLevel::Normal, start + Duration::from_secs(6)), None);
+        assert_eq!(tracker.update(PressureLevel::Normal, start + Duration::from_secs(12)), None);
+        assert_eq!(tracker.update(PressureLevel::Normal, start + Duration::from_secs(16)), Some(PressureLevel::Normal));
+        assert_eq!(tracker.level(), PressureLevel::Normal);
+    }
+}
@@ -0,0 +1,461 @@
+//! 机器人模型导出
+//! 
+//! 由关节集合（限位、速度、力矩）、坐标变换配置（头部偏移、相机安装位姿）和连杆质量
+//! 生成URDF/SDF模型，供Gazebo、MuJoCo等仿真器加载，与`sim_bridge`配合在仿真中验证动作。
+//!
+//! 连杆结构按关节分组确定：底座转盘连接底座，头部和手臂关节按定义顺序串联在机身上，
+//! 天线安装在头部末端。各关节的安装位置默认按近似布局生成，可在`joint_origins`中覆盖。
+//! 转轴由关节名推断：yaw/pan绕z轴，roll绕x轴，其余（tilt/pitch/天线）绕y轴。
+//! 辨识得到的关节动力学参数（见`dynamics`模块）可以写入模型的阻尼、摩擦和惯量。
+
+use crate::common::*;
+use crate::dynamics::DynamicsParams;
+use crate::joints::{JointDefinition, JointGroup, JointSetConfig};
+use crate::transforms::TransformConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// 模型文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelFormat {
+    Urdf,
+    Sdf,
+}
+
+impl std::str::FromStr for ModelFormat {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "urdf" => Ok(Self::Urdf),
+            "sdf" => Ok(Self::Sdf),
+            other => Err(anyhow::anyhow!("不支持的模型格式: {}（可选 urdf、sdf）", other)),
+        }
+    }
+}
+
+/// 模型导出配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RobotModelConfig {
+    pub name: String,
+    /// 底座连杆质量（kg）
+    pub base_mass: f64,
+    /// 未单独配置的连杆质量（kg）
+    pub default_link_mass: f64,
+    /// 各关节所驱动连杆的质量（kg），按关节名配置
+    #[serde(default)]
+    pub link_masses: HashMap<String, f64>,
+    /// 各关节相对父连杆的安装位置（米），未列出的关节使用默认布局
+    #[serde(default)]
+    pub joint_origins: HashMap<String, Vector3>,
+}
+
+impl Default for RobotModelConfig {
+    fn default() -> Self {
+        Self {
+            name: "reachy_mini".to_string(),
+            base_mass: 1.5,
+            default_link_mass: 0.05,
+            link_masses: HashMap::from([
+                ("base_yaw".to_string(), 0.8),
+                ("head_tilt".to_string(), 0.35),
+            ]),
+            joint_origins: HashMap::new(),
+        }
+    }
+}
+
+impl ConfigValidation for RobotModelConfig {
+    fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            return Err(anyhow::anyhow!("模型名称不能为空"));
+        }
+        
+        let masses = std::iter::once(&self.base_mass)
+            .chain(std::iter::once(&self.default_link_mass))
+            .chain(self.link_masses.values());
+        for mass in masses {
+            if !(*mass > 0.0 && mass.is_finite()) {
+                return Err(anyhow::anyhow!("连杆质量必须为正数"));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 底座连杆名
+pub const BASE_LINK: &str = "base_link";
+/// 相机连杆名
+pub const CAMERA_LINK: &str = "camera_link";
+
+/// 连杆惯量按半径2cm的均质球估算
+const LINK_RADIUS: f64 = 0.02;
+
+/// 关节类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelJointKind {
+    Revolute,
+    Continuous,
+    Fixed,
+}
+
+/// 模型中的连杆
+#[derive(Debug, Clone)]
+pub struct ModelLink {
+    pub name: String,
+    pub mass: f64,
+    /// 转动惯量（kg·m²），None时按质量估算
+    pub inertia: Option<f64>,
+}
+
+/// 模型中的关节
+#[derive(Debug, Clone)]
+pub struct ModelJoint {
+    pub name: String,
+    pub kind: ModelJointKind,
+    pub parent: String,
+    pub child: String,
+    /// 相对父连杆的位置（米）和姿态（roll, pitch, yaw）
+    pub origin: Vector3,
+    pub rpy: (f64, f64, f64),
+    pub axis: Vector3,
+    pub lower: f64,
+    pub upper: f64,
+    pub effort: f64,
+    pub velocity: f64,
+    /// 粘滞阻尼（N·m·s/rad）和库仑摩擦（N·m）
+    pub damping: f64,
+    pub friction: f64,
+}
+
+/// 连杆和关节组成的模型树，由根连杆开始按父子顺序排列
+#[derive(Debug, Clone)]
+pub struct RobotModel {
+    pub name: String,
+    pub links: Vec<ModelLink>,
+    pub joints: Vec<ModelJoint>,
+}
+
+fn link_name(joint: &str) -> String {
+    format!("{}_link", joint)
+}
+
+/// 由关节名推断转轴
+fn joint_axis(name: &str) -> Vector3 {
+    if name.contains("yaw") || name.contains("pan") {
+        Vector3::new(0.0, 0.0, 1.0)
+    } else if name.contains("roll") {
+        Vector3::new(1.0, 0.0, 0.0)
+    } else {
+        Vector3::new(0.0, 1.0, 0.0)
+    }
+}
+
+/// 左侧关节取+y，右侧取-y
+fn side(name: &str) -> f64 {
+    if name.starts_with("right") { -1.0 } else { 1.0 }
+}
+
+/// 默认布局：index为关节在所属串联链中的序号
+fn default_origin(group: JointGroup, name: &str, index: usize, transforms: &TransformConfig) -> Vector3 {
+    match (group, index) {
+        (JointGroup::Base, _) => Vector3::zero(),
+        (JointGroup::Head, 0) => transforms.head_offset,
+        (JointGroup::Head, _) => Vector3::zero(),
+        (JointGroup::Antenna, _) => Vector3::new(-0.01, 0.03 * side(name), 0.06),
+        (JointGroup::LeftArm | JointGroup::RightArm, 0) => Vector3::new(0.0, 0.08 * side(name), 0.1),
+        (JointGroup::LeftArm | JointGroup::RightArm, 1) => Vector3::zero(),
+        (JointGroup::LeftArm | JointGroup::RightArm, _) => Vector3::new(0.0, 0.0, -0.08),
+    }
+}
+
+impl RobotModel {
+    /// 由关节集合和坐标变换配置构建模型
+    pub fn build(joints: &JointSetConfig, transforms: &TransformConfig, config: &RobotModelConfig) -> Result<Self> {
+        joints.validate()?;
+        transforms.validate()?;
+        config.validate()?;
+        
+        let mut model = Self {
+            name: config.name.clone(),
+            links: vec![ModelLink { name: BASE_LINK.to_string(), mass: config.base_mass, inertia: None }],
+            joints: Vec::new(),
+        };
+        
+        // 机身连杆：有底座转盘时为转盘连杆，否则为底座本身
+        let mut body = BASE_LINK.to_string();
+        for (index, joint) in joints.group(JointGroup::Base).enumerate() {
+            model.add_joint(joint, &body, index, transforms, config);
+            body = link_name(&joint.name);
+        }
+        
+        let mut head = body.clone();
+        for (index, joint) in joints.group(JointGroup::Head).enumerate() {
+            model.add_joint(joint, &head, index, transforms, config);
+            head = link_name(&joint.name);
+        }
+        
+        for joint in joints.group(JointGroup::Antenna) {
+            model.add_joint(joint, &head, 0, transforms, config);
+        }
+        
+        for group in [JointGroup::LeftArm, JointGroup::RightArm] {
+            let mut parent = body.clone();
+            for (index, joint) in joints.group(group).enumerate() {
+                model.add_joint(joint, &parent, index, transforms, config);
+                parent = link_name(&joint.name);
+            }
+        }
+        
+        // 相机固定在头部末端
+        model.links.push(ModelLink { name: CAMERA_LINK.to_string(), mass: config.default_link_mass, inertia: None });
+        model.joints.push(ModelJoint {
+            name: "camera_joint".to_string(),
+            kind: ModelJointKind::Fixed,
+            parent: head,
+            child: CAMERA_LINK.to_string(),
+            origin: transforms.camera_mount.position,
+            rpy: transforms.camera_mount.orientation.to_euler(),
+            axis: Vector3::new(1.0, 0.0, 0.0),
+            lower: 0.0,
+            upper: 0.0,
+            effort: 0.0,
+            velocity: 0.0,
+            damping: 0.0,
+            friction: 0.0,
+        });
+        
+        Ok(model)
+    }
+    
+    fn add_joint(
+        &mut self,
+        joint: &JointDefinition,
+        parent: &str,
+        index: usize,
+        transforms: &TransformConfig,
+        config: &RobotModelConfig,
+    ) {
+        let child = link_name(&joint.name);
+        let mass = config.link_masses.get(&joint.name).copied().unwrap_or(config.default_link_mass);
+        let origin = config.joint_origins.get(&joint.name).copied()
+            .unwrap_or_else(|| default_origin(joint.group, &joint.name, index, transforms));
+        
+        self.links.push(ModelLink { name: child.clone(), mass, inertia: None });
+        self.joints.push(ModelJoint {
+            name: joint.name.clone(),
+            kind: if joint.continuous { ModelJointKind::Continuous } else { ModelJointKind::Revolute },
+            parent: parent.to_string(),
+            child,
+            origin,
+            rpy: (0.0, 0.0, 0.0),
+            axis: joint_axis(&joint.name),
+            lower: joint.min_position.radians(),
+            upper: joint.max_position.radians(),
+            effort: joint.max_torque,
+            velocity: joint.max_velocity.radians_per_second(),
+            damping: 0.0,
+            friction: 0.0,
+        });
+    }
+    
+    /// 写入辨识得到的动力学参数：摩擦写入关节，等效惯量作为关节所驱动连杆的转动惯量
+    pub fn apply_dynamics(&mut self, dynamics: &HashMap<String, DynamicsParams>) {
+        for joint in &mut self.joints {
+            let Some(params) = dynamics.get(&joint.name) else {
+                continue;
+            };
+            joint.damping = params.viscous_friction;
+            joint.friction = params.coulomb_friction;
+            
+            if params.inertia > 0.0 {
+                if let Some(link) = self.links.iter_mut().find(|link| link.name == joint.child) {
+                    link.inertia = Some(params.inertia);
+                }
+            }
+        }
+    }
+    
+    fn inertia(link: &ModelLink) -> f64 {
+        link.inertia.unwrap_or(0.4 * link.mass * LINK_RADIUS * LINK_RADIUS)
+    }
+    
+    /// 导出为指定格式
+    pub fn export(&self, format: ModelFormat) -> String {
+        match format {
+            ModelFormat::Urdf => self.to_urdf(),
+            ModelFormat::Sdf => self.to_sdf(),
+        }
+    }
+    
+    /// 导出URDF
+    pub fn to_urdf(&self) -> String {
+        let mut xml = String::new();
+        let _ = writeln!(xml, r#"<?xml version="1.0"?>"#);
+        let _ = writeln!(xml, r#"<robot name="{}">"#, self.name);
+        
+        for link in &self.links {
+            let i = Self::inertia(link);
+            let _ = writeln!(xml, r#"  <link name="{}">"#, link.name);
+            let _ = writeln!(xml, "    <inertial>");
+            let _ = writeln!(xml, r#"      <mass value="{}"/>"#, link.mass);
+            let _ = writeln!(xml, r#"      <inertia ixx="{i:e}" ixy="0" ixz="0" iyy="{i:e}" iyz="0" izz="{i:e}"/>"#);
+            let _ = writeln!(xml, "    </inertial>");
+            let _ = writeln!(xml, "  </link>");
+        }
+        
+        for joint in &self.joints {
+            let kind = match joint.kind {
+                ModelJointKind::Revolute => "revolute",
+                ModelJointKind::Continuous => "continuous",
+                ModelJointKind::Fixed => "fixed",
+            };
+            let (o, (roll, pitch, yaw), a) = (joint.origin, joint.rpy, joint.axis);
+            
+            let _ = writeln!(xml, r#"  <joint name="{}" type="{}">"#, joint.name, kind);
+            let _ = writeln!(xml, r#"    <parent link="{}"/>"#, joint.parent);
+            let _ = writeln!(xml, r#"    <child link="{}"/>"#, joint.child);
+            let _ = writeln!(xml, r#"    <origin xyz="{} {} {}" rpy="{} {} {}"/>"#, o.x, o.y, o.z, roll, pitch, yaw);
+            if joint.kind != ModelJointKind::Fixed {
+                let _ = writeln!(xml, r#"    <axis xyz="{} {} {}"/>"#, a.x, a.y, a.z);
+                let _ = writeln!(xml, r#"    <dynamics damping="{}" friction="{}"/>"#, joint.damping, joint.friction);
+            }
+            match joint.kind {
+                ModelJointKind::Revolute => {
+                    let _ = writeln!(xml, r#"    <limit lower="{}" upper="{}" effort="{}" velocity="{}"/>"#,
+                                     joint.lower, joint.upper, joint.effort, joint.velocity);
+                },
+                ModelJointKind::Continuous => {
+                    let _ = writeln!(xml, r#"    <limit effort="{}" velocity="{}"/>"#, joint.effort, joint.velocity);
+                },
+                ModelJointKind::Fixed => {},
+            }
+            let _ = writeln!(xml, "  </joint>");
+        }
+        
+        let _ = writeln!(xml, "</robot>");
+        xml
+    }
+    
+    /// 导出SDF（1.7，位姿以父连杆或关节坐标系表示）
+    pub fn to_sdf(&self) -> String {
+        let mut xml = String::new();
+        let _ = writeln!(xml, r#"<?xml version="1.0"?>"#);
+        let _ = writeln!(xml, r#"<sdf version="1.7">"#);
+        let _ = writeln!(xml, r#"  <model name="{}">"#, self.name);
+        
+        // 连杆位姿取驱动它的关节坐标系，根连杆位于模型原点
+        let parent_joint: HashMap<&str, &str> = self.joints.iter()
+            .map(|j| (j.child.as_str(), j.name.as_str()))
+            .collect();
+        
+        for link in &self.links {
+            let i = Self::inertia(link);
+            let _ = writeln!(xml, r#"    <link name="{}">"#, link.name);
+            if let Some(joint) = parent_joint.get(link.name.as_str()) {
+                let _ = writeln!(xml, r#"      <pose relative_to="{}">0 0 0 0 0 0</pose>"#, joint);
+            }
+            let _ = writeln!(xml, "      <inertial>");
+            let _ = writeln!(xml, "        <mass>{}</mass>", link.mass);
+            let _ = writeln!(xml, "        <inertia><ixx>{i:e}</ixx><ixy>0</ixy><ixz>0</ixz><iyy>{i:e}</iyy><iyz>0</iyz><izz>{i:e}</izz></inertia>");
+            let _ = writeln!(xml, "      </inertial>");
+            let _ = writeln!(xml, "    </link>");
+        }
+        
+        for joint in &self.joints {
+            let kind = match joint.kind {
+                ModelJointKind::Revolute | ModelJointKind::Continuous => "revolute",
+                ModelJointKind::Fixed => "fixed",
+            };
+            let (o, (roll, pitch, yaw), a) = (joint.origin, joint.rpy, joint.axis);
+            
+            let _ = writeln!(xml, r#"    <joint name="{}" type="{}">"#, joint.name, kind);
+            let _ = writeln!(xml, r#"      <pose relative_to="{}">{} {} {} {} {} {}</pose>"#,
+                             joint.parent, o.x, o.y, o.z, roll, pitch, yaw);
+            let _ = writeln!(xml, "      <parent>{}</parent>", joint.parent);
+            let _ = writeln!(xml, "      <child>{}</child>", joint.child);
+            if joint.kind != ModelJointKind::Fixed {
+                let _ = writeln!(xml, "      <axis>");
+                let _ = writeln!(xml, "        <xyz>{} {} {}</xyz>", a.x, a.y, a.z);
+                // SDF用无穷大的限位表示连续旋转
+                let (lower, upper) = match joint.kind {
+                    ModelJointKind::Continuous => ("-1e16".to_string(), "1e16".to_string()),
+                    _ => (joint.lower.to_string(), joint.upper.to_string()),
+                };
+                let _ = writeln!(xml, "        <limit><lower>{}</lower><upper>{}</upper><effort>{}</effort><velocity>{}</velocity></limit>",
+                                 lower, upper, joint.effort, joint.velocity);
+                let _ = writeln!(xml, "        <dynamics><damping>{}</damping><friction>{}</friction></dynamics>",
+                                 joint.damping, joint.friction);
+                let _ = writeln!(xml, "      </axis>");
+            }
+            let _ = writeln!(xml, "    </joint>");
+        }
+        
+        let _ = writeln!(xml, "  </model>");
+        let _ = writeln!(xml, "</sdf>");
+        xml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_model_tree_from_joint_set() {
+        let joints = JointSetConfig::default();
+        let model = RobotModel::build(&joints, &TransformConfig::default(), &RobotModelConfig::default()).unwrap();
+        
+        // 每个关节一个连杆，另加底座和相机
+        assert_eq!(model.joints.len(), joints.joints.len() + 1);
+        assert_eq!(model.links.len(), joints.joints.len() + 2);
+        
+        // 每个关节的父连杆都已在前面定义，模型是一棵以底座为根的树
+        for (index, joint) in model.joints.iter().enumerate() {
+            let defined = model.links[..index + 1].iter().any(|link| link.name == joint.parent);
+            assert!(defined, "关节 {} 的父连杆 {} 未定义", joint.name, joint.parent);
+        }
+        
+        let by_name = |name: &str| model.joints.iter().find(|j| j.name == name).unwrap();
+        assert_eq!(by_name("head_pan").parent, "base_yaw_link");
+        assert_eq!(by_name("head_tilt").parent, "head_pan_link");
+        assert_eq!(by_name("left_antenna").parent, "head_tilt_link");
+        assert_eq!(by_name("camera_joint").parent, "head_tilt_link");
+        assert_eq!(by_name("head_tilt").axis, Vector3::new(0.0, 1.0, 0.0));
+        assert!((by_name("base_yaw").upper - 2.79).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_export_formats() {
+        let mut joints = JointSetConfig::default();
+        joints.joints.iter_mut().find(|j| j.name == "base_yaw").unwrap().continuous = true;
+        let model = RobotModel::build(&joints, &TransformConfig::default(), &RobotModelConfig::default()).unwrap();
+        
+        let urdf = model.export(ModelFormat::Urdf);
+        assert!(urdf.contains(r#"<robot name="reachy_mini">"#));
+        assert!(urdf.contains(r#"<joint name="base_yaw" type="continuous">"#));
+        assert!(urdf.contains(r#"<joint name="head_pan" type="revolute">"#));
+        assert_eq!(urdf.matches("<link ").count(), model.links.len());
+        assert_eq!(urdf.matches("<joint ").count(), urdf.matches("</joint>").count());
+        
+        let sdf = model.export(ModelFormat::Sdf);
+        assert!(sdf.contains(r#"<sdf version="1.7">"#));
+        assert!(sdf.contains("<parent>head_pan_link</parent>"));
+        assert_eq!(sdf.matches("<link ").count(), model.links.len());
+        
+        assert_eq!("SDF".parse::<ModelFormat>().unwrap(), ModelFormat::Sdf);
+        assert!("mjcf".parse::<ModelFormat>().is_err());
+        
+        // 辨识得到的动力学参数写入模型
+        let mut model = model;
+        let params = DynamicsParams { inertia: 0.02, viscous_friction: 0.15, coulomb_friction: 0.05 };
+        model.apply_dynamics(&HashMap::from([("head_pan".to_string(), params)]));
+        let urdf = model.to_urdf();
+        assert!(urdf.contains(r#"<dynamics damping="0.15" friction="0.05"/>"#));
+        assert!(urdf.contains(r#"ixx="2e-2""#));
+    }
+}
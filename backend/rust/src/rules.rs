@@ -0,0 +1,327 @@
+//! 行为规则引擎模块
+//! 
+//! 声明式的"如果-那么"规则：在事件总线上匹配事件和状态条件，
+//! 触发动画、注视等动作。支持冷却时间和优先级，
+//! 常见的反应式行为不需要完整的行为树。
+
+use crate::common::*;
+use crate::events::{EventBus, EventEnvelope, RobotEvent};
+use crate::shutdown::CancellationToken;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::{broadcast, mpsc};
+use log::{info, warn, debug};
+
+/// 规则条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RuleCondition {
+    /// 状态键等于指定值，例如 mode == Idle
+    StateEquals { key: String, value: String },
+    /// 状态键不等于指定值（键不存在也视为不等）
+    StateNotEquals { key: String, value: String },
+    /// 事件置信度不低于阈值
+    MinConfidence(f64),
+    /// 事件来源
+    Source(String),
+}
+
+impl RuleCondition {
+    pub(crate) fn matches(&self, envelope: &EventEnvelope, state: &HashMap<String, String>) -> bool {
+        match self {
+            RuleCondition::StateEquals { key, value } => state.get(key) == Some(value),
+            RuleCondition::StateNotEquals { key, value } => state.get(key) != Some(value),
+            RuleCondition::MinConfidence(threshold) => {
+                envelope.event.confidence().is_some_and(|c| c >= *threshold)
+            }
+            RuleCondition::Source(source) => envelope.source == *source,
+        }
+    }
+}
+
+/// 规则动作
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum RuleAction {
+    PlayAnimation(String),
+    /// 看向触发事件的位置或方位
+    LookAtEvent,
+    SetState { key: String, value: String },
+    /// 发布自定义事件
+    EmitEvent(String),
+    /// 执行命名行为树（见behavior_pack模块）
+    RunBehavior(String),
+}
+
+/// 单条规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    /// 触发事件类型（见RobotEvent::kind）
+    pub on: String,
+    #[serde(default)]
+    pub conditions: Vec<RuleCondition>,
+    pub actions: Vec<RuleAction>,
+    /// 优先级，同一事件匹配多条规则时只执行优先级最高的一条
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub cooldown_ms: u64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 规则引擎配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleEngineConfig {
+    pub rules: Vec<Rule>,
+    /// 初始状态
+    pub initial_state: HashMap<String, String>,
+}
+
+impl Default for RuleEngineConfig {
+    fn default() -> Self {
+        let idle = || RuleCondition::StateEquals { key: "mode".to_string(), value: "Idle".to_string() };
+        
+        let rules = vec![
+            Rule {
+                name: "greet_face".to_string(),
+                on: "FaceDetected".to_string(),
+                conditions: vec![idle(), RuleCondition::MinConfidence(0.7)],
+                actions: vec![RuleAction::LookAtEvent, RuleAction::PlayAnimation("greet".to_string())],
+                priority: 10,
+                cooldown_ms: 30000,
+                enabled: true,
+            },
+            Rule {
+                name: "turn_to_sound".to_string(),
+                on: "SoundDetected".to_string(),
+                conditions: vec![idle(), RuleCondition::MinConfidence(0.5)],
+                actions: vec![RuleAction::LookAtEvent],
+                priority: 5,
+                cooldown_ms: 2000,
+                enabled: true,
+            },
+        ];
+        
+        let mut initial_state = HashMap::new();
+        initial_state.insert("mode".to_string(), "Idle".to_string());
+        
+        Self { rules, initial_state }
+    }
+}
+
+impl ConfigValidation for RuleEngineConfig {
+    fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for rule in &self.rules {
+            if rule.name.is_empty() || rule.on.is_empty() {
+                return Err(anyhow::anyhow!("规则名称和触发事件不能为空"));
+            }
+            
+            if !names.insert(rule.name.as_str()) {
+                return Err(anyhow::anyhow!("规则名称重复: '{}'", rule.name));
+            }
+            
+            if rule.actions.is_empty() {
+                return Err(anyhow::anyhow!("规则 '{}' 没有动作", rule.name));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// 规则触发结果
+#[derive(Debug, Clone)]
+pub struct FiredRule {
+    pub rule: String,
+    pub actions: Vec<RuleAction>,
+    /// 触发规则的事件
+    pub trigger: EventEnvelope,
+}
+
+/// 规则引擎
+#[derive(Debug)]
+pub struct RuleEngine {
+    rules: Vec<Rule>,
+    state: HashMap<String, String>,
+    last_fired: HashMap<String, u64>,
+}
+
+impl RuleEngine {
+    pub fn new(config: RuleEngineConfig) -> Result<Self> {
+        config.validate()?;
+        
+        Ok(Self {
+            rules: config.rules,
+            state: config.initial_state,
+            last_fired: HashMap::new(),
+        })
+    }
+    
+    /// 设置状态值（例如当前模式）
+    pub fn set_state(&mut self, key: &str, value: &str) {
+        self.state.insert(key.to_string(), value.to_string());
+    }
+    
+    pub fn state(&self) -> &HashMap<String, String> {
+        &self.state
+    }
+    
+    /// 对事件求值，返回触发的规则（最多一条）
+    pub fn evaluate(&mut self, envelope: &EventEnvelope) -> Option<FiredRule> {
+        let kind = envelope.event.kind();
+        
+        // 优先级相同时按定义顺序
+        let rule = self.rules.iter()
+            .filter(|r| r.enabled && r.on == kind)
+            .filter(|r| r.conditions.iter().all(|c| c.matches(envelope, &self.state)))
+            .filter(|r| match self.last_fired.get(&r.name) {
+                Some(&last) => envelope.timestamp.saturating_sub(last) >= r.cooldown_ms,
+                None => true,
+            })
+            .fold(None::<&Rule>, |best, r| match best {
+                Some(b) if b.priority >= r.priority => Some(b),
+                _ => Some(r),
+            })?
+            .clone();
+        
+        self.last_fired.insert(rule.name.clone(), envelope.timestamp);
+        
+        // 状态动作立即生效，后续事件能看到新状态
+        for action in &rule.actions {
+            if let RuleAction::SetState { key, value } = action {
+                self.set_state(key, value);
+            }
+        }
+        
+        debug!("事件 {} 触发规则 {}", kind, rule.name);
+        
+        Some(FiredRule {
+            rule: rule.name,
+            actions: rule.actions,
+            trigger: envelope.clone(),
+        })
+    }
+    
+    /// 订阅事件总线并持续求值，触发的规则发送到actions通道，直到收到关闭信号
+    pub async fn run(
+        mut self,
+        bus: EventBus,
+        actions: mpsc::UnboundedSender<FiredRule>,
+        shutdown: CancellationToken,
+    ) {
+        let mut receiver = bus.subscribe();
+        info!("规则引擎启动，共 {} 条规则", self.rules.len());
+        
+        loop {
+            let received = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                received = receiver.recv() => received,
+            };
+            
+            match received {
+                Ok(envelope) => {
+                    // 模式切换事件同步到状态
+                    if let RobotEvent::ModeChanged { to, .. } = &envelope.event {
+                        self.set_state("mode", to);
+                    }
+                    
+                    if let Some(fired) = self.evaluate(&envelope) {
+                        for action in &fired.actions {
+                            if let RuleAction::EmitEvent(name) = action {
+                                bus.publish(&format!("rule:{}", fired.rule), RobotEvent::Custom {
+                                    name: name.clone(),
+                                    data: serde_json::Value::Null,
+                                });
+                            }
+                        }
+                        
+                        if actions.send(fired).is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("规则引擎处理过慢，丢弃了 {} 个事件", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+        
+        info!("规则引擎已停止");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn face_event(timestamp: u64) -> EventEnvelope {
+        EventEnvelope {
+            source: "vision".to_string(),
+            timestamp,
+            event: RobotEvent::FaceDetected { confidence: 0.9, position: None },
+        }
+    }
+    
+    #[test]
+    fn test_rule_conditions_and_cooldown() {
+        let mut engine = RuleEngine::new(RuleEngineConfig::default()).unwrap();
+        
+        let fired = engine.evaluate(&face_event(1000)).unwrap();
+        assert_eq!(fired.rule, "greet_face");
+        assert!(fired.actions.contains(&RuleAction::PlayAnimation("greet".to_string())));
+        
+        // 冷却期间不再触发
+        assert!(engine.evaluate(&face_event(2000)).is_none());
+        assert!(engine.evaluate(&face_event(31000)).is_some());
+        
+        // 非空闲模式不触发
+        engine.set_state("mode", "Teleop");
+        assert!(engine.evaluate(&face_event(100000)).is_none());
+    }
+    
+    #[test]
+    fn test_rule_priority() {
+        let mut config = RuleEngineConfig::default();
+        config.rules.push(Rule {
+            name: "sleepy".to_string(),
+            on: "FaceDetected".to_string(),
+            conditions: Vec::new(),
+            actions: vec![RuleAction::SetState { key: "mode".to_string(), value: "Interacting".to_string() }],
+            priority: 20,
+            cooldown_ms: 0,
+            enabled: true,
+        });
+        let mut engine = RuleEngine::new(config).unwrap();
+        
+        let fired = engine.evaluate(&face_event(1000)).unwrap();
+        assert_eq!(fired.rule, "sleepy");
+        assert_eq!(engine.state().get("mode").map(String::as_str), Some("Interacting"));
+    }
+    
+    #[tokio::test]
+    async fn test_rule_engine_runs_on_bus() {
+        let bus = EventBus::default();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let engine = RuleEngine::new(RuleEngineConfig::default()).unwrap();
+        let shutdown = CancellationToken::new();
+        let handle = tokio::spawn(engine.run(bus.clone(), tx, shutdown.clone()));
+        
+        while bus.subscriber_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        bus.publish("vision", RobotEvent::FaceDetected { confidence: 0.9, position: None });
+        
+        let fired = rx.recv().await.unwrap();
+        assert_eq!(fired.rule, "greet_face");
+        
+        shutdown.cancel();
+        handle.await.unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+//! 语义分割后处理
+//! 
+//! 分割模型输出每个像素各类别的得分（形状[1, 类别数, 高, 宽]），或已经取过argmax的类别图
+//! （形状[1, 高, 宽]）。这里把输出解码为每像素一个类别ID的掩码，统计各类别占画面的比例，
+//! 并按调色板生成与原图混合的彩色叠加图供视频流显示。
+//! 
+//! 各类别的占比以SegmentationCoverage事件发布，规则可以据此做出"桌面大部分被占满"这类判断
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// 一个类别占画面的比例
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassFraction {
+    pub class_id: u32,
+    pub class_name: String,
+    /// 0-1
+    pub fraction: f32,
+}
+
+/// 一次分割的各类别占比，作为事件发布
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentationCoverage {
+    pub model_name: String,
+    /// 按占比从大到小，不含低于模型配置的min_fraction的类别
+    pub classes: Vec<ClassFraction>,
+}
+
+impl SegmentationCoverage {
+    /// 指定类别的占比，不在列表中时为0
+    pub fn fraction(&self, class_name: &str) -> f32 {
+        self.classes.iter().find(|class| class.class_name == class_name).map_or(0.0, |class| class.fraction)
+    }
+}
+
+/// 把模型输出解码为类别掩码，返回(掩码, 宽, 高)，掩码按行存放，每像素一个类别ID
+pub fn decode_mask(output: &[f32], shape: &[i64]) -> Result<(Vec<u8>, u32, u32)> {
+    let dims: Vec<usize> = shape.iter().map(|&d| usize::try_from(d).unwrap_or(0)).collect();
+    let (classes, height, width) = match dims.as_slice() {
+        [1, classes, height, width] => (*classes, *height, *width),
+        [1, height, width] => (1, *height, *width),
+        _ => return Err(anyhow::anyhow!("分割输出形状应为[1, 类别数, 高, 宽]或[1, 高, 宽]: {:?}", shape)),
+    };
+    
+    let plane = height * width;
+    if plane == 0 || classes == 0 || output.len() != classes * plane {
+        return Err(anyhow::anyhow!("分割输出长度 {} 与形状 {:?} 不符", output.len(), shape));
+    }
+    if classes > 256 {
+        return Err(anyhow::anyhow!("分割类别数 {} 超过256", classes));
+    }
+    
+    let mask = if classes == 1 {
+        // 已经是类别图
+        output.iter().map(|&id| id.round().clamp(0.0, 255.0) as u8).collect()
+    } else {
+        // 逐类别整个平面比较，便于向量化
+        let mut best = output[..plane].to_vec();
+        let mut mask = vec![0u8; plane];
+        for (class_id, scores) in output.chunks_exact(plane).enumerate().skip(1) {
+            for ((best, id), &score) in best.iter_mut().zip(mask.iter_mut()).zip(scores) {
+                if score > *best {
+                    *best = score;
+                    *id = class_id as u8;
+                }
+            }
+        }
+        mask
+    };
+    
+    Ok((mask, width as u32, height as u32))
+}
+
+/// 各类别占掩码的比例，按占比从大到小，丢弃低于min_fraction的类别；没有名称的类别名为class_<序号>
+pub fn class_fractions(mask: &[u8], class_names: &[String], min_fraction: f32) -> Vec<ClassFraction> {
+    if mask.is_empty() {
+        return Vec::new();
+    }
+    
+    let mut counts = [0usize; 256];
+    for &id in mask {
+        counts[id as usize] += 1;
+    }
+    
+    let mut fractions: Vec<ClassFraction> = counts.iter().enumerate()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(id, &count)| ClassFraction {
+            class_id: id as u32,
+            class_name: class_names.get(id).cloned().unwrap_or_else(|| format!("class_{}", id)),
+            fraction: count as f32 / mask.len() as f32,
+        })
+        .filter(|class| class.fraction >= min_fraction)
+        .collect();
+    fractions.sort_by(|a, b| b.fraction.total_cmp(&a.fraction));
+    fractions
+}
+
+/// 类别的显示颜色，与PASCAL VOC的调色板相同
+pub fn palette_color(class_id: u8) -> [u8; 3] {
+    let mut color = [0u8; 3];
+    let mut id = class_id;
+    for shift in (0..8).rev() {
+        for (channel, value) in color.iter_mut().enumerate() {
+            *value |= ((id >> channel) & 1) << shift;
+        }
+        id >>= 3;
+    }
+    color
+}
+
+/// 把掩码按调色板着色后与原图混合，alpha为掩码颜色的权重；背景（类别0）不着色
+///
+/// 掩码按最近邻缩放到原图尺寸，输出为RGB8
+pub fn overlay(image: &ImageData, mask: &[u8], mask_width: u32, mask_height: u32, alpha: f32) -> Result<ImageData> {
+    if !image.is_valid() || image.width == 0 || image.height == 0 {
+        return Err(anyhow::anyhow!("图像数据与尺寸不符"));
+    }
+    if mask.len() != (mask_width * mask_height) as usize || mask.is_empty() {
+        return Err(anyhow::anyhow!("掩码长度 {} 与尺寸 {}x{} 不符", mask.len(), mask_width, mask_height));
+    }
+    
+    let (width, height) = (image.width as usize, image.height as usize);
+    let stride = image.channels as usize;
+    let alpha = alpha.clamp(0.0, 1.0);
+    let mut data = Vec::with_capacity(width * height * 3);
+    
+    for y in 0..height {
+        let mask_row = y * mask_height as usize / height * mask_width as usize;
+        for x in 0..width {
+            let pixel = &image.data[(y * width + x) * stride..][..stride];
+            let rgb = match image.format {
+                ImageFormat::BGR8 | ImageFormat::BGRA8 => [pixel[2], pixel[1], pixel[0]],
+                ImageFormat::Gray8 => [pixel[0]; 3],
+                ImageFormat::Gray16 => [pixel[stride - 1]; 3],
+                ImageFormat::RGB8 | ImageFormat::RGBA8 => [pixel[0], pixel[1], pixel[2]],
+            };
+            
+            let class_id = mask[mask_row + x * mask_width as usize / width];
+            if class_id == 0 {
+                data.extend_from_slice(&rgb);
+                continue;
+            }
+            let color = palette_color(class_id);
+            for (&base, &tint) in rgb.iter().zip(&color) {
+                data.push((base as f32 * (1.0 - alpha) + tint as f32 * alpha).round() as u8);
+            }
+        }
+    }
+    
+    Ok(ImageData::from_raw(image.width, image.height, 3, data, ImageFormat::RGB8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_decode_mask_fractions_and_overlay() {
+        // 2个类别，2x2：右列类别1得分更高
+        let output = [0.9, 0.1, 0.8, 0.2, 0.1, 0.7, 0.2, 0.6];
+        let (mask, width, height) = decode_mask(&output, &[1, 2, 2, 2]).unwrap();
+        assert_eq!((mask.as_slice(), width, height), (&[0u8, 1, 0, 1][..], 2, 2));
+        
+        // 已取过argmax的类别图
+        let (ids, _, _) = decode_mask(&[2.0, 0.0, 2.0, 2.0], &[1, 2, 2]).unwrap();
+        assert_eq!(ids, vec![2, 0, 2, 2]);
+        assert!(decode_mask(&output, &[1, 3, 2, 2]).is_err());
+        
+        let names = vec!["background".to_string(), "table".to_string()];
+        let fractions = class_fractions(&ids, &names, 0.3);
+        assert_eq!(fractions.len(), 1);
+        assert_eq!((fractions[0].class_name.as_str(), fractions[0].fraction), ("class_2", 0.75));
+        let coverage = SegmentationCoverage { model_name: "seg".to_string(), classes: class_fractions(&mask, &names, 0.0) };
+        assert_eq!(coverage.fraction("table"), 0.5);
+        assert_eq!(coverage.fraction("chair"), 0.0);
+        
+        assert_eq!(palette_color(1), [128, 0, 0]);
+        assert_eq!(palette_color(15), [192, 128, 128]);
+        
+        // 4x2的BGR图像，掩码放大两倍：右半边着色，左半边（背景）保持原样
+        let image = ImageData::from_raw(4, 2, 3, [10u8, 20, 30].repeat(8), ImageFormat::BGR8);
+        let blended = overlay(&image, &mask, 2, 2, 0.5).unwrap();
+        assert_eq!(blended.format, ImageFormat::RGB8);
+        assert_eq!(&blended.data[..6], &[30, 20, 10, 30, 20, 10]);
+        assert_eq!(&blended.data[6..9], &[79, 10, 5]);
+    }
+}
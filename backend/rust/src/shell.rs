@@ -0,0 +1,327 @@
+//! reachy shell命令
+//! 
+//! 把`move head_pan 0.3`、`status joints`这类一行命令翻译成HTTP API请求，调试和装机时
+//! 不用为每个小测试写Python。交互循环在`src/bin/reachy.rs`（`shell`特性）。
+//! 角度和角速度默认为弧度，带`deg`后缀时为度，发给API前统一换算成API使用的度。
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+
+/// 命令帮助
+pub const HELP: &str = "\
+命令（角度默认为弧度，如 0.3；加deg后缀为度，如 20deg）:
+  move <head_pan|head_tilt> <角度> [速度]   移动单个关节，另一个关节保持当前位置
+  look <pan> <tilt> [速度]                  同时设置头部水平和垂直角度
+  body <x> <y> <z>                          移动身体（米）
+  stop                                      停止所有运动
+  led <颜色>                                设置LED颜色（颜色名或#RRGGBB）
+  status [joints|health]                    机器人状态、关节位置或健康检查
+  connect | disconnect | calibrate          连接、断开、校准
+  get <路径> | post <路径> [JSON]           直接调用任意API
+  help | quit";
+
+/// HTTP方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+/// 一次API请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiRequest {
+    pub method: Method,
+    pub path: String,
+    pub body: Option<Value>,
+}
+
+impl ApiRequest {
+    fn get(path: &str) -> Self {
+        Self { method: Method::Get, path: path.to_string(), body: None }
+    }
+    
+    fn post(path: &str, body: Option<Value>) -> Self {
+        Self { method: Method::Post, path: path.to_string(), body }
+    }
+}
+
+/// 头部关节
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadJoint {
+    Pan,
+    Tilt,
+}
+
+/// status命令显示的内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusView {
+    Summary,
+    Joints,
+    Health,
+}
+
+/// 解析后的命令，角度和速度已换算成度
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShellCommand {
+    Move { joint: HeadJoint, degrees: f64, speed: Option<f64> },
+    Look { pan: f64, tilt: f64, speed: Option<f64> },
+    Body { x: f64, y: f64, z: f64 },
+    Stop,
+    Led(String),
+    Status(StatusView),
+    Connect,
+    Disconnect,
+    Calibrate,
+    Raw(ApiRequest),
+    Help,
+    Quit,
+}
+
+/// 按空白切分，双引号或单引号内的内容作为一个参数
+fn tokenize(line: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote = None;
+    
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_token = true;
+            }
+            None if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    
+    if quote.is_some() {
+        return Err(anyhow::anyhow!("引号没有闭合"));
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// 解析角度或角速度，返回度
+fn parse_angle(text: &str) -> Result<f64> {
+    let (number, degrees) = match text.strip_suffix("deg").or_else(|| text.strip_suffix('°')) {
+        Some(number) => (number, true),
+        None => (text, false),
+    };
+    let value: f64 = number.parse().map_err(|_| anyhow::anyhow!("无法解析角度 '{}'", text))?;
+    if !value.is_finite() {
+        return Err(anyhow::anyhow!("角度必须是有限值: '{}'", text));
+    }
+    Ok(if degrees { value } else { value.to_degrees() })
+}
+
+fn parse_number(text: &str) -> Result<f64> {
+    text.parse::<f64>().ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| anyhow::anyhow!("无法解析数值 '{}'", text))
+}
+
+fn expect_args(args: &[String], min: usize, max: usize, usage: &str) -> Result<()> {
+    if args.len() < min || args.len() > max {
+        return Err(anyhow::anyhow!("用法: {}", usage));
+    }
+    Ok(())
+}
+
+fn api_path(path: &str) -> Result<String> {
+    if !path.starts_with('/') {
+        return Err(anyhow::anyhow!("API路径必须以/开头: '{}'", path));
+    }
+    Ok(path.to_string())
+}
+
+impl ShellCommand {
+    /// 解析一行命令，空行返回None
+    pub fn parse(line: &str) -> Result<Option<Self>> {
+        let tokens = tokenize(line)?;
+        let Some((name, args)) = tokens.split_first() else {
+            return Ok(None);
+        };
+        
+        let command = match name.to_lowercase().as_str() {
+            "move" => {
+                expect_args(args, 2, 3, "move <head_pan|head_tilt> <角度> [速度]")?;
+                let joint = match args[0].as_str() {
+                    "head_pan" | "pan" => HeadJoint::Pan,
+                    "head_tilt" | "tilt" => HeadJoint::Tilt,
+                    other => return Err(anyhow::anyhow!("未知关节 '{}'，可用: head_pan、head_tilt", other)),
+                };
+                ShellCommand::Move {
+                    joint,
+                    degrees: parse_angle(&args[1])?,
+                    speed: args.get(2).map(|speed| parse_angle(speed)).transpose()?,
+                }
+            }
+            "look" => {
+                expect_args(args, 2, 3, "look <pan> <tilt> [速度]")?;
+                ShellCommand::Look {
+                    pan: parse_angle(&args[0])?,
+                    tilt: parse_angle(&args[1])?,
+                    speed: args.get(2).map(|speed| parse_angle(speed)).transpose()?,
+                }
+            }
+            "body" => {
+                expect_args(args, 3, 3, "body <x> <y> <z>")?;
+                ShellCommand::Body { x: parse_number(&args[0])?, y: parse_number(&args[1])?, z: parse_number(&args[2])? }
+            }
+            "stop" => ShellCommand::Stop,
+            "led" => {
+                expect_args(args, 1, 1, "led <颜色>")?;
+                ShellCommand::Led(args[0].clone())
+            }
+            "status" => {
+                expect_args(args, 0, 1, "status [joints|health]")?;
+                match args.first().map(String::as_str) {
+                    None => ShellCommand::Status(StatusView::Summary),
+                    Some("joints") => ShellCommand::Status(StatusView::Joints),
+                    Some("health") => ShellCommand::Status(StatusView::Health),
+                    Some(other) => return Err(anyhow::anyhow!("未知的状态类型 '{}'", other)),
+                }
+            }
+            "connect" => ShellCommand::Connect,
+            "disconnect" => ShellCommand::Disconnect,
+            "calibrate" => ShellCommand::Calibrate,
+            "get" => {
+                expect_args(args, 1, 1, "get <路径>")?;
+                ShellCommand::Raw(ApiRequest::get(&api_path(&args[0])?))
+            }
+            "post" => {
+                expect_args(args, 1, 2, "post <路径> [JSON]")?;
+                let body = args.get(1)
+                    .map(|body| serde_json::from_str(body).map_err(|e| anyhow::anyhow!("请求体不是有效的JSON: {}", e)))
+                    .transpose()?;
+                ShellCommand::Raw(ApiRequest::post(&api_path(&args[0])?, body))
+            }
+            "help" | "?" => ShellCommand::Help,
+            "quit" | "exit" => ShellCommand::Quit,
+            other => return Err(anyhow::anyhow!("未知命令 '{}'，输入 help 查看可用命令", other)),
+        };
+        Ok(Some(command))
+    }
+    
+    /// 是否需要先查询当前头部位置（单关节移动时另一个关节保持不动）
+    pub fn needs_head_position(&self) -> bool {
+        matches!(self, ShellCommand::Move { .. })
+    }
+    
+    /// 翻译成API请求，head为当前头部位置（pan, tilt，度）；help和quit没有请求
+    pub fn to_request(&self, head: Option<(f64, f64)>) -> Result<Option<ApiRequest>> {
+        let request = match self {
+            ShellCommand::Move { joint, degrees, speed } => {
+                let (pan, tilt) = head.ok_or_else(|| anyhow::anyhow!("移动单个关节需要当前头部位置"))?;
+                let (pan, tilt) = match joint {
+                    HeadJoint::Pan => (*degrees, tilt),
+                    HeadJoint::Tilt => (pan, *degrees),
+                };
+                head_move(pan, tilt, *speed)
+            }
+            ShellCommand::Look { pan, tilt, speed } => head_move(*pan, *tilt, *speed),
+            ShellCommand::Body { x, y, z } => ApiRequest::post("/api/robot/body/move", Some(json!({"x": x, "y": y, "z": z}))),
+            ShellCommand::Stop => ApiRequest::post("/api/robot/stop", None),
+            ShellCommand::Led(color) => ApiRequest::post("/api/robot/led/color", Some(json!({"color": color}))),
+            ShellCommand::Status(StatusView::Health) => ApiRequest::get("/api/robot/health"),
+            ShellCommand::Status(_) => ApiRequest::get("/api/robot/status"),
+            ShellCommand::Connect => ApiRequest::post("/api/robot/connect", None),
+            ShellCommand::Disconnect => ApiRequest::post("/api/robot/disconnect", None),
+            ShellCommand::Calibrate => ApiRequest::post("/api/robot/calibrate", None),
+            ShellCommand::Raw(request) => request.clone(),
+            ShellCommand::Help | ShellCommand::Quit => return Ok(None),
+        };
+        Ok(Some(request))
+    }
+    
+    /// 把API响应整理成便于阅读的文本
+    pub fn format_response(&self, response: &Value) -> String {
+        match self {
+            ShellCommand::Status(StatusView::Joints) => {
+                let mut text = String::new();
+                for (group, unit) in [("head_position", "°"), ("body_position", " m")] {
+                    if let Some(positions) = response[group].as_object() {
+                        for (name, value) in positions {
+                            let _ = writeln!(text, "{:<16}{:>10.3}{}", format!("{}.{}", group.trim_end_matches("_position"), name),
+                                             value.as_f64().unwrap_or(f64::NAN), unit);
+                        }
+                    }
+                }
+                text.trim_end().to_string()
+            }
+            _ if response.get("success").is_some() && response.get("message").is_some() => {
+                let mark = if response["success"].as_bool() == Some(true) { "✓" } else { "✗" };
+                format!("{} {}", mark, response["message"].as_str().unwrap_or_default())
+            }
+            _ => serde_json::to_string_pretty(response).unwrap_or_default(),
+        }
+    }
+}
+
+fn head_move(pan: f64, tilt: f64, speed: Option<f64>) -> ApiRequest {
+    let mut body = json!({"pan": pan, "tilt": tilt});
+    if let Some(speed) = speed {
+        body["speed"] = json!(speed);
+    }
+    ApiRequest::post("/api/robot/head/move", Some(body))
+}
+
+/// 从/api/robot/status的响应中取出当前头部位置（pan, tilt，度）
+pub fn head_position(status: &Value) -> Result<(f64, f64)> {
+    let head = &status["head_position"];
+    match (head["pan"].as_f64(), head["tilt"].as_f64()) {
+        (Some(pan), Some(tilt)) => Ok((pan, tilt)),
+        _ => Err(anyhow::anyhow!("状态响应中没有头部位置")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_commands_translate_to_api_requests() {
+        let command = ShellCommand::parse("move head_pan 0.3").unwrap().unwrap();
+        assert!(command.needs_head_position());
+        let request = command.to_request(Some((5.0, -10.0))).unwrap().unwrap();
+        assert_eq!(request.method, Method::Post);
+        assert_eq!(request.path, "/api/robot/head/move");
+        let body = request.body.unwrap();
+        assert!((body["pan"].as_f64().unwrap() - 0.3f64.to_degrees()).abs() < 1e-9);
+        assert_eq!(body["tilt"], -10.0);
+        assert!(body.get("speed").is_none());
+        
+        let request = ShellCommand::parse("look 20deg -5deg 15deg").unwrap().unwrap().to_request(None).unwrap().unwrap();
+        assert_eq!(request.body.unwrap(), json!({"pan": 20.0, "tilt": -5.0, "speed": 15.0}));
+        
+        let request = ShellCommand::parse(r#"post /api/robot/command '{"command": "stop"}'"#).unwrap().unwrap()
+            .to_request(None).unwrap().unwrap();
+        assert_eq!(request.body.unwrap()["command"], "stop");
+        
+        assert_eq!(ShellCommand::parse("   ").unwrap(), None);
+        assert_eq!(ShellCommand::parse("status joints").unwrap(), Some(ShellCommand::Status(StatusView::Joints)));
+        assert!(ShellCommand::parse("move tail 0.3").is_err());
+        assert!(ShellCommand::parse("move head_pan abc").is_err());
+        assert!(ShellCommand::parse("dance").is_err());
+        assert!(ShellCommand::parse("post /x '{").is_err());
+        
+        let status = json!({"head_position": {"pan": 12.5, "tilt": 0.0}, "body_position": {"z": 0.1}});
+        let text = ShellCommand::Status(StatusView::Joints).format_response(&status);
+        assert!(text.contains("head.pan") && text.contains("12.500°"));
+        assert_eq!(head_position(&status).unwrap(), (12.5, 0.0));
+    }
+}
@@ -0,0 +1,139 @@
+//! 协作式关闭
+//! 
+//! 子系统的后台任务通过取消令牌接收关闭信号，在安全的检查点自行退出，
+//! 而不是被`JoinHandle::abort()`在硬件事务中途或持锁期间强行打断。
+//! 停止时先取消令牌，再在超时内等待任务结束，只有超时的任务才会被强制中止。
+
+use log::{debug, error, warn};
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// 等待任务退出的默认超时时间
+pub const DEFAULT_JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 一个子系统的后台任务组
+///
+/// 每次启动共用一个取消令牌；关闭后换成新令牌，子系统可以反复启停。
+pub struct TaskGroup {
+    name: &'static str,
+    token: CancellationToken,
+    tasks: Vec<(&'static str, JoinHandle<()>)>,
+    join_timeout: Duration,
+}
+
+impl TaskGroup {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            token: CancellationToken::new(),
+            tasks: Vec::new(),
+            join_timeout: DEFAULT_JOIN_TIMEOUT,
+        }
+    }
+    
+    pub fn with_join_timeout(mut self, timeout: Duration) -> Self {
+        self.join_timeout = timeout;
+        self
+    }
+    
+    /// 当前这一轮运行的取消令牌
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+    
+    /// 启动异步任务
+    pub fn spawn<F>(&mut self, task: &'static str, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.push((task, tokio::spawn(future)));
+    }
+    
+    /// 启动阻塞任务；阻塞任务无法被中止，必须自行检查令牌
+    pub fn spawn_blocking<F>(&mut self, task: &'static str, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.tasks.push((task, tokio::task::spawn_blocking(f)));
+    }
+    
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+    
+    /// 取消所有任务并等待其退出，返回超时后被强制中止的任务数
+    pub async fn shutdown(&mut self) -> usize {
+        self.token.cancel();
+        
+        let deadline = Instant::now() + self.join_timeout;
+        let mut aborted = 0;
+        
+        for (task, mut handle) in self.tasks.drain(..) {
+            match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(Ok(())) => debug!("{} 任务 {} 已退出", self.name, task),
+                Ok(Err(e)) if e.is_panic() => error!("{} 任务 {} 异常退出: {}", self.name, task, e),
+                Ok(Err(_)) => debug!("{} 任务 {} 已被取消", self.name, task),
+                Err(_) => {
+                    warn!("{} 任务 {} 未在 {:?} 内退出，强制中止", self.name, task, self.join_timeout);
+                    handle.abort();
+                    aborted += 1;
+                }
+            }
+        }
+        
+        // 已取消的令牌不能复用，为下一次启动准备新令牌
+        self.token = CancellationToken::new();
+        aborted
+    }
+}
+
+impl Drop for TaskGroup {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    
+    #[tokio::test]
+    async fn test_cooperative_shutdown_and_restart() {
+        let mut tasks = TaskGroup::new("test");
+        let finished = Arc::new(AtomicBool::new(false));
+        
+        let token = tasks.token();
+        let flag = Arc::clone(&finished);
+        tasks.spawn("worker", async move {
+            token.cancelled().await;
+            // 收到取消信号后完成收尾工作
+            flag.store(true, Ordering::SeqCst);
+        });
+        
+        assert_eq!(tasks.shutdown().await, 0);
+        assert!(finished.load(Ordering::SeqCst));
+        assert!(tasks.is_empty());
+        
+        // 关闭后换了新令牌，可以再次启动
+        assert!(!tasks.token().is_cancelled());
+    }
+    
+    #[tokio::test]
+    async fn test_stuck_task_is_aborted_after_timeout() {
+        let mut tasks = TaskGroup::new("test").with_join_timeout(Duration::from_millis(20));
+        tasks.spawn("stuck", std::future::pending());
+        assert_eq!(tasks.len(), 1);
+        
+        assert_eq!(tasks.shutdown().await, 1);
+    }
+}
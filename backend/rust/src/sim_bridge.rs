@@ -0,0 +1,238 @@
+//! 仿真桥接
+//! 
+//! 启用后实时控制器不再驱动硬件，而是把每个控制周期的关节目标位置通过UDP发给
+//! Gazebo/MuJoCo中的桥接插件，并用仿真器回传的关节状态代替传感器读数。
+//! 仿真模型可由`robot_model`模块导出。
+//!
+//! 报文为单个UDP数据报中的JSON对象，按`type`区分：
+//!
+//! ```json
+//! {"type": "command", "seq": 42, "timestamp": 1700000000000, "positions": {"head_pan": 0.3}}
+//! {"type": "stop", "seq": 43, "timestamp": 1700000000010}
+//! {"type": "state", "timestamp": 1700000000005, "positions": {"head_pan": 0.29}, "velocities": {}, "efforts": {}}
+//! ```
+//!
+//! 控制器发送command和stop（紧急停止时保持当前位置），仿真器回传state；
+//! 角度单位为弧度，时间戳为毫秒。
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+
+/// 单个数据报的最大长度
+const MAX_DATAGRAM: usize = 65_507;
+
+/// 仿真桥接配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimBridgeConfig {
+    pub enabled: bool,
+    /// 仿真器桥接插件的地址
+    pub simulator_address: String,
+    /// 本地接收仿真状态的地址
+    pub local_address: String,
+    /// 超过该时间没有收到仿真状态时视为仿真器离线（毫秒）
+    pub state_timeout_ms: u64,
+}
+
+impl Default for SimBridgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            simulator_address: "127.0.0.1:9870".to_string(),
+            local_address: "0.0.0.0:9871".to_string(),
+            state_timeout_ms: 500,
+        }
+    }
+}
+
+impl ConfigValidation for SimBridgeConfig {
+    fn validate(&self) -> Result<()> {
+        for address in [&self.simulator_address, &self.local_address] {
+            address.parse::<SocketAddr>()
+                .map_err(|_| anyhow::anyhow!("仿真桥接地址无效: {}", address))?;
+        }
+        
+        if self.state_timeout_ms == 0 {
+            return Err(anyhow::anyhow!("仿真状态超时必须大于0"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 仿真器回传的关节状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimState {
+    pub timestamp: u64,
+    pub positions: HashMap<String, f64>,
+    #[serde(default)]
+    pub velocities: HashMap<String, f64>,
+    #[serde(default)]
+    pub efforts: HashMap<String, f64>,
+}
+
+/// 桥接报文
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SimMessage {
+    Command {
+        seq: u64,
+        timestamp: u64,
+        positions: HashMap<String, f64>,
+    },
+    Stop {
+        seq: u64,
+        timestamp: u64,
+    },
+    State(SimState),
+}
+
+/// 与仿真器通信的UDP端点
+pub struct SimBridge {
+    socket: UdpSocket,
+    simulator: SocketAddr,
+    state_timeout: Duration,
+    seq: AtomicU64,
+    latest_state: Mutex<Option<(SimState, Instant)>>,
+}
+
+impl SimBridge {
+    /// 绑定本地地址
+    pub async fn bind(config: &SimBridgeConfig) -> Result<Self> {
+        config.validate()?;
+        
+        let simulator: SocketAddr = config.simulator_address.parse()?;
+        let socket = UdpSocket::bind(&config.local_address).await
+            .map_err(|e| anyhow::anyhow!("仿真桥接绑定 {} 失败: {}", config.local_address, e))?;
+        
+        Ok(Self {
+            socket,
+            simulator,
+            state_timeout: Duration::from_millis(config.state_timeout_ms),
+            seq: AtomicU64::new(0),
+            latest_state: Mutex::new(None),
+        })
+    }
+    
+    /// 本地实际绑定的地址
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+    
+    async fn send(&self, message: &SimMessage) -> Result<()> {
+        let bytes = serde_json::to_vec(message)?;
+        self.socket.send_to(&bytes, self.simulator).await?;
+        Ok(())
+    }
+    
+    fn next_seq(&self) -> u64 {
+        self.seq.fetch_add(1, Ordering::Relaxed)
+    }
+    
+    /// 发送本周期的关节目标位置
+    pub async fn send_targets(&self, positions: &HashMap<String, f64>) -> Result<()> {
+        self.send(&SimMessage::Command {
+            seq: self.next_seq(),
+            timestamp: current_timestamp(),
+            positions: positions.clone(),
+        }).await
+    }
+    
+    /// 通知仿真器保持当前位置
+    pub async fn send_stop(&self) -> Result<()> {
+        self.send(&SimMessage::Stop {
+            seq: self.next_seq(),
+            timestamp: current_timestamp(),
+        }).await
+    }
+    
+    /// 取走已到达的所有数据报，返回最新且未超时的仿真状态；不会阻塞
+    pub fn poll_state(&self) -> Option<SimState> {
+        let mut buffer = vec![0u8; MAX_DATAGRAM];
+        let mut latest = self.latest_state.lock().unwrap_or_else(|e| e.into_inner());
+        
+        while let Ok((len, from)) = self.socket.try_recv_from(&mut buffer) {
+            if from.ip() != self.simulator.ip() {
+                continue;
+            }
+            match serde_json::from_slice::<SimMessage>(&buffer[..len]) {
+                Ok(SimMessage::State(state)) => *latest = Some((state, Instant::now())),
+                Ok(other) => log::debug!("忽略仿真器发来的报文: {:?}", other),
+                Err(e) => log::warn!("仿真状态报文格式错误: {}", e),
+            }
+        }
+        
+        latest.as_ref()
+            .filter(|(_, received)| received.elapsed() <= self.state_timeout)
+            .map(|(state, _)| state.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_message_format() {
+        let message: SimMessage = serde_json::from_str(
+            r#"{"type": "state", "timestamp": 5, "positions": {"head_pan": 0.25}}"#
+        ).unwrap();
+        let SimMessage::State(state) = message else { panic!("应解析为状态报文") };
+        assert_eq!(state.positions["head_pan"], 0.25);
+        assert!(state.velocities.is_empty());
+        
+        let stop = serde_json::to_value(SimMessage::Stop { seq: 1, timestamp: 2 }).unwrap();
+        assert_eq!(stop["type"], "stop");
+        
+        let mut config = SimBridgeConfig::default();
+        assert!(config.validate().is_ok());
+        config.simulator_address = "gazebo".to_string();
+        assert!(config.validate().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_loopback_exchange() {
+        let simulator = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = SimBridgeConfig {
+            enabled: true,
+            simulator_address: simulator.local_addr().unwrap().to_string(),
+            local_address: "127.0.0.1:0".to_string(),
+            state_timeout_ms: 1000,
+        };
+        let bridge = SimBridge::bind(&config).await.unwrap();
+        assert!(bridge.poll_state().is_none());
+        
+        bridge.send_targets(&HashMap::from([("head_pan".to_string(), 0.3)])).await.unwrap();
+        let mut buffer = vec![0u8; MAX_DATAGRAM];
+        let (len, from) = simulator.recv_from(&mut buffer).await.unwrap();
+        match serde_json::from_slice(&buffer[..len]).unwrap() {
+            SimMessage::Command { seq, positions, .. } => {
+                assert_eq!(seq, 0);
+                assert_eq!(positions["head_pan"], 0.3);
+            },
+            other => panic!("应收到目标位置: {:?}", other),
+        }
+        
+        let state = SimState {
+            timestamp: current_timestamp(),
+            positions: HashMap::from([("head_pan".to_string(), 0.28)]),
+            ..SimState::default()
+        };
+        let reply = serde_json::to_vec(&SimMessage::State(state.clone())).unwrap();
+        simulator.send_to(&reply, from).await.unwrap();
+        
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut received = None;
+        while received.is_none() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            received = bridge.poll_state();
+        }
+        assert_eq!(received, Some(state));
+    }
+}
@@ -0,0 +1,99 @@
+//! 软启动模块
+//! 
+//! 开启扭矩或解除急停时，关节会猛地跳回上一次的设定点。
+//! 软启动先以当前位置作为设定点，再在配置的时长内把控制刚度和输出限幅
+//! 从较低值逐步提升到正常值，避免突然的冲击。
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// 软启动配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftStartConfig {
+    pub enabled: bool,
+    /// 刚度从初始值升到正常值的时长（毫秒）
+    pub duration_ms: u64,
+    /// 初始刚度（正常控制输出的比例，0~1）
+    pub initial_stiffness: f64,
+}
+
+impl Default for SoftStartConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            duration_ms: 1500,
+            initial_stiffness: 0.1,
+        }
+    }
+}
+
+impl ConfigValidation for SoftStartConfig {
+    fn validate(&self) -> Result<()> {
+        if self.enabled && self.duration_ms == 0 {
+            return Err(anyhow::anyhow!("软启动时长必须大于0"));
+        }
+        
+        if !(0.0..=1.0).contains(&self.initial_stiffness) {
+            return Err(anyhow::anyhow!("软启动初始刚度必须在0到1之间"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 一次软启动过程
+#[derive(Debug, Clone)]
+pub struct SoftStartRamp {
+    start_time: Instant,
+    duration: Duration,
+    initial_stiffness: f64,
+}
+
+impl SoftStartRamp {
+    pub fn new(config: &SoftStartConfig, start_time: Instant) -> Self {
+        Self {
+            start_time,
+            duration: Duration::from_millis(config.duration_ms),
+            initial_stiffness: config.initial_stiffness,
+        }
+    }
+    
+    /// 当前刚度比例，平滑地从初始值升到1
+    pub fn stiffness(&self, now: Instant) -> f64 {
+        let progress = now.duration_since(self.start_time).as_secs_f64() / self.duration.as_secs_f64();
+        lerp(self.initial_stiffness, 1.0, smooth_step(0.0, 1.0, progress))
+    }
+    
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+    
+    pub fn is_finished(&self, now: Instant) -> bool {
+        now.duration_since(self.start_time) >= self.duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_stiffness_ramp() {
+        let config = SoftStartConfig::default();
+        assert!(config.validate().is_ok());
+        
+        let start = Instant::now();
+        let ramp = SoftStartRamp::new(&config, start);
+        
+        assert!((ramp.stiffness(start) - 0.1).abs() < 1e-9);
+        let halfway = ramp.stiffness(start + Duration::from_millis(750));
+        assert!(halfway > 0.1 && halfway < 1.0);
+        assert!((ramp.stiffness(start + Duration::from_millis(3000)) - 1.0).abs() < 1e-9);
+        assert!(ramp.is_finished(start + Duration::from_millis(1500)));
+        
+        let invalid = SoftStartConfig { initial_stiffness: 1.5, ..SoftStartConfig::default() };
+        assert!(invalid.validate().is_err());
+    }
+}
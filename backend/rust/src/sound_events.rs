@@ -0,0 +1,228 @@
+//! 声音事件分类
+//! 
+//! 把麦克风音频切成固定长度的短窗口交给音频分类模型（如YAMNet、PANNs），为每个窗口标注
+//! 拍手、门铃、叫名字这类事件类别，不需要完整的语音识别就能让行为规则对声音做出反应。
+//! 
+//! 模型直接以单声道波形为输入（形状[1, 样本数]），频谱特征由模型内部计算。得分高于模型
+//! 置信度阈值的类别以SoundEventDetected事件发布；静音窗口不提交推理，节省算力
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// 声音事件模型的配置，作为模型任务的参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundEventConfig {
+    /// 模型要求的采样率（Hz）
+    pub sample_rate: u32,
+    /// 每个窗口的时长（毫秒）
+    pub window_ms: u32,
+    /// 相邻窗口起点的间隔（毫秒），小于窗口时长时窗口相互重叠
+    pub hop_ms: u32,
+    /// 窗口的均方根幅度低于该值时视为静音，不提交推理
+    pub min_rms: f32,
+    /// 每个窗口最多标注的类别数
+    pub top_k: usize,
+    /// 输出为logit时先做softmax；多标签模型输出各自独立的概率，默认不做
+    pub softmax: bool,
+}
+
+impl Default for SoundEventConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 16000,
+            window_ms: 1000,
+            hop_ms: 500,
+            min_rms: 0.01,
+            top_k: 3,
+            softmax: false,
+        }
+    }
+}
+
+impl ConfigValidation for SoundEventConfig {
+    fn validate(&self) -> Result<()> {
+        if self.sample_rate == 0 || self.window_ms == 0 || self.hop_ms == 0 {
+            return Err(anyhow::anyhow!("声音事件的采样率、窗口时长和窗口间隔必须大于0"));
+        }
+        
+        if self.hop_ms > self.window_ms {
+            return Err(anyhow::anyhow!("声音事件的窗口间隔不能大于窗口时长"));
+        }
+        
+        if !(0.0..=1.0).contains(&self.min_rms) {
+            return Err(anyhow::anyhow!("静音阈值必须在0-1之间"));
+        }
+        
+        if self.top_k == 0 {
+            return Err(anyhow::anyhow!("声音事件的top_k必须大于0"));
+        }
+        
+        Ok(())
+    }
+}
+
+impl SoundEventConfig {
+    /// 模型输入的样本数
+    pub fn window_samples(&self) -> usize {
+        (self.sample_rate as u64 * self.window_ms as u64 / 1000) as usize
+    }
+}
+
+/// 检测到的一个声音事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoundEvent {
+    pub model_name: String,
+    pub class_id: u32,
+    pub class_name: String,
+    pub confidence: f64,
+}
+
+/// 把样本放入长度为output的窗口：过长时取最后一段，过短时在前面补零
+pub fn fit_window(samples: &[f32], output: &mut [f32]) {
+    let len = samples.len().min(output.len());
+    let pad = output.len() - len;
+    output[..pad].fill(0.0);
+    output[pad..].copy_from_slice(&samples[samples.len() - len..]);
+}
+
+/// 线性插值重采样到指定样本数
+pub fn resample(samples: &[f32], output_len: usize) -> Vec<f32> {
+    if samples.len() == output_len || samples.is_empty() {
+        return samples.to_vec();
+    }
+    
+    let step = samples.len() as f64 / output_len as f64;
+    (0..output_len).map(|i| {
+        let position = i as f64 * step;
+        let index = position as usize;
+        let next = samples[(index + 1).min(samples.len() - 1)];
+        let t = (position - index as f64) as f32;
+        samples[index] * (1.0 - t) + next * t
+    }).collect()
+}
+
+/// 多声道混为单声道
+pub fn downmix(channels: &[Vec<f32>]) -> Vec<f32> {
+    let len = channels.iter().map(Vec::len).min().unwrap_or(0);
+    (0..len).map(|i| channels.iter().map(|channel| channel[i]).sum::<f32>() / channels.len() as f32).collect()
+}
+
+/// 均方根幅度
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// 把连续的单声道音频切成模型输入窗口
+///
+/// 每累积hop_ms的新样本输出一个窗口（包含最近window_ms的音频，已重采样到模型采样率），
+/// 静音窗口被跳过
+#[derive(Debug, Clone)]
+pub struct SoundWindower {
+    /// 输入采样率下的窗口和间隔样本数
+    window: usize,
+    hop: usize,
+    output_len: usize,
+    min_rms: f32,
+    buffer: Vec<f32>,
+    /// 距上一个窗口新到达的样本数
+    pending: usize,
+}
+
+impl SoundWindower {
+    pub fn new(input_rate: u32, config: &SoundEventConfig) -> Result<Self> {
+        config.validate()?;
+        if input_rate == 0 {
+            return Err(anyhow::anyhow!("输入采样率必须大于0"));
+        }
+        
+        let window = (input_rate as u64 * config.window_ms as u64 / 1000) as usize;
+        Ok(Self {
+            window,
+            hop: (input_rate as u64 * config.hop_ms as u64 / 1000).max(1) as usize,
+            output_len: config.window_samples(),
+            min_rms: config.min_rms,
+            buffer: Vec::with_capacity(window * 2),
+            pending: 0,
+        })
+    }
+    
+    /// 追加样本，返回期间完成的非静音窗口
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Vec<f32>> {
+        let mut windows = Vec::new();
+        let mut rest = samples;
+        while !rest.is_empty() {
+            // 每次最多追加到凑满一个间隔，保证不漏掉窗口
+            let take = rest.len().min(self.hop - self.pending);
+            self.buffer.extend_from_slice(&rest[..take]);
+            self.pending += take;
+            rest = &rest[take..];
+            if self.pending == self.hop {
+                self.pending = 0;
+                windows.extend(self.take_window());
+            }
+        }
+        windows
+    }
+    
+    /// 清空缓存的音频，例如麦克风重新打开后
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.pending = 0;
+    }
+    
+    fn take_window(&mut self) -> Option<Vec<f32>> {
+        // 只保留最近一个窗口的样本
+        if self.buffer.len() > self.window {
+            self.buffer.drain(..self.buffer.len() - self.window);
+        }
+        
+        // 刚启动时缓存不足一个窗口，前面补零
+        let mut window = vec![0.0; self.window];
+        fit_window(&self.buffer, &mut window);
+        if rms(&window) < self.min_rms {
+            return None;
+        }
+        Some(resample(&window, self.output_len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_windows_skip_silence_and_resample() {
+        let config = SoundEventConfig { window_ms: 100, hop_ms: 50, ..SoundEventConfig::default() };
+        assert_eq!(config.window_samples(), 1600);
+        assert!(SoundEventConfig { hop_ms: 200, ..config.clone() }.validate().is_err());
+        
+        // 输入8kHz：窗口800个样本，每400个样本出一个窗口，重采样为1600个
+        let mut windower = SoundWindower::new(8000, &config).unwrap();
+        assert!(windower.push(&[0.0; 1200]).is_empty());
+        let windows = windower.push(&[0.5; 1000]);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].len(), 1600);
+        // 第一个窗口前一半是静音，第二个窗口全是声音
+        assert_eq!((windows[0][0], windows[0][1599]), (0.0, 0.5));
+        assert!(windows[1].iter().all(|&s| s == 0.5));
+        
+        // 剩余的200个样本再加200个凑成一个间隔
+        assert_eq!(windower.push(&[0.5; 200]).len(), 1);
+        windower.reset();
+        assert!(windower.push(&[0.5; 399]).is_empty());
+        
+        let mut output = [1.0; 4];
+        fit_window(&[2.0, 3.0], &mut output);
+        assert_eq!(output, [0.0, 0.0, 2.0, 3.0]);
+        fit_window(&[1.0, 2.0, 3.0, 4.0, 5.0], &mut output);
+        assert_eq!(output, [2.0, 3.0, 4.0, 5.0]);
+        
+        assert_eq!(resample(&[0.0, 1.0], 4), vec![0.0, 0.5, 1.0, 1.0]);
+        assert_eq!(downmix(&[vec![1.0, 0.0], vec![0.0, 0.0]]), vec![0.5, 0.0]);
+    }
+}
@@ -0,0 +1,223 @@
+//! 声源定位模块
+//! 
+//! 基于双麦克风的到达时间差（TDOA）估计声源方位，
+//! 输出的方位事件可以交给注视控制器，让机器人转向说话的人。
+//! 双麦克风无法区分前后，估计结果统一视为前半平面。
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use log::debug;
+
+/// 空气中的声速（米/秒）
+const SPEED_OF_SOUND: f64 = 343.0;
+
+/// 声源定位配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoundLocalizationConfig {
+    pub sample_rate: u32,
+    /// 两个麦克风之间的距离（米），左麦克风位于+y方向
+    pub mic_distance: f64,
+    /// 触发定位的最小RMS能量
+    pub energy_threshold: f32,
+    /// 互相关峰值的最小归一化强度（0-1）
+    pub min_confidence: f64,
+    /// 方位平滑系数（0-1，越大越平滑）
+    pub smoothing: f64,
+}
+
+impl Default for SoundLocalizationConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 16000,
+            mic_distance: 0.1,
+            energy_threshold: 0.01,
+            min_confidence: 0.3,
+            smoothing: 0.5,
+        }
+    }
+}
+
+impl ConfigValidation for SoundLocalizationConfig {
+    fn validate(&self) -> Result<()> {
+        if self.sample_rate == 0 {
+            return Err(anyhow::anyhow!("采样率必须大于0"));
+        }
+        
+        if self.mic_distance <= 0.0 {
+            return Err(anyhow::anyhow!("麦克风间距必须为正数"));
+        }
+        
+        if !(0.0..=1.0).contains(&self.min_confidence) {
+            return Err(anyhow::anyhow!("最小置信度必须在0到1之间"));
+        }
+        
+        if !(0.0..1.0).contains(&self.smoothing) {
+            return Err(anyhow::anyhow!("平滑系数必须在0到1之间"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 声源方位事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoundBearing {
+    /// 相对麦克风阵列正前方的方位角（rad，向左为正）
+    pub azimuth: f64,
+    /// 互相关峰值强度（0-1）
+    pub confidence: f64,
+    /// 信号RMS能量
+    pub energy: f32,
+    pub timestamp: u64,
+}
+
+/// 双麦克风声源定位器
+#[derive(Debug, Clone)]
+pub struct SoundLocalizer {
+    config: SoundLocalizationConfig,
+    max_lag: usize,
+    smoothed_azimuth: Option<f64>,
+}
+
+impl SoundLocalizer {
+    pub fn new(config: SoundLocalizationConfig) -> Result<Self> {
+        config.validate()?;
+        
+        let max_lag = (config.mic_distance / SPEED_OF_SOUND * config.sample_rate as f64).ceil() as usize;
+        
+        Ok(Self {
+            config,
+            max_lag: max_lag.max(1),
+            smoothed_azimuth: None,
+        })
+    }
+    
+    /// 处理一帧双声道音频，能量或置信度不足时返回None
+    pub fn process(&mut self, left: &[f32], right: &[f32]) -> Option<SoundBearing> {
+        let len = left.len().min(right.len());
+        if len <= self.max_lag {
+            return None;
+        }
+        let (left, right) = (&left[..len], &right[..len]);
+        
+        let energy = ((left.iter().chain(right).map(|s| s * s).sum::<f32>()) / (2 * len) as f32).sqrt();
+        if energy < self.config.energy_threshold {
+            return None;
+        }
+        
+        let (lag, confidence) = self.estimate_delay(left, right)?;
+        if confidence < self.config.min_confidence {
+            return None;
+        }
+        
+        // 正的延迟表示右麦克风更晚收到，即声源在左侧
+        let tdoa = lag / self.config.sample_rate as f64;
+        let ratio = clamp(tdoa * SPEED_OF_SOUND / self.config.mic_distance, -1.0, 1.0);
+        let raw_azimuth = ratio.asin();
+        
+        let azimuth = match self.smoothed_azimuth {
+            Some(previous) => lerp(raw_azimuth, previous, self.config.smoothing),
+            None => raw_azimuth,
+        };
+        self.smoothed_azimuth = Some(azimuth);
+        
+        debug!("声源方位: {:.1}° (置信度 {:.2})", azimuth.to_degrees(), confidence);
+        
+        Some(SoundBearing {
+            azimuth,
+            confidence,
+            energy,
+            timestamp: current_timestamp(),
+        })
+    }
+    
+    /// 清除平滑状态（例如说话人切换后）
+    pub fn reset(&mut self) {
+        self.smoothed_azimuth = None;
+    }
+    
+    /// 在物理可能的延迟范围内做互相关，返回(亚采样延迟, 归一化峰值)
+    fn estimate_delay(&self, left: &[f32], right: &[f32]) -> Option<(f64, f64)> {
+        let norm = (left.iter().map(|s| (s * s) as f64).sum::<f64>()
+            * right.iter().map(|s| (s * s) as f64).sum::<f64>()).sqrt();
+        if norm <= 0.0 {
+            return None;
+        }
+        
+        let max_lag = self.max_lag as isize;
+        let correlation = |lag: isize| -> f64 {
+            let n = left.len() as isize;
+            (0..n)
+                .filter_map(|i| {
+                    let j = i + lag;
+                    (0..n).contains(&j).then(|| left[i as usize] as f64 * right[j as usize] as f64)
+                })
+                .sum::<f64>()
+        };
+        
+        let values: Vec<f64> = (-max_lag..=max_lag).map(correlation).collect();
+        let (best_index, &best) = values.iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        
+        // 抛物线插值得到亚采样精度
+        let mut offset = 0.0;
+        if best_index > 0 && best_index + 1 < values.len() {
+            let (y0, y1, y2) = (values[best_index - 1], best, values[best_index + 1]);
+            let denominator = y0 - 2.0 * y1 + y2;
+            if denominator.abs() > f64::EPSILON {
+                offset = clamp(0.5 * (y0 - y2) / denominator, -0.5, 0.5);
+            }
+        }
+        
+        let lag = best_index as f64 - max_lag as f64 + offset;
+        Some((lag, clamp(best / norm, 0.0, 1.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 生成带延迟的宽带测试信号
+    fn stereo_signal(delay: usize, len: usize) -> (Vec<f32>, Vec<f32>) {
+        let mut seed = 12345u32;
+        let source: Vec<f32> = (0..len + delay)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                ((seed >> 16) as f32 / 32768.0) - 1.0
+            })
+            .collect();
+        let left = source[delay..].to_vec();
+        let right = source[..len].to_vec();
+        (left, right)
+    }
+    
+    #[test]
+    fn test_sound_from_front() {
+        let mut localizer = SoundLocalizer::new(SoundLocalizationConfig::default()).unwrap();
+        let (left, right) = stereo_signal(0, 1024);
+        let bearing = localizer.process(&left, &right).unwrap();
+        assert!(bearing.azimuth.abs() < 0.05);
+    }
+    
+    #[test]
+    fn test_sound_from_left() {
+        let mut localizer = SoundLocalizer::new(SoundLocalizationConfig::default()).unwrap();
+        // 右麦克风晚2个采样
+        let (left, right) = stereo_signal(2, 1024);
+        let bearing = localizer.process(&left, &right).unwrap();
+        
+        let expected = (2.0 / 16000.0 * SPEED_OF_SOUND / 0.1_f64).asin();
+        assert!((bearing.azimuth - expected).abs() < 0.05);
+        assert!(bearing.azimuth > 0.0);
+    }
+    
+    #[test]
+    fn test_silence_is_ignored() {
+        let mut localizer = SoundLocalizer::new(SoundLocalizationConfig::default()).unwrap();
+        let silence = vec![0.0; 1024];
+        assert!(localizer.process(&silence, &silence).is_none());
+    }
+}
@@ -0,0 +1,201 @@
+//! 关节遥测环形缓冲
+//! 
+//! 控制循环每个周期记录各关节的设定点、测量位置和控制输出，每个关节保留最近
+//! `window_secs`秒的样本，容量按`max_rate_hz`预留（默认1 kHz下5秒），写满后覆盖最旧的样本。
+//! 平时不推送这些数据，用户报告振荡或抖动时再按关节导出最近N秒。
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// 关节遥测配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+    /// 每个关节保留的时长（秒）
+    pub window_secs: f64,
+    /// 按该采样率预留缓冲容量（Hz），控制频率更低时实际保留的时长更长
+    pub max_rate_hz: f64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 5.0,
+            max_rate_hz: 1000.0,
+        }
+    }
+}
+
+impl ConfigValidation for TelemetryConfig {
+    fn validate(&self) -> Result<()> {
+        if self.window_secs <= 0.0 || self.max_rate_hz <= 0.0 {
+            return Err(anyhow::anyhow!("遥测保留时长和采样率必须为正数"));
+        }
+        
+        if self.capacity() > 100_000 {
+            return Err(anyhow::anyhow!("遥测缓冲过大（每个关节 {} 个样本），请缩短保留时长", self.capacity()));
+        }
+        
+        Ok(())
+    }
+}
+
+impl TelemetryConfig {
+    /// 每个关节的缓冲样本数
+    pub fn capacity(&self) -> usize {
+        (self.window_secs * self.max_rate_hz).ceil() as usize
+    }
+}
+
+/// 一个控制周期的关节遥测
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TelemetrySample {
+    /// 相对记录器创建时刻的时间（秒）
+    pub time: f64,
+    /// 本周期的目标位置（rad），没有轨迹或微动时为None
+    pub setpoint: Option<f64>,
+    /// 测量位置（展开后，rad）
+    pub measured: f64,
+    /// 控制输出，没有设定点时为None
+    pub output: Option<f64>,
+}
+
+/// 导出的一段关节遥测
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryDump {
+    pub joint: String,
+    /// 按样本时间估计的采样率（Hz）
+    pub sample_rate_hz: f64,
+    pub samples: Vec<TelemetrySample>,
+}
+
+impl TelemetryDump {
+    /// 导出为CSV，缺失的设定点和输出留空
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time,setpoint,measured,output\n");
+        let optional = |value: Option<f64>| value.map(|value| value.to_string()).unwrap_or_default();
+        for sample in &self.samples {
+            let _ = writeln!(csv, "{},{},{},{}", sample.time, optional(sample.setpoint), sample.measured, optional(sample.output));
+        }
+        csv
+    }
+}
+
+/// 各关节的遥测环形缓冲
+///
+/// 关节集合在创建时固定，每个关节一把锁，控制循环写入时不与其他关节的导出竞争。
+#[derive(Debug)]
+pub struct TelemetryRecorder {
+    config: TelemetryConfig,
+    start: Instant,
+    joints: HashMap<String, Mutex<VecDeque<TelemetrySample>>>,
+}
+
+impl TelemetryRecorder {
+    pub fn new(config: TelemetryConfig, joints: impl IntoIterator<Item = String>) -> Result<Self> {
+        config.validate()?;
+        let joints = if config.enabled {
+            joints.into_iter()
+                .map(|joint| (joint, Mutex::new(VecDeque::with_capacity(config.capacity()))))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        
+        Ok(Self { config, start: Instant::now(), joints })
+    }
+    
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// 记录一个关节在at时刻的样本，缓冲写满时丢弃最旧的样本
+    pub fn record(&self, joint: &str, at: Instant, setpoint: Option<f64>, measured: f64, output: Option<f64>) {
+        let Some(ring) = self.joints.get(joint) else {
+            return;
+        };
+        
+        let mut ring = ring.lock().unwrap_or_else(|e| e.into_inner());
+        if ring.len() == self.config.capacity() {
+            ring.pop_front();
+        }
+        ring.push_back(TelemetrySample {
+            time: at.saturating_duration_since(self.start).as_secs_f64(),
+            setpoint,
+            measured,
+            output,
+        });
+    }
+    
+    /// 导出关节最近seconds秒的样本
+    pub fn dump(&self, joint: &str, seconds: f64) -> Result<TelemetryDump> {
+        if !self.config.enabled {
+            return Err(anyhow::anyhow!("关节遥测未启用"));
+        }
+        
+        if !(seconds > 0.0 && seconds <= self.config.window_secs) {
+            return Err(anyhow::anyhow!("导出时长必须在0到{}秒之间", self.config.window_secs));
+        }
+        
+        let ring = self.joints.get(joint).ok_or_else(|| anyhow::anyhow!("未知关节: {}", joint))?;
+        let ring = ring.lock().unwrap_or_else(|e| e.into_inner());
+        let samples: Vec<TelemetrySample> = match ring.back() {
+            Some(last) => {
+                let since = last.time - seconds;
+                let first = ring.partition_point(|sample| sample.time < since);
+                ring.range(first..).copied().collect()
+            }
+            None => Vec::new(),
+        };
+        drop(ring);
+        
+        let sample_rate_hz = match (samples.first(), samples.last()) {
+            (Some(first), Some(last)) if last.time > first.time => (samples.len() - 1) as f64 / (last.time - first.time),
+            _ => 0.0,
+        };
+        
+        Ok(TelemetryDump { joint: joint.to_string(), sample_rate_hz, samples })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    
+    #[test]
+    fn test_ring_keeps_latest_window() {
+        let config = TelemetryConfig { enabled: true, window_secs: 0.1, max_rate_hz: 1000.0 };
+        let recorder = TelemetryRecorder::new(config, ["head_pan".to_string()]).unwrap();
+        let start = recorder.start;
+        
+        // 1 kHz写入0.3秒，只保留最近100个样本
+        for i in 0..300u64 {
+            let setpoint = (i % 2 == 0).then_some(0.1);
+            recorder.record("head_pan", start + Duration::from_millis(i), setpoint, i as f64, setpoint.map(|_| 1.0));
+        }
+        recorder.record("unknown", start, None, 0.0, None);
+        
+        let dump = recorder.dump("head_pan", 0.1).unwrap();
+        assert_eq!(dump.samples.len(), 100);
+        assert_eq!(dump.samples[0].measured, 200.0);
+        assert_eq!(dump.samples[99].measured, 299.0);
+        assert!((dump.sample_rate_hz - 1000.0).abs() < 1.0);
+        
+        let recent = recorder.dump("head_pan", 0.0205).unwrap();
+        assert_eq!(recent.samples.len(), 21);
+        let csv = recent.to_csv();
+        assert_eq!(csv.lines().count(), 22);
+        assert!(csv.lines().nth(2).unwrap().ends_with(",280,1"));
+        assert!(csv.lines().nth(1).unwrap().ends_with(",,279,"));
+        
+        assert!(recorder.dump("head_pan", 1.0).is_err());
+        assert!(recorder.dump("tail", 0.05).is_err());
+    }
+}
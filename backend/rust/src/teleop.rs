@@ -0,0 +1,159 @@
+//! 网页遥操作消息
+//! 
+//! 网页前端不需要游戏手柄即可直接控制机器人：协商出`teleop`能力后，客户端发送
+//! teleop消息，消息带归一化的摇杆轴（-1到1）、死人开关（按住才会运动）和递增的序号。
+//! 每条teleop消息同时是心跳，按住死人开关期间即使摇杆不动也要至少每100ms发送一次；
+//! 超过`TELEOP_TIMEOUT`收不到心跳或松开死人开关时速度立即归零，机器人回复一次
+//! `teleop_stopped`消息说明原因。
+//!
+//! ```json
+//! {
+//!   "type": "teleop",
+//!   "seq": 42,
+//!   "deadman": true,
+//!   "axes": { "head_pan": 0.5, "head_tilt": -0.2, "body_x": 0.0, "body_y": 0.0, "body_z": 0.0 }
+//! }
+//! ```
+//!
+//! 轴的值乘以各关节的最大速度得到速度指令，未给出的轴为0，超出范围的值被截断，
+//! 非有限值按0处理。
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// 心跳超时：超过这个时间收不到teleop消息，速度归零
+pub const TELEOP_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// 归一化的摇杆轴，取值-1到1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeleopAxes {
+    pub head_pan: f64,
+    pub head_tilt: f64,
+    pub body_x: f64,
+    pub body_y: f64,
+    pub body_z: f64,
+}
+
+impl TeleopAxes {
+    pub const ZERO: Self = Self { head_pan: 0.0, head_tilt: 0.0, body_x: 0.0, body_y: 0.0, body_z: 0.0 };
+    
+    /// 截断到[-1, 1]，非有限值按0处理
+    pub fn normalized(self) -> Self {
+        let clamp = |value: f64| if value.is_finite() { value.clamp(-1.0, 1.0) } else { 0.0 };
+        Self {
+            head_pan: clamp(self.head_pan),
+            head_tilt: clamp(self.head_tilt),
+            body_x: clamp(self.body_x),
+            body_y: clamp(self.body_y),
+            body_z: clamp(self.body_z),
+        }
+    }
+}
+
+/// 客户端发送的teleop消息
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TeleopInput {
+    /// 递增序号，乱序到达的旧消息被丢弃
+    pub seq: u64,
+    /// 死人开关，松开时速度归零
+    #[serde(default)]
+    pub deadman: bool,
+    #[serde(default)]
+    pub axes: TeleopAxes,
+}
+
+/// 速度归零的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TeleopStopReason {
+    /// 松开了死人开关
+    DeadmanReleased,
+    /// 心跳超时
+    Timeout,
+}
+
+/// 遥操作看门狗：记录最近一次输入，按心跳和死人开关给出当前有效的轴
+#[derive(Debug, Default)]
+pub struct TeleopWatchdog {
+    last: Option<(TeleopInput, Instant)>,
+    active: bool,
+}
+
+impl TeleopWatchdog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 处理一条teleop消息（同时是心跳），乱序到达的旧消息返回false
+    pub fn update(&mut self, input: TeleopInput, now: Instant) -> bool {
+        if self.last.is_some_and(|(last, _)| input.seq <= last.seq) {
+            return false;
+        }
+        self.last = Some((TeleopInput { axes: input.axes.normalized(), ..input }, now));
+        true
+    }
+    
+    /// now时刻有效的轴；从遥操作转为停止时同时返回停止原因（每次停止只返回一次）
+    pub fn poll(&mut self, now: Instant) -> (TeleopAxes, Option<TeleopStopReason>) {
+        let reason = match self.last {
+            None => return (TeleopAxes::ZERO, None),
+            Some((_, at)) if now.duration_since(at) > TELEOP_TIMEOUT => TeleopStopReason::Timeout,
+            Some((input, _)) if !input.deadman => TeleopStopReason::DeadmanReleased,
+            Some((input, _)) => {
+                self.active = true;
+                return (input.axes, None);
+            }
+        };
+        
+        let was_active = std::mem::replace(&mut self.active, false);
+        (TeleopAxes::ZERO, was_active.then_some(reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{ClientMessage, ServerMessage};
+    
+    fn input(seq: u64, deadman: bool, head_pan: f64) -> TeleopInput {
+        TeleopInput { seq, deadman, axes: TeleopAxes { head_pan, ..TeleopAxes::ZERO } }
+    }
+    
+    #[test]
+    fn test_heartbeat_timeout_and_deadman() {
+        let mut watchdog = TeleopWatchdog::new();
+        let start = Instant::now();
+        assert_eq!(watchdog.poll(start), (TeleopAxes::ZERO, None));
+        
+        // 超出范围的轴被截断，乱序的旧消息被丢弃
+        assert!(watchdog.update(input(1, true, 2.0), start));
+        assert!(!watchdog.update(input(1, true, 0.1), start));
+        assert_eq!(watchdog.poll(start).0.head_pan, 1.0);
+        assert_eq!(watchdog.poll(start + Duration::from_millis(300)).0.head_pan, 1.0);
+        
+        // 300ms后收不到心跳速度归零，停止原因只报告一次
+        let late = start + Duration::from_millis(301);
+        assert_eq!(watchdog.poll(late), (TeleopAxes::ZERO, Some(TeleopStopReason::Timeout)));
+        assert_eq!(watchdog.poll(late), (TeleopAxes::ZERO, None));
+        
+        assert!(watchdog.update(input(2, true, f64::NAN), late));
+        assert_eq!(watchdog.poll(late).0, TeleopAxes::ZERO);
+        assert!(watchdog.update(input(3, false, 0.5), late));
+        assert_eq!(watchdog.poll(late), (TeleopAxes::ZERO, Some(TeleopStopReason::DeadmanReleased)));
+    }
+    
+    #[test]
+    fn test_teleop_wire_format() {
+        let json = r#"{"type": "teleop", "seq": 7, "deadman": true, "axes": {"head_tilt": -0.5}}"#;
+        let ClientMessage::Teleop(input) = serde_json::from_str(json).unwrap() else {
+            panic!("应解析为teleop消息");
+        };
+        assert_eq!(input.seq, 7);
+        assert_eq!(input.axes.head_tilt, -0.5);
+        assert_eq!(input.axes.body_x, 0.0);
+        
+        let stopped = ServerMessage::TeleopStopped { reason: TeleopStopReason::Timeout };
+        assert_eq!(serde_json::to_value(stopped).unwrap(), serde_json::json!({"type": "teleop_stopped", "reason": "timeout"}));
+    }
+}
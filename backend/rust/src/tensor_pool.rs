@@ -0,0 +1,176 @@
+//! 张量缓冲区池
+//! 
+//! 推理的输入、输出张量每个请求都要分配几百KB到几MB的Vec<f32>，长时间运行时给分配器带来
+//! 很大压力并产生内存碎片。缓冲区池按容量分级（2的幂）保存用完的缓冲区，之后的请求和批次
+//! 需要同一级容量时直接复用。池中保留的总量不超过PerformanceConfig.memory_pool_size_mb，
+//! 放不下的缓冲区直接释放
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::config::PerformanceConfig;
+
+/// 最小的容量等级（元素个数），更小的缓冲区不值得复用
+const MIN_CLASS: usize = 1024;
+const MB: usize = 1024 * 1024;
+
+/// 缓冲区池统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TensorPoolStats {
+    pub capacity_mb: f64,
+    /// 池中空闲缓冲区占用的内存
+    pub pooled_mb: f64,
+    pub pooled_buffers: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64,
+    /// 因池已满或容量不合适而直接释放的缓冲区数
+    pub discarded: u64,
+}
+
+#[derive(Default)]
+struct PoolState {
+    free: HashMap<usize, Vec<Vec<f32>>>,
+    pooled_bytes: usize,
+    hits: u64,
+    misses: u64,
+    discarded: u64,
+}
+
+/// 按容量分级复用的f32缓冲区池，可以在多个推理任务间共享
+pub struct TensorPool {
+    capacity_bytes: usize,
+    state: Mutex<PoolState>,
+}
+
+/// 能容纳len个元素的最小容量等级
+fn size_class(len: usize) -> usize {
+    len.max(MIN_CLASS).next_power_of_two()
+}
+
+impl TensorPool {
+    /// 按PerformanceConfig.memory_pool_size_mb创建
+    pub fn new(config: &PerformanceConfig) -> Self {
+        Self::with_capacity_mb(config.memory_pool_size_mb)
+    }
+    
+    pub fn with_capacity_mb(capacity_mb: usize) -> Self {
+        Self {
+            capacity_bytes: capacity_mb.saturating_mul(MB),
+            state: Mutex::new(PoolState::default()),
+        }
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// 取一个长度为len、内容全为0的缓冲区，用完后通过release放回
+    pub fn acquire(&self, len: usize) -> Vec<f32> {
+        let class = size_class(len);
+        let reused = {
+            let mut state = self.lock();
+            let buffer = state.free.get_mut(&class).and_then(|buffers| buffers.pop());
+            match &buffer {
+                Some(buffer) => {
+                    state.pooled_bytes -= buffer.capacity() * std::mem::size_of::<f32>();
+                    state.hits += 1;
+                },
+                None => state.misses += 1,
+            }
+            buffer
+        };
+        
+        let mut buffer = reused.unwrap_or_else(|| Vec::with_capacity(class));
+        buffer.clear();
+        buffer.resize(len, 0.0);
+        buffer
+    }
+    
+    /// 把用完的缓冲区放回池中；不是由池分配的缓冲区也可以放回，按其容量归入能满足的最大等级
+    pub fn release(&self, buffer: Vec<f32>) {
+        let capacity = buffer.capacity();
+        let bytes = capacity * std::mem::size_of::<f32>();
+        let mut state = self.lock();
+        if capacity < MIN_CLASS || state.pooled_bytes + bytes > self.capacity_bytes {
+            state.discarded += 1;
+            return;
+        }
+        
+        // 容量不小于该等级，可以满足该等级的任何请求
+        let class = 1usize << (usize::BITS - 1 - capacity.leading_zeros());
+        state.pooled_bytes += bytes;
+        state.free.entry(class).or_default().push(buffer);
+    }
+    
+    /// 释放池中所有空闲缓冲区，统计计数保留
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.free.clear();
+        state.pooled_bytes = 0;
+    }
+    
+    pub fn stats(&self) -> TensorPoolStats {
+        let state = self.lock();
+        let requests = state.hits + state.misses;
+        TensorPoolStats {
+            capacity_mb: self.capacity_bytes as f64 / MB as f64,
+            pooled_mb: state.pooled_bytes as f64 / MB as f64,
+            pooled_buffers: state.free.values().map(Vec::len).sum(),
+            hits: state.hits,
+            misses: state.misses,
+            hit_rate: if requests > 0 { state.hits as f64 / requests as f64 } else { 0.0 },
+            discarded: state.discarded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_released_buffer_is_reused_for_same_class() {
+        let pool = TensorPool::with_capacity_mb(1);
+        
+        let mut buffer = pool.acquire(3000);
+        assert_eq!(buffer.len(), 3000);
+        assert_eq!(buffer.capacity(), 4096);
+        buffer[0] = 1.0;
+        let ptr = buffer.as_ptr();
+        pool.release(buffer);
+        
+        // 同一等级复用同一块内存，内容重新清零
+        let buffer = pool.acquire(2500);
+        assert_eq!(buffer.as_ptr(), ptr);
+        assert_eq!(buffer.len(), 2500);
+        assert!(buffer.iter().all(|&v| v == 0.0));
+        
+        let stats = pool.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert!((stats.hit_rate - 0.5).abs() < 1e-9);
+        assert_eq!(stats.pooled_buffers, 0);
+    }
+    
+    #[test]
+    fn test_pool_never_retains_more_than_capacity() {
+        let pool = TensorPool::with_capacity_mb(1);
+        
+        // 每个缓冲区1MB，第二个放不下
+        let first = pool.acquire(MB / 4);
+        let second = pool.acquire(MB / 4);
+        pool.release(first);
+        pool.release(second);
+        // 太小的缓冲区不复用
+        pool.release(vec![0.0; 16]);
+        
+        let stats = pool.stats();
+        assert_eq!(stats.pooled_buffers, 1);
+        assert!((stats.pooled_mb - 1.0).abs() < 1e-9);
+        assert_eq!(stats.discarded, 2);
+        
+        pool.clear();
+        assert_eq!(pool.stats().pooled_buffers, 0);
+    }
+}
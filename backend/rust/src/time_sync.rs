@@ -0,0 +1,348 @@
+//! 时间同步模块
+//! 
+//! 机器人时钟与操作员电脑的时钟会逐渐漂移，导致日志对不上、回放错位。
+//! 本模块通过SNTP查询配置的NTP服务器，或者通过与客户端的时间戳交换，
+//! 测量本地时钟与参考时钟的偏移和漂移，并在导出遥测数据时修正时间戳。
+
+use crate::common::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+use log::{info, debug};
+
+/// 1900-01-01（NTP纪元）到1970-01-01的秒数
+const NTP_UNIX_EPOCH_DELTA: u64 = 2_208_988_800;
+
+/// 时间同步配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeSyncConfig {
+    /// NTP服务器地址（host:port），为None时只使用客户端同步
+    pub ntp_server: Option<String>,
+    pub poll_interval_ms: u64,
+    pub timeout_ms: u64,
+    /// 保留的偏移样本数量
+    pub max_samples: usize,
+    /// 往返时间超过此值的样本被丢弃（毫秒）
+    pub max_round_trip_ms: f64,
+}
+
+impl Default for TimeSyncConfig {
+    fn default() -> Self {
+        Self {
+            ntp_server: None,
+            poll_interval_ms: 64000,
+            timeout_ms: 2000,
+            max_samples: 32,
+            max_round_trip_ms: 500.0,
+        }
+    }
+}
+
+impl ConfigValidation for TimeSyncConfig {
+    fn validate(&self) -> Result<()> {
+        if self.poll_interval_ms == 0 || self.timeout_ms == 0 {
+            return Err(anyhow::anyhow!("时间同步间隔和超时必须大于0"));
+        }
+        
+        if self.max_samples < 2 {
+            return Err(anyhow::anyhow!("时间同步样本数量至少为2"));
+        }
+        
+        if self.max_round_trip_ms <= 0.0 {
+            return Err(anyhow::anyhow!("最大往返时间必须为正数"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 同步来源
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SyncSource {
+    Ntp(String),
+    Client(String),
+}
+
+/// 一次偏移测量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OffsetSample {
+    pub source: SyncSource,
+    /// 参考时钟减本地时钟（毫秒）
+    pub offset_ms: f64,
+    pub round_trip_ms: f64,
+    /// 测量时的本地时间（毫秒）
+    pub local_time: u64,
+}
+
+impl OffsetSample {
+    /// 由一次四时间戳交换计算偏移（NTP算法）
+    ///
+    /// t0: 本地发送，t1: 参考端接收，t2: 参考端发送，t3: 本地接收（毫秒）
+    pub fn from_exchange(source: SyncSource, t0: f64, t1: f64, t2: f64, t3: f64) -> Self {
+        Self {
+            source,
+            offset_ms: ((t1 - t0) + (t2 - t3)) / 2.0,
+            round_trip_ms: ((t3 - t0) - (t2 - t1)).max(0.0),
+            local_time: t3 as u64,
+        }
+    }
+}
+
+/// 时间同步状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimeSyncStatus {
+    pub synchronized: bool,
+    pub source: Option<SyncSource>,
+    pub offset_ms: f64,
+    /// 本地时钟相对参考时钟的漂移（ppm，正值表示本地时钟偏慢）
+    pub drift_ppm: f64,
+    pub round_trip_ms: f64,
+    pub last_sync: u64,
+    pub sample_count: usize,
+}
+
+/// 时间同步器
+#[derive(Debug, Clone)]
+pub struct TimeSync {
+    config: TimeSyncConfig,
+    samples: VecDeque<OffsetSample>,
+}
+
+impl TimeSync {
+    pub fn new(config: TimeSyncConfig) -> Result<Self> {
+        config.validate()?;
+        
+        Ok(Self {
+            config,
+            samples: VecDeque::new(),
+        })
+    }
+    
+    pub fn config(&self) -> &TimeSyncConfig {
+        &self.config
+    }
+    
+    /// 加入一个测量样本，往返时间过长的样本被丢弃
+    pub fn add_sample(&mut self, sample: OffsetSample) -> bool {
+        if sample.round_trip_ms > self.config.max_round_trip_ms {
+            debug!("丢弃往返时间过长的时间同步样本: {:.1}ms", sample.round_trip_ms);
+            return false;
+        }
+        
+        self.samples.push_back(sample);
+        while self.samples.len() > self.config.max_samples {
+            self.samples.pop_front();
+        }
+        true
+    }
+    
+    /// 记录客户端时间交换：客户端发送时刻、机器人接收/发送时刻、客户端接收时刻
+    ///
+    /// 从客户端角度计算出的偏移是"机器人减客户端"，这里取反得到"客户端减机器人"。
+    pub fn add_client_exchange(&mut self, client_id: &str, client_send: u64, robot_receive: u64,
+                               robot_send: u64, client_receive: u64) -> bool {
+        let mut sample = OffsetSample::from_exchange(
+            SyncSource::Client(client_id.to_string()),
+            client_send as f64, robot_receive as f64, robot_send as f64, client_receive as f64,
+        );
+        sample.offset_ms = -sample.offset_ms;
+        sample.local_time = robot_send;
+        self.add_sample(sample)
+    }
+    
+    /// 往返时间最短的样本（最可信）
+    fn best_sample(&self) -> Option<&OffsetSample> {
+        self.samples.iter().min_by(|a, b| a.round_trip_ms.total_cmp(&b.round_trip_ms))
+    }
+    
+    /// 对偏移随时间做最小二乘拟合，返回斜率（毫秒/毫秒）
+    fn drift(&self) -> f64 {
+        let n = self.samples.len() as f64;
+        if n < 2.0 {
+            return 0.0;
+        }
+        
+        let t0 = self.samples[0].local_time as f64;
+        let mean_t = self.samples.iter().map(|s| s.local_time as f64 - t0).sum::<f64>() / n;
+        let mean_o = self.samples.iter().map(|s| s.offset_ms).sum::<f64>() / n;
+        
+        let (mut covariance, mut variance) = (0.0, 0.0);
+        for sample in &self.samples {
+            let dt = sample.local_time as f64 - t0 - mean_t;
+            covariance += dt * (sample.offset_ms - mean_o);
+            variance += dt * dt;
+        }
+        
+        // 样本时间跨度太短时无法估计漂移
+        if variance < 1.0 {
+            0.0
+        } else {
+            covariance / variance
+        }
+    }
+    
+    /// 指定本地时刻的偏移估计（毫秒）
+    pub fn offset_at(&self, local_time: u64) -> f64 {
+        match self.best_sample() {
+            Some(best) => best.offset_ms + self.drift() * (local_time as f64 - best.local_time as f64),
+            None => 0.0,
+        }
+    }
+    
+    /// 把本地时间戳转换为参考时钟时间戳，用于导出的遥测数据
+    pub fn to_reference(&self, local_time: u64) -> u64 {
+        (local_time as f64 + self.offset_at(local_time)).round().max(0.0) as u64
+    }
+    
+    /// 把参考时钟时间戳转换为本地时间戳（to_reference的逆变换），用于按共享时间轴调度
+    pub fn to_local(&self, reference_time: u64) -> u64 {
+        // 偏移随本地时间缓慢变化，用参考时间处的偏移近似一次后再修正一次
+        let estimate = reference_time as f64 - self.offset_at(reference_time);
+        (reference_time as f64 - self.offset_at(estimate.max(0.0) as u64)).round().max(0.0) as u64
+    }
+    
+    /// 按参考时钟的当前时间
+    pub fn corrected_timestamp(&self) -> u64 {
+        self.to_reference(current_timestamp())
+    }
+    
+    pub fn status(&self) -> TimeSyncStatus {
+        let Some(best) = self.best_sample() else {
+            return TimeSyncStatus::default();
+        };
+        
+        let latest = self.samples.back().unwrap();
+        TimeSyncStatus {
+            synchronized: true,
+            source: Some(latest.source.clone()),
+            offset_ms: self.offset_at(current_timestamp()),
+            drift_ppm: self.drift() * 1e6,
+            round_trip_ms: best.round_trip_ms,
+            last_sync: latest.local_time,
+            sample_count: self.samples.len(),
+        }
+    }
+    
+    /// 清除所有样本
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+}
+
+/// NTP 64位时间戳转Unix毫秒
+fn ntp_to_unix_ms(bytes: &[u8]) -> f64 {
+    let seconds = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64;
+    let fraction = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as f64 / 4_294_967_296.0;
+    (seconds - NTP_UNIX_EPOCH_DELTA as f64 + fraction) * 1000.0
+}
+
+/// 向NTP服务器发送一次SNTP查询
+pub async fn query_ntp(server: &str, timeout_ms: u64) -> Result<OffsetSample> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+    
+    // LI=0, VN=4, Mode=3（客户端）
+    let mut request = [0u8; 48];
+    request[0] = 0x23;
+    
+    let t0 = current_timestamp_micros() as f64 / 1000.0;
+    socket.send(&request).await?;
+    
+    let mut response = [0u8; 48];
+    let received = timeout(Duration::from_millis(timeout_ms), socket.recv(&mut response))
+        .await
+        .map_err(|_| anyhow::anyhow!("NTP服务器 {} 响应超时", server))??;
+    let t3 = current_timestamp_micros() as f64 / 1000.0;
+    
+    if received < 48 || response[0] & 0x07 != 4 {
+        return Err(anyhow::anyhow!("NTP服务器 {} 返回了无效的响应", server));
+    }
+    
+    let t1 = ntp_to_unix_ms(&response[32..40]);
+    let t2 = ntp_to_unix_ms(&response[40..48]);
+    
+    Ok(OffsetSample::from_exchange(SyncSource::Ntp(server.to_string()), t0, t1, t2, t3))
+}
+
+/// 定期查询NTP服务器，更新共享的时间同步器，直到收到关闭信号
+pub async fn run_ntp_sync(
+    sync: std::sync::Arc<tokio::sync::RwLock<TimeSync>>,
+    server: String,
+    shutdown: crate::shutdown::CancellationToken,
+) {
+    let (poll_interval, timeout_ms) = {
+        let sync = sync.read().await;
+        (sync.config().poll_interval_ms, sync.config().timeout_ms)
+    };
+    let mut ticker = tokio::time::interval(Duration::from_millis(poll_interval));
+    
+    info!("启动NTP时间同步: {}", server);
+    
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+        match query_ntp(&server, timeout_ms).await {
+            Ok(sample) => {
+                debug!("NTP偏移 {:.2}ms，往返 {:.2}ms", sample.offset_ms, sample.round_trip_ms);
+                sync.write().await.add_sample(sample);
+            }
+            Err(e) => debug!("NTP查询失败: {}", e),
+        }
+    }
+    
+    info!("NTP时间同步结束");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_exchange_offset() {
+        // 参考时钟比本地快100ms，单程延迟10ms
+        let sample = OffsetSample::from_exchange(SyncSource::Ntp("test".to_string()), 1000.0, 1110.0, 1111.0, 1021.0);
+        assert!((sample.offset_ms - 100.0).abs() < 1e-9);
+        assert!((sample.round_trip_ms - 20.0).abs() < 1e-9);
+        
+        // 客户端时钟比机器人慢50ms
+        let mut sync = TimeSync::new(TimeSyncConfig::default()).unwrap();
+        assert!(sync.add_client_exchange("browser", 950, 1010, 1011, 971));
+        assert!((sync.offset_at(1011) + 50.0).abs() < 1e-9);
+        assert_eq!(sync.to_reference(2011), 1961);
+    }
+    
+    #[test]
+    fn test_drift_estimation() {
+        let mut sync = TimeSync::new(TimeSyncConfig::default()).unwrap();
+        // 偏移每秒增加0.1ms，即100ppm
+        for i in 0..10u64 {
+            let local_time = 1_000_000 + i * 60_000;
+            sync.add_sample(OffsetSample {
+                source: SyncSource::Ntp("test".to_string()),
+                offset_ms: 5.0 + i as f64 * 6.0,
+                round_trip_ms: 10.0 + i as f64,
+                local_time,
+            });
+        }
+        
+        assert!((sync.drift() * 1e6 - 100.0).abs() < 1e-6);
+        // 最佳样本为第一个，之后按漂移外推
+        assert!((sync.offset_at(1_060_000) - 11.0).abs() < 1e-6);
+        // 参考时间转换回本地时间
+        let local_time = 1_300_000;
+        assert!(sync.to_local(sync.to_reference(local_time)).abs_diff(local_time) <= 1);
+        
+        // 往返时间过长的样本被丢弃
+        assert!(!sync.add_sample(OffsetSample {
+            source: SyncSource::Ntp("test".to_string()),
+            offset_ms: 0.0,
+            round_trip_ms: 1000.0,
+            local_time: 2_000_000,
+        }));
+    }
+}
@@ -0,0 +1,353 @@
+//! 轨迹文件模块
+//! 
+//! 把录制或规划的关节轨迹导出为文件，或从文件导入后回放，便于与动画工具交换和离线编辑。
+//! 所有角度为弧度，角速度为rad/s，时间为相对轨迹起点的秒数，时间必须严格递增。
+//!
+//! JSON格式：
+//!
+//! ```json
+//! {
+//!   "format": "reachy-mini-trajectory",
+//!   "version": 1,
+//!   "name": "wave",
+//!   "joints": ["head_pan", "head_tilt"],
+//!   "frames": [
+//!     { "time": 0.0, "positions": [0.0, 0.1], "velocities": [0.0, 0.0] },
+//!     { "time": 0.02, "positions": [0.01, 0.1] }
+//!   ]
+//! }
+//! ```
+//!
+//! positions与joints一一对应；velocities可省略。
+//!
+//! CSV格式：以 `#` 开头的行为注释，`# name: <名称>` 指定轨迹名称；
+//! 表头为 `time` 加上每个关节的 `<关节>.position` 列和可选的 `<关节>.velocity` 列，
+//! 速度列要么每个关节都有，要么都没有：
+//!
+//! ```text
+//! # name: wave
+//! time,head_pan.position,head_pan.velocity,head_tilt.position,head_tilt.velocity
+//! 0.000,0.000000,0.000000,0.100000,0.000000
+//! ```
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 文件格式标识
+pub const TRAJECTORY_FORMAT: &str = "reachy-mini-trajectory";
+/// 当前格式版本
+pub const TRAJECTORY_VERSION: u32 = 1;
+
+/// 轨迹中的一帧
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrajectoryFrame {
+    /// 相对轨迹起点的时间（秒）
+    pub time: f64,
+    /// 各关节位置（rad），顺序与joints一致
+    pub positions: Vec<f64>,
+    /// 各关节速度（rad/s），可省略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub velocities: Option<Vec<f64>>,
+}
+
+/// 轨迹文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrajectoryFile {
+    pub format: String,
+    pub version: u32,
+    #[serde(default)]
+    pub name: String,
+    pub joints: Vec<String>,
+    pub frames: Vec<TrajectoryFrame>,
+}
+
+impl TrajectoryFile {
+    pub fn new(name: &str, joints: Vec<String>) -> Self {
+        Self {
+            format: TRAJECTORY_FORMAT.to_string(),
+            version: TRAJECTORY_VERSION,
+            name: name.to_string(),
+            joints,
+            frames: Vec::new(),
+        }
+    }
+    
+    /// 追加一帧，缺少的关节位置记为NaN，由validate()报告
+    pub fn push_frame(&mut self, time: f64, positions: &HashMap<String, f64>, velocities: Option<&HashMap<String, f64>>) {
+        let collect = |values: &HashMap<String, f64>| {
+            self.joints.iter().map(|joint| values.get(joint).copied().unwrap_or(f64::NAN)).collect()
+        };
+        
+        self.frames.push(TrajectoryFrame {
+            time,
+            positions: collect(positions),
+            velocities: velocities.map(collect),
+        });
+    }
+    
+    /// 轨迹时长（秒）
+    pub fn duration(&self) -> f64 {
+        self.frames.last().map(|frame| frame.time).unwrap_or(0.0)
+    }
+    
+    /// 检查格式、关节数量、时间顺序和数值
+    pub fn validate(&self) -> Result<()> {
+        if self.format != TRAJECTORY_FORMAT {
+            return Err(anyhow::anyhow!("不支持的轨迹格式: {}", self.format));
+        }
+        
+        if self.version > TRAJECTORY_VERSION {
+            return Err(anyhow::anyhow!("轨迹格式版本 {} 高于支持的版本 {}", self.version, TRAJECTORY_VERSION));
+        }
+        
+        if self.joints.is_empty() {
+            return Err(anyhow::anyhow!("轨迹没有关节"));
+        }
+        
+        let mut previous_time = None;
+        for (index, frame) in self.frames.iter().enumerate() {
+            if !frame.time.is_finite() || frame.time < 0.0 {
+                return Err(anyhow::anyhow!("第 {} 帧的时间无效", index));
+            }
+            
+            if previous_time.is_some_and(|previous| frame.time <= previous) {
+                return Err(anyhow::anyhow!("第 {} 帧的时间没有递增", index));
+            }
+            previous_time = Some(frame.time);
+            
+            let columns = [Some(&frame.positions), frame.velocities.as_ref()];
+            for values in columns.into_iter().flatten() {
+                if values.len() != self.joints.len() {
+                    return Err(anyhow::anyhow!("第 {} 帧的数值个数与关节数量不一致", index));
+                }
+                
+                if let Some(column) = values.iter().position(|v| !v.is_finite()) {
+                    return Err(anyhow::anyhow!("第 {} 帧关节 {} 的数值无效", index, self.joints[column]));
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// 在t秒处线性插值各关节位置，超过轨迹末尾时返回None
+    pub fn sample(&self, t: f64) -> Option<HashMap<String, f64>> {
+        let next = self.frames.iter().position(|frame| frame.time >= t)?;
+        
+        let positions = if next == 0 {
+            self.frames[0].positions.clone()
+        } else {
+            let (a, b) = (&self.frames[next - 1], &self.frames[next]);
+            let progress = (t - a.time) / (b.time - a.time);
+            a.positions.iter().zip(&b.positions).map(|(&p0, &p1)| p0 + (p1 - p0) * progress).collect()
+        };
+        
+        Some(self.joints.iter().cloned().zip(positions).collect())
+    }
+    
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+    
+    pub fn from_json(json: &str) -> Result<Self> {
+        let trajectory: Self = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("轨迹JSON格式错误: {}", e))?;
+        trajectory.validate()?;
+        Ok(trajectory)
+    }
+    
+    pub fn to_csv(&self) -> String {
+        let with_velocity = !self.frames.is_empty() && self.frames.iter().all(|frame| frame.velocities.is_some());
+        
+        let mut header = vec!["time".to_string()];
+        for joint in &self.joints {
+            header.push(format!("{}.position", joint));
+            if with_velocity {
+                header.push(format!("{}.velocity", joint));
+            }
+        }
+        
+        let mut lines = vec![format!("# name: {}", self.name), header.join(",")];
+        for frame in &self.frames {
+            let mut row = vec![format!("{:.6}", frame.time)];
+            for (index, position) in frame.positions.iter().enumerate() {
+                row.push(format!("{:.6}", position));
+                if let Some(velocities) = frame.velocities.as_ref().filter(|_| with_velocity) {
+                    row.push(format!("{:.6}", velocities[index]));
+                }
+            }
+            lines.push(row.join(","));
+        }
+        
+        lines.join("\n") + "\n"
+    }
+    
+    pub fn from_csv(csv: &str) -> Result<Self> {
+        let mut name = String::new();
+        let mut rows = Vec::new();
+        
+        for (line_number, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(value) = comment.trim().strip_prefix("name:") {
+                    name = value.trim().to_string();
+                }
+            } else if !line.is_empty() {
+                rows.push((line_number + 1, line));
+            }
+        }
+        
+        let (_, header) = rows.first().ok_or_else(|| anyhow::anyhow!("CSV缺少表头"))?;
+        let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+        if columns.first() != Some(&"time") {
+            return Err(anyhow::anyhow!("CSV第一列必须是time"));
+        }
+        
+        // 解析表头：关节按首次出现的顺序排列
+        let mut joints: Vec<String> = Vec::new();
+        let mut layout = Vec::with_capacity(columns.len() - 1);
+        for column in &columns[1..] {
+            let (joint, field) = column.rsplit_once('.')
+                .ok_or_else(|| anyhow::anyhow!("无法识别的列: {}", column))?;
+            let is_velocity = match field {
+                "position" => false,
+                "velocity" => true,
+                _ => return Err(anyhow::anyhow!("无法识别的列: {}", column)),
+            };
+            
+            let index = match joints.iter().position(|j| j == joint) {
+                Some(index) => index,
+                None => {
+                    joints.push(joint.to_string());
+                    joints.len() - 1
+                }
+            };
+            layout.push((index, is_velocity));
+        }
+        
+        let velocity_columns = layout.iter().filter(|(_, is_velocity)| *is_velocity).count();
+        let position_columns = layout.len() - velocity_columns;
+        if position_columns != joints.len() || (velocity_columns != 0 && velocity_columns != joints.len()) {
+            return Err(anyhow::anyhow!("每个关节必须有一个position列，velocity列要么都有要么都没有"));
+        }
+        
+        let mut trajectory = Self::new(&name, joints);
+        for (line_number, row) in &rows[1..] {
+            let values: Vec<f64> = row.split(',')
+                .map(|value| value.trim().parse::<f64>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| anyhow::anyhow!("第 {} 行数值错误: {}", line_number, e))?;
+            
+            if values.len() != columns.len() {
+                return Err(anyhow::anyhow!("第 {} 行的列数与表头不一致", line_number));
+            }
+            
+            let mut positions = vec![0.0; position_columns];
+            let mut velocities = vec![0.0; velocity_columns];
+            for (&(index, is_velocity), &value) in layout.iter().zip(&values[1..]) {
+                if is_velocity {
+                    velocities[index] = value;
+                } else {
+                    positions[index] = value;
+                }
+            }
+            
+            trajectory.frames.push(TrajectoryFrame {
+                time: values[0],
+                positions,
+                velocities: (velocity_columns > 0).then_some(velocities),
+            });
+        }
+        
+        trajectory.validate()?;
+        Ok(trajectory)
+    }
+    
+    /// 按扩展名（.json或.csv）保存
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = match extension(path)?.as_str() {
+            "json" => self.to_json()?,
+            _ => self.to_csv(),
+        };
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+    
+    /// 按扩展名（.json或.csv）加载并校验
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        match extension(path)?.as_str() {
+            "json" => Self::from_json(&content),
+            _ => Self::from_csv(&content),
+        }
+    }
+}
+
+fn extension(path: &Path) -> Result<String> {
+    let extension = path.extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    
+    match extension.as_str() {
+        "json" | "csv" => Ok(extension),
+        _ => Err(anyhow::anyhow!("不支持的轨迹文件类型: {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn sample_trajectory() -> TrajectoryFile {
+        let mut trajectory = TrajectoryFile::new("nod", vec!["head_pan".to_string(), "head_tilt".to_string()]);
+        for step in 0..5 {
+            let t = step as f64 * 0.1;
+            let positions = HashMap::from([("head_pan".to_string(), 0.0), ("head_tilt".to_string(), t)]);
+            let velocities = HashMap::from([("head_pan".to_string(), 0.0), ("head_tilt".to_string(), 1.0)]);
+            trajectory.push_frame(t, &positions, Some(&velocities));
+        }
+        trajectory
+    }
+    
+    #[test]
+    fn test_json_and_csv_round_trip() {
+        let trajectory = sample_trajectory();
+        assert!(trajectory.validate().is_ok());
+        
+        let from_json = TrajectoryFile::from_json(&trajectory.to_json().unwrap()).unwrap();
+        assert_eq!(from_json, trajectory);
+        
+        let csv = trajectory.to_csv();
+        assert!(csv.starts_with("# name: nod\ntime,head_pan.position,head_pan.velocity"));
+        let from_csv = TrajectoryFile::from_csv(&csv).unwrap();
+        assert_eq!(from_csv.name, "nod");
+        assert_eq!(from_csv.joints, trajectory.joints);
+        assert_eq!(from_csv.frames.len(), 5);
+        assert!((from_csv.frames[3].positions[1] - 0.3).abs() < 1e-6);
+        
+        // 不带速度列的CSV
+        let csv = "time,head_pan.position\n0,0.1\n0.5,0.2\n";
+        let trajectory = TrajectoryFile::from_csv(csv).unwrap();
+        assert!(trajectory.frames[0].velocities.is_none());
+        assert!((trajectory.sample(0.25).unwrap()["head_pan"] - 0.15).abs() < 1e-9);
+        assert!(trajectory.sample(0.6).is_none());
+    }
+    
+    #[test]
+    fn test_invalid_files() {
+        let mut trajectory = sample_trajectory();
+        trajectory.frames[2].time = 0.05;
+        assert!(trajectory.validate().is_err());
+        
+        let mut trajectory = sample_trajectory();
+        trajectory.push_frame(1.0, &HashMap::from([("head_pan".to_string(), 0.0)]), None);
+        assert!(trajectory.validate().unwrap_err().to_string().contains("head_tilt"));
+        
+        assert!(TrajectoryFile::from_csv("time,head_pan.angle\n0,0\n").is_err());
+        assert!(TrajectoryFile::from_csv("time,head_pan.position\n0,abc\n").unwrap_err().to_string().contains("第 2 行"));
+        assert!(TrajectoryFile::from_json(r#"{"format": "other", "version": 1, "joints": ["a"], "frames": []}"#).is_err());
+    }
+}
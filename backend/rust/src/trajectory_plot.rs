@@ -0,0 +1,306 @@
+//! 轨迹可视化
+//! 
+//! 执行之前把规划好的轨迹按固定采样率展开为关节位置/速度曲线，以及相机位置和
+//! 视线落点的笛卡尔路径，供前端绘图检查动作。启用`plot`特性时还可以直接渲染为PNG，
+//! 便于在没有界面的机器人上调试；指定字体文件时图中带标题、坐标轴刻度和图例。
+
+use crate::common::*;
+use crate::trajectory_file::TrajectoryFile;
+use crate::transforms::{TransformConfig, TransformTree, BASE_FRAME, CAMERA_FRAME};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 采样参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlotOptions {
+    /// 采样率（Hz）
+    pub sample_rate: f64,
+    /// 视线落点到相机的距离（米）
+    pub gaze_distance: f64,
+    /// 最多采样点数，防止过长的轨迹生成过大的结果
+    pub max_samples: usize,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            sample_rate: 50.0,
+            gaze_distance: 1.0,
+            max_samples: 10_000,
+        }
+    }
+}
+
+impl ConfigValidation for PlotOptions {
+    fn validate(&self) -> Result<()> {
+        if !(self.sample_rate > 0.0 && self.sample_rate.is_finite()) {
+            return Err(anyhow::anyhow!("采样率必须为正数"));
+        }
+        
+        if self.gaze_distance <= 0.0 {
+            return Err(anyhow::anyhow!("视线落点距离必须为正数"));
+        }
+        
+        if self.max_samples < 2 {
+            return Err(anyhow::anyhow!("最多采样点数不能小于2"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 单个关节的采样曲线
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JointSeries {
+    pub joint: String,
+    /// 位置（rad），与times一一对应
+    pub positions: Vec<f64>,
+    /// 由相邻采样点差分得到的速度（rad/s）
+    pub velocities: Vec<f64>,
+}
+
+/// 展开后的轨迹
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrajectoryPlot {
+    pub name: String,
+    pub duration: f64,
+    /// 采样时间（秒）
+    pub times: Vec<f64>,
+    pub joints: Vec<JointSeries>,
+    /// 相机在底座坐标系中的位置（米）
+    pub camera_path: Vec<Vector3>,
+    /// 视线前方gaze_distance处的点在底座坐标系中的位置（米）
+    pub gaze_path: Vec<Vector3>,
+}
+
+/// 按采样率展开轨迹，笛卡尔路径由transforms中的头部运动学计算
+pub fn sample_trajectory(
+    trajectory: &TrajectoryFile,
+    transforms: &TransformConfig,
+    options: &PlotOptions,
+) -> Result<TrajectoryPlot> {
+    trajectory.validate()?;
+    options.validate()?;
+    if trajectory.frames.is_empty() {
+        return Err(anyhow::anyhow!("轨迹没有帧"));
+    }
+    
+    let duration = trajectory.duration();
+    let steps = (duration * options.sample_rate).ceil() as usize;
+    if steps + 1 > options.max_samples {
+        return Err(anyhow::anyhow!(
+            "采样点过多：{:.1} 秒的轨迹按 {} Hz 采样需要 {} 个点，上限 {}",
+            duration, options.sample_rate, steps + 1, options.max_samples
+        ));
+    }
+    
+    let times: Vec<f64> = (0..=steps).map(|i| (i as f64 / options.sample_rate).min(duration)).collect();
+    let mut joints: Vec<JointSeries> = trajectory.joints.iter()
+        .map(|joint| JointSeries { joint: joint.clone(), positions: Vec::new(), velocities: Vec::new() })
+        .collect();
+    
+    // 坐标树查询最新变换，采样点的时间戳要晚于建树时的初始变换
+    let mut tree = TransformTree::new(transforms.clone())?;
+    let start = current_timestamp();
+    let mut camera_path = Vec::with_capacity(times.len());
+    let mut gaze_path = Vec::with_capacity(times.len());
+    
+    for (index, &t) in times.iter().enumerate() {
+        let positions = trajectory.sample(t).unwrap_or_default();
+        let mut states = HashMap::new();
+        for series in joints.iter_mut() {
+            let position = positions.get(&series.joint).copied().unwrap_or(0.0);
+            series.positions.push(position);
+            
+            let mut state = JointState::new(series.joint.clone());
+            state.position = position;
+            states.insert(series.joint.clone(), state);
+        }
+        
+        tree.update_from_joint_states(&states, start + index as u64 + 1)?;
+        camera_path.push(tree.transform_point(BASE_FRAME, CAMERA_FRAME, &Vector3::zero(), None)?);
+        gaze_path.push(tree.transform_point(BASE_FRAME, CAMERA_FRAME, &Vector3::new(options.gaze_distance, 0.0, 0.0), None)?);
+    }
+    
+    for series in joints.iter_mut() {
+        series.velocities = differentiate(&times, &series.positions);
+    }
+    
+    Ok(TrajectoryPlot {
+        name: trajectory.name.clone(),
+        duration,
+        times,
+        joints,
+        camera_path,
+        gaze_path,
+    })
+}
+
+/// 差分求导：内部点用中心差分，两端用单侧差分
+fn differentiate(times: &[f64], values: &[f64]) -> Vec<f64> {
+    let n = values.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (i.saturating_sub(1), (i + 1).min(n - 1));
+            let dt = times[b] - times[a];
+            if dt > 0.0 { (values[b] - values[a]) / dt } else { 0.0 }
+        })
+        .collect()
+}
+
+#[cfg(feature = "plot")]
+mod png {
+    use super::TrajectoryPlot;
+    use anyhow::Result;
+    use plotters::prelude::*;
+    use std::path::Path;
+    use std::sync::OnceLock;
+    
+    /// 注册绘图字体，进程内第一次指定的字体生效
+    fn register_font(path: &Path) -> Result<()> {
+        static FONT: OnceLock<std::result::Result<(), String>> = OnceLock::new();
+        
+        FONT.get_or_init(|| {
+            let bytes = std::fs::read(path).map_err(|e| format!("读取字体文件 {} 失败: {}", path.display(), e))?;
+            plotters::style::register_font("sans-serif", FontStyle::Normal, Box::leak(bytes.into_boxed_slice()))
+                .map_err(|_| format!("字体文件 {} 无效", path.display()))
+        })
+        .clone()
+        .map_err(|e| anyhow::anyhow!(e))
+    }
+    
+    fn value_range(series: &[&[f64]]) -> std::ops::Range<f64> {
+        let (min, max) = series.iter()
+            .flat_map(|values| values.iter().copied())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| (min.min(v), max.max(v)));
+        
+        if !min.is_finite() || max - min < 1e-6 {
+            let center = if min.is_finite() { min } else { 0.0 };
+            return (center - 0.1)..(center + 0.1);
+        }
+        
+        let margin = (max - min) * 0.05;
+        (min - margin)..(max + margin)
+    }
+    
+    /// 渲染为PNG：上图为关节位置，下图为关节速度；不指定字体时只画曲线和网格
+    pub fn render_png(plot: &TrajectoryPlot, width: u32, height: u32, font: Option<&Path>) -> Result<Vec<u8>> {
+        if width < 64 || height < 64 {
+            return Err(anyhow::anyhow!("图片尺寸过小: {}x{}", width, height));
+        }
+        
+        let labeled = match font {
+            Some(path) => {
+                register_font(path)?;
+                true
+            },
+            None => false,
+        };
+        let draw_error = |e: DrawingAreaErrorKind<_>| anyhow::anyhow!("绘制轨迹失败: {}", e);
+        
+        let mut buffer = vec![0u8; width as usize * height as usize * 3];
+        {
+            let root = BitMapBackend::with_buffer(&mut buffer, (width, height)).into_drawing_area();
+            root.fill(&WHITE).map_err(draw_error)?;
+            
+            let panels = root.split_evenly((2, 1));
+            let positions: Vec<&[f64]> = plot.joints.iter().map(|s| s.positions.as_slice()).collect();
+            let velocities: Vec<&[f64]> = plot.joints.iter().map(|s| s.velocities.as_slice()).collect();
+            let charts = [("位置 (rad)", positions), ("速度 (rad/s)", velocities)];
+            
+            for (area, (title, series)) in panels.iter().zip(charts) {
+                let mut builder = ChartBuilder::on(area);
+                builder.margin(10);
+                if labeled {
+                    builder.caption(format!("{} {}", plot.name, title), ("sans-serif", 18))
+                        .x_label_area_size(30)
+                        .y_label_area_size(50);
+                }
+                
+                let mut chart = builder
+                    .build_cartesian_2d(0.0..plot.duration.max(1e-3), value_range(&series))
+                    .map_err(draw_error)?;
+                
+                let mut mesh = chart.configure_mesh();
+                if !labeled {
+                    mesh.x_labels(0).y_labels(0);
+                }
+                mesh.draw().map_err(draw_error)?;
+                
+                for (index, (values, joint)) in series.iter().zip(&plot.joints).enumerate() {
+                    let color = Palette99::pick(index).mix(1.0);
+                    let points = plot.times.iter().copied().zip(values.iter().copied());
+                    chart.draw_series(LineSeries::new(points, color.stroke_width(2)))
+                        .map_err(draw_error)?
+                        .label(joint.joint.clone())
+                        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+                }
+                
+                if labeled {
+                    chart.configure_series_labels()
+                        .background_style(WHITE.mix(0.8))
+                        .border_style(BLACK)
+                        .draw()
+                        .map_err(draw_error)?;
+                }
+            }
+            
+            root.present().map_err(draw_error)?;
+        }
+        
+        let image = image::RgbImage::from_raw(width, height, buffer)
+            .ok_or_else(|| anyhow::anyhow!("绘图缓冲区尺寸错误"))?;
+        let mut png = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut png, image::ImageFormat::Png)?;
+        Ok(png.into_inner())
+    }
+}
+
+#[cfg(feature = "plot")]
+pub use png::render_png;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn wave() -> TrajectoryFile {
+        let mut trajectory = TrajectoryFile::new("wave", vec!["head_pan".to_string(), "head_tilt".to_string()]);
+        for (time, pan) in [(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)] {
+            let positions = HashMap::from([("head_pan".to_string(), pan), ("head_tilt".to_string(), 0.0)]);
+            trajectory.push_frame(time, &positions, None);
+        }
+        trajectory
+    }
+    
+    #[test]
+    fn test_sample_trajectory_paths() {
+        let options = PlotOptions { sample_rate: 10.0, ..PlotOptions::default() };
+        let plot = sample_trajectory(&wave(), &TransformConfig::default(), &options).unwrap();
+        
+        assert_eq!(plot.times.len(), 21);
+        assert_eq!(plot.camera_path.len(), 21);
+        let pan = &plot.joints[0];
+        assert!((pan.positions[10] - 1.0).abs() < 1e-9);
+        assert!((pan.velocities[5] - 1.0).abs() < 1e-9);
+        assert!((pan.velocities[15] + 1.0).abs() < 1e-9);
+        
+        // 起点视线朝正前方，转头1 rad后视线落点随之转动
+        let head = TransformConfig::default().head_offset;
+        let start = plot.gaze_path[0] - head;
+        assert!(start.y.abs() < 1e-9 && start.x > 1.0);
+        let turned = plot.gaze_path[10] - head;
+        assert!((turned.y.atan2(turned.x) - 1.0).abs() < 1e-6);
+        
+        let dense = PlotOptions { sample_rate: 1000.0, max_samples: 100, ..PlotOptions::default() };
+        assert!(sample_trajectory(&wave(), &TransformConfig::default(), &dense).is_err());
+    }
+    
+    #[test]
+    #[cfg(feature = "plot")]
+    fn test_render_png() {
+        let plot = sample_trajectory(&wave(), &TransformConfig::default(), &PlotOptions::default()).unwrap();
+        let png = render_png(&plot, 320, 240, None).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
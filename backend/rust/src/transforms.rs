@@ -0,0 +1,533 @@
+//! 坐标变换模块
+//! 
+//! 维护机器人各坐标系（底座、头部、相机、标记点等）之间的父子关系，
+//! 由运动学和标定结果更新，并提供带时间插值的查询接口，
+//! 使视觉结果可以表达在机器人底座坐标系中。
+
+use crate::common::*;
+use crate::joints::JointSetConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use log::{debug, warn};
+
+/// 底座坐标系
+pub const BASE_FRAME: &str = "base";
+/// 机身坐标系（随底座转盘旋转）
+pub const BODY_FRAME: &str = "body";
+/// 头部坐标系
+pub const HEAD_FRAME: &str = "head";
+/// 相机坐标系
+pub const CAMERA_FRAME: &str = "camera";
+
+/// 坐标变换配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformConfig {
+    /// 头部旋转中心相对底座的偏移（米）
+    pub head_offset: Vector3,
+    /// 相机相对头部的安装位姿（标定前的名义值）
+    pub camera_mount: Pose,
+    /// 头部水平转动关节名
+    pub head_pan_joint: String,
+    /// 头部俯仰关节名
+    pub head_tilt_joint: String,
+    /// 底座转盘关节名，None表示机身固定
+    #[serde(default)]
+    pub base_yaw_joint: Option<String>,
+    /// 每个动态坐标系保留的历史变换数量
+    pub history_size: usize,
+    /// 允许超出历史范围的最大时间（毫秒）
+    pub max_extrapolation_ms: u64,
+}
+
+impl Default for TransformConfig {
+    fn default() -> Self {
+        Self {
+            head_offset: Vector3::new(0.0, 0.0, 0.17),
+            camera_mount: Pose::new(Vector3::new(0.05, 0.0, 0.03), Quaternion::identity()),
+            head_pan_joint: "head_pan".to_string(),
+            head_tilt_joint: "head_tilt".to_string(),
+            base_yaw_joint: Some("base_yaw".to_string()),
+            history_size: 100,
+            max_extrapolation_ms: 100,
+        }
+    }
+}
+
+impl TransformConfig {
+    /// 头部关节名取自关节集合中的关节作用，关节集合没有头部关节时保留默认值
+    pub fn from_joint_set(joints: &JointSetConfig) -> Self {
+        let mut config = Self::default();
+        if let Ok(head) = joints.head_joints() {
+            config.head_pan_joint = head.pan;
+            config.head_tilt_joint = head.tilt;
+            config.base_yaw_joint = head.base_yaw;
+        }
+        config
+    }
+}
+
+impl ConfigValidation for TransformConfig {
+    fn validate(&self) -> Result<()> {
+        if self.head_pan_joint.is_empty() || self.head_tilt_joint.is_empty() {
+            return Err(anyhow::anyhow!("头部关节名不能为空"));
+        }
+        
+        if matches!(&self.base_yaw_joint, Some(name) if name.is_empty()) {
+            return Err(anyhow::anyhow!("底座关节名不能为空"));
+        }
+        
+        if self.history_size == 0 {
+            return Err(anyhow::anyhow!("历史变换数量必须大于0"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 刚体变换（平移 + 旋转）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Transform {
+    pub translation: Vector3,
+    pub rotation: Quaternion,
+}
+
+impl Transform {
+    pub fn new(translation: Vector3, rotation: Quaternion) -> Self {
+        Self {
+            translation,
+            rotation: rotation.normalize(),
+        }
+    }
+    
+    pub fn identity() -> Self {
+        Self::new(Vector3::zero(), Quaternion::identity())
+    }
+    
+    pub fn from_pose(pose: &Pose) -> Self {
+        Self::new(pose.position, pose.orientation)
+    }
+    
+    pub fn to_pose(&self) -> Pose {
+        Pose::new(self.translation, self.rotation)
+    }
+    
+    /// 组合变换：先应用other，再应用self
+    pub fn compose(&self, other: &Transform) -> Transform {
+        Transform::new(
+            self.translation + self.rotation.rotate_vector(&other.translation),
+            self.rotation * other.rotation,
+        )
+    }
+    
+    /// 逆变换
+    pub fn inverse(&self) -> Transform {
+        let inv_rotation = self.rotation.conjugate();
+        let inv_translation = inv_rotation.rotate_vector(&(Vector3::zero() - self.translation));
+        Transform::new(inv_translation, inv_rotation)
+    }
+    
+    /// 变换点
+    pub fn transform_point(&self, point: &Vector3) -> Vector3 {
+        self.translation + self.rotation.rotate_vector(point)
+    }
+    
+    /// 变换方向向量（不受平移影响）
+    pub fn transform_vector(&self, vector: &Vector3) -> Vector3 {
+        self.rotation.rotate_vector(vector)
+    }
+    
+    /// 在两个变换之间插值
+    pub fn interpolate(&self, other: &Transform, t: f64) -> Transform {
+        Transform::new(
+            Vector3::new(
+                lerp(self.translation.x, other.translation.x, t),
+                lerp(self.translation.y, other.translation.y, t),
+                lerp(self.translation.z, other.translation.z, t),
+            ),
+            self.rotation.slerp(&other.rotation, t),
+        )
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// 带时间戳的变换
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StampedTransform {
+    pub transform: Transform,
+    pub timestamp: u64,
+}
+
+/// 坐标变换错误
+#[derive(Debug, thiserror::Error)]
+pub enum TransformError {
+    #[error("坐标系不存在: {0}")]
+    FrameNotFound(String),
+    
+    #[error("坐标系 '{0}' 与 '{1}' 不连通")]
+    Disconnected(String, String),
+    
+    #[error("坐标系 '{frame}' 在时间 {timestamp} 没有可用变换")]
+    Extrapolation { frame: String, timestamp: u64 },
+    
+    #[error("设置父坐标系会形成环: {0}")]
+    Cycle(String),
+}
+
+/// 坐标系节点
+#[derive(Debug, Clone)]
+struct FrameNode {
+    parent: String,
+    is_static: bool,
+    history: VecDeque<StampedTransform>,
+}
+
+impl FrameNode {
+    /// 查询指定时间的变换，None表示最新值
+    fn transform_at(
+        &self,
+        name: &str,
+        timestamp: Option<u64>,
+        max_extrapolation_ms: u64,
+    ) -> std::result::Result<Transform, TransformError> {
+        let latest = self.history.back().ok_or_else(|| TransformError::Extrapolation {
+            frame: name.to_string(),
+            timestamp: timestamp.unwrap_or(0),
+        })?;
+        
+        let timestamp = match timestamp {
+            Some(ts) if !self.is_static => ts,
+            _ => return Ok(latest.transform),
+        };
+        
+        let oldest = self.history.front().unwrap_or(latest);
+        
+        if timestamp >= latest.timestamp {
+            if timestamp - latest.timestamp > max_extrapolation_ms {
+                return Err(TransformError::Extrapolation { frame: name.to_string(), timestamp });
+            }
+            return Ok(latest.transform);
+        }
+        
+        if timestamp <= oldest.timestamp {
+            if oldest.timestamp - timestamp > max_extrapolation_ms {
+                return Err(TransformError::Extrapolation { frame: name.to_string(), timestamp });
+            }
+            return Ok(oldest.transform);
+        }
+        
+        // 找到包围目标时间的两个样本并插值
+        let index = self.history.partition_point(|s| s.timestamp <= timestamp);
+        let before = &self.history[index - 1];
+        let after = &self.history[index];
+        let span = (after.timestamp - before.timestamp) as f64;
+        let t = if span > 0.0 {
+            (timestamp - before.timestamp) as f64 / span
+        } else {
+            0.0
+        };
+        
+        Ok(before.transform.interpolate(&after.transform, t))
+    }
+}
+
+/// 坐标变换树
+pub struct TransformTree {
+    config: TransformConfig,
+    frames: HashMap<String, FrameNode>,
+}
+
+impl TransformTree {
+    /// 创建新的坐标变换树，包含底座、机身、头部和相机坐标系
+    pub fn new(config: TransformConfig) -> Result<Self> {
+        config.validate()?;
+        
+        let mut tree = Self {
+            config,
+            frames: HashMap::new(),
+        };
+        
+        let now = current_timestamp();
+        tree.set_transform(BASE_FRAME, BODY_FRAME, Transform::identity(), now)?;
+        tree.set_transform(BODY_FRAME, HEAD_FRAME, Transform::new(tree.config.head_offset, Quaternion::identity()), now)?;
+        tree.set_static_transform(HEAD_FRAME, CAMERA_FRAME, Transform::from_pose(&tree.config.camera_mount))?;
+        
+        Ok(tree)
+    }
+    
+    /// 设置动态变换（child坐标系在parent坐标系中的位姿）
+    pub fn set_transform(&mut self, parent: &str, child: &str, transform: Transform, timestamp: u64) -> Result<()> {
+        self.insert(parent, child, transform, timestamp, false)
+    }
+    
+    /// 设置静态变换（如标定得到的安装位姿）
+    pub fn set_static_transform(&mut self, parent: &str, child: &str, transform: Transform) -> Result<()> {
+        self.insert(parent, child, transform, current_timestamp(), true)
+    }
+    
+    fn insert(&mut self, parent: &str, child: &str, transform: Transform, timestamp: u64, is_static: bool) -> Result<()> {
+        if parent == child || self.is_ancestor(child, parent) {
+            return Err(TransformError::Cycle(format!("{} -> {}", parent, child)).into());
+        }
+        
+        let history_size = if is_static { 1 } else { self.config.history_size };
+        let node = self.frames.entry(child.to_string()).or_insert_with(|| FrameNode {
+            parent: parent.to_string(),
+            is_static,
+            history: VecDeque::with_capacity(history_size),
+        });
+        
+        if node.parent != parent {
+            debug!("坐标系 {} 的父坐标系从 {} 变为 {}", child, node.parent, parent);
+            node.parent = parent.to_string();
+            node.history.clear();
+        }
+        node.is_static = is_static;
+        
+        // 乱序到达的旧数据直接丢弃
+        if let Some(latest) = node.history.back() {
+            if timestamp < latest.timestamp {
+                warn!("坐标系 {} 收到过期变换，已丢弃", child);
+                return Ok(());
+            }
+        }
+        
+        while node.history.len() >= history_size {
+            node.history.pop_front();
+        }
+        node.history.push_back(StampedTransform { transform, timestamp });
+        
+        Ok(())
+    }
+    
+    /// 检查ancestor是否是frame的祖先
+    fn is_ancestor(&self, ancestor: &str, frame: &str) -> bool {
+        let mut current = frame;
+        while let Some(node) = self.frames.get(current) {
+            if node.parent == ancestor {
+                return true;
+            }
+            current = &node.parent;
+        }
+        false
+    }
+    
+    /// 移除坐标系（例如不再可见的标记点）
+    pub fn remove_frame(&mut self, frame: &str) -> bool {
+        self.frames.remove(frame).is_some()
+    }
+    
+    /// 坐标系是否存在
+    pub fn has_frame(&self, frame: &str) -> bool {
+        frame == BASE_FRAME || self.frames.contains_key(frame) || self.frames.values().any(|n| n.parent == frame)
+    }
+    
+    /// 获取所有坐标系名称
+    pub fn frame_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.frames.keys().cloned().collect();
+        for node in self.frames.values() {
+            if !self.frames.contains_key(&node.parent) && !names.contains(&node.parent) {
+                names.push(node.parent.clone());
+            }
+        }
+        names.sort();
+        names
+    }
+    
+    /// 获取坐标系的父坐标系
+    pub fn parent_of(&self, frame: &str) -> Option<&str> {
+        self.frames.get(frame).map(|n| n.parent.as_str())
+    }
+    
+    /// 计算frame到其根坐标系的变换链
+    fn chain_to_root(
+        &self,
+        frame: &str,
+        timestamp: Option<u64>,
+    ) -> std::result::Result<Vec<(String, Transform)>, TransformError> {
+        let mut chain = Vec::new();
+        let mut current = frame.to_string();
+        
+        while let Some(node) = self.frames.get(&current) {
+            let transform = node.transform_at(&current, timestamp, self.config.max_extrapolation_ms)?;
+            chain.push((current.clone(), transform));
+            current = node.parent.clone();
+        }
+        chain.push((current, Transform::identity()));
+        
+        Ok(chain)
+    }
+    
+    /// 查询变换：返回把source坐标系中的点变换到target坐标系的变换
+    ///
+    /// `timestamp`为None时使用最新值，否则在历史数据中插值。
+    pub fn lookup_transform(
+        &self,
+        target: &str,
+        source: &str,
+        timestamp: Option<u64>,
+    ) -> std::result::Result<Transform, TransformError> {
+        for frame in [target, source] {
+            if !self.has_frame(frame) {
+                return Err(TransformError::FrameNotFound(frame.to_string()));
+            }
+        }
+        
+        if target == source {
+            return Ok(Transform::identity());
+        }
+        
+        let source_chain = self.chain_to_root(source, timestamp)?;
+        let target_chain = self.chain_to_root(target, timestamp)?;
+        
+        // 找到最近公共祖先
+        let common = source_chain
+            .iter()
+            .map(|(name, _)| name)
+            .find(|name| target_chain.iter().any(|(t, _)| t == *name))
+            .cloned()
+            .ok_or_else(|| TransformError::Disconnected(target.to_string(), source.to_string()))?;
+        
+        // source -> common
+        let mut source_to_common = Transform::identity();
+        for (name, transform) in &source_chain {
+            if *name == common {
+                break;
+            }
+            source_to_common = transform.compose(&source_to_common);
+        }
+        
+        // target -> common
+        let mut target_to_common = Transform::identity();
+        for (name, transform) in &target_chain {
+            if *name == common {
+                break;
+            }
+            target_to_common = transform.compose(&target_to_common);
+        }
+        
+        Ok(target_to_common.inverse().compose(&source_to_common))
+    }
+    
+    /// 把source坐标系中的点变换到target坐标系
+    pub fn transform_point(
+        &self,
+        target: &str,
+        source: &str,
+        point: &Vector3,
+        timestamp: Option<u64>,
+    ) -> std::result::Result<Vector3, TransformError> {
+        Ok(self.lookup_transform(target, source, timestamp)?.transform_point(point))
+    }
+    
+    /// 根据关节状态更新头部坐标系（运动学）
+    pub fn update_from_joint_states(&mut self, joints: &HashMap<String, JointState>, timestamp: u64) -> Result<()> {
+        let pan = joints.get(&self.config.head_pan_joint).map(|j| j.position).unwrap_or(0.0);
+        let tilt = joints.get(&self.config.head_tilt_joint).map(|j| j.position).unwrap_or(0.0);
+        let base_yaw = self.config.base_yaw_joint.as_ref()
+            .and_then(|name| joints.get(name))
+            .map(|j| j.position)
+            .unwrap_or(0.0);
+        
+        let body_rotation = Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), base_yaw);
+        self.set_transform(BASE_FRAME, BODY_FRAME, Transform::new(Vector3::zero(), body_rotation), timestamp)?;
+        
+        let rotation = Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), pan)
+            * Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), tilt);
+        let head_offset = self.config.head_offset;
+        
+        self.set_transform(BODY_FRAME, HEAD_FRAME, Transform::new(head_offset, rotation), timestamp)
+    }
+    
+    /// 应用相机外参标定结果
+    pub fn set_camera_calibration(&mut self, camera_in_head: Transform) -> Result<()> {
+        self.set_static_transform(HEAD_FRAME, CAMERA_FRAME, camera_in_head)
+    }
+    
+    /// 获取配置
+    pub fn config(&self) -> &TransformConfig {
+        &self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+    
+    fn assert_vec_eq(a: &Vector3, b: &Vector3) {
+        assert!((*a - *b).magnitude() < 1e-9, "{:?} != {:?}", a, b);
+    }
+    
+    #[test]
+    fn test_transform_inverse() {
+        let t = Transform::new(
+            Vector3::new(1.0, 2.0, 3.0),
+            Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), FRAC_PI_2),
+        );
+        let p = Vector3::new(0.5, -0.2, 0.1);
+        assert_vec_eq(&t.inverse().transform_point(&t.transform_point(&p)), &p);
+    }
+    
+    #[test]
+    fn test_lookup_camera_in_base() {
+        let config = TransformConfig {
+            head_offset: Vector3::new(0.0, 0.0, 0.2),
+            camera_mount: Pose::new(Vector3::new(0.1, 0.0, 0.0), Quaternion::identity()),
+            ..TransformConfig::default()
+        };
+        let mut tree = TransformTree::new(config).unwrap();
+        
+        let mut joints = HashMap::new();
+        let mut pan = JointState::new("head_pan".to_string());
+        pan.position = FRAC_PI_2;
+        joints.insert("head_pan".to_string(), pan);
+        tree.update_from_joint_states(&joints, current_timestamp()).unwrap();
+        
+        // 头部左转90度后，相机前方1米的点位于底座的+y方向
+        let point = tree.transform_point(BASE_FRAME, CAMERA_FRAME, &Vector3::new(1.0, 0.0, 0.0), None).unwrap();
+        assert_vec_eq(&point, &Vector3::new(0.0, 1.1, 0.2));
+        
+        let back = tree.transform_point(CAMERA_FRAME, BASE_FRAME, &point, None).unwrap();
+        assert_vec_eq(&back, &Vector3::new(1.0, 0.0, 0.0));
+        
+        // 底座再右转90度，相机重新朝向底座+x方向
+        let mut base = JointState::new("base_yaw".to_string());
+        base.position = -FRAC_PI_2;
+        joints.insert("base_yaw".to_string(), base);
+        tree.update_from_joint_states(&joints, current_timestamp()).unwrap();
+        
+        let point = tree.transform_point(BASE_FRAME, CAMERA_FRAME, &Vector3::new(1.0, 0.0, 0.0), None).unwrap();
+        assert_vec_eq(&point, &Vector3::new(1.1, 0.0, 0.2));
+    }
+    
+    #[test]
+    fn test_interpolation() {
+        let mut tree = TransformTree::new(TransformConfig::default()).unwrap();
+        tree.set_transform(BASE_FRAME, "marker", Transform::new(Vector3::new(0.0, 0.0, 0.0), Quaternion::identity()), 1000).unwrap();
+        tree.set_transform(BASE_FRAME, "marker", Transform::new(Vector3::new(1.0, 0.0, 0.0), Quaternion::identity()), 2000).unwrap();
+        
+        let t = tree.lookup_transform(BASE_FRAME, "marker", Some(1500)).unwrap();
+        assert_vec_eq(&t.translation, &Vector3::new(0.5, 0.0, 0.0));
+        
+        assert!(matches!(
+            tree.lookup_transform(BASE_FRAME, "marker", Some(5000)),
+            Err(TransformError::Extrapolation { .. })
+        ));
+    }
+    
+    #[test]
+    fn test_unknown_frame_and_cycle() {
+        let mut tree = TransformTree::new(TransformConfig::default()).unwrap();
+        assert!(matches!(
+            tree.lookup_transform(BASE_FRAME, "nowhere", None),
+            Err(TransformError::FrameNotFound(_))
+        ));
+        assert!(tree.set_static_transform(CAMERA_FRAME, BASE_FRAME, Transform::identity()).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! 三缓冲
+//! 
+//! 单写单读的无锁最新值交换：写入方和读取方各自独占一个槽位，第三个槽位在两者之间交换。
+//! 写入从不等待读取，读取总是拿到最近一次完整写入的值，两边都不会被对方的耗时阻塞。
+//! 用于硬件I/O线程与控制循环之间交换目标位置和总线状态。
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+/// 中间槽位的索引占低两位，NEW_DATA表示中间槽位有读取方尚未取走的新值
+const INDEX_MASK: u8 = 0b11;
+const NEW_DATA: u8 = 0b100;
+
+struct Shared<T> {
+    slots: [UnsafeCell<T>; 3],
+    middle: AtomicU8,
+}
+
+// SAFETY: 三个槽位在任一时刻分别归写入方、读取方和中间位置所有，
+// 归属只通过对middle的原子交换转移，不会有两个线程同时访问同一个槽位。
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    /// SAFETY: 调用方必须独占index对应的槽位
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn slot(&self, index: u8) -> &mut T {
+        &mut *self.slots[index as usize].get()
+    }
+}
+
+/// 写入端
+pub struct TripleWriter<T> {
+    shared: Arc<Shared<T>>,
+    back: u8,
+}
+
+/// 读取端
+pub struct TripleReader<T> {
+    shared: Arc<Shared<T>>,
+    front: u8,
+}
+
+/// 创建三缓冲，三个槽位都初始化为initial
+pub fn triple_buffer<T: Clone>(initial: T) -> (TripleWriter<T>, TripleReader<T>) {
+    let shared = Arc::new(Shared {
+        slots: [UnsafeCell::new(initial.clone()), UnsafeCell::new(initial.clone()), UnsafeCell::new(initial)],
+        middle: AtomicU8::new(1),
+    });
+    
+    let writer = TripleWriter { shared: Arc::clone(&shared), back: 0 };
+    let reader = TripleReader { shared, front: 2 };
+    (writer, reader)
+}
+
+impl<T> TripleWriter<T> {
+    /// 发布新值，覆盖读取方尚未取走的旧值
+    pub fn write(&mut self, value: T) {
+        self.update(|slot| *slot = value);
+    }
+    
+    /// 在写入方的槽位上原地修改后发布，可以复用槽位中已有的分配
+    pub fn update(&mut self, f: impl FnOnce(&mut T)) {
+        f(self.back_mut());
+        self.publish();
+    }
+    
+    /// 写入方的槽位，修改后调用`publish`发布；
+    /// 槽位中是读取方已经换出的某个旧值，需要完整覆盖
+    pub fn back_mut(&mut self) -> &mut T {
+        // SAFETY: back槽位归写入方独占
+        unsafe { self.shared.slot(self.back) }
+    }
+    
+    /// 发布写入方槽位中的值
+    pub fn publish(&mut self) {
+        let previous = self.shared.middle.swap(self.back | NEW_DATA, Ordering::AcqRel);
+        self.back = previous & INDEX_MASK;
+    }
+}
+
+impl<T> TripleReader<T> {
+    /// 中间槽位有新值时换到读取方
+    fn refresh(&mut self) -> bool {
+        if self.shared.middle.load(Ordering::Acquire) & NEW_DATA == 0 {
+            return false;
+        }
+        let previous = self.shared.middle.swap(self.front, Ordering::AcqRel);
+        self.front = previous & INDEX_MASK;
+        true
+    }
+    
+    /// 最近一次发布的值，从未发布过时为初始值
+    pub fn latest(&mut self) -> &T {
+        self.refresh();
+        // SAFETY: front槽位归读取方独占
+        unsafe { self.shared.slot(self.front) }
+    }
+    
+    /// 自上次读取以来有新发布的值时返回该值
+    pub fn take_update(&mut self) -> Option<&T> {
+        if self.refresh() {
+            // SAFETY: front槽位归读取方独占
+            Some(unsafe { self.shared.slot(self.front) })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_latest_value_wins() {
+        let (mut writer, mut reader) = triple_buffer(0);
+        assert_eq!(*reader.latest(), 0);
+        assert!(reader.take_update().is_none());
+        
+        writer.write(1);
+        writer.write(2);
+        assert_eq!(reader.take_update(), Some(&2));
+        assert!(reader.take_update().is_none());
+        assert_eq!(*reader.latest(), 2);
+        
+        // 写入方拿到的是读取方换出的、值为1的槽位
+        writer.update(|value| *value += 10);
+        assert_eq!(*reader.latest(), 11);
+    }
+    
+    #[test]
+    fn test_concurrent_snapshots_are_consistent() {
+        let (mut writer, mut reader) = triple_buffer((0u64, 0u64));
+        
+        let producer = std::thread::spawn(move || {
+            for i in 1..=100_000u64 {
+                writer.write((i, i * 2));
+            }
+        });
+        
+        // 读到的每一对都来自同一次写入，且不会倒退
+        let mut last = 0;
+        while last < 100_000 {
+            let (a, b) = *reader.latest();
+            assert_eq!(b, a * 2);
+            assert!(a >= last);
+            last = a;
+        }
+        producer.join().unwrap();
+    }
+}
@@ -0,0 +1,353 @@
+//! 末端速度（twist）控制
+//! 
+//! 客户端给出末端在底座坐标系下的线速度和角速度，由几何雅可比矩阵换算为关节速度后
+//! 作为速度命令下发。速度命令受控制器的命令超时看门狗保护，客户端停止发送后关节减速停下。
+//!
+//! 求解使用阻尼最小二乘 q̇ = (JᵀJ + λ²I)⁻¹Jᵀξ。可操作度 w = √det(JᵀJ) 低于阈值 w₀ 时
+//! 按 λ² = λ²max·(1 − w/w₀)² 逐渐加大阻尼，接近奇异位形（例如底座转盘与头部水平转动共线）
+//! 时关节速度保持有界而不是发散，代价是末端速度的跟踪误差。求出的关节速度超出限位时
+//! 整体按比例缩小，末端运动方向不变。
+//!
+//! 本仓库只有头部运动链（底座转盘、头部水平转动、头部俯仰），手臂没有建模；末端可选
+//! 头部旋转中心或相机。关节数少于6，求出的是最小二乘意义下最接近给定末端速度的关节速度。
+//! 关节按作用从关节集合中查找，头部和相机的位置取自坐标变换树，相机外参标定后立即生效。
+
+use crate::common::*;
+use crate::joints::HeadJoints;
+use crate::realtime::{CommandType, MotionCommand};
+use crate::transforms::{TransformTree, BODY_FRAME, CAMERA_FRAME, HEAD_FRAME};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 末端速度，底座坐标系下
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Twist {
+    /// 线速度（m/s）
+    #[serde(default = "Vector3::zero")]
+    pub linear: Vector3,
+    /// 角速度（rad/s）
+    #[serde(default = "Vector3::zero")]
+    pub angular: Vector3,
+}
+
+impl Twist {
+    pub fn new(linear: Vector3, angular: Vector3) -> Self {
+        Self { linear, angular }
+    }
+    
+    fn components(&self) -> [f64; 6] {
+        let (v, w) = (self.linear, self.angular);
+        [v.x, v.y, v.z, w.x, w.y, w.z]
+    }
+}
+
+/// 末端坐标系
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndEffector {
+    /// 头部旋转中心
+    Head,
+    /// 相机光心
+    #[default]
+    Camera,
+}
+
+/// 末端速度控制配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TwistConfig {
+    /// 底座转盘是否参与求解；不参与时只用它的位置计算头部关节轴的方向
+    pub use_base_yaw: bool,
+    pub end_effector: EndEffector,
+    /// 最大阻尼系数λmax
+    pub max_damping: f64,
+    /// 可操作度阈值w₀，低于该值时开始加阻尼
+    pub manipulability_threshold: f64,
+}
+
+impl Default for TwistConfig {
+    fn default() -> Self {
+        Self {
+            use_base_yaw: false,
+            end_effector: EndEffector::Camera,
+            max_damping: 0.05,
+            manipulability_threshold: 0.1,
+        }
+    }
+}
+
+impl ConfigValidation for TwistConfig {
+    fn validate(&self) -> Result<()> {
+        if !(self.max_damping > 0.0 && self.max_damping.is_finite()) {
+            return Err(anyhow::anyhow!("最大阻尼系数必须为正数"));
+        }
+        
+        if !(self.manipulability_threshold > 0.0 && self.manipulability_threshold.is_finite()) {
+            return Err(anyhow::anyhow!("可操作度阈值必须为正数"));
+        }
+        
+        Ok(())
+    }
+}
+
+/// 一次求解的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwistSolution {
+    /// 各关节速度（rad/s），按求解关节的顺序
+    pub joint_velocities: Vec<(String, f64)>,
+    /// 可操作度w
+    pub manipulability: f64,
+    /// 使用的阻尼系数λ，0表示远离奇异位形
+    pub damping: f64,
+    /// 为满足速度限位对全部关节速度的缩放比例，1表示没有缩放
+    pub scale: f64,
+}
+
+impl TwistSolution {
+    /// 转换为速度命令
+    pub fn to_commands(&self) -> Vec<MotionCommand> {
+        self.joint_velocities
+            .iter()
+            .map(|(joint_name, velocity)| MotionCommand {
+                joint_name: joint_name.clone(),
+                command_type: CommandType::Velocity,
+                target_position: None,
+                target_velocity: Some(*velocity),
+                target_torque: None,
+                duration: None,
+                timestamp: current_timestamp(),
+                correlation_id: None,
+            })
+            .collect()
+    }
+}
+
+/// 末端速度求解器
+#[derive(Debug, Clone)]
+pub struct TwistSolver {
+    config: TwistConfig,
+    joints: HeadJoints,
+}
+
+impl TwistSolver {
+    pub fn new(config: TwistConfig, joints: HeadJoints) -> Result<Self> {
+        config.validate()?;
+        if config.use_base_yaw && joints.base_yaw.is_none() {
+            return Err(anyhow::anyhow!("底座转盘参与求解时关节集合中必须有底座转盘"));
+        }
+        Ok(Self { config, joints })
+    }
+    
+    /// 参与求解的关节，顺序与雅可比矩阵的列一致
+    pub fn joints(&self) -> Vec<&str> {
+        let base = self.joints.base_yaw.as_deref().filter(|_| self.config.use_base_yaw);
+        base.into_iter()
+            .chain([self.joints.pan.as_str(), self.joints.tilt.as_str()])
+            .collect()
+    }
+    
+    /// 当前关节位置下的几何雅可比矩阵，每列为[线速度; 角速度]，缺少的关节按0处理。
+    /// 头部旋转中心和相机的位置取自坐标变换树中机身→头部和头部→相机的变换
+    pub fn jacobian(&self, joints: &HashMap<String, JointState>, tree: &TransformTree) -> Result<Vec<[f64; 6]>> {
+        let position = |name: &str| joints.get(name).map(|j| j.position).unwrap_or(0.0);
+        let z = Vector3::new(0.0, 0.0, 1.0);
+        
+        let base_yaw = self.joints.base_yaw.as_deref().map(position).unwrap_or(0.0);
+        let pan = position(&self.joints.pan);
+        let tilt = position(&self.joints.tilt);
+        let head_offset = tree.lookup_transform(BODY_FRAME, HEAD_FRAME, None)?.translation;
+        let camera_offset = tree.lookup_transform(HEAD_FRAME, CAMERA_FRAME, None)?.translation;
+        
+        // 与坐标变换树相同的运动学：底座绕z转，头部先绕z水平转动再绕y俯仰
+        let body = Quaternion::from_axis_angle(&z, base_yaw);
+        let panned = body * Quaternion::from_axis_angle(&z, pan);
+        let head = panned * Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), tilt);
+        
+        let head_origin = body.rotate_vector(&head_offset);
+        let end_effector = match self.config.end_effector {
+            EndEffector::Head => head_origin,
+            EndEffector::Camera => head_origin + head.rotate_vector(&camera_offset),
+        };
+        
+        let column = |axis: Vector3, origin: Vector3| {
+            let linear = axis.cross(&(end_effector - origin));
+            [linear.x, linear.y, linear.z, axis.x, axis.y, axis.z]
+        };
+        
+        let mut columns = Vec::with_capacity(3);
+        if self.config.use_base_yaw {
+            columns.push(column(z, Vector3::zero()));
+        }
+        columns.push(column(z, head_origin));
+        columns.push(column(panned.rotate_vector(&Vector3::new(0.0, 1.0, 0.0)), head_origin));
+        Ok(columns)
+    }
+    
+    /// 把末端速度换算为关节速度；max_velocities为各关节的速度上限（rad/s），没有给出的关节不限制
+    pub fn solve(
+        &self,
+        twist: &Twist,
+        joints: &HashMap<String, JointState>,
+        tree: &TransformTree,
+        max_velocities: &HashMap<String, f64>,
+    ) -> Result<TwistSolution> {
+        let xi = twist.components();
+        if xi.iter().any(|value| !value.is_finite()) {
+            return Err(anyhow::anyhow!("末端速度包含非有限值"));
+        }
+        
+        let jacobian = self.jacobian(joints, tree)?;
+        let dot = |a: &[f64; 6], b: &[f64; 6]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>();
+        
+        let jtj: Vec<Vec<f64>> = jacobian.iter()
+            .map(|a| jacobian.iter().map(|b| dot(a, b)).collect())
+            .collect();
+        let manipulability = determinant(jtj.clone()).max(0.0).sqrt();
+        
+        let threshold = self.config.manipulability_threshold;
+        let damping = if manipulability < threshold {
+            self.config.max_damping * (1.0 - manipulability / threshold)
+        } else {
+            0.0
+        };
+        
+        let mut a = jtj;
+        for (i, row) in a.iter_mut().enumerate() {
+            row[i] += damping * damping;
+        }
+        let b: Vec<f64> = jacobian.iter().map(|column| dot(column, &xi)).collect();
+        let mut velocities = solve_linear(a, b)
+            .ok_or_else(|| anyhow::anyhow!("末端速度求解失败：雅可比矩阵奇异"))?;
+        
+        let names = self.joints();
+        let mut scale: f64 = 1.0;
+        for (name, velocity) in names.iter().zip(&velocities) {
+            if let Some(limit) = max_velocities.get(*name) {
+                if velocity.abs() > *limit {
+                    scale = scale.min(limit / velocity.abs());
+                }
+            }
+        }
+        for velocity in &mut velocities {
+            *velocity *= scale;
+        }
+        
+        Ok(TwistSolution {
+            joint_velocities: names.into_iter().map(String::from).zip(velocities).collect(),
+            manipulability,
+            damping,
+            scale,
+        })
+    }
+}
+
+/// 高斯消元（列主元）求行列式
+fn determinant(mut m: Vec<Vec<f64>>) -> f64 {
+    let n = m.len();
+    let mut det = 1.0;
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| m[i][col].abs().total_cmp(&m[j][col].abs())).unwrap();
+        if m[pivot][col] == 0.0 {
+            return 0.0;
+        }
+        if pivot != col {
+            m.swap(pivot, col);
+            det = -det;
+        }
+        det *= m[col][col];
+        let pivot_row = m[col].clone();
+        for row in &mut m[col + 1..] {
+            let factor = row[col] / pivot_row[col];
+            for (value, pivot) in row.iter_mut().zip(&pivot_row).skip(col) {
+                *value -= factor * pivot;
+            }
+        }
+    }
+    det
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::joints::JointSetConfig;
+    use crate::transforms::{Transform, TransformConfig};
+    
+    fn solver(config: TwistConfig) -> TwistSolver {
+        TwistSolver::new(config, JointSetConfig::default().head_joints().unwrap()).unwrap()
+    }
+    
+    fn tree() -> TransformTree {
+        TransformTree::new(TransformConfig::default()).unwrap()
+    }
+    
+    fn joint_states(positions: &[(&str, f64)]) -> HashMap<String, JointState> {
+        positions.iter()
+            .map(|(name, position)| {
+                let mut state = JointState::new(name.to_string());
+                state.position = *position;
+                (name.to_string(), state)
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_yaw_twist_maps_to_head_pan() {
+        let solver = solver(TwistConfig::default());
+        let tree = tree();
+        let joints = joint_states(&[("head_pan", 0.3), ("head_tilt", -0.2)]);
+        
+        // 绕z轴转动的角速度只需要头部水平转动，且不受相机偏移影响
+        let twist = Twist::new(Vector3::zero(), Vector3::new(0.0, 0.0, 0.5));
+        let solution = solver.solve(&twist, &joints, &tree, &HashMap::new()).unwrap();
+        assert_eq!(solution.damping, 0.0);
+        assert_eq!(solution.joint_velocities[0].0, "head_pan");
+        assert!((solution.joint_velocities[0].1 - 0.5).abs() < 1e-3);
+        assert!(solution.joint_velocities[1].1.abs() < 1e-3);
+        
+        // 超出限位时整体缩小
+        let limits = HashMap::from([("head_pan".to_string(), 0.25)]);
+        let solution = solver.solve(&twist, &joints, &tree, &limits).unwrap();
+        assert!((solution.scale - 0.5).abs() < 1e-2);
+        assert!(solution.joint_velocities[0].1 <= 0.25 + 1e-9);
+    }
+    
+    #[test]
+    fn test_camera_calibration_reaches_jacobian() {
+        let solver = solver(TwistConfig::default());
+        let mut tree = tree();
+        let joints = joint_states(&[("head_pan", 0.0), ("head_tilt", 0.0)]);
+        
+        // 头部水平转动使相机以 z × 相机偏移 的线速度移动，名义安装位置在头部前方0.05米
+        let nominal = solver.jacobian(&joints, &tree).unwrap();
+        assert!((nominal[0][1] - 0.05).abs() < 1e-9);
+        
+        // 外参标定写入坐标变换树后，雅可比矩阵使用标定后的相机位置
+        let calibrated = Transform::new(Vector3::new(0.08, 0.01, 0.03), Quaternion::identity());
+        tree.set_camera_calibration(calibrated).unwrap();
+        let jacobian = solver.jacobian(&joints, &tree).unwrap();
+        assert!((jacobian[0][0] + 0.01).abs() < 1e-9);
+        assert!((jacobian[0][1] - 0.08).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_damping_at_collinear_axes() {
+        let solver = solver(TwistConfig { use_base_yaw: true, ..TwistConfig::default() });
+        let joints = joint_states(&[("base_yaw", 0.0), ("head_pan", 0.0), ("head_tilt", 0.0)]);
+        
+        // 底座转盘与头部水平转动共线，JᵀJ奇异，按最大阻尼求解并由两个关节平分
+        let twist = Twist::new(Vector3::zero(), Vector3::new(0.0, 0.0, 1.0));
+        let solution = solver.solve(&twist, &joints, &tree(), &HashMap::new()).unwrap();
+        assert!(solution.manipulability < 1e-6);
+        assert!((solution.damping - 0.05).abs() < 1e-9);
+        
+        let velocities: Vec<f64> = solution.joint_velocities.iter().map(|(_, v)| *v).collect();
+        assert!((velocities[0] - velocities[1]).abs() < 1e-9);
+        assert!((velocities[0] + velocities[1] - 1.0).abs() < 0.01);
+        assert!(velocities.iter().all(|v| v.is_finite() && v.abs() < 1.0));
+        
+        let commands = solution.to_commands();
+        assert_eq!(commands.len(), 3);
+        assert!(commands.iter().all(|c| matches!(c.command_type, CommandType::Velocity)));
+    }
+}
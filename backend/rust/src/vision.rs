@@ -1,13 +1,45 @@
 //! 视觉处理模块
 //! 
 //! 提供高性能的计算机视觉处理功能，包括图像捕获、处理、特征检测等。
+//! 
+//! 启用`opencv`特性时使用OpenCV后端（VideoCapture、Haar级联、ORB）；
+//! 否则使用纯Rust后端：V4L2采集、image缩放、ONNX人脸检测和FAST特征点，
+//! 在难以编译OpenCV的系统上保持同样的视觉接口。
+//! 
+//! 摄像头按`camera_backend`配置在运行时选择，没有摄像头时使用生成合成画面的模拟摄像头；
+//! 配置了`playback`时改为循环播放图片目录或MJPEG文件。
+
+#[cfg(feature = "opencv")]
+mod opencv_backend;
+#[cfg(feature = "opencv")]
+use opencv_backend as backend;
+
+#[cfg(not(feature = "opencv"))]
+mod fallback;
+#[cfg(not(feature = "opencv"))]
+mod v4l2;
+#[cfg(not(feature = "opencv"))]
+use fallback as backend;
+
+#[cfg(not(feature = "opencv"))]
+pub use fallback::resize_image;
+
+mod mock;
+mod playback;
 
+use crate::adaptive_resolution::{AdaptiveResolutionConfig, CpuSampler, ResolutionAdapter};
 use crate::common::*;
+use crate::events::{EventBus, RobotEvent};
+use crate::shutdown::{CancellationToken, TaskGroup};
 use anyhow::Result;
-use opencv::{prelude::*, core, imgproc, videoio, objdetect, features2d};
+use arc_swap::ArcSwap;
+use backend::{Camera, FaceDetector, FeatureDetector};
+use mock::MockCamera;
+pub use playback::{CameraPlaybackConfig, PlaybackCamera};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::sync::{RwLock, mpsc};
 use log::{info, warn, error, debug};
@@ -23,8 +55,22 @@ pub struct VisionConfig {
     pub enable_face_detection: bool,
     pub enable_object_detection: bool,
     pub enable_feature_detection: bool,
+    /// Haar级联文件（OpenCV后端）
     pub face_cascade_path: String,
+    /// ONNX人脸检测模型（纯Rust后端）
+    #[serde(default)]
+    pub face_model: FaceModelConfig,
+    /// 帧处理线程池的线程数，多个检测器在池中并行运行
     pub processing_threads: usize,
+    /// 摄像头后端，auto时在/dev/videoN不存在的情况下使用模拟画面
+    #[serde(default)]
+    pub camera_backend: BackendMode,
+    /// 回放图片目录或视频文件代替摄像头，设置后忽略camera_backend
+    #[serde(default)]
+    pub playback: Option<CameraPlaybackConfig>,
+    /// 按CPU占用和帧延迟自动降低/恢复分辨率
+    #[serde(default)]
+    pub adaptive_resolution: AdaptiveResolutionConfig,
 }
 
 impl Default for VisionConfig {
@@ -39,11 +85,22 @@ impl Default for VisionConfig {
             enable_object_detection: false,
             enable_feature_detection: false,
             face_cascade_path: "data/haarcascade_frontalface_alt.xml".to_string(),
+            face_model: FaceModelConfig::default(),
             processing_threads: 2,
+            camera_backend: BackendMode::default(),
+            playback: None,
+            adaptive_resolution: AdaptiveResolutionConfig::default(),
         }
     }
 }
 
+impl VisionConfig {
+    /// 采集帧率，回放时可以单独设置
+    pub fn capture_fps(&self) -> f64 {
+        self.playback.as_ref().and_then(|playback| playback.fps).unwrap_or(self.fps)
+    }
+}
+
 impl ConfigValidation for VisionConfig {
     fn validate(&self) -> Result<()> {
         if self.camera_index < 0 {
@@ -62,6 +119,58 @@ impl ConfigValidation for VisionConfig {
             return Err(anyhow::anyhow!("缓冲区大小不能为0"));
         }
         
+        if self.processing_threads == 0 {
+            return Err(anyhow::anyhow!("处理线程数不能为0"));
+        }
+        
+        self.face_model.validate()?;
+        self.adaptive_resolution.validate()?;
+        
+        if let Some(playback) = &self.playback {
+            playback.validate()?;
+        }
+        
+        Ok(())
+    }
+}
+
+/// ONNX人脸检测模型配置
+/// 
+/// 默认对应UltraFace（version-RFB-320）：输入为1x3x240x320的RGB张量，
+/// 像素归一化为(v - 127) / 128；输出依次为每个先验框的
+/// [背景, 人脸]得分和归一化的(x1, y1, x2, y2)坐标。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaceModelConfig {
+    pub model_path: String,
+    pub input_width: u32,
+    pub input_height: u32,
+    pub confidence_threshold: f32,
+    /// 非极大值抑制的IoU阈值
+    pub nms_threshold: f32,
+}
+
+impl Default for FaceModelConfig {
+    fn default() -> Self {
+        Self {
+            model_path: "models/version-RFB-320.onnx".to_string(),
+            input_width: 320,
+            input_height: 240,
+            confidence_threshold: 0.7,
+            nms_threshold: 0.3,
+        }
+    }
+}
+
+impl ConfigValidation for FaceModelConfig {
+    fn validate(&self) -> Result<()> {
+        if self.input_width == 0 || self.input_height == 0 {
+            return Err(anyhow::anyhow!("人脸检测模型输入尺寸必须为正数"));
+        }
+        
+        if !(0.0..=1.0).contains(&self.confidence_threshold) || !(0.0..=1.0).contains(&self.nms_threshold) {
+            return Err(anyhow::anyhow!("人脸检测阈值必须在0-1之间"));
+        }
+        
         Ok(())
     }
 }
@@ -71,11 +180,56 @@ impl ConfigValidation for VisionConfig {
 pub struct VisionStatus {
     pub is_running: bool,
     pub camera_connected: bool,
+    /// 摄像头是否为模拟画面或回放
+    #[serde(default)]
+    pub camera_mock: bool,
     pub current_fps: f64,
     pub frames_processed: u64,
     pub frames_dropped: u64,
     pub last_frame_timestamp: u64,
     pub processing_stats: PerformanceStats,
+    #[serde(default)]
+    pub detector_timing: DetectorTiming,
+    /// 当前实际的分辨率缩小倍数（内存压力和负载两者中较大的）
+    #[serde(default = "default_downscale")]
+    pub resolution_downscale: u32,
+}
+
+fn default_downscale() -> u32 {
+    1
+}
+
+/// 每帧各检测器的耗时（毫秒，指数平均）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DetectorTiming {
+    pub face_ms: f64,
+    pub feature_ms: f64,
+    /// 检测器并行执行的实际耗时
+    pub detection_ms: f64,
+    /// 顺序执行所需时间（各检测器耗时之和）与实际耗时之比
+    pub parallel_speedup: f64,
+    /// 从采集到处理完成的端到端延迟，包括在队列中等待的时间
+    pub frame_latency_ms: f64,
+}
+
+impl DetectorTiming {
+    /// 指数平均的权重
+    const SMOOTHING: f64 = 0.1;
+    
+    fn update(&mut self, face: Duration, feature: Duration, detection: Duration, latency_ms: f64) {
+        let first = self.detection_ms == 0.0;
+        let smooth = |average: &mut f64, value: f64| {
+            *average = if first { value } else { *average + (value - *average) * Self::SMOOTHING };
+        };
+        
+        smooth(&mut self.face_ms, face.as_secs_f64() * 1000.0);
+        smooth(&mut self.feature_ms, feature.as_secs_f64() * 1000.0);
+        smooth(&mut self.detection_ms, detection.as_secs_f64() * 1000.0);
+        smooth(&mut self.frame_latency_ms, latency_ms);
+        if self.detection_ms > 0.0 {
+            self.parallel_speedup = (self.face_ms + self.feature_ms) / self.detection_ms;
+        }
+    }
 }
 
 impl Default for VisionStatus {
@@ -83,15 +237,30 @@ impl Default for VisionStatus {
         Self {
             is_running: false,
             camera_connected: false,
+            camera_mock: false,
             current_fps: 0.0,
             frames_processed: 0,
             frames_dropped: 0,
             last_frame_timestamp: 0,
             processing_stats: PerformanceStats::new(),
+            detector_timing: DetectorTiming::default(),
+            resolution_downscale: 1,
         }
     }
 }
 
+/// 采集和处理线程逐帧更新的计数
+///
+/// 以前通过`try_write`更新状态，状态被查询方读锁占用时计数会丢失。
+#[derive(Debug, Default)]
+struct FrameCounters {
+    frames_processed: AtomicU64,
+    frames_dropped: AtomicU64,
+    last_frame_timestamp: AtomicU64,
+    processing_stats: ArcSwap<PerformanceStats>,
+    detector_timing: ArcSwap<DetectorTiming>,
+}
+
 /// 检测结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DetectionResult {
@@ -146,6 +315,7 @@ pub enum VisionError {
     #[error("配置错误: {0}")]
     Config(String),
     
+    #[cfg(feature = "opencv")]
     #[error("OpenCV错误: {0}")]
     OpenCV(#[from] opencv::Error),
 }
@@ -158,18 +328,120 @@ pub struct FrameData {
     pub timestamp: u64,
 }
 
+/// 一帧中各检测器的耗时
+struct FrameTiming {
+    face: Duration,
+    feature: Duration,
+    total: Duration,
+}
+
+/// 用rayon::join并行运行两个任务，返回各自的结果和耗时；在线程池中调用时使用该线程池
+fn join_timed<A: Send, B: Send>(
+    a: impl FnOnce() -> A + Send,
+    b: impl FnOnce() -> B + Send,
+) -> ((A, Duration), (B, Duration)) {
+    rayon::join(
+        || {
+            let start = Instant::now();
+            (a(), start.elapsed())
+        },
+        || {
+            let start = Instant::now();
+            (b(), start.elapsed())
+        },
+    )
+}
+
+/// 处理循环中按负载调整分辨率的状态
+struct LoadScaling {
+    adapter: ResolutionAdapter,
+    cpu: CpuSampler,
+    load_downscale: Arc<AtomicU32>,
+    bus: Option<EventBus>,
+}
+
+impl LoadScaling {
+    /// 记录一帧的端到端延迟，需要调整时更新采集线程使用的缩小倍数并发布事件
+    fn record(&mut self, latency_ms: f64) {
+        let cpu = &mut self.cpu;
+        let Some(change) = self.adapter.record(latency_ms, Instant::now(), || cpu.sample()) else {
+            return;
+        };
+        
+        self.load_downscale.store(change.to_downscale, Ordering::Relaxed);
+        if change.is_degraded() {
+            warn!("视觉负载过高（帧延迟 {:.0}ms，CPU {:?}%），分辨率缩小为1/{}", change.latency_ms, change.cpu_percent.map(|c| c.round()), change.to_downscale);
+        } else {
+            info!("视觉负载恢复，分辨率缩小为1/{}", change.to_downscale);
+        }
+        if let Some(bus) = &self.bus {
+            bus.publish("vision", RobotEvent::ResolutionChanged(change));
+        }
+    }
+}
+
+/// 摄像头来源：真实设备、模拟画面或回放
+enum CameraSource {
+    Device(Camera),
+    Mock(MockCamera),
+    Playback(PlaybackCamera),
+}
+
+impl CameraSource {
+    fn open(config: &VisionConfig) -> Result<Self> {
+        let device = format!("/dev/video{}", config.camera_index);
+        if let Some(playback) = &config.playback {
+            Ok(CameraSource::Playback(PlaybackCamera::open(config, playback)?))
+        } else if config.camera_backend.use_real(&device) {
+            Ok(CameraSource::Device(Camera::open(config)?))
+        } else {
+            Ok(CameraSource::Mock(MockCamera::open(config)))
+        }
+    }
+    
+    /// 是否不是真实摄像头（模拟画面或回放）
+    fn is_mock(&self) -> bool {
+        !matches!(self, CameraSource::Device(_))
+    }
+    
+    fn is_finished(&self) -> bool {
+        matches!(self, CameraSource::Playback(camera) if camera.is_finished())
+    }
+    
+    fn read(&mut self) -> Result<Option<ImageData>> {
+        match self {
+            CameraSource::Device(camera) => camera.read(),
+            CameraSource::Mock(camera) => Ok(camera.read()),
+            CameraSource::Playback(camera) => camera.read(),
+        }
+    }
+    
+    fn release(&mut self) {
+        match self {
+            CameraSource::Device(camera) => camera.release(),
+            CameraSource::Mock(camera) => camera.release(),
+            CameraSource::Playback(camera) => camera.release(),
+        }
+    }
+}
+
 /// 视觉处理器
 pub struct VisionProcessor {
     config: VisionConfig,
     status: Arc<RwLock<VisionStatus>>,
-    camera: Option<videoio::VideoCapture>,
-    face_cascade: Option<objdetect::CascadeClassifier>,
-    feature_detector: Option<features2d::ORB>,
+    counters: Arc<FrameCounters>,
+    camera: Option<CameraSource>,
+    face_detector: Option<Arc<Mutex<FaceDetector>>>,
+    feature_detector: Option<Arc<Mutex<FeatureDetector>>>,
+    /// 帧处理的工作窃取线程池，检测器和其中的预处理都在池中运行
+    thread_pool: Arc<rayon::ThreadPool>,
     frame_buffer: Arc<RwLock<VecDeque<FrameData>>>,
-    frame_sender: Option<mpsc::UnboundedSender<FrameData>>,
-    frame_receiver: Option<mpsc::UnboundedReceiver<FrameData>>,
-    processing_handle: Option<tokio::task::JoinHandle<()>>,
-    capture_handle: Option<tokio::task::JoinHandle<()>>,
+    /// 采集分辨率的缩小倍数，内存紧张时由资源监控调大
+    downscale: Arc<AtomicU32>,
+    /// 负载过高时由处理循环调大的缩小倍数，实际采用两者中较大的
+    load_downscale: Arc<AtomicU32>,
+    event_bus: Option<EventBus>,
+    tasks: TaskGroup,
     is_running: Arc<RwLock<bool>>,
 }
 
@@ -178,25 +450,30 @@ impl VisionProcessor {
     pub async fn new(config: VisionConfig) -> Result<Self> {
         config.validate()?;
         
-        info!("初始化视觉处理器...");
+        info!("初始化视觉处理器（{}后端）...", backend::NAME);
         
         let status = Arc::new(RwLock::new(VisionStatus::default()));
         let frame_buffer = Arc::new(RwLock::new(VecDeque::with_capacity(config.buffer_size)));
         let is_running = Arc::new(RwLock::new(false));
-        
-        let (frame_sender, frame_receiver) = mpsc::unbounded_channel();
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.processing_threads)
+            .thread_name(|i| format!("vision-{}", i))
+            .build()
+            .map_err(|e| VisionError::Config(format!("创建帧处理线程池失败: {}", e)))?;
         
         let mut processor = Self {
             config,
             status,
+            counters: Arc::new(FrameCounters::default()),
             camera: None,
-            face_cascade: None,
+            face_detector: None,
             feature_detector: None,
+            thread_pool: Arc::new(thread_pool),
             frame_buffer,
-            frame_sender: Some(frame_sender),
-            frame_receiver: Some(frame_receiver),
-            processing_handle: None,
-            capture_handle: None,
+            downscale: Arc::new(AtomicU32::new(1)),
+            load_downscale: Arc::new(AtomicU32::new(1)),
+            event_bus: None,
+            tasks: TaskGroup::new("视觉处理器"),
             is_running,
         };
         
@@ -210,9 +487,9 @@ impl VisionProcessor {
     async fn initialize_detectors(&mut self) -> Result<()> {
         // 初始化人脸检测器
         if self.config.enable_face_detection {
-            match objdetect::CascadeClassifier::new(&self.config.face_cascade_path) {
-                Ok(cascade) => {
-                    self.face_cascade = Some(cascade);
+            match FaceDetector::new(&self.config) {
+                Ok(detector) => {
+                    self.face_detector = Some(Arc::new(Mutex::new(detector)));
                     info!("人脸检测器初始化成功");
                 },
                 Err(e) => {
@@ -223,9 +500,9 @@ impl VisionProcessor {
         
         // 初始化特征检测器
         if self.config.enable_feature_detection {
-            match features2d::ORB::create(500, 1.2, 8, 31, 0, 2, features2d::ORB_ScoreType::HARRIS_SCORE, 31, 20) {
+            match FeatureDetector::new(&self.config) {
                 Ok(detector) => {
-                    self.feature_detector = Some(detector);
+                    self.feature_detector = Some(Arc::new(Mutex::new(detector)));
                     info!("特征检测器初始化成功");
                 },
                 Err(e) => {
@@ -241,38 +518,27 @@ impl VisionProcessor {
     async fn initialize_camera(&mut self) -> Result<()> {
         info!("初始化摄像头 {}", self.config.camera_index);
         
-        let mut camera = videoio::VideoCapture::new(self.config.camera_index, videoio::CAP_ANY)?;
-        
-        if !camera.is_opened()? {
-            return Err(VisionError::Camera("无法打开摄像头".to_string()).into());
+        let camera = CameraSource::open(&self.config)?;
+        if camera.is_mock() {
+            info!("摄像头 {} 使用模拟画面", self.config.camera_index);
         }
         
-        // 设置摄像头参数
-        camera.set(videoio::CAP_PROP_FRAME_WIDTH, self.config.frame_width as f64)?;
-        camera.set(videoio::CAP_PROP_FRAME_HEIGHT, self.config.frame_height as f64)?;
-        camera.set(videoio::CAP_PROP_FPS, self.config.fps)?;
-        
-        // 验证设置
-        let actual_width = camera.get(videoio::CAP_PROP_FRAME_WIDTH)? as i32;
-        let actual_height = camera.get(videoio::CAP_PROP_FRAME_HEIGHT)? as i32;
-        let actual_fps = camera.get(videoio::CAP_PROP_FPS)?;
-        
-        info!("摄像头参数: {}x{} @ {:.1} FPS", actual_width, actual_height, actual_fps);
-        
-        self.camera = Some(camera);
-        
         // 更新状态
         {
             let mut status = self.status.write().await;
             status.camera_connected = true;
+            status.camera_mock = camera.is_mock();
         }
         
+        self.camera = Some(camera);
+        
         Ok(())
     }
     
     /// 启动视觉处理
     pub async fn start(&mut self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
+        let is_running_lock = Arc::clone(&self.is_running);
+        let mut is_running = is_running_lock.write().await;
         if *is_running {
             return Ok(());
         }
@@ -282,11 +548,14 @@ impl VisionProcessor {
         // 初始化摄像头
         self.initialize_camera().await?;
         
+        // 每次启动使用新的帧通道，停止后可以重新启动
+        let (frame_sender, frame_receiver) = mpsc::unbounded_channel();
+        
         // 启动帧捕获任务
-        self.start_capture_task().await?;
+        self.start_capture_task(frame_sender).await?;
         
         // 启动处理任务
-        self.start_processing_task().await?;
+        self.start_processing_task(frame_receiver).await?;
         
         *is_running = true;
         
@@ -302,7 +571,8 @@ impl VisionProcessor {
     
     /// 停止视觉处理
     pub async fn stop(&mut self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
+        let is_running_lock = Arc::clone(&self.is_running);
+        let mut is_running = is_running_lock.write().await;
         if !*is_running {
             return Ok(());
         }
@@ -311,19 +581,12 @@ impl VisionProcessor {
         
         *is_running = false;
         
-        // 停止处理任务
-        if let Some(handle) = self.processing_handle.take() {
-            handle.abort();
-        }
-        
-        // 停止捕获任务
-        if let Some(handle) = self.capture_handle.take() {
-            handle.abort();
-        }
+        // 捕获循环在两帧之间退出并释放摄像头，处理循环排空剩余的帧
+        self.tasks.shutdown().await;
         
         // 关闭摄像头
         if let Some(mut camera) = self.camera.take() {
-            let _ = camera.release();
+            camera.release();
         }
         
         // 更新状态
@@ -331,6 +594,7 @@ impl VisionProcessor {
             let mut status = self.status.write().await;
             status.is_running = false;
             status.camera_connected = false;
+            status.camera_mock = false;
         }
         
         info!("视觉处理器停止完成");
@@ -338,47 +602,39 @@ impl VisionProcessor {
     }
     
     /// 启动帧捕获任务
-    async fn start_capture_task(&mut self) -> Result<()> {
+    async fn start_capture_task(&mut self, frame_sender: mpsc::UnboundedSender<FrameData>) -> Result<()> {
         let camera = self.camera.take().ok_or_else(|| {
             VisionError::Camera("摄像头未初始化".to_string())
         })?;
         
-        let frame_sender = self.frame_sender.take().ok_or_else(|| {
-            VisionError::Config("帧发送器未初始化".to_string())
-        })?;
-        
-        let is_running = Arc::clone(&self.is_running);
-        let status = Arc::clone(&self.status);
+        let shutdown = self.tasks.token();
+        let counters = Arc::clone(&self.counters);
+        let downscale = Arc::clone(&self.downscale);
+        let load_downscale = Arc::clone(&self.load_downscale);
         let config = self.config.clone();
         
-        let handle = tokio::task::spawn_blocking(move || {
-            Self::capture_loop(camera, frame_sender, is_running, status, config)
+        self.tasks.spawn_blocking("帧捕获", move || {
+            Self::capture_loop(camera, frame_sender, shutdown, counters, downscale, load_downscale, config)
         });
         
-        self.capture_handle = Some(handle);
         Ok(())
     }
     
     /// 帧捕获循环
     fn capture_loop(
-        mut camera: videoio::VideoCapture,
+        mut camera: CameraSource,
         frame_sender: mpsc::UnboundedSender<FrameData>,
-        is_running: Arc<RwLock<bool>>,
-        status: Arc<RwLock<VisionStatus>>,
+        shutdown: CancellationToken,
+        counters: Arc<FrameCounters>,
+        downscale: Arc<AtomicU32>,
+        load_downscale: Arc<AtomicU32>,
         config: VisionConfig,
     ) {
-        let mut frame = core::Mat::default();
-        let frame_interval = Duration::from_secs_f64(1.0 / config.fps);
+        let frame_interval = Duration::from_secs_f64(1.0 / config.capture_fps());
         let mut last_frame_time = Instant::now();
         
-        loop {
-            // 检查是否应该停止
-            if let Ok(running) = is_running.try_read() {
-                if !*running {
-                    break;
-                }
-            }
-            
+        // 阻塞线程无法被中止，每帧之前检查关闭信号
+        while !shutdown.is_cancelled() {
             // 控制帧率
             let elapsed = last_frame_time.elapsed();
             if elapsed < frame_interval {
@@ -386,40 +642,32 @@ impl VisionProcessor {
             }
             last_frame_time = Instant::now();
             
+            if camera.is_finished() {
+                info!("摄像头回放结束");
+                break;
+            }
+            
             // 捕获帧
-            match camera.read(&mut frame) {
-                Ok(true) => {
-                    if frame.empty() {
-                        continue;
-                    }
+            match camera.read() {
+                Ok(Some(image_data)) => {
+                    let factor = downscale.load(Ordering::Relaxed).max(load_downscale.load(Ordering::Relaxed));
+                    let frame_data = FrameData {
+                        image: image_data.subsample(factor),
+                        detection_result: None,
+                        timestamp: current_timestamp(),
+                    };
                     
-                    // 转换为ImageData
-                    match Self::mat_to_image_data(&frame) {
-                        Ok(image_data) => {
-                            let frame_data = FrameData {
-                                image: image_data,
-                                detection_result: None,
-                                timestamp: current_timestamp(),
-                            };
-                            
-                            // 发送帧数据
-                            if frame_sender.send(frame_data).is_err() {
-                                error!("发送帧数据失败，接收器可能已关闭");
-                                break;
-                            }
-                            
-                            // 更新统计
-                            if let Ok(mut status) = status.try_write() {
-                                status.frames_processed += 1;
-                                status.last_frame_timestamp = current_timestamp();
-                            }
-                        },
-                        Err(e) => {
-                            error!("转换帧数据失败: {}", e);
-                        }
+                    // 发送帧数据
+                    if frame_sender.send(frame_data).is_err() {
+                        error!("发送帧数据失败，接收器可能已关闭");
+                        break;
                     }
+                    
+                    // 更新统计
+                    counters.frames_processed.fetch_add(1, Ordering::Relaxed);
+                    counters.last_frame_timestamp.store(current_timestamp(), Ordering::Relaxed);
                 },
-                Ok(false) => {
+                Ok(None) => {
                     warn!("摄像头返回空帧");
                     std::thread::sleep(Duration::from_millis(10));
                 },
@@ -430,68 +678,98 @@ impl VisionProcessor {
             }
         }
         
+        camera.release();
         info!("帧捕获循环结束");
     }
     
     /// 启动处理任务
-    async fn start_processing_task(&mut self) -> Result<()> {
-        let frame_receiver = self.frame_receiver.take().ok_or_else(|| {
-            VisionError::Config("帧接收器未初始化".to_string())
-        })?;
-        
-        let is_running = Arc::clone(&self.is_running);
-        let status = Arc::clone(&self.status);
+    async fn start_processing_task(&mut self, frame_receiver: mpsc::UnboundedReceiver<FrameData>) -> Result<()> {
+        let shutdown = self.tasks.token();
+        let counters = Arc::clone(&self.counters);
         let frame_buffer = Arc::clone(&self.frame_buffer);
         let config = self.config.clone();
         
-        // 复制检测器（如果可用）
-        let face_cascade = self.face_cascade.clone();
+        // 共享检测器（如果可用）
+        let face_detector = self.face_detector.clone();
         let feature_detector = self.feature_detector.clone();
+        let thread_pool = Arc::clone(&self.thread_pool);
+        
+        // 每次启动从原始分辨率开始
+        self.load_downscale.store(1, Ordering::Relaxed);
+        let scaling = LoadScaling {
+            adapter: ResolutionAdapter::new(config.adaptive_resolution.clone())?,
+            cpu: CpuSampler::new(),
+            load_downscale: Arc::clone(&self.load_downscale),
+            bus: self.event_bus.clone(),
+        };
         
-        let handle = tokio::spawn(async move {
+        self.tasks.spawn("帧处理", async move {
             Self::processing_loop(
                 frame_receiver,
-                is_running,
-                status,
+                shutdown,
+                counters,
                 frame_buffer,
                 config,
-                face_cascade,
+                face_detector,
                 feature_detector,
+                thread_pool,
+                scaling,
             ).await
         });
         
-        self.processing_handle = Some(handle);
         Ok(())
     }
     
     /// 处理循环
+    #[allow(clippy::too_many_arguments)]
     async fn processing_loop(
         mut frame_receiver: mpsc::UnboundedReceiver<FrameData>,
-        is_running: Arc<RwLock<bool>>,
-        status: Arc<RwLock<VisionStatus>>,
+        shutdown: CancellationToken,
+        counters: Arc<FrameCounters>,
         frame_buffer: Arc<RwLock<VecDeque<FrameData>>>,
         config: VisionConfig,
-        face_cascade: Option<objdetect::CascadeClassifier>,
-        feature_detector: Option<features2d::ORB>,
+        face_detector: Option<Arc<Mutex<FaceDetector>>>,
+        feature_detector: Option<Arc<Mutex<FeatureDetector>>>,
+        thread_pool: Arc<rayon::ThreadPool>,
+        mut scaling: LoadScaling,
     ) {
-        while let Some(mut frame_data) = frame_receiver.recv().await {
-            // 检查是否应该停止
-            if let Ok(running) = is_running.try_read() {
-                if !*running {
-                    break;
-                }
-            }
+        let mut processing_stats = PerformanceStats::clone(&counters.processing_stats.load());
+        let mut detector_timing = DetectorTiming::clone(&counters.detector_timing.load());
+        let config = Arc::new(config);
+        
+        loop {
+            let frame_data = tokio::select! {
+                biased;
+                _ = shutdown.cancelled() => break,
+                frame = frame_receiver.recv() => match frame {
+                    Some(frame) => frame,
+                    None => break,
+                },
+            };
             
             let start_time = Instant::now();
             
-            // 处理帧
-            if let Ok(detection_result) = Self::process_frame(
-                &frame_data.image,
-                &face_cascade,
-                &feature_detector,
-                &config,
-            ).await {
-                frame_data.detection_result = Some(detection_result);
+            // 在线程池中处理帧，检测器并行运行，不占用异步运行时的线程
+            let (result_sender, result_receiver) = tokio::sync::oneshot::channel();
+            let (face, feature, frame_config) = (face_detector.clone(), feature_detector.clone(), Arc::clone(&config));
+            thread_pool.spawn(move || {
+                let result = Self::process_frame(&frame_data.image, face.as_deref(), feature.as_deref(), &frame_config);
+                let _ = result_sender.send((frame_data, result));
+            });
+            let Ok((mut frame_data, result)) = result_receiver.await else {
+                error!("帧处理线程异常退出");
+                break;
+            };
+            
+            match result {
+                Ok((detection_result, timing)) => {
+                    let latency_ms = current_timestamp().saturating_sub(frame_data.timestamp) as f64;
+                    detector_timing.update(timing.face, timing.feature, timing.total, latency_ms);
+                    counters.detector_timing.store(Arc::new(detector_timing.clone()));
+                    frame_data.detection_result = Some(detection_result);
+                    scaling.record(latency_ms);
+                },
+                Err(e) => debug!("处理帧失败: {}", e),
             }
             
             let processing_time = start_time.elapsed();
@@ -503,174 +781,92 @@ impl VisionProcessor {
                     buffer.pop_front();
                     
                     // 更新丢帧统计
-                    if let Ok(mut status) = status.try_write() {
-                        status.frames_dropped += 1;
-                    }
+                    counters.frames_dropped.fetch_add(1, Ordering::Relaxed);
                 }
                 buffer.push_back(frame_data);
             }
             
             // 更新性能统计
-            if let Ok(mut status) = status.try_write() {
-                status.processing_stats.update_frame_stats(processing_time);
-                status.current_fps = status.processing_stats.fps;
-            }
+            processing_stats.update_frame_stats(processing_time);
+            counters.processing_stats.store(Arc::new(processing_stats.clone()));
         }
         
+        // 排空通道：关闭时尚未处理的帧计为丢帧
+        frame_receiver.close();
+        let mut dropped = 0;
+        while frame_receiver.try_recv().is_ok() {
+            dropped += 1;
+        }
+        counters.frames_dropped.fetch_add(dropped, Ordering::Relaxed);
+        
         info!("处理循环结束");
     }
     
-    /// 处理单帧
-    async fn process_frame(
+    /// 处理单帧，各检测器在当前线程池中并行运行
+    fn process_frame(
         image_data: &ImageData,
-        face_cascade: &Option<objdetect::CascadeClassifier>,
-        feature_detector: &Option<features2d::ORB>,
+        face_detector: Option<&Mutex<FaceDetector>>,
+        feature_detector: Option<&Mutex<FeatureDetector>>,
         config: &VisionConfig,
-    ) -> Result<DetectionResult> {
-        let mut result = DetectionResult {
-            faces: Vec::new(),
-            objects: Vec::new(),
-            features: Vec::new(),
-            timestamp: current_timestamp(),
-        };
-        
-        // 转换为OpenCV Mat
-        let mat = Self::image_data_to_mat(image_data)?;
-        
-        // 人脸检测
-        if config.enable_face_detection {
-            if let Some(cascade) = face_cascade {
-                result.faces = Self::detect_faces(&mat, cascade)?;
+    ) -> Result<(DetectionResult, FrameTiming)> {
+        let detect_faces = || -> Result<Vec<FaceDetection>> {
+            match face_detector {
+                Some(detector) if config.enable_face_detection => {
+                    let mut detector = detector.lock().map_err(|_| VisionError::Detector("人脸检测器锁已损坏".to_string()))?;
+                    detector.detect(image_data)
+                },
+                _ => Ok(Vec::new()),
             }
-        }
-        
-        // 特征检测
-        if config.enable_feature_detection {
-            if let Some(detector) = feature_detector {
-                result.features = Self::detect_features(&mat, detector)?;
+        };
+        let detect_features = || -> Result<Vec<FeaturePoint>> {
+            match feature_detector {
+                Some(detector) if config.enable_feature_detection => {
+                    let mut detector = detector.lock().map_err(|_| VisionError::Detector("特征检测器锁已损坏".to_string()))?;
+                    detector.detect(image_data)
+                },
+                _ => Ok(Vec::new()),
             }
-        }
+        };
         
-        Ok(result)
-    }
-    
-    /// 人脸检测
-    fn detect_faces(
-        mat: &core::Mat,
-        cascade: &objdetect::CascadeClassifier,
-    ) -> Result<Vec<FaceDetection>> {
-        let mut gray = core::Mat::default();
-        imgproc::cvt_color(mat, &mut gray, imgproc::COLOR_BGR2GRAY, 0)?;
-        
-        let mut faces = core::Vector::<core::Rect>::new();
-        cascade.detect_multi_scale(
-            &gray,
-            &mut faces,
-            1.1,
-            3,
-            0,
-            core::Size::new(30, 30),
-            core::Size::new(0, 0),
-        )?;
-        
-        let mut result = Vec::new();
-        for face in faces.iter() {
-            result.push(FaceDetection {
-                x: face.x,
-                y: face.y,
-                width: face.width,
-                height: face.height,
-                confidence: 1.0, // Haar级联不提供置信度
-            });
-        }
+        let start = Instant::now();
+        let ((faces, face), (features, feature)) = join_timed(detect_faces, detect_features);
+        let timing = FrameTiming { face, feature, total: start.elapsed() };
         
-        Ok(result)
+        let result = DetectionResult {
+            faces: faces?,
+            objects: Vec::new(),
+            features: features?,
+            timestamp: current_timestamp(),
+        };
+        Ok((result, timing))
     }
     
-    /// 特征检测
-    fn detect_features(
-        mat: &core::Mat,
-        detector: &features2d::ORB,
-    ) -> Result<Vec<FeaturePoint>> {
-        let mut gray = core::Mat::default();
-        imgproc::cvt_color(mat, &mut gray, imgproc::COLOR_BGR2GRAY, 0)?;
-        
-        let mut keypoints = core::Vector::<core::KeyPoint>::new();
-        let mask = core::Mat::default();
-        
-        detector.detect(&gray, &mut keypoints, &mask)?;
-        
-        let mut result = Vec::new();
-        for kp in keypoints.iter() {
-            result.push(FeaturePoint {
-                x: kp.pt.x,
-                y: kp.pt.y,
-                response: kp.response,
-            });
-        }
-        
-        Ok(result)
+    /// 帧处理线程池，其他预处理步骤可以共用，避免和检测器争抢CPU
+    pub fn thread_pool(&self) -> Arc<rayon::ThreadPool> {
+        Arc::clone(&self.thread_pool)
     }
     
-    /// Mat转ImageData
-    fn mat_to_image_data(mat: &core::Mat) -> Result<ImageData> {
-        let rows = mat.rows();
-        let cols = mat.cols();
-        let channels = mat.channels();
-        
-        if rows <= 0 || cols <= 0 || channels <= 0 {
-            return Err(VisionError::ImageProcessing("无效的图像尺寸".to_string()).into());
+    /// 设置采集分辨率的缩小倍数（1为原始分辨率），从下一帧开始生效
+    pub fn set_downscale(&self, factor: u32) {
+        let factor = factor.max(1);
+        if self.downscale.swap(factor, Ordering::Relaxed) != factor {
+            info!("视觉采集分辨率缩小为1/{}", factor);
         }
-        
-        let mut data = vec![0u8; (rows * cols * channels) as usize];
-        
-        unsafe {
-            let mat_data = mat.ptr(0)? as *const u8;
-            std::ptr::copy_nonoverlapping(
-                mat_data,
-                data.as_mut_ptr(),
-                data.len(),
-            );
-        }
-        
-        let format = match channels {
-            1 => ImageFormat::Gray8,
-            3 => ImageFormat::BGR8,
-            4 => ImageFormat::BGRA8,
-            _ => return Err(VisionError::ImageProcessing("不支持的通道数".to_string()).into()),
-        };
-        
-        Ok(ImageData::from_raw(
-            cols as u32,
-            rows as u32,
-            channels as u32,
-            data,
-            format,
-        ))
-    }
-    
-    /// ImageData转Mat
-    fn image_data_to_mat(image_data: &ImageData) -> Result<core::Mat> {
-        let cv_type = match image_data.format {
-            ImageFormat::Gray8 => core::CV_8UC1,
-            ImageFormat::BGR8 => core::CV_8UC3,
-            ImageFormat::RGB8 => core::CV_8UC3,
-            ImageFormat::BGRA8 => core::CV_8UC4,
-            ImageFormat::RGBA8 => core::CV_8UC4,
-            _ => return Err(VisionError::ImageProcessing("不支持的图像格式".to_string()).into()),
-        };
-        
-        let mat = unsafe {
-            core::Mat::new_rows_cols_with_data(
-                image_data.height as i32,
-                image_data.width as i32,
-                cv_type,
-                image_data.data.as_ptr() as *mut std::ffi::c_void,
-                core::Mat_AUTO_STEP,
-            )?
-        };
-        
-        Ok(mat)
+    }
+    
+    /// 内存压力设置的分辨率缩小倍数
+    pub fn downscale(&self) -> u32 {
+        self.downscale.load(Ordering::Relaxed)
+    }
+    
+    /// 当前实际的分辨率缩小倍数，包括负载自适应的部分
+    pub fn effective_downscale(&self) -> u32 {
+        self.downscale().max(self.load_downscale.load(Ordering::Relaxed))
+    }
+    
+    /// 设置发布分辨率调整事件的事件总线，需要在启动前调用
+    pub fn set_event_bus(&mut self, bus: EventBus) {
+        self.event_bus = Some(bus);
     }
     
     /// 获取最新帧
@@ -687,8 +883,18 @@ impl VisionProcessor {
     
     /// 获取状态
     pub async fn get_status(&self) -> Result<VisionStatus> {
-        let status = self.status.read().await;
-        Ok(status.clone())
+        let mut status = self.status.read().await.clone();
+        
+        let counters = &self.counters;
+        status.frames_processed = counters.frames_processed.load(Ordering::Relaxed);
+        status.frames_dropped = counters.frames_dropped.load(Ordering::Relaxed);
+        status.last_frame_timestamp = counters.last_frame_timestamp.load(Ordering::Relaxed);
+        status.processing_stats = PerformanceStats::clone(&counters.processing_stats.load());
+        status.current_fps = status.processing_stats.fps;
+        status.detector_timing = DetectorTiming::clone(&counters.detector_timing.load());
+        status.resolution_downscale = self.effective_downscale();
+        
+        Ok(status)
     }
     
     /// 是否正在运行
@@ -726,6 +932,29 @@ mod tests {
         assert!(invalid_config.validate().is_err());
     }
     
+    #[test]
+    fn test_detectors_run_in_parallel_on_pool() {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let detector = |id: u32| move || {
+            std::thread::sleep(Duration::from_millis(100));
+            id
+        };
+        
+        let start = Instant::now();
+        let ((a, a_time), (b, b_time)) = pool.install(|| join_timed(detector(1), detector(2)));
+        let elapsed = start.elapsed();
+        
+        assert_eq!((a, b), (1, 2));
+        assert!(a_time + b_time >= Duration::from_millis(200));
+        // 顺序执行需要200ms
+        assert!(elapsed < Duration::from_millis(180), "并行执行耗时 {:?}", elapsed);
+        
+        let mut timing = DetectorTiming::default();
+        timing.update(a_time, b_time, elapsed, 120.0);
+        assert!(timing.parallel_speedup > 1.1);
+        assert_eq!(timing.frame_latency_ms, 120.0);
+    }
+    
     #[tokio::test]
     async fn test_vision_processor_creation() {
         let config = VisionConfig::default();
@@ -752,8 +981,59 @@ mod tests {
             ImageFormat::BGR8,
         );
         
-        // 测试转换（需要OpenCV环境）
-        // let mat_result = VisionProcessor::image_data_to_mat(&image_data);
-        // assert!(mat_result.is_ok());
+        // 两种后端都应能处理BGR8图像，纯色图像没有特征点
+        let mut detector = FeatureDetector::new(&VisionConfig::default()).unwrap();
+        let features = detector.detect(&image_data).unwrap();
+        assert!(features.is_empty());
+    }
+    
+    #[test]
+    fn test_mock_camera_selected_at_runtime() {
+        let config = VisionConfig {
+            frame_width: 64,
+            frame_height: 48,
+            camera_backend: BackendMode::Mock,
+            ..VisionConfig::default()
+        };
+        
+        let mut camera = CameraSource::open(&config).unwrap();
+        assert!(camera.is_mock());
+        
+        let first = camera.read().unwrap().unwrap();
+        assert_eq!((first.width, first.height, first.channels), (64, 48, 3));
+        assert_eq!(first.data.len(), 64 * 48 * 3);
+        
+        // 亮块随帧移动
+        let later = (0..30).filter_map(|_| camera.read().unwrap()).last().unwrap();
+        assert_ne!(first.data, later.data);
+        camera.release();
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_restart_with_mock_camera() {
+        let config = VisionConfig {
+            frame_width: 32,
+            frame_height: 24,
+            fps: 100.0,
+            enable_face_detection: false,
+            camera_backend: BackendMode::Mock,
+            ..VisionConfig::default()
+        };
+        let mut processor = VisionProcessor::new(config).await.unwrap();
+        
+        for _ in 0..2 {
+            processor.start().await.unwrap();
+            while processor.get_latest_frame().await.is_none() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            processor.stop().await.unwrap();
+            assert!(!processor.is_running().await);
+            processor.frame_buffer.write().await.clear();
+        }
+        
+        // 计数跨启停累计，不会因查询状态而丢失
+        let status = processor.get_status().await.unwrap();
+        assert!(status.frames_processed >= 2);
+        assert!(status.processing_stats.total_frames >= 2);
+    }
+}
@@ -0,0 +1,352 @@
+//! 纯Rust视觉后端
+//! 
+//! 不依赖OpenCV：V4L2采集（YUYV或MJPG），image负责格式转换和缩放，
+//! ONNX Runtime运行小型人脸检测模型，imageproc提取FAST特征点。
+//! ONNX Runtime动态库在运行时加载（可通过ORT_DYLIB_PATH指定路径）。
+
+use super::v4l2::{CaptureDevice, FOURCC_MJPG, FOURCC_YUYV};
+use super::{FaceDetection, FaceModelConfig, FeaturePoint, VisionConfig, VisionError};
+use crate::common::*;
+use crate::model_signature::{onnxruntime_available, ModelSignature};
+use crate::postprocess::nms;
+use anyhow::Result;
+use image::imageops::{self, FilterType};
+use image::{GrayImage, RgbImage};
+use log::{info, warn};
+use ort::session::Session;
+use ort::value::Tensor;
+use std::path::Path;
+
+pub const NAME: &str = "纯Rust";
+
+/// 等待一帧的超时（毫秒）
+const FRAME_TIMEOUT_MS: i32 = 1000;
+
+/// 最多保留的特征点数量（与OpenCV后端的ORB设置一致）
+const MAX_FEATURES: usize = 500;
+
+/// FAST角点阈值
+const FAST_THRESHOLD: u8 = 20;
+
+/// 摄像头
+pub struct Camera {
+    device: Option<CaptureDevice>,
+    width: u32,
+    height: u32,
+}
+
+impl Camera {
+    pub fn open(config: &VisionConfig) -> Result<Self> {
+        let (width, height) = (config.frame_width as u32, config.frame_height as u32);
+        let device = CaptureDevice::open(config.camera_index, width, height, config.fps, &[FOURCC_YUYV, FOURCC_MJPG])
+            .map_err(|e| VisionError::Camera(e.to_string()))?;
+        
+        let format = device.format();
+        info!("摄像头参数: {}x{} ({}), 输出缩放到 {}x{}",
+              format.width, format.height, fourcc_name(format.pixel_format), width, height);
+        
+        Ok(Self { device: Some(device), width, height })
+    }
+    
+    /// 读取一帧（RGB8，已缩放到配置的尺寸），超时时为None
+    pub fn read(&mut self) -> Result<Option<ImageData>> {
+        let device = self.device.as_mut().ok_or_else(|| VisionError::Camera("摄像头已关闭".to_string()))?;
+        let Some(raw) = device.read_frame(FRAME_TIMEOUT_MS)? else {
+            return Ok(None);
+        };
+        
+        let format = device.format();
+        let rgb = match format.pixel_format {
+            FOURCC_YUYV => yuyv_to_rgb(&raw, format.width, format.height, format.bytes_per_line)?,
+            _ => image::load_from_memory_with_format(&raw, image::ImageFormat::Jpeg)
+                .map_err(|e| VisionError::ImageProcessing(format!("MJPG解码失败: {}", e)))?
+                .to_rgb8(),
+        };
+        
+        let rgb = if rgb.dimensions() != (self.width, self.height) {
+            imageops::resize(&rgb, self.width, self.height, FilterType::Triangle)
+        } else {
+            rgb
+        };
+        
+        Ok(Some(ImageData::from_raw(self.width, self.height, 3, rgb.into_raw(), ImageFormat::RGB8)))
+    }
+    
+    pub fn release(&mut self) {
+        self.device = None;
+    }
+}
+
+/// ONNX人脸检测器
+pub struct FaceDetector {
+    session: Session,
+    config: FaceModelConfig,
+}
+
+impl FaceDetector {
+    pub fn new(config: &VisionConfig) -> Result<Self> {
+        let model_path = &config.face_model.model_path;
+        if !Path::new(model_path).exists() {
+            return Err(VisionError::Detector(format!("人脸检测模型不存在: {}", model_path)).into());
+        }
+        
+        if !onnxruntime_available() {
+            return Err(VisionError::Detector("无法加载ONNX Runtime动态库，可通过ORT_DYLIB_PATH指定路径".to_string()).into());
+        }
+        
+        let session = Session::builder()?
+            .with_intra_threads(config.processing_threads.max(1))?
+            .commit_from_file(model_path)?;
+        
+        // 检测时按位置取前两个输出（得分、框），输入按配置的尺寸构造
+        let signature = ModelSignature::from_session(&session);
+        let face_model = &config.face_model;
+        let mut mismatches = signature.mismatches(&[1, 3, face_model.input_height as i64, face_model.input_width as i64], &[]);
+        if signature.outputs.len() < 2 {
+            mismatches.push(format!("需要得分和框两个输出，模型的输出为 {:?}", signature.output_names()));
+        }
+        for mismatch in mismatches {
+            warn!("人脸检测模型 {} 与配置不符: {}", model_path, mismatch);
+        }
+        
+        Ok(Self { session, config: config.face_model.clone() })
+    }
+    
+    pub fn detect(&mut self, image: &ImageData) -> Result<Vec<FaceDetection>> {
+        let rgb = to_rgb_image(image)?;
+        let (width, height) = (self.config.input_width, self.config.input_height);
+        let resized = imageops::resize(&rgb, width, height, FilterType::Triangle);
+        
+        // HWC -> NCHW，并归一化到约[-1, 1]
+        let plane = (width * height) as usize;
+        let mut input = vec![0.0f32; plane * 3];
+        for (i, pixel) in resized.pixels().enumerate() {
+            for c in 0..3 {
+                input[c * plane + i] = (pixel[c] as f32 - 127.0) / 128.0;
+            }
+        }
+        
+        let tensor = Tensor::from_array(([1usize, 3, height as usize, width as usize], input.into_boxed_slice()))?;
+        let outputs = self.session.run(ort::inputs![tensor])?;
+        let (_, scores) = outputs[0].try_extract_tensor::<f32>()?;
+        let (_, boxes) = outputs[1].try_extract_tensor::<f32>()?;
+        
+        Ok(decode_faces(scores, boxes, image.width, image.height, &self.config))
+    }
+}
+
+/// FAST特征检测器
+pub struct FeatureDetector;
+
+impl FeatureDetector {
+    pub fn new(_config: &VisionConfig) -> Result<Self> {
+        Ok(Self)
+    }
+    
+    pub fn detect(&mut self, image: &ImageData) -> Result<Vec<FeaturePoint>> {
+        let gray = to_gray_image(image)?;
+        let mut corners = imageproc::corners::corners_fast9(&gray, FAST_THRESHOLD);
+        corners.sort_by(|a, b| b.score.total_cmp(&a.score));
+        corners.truncate(MAX_FEATURES);
+        
+        Ok(corners.into_iter().map(|corner| FeaturePoint {
+            x: corner.x as f32,
+            y: corner.y as f32,
+            response: corner.score,
+        }).collect())
+    }
+}
+
+/// 把图像缩放到指定尺寸，输出RGB8
+pub fn resize_image(image: &ImageData, width: u32, height: u32) -> Result<ImageData> {
+    let rgb = to_rgb_image(image)?;
+    let resized = imageops::resize(&rgb, width, height, FilterType::Triangle);
+    Ok(ImageData::from_raw(width, height, 3, resized.into_raw(), ImageFormat::RGB8))
+}
+
+fn to_rgb_image(image: &ImageData) -> Result<RgbImage> {
+    if !image.is_valid() {
+        return Err(VisionError::ImageProcessing("无效的图像尺寸".to_string()).into());
+    }
+    
+    let data = match image.format {
+        ImageFormat::RGB8 => image.data.clone(),
+        ImageFormat::BGR8 => image.data.chunks_exact(3).flat_map(|p| [p[2], p[1], p[0]]).collect(),
+        ImageFormat::RGBA8 => image.data.chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect(),
+        ImageFormat::BGRA8 => image.data.chunks_exact(4).flat_map(|p| [p[2], p[1], p[0]]).collect(),
+        ImageFormat::Gray8 => image.data.iter().flat_map(|&g| [g, g, g]).collect(),
+        _ => return Err(VisionError::ImageProcessing("不支持的图像格式".to_string()).into()),
+    };
+    
+    RgbImage::from_raw(image.width, image.height, data)
+        .ok_or_else(|| VisionError::ImageProcessing("图像数据长度与尺寸不符".to_string()).into())
+}
+
+fn to_gray_image(image: &ImageData) -> Result<GrayImage> {
+    if image.format == ImageFormat::Gray8 && image.is_valid() {
+        return GrayImage::from_raw(image.width, image.height, image.data.clone())
+            .ok_or_else(|| VisionError::ImageProcessing("图像数据长度与尺寸不符".to_string()).into());
+    }
+    
+    Ok(imageops::grayscale(&to_rgb_image(image)?))
+}
+
+/// YUYV（YUV 4:2:2）转RGB，系数为BT.601有限范围
+fn yuyv_to_rgb(data: &[u8], width: u32, height: u32, bytes_per_line: u32) -> Result<RgbImage> {
+    let stride = (bytes_per_line as usize).max(width as usize * 2);
+    if data.len() < stride * (height as usize - 1) + width as usize * 2 {
+        return Err(VisionError::ImageProcessing("YUYV帧数据不完整".to_string()).into());
+    }
+    
+    let convert = |y: u8, u: u8, v: u8| {
+        let c = y as i32 - 16;
+        let d = u as i32 - 128;
+        let e = v as i32 - 128;
+        let channel = |value: i32| ((value + 128) >> 8).clamp(0, 255) as u8;
+        [
+            channel(298 * c + 409 * e),
+            channel(298 * c - 100 * d - 208 * e),
+            channel(298 * c + 516 * d),
+        ]
+    };
+    
+    let mut rgb = Vec::with_capacity((width * height * 3) as usize);
+    for row in data.chunks(stride).take(height as usize) {
+        for pair in row[..width as usize * 2].chunks_exact(4) {
+            rgb.extend_from_slice(&convert(pair[0], pair[1], pair[3]));
+            rgb.extend_from_slice(&convert(pair[2], pair[1], pair[3]));
+        }
+    }
+    
+    RgbImage::from_raw(width, height, rgb)
+        .ok_or_else(|| VisionError::ImageProcessing("YUYV帧尺寸无效".to_string()).into())
+}
+
+fn fourcc_name(code: u32) -> String {
+    code.to_le_bytes().iter().map(|&b| b as char).collect()
+}
+
+/// 解码模型输出：得分为每个先验框的[背景, 人脸]，坐标为归一化的(x1, y1, x2, y2)
+fn decode_faces(scores: &[f32], boxes: &[f32], width: u32, height: u32, config: &FaceModelConfig) -> Vec<FaceDetection> {
+    let (candidate_scores, candidate_boxes): (Vec<f32>, Vec<[f32; 4]>) = scores.chunks_exact(2)
+        .zip(boxes.chunks_exact(4))
+        .filter(|(score, _)| score[1] >= config.confidence_threshold)
+        .map(|(score, b)| (score[1], [b[0].clamp(0.0, 1.0), b[1].clamp(0.0, 1.0), b[2].clamp(0.0, 1.0), b[3].clamp(0.0, 1.0)]))
+        .unzip();
+    
+    // 非极大值抑制，结果按得分从高到低
+    let kept = nms(&candidate_boxes, &candidate_scores, config.nms_threshold);
+    
+    kept.into_iter().map(|i| (candidate_scores[i], candidate_boxes[i])).map(|(score, [x1, y1, x2, y2])| {
+        let (w, h) = (width as f32, height as f32);
+        FaceDetection {
+            x: (x1 * w).round() as i32,
+            y: (y1 * h).round() as i32,
+            width: ((x2 - x1) * w).round() as i32,
+            height: ((y2 - y1) * h).round() as i32,
+            confidence: score as f64,
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_yuyv_conversion_and_resize() {
+        // 两个像素一组：Y=235为白色，Y=16为黑色，U=V=128为无色度
+        let row = [235u8, 128, 16, 128];
+        let data: Vec<u8> = row.iter().cycle().take(4 * 2 * 2).copied().collect();
+        let rgb = yuyv_to_rgb(&data, 4, 2, 8).unwrap();
+        assert_eq!(rgb.get_pixel(0, 0).0, [255, 255, 255]);
+        assert_eq!(rgb.get_pixel(1, 1).0, [0, 0, 0]);
+        
+        let image = ImageData::from_raw(4, 2, 3, rgb.into_raw(), ImageFormat::RGB8);
+        let resized = resize_image(&image, 2, 1).unwrap();
+        assert_eq!((resized.width, resized.height), (2, 1));
+        assert!(resized.is_valid());
+        
+        assert!(yuyv_to_rgb(&data[..10], 4, 2, 8).is_err());
+    }
+    
+    #[test]
+    fn test_decode_faces_applies_threshold_and_nms() {
+        let config = FaceModelConfig::default();
+        let scores = [0.1, 0.9, 0.2, 0.8, 0.7, 0.3, 0.05, 0.95];
+        let boxes = [
+            0.10, 0.10, 0.30, 0.40,
+            0.11, 0.10, 0.31, 0.41, // 与第一个框重叠，被抑制
+            0.50, 0.50, 0.60, 0.60, // 得分过低
+            0.60, 0.20, 0.80, 0.50,
+        ];
+        
+        let faces = decode_faces(&scores, &boxes, 640, 480, &config);
+        assert_eq!(faces.len(), 2);
+        assert_eq!(faces[0].x, 384);
+        assert!((faces[0].confidence - 0.95).abs() < 1e-6);
+        assert_eq!((faces[1].x, faces[1].y, faces[1].width, faces[1].height), (64, 48, 128, 144));
+    }
+    
+    /// 解码结果的金标准：testdata/face_decode下每个JSON文件是一组模型原始输出
+    /// （scores、boxes，格式同decode_faces）、画面尺寸、阈值和期望的检测结果。
+    /// 有意修改后处理时用 UPDATE_GOLDENS=1 重新生成expected，并在评审中检查差异。
+    #[test]
+    fn test_decode_faces_matches_goldens() {
+        let directory = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/vision/testdata/face_decode");
+        let update = std::env::var_os("UPDATE_GOLDENS").is_some();
+        let mut files: Vec<_> = std::fs::read_dir(&directory).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|e| e == "json"))
+            .collect();
+        files.sort();
+        assert!(!files.is_empty());
+        
+        for path in files {
+            let mut case: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            let floats = |key: &str| -> Vec<f32> {
+                case[key].as_array().unwrap().iter().map(|v| v.as_f64().unwrap() as f32).collect()
+            };
+            let config = FaceModelConfig {
+                confidence_threshold: case["config"]["confidence_threshold"].as_f64().unwrap() as f32,
+                nms_threshold: case["config"]["nms_threshold"].as_f64().unwrap() as f32,
+                ..FaceModelConfig::default()
+            };
+            let (width, height) = (case["width"].as_u64().unwrap() as u32, case["height"].as_u64().unwrap() as u32);
+            
+            let faces = decode_faces(&floats("scores"), &floats("boxes"), width, height, &config);
+            let actual: Vec<serde_json::Value> = faces.iter().map(|face| serde_json::json!({
+                "x": face.x,
+                "y": face.y,
+                "width": face.width,
+                "height": face.height,
+                "confidence": (face.confidence * 1e4).round() / 1e4,
+            })).collect();
+            
+            if update {
+                case["expected"] = serde_json::Value::Array(actual);
+                std::fs::write(&path, serde_json::to_string_pretty(&case).unwrap() + "\n").unwrap();
+                continue;
+            }
+            assert_eq!(
+                serde_json::Value::Array(actual), case["expected"],
+                "{} 的解码结果与金标准不一致（{}）", path.display(), case["description"]
+            );
+        }
+    }
+    
+    #[test]
+    fn test_fast_features_on_synthetic_square() {
+        let mut gray = vec![0u8; 64 * 64];
+        for y in 20..44 {
+            for x in 20..44 {
+                gray[y * 64 + x] = 255;
+            }
+        }
+        let image = ImageData::from_raw(64, 64, 1, gray, ImageFormat::Gray8);
+        
+        let features = FeatureDetector.detect(&image).unwrap();
+        assert!(!features.is_empty());
+        assert!(features.iter().all(|f| (15.0..=48.0).contains(&f.x) && (15.0..=48.0).contains(&f.y)));
+    }
+}
@@ -0,0 +1,59 @@
+//! 模拟摄像头
+//! 
+//! 没有摄像头的开发机上生成合成画面：渐变背景上一个水平往返移动的亮块，
+//! 让采集、处理和检测流程在仿真时照常运行。
+
+use super::VisionConfig;
+use crate::common::*;
+
+/// 亮块边长占画面高度的比例
+const BLOCK_RATIO: f64 = 0.25;
+
+/// 亮块往返一次的帧数
+const CYCLE_FRAMES: u64 = 120;
+
+pub struct MockCamera {
+    width: u32,
+    height: u32,
+    frame_index: u64,
+}
+
+impl MockCamera {
+    pub fn open(config: &VisionConfig) -> Self {
+        Self {
+            width: config.frame_width as u32,
+            height: config.frame_height as u32,
+            frame_index: 0,
+        }
+    }
+    
+    pub fn read(&mut self) -> Option<ImageData> {
+        let mut image = ImageData::new(self.width, self.height, 3, ImageFormat::RGB8);
+        
+        let block = ((self.height as f64 * BLOCK_RATIO) as u32).max(1);
+        let travel = self.width.saturating_sub(block) as f64;
+        let phase = (self.frame_index % CYCLE_FRAMES) as f64 / CYCLE_FRAMES as f64;
+        let block_x = (travel * (1.0 - (2.0 * phase - 1.0).abs())) as u32;
+        let block_y = (self.height - block) / 2;
+        
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let offset = ((y * self.width + x) * 3) as usize;
+                let inside = (block_x..block_x + block).contains(&x) && (block_y..block_y + block).contains(&y);
+                let pixel = if inside {
+                    [240, 240, 240]
+                } else {
+                    [(x * 255 / self.width) as u8, (y * 255 / self.height) as u8, 64]
+                };
+                image.data[offset..offset + 3].copy_from_slice(&pixel);
+            }
+        }
+        
+        self.frame_index += 1;
+        Some(image)
+    }
+    
+    pub fn release(&mut self) {
+        self.frame_index = 0;
+    }
+}
@@ -0,0 +1,174 @@
+//! OpenCV视觉后端
+//! 
+//! 使用VideoCapture采集、Haar级联检测人脸、ORB提取特征点。
+
+use super::{FaceDetection, FeaturePoint, VisionConfig, VisionError};
+use crate::common::*;
+use anyhow::Result;
+use opencv::{prelude::*, core, imgproc, videoio, objdetect, features2d};
+use log::info;
+
+pub const NAME: &str = "OpenCV";
+
+/// 摄像头
+pub struct Camera {
+    capture: videoio::VideoCapture,
+    frame: core::Mat,
+}
+
+impl Camera {
+    pub fn open(config: &VisionConfig) -> Result<Self> {
+        let mut capture = videoio::VideoCapture::new(config.camera_index, videoio::CAP_ANY)?;
+        
+        if !capture.is_opened()? {
+            return Err(VisionError::Camera("无法打开摄像头".to_string()).into());
+        }
+        
+        // 设置摄像头参数
+        capture.set(videoio::CAP_PROP_FRAME_WIDTH, config.frame_width as f64)?;
+        capture.set(videoio::CAP_PROP_FRAME_HEIGHT, config.frame_height as f64)?;
+        capture.set(videoio::CAP_PROP_FPS, config.fps)?;
+        
+        // 验证设置
+        let actual_width = capture.get(videoio::CAP_PROP_FRAME_WIDTH)? as i32;
+        let actual_height = capture.get(videoio::CAP_PROP_FRAME_HEIGHT)? as i32;
+        let actual_fps = capture.get(videoio::CAP_PROP_FPS)?;
+        
+        info!("摄像头参数: {}x{} @ {:.1} FPS", actual_width, actual_height, actual_fps);
+        
+        Ok(Self { capture, frame: core::Mat::default() })
+    }
+    
+    /// 读取一帧，摄像头返回空帧时为None
+    pub fn read(&mut self) -> Result<Option<ImageData>> {
+        if !self.capture.read(&mut self.frame)? || self.frame.empty() {
+            return Ok(None);
+        }
+        
+        mat_to_image_data(&self.frame).map(Some)
+    }
+    
+    pub fn release(&mut self) {
+        let _ = self.capture.release();
+    }
+}
+
+/// Haar级联人脸检测器
+pub struct FaceDetector {
+    cascade: objdetect::CascadeClassifier,
+}
+
+impl FaceDetector {
+    pub fn new(config: &VisionConfig) -> Result<Self> {
+        let cascade = objdetect::CascadeClassifier::new(&config.face_cascade_path)?;
+        Ok(Self { cascade })
+    }
+    
+    pub fn detect(&mut self, image: &ImageData) -> Result<Vec<FaceDetection>> {
+        let mat = image_data_to_mat(image)?;
+        let mut gray = core::Mat::default();
+        imgproc::cvt_color(&mat, &mut gray, gray_conversion(image.format)?, 0)?;
+        
+        let mut faces = core::Vector::<core::Rect>::new();
+        self.cascade.detect_multi_scale(
+            &gray,
+            &mut faces,
+            1.1,
+            3,
+            0,
+            core::Size::new(30, 30),
+            core::Size::new(0, 0),
+        )?;
+        
+        Ok(faces.iter().map(|face| FaceDetection {
+            x: face.x,
+            y: face.y,
+            width: face.width,
+            height: face.height,
+            confidence: 1.0, // Haar级联不提供置信度
+        }).collect())
+    }
+}
+
+/// ORB特征检测器
+pub struct FeatureDetector {
+    orb: core::Ptr<features2d::ORB>,
+}
+
+impl FeatureDetector {
+    pub fn new(_config: &VisionConfig) -> Result<Self> {
+        let orb = features2d::ORB::create(500, 1.2, 8, 31, 0, 2, features2d::ORB_ScoreType::HARRIS_SCORE, 31, 20)?;
+        Ok(Self { orb })
+    }
+    
+    pub fn detect(&mut self, image: &ImageData) -> Result<Vec<FeaturePoint>> {
+        let mat = image_data_to_mat(image)?;
+        let mut gray = core::Mat::default();
+        imgproc::cvt_color(&mat, &mut gray, gray_conversion(image.format)?, 0)?;
+        
+        let mut keypoints = core::Vector::<core::KeyPoint>::new();
+        let mask = core::Mat::default();
+        
+        self.orb.detect(&gray, &mut keypoints, &mask)?;
+        
+        Ok(keypoints.iter().map(|kp| FeaturePoint {
+            x: kp.pt().x,
+            y: kp.pt().y,
+            response: kp.response(),
+        }).collect())
+    }
+}
+
+/// 转换为灰度图使用的颜色转换代码
+fn gray_conversion(format: ImageFormat) -> Result<i32> {
+    match format {
+        ImageFormat::BGR8 => Ok(imgproc::COLOR_BGR2GRAY),
+        ImageFormat::RGB8 => Ok(imgproc::COLOR_RGB2GRAY),
+        ImageFormat::BGRA8 => Ok(imgproc::COLOR_BGRA2GRAY),
+        ImageFormat::RGBA8 => Ok(imgproc::COLOR_RGBA2GRAY),
+        _ => Err(VisionError::ImageProcessing("不支持的图像格式".to_string()).into()),
+    }
+}
+
+/// Mat转ImageData
+fn mat_to_image_data(mat: &core::Mat) -> Result<ImageData> {
+    let rows = mat.rows();
+    let cols = mat.cols();
+    let channels = mat.channels();
+    
+    if rows <= 0 || cols <= 0 || channels <= 0 {
+        return Err(VisionError::ImageProcessing("无效的图像尺寸".to_string()).into());
+    }
+    
+    let format = match channels {
+        1 => ImageFormat::Gray8,
+        3 => ImageFormat::BGR8,
+        4 => ImageFormat::BGRA8,
+        _ => return Err(VisionError::ImageProcessing("不支持的通道数".to_string()).into()),
+    };
+    
+    let data = mat.data_bytes()?.to_vec();
+    
+    Ok(ImageData::from_raw(
+        cols as u32,
+        rows as u32,
+        channels as u32,
+        data,
+        format,
+    ))
+}
+
+/// ImageData转Mat（复制数据）
+fn image_data_to_mat(image_data: &ImageData) -> Result<core::Mat> {
+    let channels = match image_data.format {
+        ImageFormat::Gray8 => 1,
+        ImageFormat::BGR8 | ImageFormat::RGB8 => 3,
+        ImageFormat::BGRA8 | ImageFormat::RGBA8 => 4,
+        _ => return Err(VisionError::ImageProcessing("不支持的图像格式".to_string()).into()),
+    };
+    
+    let flat = core::Mat::from_slice(&image_data.data)?;
+    let mat = flat.reshape(channels, image_data.height as i32)?.try_clone()?;
+    
+    Ok(mat)
+}
@@ -0,0 +1,261 @@
+//! 回放摄像头
+//! 
+//! 按配置的帧率循环播放图片目录（按文件名排序的PNG/JPEG）或MJPEG视频文件
+//! （连续拼接的JPEG帧，可用`ffmpeg -i in.mp4 -c:v mjpeg -f mjpeg out.mjpeg`转换），
+//! 每次运行得到完全相同的帧序列，视觉处理和行为可以不接摄像头开发和做单元测试。
+
+use super::{VisionConfig, VisionError};
+use crate::common::*;
+use anyhow::Result;
+use image::imageops::{self, FilterType};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use log::info;
+
+const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+const MJPEG_EXTENSIONS: [&str; 2] = ["mjpeg", "mjpg"];
+
+/// 回放配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraPlaybackConfig {
+    /// 图片目录或MJPEG文件
+    pub path: String,
+    /// 回放帧率，未设置时使用视觉配置的fps
+    #[serde(default)]
+    pub fps: Option<f64>,
+    /// 播放到结尾后从头开始，否则结束采集
+    #[serde(default = "default_looping")]
+    pub looping: bool,
+}
+
+fn default_looping() -> bool {
+    true
+}
+
+impl ConfigValidation for CameraPlaybackConfig {
+    fn validate(&self) -> Result<()> {
+        if self.path.is_empty() {
+            return Err(anyhow::anyhow!("回放路径不能为空"));
+        }
+        
+        if self.fps.is_some_and(|fps| fps <= 0.0) {
+            return Err(anyhow::anyhow!("回放帧率必须为正数"));
+        }
+        
+        Ok(())
+    }
+}
+
+enum Frames {
+    /// 图片文件，每次读取时解码
+    Files(Vec<PathBuf>),
+    /// MJPEG文件内容和每帧的字节范围
+    Mjpeg(Vec<u8>, Vec<(usize, usize)>),
+}
+
+impl Frames {
+    fn len(&self) -> usize {
+        match self {
+            Frames::Files(files) => files.len(),
+            Frames::Mjpeg(_, ranges) => ranges.len(),
+        }
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase()
+}
+
+/// 从SOI开始按段结构找到一帧JPEG的结尾（EOI之后），数据不完整时为None
+///
+/// 段内容（量化表、EXIF缩略图等）里可能出现0xFFD9，所以按段长度跳过，
+/// 只在扫描数据中逐字节查找标记：熵编码数据中的0xFF后面总是跟着0x00或RSTn。
+fn jpeg_end(data: &[u8], start: usize) -> Option<usize> {
+    let mut i = start + 2;
+    loop {
+        if *data.get(i)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(i + 1)?;
+        match marker {
+            0xFF => i += 1,
+            0xD9 => return Some(i + 2),
+            0x01 | 0xD0..=0xD7 => i += 2,
+            _ => {
+                let length = u16::from_be_bytes([*data.get(i + 2)?, *data.get(i + 3)?]) as usize;
+                i += 2 + length;
+                if marker == 0xDA {
+                    while *data.get(i)? != 0xFF || matches!(*data.get(i + 1)?, 0x00 | 0xD0..=0xD7) {
+                        i += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 切分MJPEG流中的每一帧，末尾不完整的帧被忽略
+fn split_mjpeg(data: &[u8]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = data[position..].windows(2).position(|pair| pair == [0xFF, 0xD8]) {
+        let start = position + offset;
+        let Some(end) = jpeg_end(data, start) else {
+            break;
+        };
+        ranges.push((start, end));
+        position = end;
+    }
+    ranges
+}
+
+/// 回放摄像头
+pub struct PlaybackCamera {
+    frames: Frames,
+    width: u32,
+    height: u32,
+    looping: bool,
+    position: usize,
+}
+
+impl PlaybackCamera {
+    pub fn open(config: &VisionConfig, playback: &CameraPlaybackConfig) -> Result<Self> {
+        playback.validate()?;
+        let path = Path::new(&playback.path);
+        
+        let frames = if path.is_dir() {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+                .map_err(|e| VisionError::Camera(format!("读取回放目录 {} 失败: {}", path.display(), e)))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|file| IMAGE_EXTENSIONS.contains(&extension(file).as_str()))
+                .collect();
+            files.sort();
+            Frames::Files(files)
+        } else if MJPEG_EXTENSIONS.contains(&extension(path).as_str()) {
+            let data = std::fs::read(path)
+                .map_err(|e| VisionError::Camera(format!("读取回放文件 {} 失败: {}", path.display(), e)))?;
+            let ranges = split_mjpeg(&data);
+            Frames::Mjpeg(data, ranges)
+        } else {
+            return Err(VisionError::Camera(format!(
+                "不支持的回放来源 {}，请使用图片目录或MJPEG文件", path.display()
+            )).into());
+        };
+        
+        if frames.len() == 0 {
+            return Err(VisionError::Camera(format!("回放来源 {} 中没有帧", path.display())).into());
+        }
+        
+        info!("摄像头回放 {}（{} 帧，{}）", path.display(), frames.len(), if playback.looping { "循环" } else { "单次" });
+        Ok(Self {
+            frames,
+            width: config.frame_width as u32,
+            height: config.frame_height as u32,
+            looping: playback.looping,
+            position: 0,
+        })
+    }
+    
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+    
+    /// 非循环回放是否已播放完
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.position >= self.frames.len()
+    }
+    
+    /// 读取下一帧（RGB8，已缩放到配置的尺寸），非循环回放结束后为None
+    pub fn read(&mut self) -> Result<Option<ImageData>> {
+        if self.position >= self.frames.len() {
+            if !self.looping {
+                return Ok(None);
+            }
+            self.position = 0;
+        }
+        
+        let index = self.position;
+        self.position += 1;
+        
+        let decoded = match &self.frames {
+            Frames::Files(files) => image::open(&files[index])
+                .map_err(|e| VisionError::ImageProcessing(format!("解码 {} 失败: {}", files[index].display(), e)))?,
+            Frames::Mjpeg(data, ranges) => {
+                let (start, end) = ranges[index];
+                image::load_from_memory_with_format(&data[start..end], image::ImageFormat::Jpeg)
+                    .map_err(|e| VisionError::ImageProcessing(format!("解码第 {} 帧失败: {}", index, e)))?
+            }
+        };
+        
+        let rgb = decoded.to_rgb8();
+        let rgb = if rgb.dimensions() != (self.width, self.height) {
+            imageops::resize(&rgb, self.width, self.height, FilterType::Triangle)
+        } else {
+            rgb
+        };
+        
+        Ok(Some(ImageData::from_raw(self.width, self.height, 3, rgb.into_raw(), ImageFormat::RGB8)))
+    }
+    
+    pub fn release(&mut self) {
+        self.position = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+    
+    fn solid(value: u8) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        ImageBuffer::from_pixel(8, 6, Rgb([value, value, value]))
+    }
+    
+    fn config() -> VisionConfig {
+        VisionConfig { frame_width: 4, frame_height: 3, ..VisionConfig::default() }
+    }
+    
+    #[test]
+    fn test_directory_playback_loops_in_name_order() {
+        let directory = std::env::temp_dir().join(format!("reachy_playback_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for (name, value) in [("b.png", 100u8), ("a.png", 50), ("c.png", 150)] {
+            solid(value).save(directory.join(name)).unwrap();
+        }
+        std::fs::write(directory.join("notes.txt"), "不是图片").unwrap();
+        
+        let playback = CameraPlaybackConfig { path: directory.display().to_string(), fps: None, looping: true };
+        let mut camera = PlaybackCamera::open(&config(), &playback).unwrap();
+        assert_eq!(camera.frame_count(), 3);
+        
+        let values: Vec<u8> = (0..4).map(|_| camera.read().unwrap().unwrap().data[0]).collect();
+        assert_eq!(values, vec![50, 100, 150, 50]);
+        
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+    
+    #[test]
+    fn test_mjpeg_playback_stops_when_not_looping() {
+        let mut data = Vec::new();
+        for value in [30u8, 200] {
+            let mut frame = std::io::Cursor::new(Vec::new());
+            solid(value).write_to(&mut frame, image::ImageFormat::Jpeg).unwrap();
+            data.extend(frame.into_inner());
+        }
+        let path = std::env::temp_dir().join(format!("reachy_playback_{}.mjpeg", rand::random::<u64>()));
+        std::fs::write(&path, &data).unwrap();
+        
+        let playback = CameraPlaybackConfig { path: path.display().to_string(), fps: Some(5.0), looping: false };
+        let mut camera = PlaybackCamera::open(&config(), &playback).unwrap();
+        assert_eq!(camera.frame_count(), 2);
+        
+        let first = camera.read().unwrap().unwrap();
+        assert_eq!((first.width, first.height), (4, 3));
+        assert!(first.data[0].abs_diff(30) <= 2);
+        assert!(camera.read().unwrap().unwrap().data[0].abs_diff(200) <= 2);
+        assert!(camera.read().unwrap().is_none());
+        assert!(camera.is_finished());
+        
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,393 @@
+//! 最小化的V4L2视频采集（mmap流式I/O）
+//! 
+//! 直接通过libc调用ioctl，不依赖bindgen生成的绑定，交叉编译时无需libclang。
+//! 只实现单平面视频采集所需的几个ioctl。
+
+use anyhow::Result;
+use log::debug;
+use std::ffi::CString;
+use std::mem::size_of;
+use std::os::raw::{c_int, c_ulong, c_void};
+use std::ptr;
+
+const IOC_WRITE: u32 = 1;
+const IOC_READ: u32 = 2;
+
+const fn ioc(dir: u32, nr: u32, size: usize) -> c_ulong {
+    ((dir << 30) | ((size as u32) << 16) | ((b'V' as u32) << 8) | nr) as c_ulong
+}
+
+const VIDIOC_QUERYCAP: c_ulong = ioc(IOC_READ, 0, size_of::<Capability>());
+const VIDIOC_S_FMT: c_ulong = ioc(IOC_READ | IOC_WRITE, 5, size_of::<Format>());
+const VIDIOC_REQBUFS: c_ulong = ioc(IOC_READ | IOC_WRITE, 8, size_of::<RequestBuffers>());
+const VIDIOC_QUERYBUF: c_ulong = ioc(IOC_READ | IOC_WRITE, 9, size_of::<Buffer>());
+const VIDIOC_QBUF: c_ulong = ioc(IOC_READ | IOC_WRITE, 15, size_of::<Buffer>());
+const VIDIOC_DQBUF: c_ulong = ioc(IOC_READ | IOC_WRITE, 17, size_of::<Buffer>());
+const VIDIOC_STREAMON: c_ulong = ioc(IOC_WRITE, 18, size_of::<c_int>());
+const VIDIOC_STREAMOFF: c_ulong = ioc(IOC_WRITE, 19, size_of::<c_int>());
+const VIDIOC_S_PARM: c_ulong = ioc(IOC_READ | IOC_WRITE, 22, size_of::<StreamParm>());
+
+const BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+const MEMORY_MMAP: u32 = 1;
+const FIELD_NONE: u32 = 1;
+const CAP_VIDEO_CAPTURE: u32 = 0x0000_0001;
+const CAP_STREAMING: u32 = 0x0400_0000;
+const CAP_DEVICE_CAPS: u32 = 0x8000_0000;
+
+/// 映射的缓冲区数量
+const BUFFER_COUNT: u32 = 4;
+
+pub const fn fourcc(code: &[u8; 4]) -> u32 {
+    (code[0] as u32) | ((code[1] as u32) << 8) | ((code[2] as u32) << 16) | ((code[3] as u32) << 24)
+}
+
+pub const FOURCC_YUYV: u32 = fourcc(b"YUYV");
+pub const FOURCC_MJPG: u32 = fourcc(b"MJPG");
+
+#[repr(C)]
+struct Capability {
+    driver: [u8; 16],
+    card: [u8; 32],
+    bus_info: [u8; 32],
+    version: u32,
+    capabilities: u32,
+    device_caps: u32,
+    reserved: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PixFormat {
+    width: u32,
+    height: u32,
+    pixelformat: u32,
+    field: u32,
+    bytesperline: u32,
+    sizeimage: u32,
+    colorspace: u32,
+    priv_: u32,
+    flags: u32,
+    ycbcr_enc: u32,
+    quantization: u32,
+    xfer_func: u32,
+}
+
+/// v4l2_format中的联合体，内核定义中包含指针，因此按指针对齐
+#[repr(C)]
+union FormatUnion {
+    pix: PixFormat,
+    raw_data: [u8; 200],
+    _align: *mut c_void,
+}
+
+#[repr(C)]
+struct Format {
+    type_: u32,
+    fmt: FormatUnion,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Fract {
+    numerator: u32,
+    denominator: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CaptureParm {
+    capability: u32,
+    capturemode: u32,
+    timeperframe: Fract,
+    extendedmode: u32,
+    readbuffers: u32,
+    reserved: [u32; 4],
+}
+
+#[repr(C)]
+union StreamParmUnion {
+    capture: CaptureParm,
+    raw_data: [u8; 200],
+}
+
+#[repr(C)]
+struct StreamParm {
+    type_: u32,
+    parm: StreamParmUnion,
+}
+
+#[repr(C)]
+struct RequestBuffers {
+    count: u32,
+    type_: u32,
+    memory: u32,
+    capabilities: u32,
+    flags: u8,
+    reserved: [u8; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Timecode {
+    type_: u32,
+    flags: u32,
+    frames: u8,
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    userbits: [u8; 4],
+}
+
+#[repr(C)]
+union BufferM {
+    offset: u32,
+    userptr: c_ulong,
+    planes: *mut c_void,
+    fd: i32,
+}
+
+#[repr(C)]
+struct Buffer {
+    index: u32,
+    type_: u32,
+    bytesused: u32,
+    flags: u32,
+    field: u32,
+    timestamp: libc::timeval,
+    timecode: Timecode,
+    sequence: u32,
+    memory: u32,
+    m: BufferM,
+    length: u32,
+    reserved2: u32,
+    request_fd: i32,
+}
+
+// 与内核头文件中的结构体大小保持一致（64位目标）
+#[cfg(target_pointer_width = "64")]
+const _: () = {
+    assert!(size_of::<Capability>() == 104);
+    assert!(size_of::<Format>() == 208);
+    assert!(size_of::<StreamParm>() == 204);
+    assert!(size_of::<RequestBuffers>() == 20);
+    assert!(size_of::<Buffer>() == 88);
+};
+
+fn xioctl<T>(fd: c_int, request: c_ulong, arg: &mut T) -> std::io::Result<()> {
+    loop {
+        // musl的ioctl请求参数为c_int，glibc为c_ulong
+        let ret = unsafe { libc::ioctl(fd, request as _, arg as *mut T as *mut c_void) };
+        if ret != -1 {
+            return Ok(());
+        }
+        
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/// 协商得到的采集格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureFormat {
+    pub width: u32,
+    pub height: u32,
+    pub pixel_format: u32,
+    /// 每行字节数（可能包含填充）
+    pub bytes_per_line: u32,
+}
+
+/// V4L2采集设备
+pub struct CaptureDevice {
+    fd: c_int,
+    buffers: Vec<(*mut c_void, usize)>,
+    format: CaptureFormat,
+    streaming: bool,
+}
+
+// mmap得到的缓冲区只由本结构体访问
+unsafe impl Send for CaptureDevice {}
+
+impl CaptureDevice {
+    /// 打开/dev/video{index}，按顺序尝试给定的像素格式并开始采集
+    pub fn open(index: i32, width: u32, height: u32, fps: f64, pixel_formats: &[u32]) -> Result<Self> {
+        let path = CString::new(format!("/dev/video{}", index))?;
+        let fd = unsafe { libc::open(path.as_ptr(), libc::O_RDWR | libc::O_CLOEXEC) };
+        if fd < 0 {
+            return Err(anyhow::anyhow!("无法打开摄像头设备 /dev/video{}: {}", index, std::io::Error::last_os_error()));
+        }
+        
+        // 先构造结构体，出错时由Drop负责清理
+        let mut device = Self {
+            fd,
+            buffers: Vec::new(),
+            format: CaptureFormat { width, height, pixel_format: 0, bytes_per_line: 0 },
+            streaming: false,
+        };
+        
+        let mut capability: Capability = unsafe { std::mem::zeroed() };
+        xioctl(fd, VIDIOC_QUERYCAP, &mut capability)
+            .map_err(|e| anyhow::anyhow!("查询摄像头能力失败: {}", e))?;
+        let caps = if capability.capabilities & CAP_DEVICE_CAPS != 0 {
+            capability.device_caps
+        } else {
+            capability.capabilities
+        };
+        if caps & CAP_VIDEO_CAPTURE == 0 || caps & CAP_STREAMING == 0 {
+            return Err(anyhow::anyhow!("设备不支持流式视频采集"));
+        }
+        
+        device.format = device.negotiate_format(width, height, pixel_formats)?;
+        device.set_frame_rate(fps);
+        device.start_streaming()?;
+        
+        Ok(device)
+    }
+    
+    fn negotiate_format(&self, width: u32, height: u32, pixel_formats: &[u32]) -> Result<CaptureFormat> {
+        for &pixel_format in pixel_formats {
+            let mut format: Format = unsafe { std::mem::zeroed() };
+            format.type_ = BUF_TYPE_VIDEO_CAPTURE;
+            format.fmt.pix = PixFormat {
+                width,
+                height,
+                pixelformat: pixel_format,
+                field: FIELD_NONE,
+                ..unsafe { std::mem::zeroed() }
+            };
+            
+            if xioctl(self.fd, VIDIOC_S_FMT, &mut format).is_err() {
+                continue;
+            }
+            
+            // 驱动可能调整尺寸或改用其他格式
+            let pix = unsafe { format.fmt.pix };
+            if pix.pixelformat == pixel_format {
+                return Ok(CaptureFormat {
+                    width: pix.width,
+                    height: pix.height,
+                    pixel_format,
+                    bytes_per_line: pix.bytesperline,
+                });
+            }
+        }
+        
+        Err(anyhow::anyhow!("摄像头不支持所需的像素格式"))
+    }
+    
+    /// 设置帧率，部分驱动不支持，失败时忽略
+    fn set_frame_rate(&self, fps: f64) {
+        let mut parm: StreamParm = unsafe { std::mem::zeroed() };
+        parm.type_ = BUF_TYPE_VIDEO_CAPTURE;
+        parm.parm.capture = CaptureParm {
+            timeperframe: Fract { numerator: 1000, denominator: (fps * 1000.0).round() as u32 },
+            ..unsafe { std::mem::zeroed() }
+        };
+        
+        if let Err(e) = xioctl(self.fd, VIDIOC_S_PARM, &mut parm) {
+            debug!("设置帧率失败: {}", e);
+        }
+    }
+    
+    fn start_streaming(&mut self) -> Result<()> {
+        let mut request: RequestBuffers = unsafe { std::mem::zeroed() };
+        request.count = BUFFER_COUNT;
+        request.type_ = BUF_TYPE_VIDEO_CAPTURE;
+        request.memory = MEMORY_MMAP;
+        xioctl(self.fd, VIDIOC_REQBUFS, &mut request)
+            .map_err(|e| anyhow::anyhow!("申请采集缓冲区失败: {}", e))?;
+        
+        for index in 0..request.count {
+            let mut buffer = self.buffer(index);
+            xioctl(self.fd, VIDIOC_QUERYBUF, &mut buffer)
+                .map_err(|e| anyhow::anyhow!("查询采集缓冲区失败: {}", e))?;
+            
+            let length = buffer.length as usize;
+            let address = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    length,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    self.fd,
+                    buffer.m.offset as libc::off_t,
+                )
+            };
+            if address == libc::MAP_FAILED {
+                return Err(anyhow::anyhow!("映射采集缓冲区失败: {}", std::io::Error::last_os_error()));
+            }
+            self.buffers.push((address, length));
+            
+            xioctl(self.fd, VIDIOC_QBUF, &mut buffer)
+                .map_err(|e| anyhow::anyhow!("缓冲区入队失败: {}", e))?;
+        }
+        
+        let mut buffer_type = BUF_TYPE_VIDEO_CAPTURE as c_int;
+        xioctl(self.fd, VIDIOC_STREAMON, &mut buffer_type)
+            .map_err(|e| anyhow::anyhow!("开始采集失败: {}", e))?;
+        self.streaming = true;
+        
+        Ok(())
+    }
+    
+    fn buffer(&self, index: u32) -> Buffer {
+        let mut buffer: Buffer = unsafe { std::mem::zeroed() };
+        buffer.index = index;
+        buffer.type_ = BUF_TYPE_VIDEO_CAPTURE;
+        buffer.memory = MEMORY_MMAP;
+        buffer
+    }
+    
+    pub fn format(&self) -> CaptureFormat {
+        self.format
+    }
+    
+    /// 等待并读取一帧原始数据，超时返回None
+    pub fn read_frame(&mut self, timeout_ms: i32) -> Result<Option<Vec<u8>>> {
+        let mut poll_fd = libc::pollfd { fd: self.fd, events: libc::POLLIN, revents: 0 };
+        let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) };
+        if ready < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(None);
+            }
+            return Err(anyhow::anyhow!("等待摄像头帧失败: {}", err));
+        }
+        if ready == 0 {
+            return Ok(None);
+        }
+        
+        let mut buffer = self.buffer(0);
+        xioctl(self.fd, VIDIOC_DQBUF, &mut buffer)
+            .map_err(|e| anyhow::anyhow!("读取采集缓冲区失败: {}", e))?;
+        
+        let (address, length) = self.buffers[buffer.index as usize];
+        let used = (buffer.bytesused as usize).min(length);
+        let data = unsafe { std::slice::from_raw_parts(address as *const u8, used) }.to_vec();
+        
+        xioctl(self.fd, VIDIOC_QBUF, &mut buffer)
+            .map_err(|e| anyhow::anyhow!("缓冲区入队失败: {}", e))?;
+        
+        Ok(Some(data))
+    }
+}
+
+impl Drop for CaptureDevice {
+    fn drop(&mut self) {
+        if self.streaming {
+            let mut buffer_type = BUF_TYPE_VIDEO_CAPTURE as c_int;
+            let _ = xioctl(self.fd, VIDIOC_STREAMOFF, &mut buffer_type);
+        }
+        
+        for (address, length) in self.buffers.drain(..) {
+            unsafe {
+                libc::munmap(address, length);
+            }
+        }
+        
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}